@@ -0,0 +1,158 @@
+use crate::environment::Environment;
+use crate::eval::eval;
+use crate::value::Value;
+use std::fmt;
+
+/// Bits per printed row; 64-bit values take two rows.
+const ROW: u32 = 32;
+
+/// An integer's two's-complement bit pattern, ready to print.
+#[derive(Debug, PartialEq)]
+pub struct Bits {
+    value: i64,
+    /// 8, 16, 32 or 64: the narrowest that holds the value
+    width: u32,
+}
+
+impl Bits {
+    pub fn new(value: i64) -> Bits {
+        let width = [8, 16, 32]
+            .into_iter()
+            .find(|&w| {
+                if value < 0 {
+                    value >= -(1 << (w - 1))
+                } else {
+                    value < 1 << w
+                }
+            })
+            .unwrap_or(64);
+        Bits { value, width }
+    }
+
+    /// The bit pattern as an unsigned number, e.g. 0xff for -1 in 8 bits.
+    fn pattern(&self) -> u64 {
+        if self.width == 64 {
+            self.value as u64
+        } else {
+            self.value as u64 & ((1 << self.width) - 1)
+        }
+    }
+}
+
+impl fmt::Display for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let signed = if self.value < 0 {
+            " (two's complement)"
+        } else {
+            ""
+        };
+        writeln!(f, "{} in {} bits{signed}:", self.value, self.width)?;
+
+        let pattern = self.pattern();
+        let mut top = self.width;
+        while top > 0 {
+            let bottom = top.saturating_sub(ROW);
+            // Each nibble is labeled with the index of its highest bit
+            let (labels, nibbles): (Vec<String>, Vec<String>) = (bottom..top)
+                .rev()
+                .step_by(4)
+                .map(|high| {
+                    let nibble = (pattern >> (high - 3)) & 0xf;
+                    (format!("{high:<4}"), format!("{nibble:04b}"))
+                })
+                .unzip();
+            writeln!(f, "  bit  {}", labels.join(" ").trim_end())?;
+            writeln!(f, "  0b   {}", nibbles.join(" "))?;
+            top = bottom;
+        }
+
+        writeln!(f, "  hex  {pattern:#x}")?;
+        write!(f, "  oct  0o{pattern:o}")
+    }
+}
+
+/// Evaluate `input` and show its bits. Only whole numbers have bits.
+pub fn bits(input: &str, env: &Environment) -> Result<Bits, String> {
+    if input.trim().is_empty() {
+        return Err("Usage: bits <expression>, e.g. bits 255 or bits -1".to_string());
+    }
+    let value = eval(&crate::parse_in(input, env)?, env)?;
+    let number = match value {
+        Value::Number(n) => n,
+        Value::Fraction(r) if r.is_integer() => r.to_f64(),
+        Value::Decimal(d) if d.is_integer() => d.to_f64(),
+        _ => {
+            return Err(format!(
+                "bits needs a whole number, got {} {value}",
+                value.kind()
+            ))
+        }
+    };
+    // i64::MAX as f64 rounds up to 2^63, which doesn't fit
+    if number.fract() != 0.0 || number < i64::MIN as f64 || number >= i64::MAX as f64 {
+        return Err(format!(
+            "bits needs a whole number between -2^63 and 2^63, got {value}"
+        ));
+    }
+    Ok(Bits::new(number as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show(input: &str) -> String {
+        bits(input, &Environment::new()).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_narrowest_width() {
+        assert_eq!(Bits::new(0).width, 8);
+        assert_eq!(Bits::new(255).width, 8);
+        assert_eq!(Bits::new(256).width, 16);
+        assert_eq!(Bits::new(-128).width, 8);
+        assert_eq!(Bits::new(-129).width, 16);
+        assert_eq!(Bits::new(1 << 40).width, 64);
+    }
+
+    #[test]
+    fn test_positive() {
+        assert_eq!(
+            show("200 + 55"),
+            "255 in 8 bits:\n\
+             \x20 bit  7    3\n\
+             \x20 0b   1111 1111\n\
+             \x20 hex  0xff\n\
+             \x20 oct  0o377"
+        );
+    }
+
+    #[test]
+    fn test_negative_is_twos_complement() {
+        let text = show("-1000");
+        assert!(text.starts_with("-1000 in 16 bits (two's complement):"));
+        assert!(text.contains("  bit  15   11   7    3\n"));
+        assert!(text.contains("  0b   1111 1100 0001 1000\n"));
+        assert!(text.contains("  hex  0xfc18\n"));
+        assert!(text.ends_with("  oct  0o176030"));
+    }
+
+    #[test]
+    fn test_64_bits_take_two_rows() {
+        let text = show("-2");
+        assert!(text.contains("in 8 bits"));
+
+        let text = show("-2^40");
+        assert_eq!(text.matches("  0b   ").count(), 2);
+        assert!(text.contains("  bit  63   59"));
+        assert!(text.contains("  hex  0xffffff0000000000"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let env = Environment::new();
+        assert!(bits("2.5", &env).unwrap_err().contains("whole number"));
+        assert!(bits("2^70", &env).is_err());
+        assert!(bits("", &env).unwrap_err().starts_with("Usage"));
+    }
+}
@@ -0,0 +1,191 @@
+//! `calculator --check file.calc`: run a file of assertions like
+//! `2 + 2 == 4`, so a set of exercises can be graded.
+//!
+//! ```text
+//! # Comments and blank lines are skipped
+//! operator <> (a, b) = sqrt(a^2 + b^2)
+//! 3 <> 4 == 5
+//! 1/3 + 1/6 == 0.5
+//! 2 ^ 10
+//! ans / 2 == 512
+//! ```
+//!
+//! Lines without `==` are still evaluated, so later lines can use their
+//! result through `ans` and `$n` or an operator they define.
+
+use crate::environment::Environment;
+use crate::eval::eval;
+use crate::value::Value;
+use crate::{input, operators};
+use std::fmt;
+
+/// Numbers this close, relative to their size, count as equal, so
+/// `0.1 + 0.2 == 0.3` holds.
+const TOLERANCE: f64 = 1e-9;
+
+/// An assertion that didn't hold, or a line that couldn't be evaluated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    /// 1-based
+    pub line: usize,
+    pub source: String,
+    /// The right-hand side's value; `None` for a line that isn't an
+    /// assertion
+    pub expected: Option<String>,
+    /// The left-hand side's value, or the error evaluating the line
+    pub actual: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.source)?;
+        match &self.expected {
+            Some(expected) => write!(f, "\n  expected {expected}, got {}", self.actual),
+            None => write!(f, "\n  {}", self.actual),
+        }
+    }
+}
+
+/// What running a check file found.
+#[derive(Debug, Default, PartialEq)]
+pub struct Report {
+    pub passed: usize,
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for failure in &self.failures {
+            writeln!(f, "FAIL {failure}")?;
+        }
+        write!(f, "{} passed, {} failed", self.passed, self.failures.len())
+    }
+}
+
+/// Run every line of `text` in `env`, top to bottom.
+pub fn check(text: &str, env: &mut Environment) -> Report {
+    let mut report = Report::default();
+
+    for (i, line) in text.lines().enumerate() {
+        let source = input::strip_comment(line).trim();
+        if source.is_empty() {
+            continue;
+        }
+        let fail = |expected: Option<String>, actual: String| Failure {
+            line: i + 1,
+            source: source.to_string(),
+            expected,
+            actual,
+        };
+
+        if let Some(definition) = operators::parse_definition(source, env) {
+            match definition {
+                Ok(operator) => env.define_operator(operator),
+                Err(e) => report.failures.push(fail(None, format!("error: {e}"))),
+            }
+            continue;
+        }
+
+        let Some((left, right)) = source.split_once("==") else {
+            match evaluate(source, env) {
+                Ok(value) => remember(value, env),
+                Err(e) => report.failures.push(fail(None, format!("error: {e}"))),
+            }
+            continue;
+        };
+
+        let expected = match evaluate(right, env) {
+            Ok(value) => value,
+            Err(e) => {
+                let e = format!("error in the expected value: {e}");
+                report.failures.push(fail(None, e));
+                continue;
+            }
+        };
+        match evaluate(left, env) {
+            Ok(actual) if matches(actual, expected) => {
+                report.passed += 1;
+                remember(actual, env);
+            }
+            Ok(actual) => report
+                .failures
+                .push(fail(Some(expected.to_string()), actual.to_string())),
+            Err(e) => report
+                .failures
+                .push(fail(Some(expected.to_string()), format!("error: {e}"))),
+        }
+    }
+
+    report
+}
+
+fn evaluate(input: &str, env: &Environment) -> Result<Value, String> {
+    eval(&crate::parse_in(input.trim(), env)?, env)
+}
+
+/// Keep a result for `ans` and `$n`, as the REPL does.
+fn remember(value: Value, env: &mut Environment) {
+    env.push_result(value);
+    env.set("ans", value);
+}
+
+/// Equal values; numbers and fractions only need to agree to `TOLERANCE`.
+fn matches(actual: Value, expected: Value) -> bool {
+    match (actual.as_number(), expected.as_number()) {
+        (Ok(a), Ok(b)) => (a - b).abs() <= TOLERANCE * a.abs().max(b.abs()).max(1.0),
+        _ => actual == expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(text: &str) -> Report {
+        check(text, &mut Environment::new())
+    }
+
+    #[test]
+    fn test_passing_file() {
+        let report = run("# arithmetic\n2 + 2 == 4\n\n0.1 + 0.2 == 0.3\nsqrt(16) == 2 ^ 2\n");
+        assert_eq!(report.passed, 3);
+        assert!(report.is_success());
+        assert_eq!(report.to_string(), "3 passed, 0 failed");
+    }
+
+    #[test]
+    fn test_failures_show_expected_and_actual() {
+        let report = run("2 + 2 == 5\n1 / 0 == 1\n3 * == 2\n");
+        assert_eq!(report.passed, 0);
+        assert_eq!(report.failures.len(), 3);
+        assert_eq!(
+            report.failures[0].to_string(),
+            "line 1: 2 + 2 == 5\n  expected 5, got 4"
+        );
+        assert!(report.failures[1].actual.starts_with("error:"));
+        assert_eq!(report.failures[2].line, 3);
+        assert!(!report.is_success());
+    }
+
+    #[test]
+    fn test_results_and_operators_carry_over() {
+        let report = run(
+            "operator <> (a, b) = sqrt(a^2 + b^2)\n3 <> 4 == 5\n2 ^ 10\nans / 2 == 512\n$1 == 5\n",
+        );
+        assert_eq!(report.failures, []);
+        assert_eq!(report.passed, 3);
+    }
+
+    #[test]
+    fn test_non_numbers_compare_exactly() {
+        let report = run("2024-03-01 + 1 day == 2024-03-02\n[1, 2] == [1, 3]\n");
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.failures[0].line, 2);
+    }
+}
@@ -0,0 +1,158 @@
+//! Startup settings from `config.toml` and definitions from `init.calc`,
+//! both in `~/.config/calc`.
+//!
+//! ```toml
+//! # Any of these can be left out
+//! mode = "decimal"    # float, frac, decimal or rpn, as :mode takes
+//! precision = 2       # show decimals to this many places, like :round 2
+//! angle = "degrees"   # or radians, for sin, cos and tan
+//! ```
+//!
+//! Only flat `key = value` lines and comments are read, not all of TOML.
+//! `init.calc` holds constants and operators, a statement per line:
+//!
+//! ```text
+//! g = 9.81
+//! operator <> (a, b) = sqrt(a^2 + b^2)
+//! ```
+
+use crate::decimal::MAX_SCALE;
+use crate::environment::{Angle, Environment, Mode};
+use crate::eval::eval;
+use crate::{input, operators};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub mode: Option<Mode>,
+    /// Start in `:mode rpn`
+    pub rpn: bool,
+    /// Decimal places to show
+    pub precision: Option<u32>,
+    pub angle: Option<Angle>,
+}
+
+/// A value without the quotes around it, if it has them.
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+/// Read `config.toml`. The first line that can't be used is an error.
+pub fn parse(text: &str) -> Result<Config, String> {
+    let mut config = Config::default();
+    for (i, line) in text.lines().enumerate() {
+        let at = |e: String| format!("line {}: {e}", i + 1);
+        // No setting has a # in its value, so a comment starts at the first
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| at(format!("expected key = value, got '{line}'")))?;
+        let value = unquote(value.trim());
+        match key.trim() {
+            "mode" => match value {
+                "rpn" => config.rpn = true,
+                _ => config.mode = Some(value.parse().map_err(at)?),
+            },
+            "precision" => {
+                let places = value
+                    .parse()
+                    .ok()
+                    .filter(|&p| p <= MAX_SCALE)
+                    .ok_or_else(|| at(format!("precision should be 0 to {MAX_SCALE}")))?;
+                config.precision = Some(places);
+            }
+            "angle" => config.angle = Some(value.parse().map_err(at)?),
+            other => {
+                return Err(at(format!(
+                    "unknown setting '{other}'; use mode, precision or angle"
+                )))
+            }
+        }
+    }
+    Ok(config)
+}
+
+/// Run `init.calc` in `env`: assignments like `g = 9.81` and operator
+/// definitions, `;`-separated or a line each, with `#` comments. Returns
+/// an error for each line that failed; the rest still take effect.
+pub fn run_script(text: &str, env: &mut Environment) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        for statement in input::statements(input::strip_comment(line)) {
+            if let Err(e) = run_statement(statement, env) {
+                errors.push(format!("line {}: {e}", i + 1));
+            }
+        }
+    }
+    errors
+}
+
+fn run_statement(statement: &str, env: &mut Environment) -> Result<(), String> {
+    if let Some(definition) = operators::parse_definition(statement, env) {
+        env.define_operator(definition?);
+        return Ok(());
+    }
+    let (name, expression) = input::assignment(statement).ok_or(format!(
+        "expected name = value or an operator definition, got '{statement}'"
+    ))?;
+    let value = eval(&crate::parse_in(expression, env)?, env)?;
+    env.set(name, value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+
+    #[test]
+    fn test_parse() {
+        let text = "# Mine\nmode = \"decimal\"\nprecision = 2  # cents\nangle = 'degrees'\n";
+        assert_eq!(
+            parse(text),
+            Ok(Config {
+                mode: Some(Mode::Decimal),
+                rpn: false,
+                precision: Some(2),
+                angle: Some(Angle::Degrees),
+            })
+        );
+        assert!(parse("mode = \"rpn\"").unwrap().rpn);
+        assert_eq!(parse(""), Ok(Config::default()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("mode = \"hex\"").unwrap_err().starts_with("line 1:"));
+        assert!(parse("\nprecision = 99")
+            .unwrap_err()
+            .starts_with("line 2:"));
+        assert!(parse("angle = \"gradians\"").is_err());
+        assert!(parse("colour = \"blue\"").is_err());
+        assert!(parse("[section]").is_err());
+    }
+
+    #[test]
+    fn test_run_script() {
+        let mut env = Environment::new();
+        let script = "# Constants\ng = 9.81\nmass = 2; weight = mass * g\n\
+                      operator <> (a, b) = sqrt(a^2 + b^2)\n\
+                      hyp = 3 <> 4\noops = 1 / 0\n2 + 2\n";
+        let errors = run_script(script, &mut env);
+        assert_eq!(env.get("weight"), Some(Value::Number(19.62)));
+        assert_eq!(env.get("hyp"), Some(Value::Number(5.0)));
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors[0].starts_with("line 6:"));
+        assert!(errors[1].starts_with("line 7:"));
+    }
+}
@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const RATES_URL: &str = "https://open.er-api.com/v6/latest/USD";
+const CACHE_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// Exchange rates relative to USD, as stored in the on-disk cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rates {
+    /// When we fetched the rates (unix seconds)
+    pub fetched_at: u64,
+    /// When the provider last updated the rates (unix seconds)
+    pub updated_at: u64,
+    pub rates: HashMap<String, f64>,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    result: String,
+    time_last_update_unix: u64,
+    rates: HashMap<String, f64>,
+}
+
+pub struct Conversion {
+    pub amount: f64,
+    pub from: String,
+    pub to: String,
+}
+
+/// Recognize `<amount> <from> to <to>`, e.g. `100 usd to eur`.
+pub fn parse_conversion(input: &str) -> Option<Conversion> {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+
+    if parts.len() != 4 || !parts[2].eq_ignore_ascii_case("to") {
+        return None;
+    }
+
+    let amount: f64 = parts[0].parse().ok()?;
+    let is_code = |s: &str| s.len() == 3 && s.chars().all(|c| c.is_ascii_alphabetic());
+
+    if !is_code(parts[1]) || !is_code(parts[3]) {
+        return None;
+    }
+
+    Some(Conversion {
+        amount,
+        from: parts[1].to_uppercase(),
+        to: parts[3].to_uppercase(),
+    })
+}
+
+impl Rates {
+    pub fn is_fresh(&self, now: u64) -> bool {
+        now.saturating_sub(self.fetched_at) < CACHE_MAX_AGE_SECS
+    }
+
+    pub fn convert(&self, conversion: &Conversion) -> Result<f64, String> {
+        let rate = |code: &str| {
+            self.rates
+                .get(code)
+                .copied()
+                .ok_or(format!("Unknown currency: {code}"))
+        };
+
+        let from = rate(&conversion.from)?;
+        let to = rate(&conversion.to)?;
+
+        Ok(conversion.amount / from * to)
+    }
+}
+
+fn cache_path() -> PathBuf {
+    let base = std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(".cache"))
+        .unwrap_or_else(std::env::temp_dir);
+    base.join("calculator").join("exchange_rates.json")
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn load_cache() -> Option<Rates> {
+    let content = fs::read_to_string(cache_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn save_cache(rates: &Rates) -> Result<(), String> {
+    let path = cache_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create cache dir: {e}"))?;
+    }
+
+    let json = serde_json::to_string_pretty(rates)
+        .map_err(|e| format!("Failed to serialize rates: {e}"))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+fn fetch_rates(now: u64) -> Result<Rates, String> {
+    let response: ApiResponse = reqwest::blocking::get(RATES_URL)
+        .map_err(|e| format!("Request failed: {e}"))?
+        .json()
+        .map_err(|e| format!("Failed to parse rates: {e}"))?;
+
+    if response.result != "success" {
+        return Err(format!("Exchange rate API error: {}", response.result));
+    }
+
+    Ok(Rates {
+        fetched_at: now,
+        updated_at: response.time_last_update_unix,
+        rates: response.rates,
+    })
+}
+
+/// Return current rates, using the on-disk cache when it's less than a day
+/// old. If fetching fails, fall back to stale cached rates. The flag is true
+/// when the returned rates are stale.
+pub fn get_rates() -> Result<(Rates, bool), String> {
+    let now = now_secs();
+    let cached = load_cache();
+
+    if let Some(rates) = &cached {
+        if rates.is_fresh(now) {
+            return Ok((rates.clone(), false));
+        }
+    }
+
+    match fetch_rates(now) {
+        Ok(rates) => {
+            // A failed cache write shouldn't block the conversion
+            if let Err(e) = save_cache(&rates) {
+                eprintln!("Warning: {e}");
+            }
+            Ok((rates, false))
+        }
+        Err(e) => match cached {
+            Some(rates) => Ok((rates, true)),
+            None => Err(format!("{e} (and no cached rates available)")),
+        },
+    }
+}
+
+/// Format a unix timestamp as `YYYY-MM-DD` (UTC).
+pub fn format_date(timestamp: u64) -> String {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_rates() -> Rates {
+        Rates {
+            fetched_at: 1_000_000,
+            updated_at: 1_714_521_600,
+            rates: HashMap::from([
+                ("USD".to_string(), 1.0),
+                ("EUR".to_string(), 0.5),
+                ("GBP".to_string(), 0.25),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_parse_conversion() {
+        let c = parse_conversion("100 usd to eur").unwrap();
+        assert_eq!(c.amount, 100.0);
+        assert_eq!(c.from, "USD");
+        assert_eq!(c.to, "EUR");
+    }
+
+    #[test]
+    fn test_parse_conversion_rejects_other_input() {
+        assert!(parse_conversion("5 + 3").is_none());
+        assert!(parse_conversion("abc usd to eur").is_none());
+        assert!(parse_conversion("100 dollars to eur").is_none());
+    }
+
+    #[test]
+    fn test_convert() {
+        let rates = sample_rates();
+        let eur_to_gbp = parse_conversion("10 eur to gbp").unwrap();
+        assert_eq!(rates.convert(&eur_to_gbp), Ok(5.0));
+    }
+
+    #[test]
+    fn test_convert_unknown_currency() {
+        let rates = sample_rates();
+        let c = parse_conversion("10 usd to xyz").unwrap();
+        assert!(rates.convert(&c).is_err());
+    }
+
+    #[test]
+    fn test_cache_freshness() {
+        let rates = sample_rates();
+        assert!(rates.is_fresh(rates.fetched_at + 60));
+        assert!(!rates.is_fresh(rates.fetched_at + CACHE_MAX_AGE_SECS));
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(1_714_521_600), "2024-05-01");
+    }
+}
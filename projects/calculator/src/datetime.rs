@@ -0,0 +1,178 @@
+//! Date, time-of-day, and duration literals: `2024-03-01`, `9:30`,
+//! `45 days`, `2h15m`.
+
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+/// Units that have to follow the number directly: `2h`, `90s`. A bare `m`
+/// is the SI milli prefix, so it only means minutes after another unit, as
+/// in `2h15m`.
+const SHORT_UNITS: &[(&str, f64)] = &[
+    ("w", 604_800.0),
+    ("d", 86_400.0),
+    ("h", 3_600.0),
+    ("min", 60.0),
+    ("s", 1.0),
+];
+
+/// Units that can also be written after a space: `45 days`.
+const LONG_UNITS: &[(&str, f64)] = &[
+    ("week", 604_800.0),
+    ("weeks", 604_800.0),
+    ("day", 86_400.0),
+    ("days", 86_400.0),
+    ("hour", 3_600.0),
+    ("hours", 3_600.0),
+    ("hr", 3_600.0),
+    ("hrs", 3_600.0),
+    ("minute", 60.0),
+    ("minutes", 60.0),
+    ("mins", 60.0),
+    ("second", 1.0),
+    ("seconds", 1.0),
+    ("sec", 1.0),
+    ("secs", 1.0),
+];
+
+fn lookup(units: &[(&str, f64)], word: &str) -> Option<f64> {
+    units.iter().find(|(u, _)| *u == word).map(|(_, s)| *s)
+}
+
+/// Seconds per `unit`. `spaced` is whether it was separated from its number
+/// by whitespace; `compound` whether it follows another unit.
+pub fn unit_seconds(unit: &str, spaced: bool, compound: bool) -> Option<f64> {
+    if let Some(seconds) = lookup(LONG_UNITS, unit) {
+        return Some(seconds);
+    }
+    if spaced {
+        return None;
+    }
+    if unit == "m" && compound {
+        return Some(60.0);
+    }
+    lookup(SHORT_UNITS, unit)
+}
+
+pub fn duration_from_seconds(seconds: f64) -> Result<Duration, String> {
+    let milliseconds = (seconds * 1000.0).round();
+    // i64::MAX as f64 rounds up, so stay strictly below it; NaN fails too
+    if milliseconds.is_nan() || milliseconds.abs() >= i64::MAX as f64 {
+        return Err("Duration out of range".to_string());
+    }
+    Duration::try_milliseconds(milliseconds as i64).ok_or("Duration out of range".to_string())
+}
+
+/// A `YYYY-MM-DD` date, optionally with a time: `2024-03-01T14:30`.
+pub fn parse_date(text: &str) -> Option<NaiveDateTime> {
+    if let Some(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .filter(|_| text.len() == 10)
+    {
+        return date.and_hms_opt(0, 0, 0);
+    }
+    ["%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+}
+
+/// A time of day: `9:30` or `17:45:10`.
+pub fn parse_time(text: &str) -> Option<NaiveTime> {
+    ["%H:%M", "%H:%M:%S"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(text, format).ok())
+}
+
+/// The current local date and time, for `now`.
+pub fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+/// Whole dates print as `Fri 2024-03-01`; anything later than midnight
+/// gets its time too.
+pub fn format_date(date: &NaiveDateTime) -> String {
+    if date.time() == NaiveTime::MIN {
+        date.format("%a %Y-%m-%d").to_string()
+    } else {
+        format!(
+            "{} {}",
+            date.format("%a %Y-%m-%d"),
+            format_time(&date.time())
+        )
+    }
+}
+
+pub fn format_time(time: &NaiveTime) -> String {
+    if time.second() == 0 {
+        time.format("%H:%M").to_string()
+    } else {
+        time.format("%H:%M:%S").to_string()
+    }
+}
+
+/// `45d`, `2h 15m`, `-1d 3h 20s`. Milliseconds are dropped.
+pub fn format_duration(duration: &Duration) -> String {
+    let sign = if *duration < Duration::zero() {
+        "-"
+    } else {
+        ""
+    };
+    let total = duration.num_seconds().abs();
+    let parts: Vec<String> = [
+        (total / 86_400, "d"),
+        (total % 86_400 / 3_600, "h"),
+        (total % 3_600 / 60, "m"),
+        (total % 60, "s"),
+    ]
+    .iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, unit)| format!("{n}{unit}"))
+    .collect();
+
+    if parts.is_empty() {
+        return "0s".to_string();
+    }
+    format!("{sign}{}", parts.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_units() {
+        assert_eq!(unit_seconds("days", true, false), Some(86_400.0));
+        assert_eq!(unit_seconds("h", false, false), Some(3_600.0));
+        assert_eq!(unit_seconds("h", true, false), None);
+        assert_eq!(unit_seconds("m", false, false), None);
+        assert_eq!(unit_seconds("m", false, true), Some(60.0));
+        assert_eq!(unit_seconds("pi", false, false), None);
+    }
+
+    #[test]
+    fn test_parse_date_and_time() {
+        let date = parse_date("2024-03-01").unwrap();
+        assert_eq!(format_date(&date), "Fri 2024-03-01");
+        let with_time = parse_date("2024-03-01T14:30").unwrap();
+        assert_eq!(format_date(&with_time), "Fri 2024-03-01 14:30");
+        assert_eq!(parse_date("2024-13-01"), None);
+
+        assert_eq!(format_time(&parse_time("9:30").unwrap()), "09:30");
+        assert_eq!(format_time(&parse_time("17:45:10").unwrap()), "17:45:10");
+        assert_eq!(parse_time("25:00"), None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(&Duration::days(45)), "45d");
+        assert_eq!(
+            format_duration(&duration_from_seconds(8_100.0).unwrap()),
+            "2h 15m"
+        );
+        assert!(duration_from_seconds(1e20 * 86_400.0).is_err());
+        assert!(duration_from_seconds(f64::NAN).is_err());
+        assert_eq!(
+            format_duration(&-(Duration::days(1) + Duration::seconds(20))),
+            "-1d 20s"
+        );
+        assert_eq!(format_duration(&Duration::zero()), "0s");
+    }
+}
@@ -0,0 +1,449 @@
+//! Exact decimals for `:mode decimal`, so `0.1 + 0.2` is `0.3` and money
+//! adds up to the cent. Division that doesn't end is rounded to
+//! `MAX_SCALE` places, half to even.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Most digits kept after the point
+pub const MAX_SCALE: u32 = 28;
+
+const OVERFLOW: &str = "Decimal overflow: the result is too big to keep exact; try :mode float";
+
+/// How to round a digit that is exactly half way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// Banker's rounding: 2.5 → 2, 3.5 → 4
+    #[default]
+    HalfEven,
+    /// Away from zero: 2.5 → 3, -2.5 → -3
+    HalfUp,
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half-even" => Ok(Rounding::HalfEven),
+            "half-up" => Ok(Rounding::HalfUp),
+            _ => Err(format!("Unknown rounding '{s}'; use half-even or half-up")),
+        }
+    }
+}
+
+impl fmt::Display for Rounding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rounding::HalfEven => write!(f, "half-even"),
+            Rounding::HalfUp => write!(f, "half-up"),
+        }
+    }
+}
+
+/// `digits / 10^scale`, without trailing zeros, so equal values always
+/// compare equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decimal {
+    digits: i128,
+    scale: u32,
+}
+
+fn pow10(n: u32) -> Result<i128, String> {
+    10_i128.checked_pow(n).ok_or_else(|| OVERFLOW.to_string())
+}
+
+/// `num / den` to the nearest whole number, for positive `den`.
+fn divide_rounded(num: i128, den: i128, rounding: Rounding) -> i128 {
+    let (quotient, remainder) = (num / den, (num % den).abs());
+    let away = match remainder.cmp(&(den - remainder)) {
+        std::cmp::Ordering::Less => false,
+        std::cmp::Ordering::Greater => true,
+        std::cmp::Ordering::Equal => rounding == Rounding::HalfUp || quotient % 2 != 0,
+    };
+    if away {
+        quotient + num.signum()
+    } else {
+        quotient
+    }
+}
+
+/// `a * b` as a 256-bit number, its (high, low) halves.
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    const LOW: u128 = u64::MAX as u128;
+    let (a1, a0, b1, b0) = (a >> 64, a & LOW, b >> 64, b & LOW);
+    let (p00, p01, p10, p11) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let middle = (p00 >> 64) + (p01 & LOW) + (p10 & LOW);
+    let low = (p00 & LOW) | (middle << 64);
+    let high = p11 + (p01 >> 64) + (p10 >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// The decimal digits of the 256-bit number `(high, low)`.
+fn wide_digits(mut high: u128, mut low: u128) -> String {
+    let mut reversed = String::new();
+    // Long division by 10, a bit at a time, until the rest fits a u128
+    while high != 0 {
+        let (mut quotient_high, mut quotient_low, mut remainder) = (0u128, 0u128, 0u128);
+        for bit in (0..256).rev() {
+            let next = if bit >= 128 {
+                (high >> (bit - 128)) & 1
+            } else {
+                (low >> bit) & 1
+            };
+            remainder = (remainder << 1) | next;
+            if remainder >= 10 {
+                remainder -= 10;
+                if bit >= 128 {
+                    quotient_high |= 1 << (bit - 128);
+                } else {
+                    quotient_low |= 1 << bit;
+                }
+            }
+        }
+        reversed.push(char::from(b'0' + remainder as u8));
+        (high, low) = (quotient_high, quotient_low);
+    }
+    let mut digits = low.to_string();
+    digits.extend(reversed.chars().rev());
+    digits
+}
+
+/// `digits` with the last `drop` of them rounded away, if it fits an i128.
+fn round_digits(digits: &str, drop: usize, rounding: Rounding) -> Option<i128> {
+    let (kept, dropped) = digits.split_at(digits.len().saturating_sub(drop));
+    let kept: i128 = if kept.is_empty() {
+        0
+    } else {
+        kept.parse().ok()?
+    };
+    // Fewer digits than dropped means a leading zero: under half
+    let away = dropped.len() == drop
+        && match dropped.as_bytes().first() {
+            Some(b'5') if dropped[1..].bytes().all(|b| b == b'0') => {
+                rounding == Rounding::HalfUp || kept % 2 != 0
+            }
+            Some(&first) => first > b'5',
+            None => false,
+        };
+    if away {
+        kept.checked_add(1)
+    } else {
+        Some(kept)
+    }
+}
+
+impl Decimal {
+    pub fn new(digits: i128, scale: u32) -> Decimal {
+        let (mut digits, mut scale) = (digits, scale);
+        while scale > 0 && digits % 10 == 0 {
+            digits /= 10;
+            scale -= 1;
+        }
+        Decimal { digits, scale }
+    }
+
+    pub fn integer(n: i64) -> Decimal {
+        Decimal::new(n as i128, 0)
+    }
+
+    /// The decimal a float prints as: `0.1` is exactly one tenth. `None` for
+    /// infinities, NaN, and numbers with too many digits.
+    pub fn from_f64(x: f64) -> Option<Decimal> {
+        if !x.is_finite() {
+            return None;
+        }
+        let text = x.abs().to_string();
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let digits: i128 = format!("{whole}{fraction}").parse().ok()?;
+        let sign = if x < 0.0 { -1 } else { 1 };
+        Some(Decimal::new(sign * digits, fraction.len() as u32))
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.scale == 0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// The whole number, if this is one that fits an i64.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.is_integer()
+            .then(|| i64::try_from(self.digits).ok())
+            .flatten()
+    }
+
+    pub fn abs(&self) -> Result<Decimal, String> {
+        let digits = self.digits.checked_abs().ok_or(OVERFLOW)?;
+        Ok(Decimal::new(digits, self.scale))
+    }
+
+    pub fn checked_neg(&self) -> Result<Decimal, String> {
+        let digits = self.digits.checked_neg().ok_or(OVERFLOW)?;
+        Ok(Decimal::new(digits, self.scale))
+    }
+
+    /// Both digit counts at the larger of the two scales.
+    fn aligned(&self, other: &Decimal) -> Result<(i128, i128, u32), String> {
+        let scale = self.scale.max(other.scale);
+        let widen = |d: &Decimal| {
+            d.digits
+                .checked_mul(pow10(scale - d.scale)?)
+                .ok_or_else(|| OVERFLOW.to_string())
+        };
+        Ok((widen(self)?, widen(other)?, scale))
+    }
+
+    pub fn checked_add(&self, other: &Decimal) -> Result<Decimal, String> {
+        let (a, b, scale) = self.aligned(other)?;
+        Ok(Decimal::new(a.checked_add(b).ok_or(OVERFLOW)?, scale))
+    }
+
+    pub fn checked_sub(&self, other: &Decimal) -> Result<Decimal, String> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    /// Rounded to `MAX_SCALE` places, or fewer if the digits don't fit
+    /// otherwise; only a whole part too big to keep is an overflow.
+    pub fn checked_mul(&self, other: &Decimal) -> Result<Decimal, String> {
+        let scale = self.scale + other.scale;
+        if let Some(digits) = self.digits.checked_mul(other.digits) {
+            if scale <= MAX_SCALE {
+                return Ok(Decimal::new(digits, scale));
+            }
+        }
+
+        // Multiply at full width, then round off places until it fits
+        let (high, low) = wide_mul(self.digits.unsigned_abs(), other.digits.unsigned_abs());
+        let digits = wide_digits(high, low);
+        let negative = (self.digits < 0) != (other.digits < 0);
+        for drop in scale.saturating_sub(MAX_SCALE)..=scale {
+            if let Some(n) = round_digits(&digits, drop as usize, Rounding::HalfEven) {
+                return Ok(Decimal::new(if negative { -n } else { n }, scale - drop));
+            }
+        }
+        Err(OVERFLOW.to_string())
+    }
+
+    /// Long division, one digit at a time, until it ends or reaches
+    /// `MAX_SCALE` places.
+    pub fn checked_div(&self, other: &Decimal) -> Result<Decimal, String> {
+        if other.digits == 0 {
+            return Err("Division by zero".to_string());
+        }
+        let (num, den, _) = self.aligned(other)?;
+        let negative = (num < 0) != (den < 0);
+        let (num, den) = (
+            num.checked_abs().ok_or(OVERFLOW)?,
+            den.checked_abs().ok_or(OVERFLOW)?,
+        );
+
+        let mut digits = num / den;
+        let mut remainder = num % den;
+        let mut scale = 0;
+        while remainder != 0 && scale < MAX_SCALE {
+            let shifted = remainder.checked_mul(10).ok_or(OVERFLOW)?;
+            digits = digits
+                .checked_mul(10)
+                .and_then(|d| d.checked_add(shifted / den))
+                .ok_or(OVERFLOW)?;
+            remainder = shifted % den;
+            scale += 1;
+        }
+        // Half to even on what's left over
+        let rounds_up = match remainder.cmp(&(den - remainder)) {
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Equal => remainder != 0 && digits % 2 != 0,
+        };
+        if rounds_up {
+            digits = digits.checked_add(1).ok_or(OVERFLOW)?;
+        }
+        let digits = if negative { -digits } else { digits };
+        Ok(Decimal::new(digits, scale))
+    }
+
+    /// Remainder with the sign of the dividend, like `%` on floats.
+    pub fn checked_rem(&self, other: &Decimal) -> Result<Decimal, String> {
+        if other.digits == 0 {
+            return Err("Division by zero".to_string());
+        }
+        let (a, b, scale) = self.aligned(other)?;
+        Ok(Decimal::new(a % b, scale))
+    }
+
+    /// Integer powers by repeated squaring; a negative exponent inverts.
+    pub fn checked_pow(&self, exponent: i64) -> Result<Decimal, String> {
+        let mut result = Decimal::integer(1);
+        let mut square = *self;
+        let mut remaining = exponent.unsigned_abs();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.checked_mul(&square)?;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                square = square.checked_mul(&square)?;
+            }
+        }
+        if exponent < 0 {
+            Decimal::integer(1).checked_div(&result)
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Round to `places` digits after the point.
+    pub fn round(&self, places: u32, rounding: Rounding) -> Decimal {
+        if self.scale <= places {
+            return *self;
+        }
+        match pow10(self.scale - places) {
+            Ok(divisor) => Decimal::new(divide_rounded(self.digits, divisor, rounding), places),
+            // More than 38 digits to drop means the value rounds to zero
+            Err(_) => Decimal::integer(0),
+        }
+    }
+
+    /// Rounded and written with exactly `places` digits after the point, the
+    /// way amounts of money are: `3.10`, `-0.50`.
+    pub fn format_places(&self, places: u32, rounding: Rounding) -> String {
+        let rounded = self.round(places, rounding);
+        let text = rounded.to_string();
+        let missing = (places - rounded.scale) as usize;
+        match (places, rounded.scale) {
+            (0, _) => text,
+            (_, 0) => format!("{text}.{}", "0".repeat(missing)),
+            _ => format!("{text}{}", "0".repeat(missing)),
+        }
+    }
+}
+
+/// Plain decimal text like `-19.99`, as `Display` writes it.
+impl FromStr for Decimal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid decimal '{s}'");
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || !all_digits(whole) || !all_digits(fraction) {
+            return Err(invalid());
+        }
+        if fraction.len() > MAX_SCALE as usize {
+            return Err(format!(
+                "Invalid decimal '{s}': at most {MAX_SCALE} digits after the point"
+            ));
+        }
+        let digits: i128 = format!("{whole}{fraction}")
+            .parse()
+            .map_err(|_| OVERFLOW.to_string())?;
+        let digits = if negative { -digits } else { digits };
+        Ok(Decimal::new(digits, fraction.len() as u32))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.digits < 0 { "-" } else { "" };
+        let digits = self.digits.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{whole}.{fraction}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(text: &str) -> Decimal {
+        Decimal::from_f64(text.parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_exact_arithmetic() {
+        assert_eq!(d("0.1").checked_add(&d("0.2")), Ok(d("0.3")));
+        assert_eq!(d("0.3").checked_sub(&d("0.1")).unwrap().to_string(), "0.2");
+        assert_eq!(d("19.99").checked_mul(&d("3")), Ok(d("59.97")));
+        assert_eq!(d("1").checked_div(&d("8")), Ok(d("0.125")));
+        assert_eq!(d("-7.5").checked_rem(&d("2")), Ok(d("-1.5")));
+        assert_eq!(d("1.1").checked_pow(2), Ok(d("1.21")));
+        assert_eq!(d("2").checked_pow(-2), Ok(d("0.25")));
+        assert_eq!(d("-0.05").to_string(), "-0.05");
+        assert_eq!(d("1200").to_string(), "1200");
+        assert!(d("1").checked_div(&d("0")).is_err());
+    }
+
+    #[test]
+    fn test_division_rounds_at_max_scale() {
+        let third = d("1").checked_div(&d("3")).unwrap();
+        assert_eq!(third.to_string(), format!("0.{}", "3".repeat(28)));
+        let two_thirds = d("2").checked_div(&d("3")).unwrap();
+        assert!(two_thirds.to_string().ends_with("67"));
+        assert_eq!(third.checked_mul(&d("3")).unwrap().scale, MAX_SCALE);
+        // Both at MAX_SCALE: the raw product has 56 places and 55 digits
+        assert_eq!(
+            third.checked_pow(2).unwrap().to_string(),
+            format!("0.{}", "1".repeat(28))
+        );
+        assert_eq!(
+            third.checked_neg().unwrap().checked_mul(&third).unwrap(),
+            third.checked_pow(2).unwrap().checked_neg().unwrap()
+        );
+        // A big whole part keeps as many places as still fit
+        let precise: Decimal = "1.000000000000000000000000001".parse().unwrap();
+        let product = precise
+            .checked_mul(&Decimal::integer(10_i64.pow(18)))
+            .unwrap();
+        assert_eq!(product.to_string(), "1000000000000000000.000000001");
+        let product = precise.checked_mul(&d("1e20")).unwrap();
+        assert_eq!(product.to_string(), "100000000000000000000.0000001");
+    }
+
+    #[test]
+    fn test_rounding_modes() {
+        let format = |text: &str, rounding| d(text).format_places(2, rounding);
+        assert_eq!(format("2.345", Rounding::HalfEven), "2.34");
+        assert_eq!(format("2.345", Rounding::HalfUp), "2.35");
+        assert_eq!(format("2.355", Rounding::HalfEven), "2.36");
+        assert_eq!(format("-2.345", Rounding::HalfUp), "-2.35");
+        assert_eq!(format("2.3451", Rounding::HalfEven), "2.35");
+        assert_eq!(format("3.1", Rounding::HalfEven), "3.10");
+        assert_eq!(format("7", Rounding::HalfEven), "7.00");
+        assert_eq!(d("2.5").format_places(0, Rounding::HalfEven), "2");
+        assert_eq!(d("2.5").format_places(0, Rounding::HalfUp), "3");
+        assert_eq!("half-up".parse(), Ok(Rounding::HalfUp));
+        assert!("ceiling".parse::<Rounding>().is_err());
+    }
+
+    #[test]
+    fn test_overflow_is_an_error() {
+        let big = Decimal::new(i128::MAX, 0);
+        assert_eq!(big.checked_add(&big).unwrap_err(), OVERFLOW);
+        assert!(d("10").checked_pow(40).is_err());
+        assert_eq!(d("1e30").checked_mul(&d("1e30")).unwrap_err(), OVERFLOW);
+        assert_eq!(Decimal::from_f64(f64::NAN), None);
+    }
+
+    #[test]
+    fn test_parse_round_trips() {
+        for text in ["0", "-19.99", "1200", "0.0000001"] {
+            assert_eq!(text.parse::<Decimal>().unwrap().to_string(), text);
+        }
+        assert_eq!("2.50".parse(), Ok(d("2.5")));
+        assert!("1.2.3".parse::<Decimal>().is_err());
+        assert!(".5".parse::<Decimal>().is_err());
+        assert!("1e5".parse::<Decimal>().is_err());
+    }
+}
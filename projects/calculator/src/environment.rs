@@ -0,0 +1,258 @@
+use crate::datetime;
+use crate::operators::Operator;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::str::FromStr;
+
+/// How number literals are evaluated, set with `:mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Float,
+    /// Exact fractions: `1/3 + 1/6` is `1/2`
+    Fraction,
+    /// Exact decimals: `0.1 + 0.2` is `0.3`
+    Decimal,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "float" => Ok(Mode::Float),
+            "frac" | "fraction" => Ok(Mode::Fraction),
+            "decimal" => Ok(Mode::Decimal),
+            _ => Err(format!(
+                "Unknown mode '{s}'; use frac, decimal, float or rpn"
+            )),
+        }
+    }
+}
+
+/// What `sin`, `cos` and `tan` take angles in, set with `:angle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Angle {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+impl FromStr for Angle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rad" | "radians" => Ok(Angle::Radians),
+            "deg" | "degrees" => Ok(Angle::Degrees),
+            _ => Err(format!("Unknown angle unit '{s}'; use degrees or radians")),
+        }
+    }
+}
+
+/// An earlier result, with the note `:note` attached to it, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub value: Value,
+    pub note: Option<String>,
+}
+
+/// Named values available to expressions: built-in constants plus anything
+/// the caller stores, like the REPL's `ans`.
+#[derive(Debug, Clone)]
+pub struct Environment {
+    variables: HashMap<String, Value>,
+    mode: Mode,
+    angle: Angle,
+    /// Earlier results, for `$1`, `$2`, ...
+    results: Vec<HistoryEntry>,
+    /// Infix operators defined with `operator`, by symbol
+    operators: HashMap<String, Rc<Operator>>,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::new()
+    }
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        let variables = HashMap::from([
+            ("pi".to_string(), Value::Number(std::f64::consts::PI)),
+            ("e".to_string(), Value::Number(std::f64::consts::E)),
+        ]);
+        Environment {
+            variables,
+            mode: Mode::Float,
+            angle: Angle::Radians,
+            results: Vec::new(),
+            operators: HashMap::new(),
+        }
+    }
+
+    /// `now` and `today` are the current date and time unless the caller
+    /// has stored something under those names.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        if let Some(value) = self.variables.get(name) {
+            return Some(*value);
+        }
+        match name {
+            "now" => Some(Value::Date(datetime::now())),
+            "today" => datetime::now().date().and_hms_opt(0, 0, 0).map(Value::Date),
+            _ => None,
+        }
+    }
+
+    /// Every name `get` knows, alphabetically.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.variables.keys().map(String::as_str).collect();
+        for builtin in ["now", "today"] {
+            if !self.variables.contains_key(builtin) {
+                names.push(builtin);
+            }
+        }
+        names.sort();
+        names
+    }
+
+    /// Everything stored with `set`, by name. `pi` and `e` are left out
+    /// unless they were changed.
+    pub fn variables(&self) -> Vec<(&str, Value)> {
+        let builtins = Environment::new();
+        let mut variables: Vec<(&str, Value)> = self
+            .variables
+            .iter()
+            .filter(|(name, value)| builtins.variables.get(*name) != Some(value))
+            .map(|(name, value)| (name.as_str(), *value))
+            .collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+        variables
+    }
+
+    pub fn set(&mut self, name: &str, value: Value) {
+        self.variables.insert(name.to_string(), value);
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.variables.remove(name);
+    }
+
+    /// Remember a result and return its number, counting from 1.
+    pub fn push_result(&mut self, value: Value) -> usize {
+        self.results.push(HistoryEntry { value, note: None });
+        self.results.len()
+    }
+
+    /// Attach `note` to the latest result, replacing any note it had.
+    /// Returns the result's number.
+    pub fn annotate(&mut self, note: &str) -> Result<usize, String> {
+        let entry = self
+            .results
+            .last_mut()
+            .ok_or("No result to attach a note to yet")?;
+        entry.note = Some(note.to_string());
+        Ok(self.results.len())
+    }
+
+    /// Every result so far, `$1` first.
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.results
+    }
+
+    /// Result number `n`, as `$n` refers to it.
+    pub fn result(&self, n: usize) -> Result<Value, String> {
+        match self.results.len() {
+            0 => Err(format!("There's no ${n} yet; no results so far")),
+            len if n == 0 || n > len => {
+                Err(format!("There's no ${n}; results so far are $1 to ${len}"))
+            }
+            _ => Ok(self.results[n - 1].value),
+        }
+    }
+
+    /// Add or replace a custom operator. Expressions parsed earlier keep
+    /// the definition they were parsed with.
+    pub fn define_operator(&mut self, operator: Operator) {
+        self.operators
+            .insert(operator.symbol.clone(), Rc::new(operator));
+    }
+
+    pub fn operator(&self, symbol: &str) -> Option<Rc<Operator>> {
+        self.operators.get(symbol).cloned()
+    }
+
+    /// Every custom operator, by symbol.
+    pub fn operators(&self) -> Vec<&Operator> {
+        let mut operators: Vec<&Operator> = self.operators.values().map(|op| &**op).collect();
+        operators.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        operators
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    pub fn angle(&self) -> Angle {
+        self.angle
+    }
+
+    pub fn set_angle(&mut self, angle: Angle) {
+        self.angle = angle;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_constants() {
+        let env = Environment::new();
+        assert_eq!(env.get("pi"), Some(Value::Number(std::f64::consts::PI)));
+        assert_eq!(env.get("missing"), None);
+    }
+
+    #[test]
+    fn test_set_overrides() {
+        let mut env = Environment::new();
+        env.set("ans", Value::Number(42.0));
+        assert_eq!(env.get("ans"), Some(Value::Number(42.0)));
+        env.remove("ans");
+        assert_eq!(env.get("ans"), None);
+        assert_eq!(env.names(), ["e", "now", "pi", "today"]);
+        env.set("pi", Value::Number(3.0));
+        env.set("m1", Value::Number(7.0));
+        assert_eq!(
+            env.variables(),
+            [("m1", Value::Number(7.0)), ("pi", Value::Number(3.0))]
+        );
+    }
+
+    #[test]
+    fn test_results_are_numbered_from_one() {
+        let mut env = Environment::new();
+        assert!(env.result(1).unwrap_err().contains("no results"));
+        assert_eq!(env.push_result(Value::Number(4.0)), 1);
+        assert_eq!(env.push_result(Value::Number(9.0)), 2);
+        assert_eq!(env.result(2), Ok(Value::Number(9.0)));
+        assert!(env.result(0).is_err());
+        assert!(env.result(3).unwrap_err().contains("$1 to $2"));
+    }
+
+    #[test]
+    fn test_notes_go_on_the_latest_result() {
+        let mut env = Environment::new();
+        assert!(env.annotate("too soon").is_err());
+        env.push_result(Value::Number(4.0));
+        env.push_result(Value::Number(9.0));
+        assert_eq!(env.annotate("area in m²"), Ok(2));
+        let notes: Vec<Option<&str>> = env.history().iter().map(|e| e.note.as_deref()).collect();
+        assert_eq!(notes, [None, Some("area in m²")]);
+    }
+}
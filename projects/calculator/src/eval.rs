@@ -0,0 +1,162 @@
+use crate::decimal::Decimal;
+use crate::environment::{Environment, Mode};
+use crate::functions;
+use crate::parser::Expr;
+use crate::rational::Rational;
+use crate::value::Value;
+
+pub fn eval(expr: &Expr, env: &Environment) -> Result<Value, String> {
+    eval_traced(expr, env, &mut |_, _| {})
+}
+
+/// Like `eval`, calling `on_step` with each part of `expr` that isn't a
+/// literal as soon as its value is known, so innermost first. `:trace`
+/// uses it to show the expression reducing step by step.
+pub fn eval_traced(
+    expr: &Expr,
+    env: &Environment,
+    on_step: &mut dyn FnMut(&Expr, &Value),
+) -> Result<Value, String> {
+    let mut eval = |e: &Expr| eval_traced(e, env, on_step);
+
+    let value = match expr {
+        Expr::Number(n) => {
+            return Ok(match env.mode() {
+                Mode::Float => Value::Number(*n),
+                Mode::Fraction => Rational::from_f64(*n)
+                    .map(Value::Fraction)
+                    .unwrap_or(Value::Number(*n)),
+                Mode::Decimal => Decimal::from_f64(*n)
+                    .map(Value::Decimal)
+                    .unwrap_or(Value::Number(*n)),
+            })
+        }
+        Expr::Temporal(value) => return Ok(*value),
+        Expr::Variable(name) => env.get(name).ok_or(format!("Unknown variable: {name}"))?,
+        Expr::Interval(low, high) => {
+            let low = eval(low)?.as_number()?;
+            Value::interval(low, eval(high)?.as_number()?)?
+        }
+        Expr::Uncertain(value, error) => {
+            let value = eval(value)?.as_number()?;
+            Value::uncertain(value, eval(error)?.as_number()?)?
+        }
+        Expr::Neg(inner) => eval(inner)?.negate()?,
+        Expr::Binary(op, left, right) => {
+            let left = eval(left)?;
+            left.apply(*op, &eval(right)?)?
+        }
+        Expr::Call(name, args) => {
+            let args = args.iter().map(eval).collect::<Result<Vec<_>, _>>()?;
+            functions::call_in(name, &args, env.angle())?
+        }
+        Expr::Result(n) => env.result(*n)?,
+        Expr::Custom(op, left, right) => {
+            let left = eval(left)?;
+            op.apply(left, eval(right)?, env)?
+        }
+    };
+    on_step(expr, &value);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn eval_str(input: &str) -> Result<Value, String> {
+        eval(&parse(input)?, &Environment::new())
+    }
+
+    #[test]
+    fn test_eval_precedence() {
+        assert_eq!(eval_str("2 + 3 * 4"), Ok(Value::Number(14.0)));
+        assert_eq!(eval_str("(2 + 3) * 4"), Ok(Value::Number(20.0)));
+        assert_eq!(eval_str("-2^2"), Ok(Value::Number(-4.0)));
+    }
+
+    #[test]
+    fn test_eval_uncertainty() {
+        let result = eval_str("(10 ± 0.5) * (3 ± 0.1)").unwrap();
+        assert_eq!(result.to_string(), "30.05 ± 2.5 [27.55, 32.55]");
+    }
+
+    #[test]
+    fn test_eval_interval_syntax() {
+        let result = eval_str("[9.5, 10.5] + 1").unwrap();
+        assert_eq!(result.bounds(), (10.5, 11.5));
+    }
+
+    #[test]
+    fn test_eval_nested_uncertainty_rejected() {
+        assert!(eval_str("(10 ± 1) ± 1").is_err());
+    }
+
+    #[test]
+    fn test_eval_variables() {
+        let mut env = Environment::new();
+        env.set("x", Value::Number(3.0));
+        assert_eq!(eval(&parse("2 * x").unwrap(), &env), Ok(Value::Number(6.0)));
+    }
+
+    #[test]
+    fn test_eval_functions() {
+        assert_eq!(eval_str("sqrt(16) + 1"), Ok(Value::Number(5.0)));
+        assert_eq!(eval_str("2sin(0)"), Ok(Value::Number(0.0)));
+        assert!(eval_str("nope(1)").is_err());
+        assert_eq!(eval_str("nCr(10, 3) + nPr(5, 2)"), Ok(Value::Number(140.0)));
+        assert_eq!(
+            eval_str("factorial(5) | sqrt | round"),
+            Ok(Value::Number(11.0))
+        );
+    }
+
+    #[test]
+    fn test_eval_fraction_mode() {
+        let mut env = Environment::new();
+        env.set_mode(Mode::Fraction);
+        let eval_frac = |input: &str| eval(&parse(input).unwrap(), &env).unwrap().to_string();
+        assert_eq!(eval_frac("1/3 + 1/6"), "1/2");
+        assert_eq!(eval_frac("0.1 + 0.2"), "3/10");
+        assert_eq!(eval_frac("(2/3)^2 - 1"), "-5/9");
+        assert_eq!(eval_frac("sqrt(1/4)"), "0.5");
+        assert_eq!(eval_str("1/4").unwrap().to_string(), "0.25");
+    }
+
+    #[test]
+    fn test_eval_decimal_mode() {
+        let mut env = Environment::new();
+        env.set_mode(Mode::Decimal);
+        let eval_dec = |input: &str| eval(&parse(input).unwrap(), &env).unwrap();
+        assert_eq!(eval_dec("0.1 + 0.2"), eval_dec("0.3"));
+        assert_eq!(eval_dec("0.1 + 0.2").to_string(), "0.3");
+        assert_eq!(eval_dec("19.99 * 3 - 0.97").to_string(), "59");
+        assert_eq!(eval_dec("1.05^2").to_string(), "1.1025");
+        assert_eq!(eval_dec("sqrt(2.25) + 0.1").to_string(), "1.6");
+        assert_eq!(eval_dec("4^0.5"), Value::Number(2.0));
+        assert_eq!(eval_str("0.1 + 0.2"), Ok(Value::Number(0.1 + 0.2)));
+    }
+
+    #[test]
+    fn test_eval_results_and_pipelines() {
+        let mut env = Environment::new();
+        env.push_result(Value::Number(1024.0));
+        env.push_result(Value::Number(-2.4));
+        let eval_env = |input: &str| eval(&parse(input)?, &env);
+        assert_eq!(eval_env("$1 / 2"), Ok(Value::Number(512.0)));
+        assert_eq!(eval_env("$2 | abs | round"), Ok(Value::Number(2.0)));
+        assert_eq!(eval_env("2 ^ 10 | sqrt"), Ok(Value::Number(32.0)));
+        assert!(eval_env("$3").unwrap_err().contains("$1 to $2"));
+    }
+
+    #[test]
+    fn test_unknown_variable() {
+        assert!(eval_str("abc + 3").is_err());
+    }
+
+    #[test]
+    fn test_eval_division_by_zero() {
+        assert!(eval_str("1 / (2 - 2)").is_err());
+    }
+}
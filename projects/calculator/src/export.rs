@@ -0,0 +1,316 @@
+//! `:export env.json` and `:import env.json`: variables and custom
+//! operators as JSON, to share definitions or set them up from a script.
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "variables": {
+//!     "m1": {"type": "number", "value": 0.07},
+//!     "third": {"type": "fraction", "numer": 1, "denom": 3},
+//!     "price": {"type": "decimal", "value": "19.99"},
+//!     "length": {"type": "interval", "low": 9.5, "high": 10.5},
+//!     "start": {"type": "date", "value": "2024-03-01T09:00:00"},
+//!     "alarm": {"type": "time", "value": "07:30:00"},
+//!     "sprint": {"type": "duration", "seconds": 1209600},
+//!     "rate": 0.2
+//!   },
+//!   "operators": [
+//!     {"symbol": "<>", "precedence": 2, "params": ["a", "b"],
+//!      "source": "sqrt(a^2 + b^2)", "body": {"call": ["sqrt", ...]}}
+//!   ],
+//!   "history": [
+//!     {"value": 1200, "note": "rent"},
+//!     {"value": {"type": "decimal", "value": "1440.00"}}
+//!   ]
+//! }
+//! ```
+//!
+//! A bare number is a plain variable. Infinity and NaN, which JSON has no
+//! numbers for, are written as `"inf"`, `"-inf"` and `"nan"`. `precedence`
+//! defaults to 2. `body` is the parsed tree of `source`, as `Expr`
+//! serializes; `:export` writes it so the file doesn't depend on what the
+//! importer has defined. A script can leave it out and the source is parsed
+//! on import, with the operators listed before it available.
+//!
+//! `history` is the session's results with their `:note`s; importing it
+//! adds them after the results so far.
+
+use crate::environment::Environment;
+use crate::operators::{self, Operator, DEFAULT_PRECEDENCE};
+use crate::parser::Expr;
+use crate::value::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Bumped when the format changes in a way older versions can't read
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct EnvFile {
+    version: u32,
+    #[serde(default)]
+    variables: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    operators: Vec<OperatorEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<HistoryItem>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HistoryItem {
+    value: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OperatorEntry {
+    symbol: String,
+    #[serde(default = "default_precedence")]
+    precedence: u8,
+    params: [String; 2],
+    source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<Expr>,
+}
+
+fn default_precedence() -> u8 {
+    DEFAULT_PRECEDENCE
+}
+
+/// How many definitions an export wrote or an import read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub variables: usize,
+    pub operators: usize,
+    pub results: usize,
+}
+
+/// The variables and operators in `env` as pretty-printed JSON.
+pub fn export(env: &Environment) -> Result<(String, Summary), String> {
+    let variables = env
+        .variables()
+        .into_iter()
+        .map(|(name, value)| Ok((name.to_string(), serde_json::to_value(value)?)))
+        .collect::<Result<BTreeMap<_, _>, serde_json::Error>>()
+        .map_err(|e| e.to_string())?;
+    let operators: Vec<OperatorEntry> = env
+        .operators()
+        .into_iter()
+        .map(|op| OperatorEntry {
+            symbol: op.symbol.clone(),
+            precedence: op.precedence,
+            params: op.params.clone(),
+            source: op.source.clone(),
+            body: Some(op.body.clone()),
+        })
+        .collect();
+    let history = env
+        .history()
+        .iter()
+        .map(|entry| {
+            Ok(HistoryItem {
+                value: serde_json::to_value(entry.value)?,
+                note: entry.note.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>, serde_json::Error>>()
+        .map_err(|e| e.to_string())?;
+    let summary = Summary {
+        variables: variables.len(),
+        operators: operators.len(),
+        results: history.len(),
+    };
+    let file = EnvFile {
+        version: VERSION,
+        variables,
+        operators,
+        history,
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    Ok((json, summary))
+}
+
+pub(crate) fn variable(name: &str, json: serde_json::Value) -> Result<Value, String> {
+    value(json).map_err(|e| format!("Variable '{name}': {e}"))
+}
+
+fn value(json: serde_json::Value) -> Result<Value, String> {
+    if let Some(n) = json.as_f64() {
+        return Ok(Value::Number(n));
+    }
+    serde_json::from_value(json).map_err(|e| e.to_string())
+}
+
+fn operator(entry: OperatorEntry, env: &Environment) -> Result<Operator, String> {
+    let [a, b] = &entry.params;
+    let head = format!(
+        "operator {} precedence {} ({a}, {b}) =",
+        entry.symbol, entry.precedence
+    );
+    let in_operator = |e: String| format!("Operator '{}': {e}", entry.symbol);
+    let Some(body) = entry.body else {
+        return operators::parse_definition(&format!("{head} {}", entry.source), env)
+            .expect("starts with 'operator '")
+            .map_err(in_operator);
+    };
+    // The symbol, precedence and operand names are checked the way a typed
+    // definition would be; the body is the one from the file.
+    operators::parse_definition(&format!("{head} {a}"), env)
+        .expect("starts with 'operator '")
+        .map_err(in_operator)?;
+    Ok(Operator {
+        symbol: entry.symbol,
+        precedence: entry.precedence,
+        params: entry.params,
+        body,
+        source: entry.source,
+    })
+}
+
+/// Add the definitions in `json` to `env`, replacing any with the same
+/// names. Nothing changes unless the whole file is valid.
+pub fn import(json: &str, env: &mut Environment) -> Result<Summary, String> {
+    let file: EnvFile = serde_json::from_str(json).map_err(|e| format!("Invalid file: {e}"))?;
+    if file.version > VERSION {
+        return Err(format!(
+            "The file is version {}, newer than this calculator reads ({VERSION})",
+            file.version
+        ));
+    }
+
+    let mut scope = env.clone();
+    let summary = Summary {
+        variables: file.variables.len(),
+        operators: file.operators.len(),
+        results: file.history.len(),
+    };
+    for (name, json) in file.variables {
+        let value = variable(&name, json)?;
+        scope.set(&name, value);
+    }
+    for entry in file.operators {
+        let operator = operator(entry, &scope)?;
+        scope.define_operator(operator);
+    }
+    for (i, item) in file.history.into_iter().enumerate() {
+        let value = value(item.value).map_err(|e| format!("Result {}: {e}", i + 1))?;
+        scope.push_result(value);
+        if let Some(note) = item.note {
+            scope.annotate(&note)?;
+        }
+    }
+    *env = scope;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::Decimal;
+    use crate::eval::eval;
+
+    fn define(input: &str, env: &mut Environment) {
+        let operator = operators::parse_definition(input, env).unwrap().unwrap();
+        env.define_operator(operator);
+    }
+
+    fn eval_in(input: &str, env: &Environment) -> Result<Value, String> {
+        eval(&crate::parse_in(input, env)?, env)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut env = Environment::new();
+        env.set("m1", Value::Number(0.07));
+        env.set("price", Value::Decimal("19.99".parse::<Decimal>().unwrap()));
+        env.set("length", Value::uncertain(10.0, 0.5).unwrap());
+        define("operator <> (a, b) = sqrt(a^2 + b^2)", &mut env);
+        define("operator ~ precedence 0 (x, y) = x <> y * 10", &mut env);
+        env.push_result(Value::Number(1200.0));
+        env.annotate("rent").unwrap();
+        env.push_result(Value::Number(5.0));
+
+        let (json, summary) = export(&env).unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                variables: 3,
+                operators: 2,
+                results: 2
+            }
+        );
+        assert!(json.contains(r#""value": "19.99""#), "{json}");
+
+        let mut other = Environment::new();
+        assert_eq!(import(&json, &mut other), Ok(summary));
+        assert_eq!(other.variables(), env.variables());
+        assert_eq!(other.operators(), env.operators());
+        assert_eq!(other.history(), env.history());
+        assert_eq!(eval_in("3 <> 4", &other), Ok(Value::Number(5.0)));
+        assert_eq!(eval_in("0 ~ 2", &other), Ok(Value::Number(20.0)));
+    }
+
+    #[test]
+    fn test_round_trip_non_finite_numbers() {
+        let mut env = Environment::new();
+        env.set("big", Value::Number(f64::INFINITY));
+        env.set("span", Value::interval(f64::NEG_INFINITY, 0.0).unwrap());
+        env.push_result(Value::Number(f64::NAN));
+
+        let (json, _) = export(&env).unwrap();
+        assert!(json.contains(r#""value": "inf""#), "{json}");
+        assert!(!json.contains("null"), "{json}");
+
+        let mut other = Environment::new();
+        import(&json, &mut other).unwrap();
+        assert_eq!(other.get("big"), Some(Value::Number(f64::INFINITY)));
+        assert_eq!(other.get("span"), env.get("span"));
+        assert!(matches!(other.history()[0].value, Value::Number(x) if x.is_nan()));
+        let bad = r#"{"version": 1, "variables": {"x": {"type": "number", "value": "lots"}}}"#;
+        assert!(import(bad, &mut other)
+            .unwrap_err()
+            .contains("invalid number 'lots'"));
+    }
+
+    #[test]
+    fn test_import_from_a_script() {
+        let json = r#"{
+            "version": 1,
+            "variables": {"rate": 0.2, "alarm": {"type": "time", "value": "07:30:00"}},
+            "operators": [
+                {"symbol": "<>", "params": ["a", "b"], "source": "a * 2 + b"},
+                {"symbol": "@@", "precedence": 3, "params": ["a", "b"], "source": "a <> b + rate"}
+            ]
+        }"#;
+        let mut env = Environment::new();
+        import(json, &mut env).unwrap();
+        assert_eq!(env.get("rate"), Some(Value::Number(0.2)));
+        assert_eq!(env.operator("<>").unwrap().precedence, DEFAULT_PRECEDENCE);
+        assert_eq!(eval_in("1 @@ 1", &env), Ok(Value::Number(3.2)));
+    }
+
+    #[test]
+    fn test_bad_files_change_nothing() {
+        let mut env = Environment::new();
+        let bad = [
+            r#"{"version": 2}"#,
+            r#"{"variables": {}}"#,
+            r#"{"version": 1, "variables": {"ok": 1, "x": {"type": "date", "value": "soon"}}}"#,
+            r#"{"version": 1, "variables": {"ok": 1}, "operators": [{"symbol": "+", "params": ["a", "b"], "source": "a"}]}"#,
+            r#"{"version": 1, "operators": [{"symbol": "<>", "params": ["a", "a"], "source": "a", "body": {"variable": "a"}}]}"#,
+            r#"{"version": 1, "operators": [{"symbol": "<>", "params": ["a", "b"], "source": "a ?? b"}]}"#,
+            r##"{"version": 1, "operators": [{"symbol": "#", "params": ["a", "b"], "source": "a * b"}]}"##,
+        ];
+        for json in bad {
+            assert!(import(json, &mut env).is_err(), "{json}");
+        }
+        assert!(env.variables().is_empty());
+        assert!(env.operators().is_empty());
+        let err = import(bad[2], &mut env).unwrap_err();
+        assert!(
+            err.starts_with("Variable 'x': Invalid date 'soon'"),
+            "{err}"
+        );
+    }
+}
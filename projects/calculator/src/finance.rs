@@ -0,0 +1,164 @@
+//! Loan and investment math, with the spreadsheet's sign convention: money
+//! paid out is negative. `pmt(0.05/12, 360, 200000)` is a mortgage payment.
+//!
+//! `npv` and `irr` take their cash flows as the remaining arguments,
+//! `npv(0.1, -1000, 300, 400, 500)`, as a list would read as an interval.
+
+/// Each function with the fewest and most arguments it takes; `None` for
+/// any number of cash flows.
+pub const FUNCTIONS: &[(&str, usize, Option<usize>)] = &[
+    ("pmt", 3, Some(4)),
+    ("fv", 3, Some(4)),
+    ("npv", 2, None),
+    ("irr", 2, None),
+];
+
+/// Steps of Newton's method `irr` tries before giving up
+const IRR_STEPS: usize = 100;
+
+/// How many arguments `name` takes, if it's one of these functions.
+pub fn arity(name: &str) -> Option<(usize, Option<usize>)> {
+    FUNCTIONS
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, least, most)| (*least, *most))
+}
+
+/// Rates below -100% would shrink money past nothing.
+fn rate(name: &str, rate: f64) -> Result<f64, String> {
+    if rate <= -1.0 {
+        return Err(format!("{name} needs a rate above -100%, got {rate}"));
+    }
+    Ok(rate)
+}
+
+/// The payment each period that pays off `pv` over `nper` periods, leaving
+/// `fv`.
+fn pmt(rate: f64, nper: f64, pv: f64, fv: f64) -> f64 {
+    if rate == 0.0 {
+        return -(pv + fv) / nper;
+    }
+    let growth = (1.0 + rate).powf(nper);
+    -rate * (fv + pv * growth) / (growth - 1.0)
+}
+
+/// What `pv` plus a `pmt` each period comes to after `nper` periods.
+fn fv(rate: f64, nper: f64, pmt: f64, pv: f64) -> f64 {
+    if rate == 0.0 {
+        return -(pv + pmt * nper);
+    }
+    let growth = (1.0 + rate).powf(nper);
+    -(pv * growth + pmt * (growth - 1.0) / rate)
+}
+
+/// Cash flows discounted to the start of the first period, each one at the
+/// end of its period as in spreadsheets.
+fn npv(rate: f64, flows: &[f64]) -> f64 {
+    flows
+        .iter()
+        .enumerate()
+        .map(|(i, flow)| flow / (1.0 + rate).powi(i as i32 + 1))
+        .sum()
+}
+
+/// The rate at which the flows, the first one now, are worth nothing,
+/// by Newton's method from 10%.
+fn irr(flows: &[f64]) -> Result<f64, String> {
+    if !(flows.iter().any(|&f| f > 0.0) && flows.iter().any(|&f| f < 0.0)) {
+        return Err("irr needs cash flows both in and out".to_string());
+    }
+    let mut guess: f64 = 0.1;
+    for _ in 0..IRR_STEPS {
+        let (mut value, mut slope) = (0.0, 0.0);
+        for (i, flow) in flows.iter().enumerate() {
+            let i = i as f64;
+            value += flow / (1.0 + guess).powf(i);
+            slope -= i * flow / (1.0 + guess).powf(i + 1.0);
+        }
+        let next = guess - value / slope;
+        if !next.is_finite() || next <= -1.0 {
+            break;
+        }
+        if (next - guess).abs() < 1e-12 {
+            return Ok(next);
+        }
+        guess = next;
+    }
+    Err("irr didn't settle on a rate for these cash flows".to_string())
+}
+
+/// Apply `name` to `args`, which the caller has checked against `arity`.
+pub fn call(name: &str, args: &[f64]) -> Result<f64, String> {
+    match (name, args) {
+        ("pmt", &[r, nper, pv, ..]) => {
+            if nper <= 0.0 {
+                return Err(format!(
+                    "pmt needs a positive number of periods, got {nper}"
+                ));
+            }
+            Ok(pmt(
+                rate(name, r)?,
+                nper,
+                pv,
+                args.get(3).copied().unwrap_or(0.0),
+            ))
+        }
+        ("fv", &[r, nper, payment, ..]) => Ok(fv(
+            rate(name, r)?,
+            nper,
+            payment,
+            args.get(3).copied().unwrap_or(0.0),
+        )),
+        ("npv", [r, flows @ ..]) => Ok(npv(rate(name, *r)?, flows)),
+        ("irr", flows) => irr(flows),
+        _ => Err(format!("Unknown function: {name}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// To the cent, as spreadsheets show these
+    fn close(a: Result<f64, String>, b: f64) -> bool {
+        a.is_ok_and(|a| (a - b).abs() < 0.005)
+    }
+
+    #[test]
+    fn test_pmt_and_fv() {
+        // PMT(5%/12, 360, 200000) in a spreadsheet
+        assert!(close(
+            call("pmt", &[0.05 / 12.0, 360.0, 200_000.0]),
+            -1073.64
+        ));
+        // Saving up to 10000 in 5 years at 6%
+        assert!(close(
+            call("pmt", &[0.06 / 12.0, 60.0, 0.0, 10_000.0]),
+            -143.33
+        ));
+        assert!(close(call("pmt", &[0.0, 10.0, 1000.0]), -100.0));
+        // FV(6%/12, 10, -200, -500)
+        assert!(close(
+            call("fv", &[0.06 / 12.0, 10.0, -200.0, -500.0]),
+            2571.18
+        ));
+        assert!(close(call("fv", &[0.0, 12.0, -100.0]), 1200.0));
+        assert!(call("pmt", &[0.05, 0.0, 1000.0]).is_err());
+        assert!(call("fv", &[-1.5, 10.0, -100.0]).is_err());
+    }
+
+    #[test]
+    fn test_npv_and_irr() {
+        // NPV(10%, -10000, 3000, 4200, 6800)
+        assert!(close(
+            call("npv", &[0.1, -10_000.0, 3000.0, 4200.0, 6800.0]),
+            1188.44
+        ));
+        let flows = [-70_000.0, 12_000.0, 15_000.0, 18_000.0, 21_000.0, 26_000.0];
+        // IRR of the same flows: 8.66%
+        assert!(call("irr", &flows).is_ok_and(|r| (r - 0.086_63).abs() < 1e-5));
+        // Losing money is a negative rate: -2.12%
+        assert!(call("irr", &flows[..5]).is_ok_and(|r| (r + 0.021_24).abs() < 1e-5));
+        assert!(call("irr", &[100.0, 200.0]).is_err());
+    }
+}
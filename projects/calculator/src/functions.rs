@@ -0,0 +1,225 @@
+use crate::environment::Angle;
+use crate::value::Value;
+use crate::{finance, probability};
+
+/// Built-in functions of one argument, callable as `sqrt(2)` or `sin(x)`.
+/// The functions of several arguments are in `probability` and `finance`.
+pub const NAMES: &[&str] = &[
+    "sin", "cos", "tan", "sqrt", "abs", "ln", "log", "exp", "round", "floor", "ceil",
+];
+
+/// Call a built-in, checking it gets the right number of arguments.
+pub fn call(name: &str, args: &[Value]) -> Result<Value, String> {
+    if let Some(arity) = probability::arity(name) {
+        if args.len() != arity {
+            return Err(arguments(name, arity, args.len()));
+        }
+        let numbers = args
+            .iter()
+            .map(Value::as_number)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("{name}: {e}"))?;
+        return probability::call(name, &numbers).map(Value::Number);
+    }
+    if let Some((least, most)) = finance::arity(name) {
+        if args.len() < least || most.is_some_and(|most| args.len() > most) {
+            return Err(arguments_between(name, least, most, args.len()));
+        }
+        let numbers = args
+            .iter()
+            .map(Value::as_number)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("{name}: {e}"))?;
+        return finance::call(name, &numbers).map(Value::Number);
+    }
+
+    match args {
+        [arg] => call_one(name, arg),
+        _ if NAMES.contains(&name) => Err(arguments(name, 1, args.len())),
+        _ => Err(unknown(name)),
+    }
+}
+
+/// Like `call`, but with `sin`, `cos` and `tan` taking angles in `angle`.
+pub fn call_in(name: &str, args: &[Value], angle: Angle) -> Result<Value, String> {
+    match (name, args, angle) {
+        ("sin" | "cos" | "tan", [arg], Angle::Degrees)
+            if !matches!(arg, Value::Interval { .. }) =>
+        {
+            let degrees = arg.as_number().map_err(|e| format!("{name}: {e}"))?;
+            in_degrees(name, degrees).map(Value::Number)
+        }
+        _ => call(name, args),
+    }
+}
+
+/// A trig function of an angle in degrees. Whole quarter turns come out
+/// exact, so `sin(180)` is 0 rather than 1.2e-16.
+fn in_degrees(name: &str, degrees: f64) -> Result<f64, String> {
+    let turn = degrees.rem_euclid(360.0);
+    if turn % 90.0 != 0.0 {
+        return apply(name, degrees.to_radians());
+    }
+    // rem_euclid rounds tiny negative angles up to exactly 360
+    let quarter = (turn / 90.0) as usize % 4;
+    let (sin, cos) = [(0.0, 1.0), (1.0, 0.0), (0.0, -1.0), (-1.0, 0.0)][quarter];
+    match name {
+        "sin" => Ok(sin),
+        "cos" => Ok(cos),
+        _ if cos == 0.0 => Err(format!("tan of {degrees}° is undefined")),
+        _ => Ok(if sin == 0.0 { 0.0 } else { sin / cos }),
+    }
+}
+
+fn arguments(name: &str, expected: usize, got: usize) -> String {
+    let plural = if expected == 1 { "" } else { "s" };
+    format!("{name} takes {expected} argument{plural}, got {got}")
+}
+
+fn arguments_between(name: &str, least: usize, most: Option<usize>, got: usize) -> String {
+    match most {
+        Some(most) => format!("{name} takes {least} or {most} arguments, got {got}"),
+        None => format!("{name} takes at least {least} arguments, got {got}"),
+    }
+}
+
+fn call_one(name: &str, arg: &Value) -> Result<Value, String> {
+    match (name, *arg) {
+        ("abs", Value::Interval { low, high }) => {
+            let (a, b) = (low.abs(), high.abs());
+            let low = if low <= 0.0 && high >= 0.0 {
+                0.0
+            } else {
+                a.min(b)
+            };
+            Value::interval(low, a.max(b))
+        }
+        // The rest of the interval-friendly functions only ever increase,
+        // so their bounds map straight through
+        (
+            "sqrt" | "ln" | "log" | "exp" | "round" | "floor" | "ceil",
+            Value::Interval { low, high },
+        ) => Value::interval(apply(name, low)?, apply(name, high)?),
+        (_, Value::Interval { .. }) if NAMES.contains(&name) => {
+            Err(format!("{name} isn't supported for intervals"))
+        }
+        ("abs", Value::Fraction(r)) => r.abs().map(Value::Fraction),
+        ("abs", Value::Decimal(d)) => d.abs().map(Value::Decimal),
+        (_, Value::Number(n)) => apply(name, n).map(Value::Number),
+        (_, Value::Fraction(r)) => apply(name, r.to_f64()).map(Value::Number),
+        (_, Value::Decimal(d)) => apply(name, d.to_f64()).map(Value::Number),
+        (_, value) if NAMES.contains(&name) => Err(format!(
+            "{name} needs a number, got {} {value}",
+            value.kind()
+        )),
+        _ => Err(unknown(name)),
+    }
+}
+
+/// Apply a built-in to a plain number.
+pub fn apply(name: &str, x: f64) -> Result<f64, String> {
+    match name {
+        "sin" => Ok(x.sin()),
+        "cos" => Ok(x.cos()),
+        "tan" => Ok(x.tan()),
+        "abs" => Ok(x.abs()),
+        "exp" => Ok(x.exp()),
+        "round" => Ok(x.round()),
+        "floor" => Ok(x.floor()),
+        "ceil" => Ok(x.ceil()),
+        "sqrt" if x < 0.0 => Err(format!("sqrt of a negative number: {x}")),
+        "sqrt" => Ok(x.sqrt()),
+        "ln" | "log" if x <= 0.0 => Err(format!("{name} of a non-positive number: {x}")),
+        "ln" => Ok(x.ln()),
+        "log" => Ok(x.log10()),
+        _ => Err(unknown(name)),
+    }
+}
+
+/// Every built-in function, of one argument or several.
+pub fn names() -> Vec<&'static str> {
+    NAMES
+        .iter()
+        .copied()
+        .chain(probability::FUNCTIONS.iter().map(|(name, _)| *name))
+        .chain(finance::FUNCTIONS.iter().map(|(name, _, _)| *name))
+        .collect()
+}
+
+fn unknown(name: &str) -> String {
+    format!(
+        "Unknown function: {name}\nAvailable functions: {}",
+        names().join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(apply("sqrt", 9.0), Ok(3.0));
+        assert_eq!(apply("log", 1000.0), Ok(3.0));
+        assert_eq!(apply("abs", -2.5), Ok(2.5));
+        assert_eq!(apply("round", 2.5), Ok(3.0));
+        assert_eq!(apply("floor", -1.5), Ok(-2.0));
+        assert!(apply("sqrt", -1.0).is_err());
+        assert!(apply("ln", 0.0).is_err());
+        assert!(apply("frobnicate", 1.0).is_err());
+    }
+
+    #[test]
+    fn test_degrees() {
+        let degrees = |name, x| call_in(name, &[Value::Number(x)], Angle::Degrees);
+        assert_eq!(degrees("sin", 180.0), Ok(Value::Number(0.0)));
+        assert_eq!(degrees("cos", -90.0), Ok(Value::Number(0.0)));
+        assert_eq!(degrees("tan", 540.0), Ok(Value::Number(0.0)));
+        assert!(degrees("tan", 270.0).is_err());
+        assert_eq!(degrees("sin", -1e-20), Ok(Value::Number(0.0)));
+        assert_eq!(degrees("cos", -1e-20), Ok(Value::Number(1.0)));
+        let half = degrees("sin", 30.0).unwrap().as_number().unwrap();
+        assert!((half - 0.5).abs() < 1e-12);
+        // Everything else is unaffected
+        assert_eq!(degrees("sqrt", 16.0), Ok(Value::Number(4.0)));
+        let right = Value::Number(std::f64::consts::FRAC_PI_2);
+        assert_eq!(
+            call_in("sin", &[right], Angle::Radians),
+            Ok(Value::Number(1.0))
+        );
+    }
+
+    #[test]
+    fn test_interval_arguments() {
+        let v = Value::interval(4.0, 9.0).unwrap();
+        assert_eq!(call("sqrt", &[v]).unwrap().bounds(), (2.0, 3.0));
+
+        let spans_zero = Value::interval(-3.0, 2.0).unwrap();
+        assert_eq!(call("abs", &[spans_zero]).unwrap().bounds(), (0.0, 3.0));
+
+        assert!(call("sin", &[v]).is_err());
+    }
+
+    #[test]
+    fn test_argument_counts() {
+        let two = [Value::Number(10.0), Value::Number(3.0)];
+        assert_eq!(call("nCr", &two), Ok(Value::Number(120.0)));
+        assert_eq!(
+            call("sqrt", &two),
+            Err("sqrt takes 1 argument, got 2".to_string())
+        );
+        assert_eq!(
+            call("nCr", &two[..1]),
+            Err("nCr takes 2 arguments, got 1".to_string())
+        );
+        assert!(call("nope", &two).unwrap_err().contains("binom_pmf"));
+        assert_eq!(
+            call("pmt", &two),
+            Err("pmt takes 3 or 4 arguments, got 2".to_string())
+        );
+        assert_eq!(
+            call("irr", &two[..1]),
+            Err("irr takes at least 2 arguments, got 1".to_string())
+        );
+    }
+}
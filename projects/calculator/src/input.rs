@@ -0,0 +1,196 @@
+//! Turning REPL lines into statements: an expression can span several lines,
+//! and one line can hold several statements separated by `;`.
+
+use crate::memory;
+
+/// A line ending in one of these continues on the next line.
+const TRAILING_OPERATORS: &[char] = &['+', '-', '*', '/', '^', '%', '±', '=', '|'];
+
+/// Collects lines until they form a complete input.
+#[derive(Debug, Default)]
+pub struct LineBuffer {
+    pending: String,
+}
+
+impl LineBuffer {
+    pub fn new() -> Self {
+        LineBuffer::default()
+    }
+
+    /// Whether earlier lines are waiting for the rest of their input, so the
+    /// REPL can show a continuation prompt.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Add a line. Returns the whole input once it's complete; an explicit
+    /// `\` at the end always continues.
+    pub fn push(&mut self, line: &str) -> Option<String> {
+        let line = strip_comment(line).trim();
+        if let Some(start) = line.strip_suffix('\\') {
+            self.append(start);
+            return None;
+        }
+
+        self.append(line);
+        if needs_more(&self.pending) {
+            return None;
+        }
+        Some(std::mem::take(&mut self.pending))
+    }
+
+    fn append(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        if !self.pending.is_empty() {
+            self.pending.push(' ');
+        }
+        self.pending.push_str(line);
+    }
+
+    /// Drop whatever was pending, e.g. after Ctrl-D.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// Unclosed brackets or a trailing operator. `m-` and `m+` end in an
+/// operator but are complete commands.
+fn needs_more(input: &str) -> bool {
+    let Some(last) = statements(input).last().copied() else {
+        return false;
+    };
+    if memory::parse_command(last).is_some() {
+        return false;
+    }
+    depth(input) > 0 || last.ends_with(TRAILING_OPERATORS)
+}
+
+/// How many `(` and `[` are still open.
+fn depth(input: &str) -> i32 {
+    input.chars().fold(0, |depth, c| match c {
+        '(' | '[' => depth + 1,
+        ')' | ']' => depth - 1,
+        _ => depth,
+    })
+}
+
+/// `line` without a trailing `# comment`. As in the lexer, a comment starts
+/// at any `#`, except inside double quotes, as in `:note "see #2"`.
+pub fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return line[..i].trim_end(),
+            _ => {}
+        }
+    }
+    line
+}
+
+/// `name = expression` split into its name and expression, if `input` is
+/// an assignment rather than a comparison like `x == 2`.
+pub fn assignment(input: &str) -> Option<(&str, &str)> {
+    let (name, expression) = input.split_once('=')?;
+    let name = name.trim();
+    let is_name = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+    (is_name && !expression.starts_with('=')).then(|| (name, expression.trim()))
+}
+
+/// The `;`-separated statements in `input`, trimmed, skipping empty ones.
+/// Separators inside brackets don't split.
+pub fn statements(input: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ';' if depth <= 0 => {
+                result.push(&input[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(&input[start..]);
+    result
+        .into_iter()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trailing_operator_continues() {
+        let mut buffer = LineBuffer::new();
+        assert_eq!(buffer.push("1 +"), None);
+        assert!(buffer.is_pending());
+        assert_eq!(buffer.push("2 *"), None);
+        assert_eq!(buffer.push("3"), Some("1 + 2 * 3".to_string()));
+        assert!(!buffer.is_pending());
+    }
+
+    #[test]
+    fn test_backslash_and_brackets_continue() {
+        let mut buffer = LineBuffer::new();
+        assert_eq!(buffer.push("sqrt(16 \\"), None);
+        assert_eq!(buffer.push(""), None);
+        assert_eq!(buffer.push(")"), Some("sqrt(16 )".to_string()));
+
+        assert_eq!(buffer.push("(1 + 2"), None);
+        assert_eq!(buffer.push(") * 3"), Some("(1 + 2 ) * 3".to_string()));
+    }
+
+    #[test]
+    fn test_memory_commands_are_complete() {
+        let mut buffer = LineBuffer::new();
+        assert_eq!(buffer.push("m-"), Some("m-".to_string()));
+        assert_eq!(buffer.push("2; m+ m1"), Some("2; m+ m1".to_string()));
+    }
+
+    #[test]
+    fn test_assignment() {
+        assert_eq!(assignment("g = 9.81"), Some(("g", "9.81")));
+        assert_eq!(
+            assignment("tax_rate=0.2 * 1.5"),
+            Some(("tax_rate", "0.2 * 1.5"))
+        );
+        assert_eq!(assignment("x == 2"), None);
+        assert_eq!(assignment("2x = 4"), None);
+        assert_eq!(assignment("a <=> b"), None);
+        assert_eq!(assignment("1 + 2"), None);
+    }
+
+    #[test]
+    fn test_strip_comment() {
+        assert_eq!(strip_comment("price * 1.2 # with VAT"), "price * 1.2");
+        assert_eq!(strip_comment("# whole line"), "");
+        assert_eq!(strip_comment("1 ## 2"), "1");
+        assert_eq!(
+            strip_comment(r#":note "see #2" # why"#),
+            r#":note "see #2""#
+        );
+
+        let mut buffer = LineBuffer::new();
+        assert_eq!(buffer.push("1 + # continues"), None);
+        assert_eq!(buffer.push("2 # done"), Some("1 + 2".to_string()));
+    }
+
+    #[test]
+    fn test_statements() {
+        assert_eq!(statements("1 + 2; ans * 3"), vec!["1 + 2", "ans * 3"]);
+        assert_eq!(statements(" ; 4 ;"), vec!["4"]);
+        assert_eq!(statements("[1; 2]"), vec!["[1; 2]"]);
+        assert!(statements("").is_empty());
+    }
+}
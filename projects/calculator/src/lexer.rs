@@ -0,0 +1,410 @@
+use crate::datetime;
+use crate::operators::OPERATOR_CHARS;
+use crate::si;
+use crate::value::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Number(f64),
+    /// `2024-03-01`, `9:30`, or `2h15m`
+    Temporal(Value),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    Percent,
+    /// `±` or `+/-`
+    PlusMinus,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    /// Only meaningful in equations passed to `solve`
+    Equals,
+    /// `$3`: the third result of the session
+    ResultRef(usize),
+    /// `|`: pass the result on to a function, as in `2 ^ 10 | sqrt`
+    Pipe,
+    /// A user-defined operator like `<>`
+    Operator(String),
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        // `# note` is a comment to the end of the line
+        if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            if let Some((value, end)) = date_or_time(&chars, i)? {
+                tokens.push(Token::Temporal(value));
+                i = end;
+                continue;
+            }
+        }
+
+        if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Scientific notation: 1.5e3, 2E-4
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+
+            let text: String = chars[start..i].iter().collect();
+            let mut n: f64 = text
+                .parse()
+                .map_err(|_| format!("Invalid number: {text}"))?;
+
+            if let Some((seconds, end)) = duration(&chars, i, n) {
+                let duration = datetime::duration_from_seconds(seconds)?;
+                tokens.push(Token::Temporal(Value::Duration(duration)));
+                i = end;
+                continue;
+            }
+
+            // SI prefix: 4.7k, 3.3µ. Only a lone letter counts, so 2pi and
+            // 3x still multiply by a variable.
+            if let Some(multiplier) = chars.get(i).and_then(|&c| si::multiplier(c)) {
+                let next = chars.get(i + 1);
+                if !next.is_some_and(|&c| c.is_alphanumeric() || c == '_') {
+                    n *= multiplier;
+                    i += 1;
+                }
+            }
+            tokens.push(Token::Number(n));
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        if c == '$' {
+            let start = i + 1;
+            i = start;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits: String = chars[start..i].iter().collect();
+            let n = digits
+                .parse()
+                .map_err(|_| "Expected a result number after $, like $1".to_string())?;
+            tokens.push(Token::ResultRef(n));
+            continue;
+        }
+
+        if OPERATOR_CHARS.contains(c) {
+            let start = i;
+            while i < chars.len() && OPERATOR_CHARS.contains(chars[i]) {
+                i += 1;
+            }
+            tokens.push(Token::Operator(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        if c == '+' && chars.get(i + 1) == Some(&'/') && chars.get(i + 2) == Some(&'-') {
+            tokens.push(Token::PlusMinus);
+            i += 3;
+            continue;
+        }
+
+        let token = match c {
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '^' => Token::Caret,
+            '%' => Token::Percent,
+            '±' => Token::PlusMinus,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ',' => Token::Comma,
+            '=' => Token::Equals,
+            '|' => Token::Pipe,
+            _ => {
+                return Err(format!(
+                    "Unknown operator: {c}\nSupported operators: + - * / ^ % ± = |, \
+                     and operators you define from {OPERATOR_CHARS}"
+                ))
+            }
+        };
+        tokens.push(token);
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+/// A `YYYY-MM-DD[THH:MM[:SS]]` date or `H:MM[:SS]` time starting at
+/// `start`, and where it ends. Anything shaped like one that doesn't parse
+/// is an error rather than quietly becoming subtraction.
+fn date_or_time(chars: &[char], start: usize) -> Result<Option<(Value, usize)>, String> {
+    let digits = |from: usize, count: usize| {
+        (from..from + count).all(|j| chars.get(j).is_some_and(|c| c.is_ascii_digit()))
+    };
+    let extend = |mut end: usize| {
+        while chars
+            .get(end)
+            .is_some_and(|c| c.is_ascii_digit() || *c == ':')
+        {
+            end += 1;
+        }
+        end
+    };
+
+    let is_date = digits(start, 4)
+        && chars.get(start + 4) == Some(&'-')
+        && digits(start + 5, 2)
+        && chars.get(start + 7) == Some(&'-')
+        && digits(start + 8, 2);
+    if is_date {
+        let end = match chars.get(start + 10) {
+            Some('T') => extend(start + 11),
+            _ => start + 10,
+        };
+        let text: String = chars[start..end].iter().collect();
+        let date = datetime::parse_date(&text).ok_or(format!("Invalid date: {text}"))?;
+        return Ok(Some((Value::Date(date), end)));
+    }
+
+    let hour_digits = (0..=2)
+        .take_while(|&n| digits(start, n))
+        .last()
+        .unwrap_or(0);
+    if hour_digits > 0 && chars.get(start + hour_digits) == Some(&':') {
+        let end = extend(start + hour_digits);
+        let text: String = chars[start..end].iter().collect();
+        let time = datetime::parse_time(&text).ok_or(format!("Invalid time: {text}"))?;
+        return Ok(Some((Value::Time(time), end)));
+    }
+
+    Ok(None)
+}
+
+/// A duration whose first number, `n`, has already been read and whose unit
+/// starts at `start`: `45 days`, `1.5h`, `2h15m`. Returns its length in
+/// seconds and where it ends, or `None` if no time unit follows.
+fn duration(chars: &[char], start: usize, n: f64) -> Option<(f64, usize)> {
+    let (per_unit, mut end) = unit(chars, start, false)?;
+    let mut seconds = n * per_unit;
+
+    // More number-unit pairs directly after: 2h15m, 1d12h
+    while chars.get(end).is_some_and(|c| c.is_ascii_digit()) {
+        let mut digits_end = end;
+        while chars
+            .get(digits_end)
+            .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+        {
+            digits_end += 1;
+        }
+        let text: String = chars[end..digits_end].iter().collect();
+        let Ok(m) = text.parse::<f64>() else { break };
+        let Some((per_unit, unit_end)) = unit(chars, digits_end, true) else {
+            break;
+        };
+        seconds += m * per_unit;
+        end = unit_end;
+    }
+
+    Some((seconds, end))
+}
+
+/// Seconds per the time unit at `start`, and where it ends. Long unit names
+/// may follow a space; `compound` units must be attached.
+fn unit(chars: &[char], start: usize, compound: bool) -> Option<(f64, usize)> {
+    let word_end = |from: usize| {
+        let mut end = from;
+        while chars.get(end).is_some_and(|c| c.is_alphabetic()) {
+            end += 1;
+        }
+        end
+    };
+
+    let end = word_end(start);
+    if end > start {
+        let word: String = chars[start..end].iter().collect();
+        return datetime::unit_seconds(&word, false, compound).map(|s| (s, end));
+    }
+    if compound {
+        return None;
+    }
+
+    let mut spaced = start;
+    while chars.get(spaced) == Some(&' ') {
+        spaced += 1;
+    }
+    let end = word_end(spaced);
+    if spaced == start || end == spaced {
+        return None;
+    }
+    let word: String = chars[spaced..end].iter().collect();
+    datetime::unit_seconds(&word, true, false).map(|s| (s, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_expression() {
+        assert_eq!(
+            tokenize("2 * (3.5 - x)").unwrap(),
+            vec![
+                Token::Number(2.0),
+                Token::Star,
+                Token::LParen,
+                Token::Number(3.5),
+                Token::Minus,
+                Token::Ident("x".to_string()),
+                Token::RParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_plus_minus_spellings() {
+        assert_eq!(tokenize("±").unwrap(), vec![Token::PlusMinus]);
+        assert_eq!(tokenize("+/-").unwrap(), vec![Token::PlusMinus]);
+    }
+
+    #[test]
+    fn test_tokenize_scientific() {
+        assert_eq!(tokenize("1.5e3").unwrap(), vec![Token::Number(1500.0)]);
+        assert_eq!(tokenize("2E-1").unwrap(), vec![Token::Number(0.2)]);
+    }
+
+    #[test]
+    fn test_tokenize_si_prefixes() {
+        assert_eq!(
+            tokenize("4.7k * 2").unwrap(),
+            vec![Token::Number(4700.0), Token::Star, Token::Number(2.0)]
+        );
+        assert_eq!(tokenize("1M").unwrap(), vec![Token::Number(1e6)]);
+        assert_eq!(tokenize("10m").unwrap(), vec![Token::Number(0.01)]);
+        assert_eq!(
+            tokenize("2pi").unwrap(),
+            vec![Token::Number(2.0), Token::Ident("pi".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_dates_and_times() {
+        let tokens = tokenize("2024-03-01 - 2024-01-01").unwrap();
+        assert_eq!(tokens.len(), 3);
+        assert!(matches!(tokens[0], Token::Temporal(Value::Date(_))));
+        assert!(matches!(
+            tokenize("9:30").unwrap()[..],
+            [Token::Temporal(Value::Time(_))]
+        ));
+        assert!(tokenize("2024-02-30").is_err());
+        assert!(tokenize("9:75").is_err());
+        // Spaced out it's still arithmetic
+        assert_eq!(tokenize("2024 - 03 - 01").unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_tokenize_durations() {
+        let duration = |s: &str| match tokenize(s).unwrap()[..] {
+            [Token::Temporal(Value::Duration(d))] => d.num_seconds(),
+            ref other => panic!("not a duration: {other:?}"),
+        };
+        assert_eq!(duration("45 days"), 45 * 86_400);
+        assert_eq!(duration("2h15m"), 8_100);
+        assert_eq!(duration("1.5h"), 5_400);
+        assert_eq!(duration("90s"), 90);
+        assert_eq!(
+            tokenize("2 h").unwrap(),
+            vec![Token::Number(2.0), Token::Ident("h".to_string())]
+        );
+        assert_eq!(tokenize("2sin").unwrap()[0], Token::Number(2.0));
+        assert!(tokenize("1e300h").is_err());
+    }
+
+    #[test]
+    fn test_tokenize_results_and_pipes() {
+        assert_eq!(
+            tokenize("$12 | sqrt").unwrap(),
+            vec![
+                Token::ResultRef(12),
+                Token::Pipe,
+                Token::Ident("sqrt".to_string())
+            ]
+        );
+        assert!(tokenize("$x").unwrap_err().contains("$1"));
+    }
+
+    #[test]
+    fn test_comments() {
+        assert_eq!(
+            tokenize("2 * 3 # six (roughly)").unwrap(),
+            [Token::Number(2.0), Token::Star, Token::Number(3.0)]
+        );
+        assert!(tokenize("# nothing but a comment").unwrap().is_empty());
+        assert_eq!(tokenize("a ## b").unwrap(), [Token::Ident("a".to_string())]);
+    }
+
+    #[test]
+    fn test_unknown_operator() {
+        assert!(tokenize("1 { 2").is_err());
+    }
+
+    #[test]
+    fn test_custom_operator_symbols() {
+        assert_eq!(
+            tokenize("a<>-b").unwrap(),
+            vec![
+                Token::Ident("a".to_string()),
+                Token::Operator("<>".to_string()),
+                Token::Minus,
+                Token::Ident("b".to_string())
+            ]
+        );
+        // Times still lex as times
+        assert!(matches!(
+            tokenize("9:30").unwrap()[..],
+            [Token::Temporal(_)]
+        ));
+    }
+
+    #[test]
+    fn test_invalid_number() {
+        assert!(tokenize("1.2.3").is_err());
+    }
+}
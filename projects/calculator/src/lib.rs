@@ -0,0 +1,71 @@
+//! Expression parsing and evaluation behind the calculator REPL, usable from
+//! other projects that need to evaluate arithmetic.
+//!
+//! ```
+//! use calculator::{eval, parse, Environment, Value};
+//!
+//! let mut env = Environment::new();
+//! env.set("width", Value::Number(4.0));
+//!
+//! let expr = parse("width * (3 + 2)").unwrap();
+//! assert_eq!(eval(&expr, &env), Ok(Value::Number(20.0)));
+//! ```
+//!
+//! Uncertain values propagate through every operator:
+//!
+//! ```
+//! let env = calculator::Environment::new();
+//! let expr = calculator::parse("(10 ± 0.5) * 2").unwrap();
+//! let result = calculator::eval(&expr, &env).unwrap();
+//! assert_eq!(result.to_string(), "20 ± 1 [19, 21]");
+//! ```
+
+pub mod bits;
+pub mod check;
+pub mod config;
+pub mod datetime;
+pub mod decimal;
+pub mod environment;
+pub mod eval;
+pub mod export;
+pub mod finance;
+pub mod functions;
+pub mod input;
+pub mod lexer;
+pub mod memory;
+pub mod operators;
+pub mod parser;
+pub mod persist;
+pub mod plot;
+pub mod probability;
+pub mod rational;
+pub mod rpn;
+pub mod si;
+pub mod solver;
+pub mod suggest;
+pub mod tape;
+pub mod trace;
+pub mod value;
+
+pub use bits::{bits, Bits};
+pub use environment::Environment;
+pub use eval::eval;
+pub use operators::Operator;
+pub use parser::Expr;
+pub use plot::{plot, Plot};
+pub use rpn::Rpn;
+pub use solver::{solve, Solution};
+pub use tape::Tape;
+pub use value::Value;
+
+/// Tokenize and parse `input` into an expression tree.
+pub fn parse(input: &str) -> Result<Expr, String> {
+    let tokens = lexer::tokenize(input)?;
+    parser::parse(&tokens)
+}
+
+/// Like `parse`, but with the custom operators defined in `env`.
+pub fn parse_in(input: &str, env: &Environment) -> Result<Expr, String> {
+    let tokens = lexer::tokenize(input)?;
+    parser::parse_in(&tokens, env)
+}
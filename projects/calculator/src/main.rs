@@ -1,157 +1,720 @@
+mod currency;
+
+use calculator::decimal::Rounding;
+use calculator::environment::{Angle, Mode};
+use calculator::export::{self, Summary};
+use calculator::input::{self, LineBuffer};
+use calculator::persist::{self, Store};
+use calculator::trace;
+use calculator::{Environment, Rpn, Tape, Value};
 use std::io::{self, Write};
+use std::path::PathBuf;
 
-fn parse_expression(input: &str) -> Option<(f64, &str, f64)> {
-    let parts: Vec<&str> = input.split_whitespace().collect();
+fn evaluate(input: &str, env: &Environment) -> Result<Value, String> {
+    let expr = calculator::parse_in(input, env)?;
+    calculator::eval(&expr, env)
+}
 
-    if parts.len() != 3 {
-        println!("Usage: <number> <operator> <number>");
-        return None;
-    }
+fn convert_currency(conversion: &currency::Conversion) -> Result<String, String> {
+    let (rates, stale) = currency::get_rates()?;
+    let result = rates.convert(conversion)?;
+    let date = currency::format_date(rates.updated_at);
 
-    let left: f64 = match parts[0].parse() {
-        Ok(n) => n,
-        Err(_) => {
-            println!("Invalid number: {}", parts[0]);
-            return None;
-        }
+    let note = if stale {
+        format!("cached rates from {date}, offline")
+    } else {
+        format!("rates from {date}")
     };
 
-    let right: f64 = match parts[2].parse() {
-        Ok(n) => n,
-        Err(_) => {
-            println!("Invalid number: {}", parts[2]);
-            return None;
+    Ok(format!("= {result:.2} {} ({note})", conversion.to))
+}
+
+fn describe(summary: Summary) -> String {
+    let plural = |n: usize, word: &str| {
+        if n == 1 {
+            format!("1 {word}")
+        } else {
+            format!("{n} {word}s")
         }
     };
+    let mut parts = vec![
+        plural(summary.variables, "variable"),
+        plural(summary.operators, "operator"),
+    ];
+    if summary.results > 0 {
+        parts.push(plural(summary.results, "result"));
+    }
+    let last = parts.pop().unwrap_or_default();
+    format!("{} and {last}", parts.join(", "))
+}
 
-    Some((left, parts[1], right))
+fn export_env(path: &str, env: &Environment) -> Result<String, String> {
+    let (json, summary) = export::export(env)?;
+    std::fs::write(path, json + "\n").map_err(|e| format!("could not write {path}: {e}"))?;
+    Ok(format!("Exported {} to {path}", describe(summary)))
 }
 
-fn calculate(left: f64, operator: &str, right: f64) -> Option<f64> {
-    match operator {
-        "+" => Some(left + right),
-        "-" => Some(left - right),
-        "*" => Some(left * right),
-        "/" => {
-            if right == 0.0 {
-                println!("Error: Division by zero");
-                None
-            } else {
-                Some(left / right)
-            }
+fn import_env(path: &str, env: &mut Environment) -> Result<String, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+    let summary = export::import(&json, env).map_err(|e| format!("{path}: {e}"))?;
+    Ok(format!("Imported {} from {path}", describe(summary)))
+}
+
+/// Handle one line in tape mode. Returns false when the user leaves the mode.
+fn tape_input(tape: &mut Tape, input: &str, env: &mut Environment) -> bool {
+    match input {
+        ":calc" | ":tape" => {
+            println!("Left tape mode");
+            return false;
         }
-        "^" => Some(left.powf(right)),
-        "%" => Some(left % right),
-        _ => {
-            println!("Unknown operator: {operator}");
-            println!("Supported operators: + - * / ^ %");
-            None
+        "subtotal" => println!("{}", tape.subtotal()),
+        "total" => {
+            let (total, line) = tape.take_total();
+            println!("{line}");
+            env.set("ans", Value::Number(total));
         }
+        "clear" => {
+            tape.clear();
+            println!("Cleared");
+        }
+        _ => match tape.enter(input, env) {
+            Ok(entry) => println!("{entry}"),
+            Err(e) => println!("Error: {e}"),
+        },
     }
+    true
 }
 
-fn main() {
-    println!("Simple Calculator — type an expression or 'quit' to exit");
+/// Everything the REPL remembers between lines.
+#[derive(Default)]
+struct Session {
+    env: Environment,
+    tape: Option<Tape>,
+    /// Set while in `:mode rpn`
+    rpn: Option<Rpn>,
+    si_output: bool,
+    /// Print every result on a line like `a; b; c`, not just the last
+    echo_all: bool,
+    /// Show decimals rounded to this many places, set with `:round`
+    rounding: Option<(u32, Rounding)>,
+    /// Print each expression's parse tree and steps, set with `:trace`
+    trace: bool,
+    /// Variables kept for the next session with `:persist`
+    persisted: Store,
+}
 
-    loop {
-        print!("> ");
-        io::stdout().flush().unwrap();
+impl Session {
+    fn show(&self, value: &Value) -> String {
+        match (value, self.rounding) {
+            _ if self.si_output => value.to_si_string(),
+            (Value::Decimal(d), Some((places, rounding))) => d.format_places(places, rounding),
+            _ => value.to_string(),
+        }
+    }
 
-        let mut input = String::new();
-        match io::stdin().read_line(&mut input) {
-            Ok(0) => break, // EOF
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!("Error reading input: {e}");
-                break;
+    /// The stack, top last and numbered 1 like an RPN calculator shows it.
+    fn show_stack(&self, rpn: &Rpn) {
+        let stack = rpn.stack();
+        if stack.is_empty() {
+            println!("  (empty)");
+        }
+        for (i, value) in stack.iter().enumerate() {
+            println!("  {}: {}", stack.len() - i, self.show(value));
+        }
+    }
+
+    /// Handle one line of `:mode rpn` that isn't a `:` command.
+    fn rpn_input(&mut self, input: &str) {
+        let Some(mut rpn) = self.rpn.take() else {
+            return;
+        };
+        match rpn.enter(input, &self.env) {
+            Ok(()) => {
+                if let Some(top) = rpn.top() {
+                    self.env.set("ans", top);
+                }
+                self.show_stack(&rpn);
             }
+            Err(e) => println!("Error: {e}"),
         }
+        self.rpn = Some(rpn);
+    }
 
-        let input = input.trim();
+    /// Evaluate `input`, printing its parse tree and then each step.
+    fn traced(&self, input: &str) -> Result<Value, String> {
+        let expr = calculator::parse_in(input, &self.env)?;
+        for line in trace::tree(&expr) {
+            println!("  {line}");
+        }
+        let trace = trace::trace(&expr, &self.env);
+        for (i, step) in trace.steps.iter().enumerate() {
+            let arrow = if i == 0 { " " } else { "→" };
+            println!("{arrow} {step}");
+        }
+        trace.result
+    }
 
-        if input.is_empty() {
-            continue;
+    /// `:round 2`, `:round 2 half-up`, or `:round off`.
+    fn set_rounding(&mut self, setting: &str) -> Result<(), String> {
+        let usage = "use :round <places> [half-even|half-up] or :round off";
+        let mut words = setting.split_whitespace();
+        match words.next() {
+            None => {}
+            Some("off") => self.rounding = None,
+            Some(places) => {
+                let places: u32 = places
+                    .parse()
+                    .ok()
+                    .filter(|&p| p <= calculator::decimal::MAX_SCALE)
+                    .ok_or(format!("Invalid number of places '{places}'; {usage}"))?;
+                let rounding = match words.next() {
+                    Some(mode) => mode.parse::<Rounding>()?,
+                    None => self.rounding.map(|(_, r)| r).unwrap_or_default(),
+                };
+                self.rounding = Some((places, rounding));
+            }
         }
+        match words.next() {
+            Some(extra) => Err(format!("Unexpected '{extra}'; {usage}")),
+            None => Ok(()),
+        }
+    }
 
+    /// Handle one statement. `echo` is whether to print its result. Returns
+    /// false when the user quits.
+    fn statement(&mut self, input: &str, echo: bool) -> bool {
         if input == "quit" || input == "exit" {
             println!("Goodbye!");
-            break;
+            return false;
+        }
+
+        if let Some(t) = self.tape.as_mut() {
+            if !tape_input(t, input, &mut self.env) {
+                self.tape = None;
+            }
+            return true;
+        }
+
+        if self.rpn.is_some() {
+            if input == ":calc" {
+                println!("Left RPN mode");
+                self.rpn = None;
+                return true;
+            }
+            if !input.starts_with(':') {
+                self.rpn_input(input);
+                return true;
+            }
+        }
+
+        if input == ":tape" {
+            println!("Tape mode: enter amounts like 100, + 25, - 10, * 2");
+            println!("Commands: subtotal, total, clear, :calc to go back");
+            self.tape = Some(Tape::new());
+            return true;
         }
 
-        if let Some((left, op, right)) = parse_expression(input) {
-            if let Some(result) = calculate(left, op, right) {
-                // Display as integer if it's a whole number
-                if result.fract() == 0.0 && result.abs() < i64::MAX as f64 {
-                    println!("= {}", result as i64);
-                } else {
-                    println!("= {result}");
+        if let Some(setting) = input.strip_prefix(":echo") {
+            match setting.trim() {
+                "all" => self.echo_all = true,
+                "last" => self.echo_all = false,
+                "" => {}
+                other => {
+                    println!("Error: Unknown setting '{other}'; use :echo all or :echo last");
+                    return true;
                 }
             }
+            let which = if self.echo_all {
+                "every"
+            } else {
+                "only the last"
+            };
+            println!("Showing {which} result on lines with several statements");
+            return true;
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        if let Some(setting) = input.strip_prefix(":trace") {
+            match setting.trim() {
+                "on" => self.trace = true,
+                "off" => self.trace = false,
+                "" => {}
+                other => {
+                    println!("Error: Unknown setting '{other}'; use :trace on or :trace off");
+                    return true;
+                }
+            }
+            let state = if self.trace { "on" } else { "off" };
+            println!("Tracing {state}");
+            return true;
+        }
 
-    #[test]
-    fn test_addition() {
-        assert_eq!(calculate(2.0, "+", 3.0), Some(5.0));
-    }
+        if let Some(setting) = input.strip_prefix(":angle") {
+            match setting.trim() {
+                "" => {}
+                unit => match unit.parse() {
+                    Ok(angle) => self.env.set_angle(angle),
+                    Err(e) => {
+                        println!("Error: {e}");
+                        return true;
+                    }
+                },
+            }
+            match self.env.angle() {
+                Angle::Radians => println!("Angles in radians: sin(pi/2) = 1"),
+                Angle::Degrees => println!("Angles in degrees: sin(90) = 1"),
+            }
+            return true;
+        }
 
-    #[test]
-    fn test_subtraction() {
-        assert_eq!(calculate(10.0, "-", 4.0), Some(6.0));
-    }
+        if input == ":si" {
+            self.si_output = !self.si_output;
+            let state = if self.si_output {
+                "on (1.5k, 3.3µ)"
+            } else {
+                "off"
+            };
+            println!("Engineering output {state}");
+            return true;
+        }
 
-    #[test]
-    fn test_multiplication() {
-        assert_eq!(calculate(3.0, "*", 7.0), Some(21.0));
-    }
+        if let Some(mode) = input.strip_prefix(":mode") {
+            let mode = mode.trim();
+            if mode == "rpn" {
+                println!("RPN mode: 3 4 + 5 * works on the stack; dup, swap, drop, clear");
+                println!("Operators and functions work as usual, :calc goes back");
+                let rpn = self.rpn.take().unwrap_or_default();
+                self.show_stack(&rpn);
+                self.rpn = Some(rpn);
+                return true;
+            }
+            if matches!(mode, "frac" | "decimal" | "float") && self.rpn.take().is_some() {
+                println!("Left RPN mode");
+            }
+            match mode {
+                "frac" => self.env.set_mode(Mode::Fraction),
+                "decimal" => self.env.set_mode(Mode::Decimal),
+                "float" => self.env.set_mode(Mode::Float),
+                "" => {}
+                other => {
+                    println!(
+                        "Error: Unknown mode '{other}'; use :mode frac, :mode decimal, :mode float or :mode rpn"
+                    );
+                    return true;
+                }
+            }
+            match self.env.mode() {
+                Mode::Fraction => println!(
+                    "Fraction mode: 1/3 + 1/6 = 1/2 (:decimal shows the last result as a decimal)"
+                ),
+                Mode::Decimal => {
+                    println!("Decimal mode: 0.1 + 0.2 = 0.3 (:round 2 shows results to the cent)")
+                }
+                Mode::Float => println!("Float mode"),
+            }
+            return true;
+        }
 
-    #[test]
-    fn test_division() {
-        assert_eq!(calculate(10.0, "/", 4.0), Some(2.5));
-    }
+        if let Some(setting) = input.strip_prefix(":round") {
+            if let Err(e) = self.set_rounding(setting) {
+                println!("Error: {e}");
+                return true;
+            }
+            match self.rounding {
+                Some((places, rounding)) => {
+                    println!("Showing decimals to {places} places, rounding {rounding}")
+                }
+                None => println!("Showing decimals in full"),
+            }
+            return true;
+        }
 
-    #[test]
-    fn test_division_by_zero() {
-        assert_eq!(calculate(10.0, "/", 0.0), None);
-    }
+        if input == ":decimal" {
+            match self.env.get("ans") {
+                Some(Value::Fraction(r)) => {
+                    let decimal = Value::Number(r.to_f64());
+                    println!("= {decimal}");
+                    self.env.set("ans", decimal);
+                }
+                Some(value) => println!("= {value}"),
+                None => println!("Error: No result yet; calculate something first"),
+            }
+            return true;
+        }
+
+        if let Some(command) = calculator::memory::parse_command(input) {
+            match command.and_then(|c| calculator::memory::apply(&c, &mut self.env).map(|l| (c, l)))
+            {
+                Ok((command, line)) => {
+                    println!("{line}");
+                    if let Err(e) = update_persisted(&mut self.persisted, |store| {
+                        calculator::memory::remember(&command, &self.env, store)
+                    }) {
+                        println!("Warning: memory not kept for next time: {e}");
+                    }
+                }
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if let Some(definition) = calculator::operators::parse_definition(input, &self.env) {
+            match definition {
+                Ok(operator) => {
+                    println!("Defined {operator}");
+                    self.env.define_operator(operator);
+                }
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if input == ":operators" {
+            let operators = self.env.operators();
+            if operators.is_empty() {
+                println!("No operators defined; try operator <> (a, b) = sqrt(a^2 + b^2)");
+            }
+            for operator in operators {
+                println!("{operator}");
+            }
+            return true;
+        }
+
+        if let Some(note) = input
+            .strip_prefix(":note")
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            let note = note.trim();
+            let note = note
+                .strip_prefix('"')
+                .and_then(|n| n.strip_suffix('"'))
+                .unwrap_or(note);
+            if note.is_empty() {
+                println!(
+                    "Error: Usage: :note \"reason\", to note why the last result was worked out"
+                );
+                return true;
+            }
+            match self.env.annotate(note) {
+                Ok(n) => println!("Noted on ${n}: {note}"),
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if input == ":history" {
+            let history = self.env.history();
+            if history.is_empty() {
+                println!("No results yet");
+            }
+            for (i, entry) in history.iter().enumerate() {
+                let line = format!("${} = {}", i + 1, self.show(&entry.value));
+                match &entry.note {
+                    Some(note) => println!("{line}  # {note}"),
+                    None => println!("{line}"),
+                }
+            }
+            return true;
+        }
+
+        if let Some(names) = input
+            .strip_prefix(":persist")
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            let names: Vec<&str> = names.split_whitespace().collect();
+            if names.is_empty() {
+                println!("Error: Usage: :persist name ..., to keep variables for the next session");
+                return true;
+            }
+            match update_persisted(&mut self.persisted, |store| {
+                store.persist(&names, &self.env)
+            }) {
+                Ok(()) => println!(
+                    "Persisted {}; loaded in every new session",
+                    names.join(", ")
+                ),
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if let Some(names) = input
+            .strip_prefix(":unpersist")
+            .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        {
+            let names: Vec<&str> = names.split_whitespace().collect();
+            if names.is_empty() {
+                println!("Error: Usage: :unpersist name ..., to stop keeping variables");
+                return true;
+            }
+            match update_persisted(&mut self.persisted, |store| store.unpersist(&names)) {
+                Ok(()) => println!("No longer persisting {}", names.join(", ")),
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if input == ":persisted" {
+            if self.persisted.is_empty() {
+                println!("No variables persisted; try g = 9.81 then :persist g");
+            }
+            for (name, value) in self.persisted.variables() {
+                println!("{name} = {}", self.show(&value));
+            }
+            return true;
+        }
+
+        if let Some(path) = input.strip_prefix(":export ") {
+            match export_env(path.trim(), &self.env) {
+                Ok(line) => println!("{line}"),
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if let Some(path) = input.strip_prefix(":import ") {
+            match import_env(path.trim(), &mut self.env) {
+                Ok(line) => println!("{line}"),
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if let Some(conversion) = currency::parse_conversion(input) {
+            match convert_currency(&conversion) {
+                Ok(line) => println!("{line}"),
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if let Some(function) = input.strip_prefix("plot ") {
+            match calculator::plot(function, &self.env) {
+                Ok(plot) => println!("{plot}"),
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if let Some(expression) = input.strip_prefix("bits ") {
+            match calculator::bits(expression, &self.env) {
+                Ok(bits) => println!("{bits}"),
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if let Some(equation) = input.strip_prefix("solve ") {
+            match calculator::solve(equation, &self.env) {
+                Ok(solution) => println!("{solution}"),
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
+
+        if let Some((name, expression)) = input::assignment(input) {
+            match evaluate(expression, &self.env) {
+                Ok(value) => {
+                    if echo {
+                        println!("{name} = {}", self.show(&value));
+                    }
+                    self.env.set(name, value);
+                }
+                Err(e) => println!("Error: {e}"),
+            }
+            return true;
+        }
 
-    #[test]
-    fn test_power() {
-        assert_eq!(calculate(2.0, "^", 10.0), Some(1024.0));
+        let result = if self.trace {
+            self.traced(input)
+        } else {
+            evaluate(input, &self.env)
+        };
+        match result {
+            Ok(result) => {
+                let n = self.env.push_result(result);
+                if echo {
+                    println!("${n} = {}", self.show(&result));
+                }
+                self.env.set("ans", result);
+            }
+            Err(e) => {
+                println!("Error: {e}");
+                if let Some(suggestion) = calculator::suggest::suggest(input, &self.env) {
+                    println!("{suggestion}");
+                }
+            }
+        }
+        true
     }
+}
+
+/// `~/.config/calc`, or `$XDG_CONFIG_HOME/calc` when that's set.
+fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("calc"))
+}
 
-    #[test]
-    fn test_modulo() {
-        assert_eq!(calculate(15.0, "%", 4.0), Some(3.0));
+/// The contents of `name` in the config directory, if it's there. Any
+/// other error reading it is printed and treated as missing.
+fn read_config_file(name: &str) -> Option<(PathBuf, String)> {
+    let path = config_dir()?.join(name);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => Some((path, text)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => {
+            println!("Warning: could not read {}: {e}", path.display());
+            None
+        }
     }
+}
 
-    #[test]
-    fn test_unknown_operator() {
-        assert_eq!(calculate(1.0, "&", 2.0), None);
+impl Session {
+    /// Apply `config.toml`, then run `init.calc`. Problems are printed
+    /// and skipped rather than stopping the calculator from starting.
+    fn configure(&mut self) {
+        if let Some((path, text)) = read_config_file("config.toml") {
+            match calculator::config::parse(&text) {
+                Ok(config) => {
+                    if let Some(mode) = config.mode {
+                        self.env.set_mode(mode);
+                    }
+                    if config.rpn {
+                        self.rpn = Some(Rpn::new());
+                    }
+                    if let Some(places) = config.precision {
+                        self.rounding = Some((places, Rounding::default()));
+                    }
+                    if let Some(angle) = config.angle {
+                        self.env.set_angle(angle);
+                    }
+                }
+                Err(e) => println!("Warning: {}: {e}; using the defaults", path.display()),
+            }
+        }
+        if let Some((path, text)) = read_config_file("init.calc") {
+            for e in calculator::config::run_script(&text, &mut self.env) {
+                println!("Warning: {}: {e}", path.display());
+            }
+        }
+        if let Some((path, text)) = read_config_file(persist::FILE_NAME) {
+            match Store::parse(&text) {
+                Ok((store, skipped)) => {
+                    for e in skipped {
+                        println!("Warning: {}: {e}", path.display());
+                    }
+                    store.load_into(&mut self.env);
+                    self.persisted = store;
+                }
+                Err(e) => println!("Warning: {}: {e}", path.display()),
+            }
+        }
     }
+}
 
-    #[test]
-    fn test_parse_valid() {
-        let result = parse_expression("5 + 3");
-        assert!(result.is_some());
-        let (left, op, right) = result.unwrap();
-        assert_eq!(left, 5.0);
-        assert_eq!(op, "+");
-        assert_eq!(right, 3.0);
+/// Apply `change` to a copy of the `:persist`ed variables and write it to
+/// the config directory. `persisted` is only replaced once that succeeds.
+fn update_persisted(
+    persisted: &mut Store,
+    change: impl FnOnce(&mut Store) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut updated = persisted.clone();
+    change(&mut updated)?;
+    if updated == *persisted {
+        return Ok(());
     }
+    let dir = config_dir().ok_or("no home directory to keep persisted variables in")?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("could not create {}: {e}", dir.display()))?;
+    let path = dir.join(persist::FILE_NAME);
+    let json = updated.to_json()?;
+    std::fs::write(&path, json + "\n")
+        .map_err(|e| format!("could not write {}: {e}", path.display()))?;
+    *persisted = updated;
+    Ok(())
+}
 
-    #[test]
-    fn test_parse_invalid_number() {
-        assert!(parse_expression("abc + 3").is_none());
+/// `calculator --check file.calc`: exit non-zero if any assertion fails.
+fn run_check(path: &str) -> ! {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Error: could not read {path}: {e}");
+            std::process::exit(2);
+        }
+    };
+    let report = calculator::check::check(&text, &mut Environment::new());
+    println!("{report}");
+    std::process::exit(if report.is_success() { 0 } else { 1 });
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => {}
+        [flag, path] if flag == "--check" => run_check(path),
+        _ => {
+            eprintln!("Usage: calculator [--check <file.calc>]");
+            std::process::exit(2);
+        }
     }
 
-    #[test]
-    fn test_parse_wrong_parts() {
-        assert!(parse_expression("5 +").is_none());
+    println!("Simple Calculator — type an expression or 'quit' to exit");
+    println!("End a line with \\ or an operator to continue it; separate statements with ;");
+    println!("Use $1, $2, ... for earlier results; pipe into functions with 2 ^ 10 | sqrt");
+    println!("Comment lines with # like this; :note \"why\" labels the last result in :history");
+    println!("Define operators with operator <> (a, b) = sqrt(a^2 + b^2); :operators lists them");
+    println!("Watch expressions being worked out step by step with :trace on");
+    println!("Try :mode rpn for a stack calculator: 3 4 + 5 *");
+    println!("Share variables and operators with :export env.json and :import env.json");
+    println!("Keep variables for next time with :persist g; :persisted lists them");
+    println!("Store values with g = 9.81; :angle deg switches sin, cos and tan to degrees");
+    println!("Settings go in ~/.config/calc/config.toml, definitions in ~/.config/calc/init.calc");
+
+    let mut session = Session::default();
+    session.configure();
+    let mut buffer = LineBuffer::new();
+
+    'repl: loop {
+        let prompt = if buffer.is_pending() {
+            "... "
+        } else if session.tape.is_some() {
+            "tape> "
+        } else if session.rpn.is_some() {
+            "rpn> "
+        } else {
+            "> "
+        };
+        print!("{prompt}");
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) => break, // EOF
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error reading input: {e}");
+                break;
+            }
+        }
+
+        // Every RPN line ends in an operator, so none of them continue
+        let input = if session.rpn.is_some() {
+            line.trim().to_string()
+        } else {
+            let Some(input) = buffer.push(&line) else {
+                continue;
+            };
+            input
+        };
+        let statements = input::statements(&input);
+        for (i, statement) in statements.iter().enumerate() {
+            let echo = session.echo_all || i + 1 == statements.len();
+            if !session.statement(statement, echo) {
+                break 'repl;
+            }
+        }
     }
 }
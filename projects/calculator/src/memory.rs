@@ -0,0 +1,171 @@
+use crate::environment::Environment;
+use crate::parser::BinOp;
+use crate::persist::Store;
+use crate::value::Value;
+
+/// The register used when a command doesn't name one
+const DEFAULT_REGISTER: &str = "m";
+
+/// Classic calculator memory. Registers are ordinary variables (`m`, `m1`,
+/// `m2`, ...), so they can also be used in expressions like `m1 * 2`, and
+/// they are kept for the next session like `:persist`ed variables.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemoryCommand {
+    /// `m+`: add the last result to a register
+    Add(String),
+    /// `m-`: subtract the last result from a register
+    Subtract(String),
+    /// `mr`: recall a register as the new result
+    Recall(String),
+    /// `mc`: clear a register
+    Clear(String),
+}
+
+/// Recognize `m+`, `m-`, `mr`, and `mc`, each optionally followed by a
+/// register name: `m+ m2`. `None` if `input` isn't a memory command.
+pub fn parse_command(input: &str) -> Option<Result<MemoryCommand, String>> {
+    let mut words = input.split_whitespace();
+    let command = words.next()?;
+    let register = words.next().unwrap_or(DEFAULT_REGISTER).to_string();
+
+    let build: fn(String) -> MemoryCommand = match command {
+        "m+" => MemoryCommand::Add,
+        "m-" => MemoryCommand::Subtract,
+        "mr" => MemoryCommand::Recall,
+        "mc" => MemoryCommand::Clear,
+        _ => return None,
+    };
+
+    if words.next().is_some() || !is_register(&register) {
+        return Some(Err(format!(
+            "Usage: {command} [register], where registers are m, m1, m2, ..."
+        )));
+    }
+    Some(Ok(build(register)))
+}
+
+fn is_register(name: &str) -> bool {
+    name.strip_prefix('m')
+        .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Run a memory command against `env`, returning the line to print. Recall
+/// also sets `ans`, like pressing MR puts the memory on the display.
+pub fn apply(command: &MemoryCommand, env: &mut Environment) -> Result<String, String> {
+    match command {
+        MemoryCommand::Add(register) | MemoryCommand::Subtract(register) => {
+            let op = if matches!(command, MemoryCommand::Add(_)) {
+                BinOp::Add
+            } else {
+                BinOp::Sub
+            };
+            let last = env
+                .get("ans")
+                .ok_or("No result to store yet; calculate something first")?;
+            let current = env.get(register).unwrap_or(Value::Number(0.0));
+            let updated = current.apply(op, &last)?;
+            env.set(register, updated);
+            Ok(format!("{register} = {updated}"))
+        }
+        MemoryCommand::Recall(register) => {
+            let value = env.get(register).unwrap_or(Value::Number(0.0));
+            env.set("ans", value);
+            Ok(format!("= {value}"))
+        }
+        MemoryCommand::Clear(register) => {
+            env.remove(register);
+            Ok(format!("{register} cleared"))
+        }
+    }
+}
+
+/// Keep registers in `store` for the next session: a register `command`
+/// changed is persisted with its new value, a cleared one is dropped.
+pub fn remember(
+    command: &MemoryCommand,
+    env: &Environment,
+    store: &mut Store,
+) -> Result<(), String> {
+    match command {
+        MemoryCommand::Add(register) | MemoryCommand::Subtract(register) => {
+            store.persist(&[register], env)
+        }
+        MemoryCommand::Clear(register) if store.contains(register) => store.unpersist(&[register]),
+        MemoryCommand::Clear(_) | MemoryCommand::Recall(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str, env: &mut Environment) -> Result<String, String> {
+        apply(&parse_command(input).unwrap()?, env)
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("m+"),
+            Some(Ok(MemoryCommand::Add("m".to_string())))
+        );
+        assert_eq!(
+            parse_command("mr m2"),
+            Some(Ok(MemoryCommand::Recall("m2".to_string())))
+        );
+        assert!(parse_command("mc x").unwrap().is_err());
+        assert_eq!(parse_command("m + 1"), None);
+        assert_eq!(parse_command("max"), None);
+    }
+
+    #[test]
+    fn test_accumulate_and_recall() {
+        let mut env = Environment::new();
+        env.set("ans", Value::Number(10.0));
+        run("m+", &mut env).unwrap();
+        run("m+", &mut env).unwrap();
+        env.set("ans", Value::Number(5.0));
+        assert_eq!(run("m-", &mut env), Ok("m = 15".to_string()));
+
+        env.set("ans", Value::Number(0.0));
+        assert_eq!(run("mr", &mut env), Ok("= 15".to_string()));
+        assert_eq!(env.get("ans"), Some(Value::Number(15.0)));
+    }
+
+    #[test]
+    fn test_named_registers_are_separate() {
+        let mut env = Environment::new();
+        env.set("ans", Value::Number(2.0));
+        run("m+ m1", &mut env).unwrap();
+        assert_eq!(env.get("m1"), Some(Value::Number(2.0)));
+        assert_eq!(env.get("m"), None);
+
+        run("mc m1", &mut env).unwrap();
+        assert_eq!(env.get("m1"), None);
+        assert_eq!(run("mr m1", &mut env), Ok("= 0".to_string()));
+    }
+
+    #[test]
+    fn test_registers_carry_over_to_the_next_session() {
+        let mut env = Environment::new();
+        let mut store = Store::new();
+        for input in ["m+", "m+ m2", "m+ m3", "mc m3", "mr"] {
+            env.set("ans", Value::Number(4.0));
+            let command = parse_command(input).unwrap().unwrap();
+            apply(&command, &mut env).unwrap();
+            remember(&command, &env, &mut store).unwrap();
+        }
+
+        let (saved, _) = Store::parse(&store.to_json().unwrap()).unwrap();
+        let mut next = Environment::new();
+        saved.load_into(&mut next);
+        assert_eq!(next.get("m"), Some(Value::Number(4.0)));
+        assert_eq!(next.get("m2"), Some(Value::Number(4.0)));
+        assert_eq!(next.get("m3"), None);
+    }
+
+    #[test]
+    fn test_store_needs_a_result() {
+        assert!(run("m+", &mut Environment::new()).is_err());
+    }
+}
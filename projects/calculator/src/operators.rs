@@ -0,0 +1,200 @@
+//! User-defined infix operators:
+//!
+//! ```text
+//! operator <> (a, b) = sqrt(a^2 + b^2)
+//! operator ~> precedence 0 (a, b) = a * 10 + b
+//! ```
+//!
+//! Once defined, `3 <> 4` works like any built-in operator.
+
+use crate::environment::Environment;
+use crate::eval::eval;
+use crate::lexer::{self, Token};
+use crate::parser::{self, Expr};
+use crate::value::Value;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Characters custom operators are made of. None of them mean anything to
+/// the calculator on their own, so `<>` or `?!` can't clash with built-ins.
+/// `#` isn't one: it starts a comment.
+pub const OPERATOR_CHARS: &str = "<>!~&@?:";
+
+/// Binds like `*` and `/` unless the definition says otherwise.
+pub const DEFAULT_PRECEDENCE: u8 = 2;
+
+const USAGE: &str = "Usage: operator <symbol> [precedence 0-3] (a, b) = <expression>, \
+                     e.g. operator <> (a, b) = sqrt(a^2 + b^2)";
+
+const PRECEDENCE_HELP: &str = "Precedence is 0 (looser than + and -), 1 (with + and -), \
+                               2 (with * and /) or 3 (with ^)";
+
+/// An infix operator the user defined. All custom operators are
+/// left-associative: `a <> b <> c` is `(a <> b) <> c`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operator {
+    pub symbol: String,
+    /// 0 to 3, loosest first; see `PRECEDENCE_HELP`
+    pub precedence: u8,
+    /// Names of the left and right operands inside `body`
+    pub params: [String; 2],
+    pub body: Expr,
+    /// The body as typed, for listing definitions
+    pub source: String,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "operator {} precedence {} ({}, {}) = {}",
+            self.symbol, self.precedence, self.params[0], self.params[1], self.source
+        )
+    }
+}
+
+impl Operator {
+    /// Apply the operator: evaluate the body with the operands bound to its
+    /// parameters, on top of everything else in `env`.
+    pub fn apply(&self, left: Value, right: Value, env: &Environment) -> Result<Value, String> {
+        let mut scope = env.clone();
+        scope.set(&self.params[0], left);
+        scope.set(&self.params[1], right);
+        eval(&self.body, &scope).map_err(|e| format!("In {}: {e}", self.symbol))
+    }
+}
+
+/// Recognize a definition like `operator <> (a, b) = sqrt(a^2 + b^2)`.
+/// `None` if `input` isn't one. The body is parsed right away, so it can
+/// use operators defined before it but never itself.
+pub fn parse_definition(input: &str, env: &Environment) -> Option<Result<Operator, String>> {
+    // All that's left of `operator # (a, b) = ...` once the comment is gone
+    if input == "operator" {
+        return Some(Err(symbol_help()));
+    }
+    let rest = input.strip_prefix("operator ")?;
+    Some(definition(rest, env))
+}
+
+fn symbol_help() -> String {
+    format!("Operators are made of the characters {OPERATOR_CHARS} (# starts a comment)\n{USAGE}")
+}
+
+fn definition(input: &str, env: &Environment) -> Result<Operator, String> {
+    let (head, source) = input.split_once('=').ok_or(USAGE)?;
+    if head.contains('#') {
+        return Err(symbol_help());
+    }
+    let tokens = lexer::tokenize(head)?;
+
+    let (symbol, rest) = match tokens.split_first() {
+        Some((Token::Operator(symbol), rest)) => (symbol.clone(), rest),
+        _ => return Err(symbol_help()),
+    };
+
+    let (precedence, rest) = match rest {
+        [Token::Ident(word), Token::Number(n), rest @ ..] if word == "precedence" => {
+            if n.fract() != 0.0 || !(0.0..=3.0).contains(n) {
+                return Err(PRECEDENCE_HELP.to_string());
+            }
+            (*n as u8, rest)
+        }
+        [Token::Ident(word), ..] if word == "precedence" => return Err(PRECEDENCE_HELP.to_string()),
+        _ => (DEFAULT_PRECEDENCE, rest),
+    };
+
+    let params = match rest {
+        [Token::LParen, Token::Ident(a), Token::Comma, Token::Ident(b), Token::RParen] => {
+            [a.clone(), b.clone()]
+        }
+        _ => return Err(USAGE.to_string()),
+    };
+    if params[0] == params[1] {
+        return Err(format!(
+            "The two operands need different names, not {} and {}",
+            params[0], params[1]
+        ));
+    }
+
+    let source = source.trim().to_string();
+    let body = parser::parse_in(&lexer::tokenize(&source)?, env)?;
+
+    Ok(Operator {
+        symbol,
+        precedence,
+        params,
+        body,
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn define(input: &str, env: &mut Environment) -> Result<(), String> {
+        let operator = parse_definition(input, env).expect("a definition")?;
+        env.define_operator(operator);
+        Ok(())
+    }
+
+    fn eval_in(input: &str, env: &Environment) -> Result<Value, String> {
+        eval(&crate::parse_in(input, env)?, env)
+    }
+
+    #[test]
+    fn test_define_and_use() {
+        let mut env = Environment::new();
+        define("operator <> (a, b) = sqrt(a^2 + b^2)", &mut env).unwrap();
+        assert_eq!(eval_in("3 <> 4", &env), Ok(Value::Number(5.0)));
+        // Binds like *, so this is 1 + (3 <> 4)
+        assert_eq!(eval_in("1 + 3 <> 4", &env), Ok(Value::Number(6.0)));
+        assert_eq!(
+            env.operator("<>").unwrap().to_string(),
+            "operator <> precedence 2 (a, b) = sqrt(a^2 + b^2)"
+        );
+        assert!(parse_definition("1 + 2", &env).is_none());
+    }
+
+    #[test]
+    fn test_precedence_levels() {
+        let mut env = Environment::new();
+        define("operator ~ precedence 0 (a, b) = a * 10 + b", &mut env).unwrap();
+        define("operator @ precedence 3 (x, y) = x - y", &mut env).unwrap();
+        // (1 + 2) ~ (3 * 4)
+        assert_eq!(eval_in("1 + 2 ~ 3 * 4", &env), Ok(Value::Number(42.0)));
+        // 2 * (5 @ 3), and left-associative: (10 @ 2) @ 3
+        assert_eq!(eval_in("2 * 5 @ 3", &env), Ok(Value::Number(4.0)));
+        assert_eq!(eval_in("10 @ 2 @ 3", &env), Ok(Value::Number(5.0)));
+        assert!(define("operator ?? precedence 4 (a, b) = a", &mut env).is_err());
+    }
+
+    #[test]
+    fn test_operands_shadow_variables() {
+        let mut env = Environment::new();
+        env.set("a", Value::Number(100.0));
+        env.set("k", Value::Number(2.0));
+        define("operator && (a, b) = k * a + b", &mut env).unwrap();
+        assert_eq!(eval_in("1 && 1", &env), Ok(Value::Number(3.0)));
+        assert_eq!(env.get("a"), Some(Value::Number(100.0)));
+    }
+
+    #[test]
+    fn test_bad_definitions() {
+        let mut env = Environment::new();
+        assert!(define("operator + (a, b) = a", &mut env).is_err());
+        assert!(define("operator <> (a) = a", &mut env).is_err());
+        assert!(define("operator <> (a, a) = a", &mut env).is_err());
+        assert!(define("operator <> (a, b)", &mut env).is_err());
+        // What's left of `operator # (a, b) = a * b` after the comment
+        let err = define("operator", &mut env).unwrap_err();
+        assert!(err.contains("# starts a comment"), "{err}");
+        assert!(define("operator ## (a, b) = a * b", &mut env)
+            .unwrap_err()
+            .contains("# starts a comment"));
+        // Not defined yet, so it can't refer to itself
+        let err = define("operator <> (a, b) = a <> b", &mut env).unwrap_err();
+        assert!(err.contains("Unknown operator"), "{err}");
+        assert!(eval_in("1 <> 2", &env).is_err());
+    }
+}
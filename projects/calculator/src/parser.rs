@@ -0,0 +1,470 @@
+use crate::environment::Environment;
+use crate::lexer::Token;
+use crate::operators::Operator;
+use crate::value::Value;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Mod,
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Pow => "^",
+            BinOp::Mod => "%",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+/// The parsed expression tree. It serializes as nested single-key objects
+/// like `{"binary": ["add", {"variable": "a"}, {"number": 1.0}]}`, which is
+/// how `:export` writes operator bodies.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Expr {
+    Number(f64),
+    /// A date, time of day, or duration literal
+    Temporal(Value),
+    Variable(String),
+    /// `[low, high]`
+    Interval(Box<Expr>, Box<Expr>),
+    /// `value ± error`
+    Uncertain(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    /// A built-in function applied to its arguments, like `sin(x)` or
+    /// `nCr(10, 3)`
+    Call(String, Vec<Expr>),
+    /// `$n`, an earlier result of the session
+    Result(usize),
+    /// A user-defined operator, carrying its definition as it was when
+    /// parsed so redefining it later can't make it call itself
+    Custom(Rc<Operator>, Box<Expr>, Box<Expr>),
+}
+
+/// Recursive-descent parser. Grammar, loosest binding first:
+///
+/// ```text
+/// pipeline := expr ('|' IDENT)*
+/// expr    := loose ('±' loose)?
+/// loose   := sum (OP0 sum)*
+/// sum     := term (('+' | '-' | OP1) term)*
+/// term    := unary (('*' | '/' | '%' | OP2) unary)*
+/// unary   := '-' unary | tight
+/// tight   := power (OP3 power)*
+/// power   := primary ('^' unary)?
+/// primary := NUMBER | DATE | TIME | DURATION | '$' NUMBER | IDENT
+///          | IDENT '(' expr (',' expr)* ')'
+///          | '(' pipeline ')' | '[' expr ',' expr ']'
+/// ```
+///
+/// Each pipeline stage is a function applied to the result so far, so
+/// `2 ^ 10 | sqrt | round` is `round(sqrt(2 ^ 10))`.
+///
+/// A number directly followed by a name or parenthesis multiplies it, so
+/// `2x` is `2 * x` and `3(x + 1)` is `3 * (x + 1)`.
+///
+/// `OP0` to `OP3` are operators defined with `operator`, by precedence.
+pub fn parse(tokens: &[Token]) -> Result<Expr, String> {
+    parse_in(tokens, &Environment::new())
+}
+
+/// Parse with the custom operators defined in `env`.
+pub fn parse_in(tokens: &[Token], env: &Environment) -> Result<Expr, String> {
+    if tokens.is_empty() {
+        return Err("Empty expression".to_string());
+    }
+    for token in tokens {
+        if let Token::Operator(symbol) = token {
+            if env.operator(symbol).is_none() {
+                return Err(format!(
+                    "Unknown operator: {symbol}\nDefine it first, e.g. operator {symbol} (a, b) = a + b"
+                ));
+            }
+        }
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        env,
+    };
+    let expr = parser.pipeline()?;
+
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(format!("Unexpected {}", describe(token))),
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    env: &'a Environment,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    /// The custom operator next up, if it binds at `precedence`.
+    fn custom(&self, precedence: u8) -> Option<Rc<Operator>> {
+        match self.peek() {
+            Some(Token::Operator(symbol)) => self
+                .env
+                .operator(symbol)
+                .filter(|op| op.precedence == precedence),
+            _ => None,
+        }
+    }
+
+    /// `left (OP next)*` for the custom operators at `precedence`.
+    fn custom_chain(
+        &mut self,
+        precedence: u8,
+        mut left: Expr,
+        next: fn(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        while let Some(op) = self.custom(precedence) {
+            self.pos += 1;
+            let right = next(self)?;
+            left = Expr::Custom(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if *token == expected => Ok(()),
+            Some(token) => Err(format!(
+                "Expected {} but found {}",
+                describe(&expected),
+                describe(token)
+            )),
+            None => Err(format!("Expected {}", describe(&expected))),
+        }
+    }
+
+    fn pipeline(&mut self) -> Result<Expr, String> {
+        let mut value = self.expr()?;
+
+        while self.peek() == Some(&Token::Pipe) {
+            self.pos += 1;
+            match self.next().cloned() {
+                Some(Token::Ident(name)) => value = Expr::Call(name, vec![value]),
+                Some(token) => {
+                    return Err(format!(
+                        "Expected a function name after '|' but found {}",
+                        describe(&token)
+                    ))
+                }
+                None => return Err("Expected a function name after '|'".to_string()),
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let value = self.loose()?;
+
+        if self.peek() == Some(&Token::PlusMinus) {
+            self.pos += 1;
+            let error = self.loose()?;
+            return Ok(Expr::Uncertain(Box::new(value), Box::new(error)));
+        }
+
+        Ok(value)
+    }
+
+    fn loose(&mut self) -> Result<Expr, String> {
+        let left = self.sum()?;
+        self.custom_chain(0, left, Self::sum)
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+
+        loop {
+            left = self.custom_chain(1, left, Self::term)?;
+            let op = match self.peek() {
+                Some(Token::Plus) => BinOp::Add,
+                Some(Token::Minus) => BinOp::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.term()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+
+        loop {
+            left = self.custom_chain(2, left, Self::unary)?;
+            let op = match self.peek() {
+                Some(Token::Star) => BinOp::Mul,
+                Some(Token::Slash) => BinOp::Div,
+                Some(Token::Percent) => BinOp::Mod,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.unary()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Minus) {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.tight()
+    }
+
+    fn tight(&mut self) -> Result<Expr, String> {
+        let left = self.power()?;
+        self.custom_chain(3, left, Self::power)
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.primary()?;
+
+        if matches!(base, Expr::Number(_))
+            && matches!(self.peek(), Some(Token::Ident(_)) | Some(Token::LParen))
+        {
+            // Implicit multiplication: 2x^2 is 2 * x^2
+            let rest = self.power()?;
+            return Ok(Expr::Binary(BinOp::Mul, Box::new(base), Box::new(rest)));
+        }
+
+        if self.peek() == Some(&Token::Caret) {
+            self.pos += 1;
+            // Right-associative: 2^3^2 = 2^(3^2)
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(BinOp::Pow, Box::new(base), Box::new(exponent)));
+        }
+
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next().cloned() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Temporal(value)) => Ok(Expr::Temporal(value)),
+            Some(Token::ResultRef(n)) => Ok(Expr::Result(n)),
+            Some(Token::LParen) => {
+                let inner = self.pipeline()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::LBracket) => {
+                let low = self.expr()?;
+                self.expect(Token::Comma)?;
+                let high = self.expr()?;
+                self.expect(Token::RBracket)?;
+                Ok(Expr::Interval(Box::new(low), Box::new(high)))
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let mut args = vec![self.expr()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    args.push(self.expr()?);
+                }
+                self.expect(Token::RParen)?;
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Ident(name)) => Ok(Expr::Variable(name)),
+            Some(token) => Err(format!("Unexpected {}", describe(&token))),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(n) => format!("number {n}"),
+        Token::Temporal(value) => format!("{} {value}", value.kind()),
+        Token::Ident(name) => format!("'{name}'"),
+        Token::Plus => "'+'".to_string(),
+        Token::Minus => "'-'".to_string(),
+        Token::Star => "'*'".to_string(),
+        Token::Slash => "'/'".to_string(),
+        Token::Caret => "'^'".to_string(),
+        Token::Percent => "'%'".to_string(),
+        Token::PlusMinus => "'±'".to_string(),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::LBracket => "'['".to_string(),
+        Token::RBracket => "']'".to_string(),
+        Token::Comma => "','".to_string(),
+        Token::Equals => "'='".to_string(),
+        Token::ResultRef(n) => format!("${n}"),
+        Token::Pipe => "'|'".to_string(),
+        Token::Operator(symbol) => format!("'{symbol}'"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+
+    fn parse_str(input: &str) -> Result<Expr, String> {
+        parse(&tokenize(input)?)
+    }
+
+    fn num(n: f64) -> Box<Expr> {
+        Box::new(Expr::Number(n))
+    }
+
+    #[test]
+    fn test_parse_valid() {
+        assert_eq!(
+            parse_str("5 + 3"),
+            Ok(Expr::Binary(BinOp::Add, num(5.0), num(3.0)))
+        );
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(
+            parse_str("1 + 2 * 3"),
+            Ok(Expr::Binary(
+                BinOp::Add,
+                num(1.0),
+                Box::new(Expr::Binary(BinOp::Mul, num(2.0), num(3.0)))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_power_binds_tighter_than_negation() {
+        assert_eq!(
+            parse_str("-2^2"),
+            Ok(Expr::Neg(Box::new(Expr::Binary(
+                BinOp::Pow,
+                num(2.0),
+                num(2.0)
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_parse_uncertain_and_interval() {
+        assert_eq!(
+            parse_str("10 ± 0.5"),
+            Ok(Expr::Uncertain(num(10.0), num(0.5)))
+        );
+        assert_eq!(
+            parse_str("[9.5, 10.5]"),
+            Ok(Expr::Interval(num(9.5), num(10.5)))
+        );
+    }
+
+    #[test]
+    fn test_parse_variable() {
+        assert_eq!(
+            parse_str("abc + 3"),
+            Ok(Expr::Binary(
+                BinOp::Add,
+                Box::new(Expr::Variable("abc".to_string())),
+                num(3.0)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_function_call() {
+        assert_eq!(
+            parse_str("sin(x + 1)"),
+            Ok(Expr::Call(
+                "sin".to_string(),
+                vec![Expr::Binary(
+                    BinOp::Add,
+                    Box::new(Expr::Variable("x".to_string())),
+                    num(1.0)
+                )]
+            ))
+        );
+        assert_eq!(
+            parse_str("nCr(10, 3)"),
+            Ok(Expr::Call(
+                "nCr".to_string(),
+                vec![Expr::Number(10.0), Expr::Number(3.0)]
+            ))
+        );
+        assert!(parse_str("sin(1").is_err());
+        assert!(parse_str("nCr(10,)").is_err());
+    }
+
+    #[test]
+    fn test_implicit_multiplication() {
+        let x = || Box::new(Expr::Variable("x".to_string()));
+        assert_eq!(
+            parse_str("2x^2"),
+            Ok(Expr::Binary(
+                BinOp::Mul,
+                num(2.0),
+                Box::new(Expr::Binary(BinOp::Pow, x(), num(2.0)))
+            ))
+        );
+        assert_eq!(
+            parse_str("3(x)"),
+            Ok(Expr::Binary(BinOp::Mul, num(3.0), x()))
+        );
+    }
+
+    #[test]
+    fn test_parse_pipeline() {
+        let call = |name: &str, arg: Expr| Expr::Call(name.to_string(), vec![arg]);
+        assert_eq!(
+            parse_str("2 ^ 10 | sqrt | round"),
+            Ok(call(
+                "round",
+                call("sqrt", Expr::Binary(BinOp::Pow, num(2.0), num(10.0)))
+            ))
+        );
+        assert_eq!(
+            parse_str("($1 | abs) + 1"),
+            Ok(Expr::Binary(
+                BinOp::Add,
+                Box::new(call("abs", Expr::Result(1))),
+                num(1.0)
+            ))
+        );
+        assert!(parse_str("4 | 2").unwrap_err().contains("function name"));
+        assert!(parse_str("4 |").is_err());
+    }
+
+    #[test]
+    fn test_parse_wrong_parts() {
+        assert!(parse_str("5 +").is_err());
+        assert!(parse_str("(1 + 2").is_err());
+        assert!(parse_str("1 2").is_err());
+    }
+}
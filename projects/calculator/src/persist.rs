@@ -0,0 +1,223 @@
+//! `:persist g` keeps chosen variables in `~/.config/calc/persisted.json`
+//! and sets them again in every new session. Unlike `:export`, which saves
+//! everything on request, only the named variables are kept, each with the
+//! value it had when it was persisted.
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "variables": {
+//!     "g": {"type": "number", "value": 9.81},
+//!     "vat": {"type": "decimal", "value": "0.2"}
+//!   }
+//! }
+//! ```
+//!
+//! Values are written the way `:export` writes variables.
+
+use crate::environment::Environment;
+use crate::export;
+use crate::value::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The file in the config directory the store is kept in
+pub const FILE_NAME: &str = "persisted.json";
+
+/// Bumped when the format changes in a way older versions can't read
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct StoreFile {
+    version: u32,
+    #[serde(default)]
+    variables: BTreeMap<String, serde_json::Value>,
+}
+
+/// The persisted variables, by name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Store {
+    variables: BTreeMap<String, Value>,
+}
+
+impl Store {
+    pub fn new() -> Store {
+        Store::default()
+    }
+
+    /// Read a store written by `to_json`. A variable that can't be read is
+    /// left out, with the reason in the list returned, so one bad entry
+    /// doesn't lose the rest.
+    pub fn parse(json: &str) -> Result<(Store, Vec<String>), String> {
+        let file: StoreFile =
+            serde_json::from_str(json).map_err(|e| format!("Invalid file: {e}"))?;
+        if file.version > VERSION {
+            return Err(format!(
+                "The file is version {}, newer than this calculator reads ({VERSION})",
+                file.version
+            ));
+        }
+        let mut variables = BTreeMap::new();
+        let mut skipped = Vec::new();
+        for (name, json) in file.variables {
+            match export::variable(&name, json) {
+                Ok(value) => {
+                    variables.insert(name, value);
+                }
+                Err(e) => skipped.push(format!("{e}; skipped")),
+            }
+        }
+        Ok((Store { variables }, skipped))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        let variables = self
+            .variables
+            .iter()
+            .map(|(name, value)| Ok((name.clone(), serde_json::to_value(value)?)))
+            .collect::<Result<_, serde_json::Error>>()
+            .map_err(|e| e.to_string())?;
+        let file = StoreFile {
+            version: VERSION,
+            variables,
+        };
+        serde_json::to_string_pretty(&file).map_err(|e| e.to_string())
+    }
+
+    /// Keep the current values of `names` from `env`, replacing any kept
+    /// before. Nothing changes unless every name is a stored variable.
+    pub fn persist(&mut self, names: &[&str], env: &Environment) -> Result<(), String> {
+        let variables = env.variables();
+        let mut kept = Vec::new();
+        for name in names {
+            match variables.iter().find(|(n, _)| n == name) {
+                Some((_, value)) => kept.push((name.to_string(), *value)),
+                None if env.get(name).is_some() => {
+                    return Err(format!("'{name}' is built in; there's nothing to persist"))
+                }
+                None => return Err(format!("Unknown variable: {name}")),
+            }
+        }
+        self.variables.extend(kept);
+        Ok(())
+    }
+
+    /// Stop keeping `names`. Nothing changes unless every one was persisted.
+    pub fn unpersist(&mut self, names: &[&str]) -> Result<(), String> {
+        if let Some(name) = names.iter().find(|n| !self.variables.contains_key(**n)) {
+            return Err(format!("'{name}' isn't persisted"));
+        }
+        for name in names {
+            self.variables.remove(*name);
+        }
+        Ok(())
+    }
+
+    /// Persisted variables and their values, alphabetically.
+    pub fn variables(&self) -> impl Iterator<Item = (&str, Value)> {
+        self.variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.variables.contains_key(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// Set every persisted variable in `env`.
+    pub fn load_into(&self, env: &mut Environment) {
+        for (name, value) in self.variables() {
+            env.set(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::Decimal;
+
+    #[test]
+    fn test_round_trip() {
+        let mut env = Environment::new();
+        env.set("g", Value::Number(9.81));
+        env.set("vat", Value::Decimal("0.2".parse::<Decimal>().unwrap()));
+        env.set("scratch", Value::Number(1.0));
+
+        let mut store = Store::new();
+        store.persist(&["g", "vat"], &env).unwrap();
+        let json = store.to_json().unwrap();
+        assert!(json.contains(r#""value": 9.81"#), "{json}");
+        assert!(!json.contains("scratch"), "{json}");
+
+        let (loaded, skipped) = Store::parse(&json).unwrap();
+        assert_eq!(loaded, store);
+        assert!(skipped.is_empty());
+        let mut next = Environment::new();
+        loaded.load_into(&mut next);
+        assert_eq!(next.get("g"), Some(Value::Number(9.81)));
+        assert_eq!(next.get("scratch"), None);
+        assert!(Store::parse(r#"{"version": 2}"#).is_err());
+    }
+
+    #[test]
+    fn test_non_finite_and_bad_entries() {
+        let mut env = Environment::new();
+        env.set("g", Value::Number(9.81));
+        env.set("big", Value::Number(f64::INFINITY));
+        let mut store = Store::new();
+        store.persist(&["g", "big"], &env).unwrap();
+        let (loaded, skipped) = Store::parse(&store.to_json().unwrap()).unwrap();
+        assert_eq!(loaded, store);
+        assert!(skipped.is_empty());
+
+        // As written before infinity had a spelling: only that one is lost
+        let old = r#"{"version": 1, "variables": {
+            "big": {"type": "number", "value": null},
+            "g": {"type": "number", "value": 9.81}
+        }}"#;
+        let (loaded, skipped) = Store::parse(old).unwrap();
+        assert_eq!(
+            loaded.variables().collect::<Vec<_>>(),
+            [("g", Value::Number(9.81))]
+        );
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].starts_with("Variable 'big'"), "{skipped:?}");
+    }
+
+    #[test]
+    fn test_persist_and_unpersist() {
+        let mut env = Environment::new();
+        env.set("g", Value::Number(9.81));
+        let mut store = Store::new();
+
+        assert_eq!(
+            store.persist(&["g", "h"], &env),
+            Err("Unknown variable: h".to_string())
+        );
+        assert!(store
+            .persist(&["pi"], &env)
+            .unwrap_err()
+            .contains("built in"));
+        assert!(store.is_empty());
+
+        store.persist(&["g"], &env).unwrap();
+        env.set("g", Value::Number(9.8));
+        store.persist(&["g"], &env).unwrap();
+        assert_eq!(
+            store.variables().collect::<Vec<_>>(),
+            [("g", Value::Number(9.8))]
+        );
+
+        assert_eq!(
+            store.unpersist(&["g", "x"]),
+            Err("'x' isn't persisted".to_string())
+        );
+        store.unpersist(&["g"]).unwrap();
+        assert!(store.is_empty());
+    }
+}
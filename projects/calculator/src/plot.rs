@@ -0,0 +1,218 @@
+use crate::environment::Environment;
+use crate::eval::eval;
+use crate::parser::Expr;
+use crate::solver::collect_variables;
+use crate::value::{format_number, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+
+const WIDTH: usize = 60;
+const HEIGHT: usize = 15;
+
+const USAGE: &str =
+    "Usage: plot <expression> from <start> to <end>, e.g. plot sin(x) from -pi to pi";
+
+/// An ASCII chart of a function, ready to print.
+#[derive(Debug, PartialEq)]
+pub struct Plot {
+    lines: Vec<String>,
+}
+
+impl fmt::Display for Plot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.lines.join("\n"))
+    }
+}
+
+/// Plot a function of one variable, like `sin(x) from -3.14 to 3.14`. The
+/// bounds can be expressions too (`-pi to pi`). Points where the function is
+/// undefined, such as `sqrt(x)` for negative `x`, are left blank.
+pub fn plot(input: &str, env: &Environment) -> Result<Plot, String> {
+    let (function, range) = input.split_once(" from ").ok_or(USAGE)?;
+    let (from, to) = range.rsplit_once(" to ").ok_or(USAGE)?;
+
+    let expr = crate::parse_in(function, env)?;
+    let from = eval(&crate::parse_in(from, env)?, env)?.as_number()?;
+    let to = eval(&crate::parse_in(to, env)?, env)?.as_number()?;
+    if from >= to {
+        return Err(format!(
+            "The start of the range must be below the end ({} to {})",
+            format_number(from),
+            format_number(to)
+        ));
+    }
+
+    let var = plotted_variable(&expr, env)?;
+    let samples = sample(&expr, &var, from, to, WIDTH, env);
+    render(&samples, from, to, HEIGHT).ok_or(format!(
+        "{} isn't defined anywhere from {} to {}",
+        function.trim(),
+        format_number(from),
+        format_number(to)
+    ))
+}
+
+/// The one name in `expr` that isn't already defined. A constant function
+/// is plotted against `x`.
+fn plotted_variable(expr: &Expr, env: &Environment) -> Result<String, String> {
+    let mut names = BTreeSet::new();
+    collect_variables(expr, &mut names);
+    let unknown: Vec<String> = names.into_iter().filter(|n| env.get(n).is_none()).collect();
+
+    match unknown.as_slice() {
+        [] => Ok("x".to_string()),
+        [var] => Ok(var.clone()),
+        _ => Err(format!(
+            "Can only plot one variable, found: {}",
+            unknown.join(", ")
+        )),
+    }
+}
+
+/// Evaluate `expr` at `count` evenly spaced points from `from` to `to`.
+/// Errors and non-finite results become `None`.
+fn sample(
+    expr: &Expr,
+    var: &str,
+    from: f64,
+    to: f64,
+    count: usize,
+    env: &Environment,
+) -> Vec<Option<f64>> {
+    let mut env = env.clone();
+    (0..count)
+        .map(|i| {
+            let x = from + (to - from) * i as f64 / (count - 1) as f64;
+            env.set(var, Value::Number(x));
+            eval(expr, &env)
+                .and_then(|v| v.as_number())
+                .ok()
+                .filter(|y| y.is_finite())
+        })
+        .collect()
+}
+
+/// Lay the samples out one per column, with the y range on the left and the
+/// x range underneath. Axes are drawn where zero falls inside the range.
+/// `None` if there's nothing to draw.
+fn render(samples: &[Option<f64>], from: f64, to: f64, height: usize) -> Option<Plot> {
+    let values = samples.iter().flatten();
+    let mut low = values.clone().copied().fold(f64::INFINITY, f64::min);
+    let mut high = values.copied().fold(f64::NEG_INFINITY, f64::max);
+    if !low.is_finite() {
+        return None;
+    }
+    if low == high {
+        low -= 1.0;
+        high += 1.0;
+    }
+
+    let width = samples.len();
+    let row_of = |y: f64| ((high - y) / (high - low) * (height - 1) as f64).round() as usize;
+    let mut grid = vec![vec![' '; width]; height];
+
+    let zero_row = (low <= 0.0 && high >= 0.0).then(|| row_of(0.0));
+    let zero_col = (from <= 0.0 && to >= 0.0)
+        .then(|| ((0.0 - from) / (to - from) * (width - 1) as f64).round() as usize);
+    if let Some(row) = zero_row {
+        grid[row].fill('─');
+    }
+    if let Some(col) = zero_col {
+        for (r, line) in grid.iter_mut().enumerate() {
+            line[col] = if Some(r) == zero_row { '┼' } else { '│' };
+        }
+    }
+
+    for (col, y) in samples.iter().enumerate() {
+        if let Some(y) = y {
+            grid[row_of(*y)][col] = '•';
+        }
+    }
+
+    let mut labels = vec![String::new(); height];
+    labels[0] = short(high);
+    labels[height - 1] = short(low);
+    if let Some(row) = zero_row {
+        labels[row] = "0".to_string();
+    }
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+    let mut lines: Vec<String> = labels
+        .iter()
+        .zip(&grid)
+        .map(|(label, row)| {
+            let row: String = row.iter().collect();
+            format!("{label:>label_width$} ┤{row}")
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+
+    let (from, to) = (short(from), short(to));
+    let gap = width
+        .saturating_sub(from.chars().count() + to.chars().count())
+        .max(1);
+    lines.push(format!(
+        "{:label_width$}  {from}{}{to}",
+        "",
+        " ".repeat(gap)
+    ));
+
+    Some(Plot { lines })
+}
+
+/// Axis labels only need a couple of decimal places.
+fn short(n: f64) -> String {
+    format_number((n * 100.0).round() / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plot_line_with_axes() {
+        let samples: Vec<Option<f64>> = (-2..=2).map(|x| Some(x as f64)).collect();
+        let plot = render(&samples, -2.0, 2.0, 5).unwrap();
+        assert_eq!(
+            plot.to_string(),
+            [
+                " 2 ┤  │ •",
+                "   ┤  │•",
+                " 0 ┤──•──",
+                "   ┤ •│",
+                "-2 ┤• │",
+                "    -2  2",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_undefined_points_are_blank() {
+        let env = Environment::new();
+        let expr = crate::parse("sqrt(x)").unwrap();
+        let samples = sample(&expr, "x", -1.0, 1.0, 5, &env);
+        assert_eq!(samples[..2], [None, None]);
+        assert_eq!(samples[4], Some(1.0));
+    }
+
+    #[test]
+    fn test_plot_command() {
+        let env = Environment::new();
+        let plot = plot("sin(x) from -pi to pi", &env).unwrap().to_string();
+        assert_eq!(plot.lines().count(), HEIGHT + 1);
+        assert!(plot.starts_with(" 1 ┤"));
+        let x_axis = plot.lines().last().unwrap().trim();
+        assert!(x_axis.starts_with("-3.14") && x_axis.ends_with(" 3.14"));
+    }
+
+    #[test]
+    fn test_plot_errors() {
+        let env = Environment::new();
+        assert!(plot("sin(x)", &env).is_err());
+        assert!(plot("x from 2 to 1", &env).is_err());
+        assert!(plot("x * y from 0 to 1", &env).is_err());
+        assert!(plot("sqrt(x) from -2 to -1", &env).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+//! Combinatorics and distributions: `nCr(10, 3)`, `factorial(20)`,
+//! `binom_pmf(10, 3, 0.5)` and friends.
+
+use std::f64::consts::{PI, SQRT_2};
+
+/// Each function and how many arguments it takes.
+pub const FUNCTIONS: &[(&str, usize)] = &[
+    ("factorial", 1),
+    ("nCr", 2),
+    ("nPr", 2),
+    ("binom_pmf", 3),
+    ("binom_cdf", 3),
+    ("poisson_pmf", 2),
+    ("normal_pdf", 3),
+    ("normal_cdf", 3),
+];
+
+/// The number of arguments `name` takes, if it's one of these functions.
+pub fn arity(name: &str) -> Option<usize> {
+    FUNCTIONS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, arity)| *arity)
+}
+
+/// A count like n or k: a whole number from 0 up.
+fn count(name: &str, x: f64) -> Result<u64, String> {
+    if x < 0.0 || x.fract() != 0.0 || x >= u64::MAX as f64 {
+        return Err(format!("{name} needs whole numbers from 0 up, got {x}"));
+    }
+    Ok(x as u64)
+}
+
+fn probability(name: &str, p: f64) -> Result<f64, String> {
+    if !(0.0..=1.0).contains(&p) {
+        return Err(format!("{name} needs a probability from 0 to 1, got {p}"));
+    }
+    Ok(p)
+}
+
+/// Exact results stop where they'd no longer fit in an i64, the same limit
+/// as whole numbers elsewhere in the calculator.
+fn exact(name: &str, args: &str, n: Option<u128>) -> Result<f64, String> {
+    match n.filter(|&n| n <= i64::MAX as u128) {
+        Some(n) => Ok(n as f64),
+        None => Err(format!(
+            "{name}({args}) is too big to compute exactly (over 2^63)"
+        )),
+    }
+}
+
+pub fn factorial(n: u64) -> Option<u128> {
+    (1..=n as u128).try_fold(1u128, |acc, i| acc.checked_mul(i))
+}
+
+/// Ways to choose `r` of `n` in order. Zero when `r > n`, as in Python's
+/// `math.perm`.
+pub fn permutations(n: u64, r: u64) -> Option<u128> {
+    if r > n {
+        return Some(0);
+    }
+    ((n - r + 1) as u128..=n as u128).try_fold(1u128, |acc, i| acc.checked_mul(i))
+}
+
+/// Ways to choose `r` of `n` in any order. Each step's product is divisible
+/// by `i`, so the running value stays a whole number.
+pub fn combinations(n: u64, r: u64) -> Option<u128> {
+    if r > n {
+        return Some(0);
+    }
+    let r = r.min(n - r) as u128;
+    (1..=r).try_fold(1u128, |acc, i| {
+        Some(acc.checked_mul(n as u128 - r + i)? / i)
+    })
+}
+
+/// Most terms `binom_cdf` adds up, about a second's work
+const MAX_CDF_TERMS: u64 = 1_000_000;
+
+/// ln C(n, k), for distributions where C(n, k) itself would overflow.
+fn ln_combinations(n: u64, k: u64) -> f64 {
+    ln_factorial(n) - ln_factorial(k) - ln_factorial(n - k)
+}
+
+fn binom_pmf(n: u64, k: u64, p: f64) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    // p^0 is 1 even for p = 0, which ln can't express
+    let term = |count: u64, p: f64| {
+        if count == 0 {
+            0.0
+        } else {
+            count as f64 * p.ln()
+        }
+    };
+    (ln_combinations(n, k) + term(k, p) + term(n - k, 1.0 - p)).exp()
+}
+
+/// ln n!, exact up to 20! and by Stirling's series past it, where the
+/// terms left out are under 1e-17.
+fn ln_factorial(n: u64) -> f64 {
+    if let Some(exact) = factorial(n).filter(|_| n <= 20) {
+        return (exact as f64).ln();
+    }
+    let n = n as f64;
+    let correction = 1.0 / (12.0 * n) - 1.0 / (360.0 * n.powi(3)) + 1.0 / (1260.0 * n.powi(5))
+        - 1.0 / (1680.0 * n.powi(7));
+    n * n.ln() - n + 0.5 * (2.0 * PI * n).ln() + correction
+}
+
+/// P(X <= k), adding up whichever tail has fewer terms.
+fn binom_cdf(name: &str, n: u64, k: u64, p: f64) -> Result<f64, String> {
+    if k >= n {
+        return Ok(1.0);
+    }
+    let terms = (k + 1).min(n - k);
+    if terms > MAX_CDF_TERMS {
+        return Err(format!(
+            "{name} adds up at most {MAX_CDF_TERMS} terms; for n this large \
+             normal_cdf(k + 0.5, n*p, sqrt(n*p*(1-p))) is a close approximation"
+        ));
+    }
+    let sum = if k < n - k {
+        (0..=k).map(|i| binom_pmf(n, i, p)).sum::<f64>()
+    } else {
+        1.0 - (k + 1..=n).map(|i| binom_pmf(n, i, p)).sum::<f64>()
+    };
+    Ok(sum.clamp(0.0, 1.0))
+}
+
+/// erf by Abramowitz and Stegun 7.1.26, accurate to about 1.5e-7.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let y = 1.0 - poly * (-x * x).exp();
+    y.copysign(x)
+}
+
+fn standard_deviation(name: &str, sigma: f64) -> Result<f64, String> {
+    if sigma <= 0.0 {
+        return Err(format!(
+            "{name} needs a positive standard deviation, got {sigma}"
+        ));
+    }
+    Ok(sigma)
+}
+
+/// Apply `name` to `args`, which the caller has checked against `arity`.
+pub fn call(name: &str, args: &[f64]) -> Result<f64, String> {
+    let list = || {
+        args.iter()
+            .map(|a| crate::value::format_number(*a))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match (name, args) {
+        ("factorial", &[n]) => exact(name, &list(), factorial(count(name, n)?)),
+        ("nCr", &[n, r]) => exact(
+            name,
+            &list(),
+            combinations(count(name, n)?, count(name, r)?),
+        ),
+        ("nPr", &[n, r]) => exact(
+            name,
+            &list(),
+            permutations(count(name, n)?, count(name, r)?),
+        ),
+        ("binom_pmf", &[n, k, p]) => Ok(binom_pmf(
+            count(name, n)?,
+            count(name, k)?,
+            probability(name, p)?,
+        )),
+        ("binom_cdf", &[n, k, p]) => binom_cdf(
+            name,
+            count(name, n)?,
+            count(name, k)?,
+            probability(name, p)?,
+        ),
+        ("poisson_pmf", &[k, lambda]) => {
+            let k = count(name, k)?;
+            if lambda <= 0.0 {
+                return Err(format!("{name} needs a positive rate, got {lambda}"));
+            }
+            Ok((k as f64 * lambda.ln() - lambda - ln_factorial(k)).exp())
+        }
+        ("normal_pdf", &[x, mu, sigma]) => {
+            let sigma = standard_deviation(name, sigma)?;
+            let z = (x - mu) / sigma;
+            Ok((-z * z / 2.0).exp() / (sigma * (2.0 * PI).sqrt()))
+        }
+        ("normal_cdf", &[x, mu, sigma]) => {
+            let sigma = standard_deviation(name, sigma)?;
+            Ok(0.5 * (1.0 + erf((x - mu) / (sigma * SQRT_2))))
+        }
+        _ => Err(format!("Unknown function: {name}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_factorial_is_exact_to_the_limit() {
+        assert_eq!(call("factorial", &[0.0]), Ok(1.0));
+        assert_eq!(call("factorial", &[20.0]), Ok(2_432_902_008_176_640_000.0));
+        assert_eq!(factorial(20), Some(2_432_902_008_176_640_000));
+        assert!(call("factorial", &[21.0]).unwrap_err().contains("too big"));
+        assert!(call("factorial", &[-1.0]).is_err());
+        assert!(call("factorial", &[2.5]).is_err());
+    }
+
+    #[test]
+    fn test_combinations_and_permutations() {
+        assert_eq!(call("nCr", &[10.0, 3.0]), Ok(120.0));
+        assert_eq!(call("nPr", &[5.0, 2.0]), Ok(20.0));
+        assert_eq!(call("nCr", &[3.0, 5.0]), Ok(0.0));
+        assert_eq!(combinations(60, 30), Some(118_264_581_564_861_424));
+        // Far past factorial's limit, since the terms cancel as they go
+        assert!(call("nCr", &[66.0, 33.0]).is_ok());
+        assert!(call("nCr", &[200.0, 100.0]).is_err());
+        assert!(call("nPr", &[30.0, 20.0]).is_err());
+    }
+
+    #[test]
+    fn test_binomial() {
+        assert!(close(
+            call("binom_pmf", &[10.0, 3.0, 0.5]).unwrap(),
+            0.117_187_5
+        ));
+        assert_eq!(call("binom_pmf", &[5.0, 0.0, 0.0]), Ok(1.0));
+        assert!(close(call("binom_cdf", &[10.0, 10.0, 0.3]).unwrap(), 1.0));
+        assert!(close(call("binom_cdf", &[4.0, 1.0, 0.5]).unwrap(), 0.3125));
+        // Large n works in logs rather than overflowing
+        assert!(close(
+            call("binom_pmf", &[1000.0, 500.0, 0.5]).unwrap(),
+            0.025_225_0
+        ));
+        assert!(call("binom_pmf", &[10.0, 3.0, 1.5]).is_err());
+        // The upper tail when it's shorter
+        assert!(close(call("binom_cdf", &[4.0, 3.0, 0.5]).unwrap(), 0.9375));
+        assert!(close(
+            call("binom_cdf", &[1000.0, 500.0, 0.5]).unwrap(),
+            0.512_612_5
+        ));
+        let err = call("binom_cdf", &[1e9, 5e8, 0.5]).unwrap_err();
+        assert!(err.contains("at most"), "{err}");
+        assert!(close(
+            call("binom_cdf", &[1e9, 999_999_999.0, 0.5]).unwrap(),
+            1.0
+        ));
+    }
+
+    #[test]
+    fn test_ln_factorial_matches_the_sum() {
+        for n in [0, 1, 5, 20, 21, 50, 1000] {
+            let sum: f64 = (2..=n).map(|i| (i as f64).ln()).sum();
+            assert!((ln_factorial(n) - sum).abs() < 1e-9 * sum.max(1.0), "{n}");
+        }
+        // Constant time, so huge counts come back at once
+        assert!(close(call("poisson_pmf", &[1e9, 3.0]).unwrap(), 0.0));
+        assert!(close(
+            call("poisson_pmf", &[1000.0, 1000.0]).unwrap(),
+            0.012_614_7
+        ));
+    }
+
+    #[test]
+    fn test_poisson_and_normal() {
+        assert!(close(
+            call("poisson_pmf", &[2.0, 3.0]).unwrap(),
+            0.224_041_8
+        ));
+        assert!(close(
+            call("normal_pdf", &[0.0, 0.0, 1.0]).unwrap(),
+            0.398_942_3
+        ));
+        assert!(close(
+            call("normal_cdf", &[1.96, 0.0, 1.0]).unwrap(),
+            0.975_002_1
+        ));
+        assert!(close(call("normal_cdf", &[0.0, 0.0, 1.0]).unwrap(), 0.5));
+        assert!(call("normal_cdf", &[0.0, 0.0, 0.0]).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Exact fractions for `:mode frac`, so `1/3 + 1/6` is `1/2` rather than
+//! `0.5000000000000001`.
+
+use std::fmt;
+
+const OVERFLOW: &str = "Fraction overflow: the result is too big to keep exact; try :mode float";
+
+/// A fraction in lowest terms with a positive denominator, so equal values
+/// always compare equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    num: i64,
+    den: i64,
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+impl Rational {
+    pub fn new(num: i64, den: i64) -> Result<Rational, String> {
+        Rational::reduce(num as i128, den as i128)
+    }
+
+    pub fn integer(n: i64) -> Rational {
+        Rational { num: n, den: 1 }
+    }
+
+    /// Work in i128 so the intermediate products of two i64 fractions can't
+    /// overflow; only a result that doesn't fit back in i64 is an error.
+    fn reduce(num: i128, den: i128) -> Result<Rational, String> {
+        if den == 0 {
+            return Err("Division by zero".to_string());
+        }
+        let divisor = gcd(num, den) * den.signum();
+        let fit = |n: i128| i64::try_from(n).map_err(|_| OVERFLOW.to_string());
+        Ok(Rational {
+            num: fit(num / divisor)?,
+            den: fit(den / divisor)?,
+        })
+    }
+
+    /// The exact fraction for the decimal a float prints as: `0.1` is
+    /// `1/10`, not the binary value nearest to it. `None` for infinities,
+    /// NaN, and numbers with too many digits.
+    pub fn from_f64(x: f64) -> Option<Rational> {
+        if !x.is_finite() {
+            return None;
+        }
+        let text = x.abs().to_string();
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let den = 10_i128.checked_pow(fraction.len() as u32)?;
+        let num: i128 = format!("{whole}{fraction}").parse().ok()?;
+        let sign = if x < 0.0 { -1 } else { 1 };
+        Rational::reduce(sign * num, den).ok()
+    }
+
+    pub fn numer(&self) -> i64 {
+        self.num
+    }
+
+    pub fn denom(&self) -> i64 {
+        self.den
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    pub fn checked_neg(&self) -> Result<Rational, String> {
+        Rational::reduce(-(self.num as i128), self.den as i128)
+    }
+
+    pub fn abs(&self) -> Result<Rational, String> {
+        Rational::reduce((self.num as i128).abs(), self.den as i128)
+    }
+
+    pub fn checked_add(&self, other: &Rational) -> Result<Rational, String> {
+        let (a, b, c, d) = self.wide(other);
+        Rational::reduce(a * d + c * b, b * d)
+    }
+
+    pub fn checked_sub(&self, other: &Rational) -> Result<Rational, String> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    pub fn checked_mul(&self, other: &Rational) -> Result<Rational, String> {
+        let (a, b, c, d) = self.wide(other);
+        Rational::reduce(a * c, b * d)
+    }
+
+    pub fn checked_div(&self, other: &Rational) -> Result<Rational, String> {
+        let (a, b, c, d) = self.wide(other);
+        Rational::reduce(a * d, b * c)
+    }
+
+    /// Remainder with the sign of the dividend, like `%` on floats.
+    pub fn checked_rem(&self, other: &Rational) -> Result<Rational, String> {
+        let quotient = self.checked_div(other)?;
+        let whole = Rational::integer(quotient.num / quotient.den);
+        self.checked_sub(&other.checked_mul(&whole)?)
+    }
+
+    /// Integer powers by repeated squaring; a negative exponent inverts.
+    pub fn checked_pow(&self, exponent: i64) -> Result<Rational, String> {
+        let base = if exponent < 0 {
+            Rational::integer(1).checked_div(self)?
+        } else {
+            *self
+        };
+        let mut result = Rational::integer(1);
+        let mut square = base;
+        let mut remaining = exponent.unsigned_abs();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.checked_mul(&square)?;
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                square = square.checked_mul(&square)?;
+            }
+        }
+        Ok(result)
+    }
+
+    fn wide(&self, other: &Rational) -> (i128, i128, i128, i128) {
+        (
+            self.num as i128,
+            self.den as i128,
+            other.num as i128,
+            other.den as i128,
+        )
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn r(num: i64, den: i64) -> Rational {
+        Rational::new(num, den).unwrap()
+    }
+
+    #[test]
+    fn test_normalizes() {
+        assert_eq!(r(2, 4), r(1, 2));
+        assert_eq!(r(3, -6), r(-1, 2));
+        assert_eq!(r(-1, 2).to_string(), "-1/2");
+        assert_eq!(r(6, 3).to_string(), "2");
+        assert!(Rational::new(1, 0).is_err());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(r(1, 3).checked_add(&r(1, 6)), Ok(r(1, 2)));
+        assert_eq!(r(1, 2).checked_sub(&r(3, 4)), Ok(r(-1, 4)));
+        assert_eq!(r(2, 3).checked_mul(&r(3, 4)), Ok(r(1, 2)));
+        assert_eq!(r(1, 2).checked_div(&r(1, 4)), Ok(r(2, 1)));
+        assert_eq!(r(7, 2).checked_rem(&r(1, 1)), Ok(r(1, 2)));
+        assert_eq!(r(-7, 2).checked_rem(&r(1, 1)), Ok(r(-1, 2)));
+        assert_eq!(r(2, 3).checked_pow(3), Ok(r(8, 27)));
+        assert_eq!(r(2, 3).checked_pow(-2), Ok(r(9, 4)));
+        assert!(r(0, 1).checked_pow(-1).is_err());
+    }
+
+    #[test]
+    fn test_overflow_is_an_error() {
+        let big = Rational::integer(i64::MAX);
+        assert_eq!(big.checked_add(&big).unwrap_err(), OVERFLOW);
+        assert!(r(3, 1).checked_pow(100).is_err());
+        assert_eq!(r(1, 1).checked_pow(i64::MAX), Ok(r(1, 1)));
+    }
+
+    #[test]
+    fn test_from_f64_uses_the_decimal() {
+        assert_eq!(Rational::from_f64(0.1), Some(r(1, 10)));
+        assert_eq!(Rational::from_f64(-2.5), Some(r(-5, 2)));
+        assert_eq!(Rational::from_f64(3.0), Some(r(3, 1)));
+        assert_eq!(Rational::from_f64(f64::NAN), None);
+        assert_eq!(Rational::from_f64(1e300), None);
+    }
+}
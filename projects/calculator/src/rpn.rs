@@ -0,0 +1,162 @@
+use crate::environment::Environment;
+use crate::eval::eval;
+use crate::finance;
+use crate::functions;
+use crate::parser::BinOp;
+use crate::probability;
+use crate::value::Value;
+
+/// A reverse Polish stack machine: `3 4 + 5 *` pushes 3 and 4, adds them,
+/// pushes 5 and multiplies, leaving 35. Operators and functions are the
+/// expression evaluator's, including ones defined with `operator`.
+#[derive(Debug, Default)]
+pub struct Rpn {
+    stack: Vec<Value>,
+}
+
+fn binop(word: &str) -> Option<BinOp> {
+    match word {
+        "+" => Some(BinOp::Add),
+        "-" => Some(BinOp::Sub),
+        "*" => Some(BinOp::Mul),
+        "/" => Some(BinOp::Div),
+        "^" => Some(BinOp::Pow),
+        "%" => Some(BinOp::Mod),
+        _ => None,
+    }
+}
+
+/// Take the top `n` values off `stack`, deepest first.
+fn pop(stack: &mut Vec<Value>, n: usize, word: &str) -> Result<Vec<Value>, String> {
+    if stack.len() < n {
+        let plural = if n == 1 { "" } else { "s" };
+        return Err(format!(
+            "'{word}' needs {n} value{plural} on the stack, it has {}",
+            stack.len()
+        ));
+    }
+    Ok(stack.split_off(stack.len() - n))
+}
+
+impl Rpn {
+    pub fn new() -> Self {
+        Rpn::default()
+    }
+
+    /// Bottom first
+    pub fn stack(&self) -> &[Value] {
+        &self.stack
+    }
+
+    pub fn top(&self) -> Option<Value> {
+        self.stack.last().copied()
+    }
+
+    /// Run the whitespace-separated words of `input` left to right. Anything
+    /// that isn't an operator, function or stack command is evaluated as an
+    /// expression and pushed, so `-2`, `pi`, `$1` and `(1+2)` all work. If
+    /// a word fails, the stack is left as it was before the line.
+    pub fn enter(&mut self, input: &str, env: &Environment) -> Result<(), String> {
+        let mut stack = self.stack.clone();
+        for word in input.split_whitespace() {
+            step(&mut stack, word, env)?;
+        }
+        self.stack = stack;
+        Ok(())
+    }
+}
+
+fn step(stack: &mut Vec<Value>, word: &str, env: &Environment) -> Result<(), String> {
+    match word {
+        "dup" => {
+            let top = pop(stack, 1, word)?[0];
+            stack.extend([top, top]);
+        }
+        "swap" => {
+            let pair = pop(stack, 2, word)?;
+            stack.extend([pair[1], pair[0]]);
+        }
+        "drop" => {
+            pop(stack, 1, word)?;
+        }
+        "clear" => stack.clear(),
+        _ => {
+            let value = if let Some(op) = binop(word) {
+                let args = pop(stack, 2, word)?;
+                args[0].apply(op, &args[1])?
+            } else if let Some(op) = env.operator(word) {
+                let args = pop(stack, 2, word)?;
+                op.apply(args[0], args[1], env)?
+            } else if let Some(arity) = probability::arity(word) {
+                functions::call_in(word, &pop(stack, arity, word)?, env.angle())?
+            } else if let Some((least, most)) = finance::arity(word) {
+                // npv and irr take the whole stack as their cash flows
+                let count = match most {
+                    Some(_) => least,
+                    None => stack.len().max(least),
+                };
+                functions::call_in(word, &pop(stack, count, word)?, env.angle())?
+            } else if functions::NAMES.contains(&word) {
+                functions::call_in(word, &pop(stack, 1, word)?, env.angle())?
+            } else {
+                let expr = crate::parse_in(word, env).map_err(|e| format!("'{word}': {e}"))?;
+                eval(&expr, env)?
+            };
+            stack.push(value);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators;
+
+    fn run(input: &str, env: &Environment) -> Result<Vec<Value>, String> {
+        let mut rpn = Rpn::new();
+        rpn.enter(input, env)?;
+        Ok(rpn.stack().to_vec())
+    }
+
+    fn numbers(values: &[f64]) -> Vec<Value> {
+        values.iter().map(|n| Value::Number(*n)).collect()
+    }
+
+    #[test]
+    fn test_operators_and_functions() {
+        let env = Environment::new();
+        assert_eq!(run("3 4 + 5 *", &env), Ok(numbers(&[35.0])));
+        assert_eq!(run("2 10 ^ sqrt", &env), Ok(numbers(&[32.0])));
+        assert_eq!(run("10 3 nCr -2 abs", &env), Ok(numbers(&[120.0, 2.0])));
+        assert_eq!(run("7 2 %", &env), Ok(numbers(&[1.0])));
+
+        let mut env = Environment::new();
+        let hypot = operators::parse_definition("operator <> (a, b) = sqrt(a^2 + b^2)", &env);
+        env.define_operator(hypot.unwrap().unwrap());
+        assert_eq!(run("3 4 <>", &env), Ok(numbers(&[5.0])));
+    }
+
+    #[test]
+    fn test_stack_commands() {
+        let env = Environment::new();
+        assert_eq!(run("1 2 swap", &env), Ok(numbers(&[2.0, 1.0])));
+        assert_eq!(run("3 dup *", &env), Ok(numbers(&[9.0])));
+        assert_eq!(run("1 2 drop", &env), Ok(numbers(&[1.0])));
+        assert_eq!(run("1 2 clear 4", &env), Ok(numbers(&[4.0])));
+    }
+
+    #[test]
+    fn test_errors_leave_the_stack_alone() {
+        let env = Environment::new();
+        let mut rpn = Rpn::new();
+        rpn.enter("1 2", &env).unwrap();
+        assert_eq!(
+            rpn.enter("+ +", &env),
+            Err("'+' needs 2 values on the stack, it has 1".to_string())
+        );
+        assert!(rpn.enter("1 0 /", &env).is_err());
+        assert!(rpn.enter("3 nope", &env).is_err());
+        assert_eq!(rpn.stack(), numbers(&[1.0, 2.0]));
+    }
+}
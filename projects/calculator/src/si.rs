@@ -0,0 +1,104 @@
+//! SI prefixes for engineering notation: `4.7k`, `3.3µ`, `2.2M`.
+
+use crate::value::format_number;
+
+/// Prefix letters and their multipliers, largest first. `u` is accepted as an
+/// easier-to-type micro, and both the micro sign and Greek mu are read.
+const PREFIXES: &[(char, i32)] = &[
+    ('T', 12),
+    ('G', 9),
+    ('M', 6),
+    ('k', 3),
+    ('m', -3),
+    ('µ', -6),
+    ('μ', -6),
+    ('u', -6),
+    ('n', -9),
+    ('p', -12),
+    ('f', -15),
+];
+
+const MAX_EXPONENT: i32 = 12;
+const MIN_EXPONENT: i32 = -15;
+
+/// The multiplier for a prefix letter, e.g. `k` → 1000.
+pub fn multiplier(prefix: char) -> Option<f64> {
+    PREFIXES
+        .iter()
+        .find(|(c, _)| *c == prefix)
+        .map(|(_, exp)| 10f64.powi(*exp))
+}
+
+fn symbol(exponent: i32) -> &'static str {
+    match exponent {
+        12 => "T",
+        9 => "G",
+        6 => "M",
+        3 => "k",
+        -3 => "m",
+        -6 => "µ",
+        -9 => "n",
+        -12 => "p",
+        -15 => "f",
+        _ => "",
+    }
+}
+
+/// Format `n` with a mantissa between 1 and 1000 and an SI prefix:
+/// 1500 → `1.5k`, 0.0000033 → `3.3µ`. Values outside the prefix range keep
+/// the largest or smallest prefix.
+pub fn format_si(n: f64) -> String {
+    if n == 0.0 || !n.is_finite() {
+        return format_number(n);
+    }
+
+    let mut exponent = (n.abs().log10() / 3.0).floor() as i32 * 3;
+    exponent = exponent.clamp(MIN_EXPONENT, MAX_EXPONENT);
+    let mut mantissa = format_number(n / 10f64.powi(exponent));
+
+    // Rounding can carry into the next prefix: 999.9999999999999k → 1M
+    if mantissa
+        .trim_start_matches('-')
+        .parse::<f64>()
+        .unwrap_or(0.0)
+        >= 1000.0
+        && exponent < MAX_EXPONENT
+    {
+        exponent += 3;
+        mantissa = format_number(n / 10f64.powi(exponent));
+    }
+
+    format!("{mantissa}{}", symbol(exponent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_si() {
+        assert_eq!(format_si(1500.0), "1.5k");
+        assert_eq!(format_si(0.0000033), "3.3µ");
+        assert_eq!(format_si(2_200_000.0), "2.2M");
+        assert_eq!(format_si(-47.0), "-47");
+        assert_eq!(format_si(0.0), "0");
+        assert_eq!(format_si(0.1), "100m");
+    }
+
+    #[test]
+    fn test_rounding_carries_into_next_prefix() {
+        assert_eq!(format_si(999_999.999_999_999_9), "1M");
+    }
+
+    #[test]
+    fn test_out_of_range_keeps_extreme_prefix() {
+        assert_eq!(format_si(5e15), "5000T");
+    }
+
+    #[test]
+    fn test_multiplier() {
+        assert_eq!(multiplier('k'), Some(1000.0));
+        assert_eq!(multiplier('u'), multiplier('µ'));
+        assert_eq!(multiplier('x'), None);
+    }
+}
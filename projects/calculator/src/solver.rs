@@ -0,0 +1,336 @@
+use crate::environment::Environment;
+use crate::functions;
+use crate::lexer::{self, Token};
+use crate::parser::{self, BinOp, Expr};
+use crate::value::{calculate, format_number, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Coefficients smaller than this are treated as zero
+const EPSILON: f64 = 1e-12;
+
+/// Largest denominator we'll show as an exact fraction
+const MAX_DENOMINATOR: i64 = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Solution {
+    /// The equation holds for every value of the variable
+    All(String),
+    /// The equation never holds
+    Nothing(String),
+    /// Real roots in ascending order (empty if only complex roots exist)
+    Roots(String, Vec<f64>),
+}
+
+impl fmt::Display for Solution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Solution::All(var) => write!(f, "true for every value of {var}"),
+            Solution::Nothing(var) => write!(f, "no value of {var} satisfies the equation"),
+            Solution::Roots(var, roots) if roots.is_empty() => {
+                write!(f, "no real solutions for {var}")
+            }
+            Solution::Roots(var, roots) => {
+                let parts: Vec<String> = roots
+                    .iter()
+                    .map(|&r| match as_fraction(r) {
+                        Some((n, 1)) => format!("{var} = {n}"),
+                        Some((n, d)) => format!("{var} = {n}/{d} ({})", format_number(r)),
+                        None => format!("{var} ≈ {}", format_number(r)),
+                    })
+                    .collect();
+                write!(f, "{}", parts.join(" or "))
+            }
+        }
+    }
+}
+
+/// Solve a linear or quadratic equation like `2x + 3 = 11` or `x^2 = 2x + 3`
+/// for its one unknown. Other names are looked up in `env`.
+pub fn solve(input: &str, env: &Environment) -> Result<Solution, String> {
+    let tokens = lexer::tokenize(input)?;
+
+    let mut sides = tokens.split(|t| *t == Token::Equals);
+    let (Some(lhs), Some(rhs), None) = (sides.next(), sides.next(), sides.next()) else {
+        return Err("An equation needs exactly one '=', e.g. solve 2x + 3 = 11".to_string());
+    };
+
+    let lhs = parser::parse_in(lhs, env)?;
+    let rhs = parser::parse_in(rhs, env)?;
+    let var = find_unknown(&[&lhs, &rhs], env)?;
+
+    // Move everything to one side: lhs - rhs = 0
+    let poly = sub(&to_poly(&lhs, &var, env)?, &to_poly(&rhs, &var, env)?);
+
+    match poly.len() {
+        0 => Ok(Solution::All(var)),
+        1 => Ok(Solution::Nothing(var)),
+        2 => Ok(Solution::Roots(var.clone(), vec![-poly[0] / poly[1]])),
+        3 => Ok(Solution::Roots(
+            var,
+            quadratic_roots(poly[2], poly[1], poly[0]),
+        )),
+        _ => Err(format!(
+            "Only linear and quadratic equations are supported (degree {} in {var})",
+            poly.len() - 1
+        )),
+    }
+}
+
+/// The variable to solve for: the one name that isn't already defined.
+fn find_unknown(exprs: &[&Expr], env: &Environment) -> Result<String, String> {
+    let mut names = BTreeSet::new();
+    for expr in exprs {
+        collect_variables(expr, &mut names);
+    }
+
+    let unknown: Vec<&String> = names.iter().filter(|n| env.get(n).is_none()).collect();
+
+    match unknown.as_slice() {
+        [var] => Ok(var.to_string()),
+        [] => Err("No unknown variable to solve for".to_string()),
+        _ => Err(format!(
+            "Can only solve for one unknown, found: {}",
+            unknown
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+pub(crate) fn collect_variables(expr: &Expr, names: &mut BTreeSet<String>) {
+    match expr {
+        Expr::Number(_) | Expr::Temporal(_) | Expr::Result(_) => {}
+        Expr::Variable(name) => {
+            names.insert(name.clone());
+        }
+        Expr::Neg(inner) => collect_variables(inner, names),
+        Expr::Call(_, args) => {
+            for arg in args {
+                collect_variables(arg, names);
+            }
+        }
+        Expr::Interval(a, b)
+        | Expr::Uncertain(a, b)
+        | Expr::Binary(_, a, b)
+        | Expr::Custom(_, a, b) => {
+            collect_variables(a, names);
+            collect_variables(b, names);
+        }
+    }
+}
+
+/// A polynomial in one variable: `p[i]` is the coefficient of `var^i`.
+/// Always trimmed so the last coefficient is non-zero (zero is `[]`).
+type Poly = Vec<f64>;
+
+fn trim(mut p: Poly) -> Poly {
+    while p.last().is_some_and(|c| c.abs() < EPSILON) {
+        p.pop();
+    }
+    p
+}
+
+fn add(a: &Poly, b: &Poly) -> Poly {
+    let len = a.len().max(b.len());
+    let p = (0..len)
+        .map(|i| a.get(i).unwrap_or(&0.0) + b.get(i).unwrap_or(&0.0))
+        .collect();
+    trim(p)
+}
+
+fn sub(a: &Poly, b: &Poly) -> Poly {
+    add(a, &b.iter().map(|c| -c).collect())
+}
+
+fn mul(a: &Poly, b: &Poly) -> Poly {
+    if a.is_empty() || b.is_empty() {
+        return Vec::new();
+    }
+    let mut p = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            p[i + j] += x * y;
+        }
+    }
+    trim(p)
+}
+
+/// The value of a polynomial that doesn't depend on the variable.
+fn constant(p: &Poly) -> Option<f64> {
+    match p.len() {
+        0 => Some(0.0),
+        1 => Some(p[0]),
+        _ => None,
+    }
+}
+
+fn to_poly(expr: &Expr, var: &str, env: &Environment) -> Result<Poly, String> {
+    let poly = |e: &Expr| to_poly(e, var, env);
+
+    match expr {
+        Expr::Number(n) => Ok(trim(vec![*n])),
+        Expr::Variable(name) if name == var => Ok(vec![0.0, 1.0]),
+        Expr::Variable(name) => match env.get(name) {
+            Some(Value::Number(n)) => Ok(trim(vec![n])),
+            Some(Value::Fraction(r)) => Ok(trim(vec![r.to_f64()])),
+            Some(Value::Decimal(d)) => Ok(trim(vec![d.to_f64()])),
+            Some(value) => Err(format!(
+                "Can't use {} '{name}' in an equation",
+                value.kind()
+            )),
+            None => Err(format!("Unknown variable: {name}")),
+        },
+        Expr::Result(n) => match env.result(*n)? {
+            Value::Number(x) => Ok(trim(vec![x])),
+            Value::Fraction(r) => Ok(trim(vec![r.to_f64()])),
+            Value::Decimal(d) => Ok(trim(vec![d.to_f64()])),
+            value => Err(format!("Can't use {} ${n} in an equation", value.kind())),
+        },
+        Expr::Neg(inner) => Ok(poly(inner)?.iter().map(|c| -c).collect()),
+        Expr::Interval(..) | Expr::Uncertain(..) => {
+            Err("Intervals aren't supported in equations".to_string())
+        }
+        Expr::Temporal(_) => Err("Dates and durations aren't supported in equations".to_string()),
+        Expr::Call(name, args) => {
+            let mut values = Vec::new();
+            for arg in args {
+                match constant(&poly(arg)?) {
+                    Some(x) => values.push(Value::Number(x)),
+                    None => return Err(format!("Can't solve for {var} inside {name}()")),
+                }
+            }
+            Ok(trim(vec![
+                functions::call_in(name, &values, env.angle())?.as_number()?
+            ]))
+        }
+        Expr::Custom(op, left, right) => match (constant(&poly(left)?), constant(&poly(right)?)) {
+            (Some(x), Some(y)) => Ok(trim(vec![op
+                .apply(Value::Number(x), Value::Number(y), env)?
+                .as_number()?])),
+            _ => Err(format!("Can't solve for {var} inside {}", op.symbol)),
+        },
+        Expr::Binary(op, left, right) => {
+            let (a, b) = (poly(left)?, poly(right)?);
+
+            // Constant subexpressions like 2^0.5 or 7 % 3 evaluate directly
+            if let (Some(x), Some(y)) = (constant(&a), constant(&b)) {
+                return Ok(trim(vec![calculate(x, *op, y)?]));
+            }
+
+            match op {
+                BinOp::Add => Ok(add(&a, &b)),
+                BinOp::Sub => Ok(sub(&a, &b)),
+                BinOp::Mul => Ok(mul(&a, &b)),
+                BinOp::Div => match constant(&b) {
+                    Some(d) if d != 0.0 => Ok(a.iter().map(|c| c / d).collect()),
+                    Some(_) => Err("Division by zero".to_string()),
+                    None => Err(format!("Can't divide by an expression containing {var}")),
+                },
+                BinOp::Pow => {
+                    let exponent = constant(&b)
+                        .filter(|e| *e >= 0.0 && e.fract() == 0.0 && *e <= 16.0)
+                        .ok_or(format!("Powers of {var} must be small whole numbers"))?;
+                    Ok((0..exponent as usize).fold(vec![1.0], |acc, _| mul(&acc, &a)))
+                }
+                BinOp::Mod => Err("Modulo isn't supported in equations".to_string()),
+            }
+        }
+    }
+}
+
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let discriminant = b * b - 4.0 * a * c;
+
+    if discriminant < -EPSILON {
+        return Vec::new();
+    }
+    if discriminant.abs() < EPSILON {
+        return vec![-b / (2.0 * a)];
+    }
+
+    let sqrt = discriminant.sqrt();
+    let mut roots = vec![(-b - sqrt) / (2.0 * a), (-b + sqrt) / (2.0 * a)];
+    roots.sort_by(|x, y| x.total_cmp(y));
+    roots
+}
+
+/// Express `x` as `numerator/denominator` if a small denominator gets within
+/// float rounding of it.
+fn as_fraction(x: f64) -> Option<(i64, i64)> {
+    (1..=MAX_DENOMINATOR).find_map(|d| {
+        let n = (x * d as f64).round();
+        if (n / d as f64 - x).abs() < 1e-9 && n.abs() < i64::MAX as f64 {
+            Some((n as i64, d))
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve_str(input: &str) -> Result<Solution, String> {
+        solve(input, &Environment::new())
+    }
+
+    fn roots(var: &str, values: &[f64]) -> Solution {
+        Solution::Roots(var.to_string(), values.to_vec())
+    }
+
+    #[test]
+    fn test_linear() {
+        assert_eq!(solve_str("2x + 3 = 11"), Ok(roots("x", &[4.0])));
+        assert_eq!(solve_str("3 * (y - 1) = y"), Ok(roots("y", &[1.5])));
+        assert_eq!(solve_str("x = 9^0.5 + 7 % 4"), Ok(roots("x", &[6.0])));
+    }
+
+    #[test]
+    fn test_quadratic() {
+        assert_eq!(solve_str("x^2 - 5x + 6 = 0"), Ok(roots("x", &[2.0, 3.0])));
+        assert_eq!(solve_str("x^2 = 2x - 1"), Ok(roots("x", &[1.0])));
+        assert_eq!(solve_str("x^2 + 1 = 0"), Ok(roots("x", &[])));
+    }
+
+    #[test]
+    fn test_identity_and_contradiction() {
+        assert_eq!(
+            solve_str("x + 1 = 1 + x"),
+            Ok(Solution::All("x".to_string()))
+        );
+        assert_eq!(
+            solve_str("x = x + 1"),
+            Ok(Solution::Nothing("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_uses_known_variables() {
+        let mut env = Environment::new();
+        env.set("k", Value::Number(2.0));
+        assert_eq!(solve("k * t = 7", &env), Ok(roots("t", &[3.5])));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(solve_str("2x + 3").is_err());
+        assert!(solve_str("x = y").is_err());
+        assert!(solve_str("x^3 = 8").is_err());
+        assert!(solve_str("1 / x = 2").is_err());
+    }
+
+    #[test]
+    fn test_display_exact_and_approximate() {
+        assert_eq!(roots("x", &[4.0]).to_string(), "x = 4");
+        assert_eq!(roots("x", &[0.75]).to_string(), "x = 3/4 (0.75)");
+        assert_eq!(
+            solve_str("x^2 = 2").unwrap().to_string(),
+            "x ≈ -1.41421356237 or x ≈ 1.41421356237"
+        );
+        assert_eq!(roots("x", &[]).to_string(), "no real solutions for x");
+    }
+}
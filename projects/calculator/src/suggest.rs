@@ -0,0 +1,260 @@
+//! Likely fixes for input that failed: brackets that don't pair up, and
+//! misspelled function or variable names.
+//!
+//! ```text
+//! > 2 * (3 + 4
+//! Error: Expected ')'
+//! Did you mean `2 * (3 + 4)`? Unclosed parenthesis at position 5
+//! > sqr(16)
+//! Error: Unknown function: sqr ...
+//! Unknown function 'sqr', did you mean 'sqrt'?
+//! ```
+
+use crate::environment::Environment;
+use crate::functions;
+use crate::lexer::{self, Token};
+use crate::parser;
+use std::fmt;
+
+/// A probable fix for input that didn't evaluate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Suggestion {
+    /// A bracket at `position` (1-based, in characters) with no partner.
+    /// `fixed` is the input repaired, when the repair parses.
+    Unbalanced {
+        bracket: char,
+        position: usize,
+        fixed: Option<String>,
+    },
+    /// A name that isn't defined but is close to one that is.
+    Misspelled {
+        /// "function" or "variable"
+        kind: &'static str,
+        name: String,
+        closest: String,
+    },
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Suggestion::Unbalanced {
+                bracket,
+                position,
+                fixed,
+            } => {
+                let what = match bracket {
+                    '(' | ')' => "parenthesis",
+                    _ => "bracket",
+                };
+                let problem = if matches!(bracket, '(' | '[') {
+                    "Unclosed"
+                } else {
+                    "Unmatched"
+                };
+                match fixed {
+                    Some(fixed) => write!(
+                        f,
+                        "Did you mean `{fixed}`? {problem} {what} at position {position}"
+                    ),
+                    None => write!(f, "{problem} {what} at position {position}"),
+                }
+            }
+            Suggestion::Misspelled {
+                kind,
+                name,
+                closest,
+            } => write!(f, "Unknown {kind} '{name}', did you mean '{closest}'?"),
+        }
+    }
+}
+
+/// The most likely fix for `input`, if one stands out. Brackets are
+/// checked first, since a missing one can make every later error
+/// misleading.
+pub fn suggest(input: &str, env: &Environment) -> Option<Suggestion> {
+    if let Some(suggestion) = unbalanced(input, env) {
+        return Some(suggestion);
+    }
+
+    let tokens = lexer::tokenize(input).ok()?;
+    for (i, token) in tokens.iter().enumerate() {
+        let Token::Ident(name) = token else {
+            continue;
+        };
+        let (kind, known) = if tokens.get(i + 1) == Some(&Token::LParen) {
+            ("function", functions::names())
+        } else {
+            ("variable", env.names())
+        };
+        if known.contains(&name.as_str()) {
+            continue;
+        }
+        if let Some(closest) = closest(name, &known) {
+            return Some(Suggestion::Misspelled {
+                kind,
+                name: name.clone(),
+                closest: closest.to_string(),
+            });
+        }
+    }
+    None
+}
+
+fn unbalanced(input: &str, env: &Environment) -> Option<Suggestion> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut open: Vec<(char, usize)> = Vec::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        let partner = match c {
+            '(' | '[' => {
+                open.push((c, i));
+                continue;
+            }
+            ')' => '(',
+            ']' => '[',
+            _ => continue,
+        };
+        let fixed: String = match open.pop() {
+            Some((opener, _)) if opener == partner => continue,
+            // The wrong kind of closer: swap it for the right one
+            Some((opener, _)) => chars[..i]
+                .iter()
+                .chain(std::iter::once(&closer(opener)))
+                .chain(&chars[i + 1..])
+                .collect(),
+            // Nothing to close: drop it
+            None => chars[..i].iter().chain(&chars[i + 1..]).collect(),
+        };
+        return Some(repair(c, i, fixed, env));
+    }
+
+    // Close whatever is still open, innermost first
+    let &(bracket, at) = open.last()?;
+    let mut fixed = input.trim_end().to_string();
+    fixed.extend(open.iter().rev().map(|(opener, _)| closer(*opener)));
+    Some(repair(bracket, at, fixed, env))
+}
+
+fn closer(opener: char) -> char {
+    if opener == '(' {
+        ')'
+    } else {
+        ']'
+    }
+}
+
+/// Offer `fixed` only if it actually parses.
+fn repair(bracket: char, index: usize, fixed: String, env: &Environment) -> Suggestion {
+    let parses = lexer::tokenize(&fixed).is_ok_and(|tokens| parser::parse_in(&tokens, env).is_ok());
+    Suggestion::Unbalanced {
+        bracket,
+        position: index + 1,
+        fixed: parses.then_some(fixed),
+    }
+}
+
+/// The candidate nearest `name` by edit distance, if it's near enough to be
+/// a typo: one edit for short names, more for longer ones. Ties go to the
+/// first candidate.
+pub fn closest<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let limit = (name.chars().count() + 1) / 3;
+    candidates
+        .iter()
+        .map(|&candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance > 0 && distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance: the fewest single-character insertions, deletions
+/// and substitutions that turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // Distances from the first i characters of `a` to each prefix of `b`
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggest_str(input: &str) -> Option<String> {
+        suggest(input, &Environment::new()).map(|s| s.to_string())
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("sqr", "sqrt"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_closest() {
+        let names = ["sin", "sqrt", "ln", "log"];
+        assert_eq!(closest("sqr", &names), Some("sqrt"));
+        assert_eq!(closest("lg", &names), Some("ln"));
+        assert_eq!(closest("sqrt", &names), None);
+        // A single letter is too short to guess at
+        assert_eq!(closest("x", &["e"]), None);
+        assert_eq!(closest("banana", &names), None);
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        assert_eq!(
+            suggest_str("2 * (3 + 4"),
+            Some("Did you mean `2 * (3 + 4)`? Unclosed parenthesis at position 5".to_string())
+        );
+        assert_eq!(
+            suggest_str("(1 + 2))"),
+            Some("Did you mean `(1 + 2)`? Unmatched parenthesis at position 8".to_string())
+        );
+        assert_eq!(
+            suggest_str("[1, 2) + 1"),
+            Some("Did you mean `[1, 2] + 1`? Unmatched parenthesis at position 6".to_string())
+        );
+        // Closing it wouldn't help, so there's no fix to offer
+        assert_eq!(
+            suggest_str("(1 +"),
+            Some("Unclosed parenthesis at position 1".to_string())
+        );
+        assert_eq!(suggest_str("(1 + 2) * 3"), None);
+    }
+
+    #[test]
+    fn test_misspelled_names() {
+        assert_eq!(
+            suggest_str("sqr(16) + 1"),
+            Some("Unknown function 'sqr', did you mean 'sqrt'?".to_string())
+        );
+        assert_eq!(
+            suggest_str("2 * pii"),
+            Some("Unknown variable 'pii', did you mean 'pi'?".to_string())
+        );
+        let mut env = Environment::new();
+        env.set("width", crate::Value::Number(3.0));
+        assert_eq!(
+            suggest("widht * 2", &env),
+            Some(Suggestion::Misspelled {
+                kind: "variable",
+                name: "widht".to_string(),
+                closest: "width".to_string(),
+            })
+        );
+        assert_eq!(suggest_str("frobnicate(2)"), None);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::environment::Environment;
+use crate::eval::eval;
+use crate::lexer::{self, Token};
+use crate::parser::{self, BinOp};
+use crate::value::calculate;
+use std::fmt;
+
+const AMOUNT_WIDTH: usize = 14;
+
+/// An adding-machine tape: every entry applies one operation to a running
+/// total, e.g. `100`, `+ 25`, `- 10`, `* 2`. A bare amount is added.
+#[derive(Debug, Default)]
+pub struct Tape {
+    total: f64,
+}
+
+/// One printed line of the tape: the amount and its operation, then the
+/// running total after applying it.
+#[derive(Debug, PartialEq)]
+pub struct TapeEntry {
+    pub op: BinOp,
+    pub amount: f64,
+    pub total: f64,
+}
+
+impl fmt::Display for TapeEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}  │{:>AMOUNT_WIDTH$.2}",
+            format_amount(self.amount),
+            self.op,
+            self.total
+        )
+    }
+}
+
+impl Tape {
+    pub fn new() -> Self {
+        Tape::default()
+    }
+
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    /// Apply one entry. The amount may be any expression the calculator
+    /// understands, such as `+ 3 * 4.50` or `- ans`.
+    pub fn enter(&mut self, input: &str, env: &Environment) -> Result<TapeEntry, String> {
+        let tokens = lexer::tokenize(input)?;
+
+        let (op, rest) = match tokens.split_first() {
+            Some((Token::Plus, rest)) => (BinOp::Add, rest),
+            Some((Token::Minus, rest)) => (BinOp::Sub, rest),
+            Some((Token::Star, rest)) => (BinOp::Mul, rest),
+            Some((Token::Slash, rest)) => (BinOp::Div, rest),
+            _ => (BinOp::Add, tokens.as_slice()),
+        };
+
+        let amount = eval(&parser::parse_in(rest, env)?, env)?.as_number()?;
+        self.total = calculate(self.total, op, amount)?;
+
+        Ok(TapeEntry {
+            op,
+            amount,
+            total: self.total,
+        })
+    }
+
+    /// The subtotal line; the total keeps running.
+    pub fn subtotal(&self) -> String {
+        total_line(self.total, "S")
+    }
+
+    /// The total line. Like an adding machine, taking the total clears it.
+    pub fn take_total(&mut self) -> (f64, String) {
+        let total = self.total;
+        self.clear();
+        (total, total_line(total, "T"))
+    }
+
+    pub fn clear(&mut self) {
+        self.total = 0.0;
+    }
+}
+
+fn format_amount(n: f64) -> String {
+    format!("{n:>AMOUNT_WIDTH$.2}")
+}
+
+fn total_line(total: f64, marker: &str) -> String {
+    format!(
+        "{}\n{} {marker}",
+        "-".repeat(AMOUNT_WIDTH + 2),
+        format_amount(total)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_total() {
+        let env = Environment::new();
+        let mut tape = Tape::new();
+
+        tape.enter("100", &env).unwrap();
+        tape.enter("+ 25.50", &env).unwrap();
+        tape.enter("- 10", &env).unwrap();
+        let entry = tape.enter("* 2", &env).unwrap();
+
+        assert_eq!(
+            entry,
+            TapeEntry {
+                op: BinOp::Mul,
+                amount: 2.0,
+                total: 231.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_amounts_can_be_expressions() {
+        let env = Environment::new();
+        let mut tape = Tape::new();
+        tape.enter("3 * 4.5", &env).unwrap();
+        tape.enter("- (2 + 1.5)", &env).unwrap();
+        assert_eq!(tape.total(), 10.0);
+    }
+
+    #[test]
+    fn test_total_clears_but_subtotal_does_not() {
+        let env = Environment::new();
+        let mut tape = Tape::new();
+        tape.enter("42", &env).unwrap();
+
+        assert!(tape.subtotal().ends_with("42.00 S"));
+        assert_eq!(tape.total(), 42.0);
+
+        let (total, line) = tape.take_total();
+        assert_eq!(total, 42.0);
+        assert!(line.ends_with("42.00 T"));
+        assert_eq!(tape.total(), 0.0);
+    }
+
+    #[test]
+    fn test_bad_entries_leave_total_alone() {
+        let env = Environment::new();
+        let mut tape = Tape::new();
+        tape.enter("10", &env).unwrap();
+
+        assert!(tape.enter("/ 0", &env).is_err());
+        assert!(tape.enter("+", &env).is_err());
+        assert!(tape.enter("+ [1, 2]", &env).is_err());
+        assert_eq!(tape.total(), 10.0);
+    }
+
+    #[test]
+    fn test_entry_display() {
+        let entry = TapeEntry {
+            op: BinOp::Sub,
+            amount: 10.0,
+            total: 115.5,
+        };
+        assert_eq!(entry.to_string(), "         10.00 -  │        115.50");
+    }
+}
@@ -0,0 +1,250 @@
+use crate::environment::Environment;
+use crate::eval::eval_traced;
+use crate::parser::{BinOp, Expr};
+use crate::value::Value;
+use std::collections::HashMap;
+
+/// How tightly each kind of expression binds, loosest first, following the
+/// grammar in `parser`. A part binding looser than its place allows gets
+/// parentheses.
+const UNCERTAIN: u8 = 1;
+const NEG: u8 = 5;
+const POWER: u8 = 7;
+const ATOM: u8 = 8;
+
+/// How a custom operator of `precedence` 0 to 3 ranks among the levels
+fn custom_level(precedence: u8) -> u8 {
+    match precedence {
+        0 => 2,
+        1 => 3,
+        2 => 4,
+        _ => 6,
+    }
+}
+
+fn binop_level(op: BinOp) -> u8 {
+    match op {
+        BinOp::Add | BinOp::Sub => 3,
+        BinOp::Mul | BinOp::Div | BinOp::Mod => 4,
+        BinOp::Pow => POWER,
+    }
+}
+
+fn value_level(value: &Value) -> u8 {
+    match value {
+        Value::Number(n) if *n < 0.0 => NEG,
+        Value::Decimal(d) if d.to_f64() < 0.0 => NEG,
+        Value::Fraction(_) => binop_level(BinOp::Div),
+        Value::Interval { .. } => UNCERTAIN,
+        _ => ATOM,
+    }
+}
+
+/// Parts of an expression already worked out, by address in the tree
+type Reduced = HashMap<*const Expr, Value>;
+
+/// `expr` as it would be typed, with the parts in `reduced` replaced by
+/// their values.
+fn render(expr: &Expr, reduced: &Reduced) -> String {
+    render_at(expr, reduced, 0)
+}
+
+/// `expr` in a place that needs at least `level`.
+fn render_at(expr: &Expr, reduced: &Reduced, level: u8) -> String {
+    let (text, own) = match reduced.get(&(expr as *const Expr)) {
+        Some(value) => (value.to_string(), value_level(value)),
+        None => render_parts(expr, reduced),
+    };
+    if own < level {
+        format!("({text})")
+    } else {
+        text
+    }
+}
+
+fn render_parts(expr: &Expr, reduced: &Reduced) -> (String, u8) {
+    let at = |e: &Expr, level: u8| render_at(e, reduced, level);
+    match expr {
+        Expr::Number(n) => {
+            let value = Value::Number(*n);
+            (value.to_string(), value_level(&value))
+        }
+        Expr::Temporal(value) => (value.to_string(), ATOM),
+        Expr::Variable(name) => (name.clone(), ATOM),
+        Expr::Result(n) => (format!("${n}"), ATOM),
+        Expr::Interval(low, high) => (format!("[{}, {}]", at(low, 0), at(high, 0)), ATOM),
+        Expr::Uncertain(value, error) => (
+            format!(
+                "{} ± {}",
+                at(value, UNCERTAIN + 1),
+                at(error, UNCERTAIN + 1)
+            ),
+            UNCERTAIN,
+        ),
+        // -(-2) rather than --2
+        Expr::Neg(inner) => (format!("-{}", at(inner, NEG + 1)), NEG),
+        // The exponent may itself be negated: 2^-1
+        Expr::Binary(BinOp::Pow, base, exponent) => {
+            (format!("{}^{}", at(base, ATOM), at(exponent, NEG)), POWER)
+        }
+        Expr::Binary(op, left, right) => {
+            let level = binop_level(*op);
+            (
+                format!("{} {op} {}", at(left, level), at(right, level + 1)),
+                level,
+            )
+        }
+        Expr::Custom(op, left, right) => {
+            let level = custom_level(op.precedence);
+            (
+                format!("{} {} {}", at(left, level), op.symbol, at(right, level + 1)),
+                level,
+            )
+        }
+        Expr::Call(name, args) => {
+            let args: Vec<String> = args.iter().map(|a| at(a, 0)).collect();
+            (format!("{name}({})", args.join(", ")), ATOM)
+        }
+    }
+}
+
+/// What a node of the parse tree shows.
+fn label(expr: &Expr) -> String {
+    match expr {
+        Expr::Number(n) => Value::Number(*n).to_string(),
+        Expr::Temporal(value) => value.to_string(),
+        Expr::Variable(name) => name.clone(),
+        Expr::Result(n) => format!("${n}"),
+        Expr::Interval(..) => "[ , ]".to_string(),
+        Expr::Uncertain(..) => "±".to_string(),
+        Expr::Neg(_) => "negate".to_string(),
+        Expr::Binary(op, ..) => op.to_string(),
+        Expr::Custom(op, ..) => op.symbol.clone(),
+        Expr::Call(name, _) => format!("{name}()"),
+    }
+}
+
+fn children(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::Interval(a, b)
+        | Expr::Uncertain(a, b)
+        | Expr::Binary(_, a, b)
+        | Expr::Custom(_, a, b) => vec![a, b],
+        Expr::Neg(inner) => vec![inner],
+        Expr::Call(_, args) => args.iter().collect(),
+        Expr::Number(_) | Expr::Temporal(_) | Expr::Variable(_) | Expr::Result(_) => vec![],
+    }
+}
+
+/// The parse tree drawn with box characters, a line per node.
+pub fn tree(expr: &Expr) -> Vec<String> {
+    let mut lines = vec![label(expr)];
+    branches(expr, "", &mut lines);
+    lines
+}
+
+fn branches(expr: &Expr, indent: &str, lines: &mut Vec<String>) {
+    let children = children(expr);
+    for (i, child) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let (branch, more) = if last {
+            ("└─ ", "   ")
+        } else {
+            ("├─ ", "│  ")
+        };
+        lines.push(format!("{indent}{branch}{}", label(child)));
+        branches(child, &format!("{indent}{more}"), lines);
+    }
+}
+
+/// How an expression was worked out.
+#[derive(Debug)]
+pub struct Trace {
+    /// The expression, then after each step what's left of it:
+    /// `2 + 3 * 4`, `2 + 12`, `14`
+    pub steps: Vec<String>,
+    /// The value, or the error the step after the last one hit
+    pub result: Result<Value, String>,
+}
+
+/// Evaluate `expr`, recording the expression after each step.
+pub fn trace(expr: &Expr, env: &Environment) -> Trace {
+    let mut reduced = Reduced::new();
+    let mut steps = vec![render(expr, &reduced)];
+    let result = eval_traced(expr, env, &mut |part, value| {
+        reduced.insert(part as *const Expr, *value);
+        let step = render(expr, &reduced);
+        // Looking up a variable that's already shown by value changes
+        // nothing visible
+        if steps.last() != Some(&step) {
+            steps.push(step);
+        }
+    });
+    Trace { steps, result }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators;
+
+    fn steps(input: &str, env: &Environment) -> Vec<String> {
+        trace(&crate::parse_in(input, env).unwrap(), env).steps
+    }
+
+    #[test]
+    fn test_reduces_innermost_first() {
+        let env = Environment::new();
+        assert_eq!(steps("2 + 3 * 4", &env), ["2 + 3 * 4", "2 + 12", "14"]);
+        assert_eq!(
+            steps("(1 + 2) * -(3 - 5)", &env),
+            [
+                "(1 + 2) * -(3 - 5)",
+                "3 * -(3 - 5)",
+                "3 * -(-2)",
+                "3 * 2",
+                "6"
+            ]
+        );
+        assert_eq!(steps("2^3^2", &env), ["2^3^2", "2^9", "512"]);
+        assert_eq!(
+            steps("sqrt(16) - 10 | abs", &env),
+            ["abs(sqrt(16) - 10)", "abs(4 - 10)", "abs(-6)", "6"]
+        );
+    }
+
+    #[test]
+    fn test_variables_and_custom_operators() {
+        let mut env = Environment::new();
+        env.set("x", Value::Number(3.0));
+        let hypot = operators::parse_definition("operator <> (a, b) = sqrt(a^2 + b^2)", &env);
+        env.define_operator(hypot.unwrap().unwrap());
+        let minus = operators::parse_definition("operator ~ precedence 0 (a, b) = a - b", &env);
+        env.define_operator(minus.unwrap().unwrap());
+        assert_eq!(
+            steps("x <> 4 + 1", &env),
+            ["x <> 4 + 1", "3 <> 4 + 1", "5 + 1", "6"]
+        );
+        assert_eq!(
+            steps("2 * (x ~ 1)", &env),
+            ["2 * (x ~ 1)", "2 * (3 ~ 1)", "2 * 2", "4"]
+        );
+    }
+
+    #[test]
+    fn test_error_keeps_the_steps_so_far() {
+        let env = Environment::new();
+        let trace = trace(&crate::parse("1 + 2 / (3 - 3)").unwrap(), &env);
+        assert_eq!(trace.steps, ["1 + 2 / (3 - 3)", "1 + 2 / 0"]);
+        assert!(trace.result.is_err());
+    }
+
+    #[test]
+    fn test_tree() {
+        let expr = crate::parse("2 + 3 * sqrt(4)").unwrap();
+        assert_eq!(
+            tree(&expr),
+            ["+", "├─ 2", "└─ *", "   ├─ 3", "   └─ sqrt()", "      └─ 4"]
+        );
+    }
+}
@@ -0,0 +1,658 @@
+use crate::datetime;
+use crate::decimal::Decimal;
+use crate::parser::BinOp;
+use crate::rational::Rational;
+use crate::si;
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "ValueRepr", try_from = "ValueRepr")]
+pub enum Value {
+    Number(f64),
+    /// An exact fraction, from `:mode frac`. Mixing one with a non-integer
+    /// float gives a float.
+    Fraction(Rational),
+    /// An exact decimal, from `:mode decimal`. Floats mixed in are taken at
+    /// the decimal they print as.
+    Decimal(Decimal),
+    /// Every value between `low` and `high`, inclusive. `10 ± 0.5` is stored
+    /// as `[9.5, 10.5]`, so uncertainty propagates through plain interval
+    /// arithmetic.
+    Interval {
+        low: f64,
+        high: f64,
+    },
+    /// A calendar date, possibly with a time: `2024-03-01`, `now`
+    Date(NaiveDateTime),
+    /// A time of day, `9:30`. Arithmetic wraps around midnight.
+    Time(NaiveTime),
+    /// The difference between two dates or times, or a literal like `45 days`
+    Duration(Duration),
+}
+
+/// How a `Value` is written in JSON: tagged with its `type`, exact values
+/// as text or integers so nothing is lost to floats.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ValueRepr {
+    Number {
+        #[serde(with = "float")]
+        value: f64,
+    },
+    Fraction {
+        numer: i64,
+        denom: i64,
+    },
+    Decimal {
+        value: String,
+    },
+    Interval {
+        #[serde(with = "float")]
+        low: f64,
+        #[serde(with = "float")]
+        high: f64,
+    },
+    /// `2024-03-01T09:00:00`
+    Date {
+        value: String,
+    },
+    /// `07:30:00`
+    Time {
+        value: String,
+    },
+    Duration {
+        seconds: f64,
+    },
+}
+
+/// Floats in JSON. It has no numbers for infinity or NaN, so those are
+/// written as the strings `"inf"`, `"-inf"` and `"nan"` instead of `null`.
+pub(crate) mod float {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(x: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        match *x {
+            x if x.is_nan() => serializer.serialize_str("nan"),
+            f64::INFINITY => serializer.serialize_str("inf"),
+            f64::NEG_INFINITY => serializer.serialize_str("-inf"),
+            x => serializer.serialize_f64(x),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Float {
+            Number(f64),
+            Text(String),
+        }
+        match Float::deserialize(deserializer)? {
+            Float::Number(x) => Ok(x),
+            Float::Text(text) => match text.as_str() {
+                "nan" => Ok(f64::NAN),
+                "inf" => Ok(f64::INFINITY),
+                "-inf" => Ok(f64::NEG_INFINITY),
+                _ => Err(D::Error::custom(format!(
+                    "invalid number '{text}'; use a number, \"inf\", \"-inf\" or \"nan\""
+                ))),
+            },
+        }
+    }
+}
+
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+const TIME_FORMAT: &str = "%H:%M:%S";
+
+impl From<Value> for ValueRepr {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Number(value) => ValueRepr::Number { value },
+            Value::Fraction(r) => ValueRepr::Fraction {
+                numer: r.numer(),
+                denom: r.denom(),
+            },
+            Value::Decimal(d) => ValueRepr::Decimal {
+                value: d.to_string(),
+            },
+            Value::Interval { low, high } => ValueRepr::Interval { low, high },
+            Value::Date(date) => ValueRepr::Date {
+                value: date.format(DATE_FORMAT).to_string(),
+            },
+            Value::Time(time) => ValueRepr::Time {
+                value: time.format(TIME_FORMAT).to_string(),
+            },
+            Value::Duration(duration) => ValueRepr::Duration {
+                seconds: duration.num_milliseconds() as f64 / 1000.0,
+            },
+        }
+    }
+}
+
+impl TryFrom<ValueRepr> for Value {
+    type Error = String;
+
+    fn try_from(repr: ValueRepr) -> Result<Self, Self::Error> {
+        match repr {
+            ValueRepr::Number { value } => Ok(Value::Number(value)),
+            ValueRepr::Fraction { numer, denom } => {
+                Ok(Value::Fraction(Rational::new(numer, denom)?))
+            }
+            ValueRepr::Decimal { value } => Ok(Value::Decimal(value.parse()?)),
+            ValueRepr::Interval { low, high } => Value::interval(low, high),
+            ValueRepr::Date { value } => NaiveDateTime::parse_from_str(&value, DATE_FORMAT)
+                .map(Value::Date)
+                .map_err(|_| format!("Invalid date '{value}'; use YYYY-MM-DDTHH:MM:SS")),
+            ValueRepr::Time { value } => NaiveTime::parse_from_str(&value, TIME_FORMAT)
+                .map(Value::Time)
+                .map_err(|_| format!("Invalid time '{value}'; use HH:MM:SS")),
+            ValueRepr::Duration { seconds } => {
+                datetime::duration_from_seconds(seconds).map(Value::Duration)
+            }
+        }
+    }
+}
+
+impl Value {
+    pub fn interval(low: f64, high: f64) -> Result<Value, String> {
+        if low > high {
+            return Err(format!("Invalid interval: {low} is greater than {high}"));
+        }
+        Ok(Value::Interval { low, high })
+    }
+
+    pub fn uncertain(value: f64, error: f64) -> Result<Value, String> {
+        Value::interval(value - error.abs(), value + error.abs())
+    }
+
+    /// Lower and upper bounds; a plain number is a zero-width interval.
+    /// Dates, times, and durations have no numeric bounds and give NaN.
+    pub fn bounds(&self) -> (f64, f64) {
+        match *self {
+            Value::Number(n) => (n, n),
+            Value::Fraction(r) => (r.to_f64(), r.to_f64()),
+            Value::Decimal(d) => (d.to_f64(), d.to_f64()),
+            Value::Interval { low, high } => (low, high),
+            _ => (f64::NAN, f64::NAN),
+        }
+    }
+
+    pub fn as_number(&self) -> Result<f64, String> {
+        match *self {
+            Value::Number(n) => Ok(n),
+            Value::Fraction(r) => Ok(r.to_f64()),
+            Value::Decimal(d) => Ok(d.to_f64()),
+            _ => Err(format!("Expected a number, got {} {self}", self.kind())),
+        }
+    }
+
+    /// What kind of value this is, for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::Fraction(_) => "fraction",
+            Value::Decimal(_) => "decimal",
+            Value::Interval { .. } => "interval",
+            Value::Date(_) => "date",
+            Value::Time(_) => "time",
+            Value::Duration(_) => "duration",
+        }
+    }
+
+    fn is_temporal(&self) -> bool {
+        matches!(self, Value::Date(_) | Value::Time(_) | Value::Duration(_))
+    }
+
+    pub fn negate(&self) -> Result<Value, String> {
+        match *self {
+            Value::Number(n) => Ok(Value::Number(-n)),
+            Value::Fraction(r) => r.checked_neg().map(Value::Fraction),
+            Value::Decimal(d) => d.checked_neg().map(Value::Decimal),
+            Value::Interval { low, high } => Ok(Value::Interval {
+                low: -high,
+                high: -low,
+            }),
+            Value::Duration(d) => Ok(Value::Duration(-d)),
+            Value::Date(_) | Value::Time(_) => Err(format!("Can't negate a {}", self.kind())),
+        }
+    }
+
+    /// The exact fraction for this value, if it has one: integers count, so
+    /// `1/3 + 1` stays a fraction.
+    fn exact(&self) -> Option<Rational> {
+        match *self {
+            Value::Fraction(r) => Some(r),
+            Value::Number(n) if n.fract() == 0.0 => Rational::from_f64(n),
+            _ => None,
+        }
+    }
+
+    /// The exact decimal for this value, if it has one: any finite number
+    /// counts, so `0.1 + 0.2` stays exact after a function like `sqrt`.
+    fn exact_decimal(&self) -> Option<Decimal> {
+        match *self {
+            Value::Decimal(d) => Some(d),
+            Value::Number(n) => Decimal::from_f64(n),
+            Value::Fraction(r) if r.is_integer() => Some(Decimal::integer(r.numer())),
+            _ => None,
+        }
+    }
+
+    /// Fractions and decimals as plain numbers, for operations that can't
+    /// stay exact.
+    fn to_float(self) -> Value {
+        match self {
+            Value::Fraction(r) => Value::Number(r.to_f64()),
+            Value::Decimal(d) => Value::Number(d.to_f64()),
+            other => other,
+        }
+    }
+
+    pub fn apply(&self, op: BinOp, rhs: &Value) -> Result<Value, String> {
+        if matches!(self, Value::Decimal(_)) || matches!(rhs, Value::Decimal(_)) {
+            if let (Some(a), Some(b)) = (self.exact_decimal(), rhs.exact_decimal()) {
+                return calculate_decimal(a, op, b);
+            }
+            return self.to_float().apply(op, &rhs.to_float());
+        }
+
+        if matches!(self, Value::Fraction(_)) || matches!(rhs, Value::Fraction(_)) {
+            if let (Some(a), Some(b)) = (self.exact(), rhs.exact()) {
+                return calculate_exact(a, op, b);
+            }
+            return self.to_float().apply(op, &rhs.to_float());
+        }
+
+        if let (Value::Number(a), Value::Number(b)) = (self, rhs) {
+            return calculate(*a, op, *b).map(Value::Number);
+        }
+
+        if self.is_temporal() || rhs.is_temporal() {
+            return self.apply_temporal(op, rhs);
+        }
+
+        let (a, b) = self.bounds();
+        let (c, d) = rhs.bounds();
+
+        match op {
+            BinOp::Add => Value::interval(a + c, b + d),
+            BinOp::Sub => Value::interval(a - d, b - c),
+            BinOp::Mul => hull(&[a * c, a * d, b * c, b * d]),
+            BinOp::Div => {
+                if c <= 0.0 && d >= 0.0 {
+                    return Err("Division by an interval containing zero".to_string());
+                }
+                hull(&[a / c, a / d, b / c, b / d])
+            }
+            BinOp::Pow => interval_pow(a, b, c, d),
+            BinOp::Mod => Err("Modulo is not supported for intervals".to_string()),
+        }
+    }
+
+    /// Date and time arithmetic: dates and times move by durations, their
+    /// differences are durations, and durations scale by numbers.
+    fn apply_temporal(&self, op: BinOp, rhs: &Value) -> Result<Value, String> {
+        let overflow = || "Date out of range".to_string();
+        let duration_overflow = || "Duration out of range".to_string();
+
+        match (*self, op, *rhs) {
+            (Value::Date(d), BinOp::Add, Value::Duration(t))
+            | (Value::Duration(t), BinOp::Add, Value::Date(d)) => d
+                .checked_add_signed(t)
+                .map(Value::Date)
+                .ok_or_else(overflow),
+            (Value::Date(d), BinOp::Sub, Value::Duration(t)) => d
+                .checked_sub_signed(t)
+                .map(Value::Date)
+                .ok_or_else(overflow),
+            (Value::Date(a), BinOp::Sub, Value::Date(b)) => Ok(Value::Duration(a - b)),
+
+            (Value::Time(time), BinOp::Add, Value::Duration(t))
+            | (Value::Duration(t), BinOp::Add, Value::Time(time)) => {
+                Ok(Value::Time(time.overflowing_add_signed(t).0))
+            }
+            (Value::Time(time), BinOp::Sub, Value::Duration(t)) => {
+                Ok(Value::Time(time.overflowing_sub_signed(t).0))
+            }
+            (Value::Time(a), BinOp::Sub, Value::Time(b)) => Ok(Value::Duration(a - b)),
+
+            (Value::Duration(a), BinOp::Add, Value::Duration(b)) => a
+                .checked_add(&b)
+                .map(Value::Duration)
+                .ok_or_else(duration_overflow),
+            (Value::Duration(a), BinOp::Sub, Value::Duration(b)) => a
+                .checked_sub(&b)
+                .map(Value::Duration)
+                .ok_or_else(duration_overflow),
+            (Value::Duration(d), BinOp::Mul, Value::Number(n))
+            | (Value::Number(n), BinOp::Mul, Value::Duration(d)) => {
+                datetime::duration_from_seconds(seconds(d) * n).map(Value::Duration)
+            }
+            (Value::Duration(d), BinOp::Div, Value::Number(n)) => {
+                if n == 0.0 {
+                    return Err("Division by zero".to_string());
+                }
+                datetime::duration_from_seconds(seconds(d) / n).map(Value::Duration)
+            }
+            // How many of one duration fit in another: (now - 2024-01-01) / 1d
+            (Value::Duration(a), BinOp::Div, Value::Duration(b)) => {
+                if b.is_zero() {
+                    return Err("Division by zero".to_string());
+                }
+                Ok(Value::Number(seconds(a) / seconds(b)))
+            }
+            (Value::Number(_), BinOp::Add | BinOp::Sub, _)
+            | (_, BinOp::Add | BinOp::Sub, Value::Number(_)) => Err(format!(
+                "Can't {} a {} and a {}; use a duration like 45 days or 2h15m",
+                op_name(op),
+                self.kind(),
+                rhs.kind()
+            )),
+            _ => Err(format!(
+                "Can't {} a {} and a {}",
+                op_name(op),
+                self.kind(),
+                rhs.kind()
+            )),
+        }
+    }
+
+    /// Like `to_string`, but with SI prefixes: `4.7k ± 470 [4.23k, 5.17k]`.
+    pub fn to_si_string(&self) -> String {
+        self.format_with(si::format_si)
+    }
+
+    fn format_with(&self, format: fn(f64) -> String) -> String {
+        match *self {
+            Value::Number(n) => format(n),
+            Value::Fraction(r) => r.to_string(),
+            Value::Decimal(d) => d.to_string(),
+            Value::Interval { low, high } => format!(
+                "{} ± {} [{}, {}]",
+                format((low + high) / 2.0),
+                format((high - low) / 2.0),
+                format(low),
+                format(high)
+            ),
+            Value::Date(d) => datetime::format_date(&d),
+            Value::Time(t) => datetime::format_time(&t),
+            Value::Duration(d) => datetime::format_duration(&d),
+        }
+    }
+}
+
+fn seconds(d: Duration) -> f64 {
+    d.num_milliseconds() as f64 / 1000.0
+}
+
+fn op_name(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "add",
+        BinOp::Sub => "subtract",
+        BinOp::Mul => "multiply",
+        BinOp::Div => "divide",
+        BinOp::Pow => "raise",
+        BinOp::Mod => "take the modulo of",
+    }
+}
+
+/// Smallest interval containing every candidate endpoint.
+fn hull(candidates: &[f64]) -> Result<Value, String> {
+    let low = candidates.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = candidates.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    Value::interval(low, high)
+}
+
+fn interval_pow(a: f64, b: f64, c: f64, d: f64) -> Result<Value, String> {
+    let exponent_is_integer = c == d && c.fract() == 0.0;
+
+    if !exponent_is_integer && a < 0.0 {
+        return Err("Non-integer power of an interval with negative values".to_string());
+    }
+
+    if exponent_is_integer && c < 0.0 && a <= 0.0 && b >= 0.0 {
+        return Err("Division by an interval containing zero".to_string());
+    }
+
+    // An even power of an interval spanning zero bottoms out at zero
+    if exponent_is_integer && c % 2.0 == 0.0 && c > 0.0 && a < 0.0 && b > 0.0 {
+        return Value::interval(0.0, a.powf(c).max(b.powf(c)));
+    }
+
+    hull(&[a.powf(c), a.powf(d), b.powf(c), b.powf(d)])
+}
+
+pub fn calculate(left: f64, op: BinOp, right: f64) -> Result<f64, String> {
+    match op {
+        BinOp::Add => Ok(left + right),
+        BinOp::Sub => Ok(left - right),
+        BinOp::Mul => Ok(left * right),
+        BinOp::Div => {
+            if right == 0.0 {
+                Err("Division by zero".to_string())
+            } else {
+                Ok(left / right)
+            }
+        }
+        BinOp::Pow => Ok(left.powf(right)),
+        BinOp::Mod => Ok(left % right),
+    }
+}
+
+/// Fraction arithmetic. Powers stay exact for integer exponents; anything
+/// else falls back to floats.
+fn calculate_exact(a: Rational, op: BinOp, b: Rational) -> Result<Value, String> {
+    let result = match op {
+        BinOp::Add => a.checked_add(&b),
+        BinOp::Sub => a.checked_sub(&b),
+        BinOp::Mul => a.checked_mul(&b),
+        BinOp::Div => a.checked_div(&b),
+        BinOp::Mod => a.checked_rem(&b),
+        BinOp::Pow if b.is_integer() => a.checked_pow(b.numer()),
+        BinOp::Pow => return calculate(a.to_f64(), op, b.to_f64()).map(Value::Number),
+    };
+    result.map(Value::Fraction)
+}
+
+/// Decimal arithmetic. Powers stay exact for whole exponents; anything
+/// else falls back to floats.
+fn calculate_decimal(a: Decimal, op: BinOp, b: Decimal) -> Result<Value, String> {
+    let result = match op {
+        BinOp::Add => a.checked_add(&b),
+        BinOp::Sub => a.checked_sub(&b),
+        BinOp::Mul => a.checked_mul(&b),
+        BinOp::Div => a.checked_div(&b),
+        BinOp::Mod => a.checked_rem(&b),
+        BinOp::Pow => match b.to_i64() {
+            Some(exponent) => a.checked_pow(exponent),
+            None => return calculate(a.to_f64(), op, b.to_f64()).map(Value::Number),
+        },
+    };
+    result.map(Value::Decimal)
+}
+
+/// Format a number for display: whole numbers without a decimal point, and
+/// everything else rounded to 12 significant digits so float noise like
+/// `27.549999999999997` prints as `27.55`.
+pub fn format_number(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        return format!("{}", n as i64);
+    }
+    if !n.is_finite() {
+        return format!("{n}");
+    }
+
+    let magnitude = n.abs().log10().floor() as i32;
+    let decimals = (11 - magnitude).clamp(0, 30) as usize;
+    let text = format!("{n:.decimals$}");
+
+    if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        text
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format_with(format_number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_bounds(value: Value, low: f64, high: f64) {
+        let (a, b) = value.bounds();
+        assert!((a - low).abs() < 1e-9, "low {a} != {low}");
+        assert!((b - high).abs() < 1e-9, "high {b} != {high}");
+    }
+
+    #[test]
+    fn test_addition() {
+        assert_eq!(calculate(2.0, BinOp::Add, 3.0), Ok(5.0));
+    }
+
+    #[test]
+    fn test_subtraction() {
+        assert_eq!(calculate(10.0, BinOp::Sub, 4.0), Ok(6.0));
+    }
+
+    #[test]
+    fn test_multiplication() {
+        assert_eq!(calculate(3.0, BinOp::Mul, 7.0), Ok(21.0));
+    }
+
+    #[test]
+    fn test_division() {
+        assert_eq!(calculate(10.0, BinOp::Div, 4.0), Ok(2.5));
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        assert!(calculate(10.0, BinOp::Div, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_power() {
+        assert_eq!(calculate(2.0, BinOp::Pow, 10.0), Ok(1024.0));
+    }
+
+    #[test]
+    fn test_modulo() {
+        assert_eq!(calculate(15.0, BinOp::Mod, 4.0), Ok(3.0));
+    }
+
+    #[test]
+    fn test_uncertain_multiplication() {
+        let a = Value::uncertain(10.0, 0.5).unwrap();
+        let b = Value::uncertain(3.0, 0.1).unwrap();
+        assert_bounds(a.apply(BinOp::Mul, &b).unwrap(), 27.55, 32.55);
+    }
+
+    #[test]
+    fn test_interval_subtraction_widens() {
+        let a = Value::interval(1.0, 2.0).unwrap();
+        assert_bounds(a.apply(BinOp::Sub, &a).unwrap(), -1.0, 1.0);
+    }
+
+    #[test]
+    fn test_interval_division_by_zero_span() {
+        let a = Value::Number(1.0);
+        let b = Value::interval(-1.0, 1.0).unwrap();
+        assert!(a.apply(BinOp::Div, &b).is_err());
+    }
+
+    #[test]
+    fn test_even_power_spanning_zero() {
+        let a = Value::interval(-2.0, 3.0).unwrap();
+        assert_bounds(a.apply(BinOp::Pow, &Value::Number(2.0)).unwrap(), 0.0, 9.0);
+    }
+
+    #[test]
+    fn test_invalid_interval() {
+        assert!(Value::interval(2.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(5.0), "5");
+        assert_eq!(format_number(2.5), "2.5");
+        assert_eq!(format_number(27.549999999999997), "27.55");
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+    }
+
+    #[test]
+    fn test_si_string() {
+        let v = Value::uncertain(4700.0, 470.0).unwrap();
+        assert_eq!(v.to_si_string(), "4.7k ± 470 [4.23k, 5.17k]");
+    }
+
+    #[test]
+    fn test_date_arithmetic() {
+        let date = Value::Date(datetime::parse_date("2024-03-01").unwrap());
+        let days = Value::Duration(Duration::days(45));
+        let later = date.apply(BinOp::Add, &days).unwrap();
+        assert_eq!(later.to_string(), "Mon 2024-04-15");
+        assert_eq!(later.apply(BinOp::Sub, &date), Ok(days));
+        assert!(date.apply(BinOp::Add, &Value::Number(45.0)).is_err());
+        assert!(date.apply(BinOp::Add, &date).is_err());
+        assert!(date.negate().is_err());
+    }
+
+    #[test]
+    fn test_time_wraps_around_midnight() {
+        let time = Value::Time(datetime::parse_time("23:30").unwrap());
+        let hour = Value::Duration(Duration::hours(1));
+        assert_eq!(time.apply(BinOp::Add, &hour).unwrap().to_string(), "00:30");
+    }
+
+    #[test]
+    fn test_duration_scaling() {
+        let hour = Value::Duration(Duration::hours(1));
+        let twice = hour.apply(BinOp::Mul, &Value::Number(2.5)).unwrap();
+        assert_eq!(twice.to_string(), "2h 30m");
+        assert_eq!(twice.apply(BinOp::Div, &hour), Ok(Value::Number(2.5)));
+        assert!(hour
+            .apply(BinOp::Div, &Value::Duration(Duration::zero()))
+            .is_err());
+
+        let longest = Value::Duration(Duration::MAX);
+        assert_eq!(
+            longest.apply(BinOp::Add, &hour),
+            Err("Duration out of range".to_string())
+        );
+        assert!(hour.apply(BinOp::Mul, &Value::Number(-1e20)).is_err());
+    }
+
+    #[test]
+    fn test_fraction_arithmetic() {
+        let third = Value::Fraction(Rational::new(1, 3).unwrap());
+        let sixth = Value::Fraction(Rational::new(1, 6).unwrap());
+        assert_eq!(third.apply(BinOp::Add, &sixth).unwrap().to_string(), "1/2");
+        assert_eq!(
+            third.apply(BinOp::Mul, &Value::Number(3.0)),
+            Ok(Value::Fraction(Rational::integer(1)))
+        );
+        assert_eq!(
+            third
+                .apply(BinOp::Pow, &Value::Number(-2.0))
+                .unwrap()
+                .to_string(),
+            "9"
+        );
+        // A non-integer float makes the result a float
+        let mixed = third.apply(BinOp::Add, &Value::Number(0.5)).unwrap();
+        assert!(matches!(mixed, Value::Number(_)));
+        assert!(third.apply(BinOp::Div, &Value::Number(0.0)).is_err());
+    }
+
+    #[test]
+    fn test_display_interval() {
+        let v = Value::uncertain(10.0, 0.5).unwrap();
+        assert_eq!(v.to_string(), "10 ± 0.5 [9.5, 10.5]");
+    }
+}
@@ -0,0 +1,261 @@
+use crate::api::{Event, MAX_EVENTS};
+use chrono::{Duration, NaiveDate};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// What a user did in the last few days, from their public events, counted
+/// per repo. Every list is busiest first, ties by repo name.
+#[derive(Debug, PartialEq)]
+pub struct Activity {
+    /// The first day counted
+    pub since: NaiveDate,
+    /// Events on or after `since`, of any kind
+    pub events: usize,
+    /// Repo, pushes and commits pushed
+    pub pushes: Vec<(String, u32, u32)>,
+    pub pull_requests: Vec<(String, u32)>,
+    pub issue_comments: Vec<(String, u32)>,
+    pub releases: Vec<(String, u32)>,
+    /// Everything else by kind, e.g. ("Watch", 4)
+    pub other: Vec<(String, u32)>,
+    /// False when GitHub's feed ran out before reaching back to `since`
+    pub complete: bool,
+}
+
+fn date(event: &Event) -> Option<NaiveDate> {
+    event
+        .created_at
+        .get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+}
+
+fn ranked(counts: HashMap<String, u32>) -> Vec<(String, u32)> {
+    let mut ranked: Vec<(String, u32)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+/// Count `events` from the `days` days up to and including `today`. A push
+/// without a commit count counts as one commit, as in the heatmap.
+pub fn summarize(events: &[Event], today: NaiveDate, days: u32) -> Activity {
+    let since = today - Duration::days(i64::from(days.max(1)) - 1);
+    let recent: Vec<&Event> = events
+        .iter()
+        .filter(|e| date(e).is_some_and(|d| d >= since))
+        .collect();
+
+    let mut pushes: HashMap<String, (u32, u32)> = HashMap::new();
+    let mut pull_requests = HashMap::new();
+    let mut issue_comments = HashMap::new();
+    let mut releases = HashMap::new();
+    let mut other = HashMap::new();
+    for event in &recent {
+        let repo = event.repo.name.clone();
+        let action = event.payload.action.as_deref();
+        match (event.kind.as_str(), action) {
+            ("PushEvent", _) => {
+                let (count, commits) = pushes.entry(repo).or_default();
+                *count += 1;
+                *commits += event.payload.size.unwrap_or(1);
+            }
+            ("PullRequestEvent", Some("opened")) => *pull_requests.entry(repo).or_default() += 1,
+            ("IssueCommentEvent", Some("created")) => *issue_comments.entry(repo).or_default() += 1,
+            ("ReleaseEvent", Some("published")) => *releases.entry(repo).or_default() += 1,
+            (kind, _) => {
+                let kind = kind.strip_suffix("Event").unwrap_or(kind);
+                *other.entry(kind.to_string()).or_default() += 1
+            }
+        }
+    }
+
+    let mut pushes: Vec<(String, u32, u32)> = pushes
+        .into_iter()
+        .map(|(repo, (count, commits))| (repo, count, commits))
+        .collect();
+    pushes.sort_by(|a, b| {
+        (Reverse(a.1), Reverse(a.2), &a.0).cmp(&(Reverse(b.1), Reverse(b.2), &b.0))
+    });
+
+    // The feed is newest first; if it was cut off, its oldest event is
+    // still inside the window
+    let oldest = events.iter().filter_map(date).min();
+    Activity {
+        since,
+        events: recent.len(),
+        pushes,
+        pull_requests: ranked(pull_requests),
+        issue_comments: ranked(issue_comments),
+        releases: ranked(releases),
+        other: ranked(other),
+        complete: events.len() < MAX_EVENTS || oldest.is_none_or(|d| d < since),
+    }
+}
+
+fn plural(n: u32, word: &str) -> String {
+    if n == 1 {
+        format!("1 {word}")
+    } else {
+        format!("{n} {word}s")
+    }
+}
+
+/// A section per kind of activity, with a line per repo.
+pub fn render(activity: &Activity) -> Vec<String> {
+    let width = activity
+        .pushes
+        .iter()
+        .map(|(repo, _, _)| repo)
+        .chain(activity.pull_requests.iter().map(|(repo, _)| repo))
+        .chain(activity.issue_comments.iter().map(|(repo, _)| repo))
+        .chain(activity.releases.iter().map(|(repo, _)| repo))
+        .map(|repo| repo.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines = Vec::new();
+    let mut section = |title: String, rows: Vec<(&str, String)>| {
+        lines.push(String::new());
+        lines.push(title);
+        for (repo, detail) in rows {
+            lines.push(format!("  {repo:<width$}  {detail}"));
+        }
+    };
+
+    let pushes: u32 = activity.pushes.iter().map(|(_, n, _)| n).sum();
+    let commits: u32 = activity.pushes.iter().map(|(_, _, c)| c).sum();
+    section(
+        format!("Pushes: {pushes} ({})", plural(commits, "commit")),
+        activity
+            .pushes
+            .iter()
+            .map(|(repo, n, c)| {
+                (
+                    repo.as_str(),
+                    format!("{}, {}", plural(*n, "push"), plural(*c, "commit")),
+                )
+            })
+            .collect(),
+    );
+    let lists = [
+        ("Pull requests opened", &activity.pull_requests),
+        ("Issue comments", &activity.issue_comments),
+        ("Releases published", &activity.releases),
+    ];
+    for (title, counts) in lists {
+        let total: u32 = counts.iter().map(|(_, n)| n).sum();
+        section(
+            format!("{title}: {total}"),
+            counts
+                .iter()
+                .map(|(repo, n)| (repo.as_str(), n.to_string()))
+                .collect(),
+        );
+    }
+    if !activity.other.is_empty() {
+        let other: Vec<String> = activity
+            .other
+            .iter()
+            .map(|(kind, n)| format!("{kind} {n}"))
+            .collect();
+        section(format!("Other: {}", other.join(", ")), Vec::new());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{EventPayload, EventRepo};
+
+    fn event(kind: &str, at: &str, repo: &str, action: Option<&str>) -> Event {
+        Event {
+            kind: kind.to_string(),
+            created_at: format!("{at}T12:00:00Z"),
+            repo: EventRepo {
+                name: repo.to_string(),
+            },
+            payload: EventPayload {
+                size: (kind == "PushEvent").then_some(2),
+                action: action.map(str::to_string),
+            },
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_summarize_counts_the_window_only() {
+        let events = vec![
+            event("PushEvent", "2024-05-10", "me/app", None),
+            event("PushEvent", "2024-05-09", "me/app", None),
+            event("PushEvent", "2024-05-08", "me/lib", None),
+            event(
+                "PullRequestEvent",
+                "2024-05-08",
+                "them/tool",
+                Some("opened"),
+            ),
+            event(
+                "PullRequestEvent",
+                "2024-05-08",
+                "them/tool",
+                Some("closed"),
+            ),
+            event(
+                "IssueCommentEvent",
+                "2024-05-07",
+                "them/tool",
+                Some("created"),
+            ),
+            event("ReleaseEvent", "2024-05-06", "me/app", Some("published")),
+            event("WatchEvent", "2024-05-06", "them/other", Some("started")),
+            event("PushEvent", "2024-04-01", "me/old", None),
+        ];
+        let activity = summarize(&events, date("2024-05-10"), 7);
+        assert_eq!(activity.since, date("2024-05-04"));
+        assert_eq!(activity.events, 8);
+        assert_eq!(
+            activity.pushes,
+            [("me/app".to_string(), 2, 4), ("me/lib".to_string(), 1, 2)]
+        );
+        assert_eq!(activity.pull_requests, [("them/tool".to_string(), 1)]);
+        assert_eq!(activity.issue_comments, [("them/tool".to_string(), 1)]);
+        assert_eq!(activity.releases, [("me/app".to_string(), 1)]);
+        assert_eq!(
+            activity.other,
+            [("PullRequest".to_string(), 1), ("Watch".to_string(), 1)]
+        );
+        assert!(activity.complete);
+    }
+
+    #[test]
+    fn test_render() {
+        let events = vec![
+            event("PushEvent", "2024-05-10", "me/app", None),
+            event(
+                "IssueCommentEvent",
+                "2024-05-09",
+                "them/tool",
+                Some("created"),
+            ),
+        ];
+        let lines = render(&summarize(&events, date("2024-05-10"), 30));
+        assert_eq!(
+            lines,
+            [
+                "",
+                "Pushes: 1 (2 commits)",
+                "  me/app     1 push, 2 commits",
+                "",
+                "Pull requests opened: 0",
+                "",
+                "Issue comments: 1",
+                "  them/tool  1",
+                "",
+                "Releases published: 0",
+            ]
+        );
+    }
+}
@@ -1,59 +1,1419 @@
-use serde::Deserialize;
+use crate::cache;
+use clap::ValueEnum;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Repo {
+    /// Stable across renames. Missing from caches written by older versions.
+    #[serde(default)]
+    pub id: u64,
     pub name: String,
     pub stargazers_count: u32,
+    pub forks_count: u32,
+    pub open_issues_count: u32,
+    pub updated_at: String,
+    /// Missing from caches written by older versions
+    #[serde(default)]
+    pub created_at: String,
+    pub license: Option<License>,
     pub language: Option<String>,
     pub description: Option<String>,
     pub fork: bool,
     pub html_url: String,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub archived: bool,
+    /// Missing from caches written by older versions
+    #[serde(default)]
+    pub default_branch: String,
 }
 
-pub async fn fetch_repos(username: &str) -> Result<Vec<Repo>, String> {
-    let client = reqwest::Client::new();
-    let mut all_repos: Vec<Repo> = Vec::new();
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct License {
+    pub spdx_id: Option<String>,
+}
+
+/// A plain repo for tests to build on, changing what they look at with
+/// `Repo { ..test_repo(...) }`.
+#[cfg(test)]
+pub fn test_repo(name: &str, stars: u32, language: Option<&str>) -> Repo {
+    Repo {
+        id: 0,
+        name: name.to_string(),
+        stargazers_count: stars,
+        forks_count: 0,
+        open_issues_count: 0,
+        updated_at: "2024-05-01T00:00:00Z".to_string(),
+        created_at: String::new(),
+        license: None,
+        language: language.map(str::to_string),
+        description: None,
+        fork: false,
+        html_url: format!("https://github.com/me/{name}"),
+        topics: Vec::new(),
+        archived: false,
+        default_branch: String::new(),
+    }
+}
+
+/// A license with this SPDX id, for tests.
+#[cfg(test)]
+pub fn test_license(id: &str) -> Option<License> {
+    Some(License {
+        spdx_id: Some(id.to_string()),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Release {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub published_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Stargazer {
+    pub starred_at: String,
+}
+
+/// One entry from a user's public activity feed, with only what the
+/// heatmap and `events` summary look at.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Event {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub created_at: String,
+    /// Missing from events cached before it was kept
+    #[serde(default)]
+    pub repo: EventRepo,
+    #[serde(default)]
+    pub payload: EventPayload,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct EventRepo {
+    /// As owner/name
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct EventPayload {
+    /// Commits in a push. Not every payload has it.
+    #[serde(default)]
+    pub size: Option<u32>,
+    /// What happened, e.g. "opened" for a pull request or "published" for
+    /// a release
+    #[serde(default)]
+    pub action: Option<String>,
+}
+
+/// An organization member. The member listing only has `login`;
+/// `public_repos` comes from each member's profile.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Member {
+    pub login: String,
+    #[serde(default)]
+    pub public_repos: u32,
+}
+
+/// A team within an organization. Like `Member`, the count only comes with
+/// the team's own endpoint, not the listing.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Team {
+    pub name: String,
+    pub slug: String,
+    #[serde(default)]
+    pub members_count: u32,
+}
+
+/// The SPDX document returned by the dependency-graph SBOM endpoint. Only
+/// the parts needed to tell direct dependencies apart are kept.
+#[derive(Debug, Deserialize)]
+pub struct Sbom {
+    pub packages: Vec<SbomPackage>,
+    #[serde(default)]
+    pub relationships: Vec<SbomRelationship>,
+    #[serde(rename = "documentDescribes", default)]
+    pub document_describes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SbomPackage {
+    #[serde(rename = "SPDXID")]
+    pub spdx_id: String,
+    pub name: String,
+    #[serde(rename = "externalRefs", default)]
+    pub external_refs: Vec<ExternalRef>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExternalRef {
+    #[serde(rename = "referenceType")]
+    pub reference_type: String,
+    /// A package URL like "pkg:cargo/serde@1.0.200"
+    #[serde(rename = "referenceLocator")]
+    pub reference_locator: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SbomRelationship {
+    #[serde(rename = "relationshipType")]
+    pub relationship_type: String,
+    #[serde(rename = "spdxElementId")]
+    pub spdx_element_id: String,
+    #[serde(rename = "relatedSpdxElement")]
+    pub related_spdx_element: String,
+}
+
+/// A repo's community profile. Only which files it found matters here.
+#[derive(Debug, Deserialize, Default)]
+pub struct CommunityProfile {
+    #[serde(default)]
+    pub files: CommunityFiles,
+}
+
+/// Each is set when the repo has that file
+#[derive(Debug, Deserialize, Default)]
+pub struct CommunityFiles {
+    pub license: Option<serde_json::Value>,
+    pub contributing: Option<serde_json::Value>,
+    pub code_of_conduct: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Commit {
+    pub commit: CommitDetails,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommitDetails {
+    pub committer: Option<CommitPerson>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommitPerson {
+    pub date: String,
+}
+
+/// An issue or pull request from a repo's issue listing.
+#[derive(Debug, Deserialize)]
+pub struct RepoIssue {
+    pub closed_at: Option<String>,
+    #[serde(default)]
+    pub comments: u32,
+    /// Set on pull requests
+    pub pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkflowRuns {
+    pub workflow_runs: Vec<WorkflowRun>,
+}
+
+/// One GitHub Actions run. `conclusion` is unset until the run completes,
+/// then one of success, failure, cancelled, skipped, timed_out, ...
+#[derive(Debug, Deserialize)]
+pub struct WorkflowRun {
+    pub status: Option<String>,
+    pub conclusion: Option<String>,
+}
+
+/// Contributors are only counted, so nothing about them is kept.
+#[derive(Debug, Deserialize)]
+pub struct Contributor {}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct User {
+    pub login: String,
+}
+
+/// A repo of the authenticated user, with what `attention` needs that the
+/// public listing in `Repo` doesn't have.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OwnedRepo {
+    pub full_name: String,
+    pub default_branch: String,
+    pub fork: bool,
+    pub archived: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequest {
+    pub number: u32,
+    pub title: String,
+    pub user: User,
+    pub created_at: String,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+/// An open issue or pull request, with who opened it and when.
+#[derive(Debug, Deserialize)]
+pub struct OpenIssue {
+    pub number: u32,
+    pub title: String,
+    pub user: User,
+    pub created_at: String,
+    pub comments: u32,
+    /// Set on pull requests
+    pub pull_request: Option<serde_json::Value>,
+}
+
+/// The combined commit status: one entry per external CI context.
+#[derive(Debug, Deserialize)]
+pub struct CombinedStatus {
+    pub statuses: Vec<CommitStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommitStatus {
+    pub context: String,
+    /// "success", "pending", "failure" or "error"
+    pub state: String,
+}
+
+/// The check runs on a commit, which is where GitHub Actions reports.
+#[derive(Debug, Deserialize)]
+pub struct CheckRuns {
+    pub check_runs: Vec<CheckRun>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckRun {
+    pub name: String,
+    /// Set once the run has completed: "success", "failure", "timed_out", ...
+    pub conclusion: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SbomResponse {
+    sbom: Sbom,
+}
+
+pub const USER_AGENT: &str = "github-stats-rust-cli";
+
+/// An HTTP response with only what the rest of this module looks at. This
+/// is also the fixture format `--record` writes and `--replay` reads.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub url: String,
+    pub status: u16,
+    /// The `Link` header, for pagination
+    #[serde(default)]
+    pub link: Option<String>,
+    /// The `x-ratelimit-remaining` header
+    #[serde(default)]
+    pub rate_limit_remaining: Option<u32>,
+    /// The `x-ratelimit-reset` header: when the limit resets, in Unix seconds
+    #[serde(default)]
+    pub rate_limit_reset: Option<i64>,
+    pub body: String,
+}
+
+/// Where REST responses come from. Every request in this module goes
+/// through one, so `--replay` and the tests run without the network.
+#[derive(Clone)]
+pub enum Transport {
+    /// Straight to GitHub
+    Live(reqwest::Client),
+    /// Straight to GitHub, also saving every response as a fixture in the
+    /// directory
+    Record(reqwest::Client, PathBuf),
+    /// Only responses saved by `Record`; a request without one fails
+    Replay(PathBuf),
+}
+
+impl Transport {
+    pub fn live() -> Transport {
+        Transport::Live(reqwest::Client::new())
+    }
+
+    /// Replayed responses are fixtures, not GitHub's data, so they stay out
+    /// of the cache and checkpoints.
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Transport::Replay(_))
+    }
+
+    async fn get(&self, url: &str, accept: &str, token: Option<&str>) -> Result<Response, String> {
+        self.send(url, token, |client| {
+            client.get(url).header("Accept", accept)
+        })
+        .await
+    }
+
+    /// POST `body` as JSON. Replayed and recorded by URL, like a GET.
+    async fn post(
+        &self,
+        url: &str,
+        body: &serde_json::Value,
+        token: &str,
+    ) -> Result<Response, String> {
+        self.send(url, Some(token), |client| {
+            client
+                .post(url)
+                .header("Accept", "application/vnd.github+json")
+                .json(body)
+        })
+        .await
+    }
+
+    async fn send(
+        &self,
+        url: &str,
+        token: Option<&str>,
+        build: impl FnOnce(&reqwest::Client) -> reqwest::RequestBuilder,
+    ) -> Result<Response, String> {
+        let (client, record) = match self {
+            Transport::Live(client) => (client, None),
+            Transport::Record(client, dir) => (client, Some(dir)),
+            Transport::Replay(dir) => return replay(dir, url),
+        };
+
+        let mut request = build(client).header("User-Agent", USER_AGENT);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {e}"))?;
+
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|h| h.to_str().ok())
+                .map(str::to_string)
+        };
+        let status = response.status().as_u16();
+        let link = header("link");
+        let rate_limit_remaining = header("x-ratelimit-remaining").and_then(|h| h.parse().ok());
+        let rate_limit_reset = header("x-ratelimit-reset").and_then(|h| h.parse().ok());
+        let body = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read response: {e}"))?;
+
+        let response = Response {
+            url: url.to_string(),
+            status,
+            link,
+            rate_limit_remaining,
+            rate_limit_reset,
+            body,
+        };
+        if let Some(dir) = record {
+            save_fixture(dir, &response)?;
+        }
+        Ok(response)
+    }
+}
+
+/// Fixtures are named after the URL without the API host, made
+/// filename-safe: `users_octocat_repos_per_page_100.json`.
+fn fixture_path(dir: &Path, url: &str) -> PathBuf {
+    let path = url.strip_prefix("https://api.github.com/").unwrap_or(url);
+    let safe: String = path
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{safe}.json"))
+}
+
+fn save_fixture(dir: &Path, response: &Response) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    let path = fixture_path(dir, &response.url);
+    let json = serde_json::to_string_pretty(response)
+        .map_err(|e| format!("Failed to serialize fixture: {e}"))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+fn replay(dir: &Path, url: &str) -> Result<Response, String> {
+    let path = fixture_path(dir, url);
+    let content = fs::read_to_string(&path).map_err(|_| {
+        format!(
+            "No fixture for {url} in {}; record one with --record {}",
+            dir.display(),
+            dir.display()
+        )
+    })?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+}
+
+/// GET `url` and parse the JSON body. `not_found` is the error to report
+/// for a 404.
+async fn get_json<T: DeserializeOwned>(
+    transport: &Transport,
+    url: &str,
+    accept: &str,
+    not_found: &str,
+    token: Option<&str>,
+) -> Result<T, String> {
+    let (data, _) = get_json_with_links(transport, url, accept, not_found, token).await?;
+    Ok(data)
+}
+
+/// The pagination links GitHub sends in the `Link` header.
+#[derive(Debug, Default, PartialEq)]
+pub struct PageLinks {
+    pub next: Option<String>,
+    /// The page number of the `last` link: how many pages there are
+    pub last_page: Option<u32>,
+}
+
+/// Parse a header like `<https://...&page=2>; rel="next", <https://...&page=9>; rel="last"`.
+pub fn parse_links(header: &str) -> PageLinks {
+    let mut links = PageLinks::default();
+    for part in header.split(',') {
+        let Some((url, params)) = part.split_once(';') else {
+            continue;
+        };
+        let url = url.trim().trim_start_matches('<').trim_end_matches('>');
+        let rel = params
+            .split(';')
+            .filter_map(|p| p.trim().strip_prefix("rel="))
+            .map(|r| r.trim_matches('"'))
+            .next();
+        match rel {
+            Some("next") => links.next = Some(url.to_string()),
+            Some("last") => links.last_page = page_number(url),
+            _ => {}
+        }
+    }
+    links
+}
+
+fn page_number(url: &str) -> Option<u32> {
+    let (_, query) = url.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("page="))
+        .and_then(|n| n.parse().ok())
+}
+
+/// `get_json`, also returning the pagination links.
+async fn get_json_with_links<T: DeserializeOwned>(
+    transport: &Transport,
+    url: &str,
+    accept: &str,
+    not_found: &str,
+    token: Option<&str>,
+) -> Result<(T, PageLinks), String> {
+    let response = transport.get(url, accept, token).await?;
+    check_status(&response, not_found)?;
+
+    let links = response
+        .link
+        .as_deref()
+        .map(parse_links)
+        .unwrap_or_default();
+    let data = serde_json::from_str(&response.body)
+        .map_err(|e| format!("Failed to parse response: {e}"))?;
+    Ok((data, links))
+}
+
+fn check_status(response: &Response, not_found: &str) -> Result<(), String> {
+    let status = response.status;
+    if status == 404 {
+        return Err(not_found.to_string());
+    }
+
+    // GitHub answers 403 (or 429) with no requests left when rate limited
+    if matches!(status, 403 | 429) && response.rate_limit_remaining == Some(0) {
+        let resets = response
+            .rate_limit_reset
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .map(|at| {
+                let local = at.with_timezone(&chrono::Local);
+                format!("; it resets at {}", local.format("%H:%M"))
+            })
+            .unwrap_or_default();
+        return Err(format!("GitHub API rate limit exceeded{resets}"));
+    }
+
+    if !(200..300).contains(&status) {
+        let status = reqwest::StatusCode::from_u16(status)
+            .map(|s| s.to_string())
+            .unwrap_or_else(|_| status.to_string());
+        return Err(format!("GitHub API error: {status} {}", response.body));
+    }
+    Ok(())
+}
+
+/// How far `get_all_pages` got, saved after every page so an interrupted
+/// fetch can carry on with `--resume`. The items fetched so far are kept
+/// apart, appended a line per page, so each save stays small.
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    /// The URL of the next page to fetch
+    next: String,
+    /// Its page number, counting from 1
+    page: u32,
+}
+
+/// GET every page of a listing, 100 items at a time, following the `next`
+/// links. Progress is checkpointed in the cache under `checkpoint`; with
+/// `resume`, a fetch that was cut short (Ctrl-C, a rate limit) picks up at
+/// the page it stopped on. Replayed fetches aren't checkpointed.
+async fn get_all_pages<T: Serialize + DeserializeOwned>(
+    transport: &Transport,
+    url: &str,
+    accept: &str,
+    not_found: &str,
+    token: Option<&str>,
+    checkpoint: &str,
+    resume: bool,
+) -> Result<Vec<T>, String> {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let mut next = format!("{url}{separator}per_page=100");
     let mut page = 1;
+    let mut all: Vec<T> = Vec::new();
+    let checkpointing = !transport.is_replay();
+    let pages = format!("{checkpoint}-pages");
+
+    if resume && checkpointing {
+        let saved = cache::load::<Checkpoint>(checkpoint)?.map(|saved| saved.data);
+        let mut fetched: Vec<Vec<T>> = cache::load_lines(&pages)?;
+        match saved {
+            // Pages count from 1, so a checkpoint at page 0 is corrupt
+            Some(saved) if saved.page >= 1 && fetched.len() >= saved.page as usize - 1 => {
+                // A page appended without the checkpoint after it is fetched
+                // again, so it mustn't stay in the file too
+                if fetched.len() > saved.page as usize - 1 {
+                    fetched.truncate(saved.page as usize - 1);
+                    cache::remove_lines(&pages)?;
+                    for items in &fetched {
+                        cache::append_line(&pages, items)?;
+                    }
+                }
+                all = fetched.into_iter().flatten().collect();
+                eprintln!(
+                    "Resuming at page {} with {} fetched already",
+                    saved.page,
+                    all.len()
+                );
+                (next, page) = (saved.next, saved.page);
+            }
+            _ => eprintln!("Nothing to resume; starting from the first page"),
+        }
+    }
+    if checkpointing && page == 1 {
+        cache::remove_lines(&pages)?;
+    }
 
     loop {
+        let fetched = tokio::select! {
+            result = get_json_with_links::<Vec<T>>(transport, &next, accept, not_found, token) => result,
+            _ = tokio::signal::ctrl_c() => Err("Interrupted".to_string()),
+        };
+        let (items, links) = fetched.map_err(|e| {
+            if page > 1 {
+                format!(
+                    "{e}\nStopped at page {page}; run again with --resume to continue from there"
+                )
+            } else {
+                e
+            }
+        })?;
+
+        if let Some(last) = links.last_page.filter(|&last| last > 1) {
+            eprintln!("  Fetched page {page} of {last}");
+        }
+        let done = items.is_empty();
+        let Some(url) = links.next.filter(|_| !done) else {
+            all.extend(items);
+            break;
+        };
+        next = url;
+        page += 1;
+        if checkpointing {
+            let saved = cache::append_line(&pages, &items).and_then(|()| {
+                let progress = Checkpoint {
+                    next: next.clone(),
+                    page,
+                };
+                cache::save(checkpoint, &progress)
+            });
+            // Like the cache itself, a failed write shouldn't stop the fetch
+            if let Err(e) = saved {
+                eprintln!("Warning: {e}");
+            }
+        }
+        all.extend(items);
+    }
+
+    if checkpointing {
+        cache::remove(checkpoint)?;
+        cache::remove_lines(&pages)?;
+    }
+    Ok(all)
+}
+
+pub async fn fetch_repos(
+    transport: &Transport,
+    username: &str,
+    resume: bool,
+) -> Result<Vec<Repo>, String> {
+    let url = format!("https://api.github.com/users/{username}/repos?sort=stars&direction=desc");
+
+    get_all_pages(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("User '{username}' not found"),
+        None,
+        &format!("checkpoint-repos-{username}"),
+        resume,
+    )
+    .await
+}
+
+/// Fetch every stargazer of `repo` ("owner/name") along with when they
+/// starred it. The star+json media type is what adds `starred_at`.
+pub async fn fetch_stargazers(
+    transport: &Transport,
+    repo: &str,
+    resume: bool,
+) -> Result<Vec<Stargazer>, String> {
+    let url = format!("https://api.github.com/repos/{repo}/stargazers");
+
+    get_all_pages(
+        transport,
+        &url,
+        "application/vnd.github.star+json",
+        &format!("Repository '{repo}' not found"),
+        None,
+        &format!("checkpoint-stars-{repo}"),
+        resume,
+    )
+    .await
+}
+
+/// The events API serves at most this many pages of 100, and errors past it.
+const MAX_EVENT_PAGES: u32 = 3;
+
+/// The most events `fetch_events` can return
+pub const MAX_EVENTS: usize = MAX_EVENT_PAGES as usize * 100;
+
+/// A user's recent public events, newest first. GitHub only keeps the last
+/// 90 days and 300 events.
+pub async fn fetch_events(transport: &Transport, username: &str) -> Result<Vec<Event>, String> {
+    let mut all: Vec<Event> = Vec::new();
+
+    for page in 1..=MAX_EVENT_PAGES {
         let url = format!(
-            "https://api.github.com/users/{username}/repos?per_page=100&page={page}&sort=stars&direction=desc"
+            "https://api.github.com/users/{username}/events/public?per_page=100&page={page}"
         );
+        let events: Vec<Event> = get_json(
+            transport,
+            &url,
+            "application/vnd.github+json",
+            &format!("User '{username}' not found"),
+            None,
+        )
+        .await?;
 
-        let response = client
-            .get(&url)
-            .header("User-Agent", "github-stats-rust-cli")
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {e}"))?;
+        if events.is_empty() {
+            break;
+        }
+        all.extend(events);
+    }
+
+    Ok(all)
+}
+
+/// Every member of `org` with their public repo count, which takes one
+/// extra request per member.
+pub async fn fetch_members(
+    transport: &Transport,
+    org: &str,
+    token: &str,
+    resume: bool,
+) -> Result<Vec<Member>, String> {
+    let url = format!("https://api.github.com/orgs/{org}/members");
+
+    let listed: Vec<Member> = get_all_pages(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("Organization '{org}' not found"),
+        Some(token),
+        &format!("checkpoint-members-{org}"),
+        resume,
+    )
+    .await?;
+
+    let mut members = Vec::new();
+    for member in listed {
+        let url = format!("https://api.github.com/users/{}", member.login);
+        let not_found = format!("User '{}' not found", member.login);
+        members.push(
+            get_json(
+                transport,
+                &url,
+                "application/vnd.github+json",
+                &not_found,
+                Some(token),
+            )
+            .await?,
+        );
+    }
+
+    Ok(members)
+}
+
+/// Every team in `org` with its member count, which takes one extra request
+/// per team. Listing teams needs a token with the read:org scope.
+pub async fn fetch_teams(
+    transport: &Transport,
+    org: &str,
+    token: &str,
+    resume: bool,
+) -> Result<Vec<Team>, String> {
+    let url = format!("https://api.github.com/orgs/{org}/teams");
+
+    let listed: Vec<Team> = get_all_pages(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("Organization '{org}' not found, or the token can't read its teams"),
+        Some(token),
+        &format!("checkpoint-teams-{org}"),
+        resume,
+    )
+    .await?;
+
+    let mut teams = Vec::new();
+    for team in listed {
+        let url = format!("https://api.github.com/orgs/{org}/teams/{}", team.slug);
+        let not_found = format!("Team '{}' not found", team.slug);
+        teams.push(
+            get_json(
+                transport,
+                &url,
+                "application/vnd.github+json",
+                &not_found,
+                Some(token),
+            )
+            .await?,
+        );
+    }
+
+    Ok(teams)
+}
+
+/// Fetch the dependency-graph SBOM for `repo` ("owner/name"). Fails with a
+/// 404 when the dependency graph is disabled for the repository.
+pub async fn fetch_sbom(transport: &Transport, repo: &str) -> Result<Sbom, String> {
+    let url = format!("https://api.github.com/repos/{repo}/dependency-graph/sbom");
+
+    let response: SbomResponse = get_json(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("No dependency graph for '{repo}'"),
+        None,
+    )
+    .await?;
+
+    Ok(response.sbom)
+}
+
+/// The most recent releases of `repo` ("owner/name"), newest first.
+pub async fn fetch_releases(
+    transport: &Transport,
+    repo: &str,
+    count: usize,
+    token: Option<&str>,
+) -> Result<Vec<Release>, String> {
+    let url = format!("https://api.github.com/repos/{repo}/releases?per_page={count}");
+
+    get_json(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("Repository '{repo}' not found"),
+        token,
+    )
+    .await
+}
+
+/// Which community files `repo` ("owner/name") has: license, contributing
+/// guide, code of conduct.
+pub async fn fetch_community_profile(
+    transport: &Transport,
+    repo: &str,
+    token: Option<&str>,
+) -> Result<CommunityProfile, String> {
+    let url = format!("https://api.github.com/repos/{repo}/community/profile");
+
+    get_json(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("Repository '{repo}' not found"),
+        token,
+    )
+    .await
+}
+
+/// The latest commit on the default branch of `repo`, if it has any. An
+/// empty repository answers 409 rather than an empty list.
+pub async fn fetch_latest_commit(
+    transport: &Transport,
+    repo: &str,
+    token: Option<&str>,
+) -> Result<Option<Commit>, String> {
+    let url = format!("https://api.github.com/repos/{repo}/commits?per_page=1");
+
+    let response = transport
+        .get(&url, "application/vnd.github+json", token)
+        .await?;
+    if response.status == 409 {
+        return Ok(None);
+    }
+    check_status(&response, &format!("Repository '{repo}' not found"))?;
+    let commits: Vec<Commit> = serde_json::from_str(&response.body)
+        .map_err(|e| format!("Failed to parse response: {e}"))?;
+    Ok(commits.into_iter().next())
+}
+
+/// The `count` most recently opened issues of `repo`, open or closed. Pull
+/// requests come along too; callers tell them apart by `pull_request`.
+pub async fn fetch_recent_issues(
+    transport: &Transport,
+    repo: &str,
+    count: usize,
+    token: Option<&str>,
+) -> Result<Vec<RepoIssue>, String> {
+    let url = format!("https://api.github.com/repos/{repo}/issues?state=all&per_page={count}");
+
+    get_json(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("Repository '{repo}' not found"),
+        token,
+    )
+    .await
+}
+
+/// Up to 100 contributors of `repo`, most active first. An empty repository
+/// answers 204 with no body.
+pub async fn fetch_contributors(
+    transport: &Transport,
+    repo: &str,
+    token: Option<&str>,
+) -> Result<Vec<Contributor>, String> {
+    let url = format!("https://api.github.com/repos/{repo}/contributors?per_page=100");
+
+    let response = transport
+        .get(&url, "application/vnd.github+json", token)
+        .await?;
+    check_status(&response, &format!("Repository '{repo}' not found"))?;
+    if response.body.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&response.body).map_err(|e| format!("Failed to parse response: {e}"))
+}
+
+/// The `count` most recent GitHub Actions runs on `branch` of `repo`,
+/// newest first. An empty `branch` means runs on any branch.
+pub async fn fetch_workflow_runs(
+    transport: &Transport,
+    repo: &str,
+    branch: &str,
+    count: usize,
+    token: Option<&str>,
+) -> Result<Vec<WorkflowRun>, String> {
+    let filter = if branch.is_empty() {
+        String::new()
+    } else {
+        format!("branch={branch}&")
+    };
+    let url = format!("https://api.github.com/repos/{repo}/actions/runs?{filter}per_page={count}");
+
+    let runs: WorkflowRuns = get_json(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("Repository '{repo}' not found"),
+        token,
+    )
+    .await?;
+    Ok(runs.workflow_runs)
+}
+
+/// Who `token` belongs to.
+pub async fn fetch_authenticated_user(transport: &Transport, token: &str) -> Result<User, String> {
+    get_json(
+        transport,
+        "https://api.github.com/user",
+        "application/vnd.github+json",
+        "The token doesn't belong to a user",
+        Some(token),
+    )
+    .await
+}
+
+/// Every repo the token's user owns, private ones included.
+pub async fn fetch_owned_repos(
+    transport: &Transport,
+    token: &str,
+    resume: bool,
+) -> Result<Vec<OwnedRepo>, String> {
+    get_all_pages(
+        transport,
+        "https://api.github.com/user/repos?affiliation=owner",
+        "application/vnd.github+json",
+        "The token doesn't belong to a user",
+        Some(token),
+        "checkpoint-owned-repos",
+        resume,
+    )
+    .await
+}
+
+/// Up to 100 open pull requests of `repo`, oldest first.
+pub async fn fetch_open_pull_requests(
+    transport: &Transport,
+    repo: &str,
+    token: &str,
+) -> Result<Vec<PullRequest>, String> {
+    let url = format!(
+        "https://api.github.com/repos/{repo}/pulls?state=open&sort=created&direction=asc&per_page=100"
+    );
+
+    get_json(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("Repository '{repo}' not found"),
+        Some(token),
+    )
+    .await
+}
+
+/// Up to 100 open issues of `repo`, oldest first. Pull requests come along
+/// too; callers tell them apart by `pull_request`.
+pub async fn fetch_open_issues(
+    transport: &Transport,
+    repo: &str,
+    token: &str,
+) -> Result<Vec<OpenIssue>, String> {
+    let url = format!(
+        "https://api.github.com/repos/{repo}/issues?state=open&sort=created&direction=asc&per_page=100"
+    );
+
+    get_json(
+        transport,
+        &url,
+        "application/vnd.github+json",
+        &format!("Repository '{repo}' not found"),
+        Some(token),
+    )
+    .await
+}
+
+/// The commit statuses and check runs on the tip of `branch`. An empty
+/// repository has no commit to look at and answers 409 (or 404 or 422),
+/// which counts as no checks at all.
+pub async fn fetch_branch_checks(
+    transport: &Transport,
+    repo: &str,
+    branch: &str,
+    token: &str,
+) -> Result<(CombinedStatus, CheckRuns), String> {
+    let base = format!("https://api.github.com/repos/{repo}/commits/{branch}");
+    let status_url = format!("{base}/status?per_page=100");
+    let status = transport
+        .get(&status_url, "application/vnd.github+json", Some(token))
+        .await?;
+    if matches!(status.status, 404 | 409 | 422) {
+        let none = (
+            CombinedStatus {
+                statuses: Vec::new(),
+            },
+            CheckRuns {
+                check_runs: Vec::new(),
+            },
+        );
+        return Ok(none);
+    }
+    check_status(&status, &format!("Repository '{repo}' not found"))?;
+    let status: CombinedStatus =
+        serde_json::from_str(&status.body).map_err(|e| format!("Failed to parse response: {e}"))?;
+
+    let runs = get_json(
+        transport,
+        &format!("{base}/check-runs?per_page=100"),
+        "application/vnd.github+json",
+        &format!("Repository '{repo}' not found"),
+        Some(token),
+    )
+    .await?;
+    Ok((status, runs))
+}
+
+/// The search API stops at this many results for any query
+pub const MAX_SEARCH_RESULTS: usize = 1000;
 
-        if response.status() == reqwest::StatusCode::NOT_FOUND {
-            return Err(format!("User '{username}' not found"));
+/// Search resets its budget every minute, so no wait is longer than this
+const MAX_SEARCH_WAIT: u64 = 61;
+
+/// How `search` orders the repos it finds
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SearchSort {
+    Stars,
+    Forks,
+    Updated,
+    /// GitHub's own relevance ranking
+    BestMatch,
+}
+
+impl SearchSort {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            SearchSort::Stars => "stars",
+            SearchSort::Forks => "forks",
+            SearchSort::Updated => "updated",
+            SearchSort::BestMatch => "best match",
         }
+    }
+}
+
+/// Repos found by a search, at most the number asked for.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResults {
+    /// Everything that matched, not just the repos fetched
+    pub total_count: u64,
+    /// GitHub gave up on part of the search before it finished
+    pub incomplete: bool,
+    /// Named "owner/name", since they come from anyone
+    pub repos: Vec<Repo>,
+}
+
+#[derive(Deserialize)]
+struct SearchPage {
+    total_count: u64,
+    #[serde(default)]
+    incomplete_results: bool,
+    items: Vec<SearchItem>,
+}
+
+#[derive(Deserialize)]
+struct SearchItem {
+    full_name: String,
+    #[serde(flatten)]
+    repo: Repo,
+}
+
+fn search_url(query: &str, sort: SearchSort, per_page: usize, page: usize) -> String {
+    let mut params = vec![("q", query.to_string())];
+    if sort != SearchSort::BestMatch {
+        params.push(("sort", sort.describe().to_string()));
+        params.push(("order", "desc".to_string()));
+    }
+    params.push(("per_page", per_page.to_string()));
+    params.push(("page", page.to_string()));
+    reqwest::Url::parse_with_params("https://api.github.com/search/repositories", &params)
+        .map(String::from)
+        .unwrap_or_default()
+}
+
+/// How long to wait before the next search request, if `response` used up
+/// the search budget: until it resets, as of `now` in Unix seconds.
+fn search_wait(response: &Response, now: i64) -> Option<Duration> {
+    if response.rate_limit_remaining != Some(0) {
+        return None;
+    }
+    let until_reset = response
+        .rate_limit_reset
+        .map_or(MAX_SEARCH_WAIT as i64, |at| at - now);
+    Some(Duration::from_secs(
+        until_reset.clamp(1, MAX_SEARCH_WAIT as i64) as u64,
+    ))
+}
+
+/// Repos matching `query`, in GitHub's search syntax ("language:rust
+/// stars:>500"), up to `limit` of them. Search has its own budget of 10
+/// requests a minute, or 30 with a token, separate from the rest of the
+/// API; when it runs out this waits for it to reset rather than failing.
+pub async fn search_repos(
+    transport: &Transport,
+    query: &str,
+    sort: SearchSort,
+    limit: usize,
+    token: Option<&str>,
+) -> Result<SearchResults, String> {
+    let limit = limit.min(MAX_SEARCH_RESULTS);
+    let per_page = limit.clamp(1, 100);
+    let mut results = SearchResults {
+        total_count: 0,
+        incomplete: false,
+        repos: Vec::new(),
+    };
 
-        if !response.status().is_success() {
+    let mut page = 1;
+    while results.repos.len() < limit {
+        let url = search_url(query, sort, per_page, page);
+        let mut response = transport
+            .get(&url, "application/vnd.github+json", token)
+            .await?;
+        if matches!(response.status, 403 | 429) {
+            // Out of searches: wait for the budget to reset and try once more
+            if let Some(wait) = search_wait(&response, chrono::Utc::now().timestamp()) {
+                eprintln!(
+                    "  Search rate limit reached; waiting {}s for it to reset",
+                    wait.as_secs()
+                );
+                tokio::time::sleep(wait).await;
+                response = transport
+                    .get(&url, "application/vnd.github+json", token)
+                    .await?;
+            }
+        }
+        if response.status == 422 {
             return Err(format!(
-                "GitHub API error: {} {}",
-                response.status(),
-                response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "unknown error".to_string())
+                "GitHub couldn't search for '{query}': {}",
+                response.body
             ));
         }
-
-        let repos: Vec<Repo> = response
-            .json()
-            .await
+        check_status(&response, "The search API wasn't found")?;
+        let found: SearchPage = serde_json::from_str(&response.body)
             .map_err(|e| format!("Failed to parse response: {e}"))?;
 
-        if repos.is_empty() {
+        results.total_count = found.total_count;
+        results.incomplete |= found.incomplete_results;
+        let last = found.items.len() < per_page;
+        results
+            .repos
+            .extend(found.items.into_iter().map(|item| Repo {
+                name: item.full_name,
+                ..item.repo
+            }));
+        let pages = (found.total_count as usize)
+            .min(MAX_SEARCH_RESULTS)
+            .div_ceil(per_page);
+        if pages > 1 {
+            eprintln!(
+                "  Fetched page {page} of {}",
+                pages.min(limit.div_ceil(per_page))
+            );
+        }
+        if last || page >= pages {
             break;
         }
 
-        all_repos.extend(repos);
         page += 1;
+        if let Some(wait) = search_wait(&response, chrono::Utc::now().timestamp()) {
+            eprintln!(
+                "  Search rate limit reached; waiting {}s for it to reset",
+                wait.as_secs()
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    results.repos.truncate(limit);
+    Ok(results)
+}
+
+#[derive(Deserialize)]
+struct Gist {
+    html_url: String,
+}
+
+/// Upload `content` as a secret gist holding one file, `filename`, and
+/// return its URL. The token needs the gist scope.
+pub async fn create_gist(
+    transport: &Transport,
+    filename: &str,
+    description: &str,
+    content: &str,
+    token: &str,
+) -> Result<String, String> {
+    let body = serde_json::json!({
+        "description": description,
+        "public": false,
+        "files": { filename: { "content": content } },
+    });
+    let response = transport
+        .post("https://api.github.com/gists", &body, token)
+        .await?;
+    match response.status {
+        401 => return Err("GitHub didn't accept the token in GITHUB_TOKEN".to_string()),
+        // A fine-grained token without gist access gets a 403 or a 404
+        403 | 404 if response.rate_limit_remaining != Some(0) => {
+            return Err(
+                "The token in GITHUB_TOKEN can't create gists; it needs the gist scope".to_string(),
+            )
+        }
+        _ => {}
+    }
+    check_status(&response, "The gists API wasn't found")?;
+    let gist: Gist = serde_json::from_str(&response.body)
+        .map_err(|e| format!("Failed to parse response: {e}"))?;
+    Ok(gist.html_url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> Transport {
+        Transport::Replay(Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures"))
     }
 
-    Ok(all_repos)
+    #[test]
+    fn test_fixture_path() {
+        let path = fixture_path(
+            Path::new("fixtures"),
+            "https://api.github.com/users/octocat/repos?per_page=100&page=2",
+        );
+        assert_eq!(
+            path,
+            Path::new("fixtures/users_octocat_repos_per_page_100_page_2.json")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_follows_pages() {
+        let repos = fetch_repos(&fixtures(), "octocat", false).await.unwrap();
+        let names: Vec<&str> = repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["Hello-World", "Spoon-Knife", "linguist"]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_skips_checkpoints() {
+        let dir = std::env::temp_dir().join(format!("github-stats-test-{}", std::process::id()));
+        cache::use_dir(dir.clone());
+        let stale = Checkpoint {
+            next: "https://api.github.com/nowhere".to_string(),
+            page: 9,
+        };
+        cache::save("checkpoint-repos-octocat", &stale).unwrap();
+
+        // A replay neither resumes from a checkpoint nor clears it
+        let repos = fetch_repos(&fixtures(), "octocat", true).await.unwrap();
+        assert_eq!(repos.len(), 3);
+        assert!(cache::load::<Checkpoint>("checkpoint-repos-octocat")
+            .unwrap()
+            .is_some());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replay_not_found() {
+        let err = fetch_repos(&fixtures(), "nobody-here", false)
+            .await
+            .unwrap_err();
+        assert_eq!(err, "User 'nobody-here' not found");
+    }
+
+    #[tokio::test]
+    async fn test_replay_rate_limited() {
+        let err = fetch_events(&fixtures(), "octocat").await.unwrap_err();
+        assert!(
+            err.starts_with("GitHub API rate limit exceeded; it resets at"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_without_fixture() {
+        let err = fetch_releases(&fixtures(), "octocat/none", 5, None)
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("No fixture for"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_replay_search() {
+        let results = search_repos(
+            &fixtures(),
+            "language:rust topic:cli stars:>500",
+            SearchSort::Stars,
+            2,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(results.total_count, 2);
+        assert!(!results.incomplete);
+        let names: Vec<&str> = results.repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["BurntSushi/ripgrep", "sharkdp/bat"]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_gist() {
+        let url = create_gist(&fixtures(), "octocat.md", "Repos", "## octocat", "token")
+            .await
+            .unwrap();
+        assert_eq!(url, "https://gist.github.com/aa5a315d61ae9438b18d");
+    }
+
+    #[test]
+    fn test_search_url() {
+        assert_eq!(
+            search_url("language:rust stars:>500", SearchSort::Stars, 100, 2),
+            "https://api.github.com/search/repositories?q=language%3Arust+stars%3A%3E500&sort=stars&order=desc&per_page=100&page=2"
+        );
+        assert!(!search_url("cli", SearchSort::BestMatch, 10, 1).contains("sort="));
+    }
+
+    #[test]
+    fn test_search_wait() {
+        let response = |remaining, reset| Response {
+            url: String::new(),
+            status: 200,
+            link: None,
+            rate_limit_remaining: remaining,
+            rate_limit_reset: reset,
+            body: String::new(),
+        };
+        assert_eq!(search_wait(&response(Some(3), Some(1030)), 1000), None);
+        assert_eq!(
+            search_wait(&response(Some(0), Some(1030)), 1000),
+            Some(Duration::from_secs(30))
+        );
+        // A reset already past, or far off, is clamped to the minute
+        assert_eq!(
+            search_wait(&response(Some(0), Some(900)), 1000),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            search_wait(&response(Some(0), None), 1000),
+            Some(Duration::from_secs(61))
+        );
+    }
+
+    #[test]
+    fn test_parse_links() {
+        let header = r#"<https://api.github.com/user/1/repos?per_page=100&page=2>; rel="next", <https://api.github.com/user/1/repos?per_page=100&page=14>; rel="last""#;
+        assert_eq!(
+            parse_links(header),
+            PageLinks {
+                next: Some("https://api.github.com/user/1/repos?per_page=100&page=2".to_string()),
+                last_page: Some(14),
+            }
+        );
+
+        // The last page links back but has no next
+        let header = r#"<https://api.github.com/user/1/repos?page=1>; rel="first", <https://api.github.com/user/1/repos?page=13>; rel="prev""#;
+        assert_eq!(parse_links(header), PageLinks::default());
+        assert_eq!(parse_links(""), PageLinks::default());
+    }
 }
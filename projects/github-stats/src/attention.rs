@@ -0,0 +1,277 @@
+use crate::api::{CheckRuns, CombinedStatus, OpenIssue, PullRequest};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Check run conclusions and commit status states that mean a failed build
+const FAILED: &[&str] = &["failure", "error", "timed_out", "startup_failure"];
+
+/// A pull request or issue someone else opened that's waiting on the owner.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Waiting {
+    pub number: u32,
+    pub title: String,
+    pub author: String,
+    /// YYYY-MM-DD
+    pub opened: String,
+}
+
+/// What in one repo needs the owner. This is what gets cached, so
+/// `--offline` ages everything against today's date.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct RepoAttention {
+    /// owner/name
+    pub repo: String,
+    pub branch: String,
+    /// Open pull requests that aren't drafts
+    pub pull_requests: Vec<Waiting>,
+    /// Open issues without a single comment, oldest first
+    pub unanswered: Vec<Waiting>,
+    /// Checks failing on the tip of the default branch
+    pub failing: Vec<String>,
+}
+
+fn waiting(number: u32, title: &str, author: &str, created_at: &str) -> Waiting {
+    Waiting {
+        number,
+        title: title.to_string(),
+        author: author.to_string(),
+        opened: created_at.chars().take(10).collect(),
+    }
+}
+
+fn days_since(date: &str, today: NaiveDate) -> i64 {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|d| (today - d).num_days())
+        .unwrap_or(0)
+}
+
+impl RepoAttention {
+    /// Leaves out what `me` opened: those wait on somebody else.
+    pub fn new(
+        repo: &str,
+        branch: &str,
+        me: &str,
+        pulls: &[PullRequest],
+        issues: &[OpenIssue],
+        status: &CombinedStatus,
+        runs: &CheckRuns,
+    ) -> RepoAttention {
+        let pull_requests = pulls
+            .iter()
+            .filter(|p| !p.draft && p.user.login != me)
+            .map(|p| waiting(p.number, &p.title, &p.user.login, &p.created_at))
+            .collect();
+        let unanswered = issues
+            .iter()
+            .filter(|i| i.pull_request.is_none() && i.comments == 0 && i.user.login != me)
+            .map(|i| waiting(i.number, &i.title, &i.user.login, &i.created_at))
+            .collect();
+
+        let mut failing: Vec<String> = status
+            .statuses
+            .iter()
+            .filter(|s| FAILED.contains(&s.state.as_str()))
+            .map(|s| s.context.clone())
+            .chain(
+                runs.check_runs
+                    .iter()
+                    .filter(|r| r.conclusion.as_deref().is_some_and(|c| FAILED.contains(&c)))
+                    .map(|r| r.name.clone()),
+            )
+            .collect();
+        failing.sort();
+        failing.dedup();
+
+        RepoAttention {
+            repo: repo.to_string(),
+            branch: branch.to_string(),
+            pull_requests,
+            unanswered,
+            failing,
+        }
+    }
+
+    /// Issues that have gone more than `days` days without a reply.
+    pub fn overdue(&self, days: i64, today: NaiveDate) -> Vec<&Waiting> {
+        self.unanswered
+            .iter()
+            .filter(|i| days_since(&i.opened, today) > days)
+            .collect()
+    }
+
+    pub fn needs_attention(&self, days: i64, today: NaiveDate) -> bool {
+        !self.pull_requests.is_empty()
+            || !self.failing.is_empty()
+            || !self.overdue(days, today).is_empty()
+    }
+}
+
+fn plural(n: usize, one: &str, many: &str) -> String {
+    if n == 1 {
+        format!("1 {one}")
+    } else {
+        format!("{n} {many}")
+    }
+}
+
+fn age(days: i64) -> String {
+    match days {
+        0 => "today".to_string(),
+        1 => "1 day".to_string(),
+        _ => format!("{days} days"),
+    }
+}
+
+/// A summary line, then each repo that needs something: its failing
+/// checks, then pull requests awaiting review, then issues waiting on a
+/// first reply for more than `days` days.
+pub fn render(report: &[RepoAttention], days: i64, today: NaiveDate) -> Vec<String> {
+    let flagged: Vec<&RepoAttention> = report
+        .iter()
+        .filter(|r| r.needs_attention(days, today))
+        .collect();
+    if flagged.is_empty() {
+        return vec![format!(
+            "Nothing needs attention in your {}.",
+            plural(report.len(), "repo", "repos")
+        )];
+    }
+
+    let pulls: usize = flagged.iter().map(|r| r.pull_requests.len()).sum();
+    let issues: usize = flagged.iter().map(|r| r.overdue(days, today).len()).sum();
+    let failing = flagged.iter().filter(|r| !r.failing.is_empty()).count();
+    let mut lines = vec![format!(
+        "{} awaiting review, {} without a reply for over {}, {} in {} of {}:",
+        plural(pulls, "pull request", "pull requests"),
+        plural(issues, "issue", "issues"),
+        plural(days as usize, "day", "days"),
+        plural(failing, "failing branch", "failing branches"),
+        flagged.len(),
+        plural(report.len(), "repo", "repos"),
+    )];
+
+    for repo in flagged {
+        lines.push(String::new());
+        lines.push(repo.repo.clone());
+        if !repo.failing.is_empty() {
+            lines.push(format!(
+                "  {} is failing: {}",
+                repo.branch,
+                repo.failing.join(", ")
+            ));
+        }
+        for pull in &repo.pull_requests {
+            lines.push(format!(
+                "  PR #{:<6} {}  ({}, open {})",
+                pull.number,
+                pull.title,
+                pull.author,
+                age(days_since(&pull.opened, today))
+            ));
+        }
+        for issue in repo.overdue(days, today) {
+            lines.push(format!(
+                "  Issue #{:<3} {}  ({}, no reply in {})",
+                issue.number,
+                issue.title,
+                issue.author,
+                age(days_since(&issue.opened, today))
+            ));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 15).unwrap()
+    }
+
+    fn attention() -> RepoAttention {
+        let pulls: Vec<PullRequest> = serde_json::from_str(
+            r#"[
+                {"number": 42, "title": "Add a --json flag", "user": {"login": "alice"}, "created_at": "2024-06-12T10:00:00Z", "draft": false},
+                {"number": 43, "title": "WIP: rewrite", "user": {"login": "bob"}, "created_at": "2024-06-13T10:00:00Z", "draft": true},
+                {"number": 44, "title": "Bump version", "user": {"login": "me"}, "created_at": "2024-06-14T10:00:00Z"}
+            ]"#,
+        )
+        .unwrap();
+        let issues: Vec<OpenIssue> = serde_json::from_str(
+            r#"[
+                {"number": 17, "title": "Crash on empty input", "user": {"login": "bob"}, "created_at": "2024-06-01T08:00:00Z", "comments": 0},
+                {"number": 20, "title": "Typo in README", "user": {"login": "carol"}, "created_at": "2024-06-12T08:00:00Z", "comments": 0},
+                {"number": 21, "title": "Answered", "user": {"login": "dan"}, "created_at": "2024-05-01T08:00:00Z", "comments": 2},
+                {"number": 42, "title": "Add a --json flag", "user": {"login": "alice"}, "created_at": "2024-06-12T10:00:00Z", "comments": 0, "pull_request": {}}
+            ]"#,
+        )
+        .unwrap();
+        let status: CombinedStatus = serde_json::from_str(
+            r#"{"state": "failure", "statuses": [{"context": "ci/circleci", "state": "failure"}, {"context": "codecov", "state": "success"}]}"#,
+        )
+        .unwrap();
+        let runs: CheckRuns = serde_json::from_str(
+            r#"{"total_count": 3, "check_runs": [
+                {"name": "test", "status": "completed", "conclusion": "failure"},
+                {"name": "lint", "status": "completed", "conclusion": "success"},
+                {"name": "build", "status": "in_progress", "conclusion": null}
+            ]}"#,
+        )
+        .unwrap();
+        RepoAttention::new("me/tool", "main", "me", &pulls, &issues, &status, &runs)
+    }
+
+    #[test]
+    fn test_new_keeps_what_waits_on_the_owner() {
+        let repo = attention();
+        assert_eq!(
+            repo.pull_requests,
+            [waiting(42, "Add a --json flag", "alice", "2024-06-12")]
+        );
+        let unanswered: Vec<u32> = repo.unanswered.iter().map(|i| i.number).collect();
+        assert_eq!(unanswered, [17, 20]);
+        assert_eq!(repo.failing, ["ci/circleci", "test"]);
+
+        // Only #17 is over a week old
+        let overdue: Vec<u32> = repo.overdue(7, today()).iter().map(|i| i.number).collect();
+        assert_eq!(overdue, [17]);
+    }
+
+    #[test]
+    fn test_needs_attention() {
+        let quiet = RepoAttention {
+            repo: "me/quiet".to_string(),
+            branch: "main".to_string(),
+            unanswered: vec![waiting(1, "New", "x", "2024-06-14")],
+            ..RepoAttention::default()
+        };
+        assert!(!quiet.needs_attention(7, today()));
+        assert!(quiet.needs_attention(0, today()));
+        assert!(attention().needs_attention(7, today()));
+    }
+
+    #[test]
+    fn test_render() {
+        let quiet = RepoAttention {
+            repo: "me/quiet".to_string(),
+            ..RepoAttention::default()
+        };
+        assert_eq!(
+            render(std::slice::from_ref(&quiet), 7, today()),
+            ["Nothing needs attention in your 1 repo."]
+        );
+        assert_eq!(
+            render(&[attention(), quiet], 7, today()),
+            [
+                "1 pull request awaiting review, 1 issue without a reply for over 7 days, 1 failing branch in 1 of 2 repos:",
+                "",
+                "me/tool",
+                "  main is failing: ci/circleci, test",
+                "  PR #42     Add a --json flag  (alice, open 3 days)",
+                "  Issue #17  Crash on empty input  (bob, no reply in 14 days)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! `--badge`: a shields.io-style SVG badge like "total stars | 1.2k",
+//! drawn locally so a profile README can embed it without a badge service.
+
+/// Background of the right-hand half
+const COLOR: &str = "#007ec6";
+const LABEL_COLOR: &str = "#555";
+/// Space on each side of the text, in pixels
+const PADDING: u32 = 5;
+
+/// `1234` as `1.2k`, `2500000` as `2.5M`; below 1000 as is.
+pub fn compact(n: u64) -> String {
+    let (value, suffix) = match n {
+        0..1_000 => return n.to_string(),
+        1_000..1_000_000 => (n as f64 / 1_000.0, "k"),
+        _ => (n as f64 / 1_000_000.0, "M"),
+    };
+    // Truncated rather than rounded, so 999,999 isn't shown as 1000.0k
+    let tenths = (value * 10.0).floor() / 10.0;
+    let digits = format!("{tenths:.1}");
+    format!("{}{suffix}", digits.trim_end_matches(".0"))
+}
+
+/// Roughly how wide a character is in 11px Verdana, in tenths of a pixel.
+fn char_width(c: char) -> u32 {
+    match c {
+        'i' | 'j' | 'l' | '.' | ',' | ':' | ';' | '\'' | '!' | '|' => 30,
+        ' ' | 'f' | 'r' | 't' | '(' | ')' | '[' | ']' | 'I' => 42,
+        'm' | 'w' => 106,
+        'M' | 'W' => 110,
+        'A'..='Z' => 75,
+        _ => 68,
+    }
+}
+
+/// Width of `text` in whole pixels.
+pub fn text_width(text: &str) -> u32 {
+    text.chars().map(char_width).sum::<u32>().div_ceil(10)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A flat two-part badge: `label` on grey, `message` on blue, each half
+/// sized to its text.
+pub fn render(label: &str, message: &str) -> String {
+    let left = text_width(label) + 2 * PADDING;
+    let right = text_width(message) + 2 * PADDING;
+    let width = left + right;
+    // Text is centered on these x positions
+    let (label_x, message_x) = (left as f64 / 2.0, left as f64 + right as f64 / 2.0);
+    let (label, message) = (escape(label), escape(message));
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{left}" height="20" fill="{LABEL_COLOR}"/>
+    <rect x="{left}" width="{right}" height="20" fill="{COLOR}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{label}</text>
+    <text x="{label_x}" y="14">{label}</text>
+    <text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##
+    )
+}
+
+/// The "total stars" badge.
+pub fn stars(total: u64) -> String {
+    render("total stars", &compact(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact() {
+        assert_eq!(compact(0), "0");
+        assert_eq!(compact(999), "999");
+        assert_eq!(compact(1_000), "1k");
+        assert_eq!(compact(1_234), "1.2k");
+        assert_eq!(compact(999_999), "999.9k");
+        assert_eq!(compact(2_500_000), "2.5M");
+    }
+
+    #[test]
+    fn test_text_width_grows_with_wide_characters() {
+        assert_eq!(text_width(""), 0);
+        assert!(text_width("mmm") > text_width("iii"));
+        assert!(text_width("total stars") > text_width("1.2k"));
+    }
+
+    #[test]
+    fn test_render_sizes_both_halves() {
+        let svg = stars(1_234);
+        let left = text_width("total stars") + 10;
+        let right = text_width("1.2k") + 10;
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(&format!(r#"width="{}""#, left + right)));
+        assert!(svg.contains(&format!(r#"<rect x="{left}" width="{right}""#)));
+        assert!(svg.contains(r#"aria-label="total stars: 1.2k""#));
+        assert!(render("a<b", "c").contains("a&lt;b"));
+    }
+}
@@ -0,0 +1,215 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A cached API response and when it was fetched.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cached<T> {
+    /// Unix seconds
+    pub fetched_at: u64,
+    pub data: T,
+}
+
+#[cfg(test)]
+thread_local! {
+    static TEST_DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Cache into `dir` instead of `~/.cache` for the rest of this thread, so
+/// tests leave the real cache alone.
+#[cfg(test)]
+pub fn use_dir(dir: PathBuf) {
+    TEST_DIR.with(|d| *d.borrow_mut() = Some(dir));
+}
+
+fn cache_dir() -> PathBuf {
+    #[cfg(test)]
+    if let Some(dir) = TEST_DIR.with(|d| d.borrow().clone()) {
+        return dir;
+    }
+    let base = std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join(".cache"))
+        .unwrap_or_else(std::env::temp_dir);
+    base.join("github-stats")
+}
+
+/// Cache keys come from user input ("owner/repo"), so keep them to
+/// filename-safe characters.
+fn cache_path(key: &str) -> PathBuf {
+    let safe: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    cache_dir().join(format!("{safe}.json"))
+}
+
+/// Where `append_line` keeps the lines under `key`.
+fn lines_path(key: &str) -> PathBuf {
+    cache_path(key).with_extension("jsonl")
+}
+
+/// A stable 64-bit FNV-1a hash of `text`, for keys made from text whose
+/// punctuation matters, like the search `stars:>500`, which `cache_path`
+/// would flatten to the same file as `stars:<500`.
+pub fn digest(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn save<T: Serialize>(key: &str, data: &T) -> Result<(), String> {
+    let path = cache_path(key);
+    fs::create_dir_all(cache_dir()).map_err(|e| format!("Failed to create cache dir: {e}"))?;
+
+    let cached = Cached {
+        fetched_at: now_secs(),
+        data,
+    };
+    let json =
+        serde_json::to_string(&cached).map_err(|e| format!("Failed to serialize cache: {e}"))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Load a cached entry, or `None` if nothing has been cached under `key`.
+pub fn load<T: DeserializeOwned>(key: &str) -> Result<Option<Cached<T>>, String> {
+    let path = cache_path(key);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    load_file(&path).map(Some)
+}
+
+/// Load a cache file from anywhere, such as a copy kept as a snapshot.
+pub fn load_file<T: DeserializeOwned>(path: &Path) -> Result<Cached<T>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {e}", path.display()))
+}
+
+/// Add `data` as one more JSON line under `key`, for data that arrives a
+/// piece at a time and would be slow to rewrite whole.
+pub fn append_line<T: Serialize>(key: &str, data: &T) -> Result<(), String> {
+    let path = lines_path(key);
+    fs::create_dir_all(cache_dir()).map_err(|e| format!("Failed to create cache dir: {e}"))?;
+    let json =
+        serde_json::to_string(data).map_err(|e| format!("Failed to serialize cache: {e}"))?;
+    fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{json}"))
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// The lines appended under `key`, up to any that doesn't parse, like one
+/// cut off mid-write; none if nothing was appended.
+pub fn load_lines<T: DeserializeOwned>(key: &str) -> Result<Vec<T>, String> {
+    let path = lines_path(key);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    Ok(content
+        .lines()
+        .map_while(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Delete a cached entry; fine if there isn't one.
+pub fn remove(key: &str) -> Result<(), String> {
+    remove_path(&cache_path(key))
+}
+
+/// Delete the lines appended under `key`; fine if there aren't any.
+pub fn remove_lines(key: &str) -> Result<(), String> {
+    remove_path(&lines_path(key))
+}
+
+fn remove_path(path: &Path) -> Result<(), String> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to remove {}: {e}", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Human-friendly age like "5 minutes ago" or "3 days ago".
+pub fn format_age(fetched_at: u64, now: u64) -> String {
+    let secs = now.saturating_sub(fetched_at);
+
+    let (n, unit) = match secs {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (secs / 60, "minute"),
+        3600..=86_399 => (secs / 3600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+
+    let plural = if n == 1 { "" } else { "s" };
+    format!("{n} {unit}{plural} ago")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_path_is_filename_safe() {
+        let path = cache_path("Rust-Lang/rust");
+        assert_eq!(path.file_name().unwrap(), "rust-lang_rust.json");
+        assert_ne!(digest("stars:>500"), digest("stars:<500"));
+        assert_eq!(digest(""), "cbf29ce484222325");
+    }
+
+    #[test]
+    fn test_append_lines() {
+        let dir = std::env::temp_dir().join(format!("github-stats-lines-{}", std::process::id()));
+        use_dir(dir.clone());
+        assert!(load_lines::<Vec<u32>>("pages").unwrap().is_empty());
+        append_line("pages", &vec![1, 2]).unwrap();
+        append_line("pages", &vec![3]).unwrap();
+        // A line cut off mid-write ends what's loaded
+        fs::write(
+            lines_path("pages"),
+            fs::read_to_string(lines_path("pages")).unwrap() + "[4,",
+        )
+        .unwrap();
+        assert_eq!(
+            load_lines::<Vec<u32>>("pages").unwrap(),
+            [vec![1, 2], vec![3]]
+        );
+        remove_lines("pages").unwrap();
+        assert!(!lines_path("pages").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(100, 130), "just now");
+        assert_eq!(format_age(0, 60), "1 minute ago");
+        assert_eq!(format_age(0, 3 * 3600), "3 hours ago");
+        assert_eq!(format_age(0, 2 * 86_400 + 5), "2 days ago");
+    }
+}
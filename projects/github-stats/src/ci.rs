@@ -0,0 +1,217 @@
+use crate::api::WorkflowRun;
+use serde::{Deserialize, Serialize};
+
+/// Recent runs on the default branch looked at for the success rate
+pub const RUN_SAMPLE: usize = 20;
+
+/// Below this share of passing runs, with the latest one failed too, a
+/// repo's CI counts as consistently failing
+const FAILING_RATE: u32 = 50;
+
+/// Fewer finished runs than this say too little to flag a repo
+const MIN_RUNS: usize = 3;
+
+/// A repo's recent GitHub Actions runs on its default branch. This is what
+/// gets cached.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Summary {
+    /// How the newest run ended ("success", "failure", ...), or where it
+    /// is if it hasn't ("in_progress", "queued"). `None` without any runs.
+    pub latest: Option<String>,
+    /// Runs that passed or failed; cancelled and skipped ones don't count
+    pub finished: usize,
+    pub passed: usize,
+}
+
+/// Conclusions that say nothing about whether the code works
+fn counts(conclusion: &str) -> bool {
+    !matches!(conclusion, "cancelled" | "skipped" | "neutral" | "stale")
+}
+
+impl Summary {
+    /// From runs newest first, as the API lists them.
+    pub fn new(runs: &[WorkflowRun]) -> Summary {
+        let finished: Vec<&str> = runs
+            .iter()
+            .filter_map(|r| r.conclusion.as_deref())
+            .filter(|c| counts(c))
+            .collect();
+        Summary {
+            latest: runs
+                .first()
+                .and_then(|r| r.conclusion.clone().or(r.status.clone())),
+            finished: finished.len(),
+            passed: finished.iter().filter(|c| **c == "success").count(),
+        }
+    }
+
+    /// Percentage of finished runs that passed
+    pub fn rate(&self) -> Option<u32> {
+        (self.finished > 0).then(|| (self.passed * 100 / self.finished) as u32)
+    }
+
+    /// Mostly failing, and still failing as of the latest run.
+    pub fn is_failing(&self) -> bool {
+        let latest_failed = matches!(
+            self.latest.as_deref(),
+            Some("failure" | "timed_out" | "startup_failure")
+        );
+        latest_failed
+            && self.finished >= MIN_RUNS
+            && self.rate().is_some_and(|rate| rate < FAILING_RATE)
+    }
+}
+
+pub fn render_table(rows: &[(String, Option<Summary>)]) -> Vec<String> {
+    const HEADERS: [&str; 3] = ["Latest", "Passed", "Rate"];
+    let cells = |summary: &Option<Summary>| match summary {
+        Some(s) if s.latest.is_none() => vec!["no runs".to_string()],
+        Some(s) => vec![
+            s.latest.clone().unwrap_or_default(),
+            format!("{}/{}", s.passed, s.finished),
+            s.rate().map_or("-".to_string(), |rate| format!("{rate}%")),
+        ],
+        None => vec!["n/a".to_string()],
+    };
+    let rows: Vec<(&str, Vec<String>)> = rows
+        .iter()
+        .map(|(name, summary)| (name.as_str(), cells(summary)))
+        .collect();
+
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain(["Repository".len()])
+        .max()
+        .unwrap_or(0);
+    // The latest conclusion varies in length, so that column fits its widest
+    let widths: Vec<usize> = HEADERS
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            rows.iter()
+                .filter(|(_, cells)| cells.len() == HEADERS.len())
+                .map(|(_, cells)| cells[i].len())
+                .chain([header.len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let line = |name: &str, cells: &[String]| {
+        let mut out = format!("  {name:<name_width$}");
+        for (i, (cell, width)) in cells.iter().zip(&widths).enumerate() {
+            if i == 0 {
+                out.push_str(&format!("  {cell:<width$}"));
+            } else {
+                out.push_str(&format!("  {cell:>width$}"));
+            }
+        }
+        out.trim_end().to_string()
+    };
+
+    let headers: Vec<String> = HEADERS.iter().map(|h| h.to_string()).collect();
+    let mut lines = vec![line("Repository", &headers)];
+    lines.push(format!("  {}", "-".repeat(lines[0].chars().count() - 2)));
+    for (name, cells) in &rows {
+        lines.push(line(name, cells));
+    }
+    lines
+}
+
+/// The names of the repos whose CI is consistently failing.
+pub fn failing(rows: &[(String, Option<Summary>)]) -> Vec<&str> {
+    rows.iter()
+        .filter(|(_, summary)| summary.as_ref().is_some_and(Summary::is_failing))
+        .map(|(name, _)| name.as_str())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs(conclusions: &[Option<&str>]) -> Vec<WorkflowRun> {
+        conclusions
+            .iter()
+            .map(|c| {
+                let status = if c.is_some() {
+                    "completed"
+                } else {
+                    "in_progress"
+                };
+                WorkflowRun {
+                    status: Some(status.to_string()),
+                    conclusion: c.map(str::to_string),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_summary_from_runs() {
+        let summary = Summary::new(&runs(&[
+            None,
+            Some("success"),
+            Some("failure"),
+            Some("cancelled"),
+            Some("success"),
+            Some("skipped"),
+        ]));
+        assert_eq!(
+            summary,
+            Summary {
+                latest: Some("in_progress".to_string()),
+                finished: 3,
+                passed: 2,
+            }
+        );
+        assert_eq!(summary.rate(), Some(66));
+        assert_eq!(Summary::new(&[]).rate(), None);
+    }
+
+    #[test]
+    fn test_is_failing() {
+        let failure = Some("failure");
+        let success = Some("success");
+        assert!(Summary::new(&runs(&[failure, failure, success, failure])).is_failing());
+        // Fixed by the latest run
+        assert!(!Summary::new(&runs(&[success, failure, failure, failure])).is_failing());
+        // Too few runs to tell
+        assert!(!Summary::new(&runs(&[failure, failure])).is_failing());
+        // Mostly passing
+        assert!(!Summary::new(&runs(&[failure, success, success, success])).is_failing());
+    }
+
+    #[test]
+    fn test_render_table() {
+        let rows = vec![
+            (
+                "tool".to_string(),
+                Some(Summary::new(&runs(&[
+                    Some("failure"),
+                    Some("failure"),
+                    Some("failure"),
+                ]))),
+            ),
+            (
+                "site".to_string(),
+                Some(Summary::new(&runs(&[Some("success")]))),
+            ),
+            ("docs".to_string(), Some(Summary::default())),
+            ("gone".to_string(), None),
+        ];
+        assert_eq!(
+            render_table(&rows),
+            [
+                "  Repository  Latest   Passed  Rate",
+                "  ---------------------------------",
+                "  tool        failure     0/3    0%",
+                "  site        success     1/1  100%",
+                "  docs        no runs",
+                "  gone        n/a",
+            ]
+        );
+        assert_eq!(failing(&rows), ["tool"]);
+    }
+}
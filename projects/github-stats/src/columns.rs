@@ -0,0 +1,195 @@
+use crate::api::Repo;
+use crate::diff::RepoDiff;
+use clap::ValueEnum;
+use std::cmp::Ordering;
+
+/// Longest a cell may get before it's truncated with an ellipsis
+const MAX_CELL_WIDTH: usize = 40;
+
+/// A column that can be shown in the repository table
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Column {
+    Name,
+    Stars,
+    Forks,
+    Issues,
+    Updated,
+    License,
+    Language,
+    Description,
+    Url,
+}
+
+impl Column {
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::Name => "Repository",
+            Column::Stars => "Stars",
+            Column::Forks => "Forks",
+            Column::Issues => "Issues",
+            Column::Updated => "Updated",
+            Column::License => "License",
+            Column::Language => "Language",
+            Column::Description => "Description",
+            Column::Url => "URL",
+        }
+    }
+
+    pub fn value(&self, repo: &Repo) -> String {
+        match self {
+            Column::Name => repo.name.clone(),
+            Column::Stars => repo.stargazers_count.to_string(),
+            Column::Forks => repo.forks_count.to_string(),
+            Column::Issues => repo.open_issues_count.to_string(),
+            // "2024-05-01T12:34:56Z" -> "2024-05-01"
+            Column::Updated => repo.updated_at.chars().take(10).collect(),
+            Column::License => repo
+                .license
+                .as_ref()
+                .and_then(|l| l.spdx_id.clone())
+                .unwrap_or_else(|| "(none)".to_string()),
+            Column::Language => repo
+                .language
+                .clone()
+                .unwrap_or_else(|| "(none)".to_string()),
+            Column::Description => repo.description.clone().unwrap_or_default(),
+            Column::Url => repo.html_url.clone(),
+        }
+    }
+
+    /// Numbers read better right-aligned
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Column::Stars | Column::Forks | Column::Issues)
+    }
+
+    /// Order two repos by this column: biggest numbers and newest dates
+    /// first, text alphabetically.
+    pub fn compare(&self, a: &Repo, b: &Repo) -> Ordering {
+        match self {
+            Column::Stars => b.stargazers_count.cmp(&a.stargazers_count),
+            Column::Forks => b.forks_count.cmp(&a.forks_count),
+            Column::Issues => b.open_issues_count.cmp(&a.open_issues_count),
+            Column::Updated => b.updated_at.cmp(&a.updated_at),
+            _ => self
+                .value(a)
+                .to_lowercase()
+                .cmp(&self.value(b).to_lowercase()),
+        }
+    }
+}
+
+/// Lay out `repos` as a table with one column per entry in `columns`, each
+/// column as wide as its widest cell. With a `diff`, cells get its
+/// annotations appended. Returns the header, separator, and row lines ready
+/// to print.
+pub fn render_table(columns: &[Column], repos: &[&Repo], diff: Option<&RepoDiff>) -> Vec<String> {
+    let rows: Vec<Vec<String>> = repos
+        .iter()
+        .map(|repo| {
+            columns
+                .iter()
+                .map(|col| {
+                    let cell = truncate(&col.value(repo), MAX_CELL_WIDTH);
+                    match diff.and_then(|d| d.annotation(*col, repo)) {
+                        Some(note) => format!("{cell} {note}"),
+                        None => cell,
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(col.header().len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let format_row = |cells: &[String]| -> String {
+        let line = cells
+            .iter()
+            .zip(columns)
+            .zip(&widths)
+            .map(|((cell, col), &width)| {
+                if col.is_numeric() {
+                    format!("{cell:>width$}")
+                } else {
+                    format!("{cell:<width$}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        format!("  {}", line.trim_end())
+    };
+
+    let headers: Vec<String> = columns.iter().map(|c| c.header().to_string()).collect();
+    let total_width = widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1);
+
+    let mut lines = vec![
+        format_row(&headers),
+        format!("  {}", "-".repeat(total_width)),
+    ];
+    lines.extend(rows.iter().map(|row| format_row(row)));
+    lines
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let mut out: String = s.chars().take(max - 1).collect();
+        out.push('…');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_repo;
+
+    fn repo(name: &str, stars: u32) -> Repo {
+        Repo {
+            forks_count: 2,
+            updated_at: "2024-05-01T12:34:56Z".to_string(),
+            html_url: format!("https://github.com/octocat/{name}"),
+            ..test_repo(name, stars, Some("Rust"))
+        }
+    }
+
+    #[test]
+    fn test_columns_size_to_widest_cell() {
+        let a = repo("a", 5);
+        let long = repo("much-longer-name", 12345);
+        let lines = render_table(&[Column::Name, Column::Stars], &[&a, &long], None);
+
+        assert_eq!(lines[0], "  Repository        Stars");
+        assert_eq!(lines[2], "  a                     5");
+        assert_eq!(lines[3], "  much-longer-name  12345");
+        assert_eq!(lines[1].trim().len(), lines[3].trim().len());
+    }
+
+    #[test]
+    fn test_updated_shows_date_only() {
+        assert_eq!(Column::Updated.value(&repo("a", 1)), "2024-05-01");
+    }
+
+    #[test]
+    fn test_compare_orders() {
+        let (a, b) = (repo("alpha", 1), repo("Beta", 9));
+        assert_eq!(Column::Stars.compare(&a, &b), Ordering::Greater);
+        assert_eq!(Column::Name.compare(&a, &b), Ordering::Less);
+    }
+
+    #[test]
+    fn test_truncate_long_cells() {
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(truncate("abc", 4), "abc");
+    }
+}
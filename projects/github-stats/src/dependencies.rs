@@ -0,0 +1,196 @@
+use crate::api::{Sbom, SbomPackage};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Direct dependency counts keyed by ecosystem name ("Cargo", "npm", ...)
+pub type EcosystemCounts = BTreeMap<String, usize>;
+
+/// Map a package URL type to the name people know the ecosystem by.
+fn ecosystem_name(purl_type: &str) -> String {
+    match purl_type {
+        "cargo" => "Cargo",
+        "npm" => "npm",
+        "pypi" => "pip",
+        "gem" => "RubyGems",
+        "golang" => "Go",
+        "maven" => "Maven",
+        "nuget" => "NuGet",
+        "composer" => "Composer",
+        "githubactions" | "github" => "Actions",
+        other => other,
+    }
+    .to_string()
+}
+
+/// The ecosystem of a package, from its purl ("pkg:cargo/serde@1.0").
+fn ecosystem(package: &SbomPackage) -> Option<String> {
+    package
+        .external_refs
+        .iter()
+        .find(|r| r.reference_type == "purl")
+        .and_then(|r| r.reference_locator.strip_prefix("pkg:"))
+        .and_then(|purl| purl.split('/').next())
+        .map(ecosystem_name)
+}
+
+/// The SPDX id of the repository itself, which direct dependencies hang off.
+fn root_id(sbom: &Sbom) -> Option<&str> {
+    sbom.relationships
+        .iter()
+        .find(|r| r.relationship_type == "DESCRIBES" && r.spdx_element_id == "SPDXRef-DOCUMENT")
+        .map(|r| r.related_spdx_element.as_str())
+        .or(sbom.document_describes.first().map(|s| s.as_str()))
+}
+
+/// Count direct dependencies per ecosystem. A package is direct when the
+/// repository DEPENDS_ON it; if the SBOM has no such relationships every
+/// package is counted. Packages listed at several versions count once.
+pub fn count_direct(sbom: &Sbom) -> EcosystemCounts {
+    let root = root_id(sbom);
+
+    let direct: BTreeSet<&str> = sbom
+        .relationships
+        .iter()
+        .filter(|r| r.relationship_type == "DEPENDS_ON" && Some(r.spdx_element_id.as_str()) == root)
+        .map(|r| r.related_spdx_element.as_str())
+        .collect();
+
+    let mut seen: BTreeSet<(String, &str)> = BTreeSet::new();
+    for package in &sbom.packages {
+        if Some(package.spdx_id.as_str()) == root {
+            continue;
+        }
+        if !direct.is_empty() && !direct.contains(package.spdx_id.as_str()) {
+            continue;
+        }
+        if let Some(eco) = ecosystem(package) {
+            seen.insert((eco, package.name.as_str()));
+        }
+    }
+
+    let mut counts = EcosystemCounts::new();
+    for (eco, _) in seen {
+        *counts.entry(eco).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// One row per repo, one column per ecosystem (busiest first), and a total
+/// row. Repos whose dependency graph couldn't be read show "n/a".
+pub fn render_table(rows: &[(String, Option<EcosystemCounts>)]) -> Vec<String> {
+    let mut totals = EcosystemCounts::new();
+    for counts in rows.iter().filter_map(|(_, c)| c.as_ref()) {
+        for (eco, n) in counts {
+            *totals.entry(eco.clone()).or_insert(0) += n;
+        }
+    }
+
+    let mut ecosystems: Vec<(&String, &usize)> = totals.iter().collect();
+    ecosystems.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let ecosystems: Vec<&String> = ecosystems.into_iter().map(|(eco, _)| eco).collect();
+
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain(["Repository".len(), "Total".len()])
+        .max()
+        .unwrap_or(0);
+    let widths: Vec<usize> = ecosystems.iter().map(|e| e.len().max(4)).collect();
+
+    let line = |name: &str, cells: Vec<String>| {
+        let mut out = format!("  {name:<name_width$}");
+        for (cell, width) in cells.iter().zip(&widths) {
+            out.push_str(&format!("  {cell:>width$}"));
+        }
+        out.trim_end().to_string()
+    };
+
+    let mut lines = vec![line(
+        "Repository",
+        ecosystems.iter().map(|e| e.to_string()).collect(),
+    )];
+    lines.push(format!("  {}", "-".repeat(lines[0].chars().count() - 2)));
+
+    for (name, counts) in rows {
+        let cells = match counts {
+            Some(counts) => ecosystems
+                .iter()
+                .map(|e| counts.get(*e).copied().unwrap_or(0).to_string())
+                .collect(),
+            None => vec!["n/a".to_string()],
+        };
+        lines.push(line(name, cells));
+    }
+
+    lines.push(line(
+        "Total",
+        ecosystems.iter().map(|e| totals[*e].to_string()).collect(),
+    ));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SBOM: &str = r#"{
+        "documentDescribes": ["SPDXRef-DOCUMENT"],
+        "packages": [
+            {"SPDXID": "SPDXRef-repo", "name": "com.github.me/tool"},
+            {"SPDXID": "SPDXRef-serde", "name": "serde", "externalRefs": [
+                {"referenceType": "purl", "referenceLocator": "pkg:cargo/serde@1.0.200"}]},
+            {"SPDXID": "SPDXRef-serde-old", "name": "serde", "externalRefs": [
+                {"referenceType": "purl", "referenceLocator": "pkg:cargo/serde@1.0.100"}]},
+            {"SPDXID": "SPDXRef-itoa", "name": "itoa", "externalRefs": [
+                {"referenceType": "purl", "referenceLocator": "pkg:cargo/itoa@1.0.0"}]},
+            {"SPDXID": "SPDXRef-requests", "name": "requests", "externalRefs": [
+                {"referenceType": "purl", "referenceLocator": "pkg:pypi/requests@2.31.0"}]}
+        ],
+        "relationships": [
+            {"relationshipType": "DESCRIBES", "spdxElementId": "SPDXRef-DOCUMENT", "relatedSpdxElement": "SPDXRef-repo"},
+            {"relationshipType": "DEPENDS_ON", "spdxElementId": "SPDXRef-repo", "relatedSpdxElement": "SPDXRef-serde"},
+            {"relationshipType": "DEPENDS_ON", "spdxElementId": "SPDXRef-repo", "relatedSpdxElement": "SPDXRef-serde-old"},
+            {"relationshipType": "DEPENDS_ON", "spdxElementId": "SPDXRef-repo", "relatedSpdxElement": "SPDXRef-requests"},
+            {"relationshipType": "DEPENDS_ON", "spdxElementId": "SPDXRef-serde", "relatedSpdxElement": "SPDXRef-itoa"}
+        ]
+    }"#;
+
+    fn counts(pairs: &[(&str, usize)]) -> EcosystemCounts {
+        pairs.iter().map(|(e, n)| (e.to_string(), *n)).collect()
+    }
+
+    #[test]
+    fn test_count_direct_skips_transitive_and_duplicates() {
+        let sbom: Sbom = serde_json::from_str(SBOM).unwrap();
+        assert_eq!(count_direct(&sbom), counts(&[("Cargo", 1), ("pip", 1)]));
+    }
+
+    #[test]
+    fn test_count_without_relationships_counts_everything() {
+        let mut sbom: Sbom = serde_json::from_str(SBOM).unwrap();
+        sbom.relationships.clear();
+        assert_eq!(count_direct(&sbom), counts(&[("Cargo", 2), ("pip", 1)]));
+    }
+
+    #[test]
+    fn test_render_table() {
+        let rows = vec![
+            (
+                "tool".to_string(),
+                Some(counts(&[("Cargo", 3), ("npm", 1)])),
+            ),
+            ("site".to_string(), Some(counts(&[("npm", 1)]))),
+            ("old".to_string(), None),
+        ];
+        assert_eq!(
+            render_table(&rows),
+            vec![
+                "  Repository  Cargo   npm",
+                "  -----------------------",
+                "  tool            3     1",
+                "  site            0     1",
+                "  old           n/a",
+                "  Total           3     2",
+            ]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use crate::api::Repo;
+use crate::columns::Column;
+use std::collections::HashMap;
+
+/// What changed between two fetches of the same user's repos.
+#[derive(Debug, Default, PartialEq)]
+pub struct RepoDiff {
+    /// Names of repos that weren't there last time
+    pub created: Vec<String>,
+    /// (old name, new name)
+    pub renamed: Vec<(String, String)>,
+    /// Names of repos that were there last time but are gone now
+    pub removed: Vec<String>,
+    pub archived: Vec<String>,
+    /// Star change per repo, by current name; unchanged repos are left out
+    pub star_deltas: HashMap<String, i64>,
+}
+
+impl RepoDiff {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty()
+            && self.renamed.is_empty()
+            && self.removed.is_empty()
+            && self.archived.is_empty()
+            && self.star_deltas.is_empty()
+    }
+
+    pub fn total_star_delta(&self) -> i64 {
+        self.star_deltas.values().sum()
+    }
+
+    /// Extra text for a table cell: `+N`/`-N` after star counts, and a
+    /// marker after the names of new, renamed, and archived repos.
+    pub fn annotation(&self, column: Column, repo: &Repo) -> Option<String> {
+        match column {
+            Column::Stars => self
+                .star_deltas
+                .get(&repo.name)
+                .map(|delta| format!("({delta:+})")),
+            Column::Name => {
+                if self.created.contains(&repo.name) {
+                    Some("[new]".to_string())
+                } else if let Some((old, _)) = self.renamed.iter().find(|(_, n)| *n == repo.name) {
+                    Some(format!("[was {old}]"))
+                } else if self.archived.contains(&repo.name) {
+                    Some("[archived]".to_string())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Repos are matched by ID so renames are caught, falling back to name for
+/// lists cached before IDs were stored.
+fn same_repo(a: &Repo, b: &Repo) -> bool {
+    if a.id != 0 && b.id != 0 {
+        a.id == b.id
+    } else {
+        a.name == b.name
+    }
+}
+
+/// Compare two repo lists.
+pub fn diff(previous: &[Repo], current: &[Repo]) -> RepoDiff {
+    let mut result = RepoDiff::default();
+
+    for repo in current {
+        let before = previous.iter().find(|p| same_repo(p, repo));
+
+        let Some(before) = before else {
+            result.created.push(repo.name.clone());
+            continue;
+        };
+
+        if before.name != repo.name {
+            result
+                .renamed
+                .push((before.name.clone(), repo.name.clone()));
+        }
+        if repo.archived && !before.archived {
+            result.archived.push(repo.name.clone());
+        }
+
+        let delta = repo.stargazers_count as i64 - before.stargazers_count as i64;
+        if delta != 0 {
+            result.star_deltas.insert(repo.name.clone(), delta);
+        }
+    }
+
+    result.removed = previous
+        .iter()
+        .filter(|p| !current.iter().any(|repo| same_repo(p, repo)))
+        .map(|p| p.name.clone())
+        .collect();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_repo;
+
+    fn repo(id: u64, name: &str, stars: u32, archived: bool) -> Repo {
+        Repo {
+            id,
+            archived,
+            ..test_repo(name, stars, Some("Rust"))
+        }
+    }
+
+    #[test]
+    fn test_diff_finds_every_kind_of_change() {
+        let previous = vec![
+            repo(1, "old-name", 10, false),
+            repo(2, "stable", 5, false),
+            repo(3, "retired", 7, false),
+            repo(5, "deleted", 2, false),
+        ];
+        let current = vec![
+            repo(1, "new-name", 12, false),
+            repo(2, "stable", 5, false),
+            repo(3, "retired", 6, true),
+            repo(4, "fresh", 0, false),
+        ];
+
+        let d = diff(&previous, &current);
+        assert_eq!(d.created, vec!["fresh"]);
+        assert_eq!(
+            d.renamed,
+            vec![("old-name".to_string(), "new-name".to_string())]
+        );
+        assert_eq!(d.archived, vec!["retired"]);
+        assert_eq!(d.removed, vec!["deleted"]);
+        assert_eq!(d.star_deltas.len(), 2);
+        assert_eq!(d.total_star_delta(), 1);
+    }
+
+    #[test]
+    fn test_repos_without_ids_match_by_name() {
+        let previous = vec![repo(0, "tool", 3, false)];
+        let current = vec![repo(9, "tool", 4, false)];
+        let d = diff(&previous, &current);
+        assert!(d.created.is_empty());
+        assert_eq!(d.star_deltas.get("tool"), Some(&1));
+    }
+
+    #[test]
+    fn test_annotations() {
+        let previous = vec![repo(1, "a", 10, false)];
+        let current = vec![repo(1, "b", 8, false), repo(2, "c", 1, false)];
+        let d = diff(&previous, &current);
+
+        assert_eq!(
+            d.annotation(Column::Stars, &current[0]).as_deref(),
+            Some("(-2)")
+        );
+        assert_eq!(
+            d.annotation(Column::Name, &current[0]).as_deref(),
+            Some("[was a]")
+        );
+        assert_eq!(
+            d.annotation(Column::Name, &current[1]).as_deref(),
+            Some("[new]")
+        );
+        assert_eq!(d.annotation(Column::Language, &current[0]), None);
+    }
+}
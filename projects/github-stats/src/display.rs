@@ -1,79 +1,156 @@
 use crate::api::Repo;
+use crate::columns::Column;
+use crate::diff::RepoDiff;
+use crate::export::{Exporter, Markdown, Summary};
+use crate::query::{self, SortKey};
+use crate::redact;
+use clap::ValueEnum;
 use std::collections::HashMap;
 
-pub fn display_repos(username: &str, repos: &[Repo], limit: usize, sort_by: &str) {
+/// What `--group-by` splits the listing on
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum GroupBy {
+    Language,
+}
+
+/// Non-fork repos ordered by the `--sort` keys.
+pub fn sorted_repos<'a>(repos: &'a [Repo], sort_by: &[SortKey]) -> Vec<&'a Repo> {
     let mut filtered: Vec<&Repo> = repos.iter().filter(|r| !r.fork).collect();
+    filtered.sort_by(|a, b| query::compare(sort_by, a, b));
+    filtered
+}
 
-    match sort_by {
-        "stars" => filtered.sort_by(|a, b| b.stargazers_count.cmp(&a.stargazers_count)),
-        "name" => {
-            filtered.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-        }
-        _ => filtered.sort_by(|a, b| b.stargazers_count.cmp(&a.stargazers_count)),
-    }
+/// Print the repo listing through `exporter` and return it as markdown,
+/// for `--publish`. With `redacted`, names are hashed after sorting, so the
+/// order is the same as without.
+#[allow(clippy::too_many_arguments)]
+pub fn display_repos(
+    username: &str,
+    repos: &[Repo],
+    limit: usize,
+    sort_by: &[SortKey],
+    columns: &[Column],
+    diff: Option<&RepoDiff>,
+    redacted: bool,
+    group_by: Option<GroupBy>,
+    exporter: &dyn Exporter,
+) -> Result<String, String> {
+    let mut filtered = sorted_repos(repos, sort_by);
 
-    let display_count = limit.min(filtered.len());
+    let redacted_repos: Vec<Repo>;
+    let redacted_diff: Option<RepoDiff>;
+    let (username, diff) = if redacted {
+        redacted_repos = filtered.iter().map(|r| redact::repo(r)).collect();
+        filtered = redacted_repos.iter().collect();
+        redacted_diff = diff.map(redact::diff);
+        (redact::owner(username), redacted_diff.as_ref())
+    } else {
+        (username.to_string(), diff)
+    };
 
-    println!("\n{username}");
-    println!("{}", "=".repeat(username.len()));
-    println!(
-        "Public repos: {} (showing top {display_count} by {sort_by})\n",
-        filtered.len()
+    let summary = Summary::new(
+        username,
+        &filtered,
+        limit,
+        query::describe(sort_by),
+        columns,
+        diff,
     );
+    let summary = match group_by {
+        Some(GroupBy::Language) => summary.by_language(&filtered, limit),
+        None => summary,
+    };
+    println!("{}", exporter.render(&summary)?);
+    Markdown.render(&summary)
+}
 
-    println!(
-        "  {:<28} {:<10} {:<15}",
-        "Repository", "Stars", "Language"
-    );
-    println!("  {}", "-".repeat(53));
+/// List what changed since the previous fetch. `since` is when that was,
+/// e.g. "3 days ago".
+pub fn display_changes(diff: &RepoDiff, since: &str, redacted: bool) {
+    let redacted_diff;
+    let diff = if redacted {
+        redacted_diff = redact::diff(diff);
+        &redacted_diff
+    } else {
+        diff
+    };
+
+    if diff.is_empty() {
+        println!("\nNo changes since last run ({since}).");
+        return;
+    }
 
-    for repo in filtered.iter().take(limit) {
-        let language = repo.language.as_deref().unwrap_or("(none)");
+    println!("\nChanges since last run ({since}):");
+    if !diff.created.is_empty() {
+        println!("  New:       {}", diff.created.join(", "));
+    }
+    for (old, new) in &diff.renamed {
+        println!("  Renamed:   {old} → {new}");
+    }
+    if !diff.archived.is_empty() {
+        println!("  Archived:  {}", diff.archived.join(", "));
+    }
+    if !diff.removed.is_empty() {
+        println!("  Removed:   {}", diff.removed.join(", "));
+    }
+    if !diff.star_deltas.is_empty() {
         println!(
-            "  {:<28} {:<10} {:<15}",
-            repo.name, repo.stargazers_count, language
+            "  Stars:     {:+} across {} repos",
+            diff.total_star_delta(),
+            diff.star_deltas.len()
         );
     }
-
-    display_summary(&filtered);
 }
 
-fn display_summary(repos: &[&Repo]) {
-    if repos.is_empty() {
-        println!("\nNo repositories found.");
-        return;
-    }
-
-    let total_stars: u32 = repos.iter().map(|r| r.stargazers_count).sum();
-
-    let mut lang_counts: HashMap<&str, usize> = HashMap::new();
+/// Repos per SPDX license ID, most common first. GitHub reports licenses it
+/// can't identify as NOASSERTION, shown as "other"; repos without a license
+/// file count as "none".
+pub fn license_counts(repos: &[&Repo]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
     for repo in repos {
-        if let Some(lang) = &repo.language {
-            *lang_counts.entry(lang.as_str()).or_insert(0) += 1;
-        }
+        let license = match repo.license.as_ref().and_then(|l| l.spdx_id.as_deref()) {
+            None => "none",
+            Some("NOASSERTION") => "other",
+            Some(id) => id,
+        };
+        *counts.entry(license.to_string()).or_insert(0) += 1;
     }
 
-    let mut lang_sorted: Vec<(&&str, &usize)> = lang_counts.iter().collect();
-    lang_sorted.sort_by(|a, b| b.1.cmp(a.1));
-
-    let lang_summary: String = lang_sorted
-        .iter()
-        .take(5)
-        .map(|(lang, count)| format!("{lang} ({count})"))
-        .collect::<Vec<_>>()
-        .join(", ");
+    let mut sorted: Vec<(String, usize)> = counts.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    sorted
+}
 
-    println!("\nSummary:");
-    println!("  Total stars:  {total_stars}");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{test_license, test_repo};
 
-    if !lang_summary.is_empty() {
-        println!("  Languages:    {lang_summary}");
+    fn repo(name: &str, license: Option<&str>) -> Repo {
+        Repo {
+            license: license.and_then(test_license),
+            ..test_repo(name, 0, None)
+        }
     }
 
-    if let Some(top) = repos.first() {
-        println!(
-            "  Most starred: {} ({} stars)",
-            top.name, top.stargazers_count
+    #[test]
+    fn test_license_counts() {
+        let repos = [
+            repo("a", Some("MIT")),
+            repo("b", Some("Apache-2.0")),
+            repo("c", Some("MIT")),
+            repo("d", None),
+            repo("e", Some("NOASSERTION")),
+        ];
+        let refs: Vec<&Repo> = repos.iter().collect();
+        assert_eq!(
+            license_counts(&refs),
+            vec![
+                ("MIT".to_string(), 2),
+                ("Apache-2.0".to_string(), 1),
+                ("none".to_string(), 1),
+                ("other".to_string(), 1),
+            ]
         );
     }
 }
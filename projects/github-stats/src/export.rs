@@ -0,0 +1,602 @@
+//! Output formats for the repo listing. Each is an `Exporter` in
+//! `EXPORTERS`, picked by name with `--format`.
+
+use crate::api::Repo;
+use crate::columns::{self, Column};
+use crate::diff::RepoDiff;
+use crate::display;
+use clap::ValueEnum;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Languages listed in the summary
+const TOP_LANGUAGES: usize = 5;
+
+/// Everything the repo listing shows, worked out once for every format.
+pub struct Summary<'a> {
+    pub owner: String,
+    /// What the repos are, for the heading: "Public repos" unless set
+    pub kind: &'static str,
+    /// Repos after filtering, not counting forks
+    pub total: usize,
+    /// How the repos are ordered, e.g. "stars"
+    pub sorted_by: String,
+    pub columns: &'a [Column],
+    /// The repos shown, at most `--limit` of them
+    pub repos: Vec<&'a Repo>,
+    /// Changes since the last run, for formats that annotate them
+    pub diff: Option<&'a RepoDiff>,
+    pub total_stars: u32,
+    /// Most common first
+    pub languages: Vec<(String, usize)>,
+    pub most_starred: Option<&'a Repo>,
+    pub licenses: Vec<(String, usize)>,
+    pub archived: Vec<&'a str>,
+    /// A section per language with `--group-by language`; empty for one
+    /// flat table
+    pub groups: Vec<Group<'a>>,
+}
+
+/// One language's repos in a grouped listing.
+pub struct Group<'a> {
+    /// The language, or "No language"
+    pub name: String,
+    pub total: usize,
+    pub stars: u32,
+    /// The repos shown, at most `--limit` of them
+    pub repos: Vec<&'a Repo>,
+}
+
+impl<'a> Summary<'a> {
+    /// `repos` are all the filtered repos in display order; the first
+    /// `limit` are shown and the rest only count towards the totals.
+    pub fn new(
+        owner: String,
+        repos: &[&'a Repo],
+        limit: usize,
+        sorted_by: String,
+        columns: &'a [Column],
+        diff: Option<&'a RepoDiff>,
+    ) -> Summary<'a> {
+        let mut languages: HashMap<&str, usize> = HashMap::new();
+        for repo in repos {
+            if let Some(language) = &repo.language {
+                *languages.entry(language.as_str()).or_insert(0) += 1;
+            }
+        }
+        let mut languages: Vec<(String, usize)> = languages
+            .into_iter()
+            .map(|(language, count)| (language.to_string(), count))
+            .collect();
+        languages.sort_by_key(|(language, count)| (Reverse(*count), language.clone()));
+        languages.truncate(TOP_LANGUAGES);
+
+        Summary {
+            owner,
+            kind: "Public repos",
+            total: repos.len(),
+            sorted_by,
+            columns,
+            repos: repos.iter().take(limit).copied().collect(),
+            diff,
+            total_stars: repos.iter().map(|r| r.stargazers_count).sum(),
+            languages,
+            // The first with the most stars, so ties go by the display order
+            most_starred: repos
+                .iter()
+                .copied()
+                .rev()
+                .max_by_key(|r| r.stargazers_count),
+            licenses: display::license_counts(repos),
+            archived: repos
+                .iter()
+                .filter(|r| r.archived)
+                .map(|r| r.name.as_str())
+                .collect(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Split the listing into a section per language, each with its own top
+    /// `limit`, the language with the most stars first and repos without
+    /// one last. `repos` are the same as for `new`.
+    pub fn by_language(mut self, repos: &[&'a Repo], limit: usize) -> Summary<'a> {
+        let mut groups: Vec<(Option<&str>, Vec<&'a Repo>)> = Vec::new();
+        for repo in repos {
+            let language = repo.language.as_deref();
+            match groups.iter_mut().find(|(l, _)| *l == language) {
+                Some((_, members)) => members.push(repo),
+                None => groups.push((language, vec![repo])),
+            }
+        }
+
+        let mut groups: Vec<Group> = groups
+            .into_iter()
+            .map(|(language, members)| Group {
+                name: language.unwrap_or("No language").to_string(),
+                total: members.len(),
+                stars: members.iter().map(|r| r.stargazers_count).sum(),
+                repos: members.into_iter().take(limit).collect(),
+            })
+            .collect();
+        groups.sort_by_key(|g| {
+            (
+                g.name == "No language",
+                Reverse(g.stars),
+                Reverse(g.total),
+                g.name.clone(),
+            )
+        });
+        self.repos = groups.iter().flat_map(|g| g.repos.clone()).collect();
+        self.groups = groups;
+        self
+    }
+}
+
+/// Turns a `Summary` into text in some format.
+pub trait Exporter: Sync {
+    /// What `--format` calls it
+    fn name(&self) -> &'static str;
+
+    fn render(&self, summary: &Summary) -> Result<String, String>;
+}
+
+/// Every format, in the order `--help` lists them. A new format is one
+/// more `Exporter` here.
+pub const EXPORTERS: &[&dyn Exporter] = &[&Table, &Json, &Csv, &Markdown, &Html];
+
+/// Parse a `--format` name.
+pub fn by_name(name: &str) -> Result<&'static dyn Exporter, String> {
+    EXPORTERS
+        .iter()
+        .copied()
+        .find(|e| e.name() == name.to_lowercase())
+        .ok_or_else(|| {
+            let names: Vec<&str> = EXPORTERS.iter().map(|e| e.name()).collect();
+            format!("Unknown format '{name}'; use {}", names.join(", "))
+        })
+}
+
+/// The name `--columns` takes for a column, used as a field name.
+fn key(column: Column) -> String {
+    column
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+fn counts_list(counts: &[(String, usize)]) -> String {
+    counts
+        .iter()
+        .map(|(name, count)| format!("{name} ({count})"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The summary lines shared by the text formats, as (label, value).
+fn summary_lines(summary: &Summary) -> Vec<(&'static str, String)> {
+    let mut lines = vec![("Total stars", summary.total_stars.to_string())];
+    if !summary.groups.is_empty() {
+        let subtotals: Vec<String> = summary
+            .groups
+            .iter()
+            .map(|g| format!("{} ({})", g.name, stars(g)))
+            .collect();
+        lines.push(("Languages", subtotals.join(", ")));
+    } else if !summary.languages.is_empty() {
+        lines.push(("Languages", counts_list(&summary.languages)));
+    }
+    if let Some(top) = summary.most_starred {
+        lines.push((
+            "Most starred",
+            format!("{} ({} stars)", top.name, top.stargazers_count),
+        ));
+    }
+    lines.push(("Licenses", counts_list(&summary.licenses)));
+    if !summary.archived.is_empty() {
+        lines.push((
+            "Archived",
+            format!(
+                "{} ({})",
+                summary.archived.len(),
+                summary.archived.join(", ")
+            ),
+        ));
+    }
+    lines
+}
+
+/// "3 repos, 120 stars"
+fn stars(group: &Group) -> String {
+    let plural = |n: usize, what: &str| {
+        if n == 1 {
+            format!("1 {what}")
+        } else {
+            format!("{n} {what}s")
+        }
+    };
+    format!(
+        "{}, {}",
+        plural(group.total, "repo"),
+        plural(group.stars as usize, "star")
+    )
+}
+
+fn heading(summary: &Summary) -> String {
+    if !summary.groups.is_empty() {
+        let most = summary.groups.iter().map(|g| g.repos.len()).max();
+        return format!(
+            "{}: {} in {} languages (showing top {} by {} in each)",
+            summary.kind,
+            summary.total,
+            summary.groups.len(),
+            most.unwrap_or(0),
+            summary.sorted_by
+        );
+    }
+    format!(
+        "{}: {} (showing top {} by {})",
+        summary.kind,
+        summary.total,
+        summary.repos.len(),
+        summary.sorted_by
+    )
+}
+
+/// The aligned plain-text table, with `--diff` annotations.
+pub struct Table;
+
+impl Exporter for Table {
+    fn name(&self) -> &'static str {
+        "table"
+    }
+
+    fn render(&self, summary: &Summary) -> Result<String, String> {
+        let mut lines = vec![
+            String::new(),
+            summary.owner.clone(),
+            "=".repeat(summary.owner.len()),
+            heading(summary),
+            String::new(),
+        ];
+        if summary.groups.is_empty() {
+            lines.extend(columns::render_table(
+                summary.columns,
+                &summary.repos,
+                summary.diff,
+            ));
+        }
+        for (i, group) in summary.groups.iter().enumerate() {
+            if i > 0 {
+                lines.push(String::new());
+            }
+            let title = format!("{}: {}", group.name, stars(group));
+            lines.push(format!("  {title}"));
+            lines.push(format!("  {}", "-".repeat(title.chars().count())));
+            lines.extend(columns::render_table(
+                summary.columns,
+                &group.repos,
+                summary.diff,
+            ));
+        }
+
+        if summary.total == 0 {
+            lines.push(String::new());
+            lines.push("No repositories found.".to_string());
+            return Ok(lines.join("\n"));
+        }
+        lines.push(String::new());
+        lines.push("Summary:".to_string());
+        for (label, value) in summary_lines(summary) {
+            let label = format!("{label}:");
+            lines.push(format!("  {label:<13} {value}"));
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+pub struct Json;
+
+impl Exporter for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn render(&self, summary: &Summary) -> Result<String, String> {
+        let repos: Vec<serde_json::Value> = summary
+            .repos
+            .iter()
+            .map(|repo| {
+                let fields = summary.columns.iter().map(|col| {
+                    let value = col.value(repo);
+                    let value = match value.parse::<u64>() {
+                        Ok(n) if col.is_numeric() => serde_json::Value::from(n),
+                        _ => serde_json::Value::from(value),
+                    };
+                    (key(*col), value)
+                });
+                serde_json::Value::Object(fields.collect())
+            })
+            .collect();
+        let counts = |counts: &[(String, usize)], field: &str| -> Vec<serde_json::Value> {
+            counts
+                .iter()
+                .map(|(name, count)| serde_json::json!({ field: name, "repos": count }))
+                .collect()
+        };
+
+        let json = serde_json::json!({
+            "owner": summary.owner,
+            "public_repos": summary.total,
+            "sorted_by": summary.sorted_by,
+            "repos": repos,
+            "total_stars": summary.total_stars,
+            "languages": counts(&summary.languages, "language"),
+            "most_starred": summary.most_starred.map(|r| &r.name),
+            "licenses": counts(&summary.licenses, "license"),
+            "archived": summary.archived,
+        });
+        serde_json::to_string_pretty(&json).map_err(|e| format!("Failed to write JSON: {e}"))
+    }
+}
+
+/// Just the table, one row per repo, for spreadsheets.
+pub struct Csv;
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl Exporter for Csv {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn render(&self, summary: &Summary) -> Result<String, String> {
+        let row = |cells: Vec<String>| {
+            cells
+                .iter()
+                .map(|c| csv_field(c))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        let mut lines = vec![row(summary
+            .columns
+            .iter()
+            .map(|c| c.header().to_string())
+            .collect())];
+        for repo in &summary.repos {
+            lines.push(row(summary.columns.iter().map(|c| c.value(repo)).collect()));
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+pub struct Markdown;
+
+fn markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|").replace('\n', " ")
+}
+
+impl Exporter for Markdown {
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+
+    fn render(&self, summary: &Summary) -> Result<String, String> {
+        let mut lines = vec![
+            format!("## {}", summary.owner),
+            String::new(),
+            heading(summary),
+            String::new(),
+        ];
+
+        let headers: Vec<&str> = summary.columns.iter().map(|c| c.header()).collect();
+        let rules: Vec<&str> = summary
+            .columns
+            .iter()
+            .map(|c| if c.is_numeric() { "---:" } else { "---" })
+            .collect();
+        lines.push(format!("| {} |", headers.join(" | ")));
+        lines.push(format!("| {} |", rules.join(" | ")));
+        for repo in &summary.repos {
+            let cells: Vec<String> = summary
+                .columns
+                .iter()
+                .map(|c| markdown_cell(&c.value(repo)))
+                .collect();
+            lines.push(format!("| {} |", cells.join(" | ")));
+        }
+
+        lines.push(String::new());
+        for (label, value) in summary_lines(summary) {
+            lines.push(format!("- **{label}:** {}", markdown_cell(&value)));
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+/// A fragment to paste into a page: heading, table and summary list.
+pub struct Html;
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Exporter for Html {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn render(&self, summary: &Summary) -> Result<String, String> {
+        let mut lines = vec![
+            format!("<h2>{}</h2>", escape_html(&summary.owner)),
+            format!("<p>{}</p>", escape_html(&heading(summary))),
+            "<table>".to_string(),
+        ];
+
+        let headers: String = summary
+            .columns
+            .iter()
+            .map(|c| format!("<th>{}</th>", c.header()))
+            .collect();
+        lines.push(format!("  <tr>{headers}</tr>"));
+        for repo in &summary.repos {
+            let cells: String = summary
+                .columns
+                .iter()
+                .map(|c| {
+                    let value = escape_html(&c.value(repo));
+                    match c {
+                        Column::Url => format!("<td><a href=\"{value}\">{value}</a></td>"),
+                        _ if c.is_numeric() => format!("<td align=\"right\">{value}</td>"),
+                        _ => format!("<td>{value}</td>"),
+                    }
+                })
+                .collect();
+            lines.push(format!("  <tr>{cells}</tr>"));
+        }
+        lines.push("</table>".to_string());
+
+        lines.push("<ul>".to_string());
+        for (label, value) in summary_lines(summary) {
+            lines.push(format!(
+                "  <li><strong>{label}:</strong> {}</li>",
+                escape_html(&value)
+            ));
+        }
+        lines.push("</ul>".to_string());
+        Ok(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{test_license, test_repo};
+
+    fn repo(name: &str, stars: u32, language: &str) -> Repo {
+        Repo {
+            license: test_license("MIT"),
+            description: Some("Tools, \"mostly\" <small>".to_string()),
+            ..test_repo(name, stars, Some(language))
+        }
+    }
+
+    fn render(format: &str, columns: &[Column]) -> String {
+        let repos = [repo("cli", 42, "Rust"), repo("web", 7, "Go")];
+        let refs: Vec<&Repo> = repos.iter().collect();
+        let summary = Summary::new(
+            "me".to_string(),
+            &refs,
+            10,
+            "stars".to_string(),
+            columns,
+            None,
+        );
+        by_name(format).unwrap().render(&summary).unwrap()
+    }
+
+    #[test]
+    fn test_by_name() {
+        assert_eq!(by_name("JSON").unwrap().name(), "json");
+        let err = by_name("yaml").err().unwrap();
+        assert!(err.contains("table, json, csv, markdown, html"));
+    }
+
+    #[test]
+    fn test_table_keeps_the_text_layout() {
+        let text = render("table", &[Column::Name, Column::Stars]);
+        assert!(text.starts_with("\nme\n==\nPublic repos: 2 (showing top 2 by stars)\n"));
+        assert!(text.contains("  cli            42\n"));
+        assert!(text.contains("  Most starred: cli (42 stars)"));
+        assert!(text.contains("  Languages:    Go (1), Rust (1)"));
+
+        let repos = [repo("cli", 42, "Rust")];
+        let refs: Vec<&Repo> = repos.iter().collect();
+        let columns = [Column::Name];
+        let mut summary = Summary::new(
+            "q".to_string(),
+            &refs,
+            10,
+            "stars".to_string(),
+            &columns,
+            None,
+        );
+        summary.kind = "Search results";
+        assert_eq!(
+            heading(&summary),
+            "Search results: 1 (showing top 1 by stars)"
+        );
+    }
+
+    #[test]
+    fn test_table_grouped_by_language() {
+        let repos = [
+            repo("cli", 42, "Rust"),
+            repo("web", 50, "Go"),
+            repo("lib", 9, "Rust"),
+            repo("old", 1, "Rust"),
+        ];
+        let refs: Vec<&Repo> = repos.iter().collect();
+        let columns = [Column::Name, Column::Stars];
+        let summary = Summary::new(
+            "me".to_string(),
+            &refs,
+            2,
+            "stars".to_string(),
+            &columns,
+            None,
+        )
+        .by_language(&refs, 2);
+        let names: Vec<&str> = summary.repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["cli", "lib", "web"]);
+
+        let text = Table.render(&summary).unwrap();
+        assert!(text.contains("Public repos: 4 in 2 languages (showing top 2 by stars in each)"));
+        assert!(text.contains(
+            "  Rust: 3 repos, 52 stars\n  -----------------------\n  \
+             Repository  Stars\n  -----------------\n  cli            42\n  lib             9\n\n  Go:"
+        ));
+        assert!(!text.contains("old"));
+        assert!(text.contains("  Languages:    Rust (3 repos, 52 stars), Go (1 repo, 50 stars)"));
+    }
+
+    #[test]
+    fn test_json_types_numbers() {
+        let json: serde_json::Value =
+            serde_json::from_str(&render("json", &[Column::Name, Column::Stars])).unwrap();
+        assert_eq!(
+            json["repos"][0],
+            serde_json::json!({ "name": "cli", "stars": 42 })
+        );
+        assert_eq!(json["total_stars"], 49);
+        assert_eq!(json["licenses"][0]["license"], "MIT");
+    }
+
+    #[test]
+    fn test_csv_and_markdown_escape_cells() {
+        let columns = [Column::Name, Column::Description];
+        assert_eq!(
+            render("csv", &columns).lines().nth(1),
+            Some("cli,\"Tools, \"\"mostly\"\" <small>\"")
+        );
+        let markdown = render("markdown", &[Column::Name, Column::Stars]);
+        assert!(markdown.contains("| Repository | Stars |\n| --- | ---: |\n| cli | 42 |"));
+    }
+
+    #[test]
+    fn test_html_escapes_text() {
+        let html = render("html", &[Column::Description, Column::Url]);
+        assert!(html.contains("<td>Tools, &quot;mostly&quot; &lt;small&gt;</td>"));
+        assert!(html.contains("<a href=\"https://github.com/me/cli\">"));
+    }
+}
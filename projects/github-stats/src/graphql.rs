@@ -0,0 +1,278 @@
+use crate::api::{License, Repo};
+use serde::Deserialize;
+
+const ENDPOINT: &str = "https://api.github.com/graphql";
+
+/// One page of a user's or organization's public repos with everything the
+/// REST listing gives us, so a page costs one request instead of several.
+const REPOS_QUERY: &str = r#"
+query($login: String!, $cursor: String) {
+  repositoryOwner(login: $login) {
+    repositories(first: 100, after: $cursor, privacy: PUBLIC, ownerAffiliations: [OWNER]) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        databaseId
+        name
+        stargazerCount
+        forkCount
+        issues(states: OPEN) { totalCount }
+        pullRequests(states: OPEN) { totalCount }
+        updatedAt
+        createdAt
+        licenseInfo { spdxId }
+        primaryLanguage { name }
+        description
+        isFork
+        isArchived
+        url
+        defaultBranchRef { name }
+        repositoryTopics(first: 20) { nodes { topic { name } } }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Deserialize)]
+struct Response {
+    data: Option<Data>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphqlError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Data {
+    repository_owner: Option<Owner>,
+}
+
+#[derive(Deserialize)]
+struct Owner {
+    repositories: Connection<RepoNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Connection<T> {
+    #[serde(default)]
+    page_info: Option<PageInfo>,
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Count {
+    #[serde(rename = "totalCount")]
+    total_count: u32,
+}
+
+#[derive(Deserialize)]
+struct Named {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxId {
+    spdx_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TopicNode {
+    topic: Named,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepoNode {
+    database_id: Option<u64>,
+    name: String,
+    stargazer_count: u32,
+    fork_count: u32,
+    issues: Count,
+    pull_requests: Count,
+    updated_at: String,
+    created_at: String,
+    license_info: Option<SpdxId>,
+    primary_language: Option<Named>,
+    description: Option<String>,
+    is_fork: bool,
+    is_archived: bool,
+    url: String,
+    /// Missing when the repository is empty
+    default_branch_ref: Option<Named>,
+    repository_topics: Connection<TopicNode>,
+}
+
+impl From<RepoNode> for Repo {
+    fn from(node: RepoNode) -> Self {
+        Repo {
+            id: node.database_id.unwrap_or(0),
+            name: node.name,
+            stargazers_count: node.stargazer_count,
+            forks_count: node.fork_count,
+            // REST's open_issues_count includes pull requests
+            open_issues_count: node.issues.total_count + node.pull_requests.total_count,
+            updated_at: node.updated_at,
+            created_at: node.created_at,
+            license: node.license_info.map(|l| License { spdx_id: l.spdx_id }),
+            language: node.primary_language.map(|l| l.name),
+            description: node.description,
+            fork: node.is_fork,
+            html_url: node.url,
+            topics: node
+                .repository_topics
+                .nodes
+                .into_iter()
+                .map(|t| t.topic.name)
+                .collect(),
+            archived: node.is_archived,
+            default_branch: node.default_branch_ref.map(|b| b.name).unwrap_or_default(),
+        }
+    }
+}
+
+/// The same repos as `api::fetch_repos`, fetched 100 at a time through the
+/// GraphQL API. GitHub only serves GraphQL to authenticated clients.
+pub async fn fetch_repos(username: &str, token: &str) -> Result<Vec<Repo>, String> {
+    let client = reqwest::Client::new();
+    let mut repos = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    loop {
+        let body = serde_json::json!({
+            "query": REPOS_QUERY,
+            "variables": { "login": username, "cursor": cursor },
+        });
+
+        let response = client
+            .post(ENDPOINT)
+            .header("User-Agent", crate::api::USER_AGENT)
+            .bearer_auth(token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {e}"))?;
+
+        if !response.status().is_success() {
+            return Err(format!("GitHub GraphQL error: {}", response.status()));
+        }
+
+        let response: Response = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {e}"))?;
+        let (page, next) = parse_page(response, username)?;
+        repos.extend(page);
+
+        match next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    Ok(repos)
+}
+
+/// A page of repos and the cursor for the next page, if there is one.
+fn parse_page(response: Response, username: &str) -> Result<(Vec<Repo>, Option<String>), String> {
+    if !response.errors.is_empty() {
+        let messages: Vec<String> = response.errors.into_iter().map(|e| e.message).collect();
+        return Err(format!("GitHub GraphQL error: {}", messages.join("; ")));
+    }
+
+    let owner = response
+        .data
+        .and_then(|d| d.repository_owner)
+        .ok_or(format!("User '{username}' not found"))?;
+
+    let connection = owner.repositories;
+    let next = connection
+        .page_info
+        .filter(|p| p.has_next_page)
+        .and_then(|p| p.end_cursor);
+    let repos = connection.nodes.into_iter().map(Repo::from).collect();
+
+    Ok((repos, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(json: serde_json::Value) -> Response {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_maps_nodes_to_repos() {
+        let page = response(serde_json::json!({
+            "data": { "repositoryOwner": { "repositories": {
+                "pageInfo": { "hasNextPage": true, "endCursor": "abc" },
+                "nodes": [{
+                    "databaseId": 42,
+                    "name": "tool",
+                    "stargazerCount": 10,
+                    "forkCount": 2,
+                    "issues": { "totalCount": 3 },
+                    "pullRequests": { "totalCount": 1 },
+                    "updatedAt": "2024-05-01T00:00:00Z",
+                    "createdAt": "2020-01-15T00:00:00Z",
+                    "licenseInfo": { "spdxId": "MIT" },
+                    "primaryLanguage": { "name": "Rust" },
+                    "description": null,
+                    "isFork": false,
+                    "isArchived": true,
+                    "url": "https://github.com/me/tool",
+                    "defaultBranchRef": { "name": "main" },
+                    "repositoryTopics": { "nodes": [{ "topic": { "name": "cli" } }] }
+                }]
+            }}}
+        }));
+
+        let (repos, next) = parse_page(page, "me").unwrap();
+        assert_eq!(next.as_deref(), Some("abc"));
+
+        let repo = &repos[0];
+        assert_eq!(repo.id, 42);
+        assert_eq!(repo.stargazers_count, 10);
+        assert_eq!(repo.open_issues_count, 4);
+        assert_eq!(repo.language.as_deref(), Some("Rust"));
+        assert_eq!(
+            repo.license.as_ref().unwrap().spdx_id.as_deref(),
+            Some("MIT")
+        );
+        assert_eq!(repo.topics, vec!["cli"]);
+        assert!(repo.archived);
+        assert_eq!(repo.default_branch, "main");
+        assert_eq!(repo.created_at, "2020-01-15T00:00:00Z");
+    }
+
+    #[test]
+    fn test_missing_owner_and_errors() {
+        let missing = response(serde_json::json!({ "data": { "repositoryOwner": null } }));
+        assert_eq!(
+            parse_page(missing, "ghost").unwrap_err(),
+            "User 'ghost' not found"
+        );
+
+        let failed = response(serde_json::json!({
+            "data": null,
+            "errors": [{ "message": "Bad credentials" }]
+        }));
+        assert!(parse_page(failed, "me")
+            .unwrap_err()
+            .contains("Bad credentials"));
+    }
+}
@@ -0,0 +1,310 @@
+use crate::api::{Commit, CommunityProfile, Contributor, Release, RepoIssue};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Recently opened issues looked at for responsiveness
+pub const ISSUE_SAMPLE: usize = 30;
+
+/// Releases looked at for cadence
+pub const RELEASE_SAMPLE: usize = 30;
+
+/// Each part of the score is out of this many points
+const PART: u32 = 20;
+
+/// What the health score is computed from, gathered from five endpoints.
+/// This is what gets cached, so `--offline` rescores against today's date.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct Signals {
+    pub license: bool,
+    /// A contributing guide or code of conduct
+    pub community_files: bool,
+    /// YYYY-MM-DD of the latest commit on the default branch
+    pub last_commit: Option<String>,
+    /// Issues (not pull requests) among the recently opened ones
+    pub issues: usize,
+    /// Of those, how many got a comment or were closed
+    pub responded: usize,
+    /// YYYY-MM-DD of each recent release, newest first
+    pub releases: Vec<String>,
+    pub contributors: usize,
+}
+
+impl Signals {
+    pub fn new(
+        profile: &CommunityProfile,
+        latest: Option<&Commit>,
+        issues: &[RepoIssue],
+        releases: &[Release],
+        contributors: &[Contributor],
+    ) -> Signals {
+        let date = |s: &str| s.chars().take(10).collect::<String>();
+        let issues: Vec<&RepoIssue> = issues.iter().filter(|i| i.pull_request.is_none()).collect();
+        Signals {
+            license: profile.files.license.is_some(),
+            community_files: profile.files.contributing.is_some()
+                || profile.files.code_of_conduct.is_some(),
+            last_commit: latest
+                .and_then(|c| c.commit.committer.as_ref())
+                .map(|p| date(&p.date)),
+            issues: issues.len(),
+            responded: issues
+                .iter()
+                .filter(|i| i.comments > 0 || i.closed_at.is_some())
+                .count(),
+            releases: releases
+                .iter()
+                .filter_map(|r| r.published_at.as_deref())
+                .map(date)
+                .collect(),
+            contributors: contributors.len(),
+        }
+    }
+}
+
+/// Points for each part of the health score, each out of 20.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Score {
+    /// 15 for a license, 5 for a contributing guide or code of conduct
+    pub license: u32,
+    /// How recently anything was committed
+    pub activity: u32,
+    /// Share of recent issues that got a response. A repo with no issues
+    /// has kept nobody waiting and gets full marks.
+    pub issues: u32,
+    /// Releases in the last year
+    pub releases: u32,
+    pub contributors: u32,
+}
+
+fn days_since(date: &str, today: NaiveDate) -> Option<i64> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Some((today - date).num_days())
+}
+
+impl Score {
+    pub fn new(signals: &Signals, today: NaiveDate) -> Score {
+        let license =
+            if signals.license { 15 } else { 0 } + if signals.community_files { 5 } else { 0 };
+
+        let activity = match signals
+            .last_commit
+            .as_deref()
+            .and_then(|d| days_since(d, today))
+        {
+            Some(0..=30) => 20,
+            Some(31..=90) => 15,
+            Some(91..=180) => 10,
+            Some(181..=365) => 5,
+            _ => 0,
+        };
+
+        let issues = match signals.issues {
+            0 => PART,
+            total => (PART as usize * signals.responded).div_ceil(total) as u32,
+        };
+
+        let last_year = signals
+            .releases
+            .iter()
+            .filter_map(|d| days_since(d, today))
+            .filter(|&days| days <= 365)
+            .count();
+        let releases = match (last_year, signals.releases.len()) {
+            (0, 0) => 0,
+            (0, _) => 5,
+            (1, _) => 10,
+            (2..=3, _) => 15,
+            _ => 20,
+        };
+
+        let contributors = match signals.contributors {
+            0 => 0,
+            1 => 5,
+            2..=4 => 10,
+            5..=9 => 15,
+            _ => 20,
+        };
+
+        Score {
+            license,
+            activity,
+            issues,
+            releases,
+            contributors,
+        }
+    }
+
+    /// Out of 100
+    pub fn total(&self) -> u32 {
+        self.license + self.activity + self.issues + self.releases + self.contributors
+    }
+
+    pub fn grade(&self) -> char {
+        match self.total() {
+            90.. => 'A',
+            75..=89 => 'B',
+            60..=74 => 'C',
+            40..=59 => 'D',
+            _ => 'F',
+        }
+    }
+}
+
+/// One row per repo with its points for each part, the total and the
+/// grade. Repos whose signals couldn't be fetched show "n/a".
+pub fn render_table(rows: &[(String, Option<Score>)]) -> Vec<String> {
+    const HEADERS: [&str; 7] = [
+        "License",
+        "Activity",
+        "Issues",
+        "Releases",
+        "Contributors",
+        "Score",
+        "Grade",
+    ];
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain(["Repository".len()])
+        .max()
+        .unwrap_or(0);
+
+    let line = |name: &str, cells: Vec<String>| {
+        let mut out = format!("  {name:<name_width$}");
+        for (cell, header) in cells.iter().zip(HEADERS) {
+            out.push_str(&format!("  {cell:>width$}", width = header.len()));
+        }
+        out.trim_end().to_string()
+    };
+
+    let mut lines = vec![line(
+        "Repository",
+        HEADERS.iter().map(|h| h.to_string()).collect(),
+    )];
+    lines.push(format!("  {}", "-".repeat(lines[0].chars().count() - 2)));
+
+    for (name, score) in rows {
+        let cells = match score {
+            Some(s) => vec![
+                s.license.to_string(),
+                s.activity.to_string(),
+                s.issues.to_string(),
+                s.releases.to_string(),
+                s.contributors.to_string(),
+                s.total().to_string(),
+                s.grade().to_string(),
+            ],
+            None => vec!["n/a".to_string()],
+        };
+        lines.push(line(name, cells));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()
+    }
+
+    fn healthy() -> Signals {
+        Signals {
+            license: true,
+            community_files: true,
+            last_commit: Some("2024-05-20".to_string()),
+            issues: 10,
+            responded: 9,
+            releases: vec![
+                "2024-05-01".to_string(),
+                "2024-01-15".to_string(),
+                "2022-03-01".to_string(),
+            ],
+            contributors: 12,
+        }
+    }
+
+    #[test]
+    fn test_signals_from_responses() {
+        let profile: CommunityProfile = serde_json::from_str(
+            r#"{"health_percentage": 71, "files": {"license": {"spdx_id": "MIT"}, "contributing": null, "code_of_conduct": null}}"#,
+        )
+        .unwrap();
+        let latest: Commit =
+            serde_json::from_str(r#"{"commit": {"committer": {"date": "2024-05-20T08:00:00Z"}}}"#)
+                .unwrap();
+        let issues: Vec<RepoIssue> = serde_json::from_str(
+            r#"[
+                {"closed_at": null, "comments": 0},
+                {"closed_at": "2024-05-02T00:00:00Z", "comments": 0},
+                {"closed_at": null, "comments": 3},
+                {"closed_at": null, "comments": 1, "pull_request": {}}
+            ]"#,
+        )
+        .unwrap();
+        let releases: Vec<Release> = serde_json::from_str(
+            r#"[{"tag_name": "v1.1", "name": null, "published_at": "2024-05-01T10:00:00Z"},
+                {"tag_name": "draft", "name": null, "published_at": null}]"#,
+        )
+        .unwrap();
+        let contributors: Vec<Contributor> =
+            serde_json::from_str(r#"[{"login": "a", "contributions": 40}, {"login": "b"}]"#)
+                .unwrap();
+
+        let signals = Signals::new(&profile, Some(&latest), &issues, &releases, &contributors);
+        assert_eq!(
+            signals,
+            Signals {
+                license: true,
+                community_files: false,
+                last_commit: Some("2024-05-20".to_string()),
+                issues: 3,
+                responded: 2,
+                releases: vec!["2024-05-01".to_string()],
+                contributors: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_score_and_grade() {
+        let score = Score::new(&healthy(), today());
+        assert_eq!(
+            score,
+            Score {
+                license: 20,
+                activity: 20,
+                issues: 18,
+                releases: 15,
+                contributors: 20,
+            }
+        );
+        assert_eq!((score.total(), score.grade()), (93, 'A'));
+
+        let abandoned = Signals {
+            last_commit: Some("2021-01-01".to_string()),
+            ..Signals::default()
+        };
+        let score = Score::new(&abandoned, today());
+        // No issues means nobody was left waiting
+        assert_eq!((score.issues, score.total(), score.grade()), (20, 20, 'F'));
+    }
+
+    #[test]
+    fn test_render_table() {
+        let rows = vec![
+            ("tool".to_string(), Some(Score::new(&healthy(), today()))),
+            ("empty".to_string(), None),
+        ];
+        assert_eq!(
+            render_table(&rows),
+            vec![
+                "  Repository  License  Activity  Issues  Releases  Contributors  Score  Grade",
+                "  ---------------------------------------------------------------------------",
+                "  tool             20        20      18        15            20     93      A",
+                "  empty           n/a",
+            ]
+        );
+    }
+}
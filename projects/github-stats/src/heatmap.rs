@@ -0,0 +1,176 @@
+use crate::api::Event;
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+/// Weeks shown: the events API only reaches back 90 days.
+pub const WEEKS: usize = 13;
+
+/// From no commits to the busiest day, like the contribution graph's greens.
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// Rows run Sunday to Saturday like GitHub's graph, labeling every other day.
+const ROW_LABELS: [&str; 7] = ["", "Mon", "", "Wed", "", "Fri", ""];
+
+/// Commits per day from push events. A push without a commit count counts
+/// as one commit.
+pub fn daily_commits(events: &[Event]) -> BTreeMap<NaiveDate, u32> {
+    let mut days = BTreeMap::new();
+    for event in events.iter().filter(|e| e.kind == "PushEvent") {
+        let Some(date) = event
+            .created_at
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        *days.entry(date).or_insert(0) += event.payload.size.unwrap_or(1);
+    }
+    days
+}
+
+/// 0 for no commits, otherwise 1–4 by how close the day is to the busiest.
+fn level(count: u32, max: u32) -> usize {
+    if count == 0 || max == 0 {
+        return 0;
+    }
+    (count * 4).div_ceil(max).clamp(1, 4) as usize
+}
+
+/// A weekday-by-week grid of the `weeks` weeks up to and including the one
+/// containing `today`, with month names over the weeks they start in and a
+/// shading legend underneath.
+pub fn render(days: &BTreeMap<NaiveDate, u32>, today: NaiveDate, weeks: usize) -> Vec<String> {
+    let this_sunday = today - Duration::days(today.weekday().num_days_from_sunday() as i64);
+    let start = this_sunday - Duration::weeks(weeks as i64 - 1);
+    let max = days
+        .range(start..=today)
+        .map(|(_, n)| *n)
+        .max()
+        .unwrap_or(0);
+
+    // Each week is a two-character column: the shade and a space
+    let mut months = String::new();
+    for week in 0..weeks {
+        let sunday = start + Duration::weeks(week as i64);
+        let starts_month = week == 0 || (sunday + Duration::days(6)).month() != sunday.month();
+        let column = week * 2;
+        if starts_month && months.chars().count() <= column {
+            let first = if week == 0 {
+                sunday
+            } else {
+                sunday + Duration::days(6)
+            };
+            months.push_str(&" ".repeat(column - months.chars().count()));
+            months.push_str(&first.format("%b").to_string());
+        }
+    }
+
+    let mut lines = vec![format!("    {months}").trim_end().to_string()];
+    for (row, label) in ROW_LABELS.iter().enumerate() {
+        let cells: Vec<String> = (0..weeks)
+            .map(|week| {
+                let date = start + Duration::days((week * 7 + row) as i64);
+                if date > today {
+                    return " ".to_string();
+                }
+                let count = days.get(&date).copied().unwrap_or(0);
+                SHADES[level(count, max)].to_string()
+            })
+            .collect();
+        lines.push(
+            format!("{label:<3} {}", cells.join(" "))
+                .trim_end()
+                .to_string(),
+        );
+    }
+
+    let legend: Vec<String> = SHADES.iter().map(char::to_string).collect();
+    lines.push(format!("    Less {} More", legend.join(" ")));
+    lines
+}
+
+/// "123 commits on 45 days; busiest: Tue 2024-05-14 (12)"
+pub fn summary(days: &BTreeMap<NaiveDate, u32>) -> String {
+    let total: u32 = days.values().sum();
+    // Earliest wins a tie, so iterate newest first and keep the last max
+    let busiest = days.iter().rev().max_by_key(|(_, n)| **n);
+
+    match busiest {
+        Some((date, n)) => format!(
+            "{total} commits on {} days; busiest: {} ({n})",
+            days.len(),
+            date.format("%a %Y-%m-%d")
+        ),
+        None => "No public commits in the last 90 days".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::EventPayload;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn event(kind: &str, at: &str, size: Option<u32>) -> Event {
+        Event {
+            kind: kind.to_string(),
+            created_at: at.to_string(),
+            repo: Default::default(),
+            payload: EventPayload {
+                size,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_daily_commits_counts_pushes_only() {
+        let events = vec![
+            event("PushEvent", "2024-05-01T10:00:00Z", Some(3)),
+            event("PushEvent", "2024-05-01T18:00:00Z", None),
+            event("WatchEvent", "2024-05-01T19:00:00Z", None),
+            event("PushEvent", "2024-05-03T09:00:00Z", Some(2)),
+        ];
+        let days = daily_commits(&events);
+        assert_eq!(days.get(&date("2024-05-01")), Some(&4));
+        assert_eq!(days.get(&date("2024-05-03")), Some(&2));
+        assert_eq!(days.len(), 2);
+        assert_eq!(
+            summary(&days),
+            "6 commits on 2 days; busiest: Wed 2024-05-01 (4)"
+        );
+    }
+
+    #[test]
+    fn test_levels() {
+        assert_eq!(level(0, 10), 0);
+        assert_eq!(level(1, 10), 1);
+        assert_eq!(level(5, 10), 2);
+        assert_eq!(level(10, 10), 4);
+    }
+
+    #[test]
+    fn test_render() {
+        // Wednesday 2024-05-01 is the busiest; the current week stops at
+        // Thursday 2024-05-02
+        let days = BTreeMap::from([(date("2024-04-22"), 1), (date("2024-05-01"), 4)]);
+        let lines = render(&days, date("2024-05-02"), 2);
+        assert_eq!(
+            lines,
+            vec![
+                "    Apr",
+                "    · ·",
+                "Mon ░ ·",
+                "    · ·",
+                "Wed · █",
+                "    · ·",
+                "Fri ·",
+                "    ·",
+                "    Less · ░ ▒ ▓ █ More",
+            ]
+        );
+    }
+}
@@ -1,56 +1,1103 @@
+mod activity;
 mod api;
+mod attention;
+mod badge;
+mod cache;
+mod ci;
+mod columns;
+mod dependencies;
+mod diff;
 mod display;
+mod export;
+mod graphql;
+mod health;
+mod heatmap;
+mod org;
+mod query;
+mod redact;
+mod snapshot;
+mod stars;
+mod trend;
+mod tui;
 
-use clap::Parser;
+use api::Transport;
+use clap::{Parser, Subcommand, ValueEnum};
+use columns::Column;
+use display::GroupBy;
+use export::Exporter;
+use query::{Filter, SortKey};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// How many repos `attention` checks at once
+const ATTENTION_CONCURRENCY: usize = 8;
 
 /// Fetch and display GitHub repository statistics for a user or organization
 #[derive(Parser)]
-#[command(name = "github-stats", version, about)]
+#[command(
+    name = "github-stats",
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// GitHub username or organization
-    username: String,
+    #[arg(required = true)]
+    username: Option<String>,
 
     /// Maximum number of repos to display
     #[arg(short, long, default_value_t = 10)]
     limit: usize,
 
-    /// Sort by: stars or name
-    #[arg(short, long, default_value = "stars")]
-    sort: String,
+    /// Sort keys, comma-separated. Counts and dates sort highest first and
+    /// text A to Z; a leading + or - forces ascending or descending, e.g.
+    /// "stars,+name"
+    #[arg(
+        short,
+        long,
+        default_value = "stars",
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        value_parser = query::parse_sort_key
+    )]
+    sort: Vec<SortKey>,
+
+    /// Only show repos matching an expression over name, stars, forks,
+    /// issues, updated, license, language, description, archived and fork,
+    /// e.g. "stars > 100 && language == 'Rust'"
+    #[arg(long, value_parser = query::parse_filter)]
+    filter: Option<Filter>,
 
     /// Filter by programming language (case-insensitive)
     #[arg(long)]
     language: Option<String>,
+
+    /// Leave out archived repos
+    #[arg(long)]
+    exclude_archived: bool,
+
+    /// Print a section per language, each with its own top --limit repos
+    /// and star subtotal, instead of one table
+    #[arg(long, value_enum, value_name = "KEY", conflicts_with = "tui")]
+    group_by: Option<GroupBy>,
+
+    /// Columns to show, comma-separated
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "name,stars,language"
+    )]
+    columns: Vec<Column>,
+
+    /// Also summarize each shown repo's direct dependencies by ecosystem
+    #[arg(long)]
+    dependencies: bool,
+
+    /// Also grade each shown repo's open-source health from its license,
+    /// latest commit, responses to recent issues, release cadence and
+    /// contributor count. Takes five requests per repo; set GITHUB_TOKEN for
+    /// a higher rate limit
+    #[arg(long)]
+    health: bool,
+
+    /// Also show how each shown repo's latest GitHub Actions run on its
+    /// default branch ended and how many of the last 20 passed, flagging
+    /// repos whose CI keeps failing
+    #[arg(long)]
+    ci: bool,
+
+    /// Highlight new, renamed, and archived repos and star changes since the
+    /// previous run for this user
+    #[arg(long, conflicts_with = "offline")]
+    diff: bool,
+
+    /// Also draw a weekday-by-week heatmap of the user's commits over the
+    /// last 90 days, from their public push events
+    #[arg(long)]
+    heatmap: bool,
+
+    /// Also chart how the primary languages of new repos shifted, by the
+    /// year each repo was created
+    #[arg(long)]
+    trend: bool,
+
+    /// Also write an SVG badge of the total stars across the user's repos
+    /// (after filtering, forks left out) to this file, for a profile README
+    #[arg(long, value_name = "FILE", conflicts_with = "tui")]
+    badge: Option<PathBuf>,
+
+    /// Browse repos interactively instead of printing a table
+    #[arg(long, conflicts_with_all = ["ci", "dependencies", "health", "heatmap", "trend"])]
+    tui: bool,
+
+    /// Output format for the repo listing: table, json, csv, markdown or
+    /// html. Only table can be combined with --diff, --dependencies,
+    /// --health, --ci, --heatmap, --trend and --group-by
+    #[arg(long, default_value = "table", value_parser = export::by_name)]
+    format: &'static dyn Exporter,
+
+    /// Replace repo and owner names with stable hashes and leave out
+    /// descriptions, so output can be shared. Stats are unchanged
+    #[arg(long, conflicts_with = "tui")]
+    redact: bool,
+
+    /// Also upload the listing as a markdown report to a secret gist and
+    /// print its URL. Needs a token with the gist scope in GITHUB_TOKEN
+    #[arg(long, value_enum, value_name = "TARGET", conflicts_with = "tui")]
+    publish: Option<Publish>,
+
+    /// Fetch repos through the GraphQL API: one request per 100 repos.
+    /// Needs a token in GITHUB_TOKEN
+    #[arg(long, conflicts_with_all = ["resume", "record", "replay"])]
+    graphql: bool,
+
+    /// Don't touch the network; render from previously cached data
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Carry on with a fetch that was cut short (Ctrl-C, a rate limit) from
+    /// the page it stopped on, instead of starting over
+    #[arg(long, global = true, conflicts_with = "offline")]
+    resume: bool,
+
+    /// Save every API response as a JSON fixture in this directory, for
+    /// --replay and tests
+    #[arg(long, global = true, value_name = "DIR", conflicts_with_all = ["offline", "replay"])]
+    record: Option<PathBuf>,
+
+    /// Answer API requests from fixtures saved with --record instead of the
+    /// network
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "offline")]
+    replay: Option<PathBuf>,
+}
+
+/// Where `--publish` uploads the report
+#[derive(Clone, Copy, ValueEnum)]
+enum Publish {
+    Gist,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show star history for a single repository
+    Stars(StarsArgs),
+    /// List an organization's members with their public repo counts.
+    /// Needs a token in GITHUB_TOKEN
+    Members(OrgArgs),
+    /// List an organization's teams with their member counts. Needs a token
+    /// with the read:org scope in GITHUB_TOKEN
+    Teams(OrgArgs),
+    /// Compare two saved copies of a user's cached repo list
+    /// (~/.cache/github-stats/repos-USER.json): stars gained per repo, repos
+    /// added and removed, and shifts in language. Works offline
+    DiffSnapshots(DiffSnapshotsArgs),
+    /// Triage your own repos: pull requests awaiting review, issues with no
+    /// reply, and failing checks on default branches. Needs a token in
+    /// GITHUB_TOKEN; forks and archived repos are skipped
+    Attention(AttentionArgs),
+    /// Summarize a user's recent public activity: pushes per repo, pull
+    /// requests opened, issue comments and releases published
+    Events(EventsArgs),
+    /// Search all of GitHub's repos, e.g. "language:rust topic:cli
+    /// stars:>500". Set GITHUB_TOKEN to search three times as fast
+    Search(SearchArgs),
+}
+
+#[derive(clap::Args)]
+struct SearchArgs {
+    /// A query in GitHub's search syntax
+    query: String,
+
+    /// How many repos to show, at most 1000
+    #[arg(short, long, default_value_t = 30)]
+    limit: usize,
+
+    /// Order of the results
+    #[arg(short, long, value_enum, default_value = "stars")]
+    sort: api::SearchSort,
+
+    /// Columns to show, comma-separated
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "name,stars,language,description"
+    )]
+    columns: Vec<Column>,
+
+    /// Output format: table, json, csv, markdown or html
+    #[arg(long, default_value = "table", value_parser = export::by_name)]
+    format: &'static dyn Exporter,
+}
+
+#[derive(clap::Args)]
+struct EventsArgs {
+    /// GitHub username
+    username: String,
+
+    /// Count the events of this many days up to today; GitHub keeps 90
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..=90))]
+    days: u32,
+}
+
+#[derive(clap::Args)]
+struct AttentionArgs {
+    /// Flag issues that have gone this many days without a reply
+    #[arg(long, default_value_t = 7)]
+    days: u32,
+}
+
+#[derive(clap::Args)]
+struct DiffSnapshotsArgs {
+    /// The earlier snapshot
+    old: PathBuf,
+    /// The later snapshot
+    new: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct OrgArgs {
+    /// Organization login
+    org: String,
+}
+
+#[derive(clap::Args)]
+struct StarsArgs {
+    /// Repository as owner/name
+    repo: String,
+
+    /// Draw a cumulative star chart over time
+    #[arg(long)]
+    graph: bool,
+
+    /// Only chart stars from this date on (YYYY-MM-DD or YYYY-MM)
+    #[arg(long, value_parser = stars::parse_since)]
+    since: Option<String>,
+}
+
+/// Run `fetch` and cache the result under `key`, or with `--offline` skip the
+/// network entirely and return what was cached last time. `what` describes
+/// the data for the error shown when nothing is cached. Replayed data isn't
+/// cached.
+async fn fetch_or_cached<T, F>(
+    transport: &Transport,
+    key: &str,
+    offline: bool,
+    what: &str,
+    fetch: F,
+) -> Result<T, String>
+where
+    T: Serialize + DeserializeOwned,
+    F: Future<Output = Result<T, String>>,
+{
+    if offline {
+        let cached = cache::load::<T>(key)?.ok_or(format!(
+            "No cached data for {what}. Run once without --offline to cache it."
+        ))?;
+        // On stderr, so it doesn't end up in an exported file
+        eprintln!(
+            "Offline: using data cached {}",
+            cache::format_age(cached.fetched_at, cache::now_secs())
+        );
+        return Ok(cached.data);
+    }
+
+    let data = fetch.await?;
+    if transport.is_replay() {
+        return Ok(data);
+    }
+
+    // A failed cache write shouldn't fail the command
+    if let Err(e) = cache::save(key, &data) {
+        eprintln!("Warning: {e}");
+    }
+
+    Ok(data)
+}
+
+/// The token in GITHUB_TOKEN. `what` names the option or command that
+/// needs it, for the error.
+fn github_token(what: &str) -> Result<String, String> {
+    std::env::var("GITHUB_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .ok_or(format!(
+            "{what} needs a GitHub token in the GITHUB_TOKEN environment variable"
+        ))
+}
+
+async fn run_repos(args: Args, transport: &Transport) -> Result<(), String> {
+    let username = args.username.unwrap_or_default();
+    let table = args.format.name() == "table";
+    if !table
+        && (args.diff
+            || args.dependencies
+            || args.health
+            || args.ci
+            || args.heatmap
+            || args.trend
+            || args.tui
+            || args.group_by.is_some())
+    {
+        return Err(format!(
+            "--format {} prints only the repo listing; drop --diff, --dependencies, --health, --ci, --heatmap, --trend, --tui and --group-by",
+            args.format.name()
+        ));
+    }
+    let shown_name = if args.redact {
+        redact::owner(&username)
+    } else {
+        username.clone()
+    };
+    // Checked before fetching, so a missing token doesn't waste the fetch
+    let publish_token = match args.publish {
+        Some(Publish::Gist) => Some(github_token("--publish gist")?),
+        None => None,
+    };
+
+    // Other formats keep stdout for the export alone
+    if !args.offline && table {
+        println!("Fetching repos for {shown_name}...");
+    } else if !args.offline {
+        eprintln!("Fetching repos for {shown_name}...");
+    }
+
+    // The previous run's list, before this fetch replaces it in the cache
+    let previous = if args.diff {
+        cache::load::<Vec<api::Repo>>(&format!("repos-{username}"))?
+    } else {
+        None
+    };
+
+    let fetch = async {
+        if args.graphql {
+            graphql::fetch_repos(&username, &github_token("--graphql")?).await
+        } else {
+            api::fetch_repos(transport, &username, args.resume).await
+        }
+    };
+    let mut repos = fetch_or_cached(
+        transport,
+        &format!("repos-{username}"),
+        args.offline,
+        &format!("user '{username}'"),
+        fetch,
+    )
+    .await?;
+
+    if let Some(ref lang) = args.language {
+        let lang_lower = lang.to_lowercase();
+        repos.retain(|r| {
+            r.language
+                .as_ref()
+                .map(|l| l.to_lowercase() == lang_lower)
+                .unwrap_or(false)
+        });
+
+        if repos.is_empty() {
+            println!("No {lang} repositories found for {shown_name}");
+            return Ok(());
+        }
+    }
+
+    if args.exclude_archived {
+        repos.retain(|r| !r.archived);
+    }
+
+    if let Some(ref filter) = args.filter {
+        repos.retain(|r| filter.matches(r));
+        if repos.is_empty() {
+            println!("No repositories matching the filter found for {shown_name}");
+            return Ok(());
+        }
+    }
+
+    if let Some(path) = &args.badge {
+        let stars: u64 = repos
+            .iter()
+            .filter(|r| !r.fork)
+            .map(|r| r.stargazers_count as u64)
+            .sum();
+        std::fs::write(path, badge::stars(stars))
+            .map_err(|e| format!("Couldn't write {}: {e}", path.display()))?;
+        let note = format!(
+            "Wrote a total stars badge ({}) to {}",
+            badge::compact(stars),
+            path.display()
+        );
+        if table {
+            println!("{note}");
+        } else {
+            eprintln!("{note}");
+        }
+    }
+
+    if args.tui {
+        repos.retain(|r| !r.fork);
+        return tui::run(&username, repos, args.columns, args.offline, transport).await;
+    }
+
+    let changes = previous
+        .as_ref()
+        .map(|p| (diff::diff(&p.data, &repos), p.fetched_at));
+
+    let report = display::display_repos(
+        &username,
+        &repos,
+        args.limit,
+        &args.sort,
+        &args.columns,
+        changes.as_ref().map(|(d, _)| d),
+        args.redact,
+        args.group_by,
+        args.format,
+    )?;
+
+    if let Some(token) = &publish_token {
+        let url = api::create_gist(
+            transport,
+            &format!("github-stats-{shown_name}.md"),
+            &format!("GitHub repo stats for {shown_name}"),
+            &report,
+            token,
+        )
+        .await?;
+        let note = format!("Published the report to {url}");
+        if table {
+            println!("\n{note}");
+        } else {
+            eprintln!("{note}");
+        }
+    }
+
+    match &changes {
+        Some((changes, fetched_at)) => display::display_changes(
+            changes,
+            &cache::format_age(*fetched_at, cache::now_secs()),
+            args.redact,
+        ),
+        None if args.diff => println!(
+            "\nNo previous run for {shown_name} to compare against; the next --diff will compare with this one."
+        ),
+        None => {}
+    }
+
+    let shown: Vec<&api::Repo> = display::sorted_repos(&repos, &args.sort)
+        .into_iter()
+        .take(args.limit)
+        .collect();
+    if args.dependencies {
+        run_dependencies(transport, &username, &shown, args.offline, args.redact).await;
+    }
+
+    if args.health {
+        run_health(transport, &username, &shown, args.offline, args.redact).await;
+    }
+
+    if args.ci {
+        run_ci(transport, &username, &shown, args.offline, args.redact).await;
+    }
+
+    if args.trend {
+        display_trend(&repos);
+    }
+
+    if args.heatmap {
+        run_heatmap(transport, &username, args.offline).await?;
+    }
+
+    Ok(())
+}
+
+/// Forks are left out: their language says more about the upstream project.
+fn display_trend(repos: &[api::Repo]) {
+    let own: Vec<&api::Repo> = repos.iter().filter(|r| !r.fork).collect();
+    let trend = trend::aggregate(&own);
+    if trend.years.is_empty() {
+        println!("\nNo creation dates to chart; run without --offline to refresh the cache.");
+        return;
+    }
+
+    println!("\nLanguages by creation year:");
+    for line in trend::render(&trend, trend::WIDTH) {
+        println!("{line}");
+    }
+    if trend.undated > 0 {
+        println!(
+            "\n  {} repos have no creation date in the cache; run without --offline to refresh it.",
+            trend.undated
+        );
+    }
+}
+
+async fn run_heatmap(transport: &Transport, username: &str, offline: bool) -> Result<(), String> {
+    if !offline {
+        println!("\nFetching recent activity...");
+    }
+
+    let events = fetch_or_cached(
+        transport,
+        &format!("events-v2-{username}"),
+        offline,
+        &format!("user '{username}'"),
+        api::fetch_events(transport, username),
+    )
+    .await?;
+    let days = heatmap::daily_commits(&events);
+    let today = chrono::Local::now().date_naive();
+
+    println!("\nCommits by day:");
+    for line in heatmap::render(&days, today, heatmap::WEEKS) {
+        println!("  {line}");
+    }
+    println!("\n  {}", heatmap::summary(&days));
+
+    Ok(())
+}
+
+/// Print direct dependency counts for each repo. A repo without a readable
+/// dependency graph is reported and skipped rather than failing the run.
+async fn run_dependencies(
+    transport: &Transport,
+    owner: &str,
+    repos: &[&api::Repo],
+    offline: bool,
+    redacted: bool,
+) {
+    if !offline {
+        println!("\nFetching dependency graphs...");
+    }
+
+    let mut rows = Vec::new();
+    for repo in repos {
+        let full_name = format!("{owner}/{}", repo.name);
+        let fetch = async {
+            api::fetch_sbom(transport, &full_name)
+                .await
+                .map(|s| dependencies::count_direct(&s))
+        };
+
+        let counts = match fetch_or_cached(
+            transport,
+            &format!("deps-{full_name}"),
+            offline,
+            &format!("repository '{full_name}'"),
+            fetch,
+        )
+        .await
+        {
+            Ok(counts) => Some(counts),
+            Err(_) if redacted => {
+                eprintln!("Warning: no dependency graph for a repo");
+                None
+            }
+            Err(e) => {
+                eprintln!("Warning: {e}");
+                None
+            }
+        };
+        let name = if redacted {
+            redact::repo_name(&repo.name)
+        } else {
+            repo.name.clone()
+        };
+        rows.push((name, counts));
+    }
+
+    println!("\nDirect dependencies:");
+    for line in dependencies::render_table(&rows) {
+        println!("{line}");
+    }
+}
+
+/// Fetch what the health score needs for `repo` ("owner/name").
+async fn fetch_health(
+    transport: &Transport,
+    repo: &str,
+    token: Option<&str>,
+) -> Result<health::Signals, String> {
+    let profile = api::fetch_community_profile(transport, repo, token).await?;
+    let latest = api::fetch_latest_commit(transport, repo, token).await?;
+    let issues = api::fetch_recent_issues(transport, repo, health::ISSUE_SAMPLE, token).await?;
+    let releases = api::fetch_releases(transport, repo, health::RELEASE_SAMPLE, token).await?;
+    let contributors = api::fetch_contributors(transport, repo, token).await?;
+    Ok(health::Signals::new(
+        &profile,
+        latest.as_ref(),
+        &issues,
+        &releases,
+        &contributors,
+    ))
+}
+
+/// Print a health grade for each repo. Like `run_dependencies`, a repo
+/// that can't be scored is reported and shown as n/a.
+async fn run_health(
+    transport: &Transport,
+    owner: &str,
+    repos: &[&api::Repo],
+    offline: bool,
+    redacted: bool,
+) {
+    if !offline {
+        println!("\nFetching repo health...");
+    }
+
+    let token = github_token("--health").ok();
+    let today = chrono::Local::now().date_naive();
+    let mut rows = Vec::new();
+    for repo in repos {
+        let full_name = format!("{owner}/{}", repo.name);
+        let signals = match fetch_or_cached(
+            transport,
+            &format!("health-{full_name}"),
+            offline,
+            &format!("repository '{full_name}'"),
+            fetch_health(transport, &full_name, token.as_deref()),
+        )
+        .await
+        {
+            Ok(signals) => Some(signals),
+            Err(_) if redacted => {
+                eprintln!("Warning: no health data for a repo");
+                None
+            }
+            Err(e) => {
+                eprintln!("Warning: {e}");
+                None
+            }
+        };
+        let name = if redacted {
+            redact::repo_name(&repo.name)
+        } else {
+            repo.name.clone()
+        };
+        rows.push((name, signals.map(|s| health::Score::new(&s, today))));
+    }
+
+    println!("\nHealth (each part out of 20, score out of 100):");
+    for line in health::render_table(&rows) {
+        println!("{line}");
+    }
+}
+
+async fn fetch_ci(
+    transport: &Transport,
+    repo: &str,
+    branch: &str,
+    token: Option<&str>,
+) -> Result<ci::Summary, String> {
+    let runs = api::fetch_workflow_runs(transport, repo, branch, ci::RUN_SAMPLE, token).await?;
+    Ok(ci::Summary::new(&runs))
+}
+
+/// Print each repo's recent CI results, then which repos keep failing.
+async fn run_ci(
+    transport: &Transport,
+    owner: &str,
+    repos: &[&api::Repo],
+    offline: bool,
+    redacted: bool,
+) {
+    if !offline {
+        println!("\nFetching workflow runs...");
+    }
+
+    let token = github_token("--ci").ok();
+    let mut rows = Vec::new();
+    for repo in repos {
+        let full_name = format!("{owner}/{}", repo.name);
+        let summary = match fetch_or_cached(
+            transport,
+            &format!("ci-{full_name}"),
+            offline,
+            &format!("repository '{full_name}'"),
+            fetch_ci(
+                transport,
+                &full_name,
+                &repo.default_branch,
+                token.as_deref(),
+            ),
+        )
+        .await
+        {
+            Ok(summary) => Some(summary),
+            Err(_) if redacted => {
+                eprintln!("Warning: no workflow runs for a repo");
+                None
+            }
+            Err(e) => {
+                eprintln!("Warning: {e}");
+                None
+            }
+        };
+        let name = if redacted {
+            redact::repo_name(&repo.name)
+        } else {
+            repo.name.clone()
+        };
+        rows.push((name, summary));
+    }
+
+    println!("\nCI on the default branch (last {} runs):", ci::RUN_SAMPLE);
+    for line in ci::render_table(&rows) {
+        println!("{line}");
+    }
+    let failing = ci::failing(&rows);
+    if failing.is_empty() {
+        println!("\nNo repos with consistently failing CI.");
+    } else {
+        println!("\nConsistently failing CI: {}", failing.join(", "));
+    }
+}
+
+async fn run_stars(
+    args: StarsArgs,
+    transport: &Transport,
+    offline: bool,
+    resume: bool,
+) -> Result<(), String> {
+    if !args.repo.contains('/') {
+        return Err(format!("Expected owner/name, got '{}'", args.repo));
+    }
+
+    if !offline {
+        println!("Fetching stargazers for {}...", args.repo);
+    }
+
+    let stargazers = fetch_or_cached(
+        transport,
+        &format!("stars-{}", args.repo),
+        offline,
+        &format!("repository '{}'", args.repo),
+        api::fetch_stargazers(transport, &args.repo, resume),
+    )
+    .await?;
+    let mut timestamps: Vec<&str> = stargazers.iter().map(|s| s.starred_at.as_str()).collect();
+    timestamps.sort();
+
+    println!("\n{}", args.repo);
+    println!("{}", "=".repeat(args.repo.len()));
+    println!("Total stars: {}", timestamps.len());
+
+    if let (Some(first), Some(last)) = (timestamps.first(), timestamps.last()) {
+        // The date part of the timestamp, or all of it if it's shorter
+        println!("First star:  {}", first.get(..10).unwrap_or(first));
+        println!("Latest star: {}", last.get(..10).unwrap_or(last));
+    }
+
+    if args.graph {
+        let points = stars::monthly_cumulative(&timestamps, args.since.as_deref());
+
+        if points.is_empty() {
+            println!("\nNo stars in the selected range.");
+        } else {
+            println!("\nCumulative stars by month:");
+            for line in stars::render_chart(&points) {
+                println!("{line}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_members(
+    args: OrgArgs,
+    transport: &Transport,
+    offline: bool,
+    resume: bool,
+) -> Result<(), String> {
+    if !offline {
+        println!("Fetching members of {}...", args.org);
+    }
+
+    let fetch =
+        async { api::fetch_members(transport, &args.org, &github_token("members")?, resume).await };
+    let members = fetch_or_cached(
+        transport,
+        &format!("members-{}", args.org),
+        offline,
+        &format!("organization '{}'", args.org),
+        fetch,
+    )
+    .await?;
+
+    println!("\n{}", args.org);
+    println!("{}", "=".repeat(args.org.len()));
+    println!("Members: {}\n", members.len());
+    for line in org::render_members(&members) {
+        println!("{line}");
+    }
+
+    Ok(())
+}
+
+async fn run_teams(
+    args: OrgArgs,
+    transport: &Transport,
+    offline: bool,
+    resume: bool,
+) -> Result<(), String> {
+    if !offline {
+        println!("Fetching teams of {}...", args.org);
+    }
+
+    let fetch =
+        async { api::fetch_teams(transport, &args.org, &github_token("teams")?, resume).await };
+    let teams = fetch_or_cached(
+        transport,
+        &format!("teams-{}", args.org),
+        offline,
+        &format!("organization '{}'", args.org),
+        fetch,
+    )
+    .await?;
+
+    println!("\n{}", args.org);
+    println!("{}", "=".repeat(args.org.len()));
+    println!("Teams: {}\n", teams.len());
+    for line in org::render_teams(&teams) {
+        println!("{line}");
+    }
+
+    Ok(())
+}
+
+/// Fetch what needs attention in `repo`: three requests for pull requests,
+/// issues and statuses, plus one for check runs, made at the same time.
+async fn fetch_repo_attention(
+    transport: &Transport,
+    repo: &api::OwnedRepo,
+    me: &str,
+    token: &str,
+) -> Result<attention::RepoAttention, String> {
+    let name = &repo.full_name;
+    let (pulls, issues, (status, runs)) = tokio::try_join!(
+        api::fetch_open_pull_requests(transport, name, token),
+        api::fetch_open_issues(transport, name, token),
+        api::fetch_branch_checks(transport, name, &repo.default_branch, token),
+    )?;
+    Ok(attention::RepoAttention::new(
+        name,
+        &repo.default_branch,
+        me,
+        &pulls,
+        &issues,
+        &status,
+        &runs,
+    ))
+}
+
+/// Check every repo the token's user owns, `ATTENTION_CONCURRENCY` at a
+/// time. A repo that can't be checked is reported and left out.
+async fn fetch_attention(
+    transport: &Transport,
+    token: &str,
+    resume: bool,
+) -> Result<Vec<attention::RepoAttention>, String> {
+    let me = api::fetch_authenticated_user(transport, token).await?.login;
+    let repos = api::fetch_owned_repos(transport, token, resume).await?;
+
+    let limit = Arc::new(Semaphore::new(ATTENTION_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for repo in repos.into_iter().filter(|r| !r.fork && !r.archived) {
+        let (transport, token, me, limit) = (
+            transport.clone(),
+            token.to_string(),
+            me.clone(),
+            limit.clone(),
+        );
+        tasks.spawn(async move {
+            let _permit = limit.acquire_owned().await;
+            let result = fetch_repo_attention(&transport, &repo, &me, &token).await;
+            (repo.full_name, result)
+        });
+    }
+
+    let mut report = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined.map_err(|e| e.to_string())? {
+            (_, Ok(repo)) => report.push(repo),
+            (name, Err(e)) => eprintln!("Warning: {name}: {e}"),
+        }
+    }
+    report.sort_by(|a, b| a.repo.cmp(&b.repo));
+    Ok(report)
+}
+
+async fn run_attention(
+    args: AttentionArgs,
+    transport: &Transport,
+    offline: bool,
+    resume: bool,
+) -> Result<(), String> {
+    if !offline {
+        println!("Checking your repos...");
+    }
+
+    let fetch = async { fetch_attention(transport, &github_token("attention")?, resume).await };
+    let report = fetch_or_cached(transport, "attention", offline, "your repos", fetch).await?;
+
+    let today = chrono::Local::now().date_naive();
+    println!();
+    for line in attention::render(&report, args.days.into(), today) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+async fn run_events(args: EventsArgs, transport: &Transport, offline: bool) -> Result<(), String> {
+    if !offline {
+        println!("Fetching recent activity for {}...", args.username);
+    }
+
+    let events = fetch_or_cached(
+        transport,
+        // v2: events cached before they kept their repo would show
+        // every event under a blank name
+        &format!("events-v2-{}", args.username),
+        offline,
+        &format!("user '{}'", args.username),
+        api::fetch_events(transport, &args.username),
+    )
+    .await?;
+    let today = chrono::Local::now().date_naive();
+    let activity = activity::summarize(&events, today, args.days);
+
+    println!(
+        "\n{} in the last {} days (since {}): {} events",
+        args.username, args.days, activity.since, activity.events
+    );
+    for line in activity::render(&activity) {
+        println!("{line}");
+    }
+    if !activity.complete {
+        println!(
+            "\nGitHub's feed stops at the latest {} events, so earlier days are missing.",
+            api::MAX_EVENTS
+        );
+    }
+    Ok(())
+}
+
+async fn run_search(args: SearchArgs, transport: &Transport, offline: bool) -> Result<(), String> {
+    let table = args.format.name() == "table";
+    if !offline && table {
+        println!("Searching for {}...", args.query);
+    } else if !offline {
+        eprintln!("Searching for {}...", args.query);
+    }
+
+    let token = std::env::var("GITHUB_TOKEN").ok().filter(|t| !t.is_empty());
+    let fetch = api::search_repos(
+        transport,
+        &args.query,
+        args.sort,
+        args.limit,
+        token.as_deref(),
+    );
+    let key = format!(
+        "search-{}-{}-{}",
+        cache::digest(&args.query),
+        args.sort.describe(),
+        args.limit
+    );
+    let results = fetch_or_cached(
+        transport,
+        &key,
+        offline,
+        &format!("the search '{}'", args.query),
+        fetch,
+    )
+    .await?;
+
+    let mut note = format!("{} repos match", results.total_count);
+    if results.total_count as usize > api::MAX_SEARCH_RESULTS {
+        note.push_str(&format!(
+            "; GitHub returns the first {}",
+            api::MAX_SEARCH_RESULTS
+        ));
+    }
+    if results.incomplete {
+        note.push_str(" (GitHub timed out, so some may be missing)");
+    }
+    if table {
+        println!("{note}");
+    } else {
+        eprintln!("{note}");
+    }
+
+    let repos: Vec<&api::Repo> = results.repos.iter().collect();
+    let mut summary = export::Summary::new(
+        args.query.clone(),
+        &repos,
+        args.limit,
+        args.sort.describe().to_string(),
+        &args.columns,
+        None,
+    );
+    summary.kind = "Search results";
+    println!("{}", args.format.render(&summary)?);
+    Ok(())
+}
+
+fn run_diff_snapshots(args: DiffSnapshotsArgs) -> Result<(), String> {
+    let old = cache::load_file::<Vec<api::Repo>>(&args.old)?;
+    let new = cache::load_file::<Vec<api::Repo>>(&args.new)?;
+    if old.fetched_at > new.fetched_at {
+        eprintln!(
+            "Warning: {} was fetched after {}; the changes are shown backwards",
+            args.old.display(),
+            args.new.display()
+        );
+    }
+
+    for line in snapshot::render(&snapshot::compare(&old, &new)) {
+        println!("{line}");
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() {
-    let args = Args::parse();
-
-    println!("Fetching repos for {}...", args.username);
-
-    match api::fetch_repos(&args.username).await {
-        Ok(mut repos) => {
-            if let Some(ref lang) = args.language {
-                let lang_lower = lang.to_lowercase();
-                repos.retain(|r| {
-                    r.language
-                        .as_ref()
-                        .map(|l| l.to_lowercase() == lang_lower)
-                        .unwrap_or(false)
-                });
-
-                if repos.is_empty() {
-                    println!("No {lang} repositories found for {}", args.username);
-                    return;
-                }
-            }
+    let mut args = Args::parse();
+    let transport = match (args.record.take(), args.replay.take()) {
+        (Some(dir), _) => Transport::Record(reqwest::Client::new(), dir),
+        (_, Some(dir)) => Transport::Replay(dir),
+        _ => Transport::live(),
+    };
 
-            display::display_repos(&args.username, &repos, args.limit, &args.sort);
+    let (offline, resume) = (args.offline, args.resume);
+    let result = match args.command {
+        Some(Command::Stars(stars_args)) => {
+            run_stars(stars_args, &transport, offline, resume).await
+        }
+        Some(Command::Members(org_args)) => {
+            run_members(org_args, &transport, offline, resume).await
         }
-        Err(e) => {
-            eprintln!("Error: {e}");
-            std::process::exit(1);
+        Some(Command::Teams(org_args)) => run_teams(org_args, &transport, offline, resume).await,
+        Some(Command::DiffSnapshots(snapshot_args)) => run_diff_snapshots(snapshot_args),
+        Some(Command::Events(events_args)) => run_events(events_args, &transport, offline).await,
+        Some(Command::Search(search_args)) => run_search(search_args, &transport, offline).await,
+        Some(Command::Attention(attention_args)) => {
+            run_attention(attention_args, &transport, offline, resume).await
         }
+        None => run_repos(args, &transport).await,
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
     }
 }
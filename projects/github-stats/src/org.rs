@@ -0,0 +1,92 @@
+use crate::api::{Member, Team};
+use std::cmp::Reverse;
+
+/// Members with the most public repos first, ties by login.
+pub fn render_members(members: &[Member]) -> Vec<String> {
+    let mut sorted: Vec<&Member> = members.iter().collect();
+    sorted.sort_by_key(|m| (Reverse(m.public_repos), m.login.to_lowercase()));
+
+    let rows = sorted
+        .iter()
+        .map(|m| (m.login.as_str(), m.public_repos))
+        .collect::<Vec<_>>();
+    render_table(("Member", "Public repos"), &rows)
+}
+
+/// Largest teams first, ties by name.
+pub fn render_teams(teams: &[Team]) -> Vec<String> {
+    let mut sorted: Vec<&Team> = teams.iter().collect();
+    sorted.sort_by_key(|t| (Reverse(t.members_count), t.name.to_lowercase()));
+
+    let rows = sorted
+        .iter()
+        .map(|t| (t.name.as_str(), t.members_count))
+        .collect::<Vec<_>>();
+    render_table(("Team", "Members"), &rows)
+}
+
+fn render_table(headers: (&str, &str), rows: &[(&str, u32)]) -> Vec<String> {
+    let name_width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain([headers.0.chars().count()])
+        .max()
+        .unwrap_or(0);
+    let count_width = headers.1.chars().count();
+
+    let mut lines = vec![format!(
+        "  {:<name_width$}  {:>count_width$}",
+        headers.0, headers.1
+    )];
+    lines.push(format!("  {}", "-".repeat(name_width + 2 + count_width)));
+    for (name, count) in rows {
+        lines.push(format!("  {name:<name_width$}  {count:>count_width$}"));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(login: &str, public_repos: u32) -> Member {
+        Member {
+            login: login.to_string(),
+            public_repos,
+        }
+    }
+
+    #[test]
+    fn test_render_members() {
+        let members = vec![member("bob", 3), member("alice", 12), member("Carol", 3)];
+        assert_eq!(
+            render_members(&members),
+            vec![
+                "  Member  Public repos",
+                "  --------------------",
+                "  alice             12",
+                "  bob                3",
+                "  Carol              3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_teams() {
+        let team = |name: &str, members_count| Team {
+            name: name.to_string(),
+            slug: name.to_lowercase(),
+            members_count,
+        };
+        let teams = vec![team("Docs", 2), team("Infrastructure", 7)];
+        assert_eq!(
+            render_teams(&teams),
+            vec![
+                "  Team            Members",
+                "  -----------------------",
+                "  Infrastructure        7",
+                "  Docs                  2",
+            ]
+        );
+    }
+}
@@ -0,0 +1,509 @@
+//! `--filter` and `--sort` expressions over repo fields, e.g.
+//! `--filter "stars > 100 && language == 'Rust'" --sort "stars,name"`.
+
+use crate::api::Repo;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A repo field that expressions can refer to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Name,
+    Stars,
+    Forks,
+    Issues,
+    Updated,
+    License,
+    Language,
+    Description,
+    Archived,
+    Fork,
+}
+
+const FIELDS: &str =
+    "name, stars, forks, issues, updated, license, language, description, archived, fork";
+
+/// A field's value on one repo. Text compares case-insensitively, so it's
+/// stored lowercased; a missing language or license is the empty string.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "a number",
+            Value::Text(_) => "text",
+            Value::Bool(_) => "true or false",
+        }
+    }
+}
+
+impl Field {
+    fn parse(name: &str) -> Result<Field, String> {
+        Ok(match name.to_lowercase().as_str() {
+            "name" => Field::Name,
+            "stars" => Field::Stars,
+            "forks" => Field::Forks,
+            "issues" => Field::Issues,
+            "updated" => Field::Updated,
+            "license" => Field::License,
+            "language" => Field::Language,
+            "description" => Field::Description,
+            "archived" => Field::Archived,
+            "fork" => Field::Fork,
+            _ => return Err(format!("Unknown field '{name}'; use one of: {FIELDS}")),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Field::Name => "name",
+            Field::Stars => "stars",
+            Field::Forks => "forks",
+            Field::Issues => "issues",
+            Field::Updated => "updated",
+            Field::License => "license",
+            Field::Language => "language",
+            Field::Description => "description",
+            Field::Archived => "archived",
+            Field::Fork => "fork",
+        }
+    }
+
+    fn value(&self, repo: &Repo) -> Value {
+        let text =
+            |s: Option<&String>| Value::Text(s.map(|s| s.to_lowercase()).unwrap_or_default());
+        match self {
+            Field::Name => text(Some(&repo.name)),
+            Field::Stars => Value::Number(repo.stargazers_count as f64),
+            Field::Forks => Value::Number(repo.forks_count as f64),
+            Field::Issues => Value::Number(repo.open_issues_count as f64),
+            Field::Updated => text(Some(&repo.updated_at)),
+            Field::License => text(repo.license.as_ref().and_then(|l| l.spdx_id.as_ref())),
+            Field::Language => text(repo.language.as_ref()),
+            Field::Description => text(repo.description.as_ref()),
+            Field::Archived => Value::Bool(repo.archived),
+            Field::Fork => Value::Bool(repo.fork),
+        }
+    }
+
+    /// Counts and dates sort biggest and newest first unless asked
+    /// otherwise; everything else A to Z.
+    fn descending_by_default(&self) -> bool {
+        matches!(
+            self,
+            Field::Stars | Field::Forks | Field::Issues | Field::Updated
+        )
+    }
+
+    /// What kind of value the field holds, so mismatched comparisons are
+    /// caught when the filter is parsed rather than on every repo.
+    fn kind(&self) -> &'static str {
+        match self {
+            Field::Stars | Field::Forks | Field::Issues => Value::Number(0.0).kind(),
+            Field::Archived | Field::Fork => Value::Bool(false).kind(),
+            _ => Value::Text(String::new()).kind(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn test(&self, ordering: Option<Ordering>) -> bool {
+        match (self, ordering) {
+            (Op::Eq, Some(o)) => o.is_eq(),
+            (Op::Ne, o) => o != Some(Ordering::Equal),
+            (Op::Lt, Some(o)) => o.is_lt(),
+            (Op::Le, Some(o)) => o.is_le(),
+            (Op::Gt, Some(o)) => o.is_gt(),
+            (Op::Ge, Some(o)) => o.is_ge(),
+            (_, None) => false,
+        }
+    }
+}
+
+/// A parsed `--filter` expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Compare(Field, Op, Value),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+impl Filter {
+    pub fn matches(&self, repo: &Repo) -> bool {
+        match self {
+            Filter::Compare(field, op, value) => op.test(field.value(repo).partial_cmp(value)),
+            Filter::Not(inner) => !inner.matches(repo),
+            Filter::And(a, b) => a.matches(repo) && b.matches(repo),
+            Filter::Or(a, b) => a.matches(repo) || b.matches(repo),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Text(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek() {
+                if d.is_ascii_digit() || d == '.' || d == '_' {
+                    number.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let value = number
+                .replace('_', "")
+                .parse()
+                .map_err(|_| format!("Invalid number '{number}'"))?;
+            tokens.push(Token::Number(value));
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&d) = chars.peek() {
+                if d.is_alphanumeric() || d == '_' {
+                    ident.push(d);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Ident(ident));
+            continue;
+        }
+
+        chars.next();
+        let token = match (c, chars.peek().copied()) {
+            ('\'' | '"', _) => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some(d) if d == c => break,
+                        Some(d) => text.push(d),
+                        None => return Err(format!("Unclosed string {c}{text}")),
+                    }
+                }
+                Token::Text(text)
+            }
+            ('(', _) => Token::LParen,
+            (')', _) => Token::RParen,
+            ('&', Some('&')) => Token::And,
+            ('|', Some('|')) => Token::Or,
+            ('=', Some('=')) => Token::Op(Op::Eq),
+            ('!', Some('=')) => Token::Op(Op::Ne),
+            ('<', Some('=')) => Token::Op(Op::Le),
+            ('>', Some('=')) => Token::Op(Op::Ge),
+            ('<', _) => Token::Op(Op::Lt),
+            ('>', _) => Token::Op(Op::Gt),
+            ('!', _) => Token::Not,
+            _ => return Err(format!("Unexpected '{c}' in filter")),
+        };
+        // Skip the second character of the two-character tokens
+        if matches!(
+            token,
+            Token::And | Token::Or | Token::Op(Op::Eq | Op::Ne | Op::Le | Op::Ge)
+        ) {
+            chars.next();
+        }
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent over the tokens; `||` binds loosest, then `&&`, then
+/// `!`.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut left = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            left = Filter::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut left = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            left = Filter::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Filter, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Filter::Not(Box::new(self.unary()?))),
+            Some(Token::LParen) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("Missing ')' in filter".to_string()),
+                }
+            }
+            Some(Token::Ident(name)) => self.comparison(Field::parse(&name)?),
+            Some(other) => Err(format!("Expected a field name, found {other:?}")),
+            None => Err("Filter ends too early".to_string()),
+        }
+    }
+
+    /// `field op literal`, or a bare true/false field like `archived`.
+    fn comparison(&mut self, field: Field) -> Result<Filter, String> {
+        let Some(Token::Op(op)) = self.peek().cloned() else {
+            if field.kind() == Value::Bool(true).kind() {
+                return Ok(Filter::Compare(field, Op::Eq, Value::Bool(true)));
+            }
+            return Err(format!("Expected a comparison after '{}'", field.name()));
+        };
+        self.next();
+
+        let value = match self.next() {
+            Some(Token::Number(n)) => Value::Number(n),
+            Some(Token::Text(s)) => Value::Text(s.to_lowercase()),
+            Some(Token::Ident(s)) if s == "true" || s == "false" => Value::Bool(s == "true"),
+            _ => {
+                return Err(format!(
+                    "Expected a value to compare '{}' with",
+                    field.name()
+                ))
+            }
+        };
+        if value.kind() != field.kind() {
+            return Err(format!(
+                "'{}' is {}, so it can't be compared with {}",
+                field.name(),
+                field.kind(),
+                value.kind()
+            ));
+        }
+        Ok(Filter::Compare(field, op, value))
+    }
+}
+
+/// Parse a `--filter` expression.
+pub fn parse_filter(input: &str) -> Result<Filter, String> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+    let filter = parser.or()?;
+    match parser.peek() {
+        None => Ok(filter),
+        Some(token) => Err(format!("Unexpected {token:?} in filter")),
+    }
+}
+
+/// One field of a `--sort` list. A plain field sorts in its usual
+/// direction; a leading `+` forces ascending and `-` descending.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortKey {
+    field: Field,
+    descending: bool,
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.descending == self.field.descending_by_default() {
+            ""
+        } else if self.descending {
+            "-"
+        } else {
+            "+"
+        };
+        write!(f, "{sign}{}", self.field.name())
+    }
+}
+
+/// Parse one `--sort` key like "stars" or "+stars".
+pub fn parse_sort_key(key: &str) -> Result<SortKey, String> {
+    let key = key.trim();
+    let (name, direction) = if let Some(name) = key.strip_prefix('-') {
+        (name, Some(true))
+    } else if let Some(name) = key.strip_prefix('+') {
+        (name, Some(false))
+    } else {
+        (key, None)
+    };
+    if name.is_empty() {
+        return Err("Empty sort key".to_string());
+    }
+    let field = Field::parse(name)?;
+    Ok(SortKey {
+        field,
+        descending: direction.unwrap_or(field.descending_by_default()),
+    })
+}
+
+/// Order two repos by each key in turn.
+pub fn compare(keys: &[SortKey], a: &Repo, b: &Repo) -> Ordering {
+    keys.iter()
+        .map(|key| {
+            let ordering = key
+                .field
+                .value(a)
+                .partial_cmp(&key.field.value(b))
+                .unwrap_or(Ordering::Equal);
+            if key.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// "stars,name": the keys as they'd be written on the command line.
+pub fn describe(keys: &[SortKey]) -> String {
+    keys.iter()
+        .map(|k| k.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{test_license, test_repo};
+
+    fn repo(name: &str, stars: u32, language: Option<&str>) -> Repo {
+        Repo {
+            license: test_license("MIT"),
+            ..test_repo(name, stars, language)
+        }
+    }
+
+    fn matches(filter: &str, repo: &Repo) -> bool {
+        parse_filter(filter).unwrap().matches(repo)
+    }
+
+    #[test]
+    fn test_filter_comparisons() {
+        let r = repo("ripgrep", 150, Some("Rust"));
+        assert!(matches("stars > 100 && language == 'rust'", &r));
+        assert!(!matches("stars > 100 && language == \"Go\"", &r));
+        assert!(matches("stars >= 150 && stars <= 150", &r));
+        assert!(matches("language != 'Go' || stars < 10", &r));
+        assert!(matches("license == 'mit' && updated >= '2024-01-01'", &r));
+        assert!(matches("!archived && fork == false", &r));
+        assert!(!matches("!(stars > 100)", &r));
+
+        let untyped = repo("notes", 0, None);
+        assert!(matches("language == ''", &untyped));
+    }
+
+    #[test]
+    fn test_filter_precedence() {
+        let r = repo("ripgrep", 5, Some("Rust"));
+        // && binds tighter than ||
+        assert!(matches(
+            "name == 'ripgrep' || stars > 100 && language == 'Go'",
+            &r
+        ));
+        assert!(!matches(
+            "(name == 'ripgrep' || stars > 100) && language == 'Go'",
+            &r
+        ));
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        assert!(parse_filter("stars > 'many'")
+            .unwrap_err()
+            .contains("can't be compared"));
+        assert!(parse_filter("stargazers > 1")
+            .unwrap_err()
+            .contains("Unknown field"));
+        assert!(parse_filter("stars > 1 &&").is_err());
+        assert!(parse_filter("(stars > 1").is_err());
+        assert!(parse_filter("name == 'open").is_err());
+        assert!(parse_filter("name").is_err());
+        assert!(parse_filter("stars > 1 stars").is_err());
+    }
+
+    #[test]
+    fn test_multi_key_sort() {
+        let keys: Vec<SortKey> = ["stars", "name"]
+            .iter()
+            .map(|k| parse_sort_key(k).unwrap())
+            .collect();
+        assert_eq!(describe(&keys), "stars,name");
+
+        let mut repos = [
+            repo("beta", 10, None),
+            repo("Alpha", 10, None),
+            repo("gamma", 50, None),
+        ];
+        repos.sort_by(|a, b| compare(&keys, a, b));
+        let names: Vec<&str> = repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["gamma", "Alpha", "beta"]);
+
+        // A sign only where it goes against the field's usual direction
+        let keys: Vec<SortKey> = ["+stars", "-name", "-forks", "+language"]
+            .iter()
+            .map(|k| parse_sort_key(k).unwrap())
+            .collect();
+        assert_eq!(describe(&keys), "+stars,-name,forks,language");
+        repos.sort_by(|a, b| compare(&keys, a, b));
+        let names: Vec<&str> = repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["beta", "Alpha", "gamma"]);
+
+        assert!(parse_sort_key("-").is_err());
+        assert!(parse_sort_key("+").is_err());
+        assert!(parse_sort_key("-popularity").is_err());
+    }
+}
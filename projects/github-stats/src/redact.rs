@@ -0,0 +1,116 @@
+//! `--redact`: swap names for stable hashes before anything is printed, so
+//! output can be shared without naming private repos or orgs. Numbers,
+//! languages and licenses are kept.
+
+use crate::api::Repo;
+use crate::diff::RepoDiff;
+
+/// FNV-1a, so a name hashes the same on every run and Rust version, and
+/// redacted output from different days still lines up.
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// `prefix-` and 8 hex digits of the name's hash, e.g. `repo-3f2a9c1e`.
+pub fn name(prefix: &str, name: &str) -> String {
+    format!("{prefix}-{:08x}", fnv1a(name) as u32)
+}
+
+pub fn repo_name(name: &str) -> String {
+    self::name("repo", name)
+}
+
+/// The user or organization, for headers.
+pub fn owner(username: &str) -> String {
+    name("owner", username)
+}
+
+/// A copy of `repo` with its name hashed and the free text dropped.
+pub fn repo(repo: &Repo) -> Repo {
+    let name = repo_name(&repo.name);
+    Repo {
+        html_url: format!("https://github.com/redacted/{name}"),
+        name,
+        description: None,
+        topics: Vec::new(),
+        ..repo.clone()
+    }
+}
+
+/// `diff` with every repo name hashed, to match the redacted repos.
+pub fn diff(diff: &RepoDiff) -> RepoDiff {
+    let names = |names: &[String]| names.iter().map(|n| repo_name(n)).collect();
+    RepoDiff {
+        created: names(&diff.created),
+        renamed: diff
+            .renamed
+            .iter()
+            .map(|(old, new)| (repo_name(old), repo_name(new)))
+            .collect(),
+        removed: names(&diff.removed),
+        archived: names(&diff.archived),
+        star_deltas: diff
+            .star_deltas
+            .iter()
+            .map(|(name, delta)| (repo_name(name), *delta))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{test_license, test_repo};
+
+    fn sample() -> Repo {
+        Repo {
+            forks_count: 3,
+            open_issues_count: 1,
+            license: test_license("MIT"),
+            description: Some("Internal billing service".to_string()),
+            html_url: "https://github.com/acme/secret-project".to_string(),
+            topics: vec!["billing".to_string()],
+            ..test_repo("secret-project", 42, Some("Rust"))
+        }
+    }
+
+    #[test]
+    fn test_names_hash_stably() {
+        assert_eq!(repo_name("secret-project"), repo_name("secret-project"));
+        assert_ne!(repo_name("secret-project"), repo_name("secret-project2"));
+        assert!(repo_name("x").starts_with("repo-"));
+        assert_eq!(repo_name("x").len(), "repo-".len() + 8);
+        // Known FNV-1a value, so a change of hash doesn't go unnoticed
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_repo_keeps_stats_and_drops_text() {
+        let redacted = repo(&sample());
+        assert_eq!(redacted.name, repo_name("secret-project"));
+        assert_eq!(redacted.stargazers_count, 42);
+        assert_eq!(redacted.forks_count, 3);
+        assert_eq!(redacted.language.as_deref(), Some("Rust"));
+        assert_eq!(redacted.description, None);
+        assert!(redacted.topics.is_empty());
+        assert!(!redacted.html_url.contains("acme"));
+        assert!(!redacted.html_url.contains("secret"));
+    }
+
+    #[test]
+    fn test_diff_names_match_repos() {
+        let original = RepoDiff {
+            created: vec!["secret-project".to_string()],
+            renamed: vec![("old".to_string(), "new".to_string())],
+            star_deltas: [("secret-project".to_string(), 5)].into(),
+            ..RepoDiff::default()
+        };
+        let redacted = diff(&original);
+        let name = repo(&sample()).name;
+        assert_eq!(redacted.created, vec![name.clone()]);
+        assert_eq!(redacted.star_deltas[&name], 5);
+        assert_eq!(redacted.renamed[0].1, repo_name("new"));
+    }
+}
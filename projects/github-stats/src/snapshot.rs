@@ -0,0 +1,249 @@
+//! `diff-snapshots`: what changed between two copies of a user's cached repo
+//! list, e.g. `~/.cache/github-stats/repos-octocat.json` copied aside each
+//! month. Works entirely offline.
+
+use crate::api::Repo;
+use crate::cache::Cached;
+use crate::diff::{self, RepoDiff};
+use std::collections::BTreeMap;
+
+/// Non-fork repos with a primary language, before and after.
+#[derive(Debug, PartialEq)]
+pub struct LanguageShift {
+    pub language: String,
+    pub before: usize,
+    pub after: usize,
+}
+
+impl LanguageShift {
+    pub fn change(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+#[derive(Debug)]
+pub struct SnapshotDiff {
+    /// When each snapshot was fetched, in Unix seconds
+    pub from: u64,
+    pub to: u64,
+    /// Repos in each snapshot
+    pub counts: (usize, usize),
+    pub repos: RepoDiff,
+    /// Languages whose repo count changed, biggest change first
+    pub languages: Vec<LanguageShift>,
+}
+
+pub fn compare(old: &Cached<Vec<Repo>>, new: &Cached<Vec<Repo>>) -> SnapshotDiff {
+    SnapshotDiff {
+        from: old.fetched_at,
+        to: new.fetched_at,
+        counts: (old.data.len(), new.data.len()),
+        repos: diff::diff(&old.data, &new.data),
+        languages: language_shifts(&old.data, &new.data),
+    }
+}
+
+/// Forks are left out, as in `--trend`: their language says more about the
+/// upstream project.
+fn language_shifts(old: &[Repo], new: &[Repo]) -> Vec<LanguageShift> {
+    let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for repo in old.iter().filter(|r| !r.fork) {
+        counts.entry(language(repo)).or_default().0 += 1;
+    }
+    for repo in new.iter().filter(|r| !r.fork) {
+        counts.entry(language(repo)).or_default().1 += 1;
+    }
+
+    let mut shifts: Vec<LanguageShift> = counts
+        .into_iter()
+        .filter(|(_, (before, after))| before != after)
+        .map(|(language, (before, after))| LanguageShift {
+            language: language.to_string(),
+            before,
+            after,
+        })
+        .collect();
+    // Stable, so equal changes stay in name order
+    shifts.sort_by_key(|s| std::cmp::Reverse(s.change().abs()));
+    shifts
+}
+
+fn language(repo: &Repo) -> &str {
+    repo.language.as_deref().unwrap_or("(none)")
+}
+
+fn date(secs: u64) -> String {
+    chrono::DateTime::from_timestamp(secs as i64, 0)
+        .map(|at| at.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+pub fn render(diff: &SnapshotDiff) -> Vec<String> {
+    let days = diff.to.saturating_sub(diff.from) / 86_400;
+    let mut lines = vec![
+        format!(
+            "Snapshots: {} → {} ({days} days)",
+            date(diff.from),
+            date(diff.to)
+        ),
+        format!("Repos:     {} → {}", diff.counts.0, diff.counts.1),
+    ];
+
+    let changes = &diff.repos;
+    if changes.is_empty() && diff.languages.is_empty() {
+        lines.push("\nNo changes between the snapshots.".to_string());
+        return lines;
+    }
+
+    if !changes.star_deltas.is_empty() {
+        let mut deltas: Vec<(&String, &i64)> = changes.star_deltas.iter().collect();
+        deltas.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let width = deltas
+            .iter()
+            .map(|(name, _)| name.len())
+            .fold("Total".len(), usize::max);
+
+        lines.push("\nStars gained:".to_string());
+        for (name, delta) in deltas {
+            lines.push(format!("  {name:<width$}  {delta:+}"));
+        }
+        lines.push(format!(
+            "  {:<width$}  {:+}",
+            "Total",
+            changes.total_star_delta()
+        ));
+    }
+
+    let mut repos = Vec::new();
+    if !changes.created.is_empty() {
+        repos.push(format!("  Added:     {}", changes.created.join(", ")));
+    }
+    if !changes.removed.is_empty() {
+        repos.push(format!("  Removed:   {}", changes.removed.join(", ")));
+    }
+    for (old, new) in &changes.renamed {
+        repos.push(format!("  Renamed:   {old} → {new}"));
+    }
+    if !changes.archived.is_empty() {
+        repos.push(format!("  Archived:  {}", changes.archived.join(", ")));
+    }
+    if !repos.is_empty() {
+        lines.push("\nRepos:".to_string());
+        lines.extend(repos);
+    }
+
+    if !diff.languages.is_empty() {
+        let width = diff
+            .languages
+            .iter()
+            .map(|s| s.language.len())
+            .max()
+            .unwrap_or(0);
+        lines.push("\nLanguages:".to_string());
+        for shift in &diff.languages {
+            lines.push(format!(
+                "  {:<width$}  {} → {}  ({:+})",
+                shift.language,
+                shift.before,
+                shift.after,
+                shift.change()
+            ));
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_repo;
+
+    fn repo(id: u64, name: &str, stars: u32, language: Option<&str>) -> Repo {
+        Repo {
+            id,
+            ..test_repo(name, stars, language)
+        }
+    }
+
+    fn snapshot(fetched_at: u64, data: Vec<Repo>) -> Cached<Vec<Repo>> {
+        Cached { fetched_at, data }
+    }
+
+    // 2024-05-01 and 2024-06-01, midnight UTC
+    const MAY: u64 = 1_714_521_600;
+    const JUNE: u64 = 1_717_200_000;
+
+    #[test]
+    fn test_language_shifts() {
+        let old = vec![
+            repo(1, "a", 0, Some("Python")),
+            repo(2, "b", 0, Some("Python")),
+            repo(3, "c", 0, Some("Go")),
+        ];
+        let mut fork = repo(9, "fork", 0, Some("C"));
+        fork.fork = true;
+        let new = vec![
+            repo(1, "a", 0, Some("Rust")),
+            repo(2, "b", 0, Some("Rust")),
+            repo(3, "c", 0, Some("Go")),
+            repo(4, "d", 0, None),
+            fork,
+        ];
+
+        let shifts = language_shifts(&old, &new);
+        let summary: Vec<(&str, i64)> = shifts
+            .iter()
+            .map(|s| (s.language.as_str(), s.change()))
+            .collect();
+        assert_eq!(summary, [("Python", -2), ("Rust", 2), ("(none)", 1)]);
+    }
+
+    #[test]
+    fn test_render() {
+        let old = snapshot(
+            MAY,
+            vec![
+                repo(1, "tool", 10, Some("Rust")),
+                repo(2, "gone", 3, Some("Python")),
+                repo(3, "quiet", 1, Some("Rust")),
+            ],
+        );
+        let new = snapshot(
+            JUNE,
+            vec![
+                repo(1, "tool", 25, Some("Rust")),
+                repo(3, "quiet", 0, Some("Rust")),
+                repo(4, "fresh", 2, Some("Rust")),
+            ],
+        );
+
+        assert_eq!(
+            render(&compare(&old, &new)),
+            vec![
+                "Snapshots: 2024-05-01 → 2024-06-01 (31 days)",
+                "Repos:     3 → 3",
+                "\nStars gained:",
+                "  tool   +15",
+                "  quiet  -1",
+                "  Total  +14",
+                "\nRepos:",
+                "  Added:     fresh",
+                "  Removed:   gone",
+                "\nLanguages:",
+                "  Python  1 → 0  (-1)",
+                "  Rust    2 → 3  (+1)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_unchanged() {
+        let repos = vec![repo(1, "tool", 10, Some("Rust"))];
+        let lines = render(&compare(
+            &snapshot(MAY, repos.clone()),
+            &snapshot(MAY, repos),
+        ));
+        assert_eq!(lines.last().unwrap(), "\nNo changes between the snapshots.");
+    }
+}
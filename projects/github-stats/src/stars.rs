@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+const CHART_WIDTH: usize = 50;
+
+/// Validate a `--since` date: `YYYY-MM-DD` or `YYYY-MM`.
+pub fn parse_since(s: &str) -> Result<String, String> {
+    let valid = match s.len() {
+        7 | 10 => s.chars().enumerate().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        }),
+        _ => false,
+    };
+
+    if valid {
+        Ok(s.to_string())
+    } else {
+        Err(format!("Invalid date: '{s}'. Use YYYY-MM-DD or YYYY-MM"))
+    }
+}
+
+/// Turn star timestamps (ISO 8601, e.g. "2024-05-01T12:00:00Z") into a
+/// cumulative total per month. Stars before `since` are not charted but still
+/// count toward the running total, so the line starts at the real star count.
+/// Months without new stars are filled in so the timeline has no gaps.
+pub fn monthly_cumulative(timestamps: &[&str], since: Option<&str>) -> Vec<(String, usize)> {
+    let mut baseline = 0;
+    let mut per_month: BTreeMap<&str, usize> = BTreeMap::new();
+
+    for ts in timestamps {
+        if since.is_some_and(|since| *ts < since) {
+            baseline += 1;
+        } else if let Some(month) = ts.get(..7) {
+            *per_month.entry(month).or_insert(0) += 1;
+        }
+    }
+
+    let (Some(first), Some(last)) = (per_month.keys().next(), per_month.keys().last()) else {
+        return Vec::new();
+    };
+
+    let mut points = Vec::new();
+    let mut total = baseline;
+    let mut month = first.to_string();
+
+    loop {
+        total += per_month.get(month.as_str()).copied().unwrap_or(0);
+        points.push((month.clone(), total));
+
+        if month.as_str() >= *last {
+            break;
+        }
+        month = next_month(&month);
+    }
+
+    points
+}
+
+fn next_month(month: &str) -> String {
+    let year: u32 = month[..4].parse().unwrap_or(0);
+    let m: u32 = month[5..7].parse().unwrap_or(1);
+
+    if m >= 12 {
+        format!("{:04}-01", year + 1)
+    } else {
+        format!("{year:04}-{:02}", m + 1)
+    }
+}
+
+/// Render one horizontal bar per month, scaled so the largest total fills
+/// the chart width.
+pub fn render_chart(points: &[(String, usize)]) -> Vec<String> {
+    let max = points.iter().map(|(_, n)| *n).max().unwrap_or(0).max(1);
+
+    points
+        .iter()
+        .map(|(month, total)| {
+            let bar = "#".repeat(total * CHART_WIDTH / max);
+            format!("  {month}  {bar:<CHART_WIDTH$}  {total}")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monthly_cumulative_fills_gaps() {
+        let stars = [
+            "2023-11-05T00:00:00Z",
+            "2023-11-20T00:00:00Z",
+            "2024-02-01T00:00:00Z",
+        ];
+        assert_eq!(
+            monthly_cumulative(&stars, None),
+            vec![
+                ("2023-11".to_string(), 2),
+                ("2023-12".to_string(), 2),
+                ("2024-01".to_string(), 2),
+                ("2024-02".to_string(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_since_keeps_earlier_stars_in_total() {
+        let stars = [
+            "2020-01-01T00:00:00Z",
+            "2020-01-02T00:00:00Z",
+            "2024-03-01T00:00:00Z",
+        ];
+        assert_eq!(
+            monthly_cumulative(&stars, Some("2024-01-01")),
+            vec![("2024-03".to_string(), 3)]
+        );
+    }
+
+    #[test]
+    fn test_no_stars() {
+        assert!(monthly_cumulative(&[], None).is_empty());
+    }
+
+    #[test]
+    fn test_render_chart_scales_to_max() {
+        let lines = render_chart(&[("2024-01".to_string(), 5), ("2024-02".to_string(), 10)]);
+        assert!(lines[0].contains(&format!("{} ", "#".repeat(CHART_WIDTH / 2))));
+        assert!(lines[1].contains(&"#".repeat(CHART_WIDTH)));
+        assert!(lines[1].ends_with("  10"));
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert!(parse_since("2024-05-01").is_ok());
+        assert!(parse_since("2024-05").is_ok());
+        assert!(parse_since("May 2024").is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use crate::api::Repo;
+use std::collections::{BTreeMap, HashMap};
+
+/// Languages charted separately; the rest are lumped into "other".
+pub const TOP_LANGUAGES: usize = 5;
+
+/// Widest a year's bar may get; busier years are scaled down to fit.
+pub const WIDTH: usize = 50;
+
+/// One fill per charted language, in order, then the one for "other".
+const FILLS: [char; TOP_LANGUAGES + 1] = ['█', '▓', '▒', '░', '#', '·'];
+
+/// Repos per creation year, split by primary language.
+#[derive(Debug, PartialEq)]
+pub struct Trend {
+    /// The most common languages overall, most common first
+    pub languages: Vec<String>,
+    /// For every year from the first to the last: repos per entry of
+    /// `languages`, then repos in any other language
+    pub years: BTreeMap<i32, Vec<usize>>,
+    /// Repos without a creation date, from caches written by older versions
+    pub undated: usize,
+}
+
+fn language(repo: &Repo) -> &str {
+    repo.language.as_deref().unwrap_or("(none)")
+}
+
+fn year(repo: &Repo) -> Option<i32> {
+    repo.created_at.get(..4)?.parse().ok()
+}
+
+pub fn aggregate(repos: &[&Repo]) -> Trend {
+    let mut totals: HashMap<&str, usize> = HashMap::new();
+    for repo in repos {
+        *totals.entry(language(repo)).or_insert(0) += 1;
+    }
+    let mut ranked: Vec<(&str, usize)> = totals.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let languages: Vec<String> = ranked
+        .iter()
+        .take(TOP_LANGUAGES)
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    let mut years: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+    let mut undated = 0;
+    for repo in repos {
+        let Some(year) = year(repo) else {
+            undated += 1;
+            continue;
+        };
+        let column = languages
+            .iter()
+            .position(|l| l == language(repo))
+            .unwrap_or(languages.len());
+        years
+            .entry(year)
+            .or_insert_with(|| vec![0; languages.len() + 1])[column] += 1;
+    }
+
+    // Empty years stay in the chart so gaps show
+    if let (Some(&first), Some(&last)) = (years.keys().next(), years.keys().next_back()) {
+        for year in first..=last {
+            years
+                .entry(year)
+                .or_insert_with(|| vec![0; languages.len() + 1]);
+        }
+    }
+
+    Trend {
+        languages,
+        years,
+        undated,
+    }
+}
+
+/// A stacked bar per year, one fill per language, and a legend. Each repo is
+/// one cell unless the busiest year is wider than `width`.
+pub fn render(trend: &Trend, width: usize) -> Vec<String> {
+    let busiest = trend
+        .years
+        .values()
+        .map(|counts| counts.iter().sum::<usize>())
+        .max()
+        .unwrap_or(0);
+    let scale = if busiest > width {
+        width as f64 / busiest as f64
+    } else {
+        1.0
+    };
+    let bar_width = busiest.min(width);
+
+    let mut lines = Vec::new();
+    for (year, counts) in &trend.years {
+        // Round the running total, not each segment, so bars stay in
+        // proportion to their year's total
+        let mut bar = String::new();
+        let mut running = 0;
+        for (i, count) in counts.iter().enumerate() {
+            let start = (running as f64 * scale).round() as usize;
+            running += count;
+            let end = (running as f64 * scale).round() as usize;
+            let fill = if i < trend.languages.len() {
+                FILLS[i]
+            } else {
+                FILLS[TOP_LANGUAGES]
+            };
+            bar.extend(std::iter::repeat_n(fill, end - start));
+        }
+        let padding = " ".repeat(bar_width - bar.chars().count());
+        lines.push(format!("  {year}  {bar}{padding}  {running}"));
+    }
+
+    let mut legend: Vec<String> = trend
+        .languages
+        .iter()
+        .zip(FILLS)
+        .map(|(language, fill)| format!("{fill} {language}"))
+        .collect();
+    let has_other = trend
+        .years
+        .values()
+        .any(|counts| counts[trend.languages.len()] > 0);
+    if has_other {
+        legend.push(format!("{} other", FILLS[TOP_LANGUAGES]));
+    }
+    lines.push(format!("  {}", legend.join("  ")));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_repo;
+
+    fn repo(language: Option<&str>, created_at: Option<&str>) -> Repo {
+        Repo {
+            created_at: created_at.unwrap_or_default().to_string(),
+            ..test_repo("repo", 0, language)
+        }
+    }
+
+    fn sample() -> Vec<Repo> {
+        vec![
+            repo(Some("Python"), Some("2016-03-01T00:00:00Z")),
+            repo(Some("Python"), Some("2016-09-01T00:00:00Z")),
+            repo(Some("Rust"), Some("2018-01-01T00:00:00Z")),
+            repo(Some("Rust"), Some("2018-02-01T00:00:00Z")),
+            repo(Some("Python"), Some("2018-03-01T00:00:00Z")),
+            repo(None, Some("2018-04-01T00:00:00Z")),
+            repo(Some("Rust"), None),
+        ]
+    }
+
+    #[test]
+    fn test_aggregate_by_year() {
+        let repos = sample();
+        let refs: Vec<&Repo> = repos.iter().collect();
+        let trend = aggregate(&refs);
+
+        // Rust's undated repo still counts towards the ranking
+        assert_eq!(trend.languages, vec!["Python", "Rust", "(none)"]);
+        assert_eq!(trend.undated, 1);
+        assert_eq!(
+            trend.years,
+            BTreeMap::from([
+                (2016, vec![2, 0, 0, 0]),
+                (2017, vec![0, 0, 0, 0]),
+                (2018, vec![1, 2, 1, 0]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_other_languages_are_lumped() {
+        let names = ["A", "B", "C", "D", "E", "F", "G"];
+        let repos: Vec<Repo> = names
+            .iter()
+            .map(|n| repo(Some(n), Some("2020-01-01T00:00:00Z")))
+            .collect();
+        let refs: Vec<&Repo> = repos.iter().collect();
+        let trend = aggregate(&refs);
+        assert_eq!(trend.languages.len(), TOP_LANGUAGES);
+        assert_eq!(trend.years[&2020], vec![1, 1, 1, 1, 1, 2]);
+        assert!(render(&trend, WIDTH).last().unwrap().ends_with("· other"));
+    }
+
+    #[test]
+    fn test_render() {
+        let repos = sample();
+        let refs: Vec<&Repo> = repos.iter().collect();
+        let lines = render(&aggregate(&refs), WIDTH);
+        assert_eq!(
+            lines,
+            vec![
+                "  2016  ██    2",
+                "  2017        0",
+                "  2018  █▓▓▒  4",
+                "  █ Python  ▓ Rust  ▒ (none)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_scales_busy_years() {
+        let repos: Vec<Repo> = (0..100)
+            .map(|_| repo(Some("Go"), Some("2021-06-01T00:00:00Z")))
+            .collect();
+        let refs: Vec<&Repo> = repos.iter().collect();
+        let lines = render(&aggregate(&refs), 20);
+        assert_eq!(lines[0], format!("  2021  {}  100", "█".repeat(20)));
+    }
+}
@@ -0,0 +1,468 @@
+use crate::api::{self, Release, Repo, Transport};
+use crate::cache;
+use crate::columns::Column;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// How many releases the detail pane lists
+const RELEASE_COUNT: usize = 5;
+
+/// Wait this long on a highlighted repo before fetching its releases, so
+/// scrolling past repos doesn't fire a request for each one.
+const FETCH_DELAY: Duration = Duration::from_millis(300);
+
+const PAGE_SIZE: usize = 10;
+
+/// Releases for the detail pane, fetched the first time a repo is
+/// highlighted.
+enum Releases {
+    Loading,
+    Loaded(Vec<Release>),
+    Failed(String),
+}
+
+struct App {
+    owner: String,
+    repos: Vec<Repo>,
+    columns: Vec<Column>,
+    query: String,
+    /// Index into `columns` of the column to sort by
+    sort: usize,
+    reverse: bool,
+    /// Index into `visible()`
+    selected: usize,
+    releases: HashMap<String, Releases>,
+    quit: bool,
+}
+
+impl App {
+    fn new(owner: &str, repos: Vec<Repo>, columns: Vec<Column>) -> Self {
+        // Start sorted by stars when that column is shown
+        let sort = columns
+            .iter()
+            .position(|c| *c == Column::Stars)
+            .unwrap_or(0);
+
+        App {
+            owner: owner.to_string(),
+            repos,
+            columns,
+            query: String::new(),
+            sort,
+            reverse: false,
+            selected: 0,
+            releases: HashMap::new(),
+            quit: false,
+        }
+    }
+
+    /// Repos matching the search, in the current sort order.
+    fn visible(&self) -> Vec<&Repo> {
+        let query = self.query.to_lowercase();
+        let column = self.columns[self.sort];
+
+        let mut repos: Vec<&Repo> = self
+            .repos
+            .iter()
+            .filter(|r| matches_query(r, &query))
+            .collect();
+        repos.sort_by(|a, b| {
+            let ordering = column.compare(a, b);
+            if self.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        repos
+    }
+
+    fn selected_repo(&self) -> Option<&Repo> {
+        self.visible().get(self.selected).copied()
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Esc if self.query.is_empty() => self.quit = true,
+            KeyCode::Esc => self.set_query(String::new()),
+            KeyCode::Char(c) => self.set_query(format!("{}{c}", self.query)),
+            KeyCode::Backspace => {
+                let mut query = self.query.clone();
+                query.pop();
+                self.set_query(query);
+            }
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected += 1,
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(PAGE_SIZE),
+            KeyCode::PageDown => self.selected += PAGE_SIZE,
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = usize::MAX,
+            KeyCode::Tab => {
+                self.sort = (self.sort + 1) % self.columns.len();
+                self.reverse = false;
+            }
+            KeyCode::BackTab => self.reverse = !self.reverse,
+            _ => {}
+        }
+
+        let count = self.visible().len();
+        self.selected = self.selected.min(count.saturating_sub(1));
+    }
+
+    fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.selected = 0;
+    }
+}
+
+/// Case-insensitive search over name, description, language, and topics.
+/// `query` must already be lowercase.
+fn matches_query(repo: &Repo, query: &str) -> bool {
+    let contains = |s: &str| s.to_lowercase().contains(query);
+
+    query.is_empty()
+        || contains(&repo.name)
+        || repo.description.as_deref().is_some_and(contains)
+        || repo.language.as_deref().is_some_and(contains)
+        || repo.topics.iter().any(|t| contains(t))
+}
+
+/// Releases from the network (caching them), or only from the cache when
+/// offline.
+async fn load_releases(
+    transport: &Transport,
+    repo: &str,
+    offline: bool,
+) -> Result<Vec<Release>, String> {
+    let key = format!("releases-{repo}");
+
+    if offline {
+        return cache::load(&key)?
+            .map(|cached| cached.data)
+            .ok_or("Not cached (offline)".to_string());
+    }
+
+    let releases = api::fetch_releases(transport, repo, RELEASE_COUNT, None).await?;
+    // A failed cache write shouldn't hide the releases we just fetched
+    if !transport.is_replay() {
+        let _ = cache::save(&key, &releases);
+    }
+    Ok(releases)
+}
+
+/// Browse `repos` interactively until the user quits.
+pub async fn run(
+    owner: &str,
+    repos: Vec<Repo>,
+    columns: Vec<Column>,
+    offline: bool,
+    transport: &Transport,
+) -> Result<(), String> {
+    if columns.is_empty() {
+        return Err("--tui needs at least one column".to_string());
+    }
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(
+        &mut terminal,
+        App::new(owner, repos, columns),
+        offline,
+        transport,
+    )
+    .await;
+    ratatui::restore();
+    result
+}
+
+async fn event_loop(
+    terminal: &mut DefaultTerminal,
+    mut app: App,
+    offline: bool,
+    transport: &Transport,
+) -> Result<(), String> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut highlighted: Option<(String, Instant)> = None;
+
+    while !app.quit {
+        terminal
+            .draw(|frame| draw(frame, &app))
+            .map_err(|e| format!("Failed to draw: {e}"))?;
+
+        let ready =
+            event::poll(Duration::from_millis(50)).map_err(|e| format!("Input error: {e}"))?;
+        if ready {
+            if let Event::Key(key) = event::read().map_err(|e| format!("Input error: {e}"))? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+
+        while let Ok((name, result)) = rx.try_recv() {
+            let releases = match result {
+                Ok(releases) => Releases::Loaded(releases),
+                Err(e) => Releases::Failed(e),
+            };
+            app.releases.insert(name, releases);
+        }
+
+        let Some(name) = app.selected_repo().map(|r| r.name.clone()) else {
+            continue;
+        };
+        match &highlighted {
+            Some((current, since)) if *current == name => {
+                if since.elapsed() >= FETCH_DELAY && !app.releases.contains_key(&name) {
+                    app.releases.insert(name.clone(), Releases::Loading);
+                    let tx = tx.clone();
+                    let full_name = format!("{}/{name}", app.owner);
+                    let transport = transport.clone();
+                    tokio::spawn(async move {
+                        let result = load_releases(&transport, &full_name, offline).await;
+                        let _ = tx.send((name, result));
+                    });
+                }
+            }
+            _ => highlighted = Some((name, Instant::now())),
+        }
+    }
+
+    Ok(())
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let [search, body, help] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [list, detail] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(body);
+
+    let visible = app.visible();
+
+    let title = format!(
+        " {} — {} of {} repos ",
+        app.owner,
+        visible.len(),
+        app.repos.len()
+    );
+    frame.render_widget(
+        Paragraph::new(format!("Search: {}▏", app.query)).block(Block::bordered().title(title)),
+        search,
+    );
+
+    draw_table(frame, app, &visible, list);
+    draw_detail(frame, app, visible.get(app.selected).copied(), detail);
+
+    frame.render_widget(
+        Line::from(
+            "type to search · ↑/↓ move · Tab sort column · Shift-Tab reverse · Esc clear/quit",
+        )
+        .dim(),
+        help,
+    );
+}
+
+fn draw_table(frame: &mut Frame, app: &App, repos: &[&Repo], area: Rect) {
+    let header = app.columns.iter().enumerate().map(|(i, col)| {
+        let arrow = match (i == app.sort, app.reverse) {
+            (false, _) => "",
+            (true, false) => " ▼",
+            (true, true) => " ▲",
+        };
+        Line::from(format!("{}{arrow}", col.header()))
+    });
+
+    let rows = repos.iter().map(|repo| {
+        Row::new(app.columns.iter().map(|col| {
+            let line = Line::from(col.value(repo));
+            if col.is_numeric() {
+                line.right_aligned()
+            } else {
+                line
+            }
+        }))
+    });
+
+    let widths = app.columns.iter().map(|col| {
+        let widest = repos
+            .iter()
+            .map(|r| col.value(r).chars().count())
+            .chain([col.header().len() + 2])
+            .max()
+            .unwrap_or(0);
+        Constraint::Max(widest as u16)
+    });
+
+    let table = Table::new(rows, widths)
+        .header(Row::new(header).bold())
+        .block(Block::bordered())
+        .row_highlight_style(Style::new().reversed());
+
+    let mut state = TableState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_detail(frame: &mut Frame, app: &App, repo: Option<&Repo>, area: Rect) {
+    let Some(repo) = repo else {
+        frame.render_widget(
+            Paragraph::new("No matching repositories").block(Block::bordered()),
+            area,
+        );
+        return;
+    };
+
+    let mut lines = vec![
+        Line::from(repo.name.clone()).bold(),
+        Line::from(repo.description.clone().unwrap_or_default()),
+        Line::from(""),
+        Line::from(format!(
+            "★ {}   forks {}   issues {}",
+            repo.stargazers_count, repo.forks_count, repo.open_issues_count
+        )),
+        Line::from(format!("Language: {}", Column::Language.value(repo))),
+        Line::from(format!("License:  {}", Column::License.value(repo))),
+        Line::from(format!("Updated:  {}", Column::Updated.value(repo))),
+    ];
+
+    if !repo.topics.is_empty() {
+        lines.push(Line::from(format!("Topics:   {}", repo.topics.join(", "))));
+    }
+    lines.push(Line::from(repo.html_url.clone()).dim());
+    lines.push(Line::from(""));
+    lines.push(Line::from("Recent releases").bold());
+
+    match app.releases.get(&repo.name) {
+        None | Some(Releases::Loading) => lines.push(Line::from("  Loading…").dim()),
+        Some(Releases::Failed(e)) => lines.push(Line::from(format!("  {e}")).dim()),
+        Some(Releases::Loaded(releases)) if releases.is_empty() => {
+            lines.push(Line::from("  No releases").dim())
+        }
+        Some(Releases::Loaded(releases)) => {
+            for release in releases {
+                let date = release
+                    .published_at
+                    .as_deref()
+                    .and_then(|d| d.get(..10))
+                    .unwrap_or("unpublished");
+                let name = release.name.as_deref().unwrap_or_default();
+                lines.push(Line::from(format!(
+                    "  {}  {date}  {name}",
+                    release.tag_name
+                )));
+            }
+        }
+    }
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered()),
+        area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_repo;
+
+    fn repo(name: &str, stars: u32, topics: &[&str]) -> Repo {
+        Repo {
+            description: Some(format!("The {name} project")),
+            topics: topics.iter().map(|t| t.to_string()).collect(),
+            ..test_repo(name, stars, Some("Rust"))
+        }
+    }
+
+    fn app() -> App {
+        let repos = vec![
+            repo("cli", 5, &["terminal"]),
+            repo("web", 50, &[]),
+            repo("api", 20, &["http"]),
+        ];
+        App::new("me", repos, vec![Column::Name, Column::Stars])
+    }
+
+    fn names(app: &App) -> Vec<&str> {
+        app.visible().iter().map(|r| r.name.as_str()).collect()
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn test_starts_sorted_by_stars() {
+        assert_eq!(names(&app()), ["web", "api", "cli"]);
+    }
+
+    #[test]
+    fn test_search_as_you_type_matches_topics() {
+        let mut app = app();
+        press(&mut app, KeyCode::Down);
+        for c in "term".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(names(&app), ["cli"]);
+        assert_eq!(app.selected, 0);
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(names(&app).len(), 3);
+        assert!(!app.quit);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_tab_cycles_sort_and_backtab_reverses() {
+        let mut app = app();
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(names(&app), ["api", "cli", "web"]);
+        press(&mut app, KeyCode::BackTab);
+        assert_eq!(names(&app), ["web", "cli", "api"]);
+    }
+
+    #[test]
+    fn test_draw_shows_table_and_detail() {
+        let mut app = app();
+        app.releases
+            .insert("web".to_string(), Releases::Loaded(Vec::new()));
+
+        let backend = ratatui::backend::TestBackend::new(100, 20);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("me — 3 of 3 repos"));
+        assert!(screen.contains("Stars ▼"));
+        assert!(screen.contains("The web project"));
+        assert!(screen.contains("No releases"));
+    }
+
+    #[test]
+    fn test_selection_stays_in_bounds() {
+        let mut app = app();
+        press(&mut app, KeyCode::PageDown);
+        assert_eq!(app.selected_repo().unwrap().name, "cli");
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Up);
+        assert_eq!(app.selected, 0);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::task::Task;
+use crate::tracking::start_of_week;
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+use std::fmt;
+
+/// Agenda groups, in the order they're shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    Overdue,
+    Today,
+    Tomorrow,
+    ThisWeek,
+    Later,
+    NoDate,
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let label = match self {
+            Section::Overdue => "Overdue",
+            Section::Today => "Today",
+            Section::Tomorrow => "Tomorrow",
+            Section::ThisWeek => "This Week",
+            Section::Later => "Later",
+            Section::NoDate => "No Date",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// Which agenda group a task belongs in. "This week" runs through Sunday.
+pub fn section(task: &Task, now: NaiveDateTime) -> Section {
+    let Some(due) = task.due else {
+        return Section::NoDate;
+    };
+
+    if due.is_overdue(now) {
+        return Section::Overdue;
+    }
+
+    let today = now.date();
+    let end_of_week = start_of_week(now).date() + Duration::days(6);
+
+    match due.date() {
+        d if d == today => Section::Today,
+        d if d == today + Duration::days(1) => Section::Tomorrow,
+        d if d <= end_of_week => Section::ThisWeek,
+        _ => Section::Later,
+    }
+}
+
+/// Pending tasks grouped into sections, soonest first within each. Empty
+/// sections are left out.
+pub fn agenda(tasks: &[Task], now: NaiveDateTime) -> Vec<(Section, Vec<&Task>)> {
+    let mut pending: Vec<&Task> = tasks.iter().filter(|t| !t.is_done()).collect();
+    pending.sort_by_key(|t| (section(t, now), t.due.map(|d| d.deadline()), t.id));
+
+    let mut groups: Vec<(Section, Vec<&Task>)> = Vec::new();
+    for task in pending {
+        let section = section(task, now);
+        match groups.last_mut() {
+            Some((last, tasks)) if *last == section => tasks.push(task),
+            _ => groups.push((section, vec![task])),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::Due;
+    use crate::task::Status;
+    use crate::testing::{self, may, now};
+    use chrono::NaiveDate;
+
+    fn task(id: u32, due_day: Option<u32>) -> Task {
+        Task {
+            due: due_day.map(|d| Due::Date(may(d))),
+            ..testing::task(id)
+        }
+    }
+
+    #[test]
+    fn test_sections() {
+        let cases = [
+            (Some(1), Section::Today),
+            (Some(2), Section::Tomorrow),
+            (Some(5), Section::ThisWeek),
+            (Some(6), Section::Later),
+            (None, Section::NoDate),
+        ];
+        for (day, expected) in cases {
+            assert_eq!(section(&task(1, day), now()), expected, "day {day:?}");
+        }
+    }
+
+    #[test]
+    fn test_past_due_is_overdue() {
+        let mut task = task(1, None);
+        task.due = Some(Due::DateTime(now() - Duration::minutes(5)));
+        assert_eq!(section(&task, now()), Section::Overdue);
+    }
+
+    #[test]
+    fn test_agenda_groups_pending_tasks_in_order() {
+        let mut done = task(5, Some(1));
+        done.status = Status::Done;
+        let mut overdue = task(6, None);
+        overdue.due = Some(Due::Date(NaiveDate::from_ymd_opt(2024, 4, 29).unwrap()));
+        let tasks = vec![
+            task(1, None),
+            task(2, Some(9)),
+            task(3, Some(1)),
+            task(4, Some(2)),
+            done,
+            overdue,
+        ];
+
+        let groups: Vec<(Section, Vec<u32>)> = agenda(&tasks, now())
+            .into_iter()
+            .map(|(s, tasks)| (s, tasks.iter().map(|t| t.id).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                (Section::Overdue, vec![6]),
+                (Section::Today, vec![3]),
+                (Section::Tomorrow, vec![4]),
+                (Section::Later, vec![2]),
+                (Section::NoDate, vec![1]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use crate::task::Task;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Normalize a name given to `--assign` or `--assignee`: `@Alice` and
+/// `alice` are the same person.
+pub fn parse(input: &str) -> Result<String, String> {
+    let name = input.trim().trim_start_matches('@').to_lowercase();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!(
+            "Invalid assignee: '{input}'. Use a single name, e.g. alice"
+        ));
+    }
+    Ok(name)
+}
+
+/// Task counts for one assignee in `todo stats`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AssigneeCount {
+    /// None for unassigned tasks
+    pub assignee: Option<String>,
+    pub pending: usize,
+    pub completed: usize,
+}
+
+/// Counts per assignee, most pending first, with unassigned tasks last.
+pub fn counts(tasks: &[Task]) -> Vec<AssigneeCount> {
+    let mut by_name: BTreeMap<Option<&str>, (usize, usize)> = BTreeMap::new();
+    for task in tasks {
+        let entry = by_name.entry(task.assignee.as_deref()).or_default();
+        if task.is_done() {
+            entry.1 += 1;
+        } else {
+            entry.0 += 1;
+        }
+    }
+
+    let mut counts: Vec<AssigneeCount> = by_name
+        .into_iter()
+        .map(|(name, (pending, completed))| AssigneeCount {
+            assignee: name.map(String::from),
+            pending,
+            completed,
+        })
+        .collect();
+    counts.sort_by_key(|c| (c.assignee.is_none(), std::cmp::Reverse(c.pending)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Status;
+    use crate::testing;
+
+    fn task(id: u32, assignee: Option<&str>, completed: bool) -> Task {
+        let mut task = Task {
+            assignee: assignee.map(String::from),
+            ..testing::task(id)
+        };
+        if completed {
+            task.status = Status::Done;
+        }
+        task
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("@Alice"), Ok("alice".to_string()));
+        assert_eq!(parse(" bob "), Ok("bob".to_string()));
+        assert!(parse("").is_err());
+        assert!(parse("@").is_err());
+        assert!(parse("alice smith").is_err());
+    }
+
+    #[test]
+    fn test_counts() {
+        let tasks = [
+            task(1, Some("alice"), false),
+            task(2, None, false),
+            task(3, Some("bob"), false),
+            task(4, Some("bob"), false),
+            task(5, Some("alice"), true),
+            task(6, None, false),
+            task(7, None, false),
+        ];
+        let count = |assignee: Option<&str>, pending, completed| AssigneeCount {
+            assignee: assignee.map(String::from),
+            pending,
+            completed,
+        };
+        assert_eq!(
+            counts(&tasks),
+            vec![
+                count(Some("bob"), 2, 0),
+                count(Some("alice"), 1, 1),
+                count(None, 3, 0),
+            ]
+        );
+    }
+}
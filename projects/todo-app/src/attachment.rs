@@ -0,0 +1,86 @@
+use crate::task::Task;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A file or link attached to a task with `todo attach`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// A URL, or an absolute path to a local file
+    pub target: String,
+    pub added: NaiveDateTime,
+}
+
+pub fn is_url(target: &str) -> bool {
+    ["http://", "https://", "ftp://", "mailto:", "file://"]
+        .iter()
+        .any(|scheme| target.starts_with(scheme))
+}
+
+/// What to store for `target`: URLs as given, local paths made absolute so
+/// they still resolve from another directory. A path must exist now.
+pub fn resolve(target: &str) -> Result<String, String> {
+    if is_url(target) {
+        return Ok(target.to_string());
+    }
+    let path = Path::new(target);
+    if !path.exists() {
+        return Err(format!("No such file: '{target}'"));
+    }
+    let absolute = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve '{target}': {e}"))?;
+    Ok(absolute.display().to_string())
+}
+
+impl Task {
+    /// Attach `target` unless it's already attached. Returns false if it was.
+    pub fn attach(&mut self, target: String, now: NaiveDateTime) -> bool {
+        if self.attachments.iter().any(|a| a.target == target) {
+            return false;
+        }
+        self.attachments.push(Attachment { target, added: now });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Priority;
+    use crate::testing;
+
+    fn at(hour: u32) -> NaiveDateTime {
+        testing::at(1, hour, 0)
+    }
+
+    #[test]
+    fn test_urls_are_kept_as_given() {
+        assert!(is_url("https://github.com/me/repo/issues/123"));
+        assert!(!is_url("./spec.pdf"));
+        assert_eq!(
+            resolve("https://example.com/a"),
+            Ok("https://example.com/a".to_string())
+        );
+    }
+
+    #[test]
+    fn test_paths_must_exist() {
+        let resolved = resolve("Cargo.toml").unwrap();
+        assert!(Path::new(&resolved).is_absolute());
+        assert!(resolved.ends_with("Cargo.toml"));
+        assert!(resolve("./no-such-spec.pdf")
+            .unwrap_err()
+            .contains("No such file"));
+    }
+
+    #[test]
+    fn test_attach_skips_duplicates() {
+        let mut task = Task::new(1, "Review".to_string(), Priority::Medium);
+        assert!(task.attach("https://example.com/pr/1".to_string(), at(9)));
+        assert!(!task.attach("https://example.com/pr/1".to_string(), at(10)));
+        assert!(task.attach("/tmp/notes.txt".to_string(), at(11)));
+        assert_eq!(task.attachments.len(), 2);
+        assert_eq!(task.attachments[0].added, at(9));
+    }
+}
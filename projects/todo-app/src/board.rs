@@ -0,0 +1,126 @@
+use crate::task::{Priority, Status, Task};
+use serde::Serialize;
+
+/// Width of each board column, in characters
+const COLUMN_WIDTH: usize = 28;
+
+/// Tasks grouped by status, for `todo board` and its JSON output.
+#[derive(Debug, Serialize)]
+pub struct Column<'a> {
+    pub status: Status,
+    pub tasks: Vec<&'a Task>,
+}
+
+/// One column per status, in board order. Within a column, high priority
+/// comes first, then lower IDs.
+pub fn columns(tasks: &[Task]) -> Vec<Column<'_>> {
+    Status::ALL
+        .iter()
+        .map(|&status| {
+            let mut column: Vec<&Task> = tasks.iter().filter(|t| t.status == status).collect();
+            column.sort_by_key(|t| (rank(&t.priority), t.id));
+            Column {
+                status,
+                tasks: column,
+            }
+        })
+        .collect()
+}
+
+fn rank(priority: &Priority) -> u8 {
+    match priority {
+        Priority::High => 0,
+        Priority::Medium => 1,
+        Priority::Low => 2,
+    }
+}
+
+/// `3   Description`, cut to fit a column. High priority gets a `!`.
+fn card(task: &Task) -> String {
+    let marker = if task.priority == Priority::High {
+        "!"
+    } else {
+        " "
+    };
+    let text = format!("{:<3}{marker}{}", task.id, task.description);
+    if text.chars().count() <= COLUMN_WIDTH {
+        return text;
+    }
+    let cut: String = text.chars().take(COLUMN_WIDTH - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// The board as lines: a heading per column, then the cards side by side.
+pub fn render(columns: &[Column]) -> Vec<String> {
+    let row = |cells: Vec<String>| -> String {
+        let padded: Vec<String> = cells
+            .iter()
+            .map(|c| format!("{c}{}", " ".repeat(COLUMN_WIDTH - c.chars().count())))
+            .collect();
+        format!("  {}", padded.join("  ")).trim_end().to_string()
+    };
+
+    let mut lines = vec![
+        row(columns
+            .iter()
+            .map(|c| format!("{} ({})", c.status.title(), c.tasks.len()))
+            .collect()),
+        row(columns.iter().map(|_| "-".repeat(COLUMN_WIDTH)).collect()),
+    ];
+
+    let height = columns.iter().map(|c| c.tasks.len()).max().unwrap_or(0);
+    for i in 0..height {
+        lines.push(row(columns
+            .iter()
+            .map(|c| c.tasks.get(i).map(|t| card(t)).unwrap_or_default())
+            .collect()));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: u32, description: &str, priority: Priority, status: Status) -> Task {
+        let mut task = Task::new(id, description.to_string(), priority);
+        task.status = status;
+        task
+    }
+
+    #[test]
+    fn test_columns_group_and_order() {
+        let tasks = vec![
+            task(1, "Low", Priority::Low, Status::Backlog),
+            task(2, "High", Priority::High, Status::Backlog),
+            task(3, "Doing", Priority::Medium, Status::InProgress),
+        ];
+        let ids: Vec<Vec<u32>> = columns(&tasks)
+            .iter()
+            .map(|c| c.tasks.iter().map(|t| t.id).collect())
+            .collect();
+        assert_eq!(ids, vec![vec![2, 1], vec![3], vec![]]);
+    }
+
+    #[test]
+    fn test_render() {
+        let tasks = vec![
+            task(1, "Write the parser", Priority::High, Status::InProgress),
+            task(
+                2,
+                "A description far too long for one column",
+                Priority::Medium,
+                Status::Backlog,
+            ),
+            task(3, "Set up CI", Priority::Low, Status::Backlog),
+        ];
+        let lines = render(&columns(&tasks));
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("  Backlog (2)"));
+        assert!(lines[0].contains("In Progress (1)"));
+        assert!(lines[0].ends_with("Done (0)"));
+        assert!(lines[2].contains("2   A description far too l…"));
+        assert!(lines[2].ends_with("1  !Write the parser"));
+        assert_eq!(lines[3].trim(), "3   Set up CI");
+    }
+}
@@ -0,0 +1,106 @@
+use crate::task::Task;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Normalize a context given to `--context` or `todo list @where`: where a
+/// task can be done, like home, office or errands. `@Office` and `office`
+/// are the same place.
+pub fn parse(input: &str) -> Result<String, String> {
+    let name = input.trim().trim_start_matches('@').to_lowercase();
+    if !name.starts_with(char::is_alphabetic) || name.contains(char::is_whitespace) {
+        return Err(format!(
+            "Invalid context: '{input}'. Use a single word, e.g. @office"
+        ));
+    }
+    Ok(name)
+}
+
+/// Open tasks in one context, for `todo contexts`.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ContextCount {
+    /// None for tasks without a context
+    pub context: Option<String>,
+    pub open: usize,
+    /// Open tasks past their due date
+    pub overdue: usize,
+}
+
+/// Counts of open tasks per context, most first, with tasks that have no
+/// context last. Contexts with nothing open are left out.
+pub fn counts(tasks: &[Task], today: chrono::NaiveDate) -> Vec<ContextCount> {
+    let mut by_name: BTreeMap<Option<&str>, (usize, usize)> = BTreeMap::new();
+    for task in tasks.iter().filter(|t| !t.is_done()) {
+        let entry = by_name.entry(task.context.as_deref()).or_default();
+        entry.0 += 1;
+        if task.due.as_ref().is_some_and(|due| due.date() < today) {
+            entry.1 += 1;
+        }
+    }
+
+    let mut counts: Vec<ContextCount> = by_name
+        .into_iter()
+        .map(|(name, (open, overdue))| ContextCount {
+            context: name.map(String::from),
+            open,
+            overdue,
+        })
+        .collect();
+    counts.sort_by_key(|c| (c.context.is_none(), std::cmp::Reverse(c.open)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::Due;
+    use crate::task::Status;
+    use crate::testing::{self, may};
+
+    fn task(id: u32, context: Option<&str>, done: bool) -> Task {
+        let mut task = Task {
+            context: context.map(String::from),
+            ..testing::task(id)
+        };
+        if done {
+            task.status = Status::Done;
+        }
+        task
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("@Office"), Ok("office".to_string()));
+        assert_eq!(parse("errands"), Ok("errands".to_string()));
+        assert!(parse("@").is_err());
+        assert!(parse("@123").is_err());
+        assert!(parse("the office").is_err());
+    }
+
+    #[test]
+    fn test_counts() {
+        let today = may(1);
+        let mut late = task(4, Some("office"), false);
+        late.due = Some(Due::Date(today.pred_opt().unwrap()));
+        let tasks = [
+            task(1, Some("home"), false),
+            task(2, None, false),
+            task(3, Some("office"), false),
+            late,
+            task(5, Some("home"), true),
+            task(6, Some("errands"), true),
+        ];
+        let count = |context: Option<&str>, open, overdue| ContextCount {
+            context: context.map(String::from),
+            open,
+            overdue,
+        };
+        assert_eq!(
+            counts(&tasks, today),
+            vec![
+                count(Some("office"), 2, 1),
+                count(Some("home"), 1, 0),
+                count(None, 1, 0),
+            ]
+        );
+    }
+}
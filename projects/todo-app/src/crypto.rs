@@ -0,0 +1,223 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+/// Start of an encrypted file, followed by the salt, nonce, and ciphertext.
+const MAGIC: &[u8] = b"todo-encrypted-v1\n";
+/// Start of an encrypted line in the history log. The rest is the same
+/// salt, nonce, and ciphertext in base64.
+const LINE_PREFIX: &str = "encrypted:";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// `Some` once `--encrypt` or `todo decrypt` has decided; otherwise files
+/// keep whatever form they're already in.
+static MODE: OnceLock<bool> = OnceLock::new();
+static KEY_FILE: OnceLock<PathBuf> = OnceLock::new();
+static PASSPHRASE: OnceLock<String> = OnceLock::new();
+/// One salt for everything this run writes, so the slow key derivation
+/// happens once per run instead of once per write.
+static WRITE_SALT: OnceLock<[u8; SALT_LEN]> = OnceLock::new();
+static KEYS: Mutex<Vec<([u8; SALT_LEN], Key)>> = Mutex::new(Vec::new());
+
+pub fn set_mode(encrypt: bool) {
+    let _ = MODE.set(encrypt);
+}
+
+/// Whether to encrypt a file that is currently `encrypted` or not.
+pub fn should_encrypt(encrypted: bool) -> bool {
+    MODE.get().copied().unwrap_or(encrypted)
+}
+
+/// Read the passphrase from this file instead of prompting.
+pub fn set_key_file(path: PathBuf) {
+    let _ = KEY_FILE.set(path);
+}
+
+fn key_file() -> Option<PathBuf> {
+    KEY_FILE
+        .get()
+        .cloned()
+        .or_else(|| std::env::var_os("TODO_KEY_FILE").map(PathBuf::from))
+}
+
+fn read_key_file(path: &PathBuf) -> Result<String, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read key file {}: {e}", path.display()))?;
+    let key = content.trim_end_matches(['\r', '\n']).to_string();
+    if key.is_empty() {
+        return Err(format!("Key file {} is empty", path.display()));
+    }
+    Ok(key)
+}
+
+fn passphrase() -> Result<&'static str, String> {
+    if let Some(passphrase) = PASSPHRASE.get() {
+        return Ok(passphrase);
+    }
+
+    let passphrase = match key_file() {
+        Some(path) => read_key_file(&path)?,
+        None => rpassword::prompt_password("Passphrase: ")
+            .map_err(|e| format!("Failed to read passphrase: {e}"))?,
+    };
+    Ok(PASSPHRASE.get_or_init(|| passphrase))
+}
+
+/// Ask for a new passphrase twice before encrypting for the first time. A
+/// key file needs no confirmation.
+pub fn choose_passphrase() -> Result<(), String> {
+    if PASSPHRASE.get().is_some() || key_file().is_some() {
+        return Ok(());
+    }
+
+    let prompt = |text: &str| {
+        rpassword::prompt_password(text).map_err(|e| format!("Failed to read passphrase: {e}"))
+    };
+    let first = prompt("New passphrase: ")?;
+    if first.is_empty() {
+        return Err("The passphrase can't be empty".to_string());
+    }
+    if prompt("Repeat passphrase: ")? != first {
+        return Err("Passphrases don't match".to_string());
+    }
+    let _ = PASSPHRASE.set(first);
+    Ok(())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8; SALT_LEN]) -> Result<Key, String> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {e}"))?;
+    Ok(key)
+}
+
+/// The key for `salt`, derived from the passphrase on first use.
+fn key(salt: &[u8; SALT_LEN]) -> Result<Key, String> {
+    let mut keys = KEYS.lock().map_err(|_| "Key cache poisoned".to_string())?;
+    if let Some((_, key)) = keys.iter().find(|(s, _)| s == salt) {
+        return Ok(*key);
+    }
+    let key = derive_key(passphrase()?, salt)?;
+    keys.push((*salt, key));
+    Ok(key)
+}
+
+/// salt ‖ nonce ‖ ciphertext
+fn seal_with(key: &Key, salt: &[u8; SALT_LEN], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+
+    let mut sealed = salt.to_vec();
+    sealed.extend_from_slice(&nonce);
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+fn split(sealed: &[u8]) -> Option<([u8; SALT_LEN], &[u8], &[u8])> {
+    if sealed.len() < SALT_LEN + NONCE_LEN {
+        return None;
+    }
+    let (salt, rest) = sealed.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    Some((salt.try_into().ok()?, nonce, ciphertext))
+}
+
+/// Decrypting fails the same way for a wrong passphrase and for tampering;
+/// `what` names the file for the error.
+fn open_with(key: &Key, nonce: &[u8], ciphertext: &[u8], what: &str) -> Result<Vec<u8>, String> {
+    ChaCha20Poly1305::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| format!("Wrong passphrase for {what}, or the file is damaged"))
+}
+
+fn seal(plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let salt = WRITE_SALT.get_or_init(|| {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        salt
+    });
+    seal_with(&key(salt)?, salt, plaintext)
+}
+
+fn open(sealed: &[u8], what: &str) -> Result<Vec<u8>, String> {
+    let (salt, nonce, ciphertext) = split(sealed).ok_or(format!("{what} is damaged"))?;
+    open_with(&key(&salt)?, nonce, ciphertext, what)
+}
+
+pub fn is_encrypted(content: &[u8]) -> bool {
+    content.starts_with(MAGIC)
+}
+
+pub fn encrypt_file(plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut content = MAGIC.to_vec();
+    content.extend(seal(plaintext)?);
+    Ok(content)
+}
+
+/// The plaintext of `content`, which must be encrypted.
+pub fn decrypt_file(content: &[u8], what: &str) -> Result<Vec<u8>, String> {
+    open(&content[MAGIC.len()..], what)
+}
+
+pub fn encrypt_line(line: &str) -> Result<String, String> {
+    Ok(format!(
+        "{LINE_PREFIX}{}",
+        STANDARD.encode(seal(line.as_bytes())?)
+    ))
+}
+
+/// Plain lines pass through unchanged.
+pub fn decrypt_line(line: &str, what: &str) -> Result<String, String> {
+    let Some(encoded) = line.strip_prefix(LINE_PREFIX) else {
+        return Ok(line.to_string());
+    };
+    let sealed = STANDARD
+        .decode(encoded)
+        .map_err(|_| format!("{what} is damaged"))?;
+    let plaintext = open(&sealed, what)?;
+    String::from_utf8(plaintext).map_err(|_| format!("{what} is damaged"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_wrong_passphrase() {
+        let salt = [7; SALT_LEN];
+        let key = derive_key("correct horse", &salt).unwrap();
+        let sealed = seal_with(&key, &salt, b"[1, 2, 3]").unwrap();
+        assert!(!sealed.windows(9).any(|w| w == b"[1, 2, 3]"));
+
+        let (read_salt, nonce, ciphertext) = split(&sealed).unwrap();
+        assert_eq!(read_salt, salt);
+        assert_eq!(
+            open_with(&key, nonce, ciphertext, "todos.json").unwrap(),
+            b"[1, 2, 3]"
+        );
+
+        let wrong = derive_key("battery staple", &salt).unwrap();
+        assert_eq!(
+            open_with(&wrong, nonce, ciphertext, "todos.json").unwrap_err(),
+            "Wrong passphrase for todos.json, or the file is damaged"
+        );
+    }
+
+    #[test]
+    fn test_plain_content_is_recognized() {
+        assert!(!is_encrypted(b"[]"));
+        assert!(is_encrypted(b"todo-encrypted-v1\nrest"));
+        assert_eq!(decrypt_line("{}", "log").unwrap(), "{}");
+        assert!(split(&[0; 10]).is_none());
+    }
+}
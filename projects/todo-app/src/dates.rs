@@ -0,0 +1,455 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// When a task is due: either a whole day or a specific time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Due {
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+}
+
+impl fmt::Display for Due {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Due::Date(d) => write!(f, "{}", d.format("%a %Y-%m-%d")),
+            Due::DateTime(dt) => write!(f, "{}", dt.format("%a %Y-%m-%d %H:%M")),
+        }
+    }
+}
+
+impl Due {
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            Due::Date(d) => *d,
+            Due::DateTime(dt) => dt.date(),
+        }
+    }
+
+    /// The last moment the task still counts as on time; a whole-day due
+    /// date lasts until the end of that day.
+    pub fn deadline(&self) -> NaiveDateTime {
+        match self {
+            Due::Date(d) => d
+                .and_hms_opt(23, 59, 59)
+                .unwrap_or(d.and_time(NaiveTime::MIN)),
+            Due::DateTime(dt) => *dt,
+        }
+    }
+
+    pub fn is_overdue(&self, now: NaiveDateTime) -> bool {
+        self.deadline() < now
+    }
+
+    /// Describe the due date relative to `now`: "due in 3 days",
+    /// "due tomorrow", "due in 2 hours", "overdue by 1 day".
+    pub fn relative(&self, now: NaiveDateTime) -> String {
+        let days = (self.date() - now.date()).num_days();
+
+        if self.is_overdue(now) {
+            if days < 0 {
+                return format!("overdue by {}", plural(-days, "day"));
+            }
+            return format!("overdue by {}", hours_or_minutes(now - self.deadline()));
+        }
+
+        match (days, self) {
+            (0, Due::DateTime(dt)) => format!("due in {}", hours_or_minutes(*dt - now)),
+            (0, Due::Date(_)) => "due today".to_string(),
+            (1, _) => "due tomorrow".to_string(),
+            _ => format!("due in {}", plural(days, "day")),
+        }
+    }
+}
+
+fn plural(n: i64, unit: &str) -> String {
+    if n == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{n} {unit}s")
+    }
+}
+
+/// Rounded to the nearest hour once it's an hour or more.
+fn hours_or_minutes(d: Duration) -> String {
+    let minutes = d.num_minutes();
+    if minutes >= 60 {
+        plural((minutes + 30) / 60, "hour")
+    } else {
+        plural(minutes.max(1), "minute")
+    }
+}
+
+/// Parse a human-friendly due date relative to `now`.
+///
+/// Understands:
+/// - `today`, `tonight`, `tomorrow`
+/// - weekdays: `friday`, `this friday` (next one, counting today), `next friday`
+///   (strictly after today)
+/// - `next week`, `next month`
+/// - `in 3 days`, `in 2 weeks`, `in 4 hours`, `in 30 minutes`, `in a week`
+/// - ISO dates: `2024-05-03`
+///
+/// Any of the day forms can be followed by a time: `5pm`, `5:30pm`, `17:00`,
+/// `noon`, `midnight`, optionally preceded by `at`.
+pub fn parse_due(input: &str, now: NaiveDateTime) -> Result<Due, String> {
+    let lower = input.trim().to_lowercase();
+    let mut words: Vec<&str> = lower.split_whitespace().collect();
+    let invalid = || format!("Couldn't understand due date: '{input}'");
+
+    if words.is_empty() {
+        return Err(invalid());
+    }
+
+    // "in 3 hours" is already a full timestamp
+    if words[0] == "in" {
+        return parse_offset(&words[1..], now).ok_or_else(invalid);
+    }
+
+    let mut time = None;
+    if let Some(t) = words.last().and_then(|w| parse_time(w)) {
+        time = Some(t);
+        words.pop();
+        if words.last() == Some(&"at") {
+            words.pop();
+        }
+    }
+
+    let today = now.date();
+    let date = match words.as_slice() {
+        // A bare time means today
+        [] if time.is_some() => today,
+        ["today"] => today,
+        ["tonight"] => {
+            time = time.or(NaiveTime::from_hms_opt(20, 0, 0));
+            today
+        }
+        ["tomorrow"] => today + Duration::days(1),
+        ["next", "week"] => today + Duration::weeks(1),
+        ["next", "month"] => add_month(today).ok_or_else(invalid)?,
+        ["next", day] => next_weekday(today, parse_weekday(day).ok_or_else(invalid)?, false),
+        ["this", day] | [day] if parse_weekday(day).is_some() => {
+            next_weekday(today, parse_weekday(day).unwrap(), true)
+        }
+        [iso] => NaiveDate::parse_from_str(iso, "%Y-%m-%d").map_err(|_| invalid())?,
+        _ => return Err(invalid()),
+    };
+
+    Ok(match time {
+        Some(t) => Due::DateTime(date.and_time(t)),
+        None => Due::Date(date),
+    })
+}
+
+fn parse_offset(words: &[&str], now: NaiveDateTime) -> Option<Due> {
+    let [amount, unit] = words else {
+        return None;
+    };
+
+    let n: i64 = match *amount {
+        "a" | "an" | "one" => 1,
+        _ => amount.parse().ok()?,
+    };
+
+    let unit = unit.trim_end_matches('s');
+    match unit {
+        "minute" | "min" => Some(Due::DateTime(now + Duration::minutes(n))),
+        "hour" | "hr" => Some(Due::DateTime(now + Duration::hours(n))),
+        "day" => Some(Due::Date(now.date() + Duration::days(n))),
+        "week" => Some(Due::Date(now.date() + Duration::weeks(n))),
+        _ => None,
+    }
+}
+
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    match word {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+
+    let (clock, offset) = if let Some(t) = word.strip_suffix("am") {
+        (t, Some(0))
+    } else if let Some(t) = word.strip_suffix("pm") {
+        (t, Some(12))
+    } else {
+        (word, None)
+    };
+
+    let (hour, minute) = match clock.split_once(':') {
+        Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        // A bare number is only a time with am/pm ("5pm"), otherwise it's ambiguous
+        None if offset.is_some() => (clock.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+
+    let hour = match offset {
+        Some(add) if (1..=12).contains(&hour) => hour % 12 + add,
+        Some(_) => return None,
+        None => hour,
+    };
+
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" | "tues" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" | "thurs" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// The next `target` weekday on or after `from` (or strictly after, if
+/// `include_today` is false).
+fn next_weekday(from: NaiveDate, target: Weekday, include_today: bool) -> NaiveDate {
+    let mut days = (7 + target.num_days_from_monday() as i64
+        - from.weekday().num_days_from_monday() as i64)
+        % 7;
+    if days == 0 && !include_today {
+        days = 7;
+    }
+    from + Duration::days(days)
+}
+
+/// A length of time like `30m`, `4h`, `3d` or `2w`, as `todo snooze` takes.
+pub fn parse_duration(input: &str) -> Result<Duration, String> {
+    let invalid = || format!("Couldn't understand '{input}'; use something like 30m, 4h, 3d or 2w");
+    let input = input.trim().to_lowercase();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let n: i64 = input[..split].parse().map_err(|_| invalid())?;
+    if n == 0 {
+        return Err(invalid());
+    }
+
+    let duration = match &input[split..] {
+        "m" | "min" | "mins" => Duration::try_minutes(n),
+        "h" | "hr" | "hrs" => Duration::try_hours(n),
+        "d" | "day" | "days" => Duration::try_days(n),
+        "w" | "wk" | "wks" => Duration::try_weeks(n),
+        _ => return Err(invalid()),
+    };
+    duration.ok_or_else(|| format!("'{input}' is too long a duration"))
+}
+
+/// How often a task comes back once it's done.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly => write!(f, "weekly"),
+            Recurrence::Monthly => write!(f, "monthly"),
+            Recurrence::Yearly => write!(f, "yearly"),
+        }
+    }
+}
+
+impl Recurrence {
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s.trim().to_lowercase().as_str() {
+            "daily" | "day" => Ok(Recurrence::Daily),
+            "weekly" | "week" => Ok(Recurrence::Weekly),
+            "monthly" | "month" => Ok(Recurrence::Monthly),
+            "yearly" | "year" | "annually" => Ok(Recurrence::Yearly),
+            _ => Err(format!(
+                "Invalid recurrence: '{s}'. Use daily, weekly, monthly, or yearly"
+            )),
+        }
+    }
+
+    /// When the next occurrence is due, one period after `due`. The 31st
+    /// of a month recurs on the last day of shorter months.
+    pub fn after(&self, due: Due) -> Option<Due> {
+        let next = |date: NaiveDate| match self {
+            Recurrence::Daily => Some(date + Duration::days(1)),
+            Recurrence::Weekly => Some(date + Duration::weeks(1)),
+            Recurrence::Monthly => add_month(date),
+            Recurrence::Yearly => date.checked_add_months(chrono::Months::new(12)),
+        };
+        Some(match due {
+            Due::Date(d) => Due::Date(next(d)?),
+            Due::DateTime(dt) => Due::DateTime(next(dt.date())?.and_time(dt.time())),
+        })
+    }
+}
+
+fn add_month(date: NaiveDate) -> Option<NaiveDate> {
+    date.checked_add_months(chrono::Months::new(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::now;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn at(d: NaiveDate, h: u32, m: u32) -> Due {
+        Due::DateTime(d.and_hms_opt(h, m, 0).unwrap())
+    }
+
+    #[test]
+    fn test_relative_days() {
+        assert_eq!(parse_due("today", now()), Ok(Due::Date(date(2024, 5, 1))));
+        assert_eq!(
+            parse_due("Tomorrow", now()),
+            Ok(Due::Date(date(2024, 5, 2)))
+        );
+        assert_eq!(
+            parse_due("in 3 days", now()),
+            Ok(Due::Date(date(2024, 5, 4)))
+        );
+        assert_eq!(
+            parse_due("in a week", now()),
+            Ok(Due::Date(date(2024, 5, 8)))
+        );
+        assert_eq!(
+            parse_due("next month", now()),
+            Ok(Due::Date(date(2024, 6, 1)))
+        );
+    }
+
+    #[test]
+    fn test_weekdays() {
+        assert_eq!(parse_due("friday", now()), Ok(Due::Date(date(2024, 5, 3))));
+        assert_eq!(
+            parse_due("next friday", now()),
+            Ok(Due::Date(date(2024, 5, 3)))
+        );
+        // Today is Wednesday
+        assert_eq!(
+            parse_due("wednesday", now()),
+            Ok(Due::Date(date(2024, 5, 1)))
+        );
+        assert_eq!(
+            parse_due("next wed", now()),
+            Ok(Due::Date(date(2024, 5, 8)))
+        );
+    }
+
+    #[test]
+    fn test_times() {
+        assert_eq!(
+            parse_due("tomorrow 5pm", now()),
+            Ok(at(date(2024, 5, 2), 17, 0))
+        );
+        assert_eq!(
+            parse_due("friday at 9:30am", now()),
+            Ok(at(date(2024, 5, 3), 9, 30))
+        );
+        assert_eq!(
+            parse_due("today 17:45", now()),
+            Ok(at(date(2024, 5, 1), 17, 45))
+        );
+        assert_eq!(parse_due("noon", now()), Ok(at(date(2024, 5, 1), 12, 0)));
+        assert_eq!(parse_due("12am", now()), Ok(at(date(2024, 5, 1), 0, 0)));
+        assert_eq!(
+            parse_due("in 2 hours", now()),
+            Ok(at(date(2024, 5, 1), 11, 0))
+        );
+    }
+
+    #[test]
+    fn test_iso_date() {
+        assert_eq!(
+            parse_due("2024-12-25", now()),
+            Ok(Due::Date(date(2024, 12, 25)))
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse_due("someday", now()).is_err());
+        assert!(parse_due("in three fortnights", now()).is_err());
+        assert!(parse_due("13pm", now()).is_err());
+        assert!(parse_due("", now()).is_err());
+    }
+
+    #[test]
+    fn test_relative() {
+        let due = |d: u32| Due::Date(date(2024, 5, d));
+        assert_eq!(due(1).relative(now()), "due today");
+        assert_eq!(due(2).relative(now()), "due tomorrow");
+        assert_eq!(due(4).relative(now()), "due in 3 days");
+        assert_eq!(
+            due(1).relative(at(date(2024, 5, 2), 9, 0).deadline()),
+            "overdue by 1 day"
+        );
+        assert_eq!(
+            at(date(2024, 5, 1), 11, 0).relative(now()),
+            "due in 2 hours"
+        );
+        assert_eq!(
+            at(date(2024, 5, 1), 9, 20).relative(now()),
+            "due in 20 minutes"
+        );
+        assert_eq!(
+            at(date(2024, 5, 1), 8, 0).relative(now()),
+            "overdue by 1 hour"
+        );
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("3d"), Ok(Duration::days(3)));
+        assert_eq!(parse_duration("30m"), Ok(Duration::minutes(30)));
+        assert_eq!(parse_duration("4H"), Ok(Duration::hours(4)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::weeks(2)));
+        assert!(parse_duration("0d").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3 years").is_err());
+        assert!(parse_duration("999999999999999d")
+            .unwrap_err()
+            .contains("too long"));
+    }
+
+    #[test]
+    fn test_recurrence() {
+        assert_eq!(Recurrence::from_str("Monthly"), Ok(Recurrence::Monthly));
+        assert!(Recurrence::from_str("hourly").is_err());
+
+        let jan31 = Due::Date(date(2024, 1, 31));
+        assert_eq!(
+            Recurrence::Monthly.after(jan31),
+            Some(Due::Date(date(2024, 2, 29)))
+        );
+        assert_eq!(
+            Recurrence::Weekly.after(jan31),
+            Some(Due::Date(date(2024, 2, 7)))
+        );
+        let evening = date(2024, 2, 29).and_hms_opt(17, 0, 0).unwrap();
+        assert_eq!(
+            Recurrence::Yearly.after(Due::DateTime(evening)),
+            Some(Due::DateTime(
+                date(2025, 2, 28).and_hms_opt(17, 0, 0).unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_due_serialization() {
+        let d = Due::Date(date(2024, 5, 3));
+        assert_eq!(serde_json::to_string(&d).unwrap(), "\"2024-05-03\"");
+        let parsed: Due = serde_json::from_str("\"2024-05-03T17:00:00\"").unwrap();
+        assert_eq!(parsed, at(date(2024, 5, 3), 17, 0));
+    }
+}
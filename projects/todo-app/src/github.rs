@@ -0,0 +1,414 @@
+//! Tasks as GitHub issues labelled `todo`, for `TODO_BACKEND=github`. Adding
+//! a task opens an issue, finishing it closes the issue, and removing it
+//! closes the issue as not planned.
+//!
+//! The whole task is kept as JSON in a comment at the end of the issue body,
+//! so fields GitHub has no place for survive the round trip. The title and
+//! open/closed state on GitHub win, so edits made there show up here.
+
+use crate::remote::{BoxFuture, RemoteBackend};
+use crate::storage;
+use crate::task::{Priority, Status, Task};
+use chrono::Local;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+const API: &str = "https://api.github.com";
+
+/// Only issues with this label are tasks
+const LABEL: &str = "todo";
+
+const MARKER: &str = "<!-- todo-app";
+
+const PER_PAGE: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    pub body: Option<String>,
+    /// "open" or "closed"
+    pub state: String,
+    pub state_reason: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<Label>,
+    /// Set on pull requests, which the issues API lists too
+    pub pull_request: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Label {
+    pub name: String,
+}
+
+/// A task and the issue it lives in.
+#[derive(Debug, PartialEq)]
+pub struct Linked {
+    pub number: u64,
+    pub task: Task,
+    /// Whether the issue body holds the task. Issues opened by hand don't
+    /// until the first save writes it.
+    pub stored: bool,
+}
+
+/// What a save has to do on GitHub.
+#[derive(Debug, PartialEq)]
+pub enum Change<'a> {
+    Open(&'a Task),
+    Update(u64, &'a Task),
+    /// Removed: close as not planned
+    Close(u64),
+}
+
+/// The issue body for `task`. `-->` would end the comment early, so `>` is
+/// written as a JSON escape instead.
+pub fn body(task: &Task) -> Result<String, String> {
+    let json = serde_json::to_string(task).map_err(|e| format!("Failed to serialize task: {e}"))?;
+    Ok(format!("{MARKER}\n{}\n-->", json.replace('>', "\\u003e")))
+}
+
+fn stored_task(body: &str) -> Option<Task> {
+    let start = body.find(MARKER)? + MARKER.len();
+    let end = start + body[start..].find("-->")?;
+    serde_json::from_str(body[start..end].trim()).ok()
+}
+
+/// Turn `issues` into tasks, skipping pull requests and issues closed as not
+/// planned. Issues opened by hand get IDs after the stored ones, in issue
+/// order, so they come out the same every time until they're saved.
+pub fn tasks_from_issues(issues: &[Issue]) -> Vec<Linked> {
+    let mut linked = Vec::new();
+    let mut by_hand = Vec::new();
+
+    for issue in issues {
+        if issue.pull_request.is_some() || issue.state_reason.as_deref() == Some("not_planned") {
+            continue;
+        }
+        let stored = issue.body.as_deref().and_then(stored_task);
+        let is_stored = stored.is_some();
+        let mut task = match stored {
+            Some(task) => task,
+            None => {
+                let mut task = Task::new(0, String::new(), Priority::Medium);
+                task.tags = issue
+                    .labels
+                    .iter()
+                    .map(|l| l.name.clone())
+                    .filter(|name| name != LABEL)
+                    .collect();
+                task
+            }
+        };
+
+        task.description = issue.title.clone();
+        if issue.state == "closed" {
+            task.status = Status::Done;
+        } else if task.is_done() {
+            // Reopened on GitHub
+            task.status = Status::Backlog;
+        }
+
+        let entry = Linked {
+            number: issue.number,
+            task,
+            stored: is_stored,
+        };
+        if entry.stored {
+            linked.push(entry);
+        } else {
+            by_hand.push(entry);
+        }
+    }
+
+    by_hand.sort_by_key(|l| l.number);
+    for mut entry in by_hand {
+        entry.task.id = next_id(&linked);
+        linked.push(entry);
+    }
+    linked
+}
+
+fn next_id(linked: &[Linked]) -> u32 {
+    linked.iter().map(|l| l.task.id).max().unwrap_or(0) + 1
+}
+
+/// The requests that make the issues in `remote` match `tasks`.
+pub fn plan<'a>(remote: &[Linked], tasks: &'a [Task]) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    for task in tasks {
+        match remote.iter().find(|l| l.task.id == task.id) {
+            None => changes.push(Change::Open(task)),
+            Some(linked) if !linked.stored || linked.task != *task => {
+                changes.push(Change::Update(linked.number, task))
+            }
+            Some(_) => {}
+        }
+    }
+    for linked in remote {
+        if !tasks.iter().any(|t| t.id == linked.task.id) {
+            changes.push(Change::Close(linked.number));
+        }
+    }
+    changes
+}
+
+/// The `todo` label plus one per tag
+fn labels(task: &Task) -> Vec<&str> {
+    std::iter::once(LABEL)
+        .chain(task.tags.iter().map(String::as_str))
+        .collect()
+}
+
+fn state(task: &Task) -> &'static str {
+    if task.is_done() {
+        "closed"
+    } else {
+        "open"
+    }
+}
+
+pub struct GitHubIssues {
+    client: reqwest::Client,
+    /// owner/name
+    repo: String,
+    token: String,
+}
+
+impl GitHubIssues {
+    pub fn new(repo: String, token: String) -> Self {
+        GitHubIssues {
+            client: reqwest::Client::new(),
+            repo,
+            token,
+        }
+    }
+
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, String> {
+        let mut request = self
+            .client
+            .request(method, format!("{API}/repos/{}{path}", self.repo))
+            .header("User-Agent", "todo-app")
+            .header("Accept", "application/vnd.github+json")
+            .bearer_auth(&self.token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach GitHub: {e}"))?;
+        let status = response.status();
+        let json: Value = response.json().await.unwrap_or(Value::Null);
+
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(format!(
+                "Repository '{}' not found, or the token can't see it",
+                self.repo
+            ));
+        }
+        if !status.is_success() {
+            let message = json["message"].as_str().unwrap_or("no details");
+            return Err(format!("GitHub API error: {status}: {message}"));
+        }
+        Ok(json)
+    }
+
+    async fn fetch_issues(&self) -> Result<Vec<Issue>, String> {
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let path = format!("/issues?labels={LABEL}&state=all&per_page={PER_PAGE}&page={page}");
+            let json = self.request(reqwest::Method::GET, &path, None).await?;
+            let batch: Vec<Issue> = serde_json::from_value(json)
+                .map_err(|e| format!("Unexpected response from GitHub: {e}"))?;
+            let done = batch.len() < PER_PAGE;
+            issues.extend(batch);
+            if done {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    async fn apply(&self, change: &Change<'_>) -> Result<(), String> {
+        use reqwest::Method;
+
+        match *change {
+            Change::Open(task) => {
+                let issue = json!({
+                    "title": task.description,
+                    "body": body(task)?,
+                    "labels": labels(task),
+                });
+                let created = self.request(Method::POST, "/issues", Some(issue)).await?;
+                // Issues can't be opened closed, as an imported done task
+                // would need
+                if task.is_done() {
+                    let number = created["number"]
+                        .as_u64()
+                        .ok_or("GitHub sent no issue number")?;
+                    let path = format!("/issues/{number}");
+                    let close = json!({ "state": "closed" });
+                    self.request(Method::PATCH, &path, Some(close)).await?;
+                }
+            }
+            Change::Update(number, task) => {
+                let issue = json!({
+                    "title": task.description,
+                    "body": body(task)?,
+                    "labels": labels(task),
+                    "state": state(task),
+                });
+                let path = format!("/issues/{number}");
+                self.request(Method::PATCH, &path, Some(issue)).await?;
+            }
+            Change::Close(number) => {
+                let close = json!({ "state": "closed", "state_reason": "not_planned" });
+                let path = format!("/issues/{number}");
+                self.request(Method::PATCH, &path, Some(close)).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RemoteBackend for GitHubIssues {
+    fn load(&self) -> BoxFuture<'_, Result<Vec<Task>, String>> {
+        Box::pin(async {
+            let linked = tasks_from_issues(&self.fetch_issues().await?);
+            Ok(linked.into_iter().map(|l| l.task).collect())
+        })
+    }
+
+    /// Compares against the issues as they are now, so only tasks that
+    /// changed cost a request.
+    fn save<'a>(&'a self, tasks: &'a [Task]) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(async move {
+            let remote = tasks_from_issues(&self.fetch_issues().await?);
+            let previous: Vec<Task> = remote.iter().map(|l| l.task.clone()).collect();
+            let tasks = storage::stamp_changes(&previous, tasks, Local::now().naive_local());
+            for change in plan(&remote, &tasks) {
+                self.apply(&change).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issue(number: u64, title: &str, body: Option<String>, state: &str) -> Issue {
+        serde_json::from_value(json!({
+            "number": number,
+            "title": title,
+            "body": body,
+            "state": state,
+            "state_reason": if state == "closed" { Some("completed") } else { None },
+            "labels": [{ "name": "todo" }, { "name": "docs" }],
+        }))
+        .unwrap()
+    }
+
+    fn task(id: u32, description: &str) -> Task {
+        Task::new(id, description.to_string(), Priority::High)
+    }
+
+    #[test]
+    fn test_body_round_trip() {
+        let mut original = task(3, "Fix <!-- --> in the docs");
+        original.tags = vec!["docs".to_string()];
+        let body = body(&original).unwrap();
+        assert!(body.starts_with(MARKER));
+        assert_eq!(body.matches("-->").count(), 1);
+        assert_eq!(stored_task(&body), Some(original));
+        assert_eq!(stored_task("Just an issue"), None);
+    }
+
+    #[test]
+    fn test_tasks_from_issues() {
+        let mut stored = task(7, "Old title");
+        stored.status = Status::Done;
+        let mut pull: Issue = issue(9, "A pull request", None, "open");
+        pull.pull_request = Some(json!({}));
+        let mut dropped = issue(10, "Won't do", None, "closed");
+        dropped.state_reason = Some("not_planned".to_string());
+        let issues = vec![
+            issue(12, "By hand", None, "open"),
+            issue(4, "Renamed on GitHub", Some(body(&stored).unwrap()), "open"),
+            issue(
+                5,
+                "Closed on GitHub",
+                Some(body(&task(2, "x")).unwrap()),
+                "closed",
+            ),
+            pull,
+            dropped,
+        ];
+
+        let linked = tasks_from_issues(&issues);
+        let summary: Vec<(u64, u32, &str, Status, bool)> = linked
+            .iter()
+            .map(|l| {
+                let t = &l.task;
+                (l.number, t.id, t.description.as_str(), t.status, l.stored)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (4, 7, "Renamed on GitHub", Status::Backlog, true),
+                (5, 2, "Closed on GitHub", Status::Done, true),
+                (12, 8, "By hand", Status::Backlog, false),
+            ]
+        );
+        assert_eq!(linked[2].task.tags, ["docs"]);
+    }
+
+    #[test]
+    fn test_plan() {
+        let kept = task(1, "Kept");
+        let removed = task(2, "Removed");
+        let remote = vec![
+            Linked {
+                number: 11,
+                task: kept.clone(),
+                stored: true,
+            },
+            Linked {
+                number: 12,
+                task: removed,
+                stored: true,
+            },
+            Linked {
+                number: 13,
+                task: task(3, "By hand"),
+                stored: false,
+            },
+        ];
+        let mut done = kept.clone();
+        done.status = Status::Done;
+        let added = task(4, "Added");
+
+        let tasks = [kept.clone(), task(3, "By hand"), added.clone()];
+        assert_eq!(
+            plan(&remote, &tasks),
+            [
+                // Written once so its ID sticks
+                Change::Update(13, &tasks[1]),
+                Change::Open(&added),
+                Change::Close(12),
+            ]
+        );
+
+        let tasks = [done.clone()];
+        assert_eq!(plan(&remote[..1], &tasks), [Change::Update(11, &done)]);
+        assert_eq!(state(&done), "closed");
+    }
+}
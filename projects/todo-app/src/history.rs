@@ -0,0 +1,278 @@
+use crate::crypto;
+use crate::storage;
+use crate::task::Task;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Append-only, one JSON event per line, so past entries are never rewritten.
+/// Only encrypting or decrypting the store rewrites it. Lines are encrypted
+/// one at a time whenever todos.json is.
+const LOG_FILE: &str = "todos.log.jsonl";
+
+/// One change to one task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub at: NaiveDateTime,
+    pub task: u32,
+    /// The description at the time, so removed tasks still read sensibly
+    pub description: String,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Change {
+    Created,
+    /// Names of the fields that changed
+    Edited {
+        fields: Vec<String>,
+    },
+    Completed,
+    Reopened,
+    Removed,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Created => write!(f, "created"),
+            Change::Edited { .. } => write!(f, "edited"),
+            Change::Completed => write!(f, "completed"),
+            Change::Reopened => write!(f, "reopened"),
+            Change::Removed => write!(f, "removed"),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "  {}  {:<4} {:<10} {}",
+            self.at.format("%Y-%m-%d %H:%M"),
+            self.task,
+            self.change.to_string(),
+            self.description
+        )?;
+        if let Change::Edited { fields } = &self.change {
+            write!(f, " ({})", fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The events that turn `previous` into `tasks`, matching tasks by ID.
+pub fn changes(previous: &[Task], tasks: &[Task], at: NaiveDateTime) -> Vec<Event> {
+    let event = |task: &Task, change| Event {
+        at,
+        task: task.id,
+        description: task.description.clone(),
+        change,
+    };
+    let mut events = Vec::new();
+
+    for task in tasks {
+        let Some(old) = previous.iter().find(|t| t.id == task.id) else {
+            events.push(event(task, Change::Created));
+            continue;
+        };
+
+        let fields = changed_fields(old, task);
+        if !fields.is_empty() {
+            events.push(event(task, Change::Edited { fields }));
+        }
+        match (old.is_done(), task.is_done()) {
+            (false, true) => events.push(event(task, Change::Completed)),
+            (true, false) => events.push(event(task, Change::Reopened)),
+            _ => {}
+        }
+    }
+
+    for old in previous {
+        if !tasks.iter().any(|t| t.id == old.id) {
+            events.push(event(old, Change::Removed));
+        }
+    }
+
+    events
+}
+
+/// Everything that can change except completion, which has its own events,
+/// and `modified`, which is bookkeeping.
+fn changed_fields(old: &Task, new: &Task) -> Vec<String> {
+    let mut fields = Vec::new();
+    if old.description != new.description {
+        fields.push("description");
+    }
+    if old.priority != new.priority {
+        fields.push("priority");
+    }
+    if old.due != new.due {
+        fields.push("due");
+    }
+    if old.tags != new.tags {
+        fields.push("tags");
+    }
+    // Moving to or from done is completing or reopening
+    if old.status != new.status && !old.is_done() && !new.is_done() {
+        fields.push("status");
+    }
+    if old.assignee != new.assignee {
+        fields.push("assignee");
+    }
+    if old.snoozed_until != new.snoozed_until {
+        fields.push("snooze");
+    }
+    if old.intervals != new.intervals {
+        fields.push("tracked time");
+    }
+    fields.into_iter().map(String::from).collect()
+}
+
+pub fn append(events: &[Event]) -> Result<(), String> {
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let lines = to_lines(events)?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(LOG_FILE)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .map_err(|e| format!("Failed to write {LOG_FILE}: {e}"))
+}
+
+/// Replace the whole log with `events`.
+pub fn rewrite(events: &[Event]) -> Result<(), String> {
+    if events.is_empty() && !Path::new(LOG_FILE).exists() {
+        return Ok(());
+    }
+    fs::write(LOG_FILE, to_lines(events)?).map_err(|e| format!("Failed to write {LOG_FILE}: {e}"))
+}
+
+fn to_lines(events: &[Event]) -> Result<String, String> {
+    let encrypt = crypto::should_encrypt(storage::is_encrypted());
+    let mut lines = String::new();
+    for event in events {
+        let mut line =
+            serde_json::to_string(event).map_err(|e| format!("Failed to serialize event: {e}"))?;
+        if encrypt {
+            line = crypto::encrypt_line(&line)?;
+        }
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    Ok(lines)
+}
+
+pub fn load() -> Result<Vec<Event>, String> {
+    if !Path::new(LOG_FILE).exists() {
+        return Ok(Vec::new());
+    }
+
+    let content =
+        fs::read_to_string(LOG_FILE).map_err(|e| format!("Failed to read {LOG_FILE}: {e}"))?;
+    let content = content
+        .lines()
+        .map(|line| crypto::decrypt_line(line, LOG_FILE))
+        .collect::<Result<Vec<_>, _>>()?
+        .join("\n");
+    parse(&content).map_err(|e| format!("Failed to parse {LOG_FILE}: {e}"))
+}
+
+fn parse(content: &str) -> Result<Vec<Event>, String> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| serde_json::from_str(line).map_err(|e| format!("line {}: {e}", i + 1)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Priority, Status};
+    use crate::testing;
+
+    fn at() -> NaiveDateTime {
+        testing::at(1, 9, 30)
+    }
+
+    fn task(id: u32, description: &str) -> Task {
+        Task::new(id, description.to_string(), Priority::Medium)
+    }
+
+    fn kinds(events: &[Event]) -> Vec<(u32, Change)> {
+        events.iter().map(|e| (e.task, e.change.clone())).collect()
+    }
+
+    #[test]
+    fn test_changes() {
+        let mut done = task(2, "Finish");
+        let mut reopened = task(3, "Again");
+        reopened.status = Status::Done;
+        let previous = vec![
+            task(1, "Old"),
+            done.clone(),
+            reopened.clone(),
+            task(4, "Same"),
+        ];
+
+        done.status = Status::Done;
+        done.priority = Priority::High;
+        reopened.status = Status::Backlog;
+        let tasks = vec![done, reopened, task(4, "Same"), task(5, "New")];
+
+        assert_eq!(
+            kinds(&changes(&previous, &tasks, at())),
+            vec![
+                (
+                    2,
+                    Change::Edited {
+                        fields: vec!["priority".to_string()]
+                    }
+                ),
+                (2, Change::Completed),
+                (3, Change::Reopened),
+                (5, Change::Created),
+                (1, Change::Removed),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_events_round_trip_as_json_lines() {
+        let events = changes(&[task(1, "Gone")], &[task(2, "Here")], at());
+        let content: String = events
+            .iter()
+            .map(|e| serde_json::to_string(e).unwrap() + "\n")
+            .collect();
+
+        assert!(content.contains(r#""event":"created""#));
+        assert_eq!(parse(&content).unwrap(), events);
+        assert!(parse("{oops\n").unwrap_err().starts_with("line 1"));
+    }
+
+    #[test]
+    fn test_display() {
+        let event = Event {
+            at: at(),
+            task: 7,
+            description: "Write report".to_string(),
+            change: Change::Edited {
+                fields: vec!["due".to_string(), "tags".to_string()],
+            },
+        };
+        assert_eq!(
+            event.to_string(),
+            "  2024-05-01 09:30  7    edited     Write report (due, tags)"
+        );
+    }
+}
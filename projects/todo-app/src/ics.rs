@@ -0,0 +1,183 @@
+//! iCalendar (RFC 5545) export, so tasks with due dates show up in
+//! calendar apps.
+
+use crate::dates::Due;
+use crate::task::{Priority, Status, Task};
+use chrono::NaiveDateTime;
+
+/// Content lines longer than this many bytes must be folded.
+const MAX_LINE: usize = 75;
+
+/// Escape text for a property value: backslashes, `;` and `,` get a
+/// backslash, and newlines become `\n`.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Split a content line into 75-byte pieces, each continuation starting
+/// with a space, without cutting a character in half.
+pub fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the next line
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+/// `DUE:...` or `DTSTART:...`: a whole-day due date is a DATE value, a due
+/// time is a floating local time.
+fn date_property(name: &str, due: &Due) -> String {
+    match due {
+        Due::Date(d) => format!("{name};VALUE=DATE:{}", d.format("%Y%m%d")),
+        Due::DateTime(dt) => format!("{name}:{}", dt.format("%Y%m%dT%H%M%S")),
+    }
+}
+
+/// iCalendar priorities run from 1 (highest) to 9 (lowest).
+fn priority(priority: &Priority) -> u8 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+/// A VTODO for task apps plus a VEVENT for calendars that ignore to-dos.
+fn components(task: &Task, due: &Due, stamp: &str) -> Vec<String> {
+    let summary = format!("SUMMARY:{}", escape(&task.description));
+    let status = match task.status {
+        Status::Backlog => "STATUS:NEEDS-ACTION",
+        Status::InProgress => "STATUS:IN-PROCESS",
+        Status::Done => "STATUS:COMPLETED",
+    };
+
+    let mut lines = vec![
+        "BEGIN:VTODO".to_string(),
+        format!("UID:task-{}@todo-app", task.id),
+        format!("DTSTAMP:{stamp}"),
+        summary.clone(),
+        date_property("DUE", due),
+        format!("PRIORITY:{}", priority(&task.priority)),
+        status.to_string(),
+    ];
+    if !task.tags.is_empty() {
+        let tags: Vec<String> = task.tags.iter().map(|t| escape(t)).collect();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    lines.push("END:VTODO".to_string());
+
+    lines.extend([
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:task-{}-due@todo-app", task.id),
+        format!("DTSTAMP:{stamp}"),
+        summary,
+        date_property("DTSTART", due),
+        "TRANSP:TRANSPARENT".to_string(),
+        "END:VEVENT".to_string(),
+    ]);
+    lines
+}
+
+/// A calendar of every task with a due date. `now_utc` stamps each entry.
+pub fn calendar(tasks: &[Task], now_utc: NaiveDateTime) -> String {
+    let stamp = now_utc.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//learn-rust//todo-app//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Todo".to_string(),
+    ];
+    for task in tasks {
+        if let Some(due) = &task.due {
+            lines.extend(components(task, due, &stamp));
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|l| fold(l) + "\r\n").collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{at, may};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain text"), "plain text");
+        assert_eq!(escape("a, b; c"), "a\\, b\\; c");
+        assert_eq!(escape("C:\\path"), "C:\\\\path");
+        assert_eq!(escape("two\r\nlines"), "two\\nlines");
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("short"), "short");
+
+        let long = "x".repeat(160);
+        let folded = fold(&long);
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|l| l.len() <= MAX_LINE));
+        assert!(lines[1].starts_with(' '));
+        assert_eq!(folded.replace("\r\n ", ""), long);
+
+        // Never split inside a multi-byte character
+        let accents = "é".repeat(80);
+        for line in fold(&accents).split("\r\n") {
+            assert!(line.len() <= MAX_LINE);
+        }
+    }
+
+    #[test]
+    fn test_calendar() {
+        let mut dated = Task::new(1, "Pay rent, on time".to_string(), Priority::High);
+        dated.due = Some(Due::Date(may(3)));
+        dated.tags = vec!["home".to_string(), "money".to_string()];
+
+        let mut timed = Task::new(2, "Call the dentist".to_string(), Priority::Low);
+        timed.due = Some(Due::DateTime(at(2, 17, 0)));
+        timed.status = Status::Done;
+
+        let undated = Task::new(3, "Someday".to_string(), Priority::Medium);
+
+        let ics = calendar(&[dated, timed, undated], at(1, 8, 30));
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("DTSTAMP:20240501T083000Z\r\n"));
+
+        assert!(ics.contains("UID:task-1@todo-app\r\n"));
+        assert!(ics.contains("SUMMARY:Pay rent\\, on time\r\n"));
+        assert!(ics.contains("DUE;VALUE=DATE:20240503\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20240503\r\n"));
+        assert!(ics.contains("PRIORITY:1\r\n"));
+        assert!(ics.contains("CATEGORIES:home,money\r\n"));
+
+        assert!(ics.contains("DUE:20240502T170000\r\n"));
+        assert!(ics.contains("STATUS:COMPLETED\r\n"));
+
+        assert!(!ics.contains("Someday"));
+        assert_eq!(ics.matches("BEGIN:VTODO").count(), 2);
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::dates;
+use crate::task::{Priority, Status, Task};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::io::Read;
+
+/// Which CSV header holds each task field. Defaults to headers named after
+/// the fields; `--map description=Title,priority=Pri` overrides them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMap {
+    pub description: String,
+    pub priority: String,
+    pub due: String,
+    pub tags: String,
+    pub completed: String,
+}
+
+impl Default for ColumnMap {
+    fn default() -> Self {
+        ColumnMap {
+            description: "description".to_string(),
+            priority: "priority".to_string(),
+            due: "due".to_string(),
+            tags: "tags".to_string(),
+            completed: "completed".to_string(),
+        }
+    }
+}
+
+impl ColumnMap {
+    /// Apply `field=Header` pairs separated by commas.
+    pub fn apply(&mut self, spec: &str) -> Result<(), String> {
+        for pair in spec.split(',').filter(|p| !p.trim().is_empty()) {
+            let (field, header) = pair
+                .split_once('=')
+                .ok_or(format!("Expected field=Header, got '{pair}'"))?;
+            let header = header.trim().to_string();
+            match field.trim() {
+                "description" => self.description = header,
+                "priority" => self.priority = header,
+                "due" => self.due = header,
+                "tags" => self.tags = header,
+                "completed" => self.completed = header,
+                other => {
+                    return Err(format!(
+                        "Unknown field '{other}'. Use description, priority, due, tags, completed"
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A row that couldn't be imported, by its line in the file.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+/// Read tasks from CSV with a header row, numbering them from `first_id`.
+/// Bad rows are collected as errors rather than stopping the import; only
+/// an unreadable file or a missing description column fails outright.
+pub fn import_csv(
+    reader: impl Read,
+    map: &ColumnMap,
+    first_id: u32,
+    now: NaiveDateTime,
+) -> Result<(Vec<Task>, Vec<RowError>), String> {
+    let mut csv = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers = csv
+        .headers()
+        .map_err(|e| format!("Failed to read CSV header: {e}"))?
+        .clone();
+
+    let find = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+    };
+    let description = find(&map.description)
+        .ok_or(format!("No '{}' column in the CSV header", map.description))?;
+    let priority = find(&map.priority);
+    let due = find(&map.due);
+    let tags = find(&map.tags);
+    let completed = find(&map.completed);
+
+    let mut tasks = Vec::new();
+    let mut errors = Vec::new();
+
+    for record in csv.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                errors.push(RowError {
+                    line,
+                    message: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |p| p.line());
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|i| record.get(i))
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+
+        let id = first_id + tasks.len() as u32;
+        match row_to_task(
+            id,
+            cell(Some(description)),
+            cell(priority),
+            cell(due),
+            cell(tags),
+            cell(completed),
+            now,
+        ) {
+            Ok(task) => tasks.push(task),
+            Err(message) => errors.push(RowError { line, message }),
+        }
+    }
+
+    Ok((tasks, errors))
+}
+
+fn row_to_task(
+    id: u32,
+    description: Option<&str>,
+    priority: Option<&str>,
+    due: Option<&str>,
+    tags: Option<&str>,
+    completed: Option<&str>,
+    now: NaiveDateTime,
+) -> Result<Task, String> {
+    let description = description.ok_or("Missing description")?;
+    let priority = priority.map_or(Ok(Priority::Medium), parse_priority)?;
+
+    let mut task = Task::new(id, description.to_string(), priority);
+    task.due = due.map(|d| dates::parse_due(d, now)).transpose()?;
+    task.tags = tags
+        .unwrap_or_default()
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(|t| t.trim_start_matches(['#', '@']).to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    let done = completed.is_some_and(|c| {
+        matches!(
+            c.to_lowercase().as_str(),
+            "1" | "x" | "y" | "yes" | "true" | "done" | "completed"
+        )
+    });
+    if done {
+        task.status = Status::Done;
+    }
+    Ok(task)
+}
+
+/// Our own names, plus Todoist's p1 (highest) to p4.
+fn parse_priority(s: &str) -> Result<Priority, String> {
+    match s.to_lowercase().as_str() {
+        "p1" => Ok(Priority::High),
+        "p2" => Ok(Priority::Medium),
+        "p3" | "p4" => Ok(Priority::Low),
+        _ => Priority::from_str(s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::now;
+
+    #[test]
+    fn test_import_with_default_headers() {
+        let csv = "Description,Priority,Due,Tags,Completed\n\
+                   Write report,high,2024-05-03,\"work, writing\",\n\
+                   Buy milk,,,,yes\n";
+        let (tasks, errors) = import_csv(csv.as_bytes(), &ColumnMap::default(), 7, now()).unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].id, 7);
+        assert_eq!(tasks[0].priority, Priority::High);
+        assert_eq!(tasks[0].tags, vec!["work", "writing"]);
+        assert_eq!(tasks[0].due.unwrap().date().to_string(), "2024-05-03");
+        assert_eq!(tasks[1].id, 8);
+        assert_eq!(tasks[1].priority, Priority::Medium);
+        assert!(tasks[1].is_done());
+    }
+
+    #[test]
+    fn test_column_mapping() {
+        let mut map = ColumnMap::default();
+        map.apply("description=Title,priority=Pri").unwrap();
+        let csv = "Pri,Title\np1,Ship it\n";
+        let (tasks, _) = import_csv(csv.as_bytes(), &map, 1, now()).unwrap();
+        assert_eq!(tasks[0].description, "Ship it");
+        assert_eq!(tasks[0].priority, Priority::High);
+
+        assert!(map.apply("owner=Who").is_err());
+        assert!(map.apply("description").is_err());
+    }
+
+    #[test]
+    fn test_bad_rows_are_reported_not_fatal() {
+        let csv = "description,priority,due\n\
+                   Good one,low,\n\
+                   ,low,\n\
+                   Bad priority,urgent,\n\
+                   Bad date,,someday\n\
+                   Also good,,tomorrow\n";
+        let (tasks, errors) = import_csv(csv.as_bytes(), &ColumnMap::default(), 1, now()).unwrap();
+
+        let ids: Vec<(u32, &str)> = tasks
+            .iter()
+            .map(|t| (t.id, t.description.as_str()))
+            .collect();
+        assert_eq!(ids, vec![(1, "Good one"), (2, "Also good")]);
+
+        let lines: Vec<u64> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![3, 4, 5]);
+        assert_eq!(errors[0].message, "Missing description");
+    }
+
+    #[test]
+    fn test_missing_description_column_fails() {
+        let csv = "Title\nSomething\n";
+        assert!(import_csv(csv.as_bytes(), &ColumnMap::default(), 1, now()).is_err());
+    }
+}
@@ -1,10 +1,69 @@
+mod agenda;
+mod assignee;
+mod attachment;
+mod board;
+mod context;
+mod crypto;
+mod dates;
+mod github;
+mod history;
+mod ics;
+mod import;
+mod next;
+mod output;
+mod plan;
+mod quickadd;
+mod remote;
+mod review;
+mod rules;
+mod server;
 mod storage;
+mod sync;
 mod task;
+mod template;
+#[cfg(test)]
+mod testing;
+mod today;
+mod tracking;
 
-use task::{Priority, Task};
+use chrono::{Local, NaiveDateTime};
+use output::{Output, OutputFormat};
+use serde::Serialize;
+use std::collections::HashMap;
+use task::{Priority, Status, Task};
 
 fn run() -> Result<(), String> {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // --json is global, so accept it anywhere on the command line
+    let format = if args.iter().any(|a| a == "--json") {
+        args.retain(|a| a != "--json");
+        OutputFormat::Json
+    } else {
+        OutputFormat::Text
+    };
+    let out = Output::new(format);
+
+    // Likewise --no-rules, which shows tasks exactly as stored
+    let use_rules = !args.iter().any(|a| a == "--no-rules");
+    args.retain(|a| a != "--no-rules");
+
+    if let Some(i) = args.iter().position(|a| a == "--key-file") {
+        let path = args
+            .get(i + 1)
+            .ok_or("Usage: todo --key-file <path> <command>")?;
+        crypto::set_key_file(path.into());
+        args.drain(i..i + 2);
+    }
+
+    // --encrypt converts plain storage once; after that it stays encrypted
+    if args.iter().any(|a| a == "--encrypt") {
+        args.retain(|a| a != "--encrypt");
+        if args.get(1).is_some_and(|a| a == "decrypt") {
+            return Err("--encrypt and todo decrypt can't be combined".to_string());
+        }
+        cmd_encrypt(args.len() < 2, &out)?;
+    }
 
     if args.len() < 2 {
         print_usage();
@@ -12,10 +71,35 @@ fn run() -> Result<(), String> {
     }
 
     match args[1].as_str() {
-        "add" => cmd_add(&args[2..])?,
-        "list" | "ls" => cmd_list()?,
-        "done" => cmd_done(&args[2..])?,
-        "remove" | "rm" => cmd_remove(&args[2..])?,
+        "add" => cmd_add(&args[2..], &out)?,
+        "edit" => cmd_edit(&args[2..], &out)?,
+        "list" | "ls" => cmd_list(&args[2..], use_rules, &out)?,
+        "agenda" => cmd_agenda(use_rules, &out)?,
+        "today" => cmd_today(&args[2..], &out)?,
+        "board" => cmd_board(use_rules, &out)?,
+        "next" => cmd_next(use_rules, &out)?,
+        "plan" => cmd_plan(&args[2..], use_rules, &out)?,
+        "move" | "mv" => cmd_move(&args[2..], &out)?,
+        "done" => cmd_done(&args[2..], &out)?,
+        "reopen" => cmd_reopen(&args[2..], &out)?,
+        "snooze" => cmd_snooze(&args[2..], &out)?,
+        "remove" | "rm" => cmd_remove(&args[2..], &out)?,
+        "show" => cmd_show(&args[2..], use_rules, &out)?,
+        "attach" => cmd_attach(&args[2..], &out)?,
+        "start" => cmd_start(&args[2..], &out)?,
+        "stop" => cmd_stop(&out)?,
+        "report" => cmd_report(&args[2..], &out)?,
+        "review" => cmd_review(&args[2..], &out)?,
+        "stats" => cmd_stats(&out)?,
+        "contexts" => cmd_contexts(&out)?,
+        "sync" => cmd_sync(&out)?,
+        "import" => cmd_import(&args[2..], &out)?,
+        "export" => cmd_export(&args[2..], &out)?,
+        "log" => cmd_log(&args[2..], &out)?,
+        "rules" => cmd_rules(use_rules, &out)?,
+        "template" => cmd_template(&args[2..], &out)?,
+        "decrypt" => cmd_decrypt(&out)?,
+        "serve" => cmd_serve(&args[2..])?,
         "help" | "--help" | "-h" => print_usage(),
         _ => {
             eprintln!("Unknown command: {}", args[1]);
@@ -30,106 +114,741 @@ fn print_usage() {
     println!("TODO App — A simple task manager");
     println!();
     println!("Usage:");
-    println!("  todo add <description> [--priority low|medium|high]");
-    println!("  todo list");
+    println!(
+        "  todo add <description> [--priority low|medium|high] [--due <when>] [--tag <tag>]..."
+    );
+    println!("           [--context <where>] [--recur daily|weekly|monthly|yearly]");
+    println!("           [--assign <name>] [--estimate <30m|2h|1d>] [--after <id>]...");
+    println!("  todo edit <id> [--description <text>] [--priority ...] [--due ...] [--tag ...]...");
+    println!("           [--context <where>] [--recur ... | --no-recur]");
+    println!("           [--assign <name> | --unassign] [--estimate ...] [--after <id>]...");
+    println!("  todo list [@<where>] [--assignee <name>] [--context <where>] [--snoozed]");
+    println!("  todo agenda");
+    println!("  todo today [--roll | <description> [--priority ...] [--tag ...]...]");
+    println!("  todo board");
+    println!("  todo next");
+    println!("  todo plan --capacity <6h>");
+    println!("  todo move <id> backlog|in-progress|done");
+    println!("  todo show <id>");
+    println!("  todo attach <id> <file|url>");
     println!("  todo done <id>");
+    println!("  todo reopen <id>");
     println!("  todo remove <id>");
+    println!("  todo snooze <id> <30m|4h|3d|2w>");
+    println!("  todo start <id>");
+    println!("  todo stop");
+    println!("  todo report [--week]");
+    println!("  todo review [--week]");
+    println!("  todo stats");
+    println!("  todo contexts");
+    println!("  todo log [<id>]");
+    println!("  todo sync");
+    println!("  todo import [--format csv] <file> [--map field=Header,...]");
+    println!("  todo export [--format ics] [<file>] [--url]");
+    println!("  todo rules");
+    println!("  todo template save <name> (<id>... | --tag <tag>)");
+    println!("  todo template apply <name> [--tag <tag>]... [--set <placeholder>=<value>]...");
+    println!("  todo template list | remove <name>");
+    println!("  todo decrypt");
+    println!("  todo serve [--port <port>]");
+    println!();
+    println!("Options:");
+    println!("  --json      Print machine-readable JSON instead of text");
+    println!("  --no-rules  Ignore the rules in {}", rules::RULES_FILE);
+    println!("  --encrypt   Store todos encrypted with a passphrase from now on");
+    println!("  --key-file <path>  Read the passphrase from a file (or $TODO_KEY_FILE)");
+    println!();
+    println!("Sync uses the git checkout in $TODO_SYNC_DIR (default ~/.todo-sync).");
+    println!("TODO_BACKEND=github keeps tasks as issues labelled todo in $TODO_GITHUB_REPO");
+    println!("(owner/name) instead of todos.json, using the token in $GITHUB_TOKEN.");
+    println!("Import reads description, priority, due, tags, and completed columns;");
+    println!("--map renames them, e.g. --map description=Content,priority=Priority.");
+    println!("Export writes tasks with due dates to todos.ics for calendar apps; --url");
+    println!("prints the calendar instead, e.g. to publish it where a calendar can subscribe.");
+    println!("Quick-add: words in a new task's description set its fields, e.g. !high,");
+    println!("#tag, @context, due:friday (due:friday_5pm for a time) and rec:monthly.");
+    println!("Contexts say where a task can be done (@home, @office, @errands); todo list");
+    println!("@office shows just those, and todo contexts counts what's open in each.");
+    println!("Done on a recurring task adds the next one, due a period after the last.");
+    println!("Edit replaces only the fields given; --tag there replaces all the tags.");
+    println!("todo today lists what's due today; with a description it adds a task due today.");
+    println!(
+        "With --roll it moves unfinished tasks from earlier days on to today, counting each move."
+    );
+    println!("Snoozed tasks stay out of todo list until the snooze runs out.");
+    println!("todo next picks one task to do now from priority, due date, effort (--estimate),");
+    println!(
+        "and what it unblocks, and says why; tasks still waiting on --after ones are skipped."
+    );
+    println!("todo plan fills the time given with the tasks worth the most to todo next that");
+    println!("have an --estimate, and lists the ones that didn't fit or have no estimate.");
+    println!("todo review prints this week's completed, added, overdue and carried-over tasks");
+    println!("as Markdown grouped by tag, ready to paste into a standup doc.");
+    println!("Rules can raise priorities and hide tasks when listing; see todo rules.");
+    println!(
+        "Templates keep a set of tasks in {} to add again with fresh ids;",
+        template::TEMPLATES_FILE
+    );
+    println!("{{placeholders}} in them, like {{version}}, are filled in by --set when applied.");
+    println!("Serve exposes GET/POST /tasks and GET/PATCH/DELETE /tasks/<id> as JSON.");
+    println!("Encrypted todos are decrypted as they're read; todo decrypt undoes --encrypt.");
     println!();
     println!("Examples:");
     println!("  todo add \"Learn Rust ownership\"");
     println!("  todo add \"Build a web server\" --priority high");
+    println!("  todo add \"Call the dentist\" --due \"tomorrow 5pm\"");
+    println!("  todo add \"Review PRs\" --tag work");
+    println!("  todo add \"Deploy\" --after 3 --estimate 30m");
+    println!("  todo add \"pay rent !high #finance @home due:friday rec:monthly\"");
+    println!("  todo today \"Call the bank\"");
+    println!("  todo edit 3 --assign alice");
+    println!("  todo list --assignee alice");
+    println!("  todo move 2 in-progress");
     println!("  todo done 1");
+    println!("  todo snooze 4 3d");
+    println!("  todo attach 1 ./spec.pdf");
+    println!("  todo start 2");
+    println!("  todo plan --capacity 6h");
+    println!("  todo report --week");
+    println!("  todo review --week > standup.md");
+    println!("  todo log 2");
+    println!("  todo template save release-checklist --tag release");
+    println!("  todo template apply release-checklist --tag v2.0 --set version=2.0");
+    println!("  todo import --format csv tasks.csv --map description=Title,priority=Pri");
+    println!("  todo export --url > ~/public_html/todo.ics");
+    println!("  todo list --json");
+    println!("  todo --encrypt --key-file ~/.todo-key list");
+}
+
+/// The options `todo add` and `todo edit` share. Each is None unless given.
+#[derive(Default)]
+struct TaskOptions {
+    priority: Option<Priority>,
+    due: Option<dates::Due>,
+    tags: Option<Vec<String>>,
+    context: Option<String>,
+    recur: Option<dates::Recurrence>,
+    assignee: Option<String>,
+    /// Minutes
+    estimate: Option<u32>,
+    depends_on: Option<Vec<u32>>,
 }
 
-fn cmd_add(args: &[String]) -> Result<(), String> {
-    if args.is_empty() {
-        return Err("Usage: todo add <description> [--priority low|medium|high]".to_string());
+impl TaskOptions {
+    /// Take `flag` and its value if it's one of the shared options.
+    /// Returns false for any other flag.
+    fn parse(&mut self, flag: &str, value: &str) -> Result<bool, String> {
+        match flag {
+            "--priority" => self.priority = Some(Priority::from_str(value)?),
+            "--due" => self.due = Some(dates::parse_due(value, now())?),
+            "--tag" => self
+                .tags
+                .get_or_insert_with(Vec::new)
+                .push(value.trim_start_matches('#').to_lowercase()),
+            "--context" => self.context = Some(context::parse(value)?),
+            "--recur" => self.recur = Some(dates::Recurrence::from_str(value)?),
+            "--assign" => self.assignee = Some(assignee::parse(value)?),
+            "--estimate" => {
+                let minutes = dates::parse_duration(value)?.num_minutes();
+                self.estimate = Some(u32::try_from(minutes).map_err(|_| "Estimate too long")?);
+            }
+            "--after" => self.depends_on.get_or_insert_with(Vec::new).push(
+                value
+                    .parse()
+                    .map_err(|_| format!("Invalid ID: '{value}'"))?,
+            ),
+            _ => return Ok(false),
+        }
+        Ok(true)
     }
 
-    let description = &args[0];
-    let priority = if args.len() > 2 && args[1] == "--priority" {
-        Priority::from_str(&args[2])?
-    } else {
-        Priority::Medium
-    };
+    fn changes_anything(&self) -> bool {
+        self.priority.is_some()
+            || self.due.is_some()
+            || self.tags.is_some()
+            || self.context.is_some()
+            || self.recur.is_some()
+            || self.assignee.is_some()
+            || self.estimate.is_some()
+            || self.depends_on.is_some()
+    }
+}
+
+fn cmd_add(args: &[String], out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo add <description> [--priority low|medium|high] [--due <when>] [--tag <tag>]... [--context <where>] [--recur daily|weekly|monthly|yearly] [--assign <name>] [--estimate <30m|2h|1d>] [--after <id>]...";
+
+    let (description, options) = args.split_first().ok_or(USAGE)?;
+    let mut parsed = TaskOptions::default();
+
+    let mut iter = options.iter();
+    while let Some(flag) = iter.next() {
+        let value = iter.next().ok_or(USAGE)?;
+        if !parsed.parse(flag, value)? {
+            return Err(format!("Unknown option: {flag}\n{USAGE}"));
+        }
+    }
+    add_task(description, parsed, out)
+}
 
-    let mut tasks = storage::load_tasks()?;
+/// Add a task, with anything the description sets by quick-add words
+/// (see `quickadd`) unless a flag sets it too.
+fn add_task(description: &str, parsed: TaskOptions, out: &Output) -> Result<(), String> {
+    let quick = quickadd::parse(description, now())?;
+    let priority = parsed
+        .priority
+        .or(quick.priority)
+        .unwrap_or(Priority::Medium);
+    let mut tags = quick.tags;
+    for tag in parsed.tags.unwrap_or_default() {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let mut tasks = storage::open()?.load()?;
     let id = storage::next_id(&tasks);
-    let task = Task::new(id, description.clone(), priority.clone());
+    let depends_on = parsed.depends_on.unwrap_or_default();
+    next::check_depends_on(&tasks, id, &depends_on)?;
+    let mut task = Task::new(id, quick.description, priority.clone());
+    task.due = parsed.due.or(quick.due);
+    task.tags = tags;
+    task.context = parsed.context.or(quick.context);
+    task.recur = parsed.recur.or(quick.recur);
+    task.assignee = parsed.assignee;
+    task.estimate = parsed.estimate;
+    task.depends_on = depends_on;
 
-    println!("Added: {} (id: {}, priority: {})", task.description, task.id, priority);
+    out.task(
+        &task,
+        &format!(
+            "Added: {} (id: {}, priority: {})",
+            task.description, task.id, priority
+        ),
+    )?;
 
     tasks.push(task);
-    storage::save_tasks(&tasks)?;
+    storage::open()?.save(&tasks)?;
 
     Ok(())
 }
 
-fn cmd_list() -> Result<(), String> {
-    let tasks = storage::load_tasks()?;
+fn cmd_edit(args: &[String], out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo edit <id> [--description <text>] [--priority low|medium|high] [--due <when>] [--tag <tag>]... [--context <where>] [--recur daily|weekly|monthly|yearly | --no-recur] [--assign <name> | --unassign] [--estimate <30m|2h|1d>] [--after <id>]...";
+
+    let id = parse_id(args, USAGE)?;
+    let mut parsed = TaskOptions::default();
+    let mut description = None;
+    let mut unassign = false;
+    let mut no_recur = false;
 
-    if tasks.is_empty() {
-        println!("No tasks yet. Add one with: todo add \"your task\"");
+    let mut iter = args[1..].iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--unassign" {
+            unassign = true;
+            continue;
+        }
+        if flag == "--no-recur" {
+            no_recur = true;
+            continue;
+        }
+        let value = iter.next().ok_or(USAGE)?;
+        if flag == "--description" {
+            description = Some(value.clone());
+        } else if !parsed.parse(flag, value)? {
+            return Err(format!("Unknown option: {flag}\n{USAGE}"));
+        }
+    }
+    if unassign && parsed.assignee.is_some() {
+        return Err(format!(
+            "--assign and --unassign can't be combined\n{USAGE}"
+        ));
+    }
+    if no_recur && parsed.recur.is_some() {
+        return Err(format!("--recur and --no-recur can't be combined\n{USAGE}"));
+    }
+    if description.is_none() && !unassign && !no_recur && !parsed.changes_anything() {
+        return Err(format!("Nothing to change\n{USAGE}"));
+    }
+
+    let mut tasks = storage::open()?.load()?;
+    if let Some(depends_on) = &parsed.depends_on {
+        next::check_depends_on(&tasks, id, depends_on)?;
+    }
+    let task = tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or(format!("Task {id} not found"))?;
+
+    if let Some(description) = description {
+        task.description = description;
+    }
+    if let Some(priority) = parsed.priority {
+        task.priority = priority;
+    }
+    if parsed.due.is_some() {
+        task.due = parsed.due;
+    }
+    if let Some(tags) = parsed.tags {
+        task.tags = tags;
+    }
+    if parsed.context.is_some() {
+        task.context = parsed.context;
+    }
+    if parsed.recur.is_some() || no_recur {
+        task.recur = parsed.recur;
+    }
+    if parsed.assignee.is_some() || unassign {
+        task.assignee = parsed.assignee;
+    }
+    if parsed.estimate.is_some() {
+        task.estimate = parsed.estimate;
+    }
+    if let Some(depends_on) = parsed.depends_on {
+        task.depends_on = depends_on;
+    }
+
+    out.task(task, &format!("Updated task {id}: {}", task.description))?;
+    storage::open()?.save(&tasks)
+}
+
+/// The saved tasks as the rules present them, and how many the rules hid.
+fn load_visible_tasks(use_rules: bool) -> Result<(Vec<Task>, usize), String> {
+    let mut tasks = storage::open()?.load()?;
+    if !use_rules {
+        return Ok((tasks, 0));
+    }
+    let hidden = rules::apply(&rules::load()?, &mut tasks, now());
+    Ok((tasks, hidden))
+}
+
+fn cmd_list(args: &[String], use_rules: bool, out: &Output) -> Result<(), String> {
+    const USAGE: &str =
+        "Usage: todo list [@<where>] [--assignee <name>] [--context <where>] [--snoozed]";
+
+    let mut assignee = None;
+    let mut context = None;
+    let mut snoozed = false;
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        match flag.as_str() {
+            "--assignee" => assignee = Some(assignee::parse(iter.next().ok_or(USAGE)?)?),
+            "--context" => context = Some(context::parse(iter.next().ok_or(USAGE)?)?),
+            "--snoozed" => snoozed = true,
+            _ if flag.starts_with('@') => context = Some(context::parse(flag)?),
+            _ => return Err(format!("Unknown option: {flag}\n{USAGE}")),
+        }
+    }
+
+    let (mut tasks, hidden) = load_visible_tasks(use_rules)?;
+    let now = now();
+    let snoozed_count = tasks.iter().filter(|t| t.is_snoozed(now)).count();
+    tasks.retain(|t| t.is_snoozed(now) == snoozed);
+    if snoozed && tasks.is_empty() && !out.is_json() {
+        println!("No snoozed tasks");
         return Ok(());
     }
+    if let Some(assignee) = &assignee {
+        tasks.retain(|t| t.assignee.as_ref() == Some(assignee));
+        if tasks.is_empty() && !out.is_json() {
+            println!("No tasks assigned to {assignee}");
+            return Ok(());
+        }
+    }
+    if let Some(context) = &context {
+        tasks.retain(|t| t.context.as_ref() == Some(context));
+        if tasks.is_empty() && !out.is_json() {
+            println!("No tasks at {context}");
+            return Ok(());
+        }
+    }
+    out.task_list(&tasks)?;
+
+    if hidden > 0 && !out.is_json() {
+        println!("  {hidden} hidden by rules (todo list --no-rules shows them)");
+    }
+    if !snoozed && snoozed_count > 0 && !out.is_json() {
+        println!("  {snoozed_count} snoozed (todo list --snoozed shows them)");
+    }
+    Ok(())
+}
 
-    let pending = tasks.iter().filter(|t| !t.completed).count();
-    let completed = tasks.iter().filter(|t| t.completed).count();
+#[derive(Serialize)]
+struct AgendaSection<'a> {
+    section: agenda::Section,
+    tasks: Vec<&'a Task>,
+}
 
-    println!(
-        "  {:<4} {:<8} {:<9} {}",
-        "ID", "Status", "Priority", "Description"
-    );
-    println!("  {}", "-".repeat(50));
+fn cmd_agenda(use_rules: bool, out: &Output) -> Result<(), String> {
+    let (tasks, _) = load_visible_tasks(use_rules)?;
+    let now = now();
+    let groups = agenda::agenda(&tasks, now);
 
-    for task in &tasks {
-        println!("{task}");
+    let sections: Vec<AgendaSection> = groups
+        .iter()
+        .map(|(section, tasks)| AgendaSection {
+            section: *section,
+            tasks: tasks.clone(),
+        })
+        .collect();
+
+    out.data(&sections, || {
+        if groups.is_empty() {
+            println!("Nothing pending. Add a task with: todo add \"your task\"");
+            return;
+        }
+
+        for (i, (section, tasks)) in groups.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            println!("{section}");
+            for task in tasks {
+                let tags: String = task.tags.iter().map(|t| format!(" #{t}")).collect();
+                let when = task
+                    .due
+                    .map(|due| format!(" — {}", due.relative(now)))
+                    .unwrap_or_default();
+                println!("  {:<4} {}{tags}{when}", task.id, task.description);
+            }
+        }
+    })
+}
+
+fn cmd_today(args: &[String], out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo today [--roll | <description> [--priority low|medium|high] [--tag <tag>]... [--context <where>] [--recur daily|weekly|monthly|yearly] [--assign <name>] [--estimate <30m|2h|1d>] [--after <id>]...]";
+
+    let today = now().date();
+    let roll = args.first().is_some_and(|a| a == "--roll");
+    if roll && args.len() > 1 {
+        return Err(USAGE.to_string());
     }
 
-    println!();
-    println!("  {pending} pending, {completed} completed");
+    if let Some((description, options)) = args.split_first().filter(|_| !roll) {
+        let mut parsed = TaskOptions::default();
+        let mut iter = options.iter();
+        while let Some(flag) = iter.next() {
+            let value = iter.next().ok_or(USAGE)?;
+            if flag == "--due" {
+                return Err(format!(
+                    "todo today adds tasks due today; use todo add --due for other days\n{USAGE}"
+                ));
+            }
+            if !parsed.parse(flag, value)? {
+                return Err(format!("Unknown option: {flag}\n{USAGE}"));
+            }
+        }
+        parsed.due = Some(dates::Due::Date(today));
+        return add_task(description, parsed, out);
+    }
+
+    let mut tasks = storage::open()?.load()?;
+    // Only on request: moving a task's due date ends it being overdue
+    let rolled = if roll {
+        today::roll_over(&mut tasks, today)
+    } else {
+        0
+    };
+    if rolled > 0 {
+        storage::open()?.save(&tasks)?;
+    }
+    let due = today::due_today(&tasks, today);
+
+    if out.is_json() {
+        return out.task_list(&due);
+    }
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    if rolled > 0 {
+        println!(
+            "Moved {rolled} unfinished task{} from earlier days to today\n",
+            plural(rolled)
+        );
+    }
+    let overdue = today::overdue(&tasks, today);
+    if overdue > 0 {
+        println!(
+            "{overdue} unfinished task{} from earlier days; todo today --roll moves {} here\n",
+            plural(overdue),
+            if overdue == 1 { "it" } else { "them" }
+        );
+    }
+    if due.is_empty() {
+        println!("Nothing due today. Capture something with: todo today \"call the bank\"");
+        return Ok(());
+    }
+    out.task_list(&due)?;
 
+    let carried: Vec<String> = due
+        .iter()
+        .filter(|t| t.rollovers > 0 && !t.is_done())
+        .map(|t| format!("{} ({}x)", t.id, t.rollovers))
+        .collect();
+    if !carried.is_empty() {
+        println!("  Rolled over: {}", carried.join(", "));
+    }
     Ok(())
 }
 
-fn cmd_done(args: &[String]) -> Result<(), String> {
-    if args.is_empty() {
-        return Err("Usage: todo done <id>".to_string());
+fn cmd_board(use_rules: bool, out: &Output) -> Result<(), String> {
+    let (mut tasks, _) = load_visible_tasks(use_rules)?;
+    let now = now();
+    tasks.retain(|t| !t.is_snoozed(now));
+    let columns = board::columns(&tasks);
+
+    out.data(&columns, || {
+        if tasks.is_empty() {
+            println!("No tasks yet. Add one with: todo add \"your task\"");
+            return;
+        }
+        for line in board::render(&columns) {
+            println!("{line}");
+        }
+    })
+}
+
+fn cmd_next(use_rules: bool, out: &Output) -> Result<(), String> {
+    let (tasks, _) = load_visible_tasks(use_rules)?;
+    let now = now();
+    let ranked = next::ranked(&tasks, now);
+    let best = ranked.first();
+
+    out.data(&best, || {
+        let Some(best) = best else {
+            let waiting = tasks
+                .iter()
+                .filter(|t| !t.is_done() && !next::waiting_on(t, &tasks).is_empty())
+                .count();
+            if waiting > 0 {
+                println!("Nothing can start yet: every open task is waiting on another");
+            } else {
+                println!("Nothing to do. Add a task with: todo add \"your task\"");
+            }
+            return;
+        };
+
+        println!("Next: {} {}", best.task.id, best.task.description);
+        if best.reasons.is_empty() {
+            println!("  No task stands out; this one comes first by due date and ID");
+        }
+        for reason in &best.reasons {
+            println!("  {:+4}  {}", reason.points, reason.text);
+        }
+        let then: Vec<String> = ranked[1..]
+            .iter()
+            .take(2)
+            .map(|p| format!("{} {}", p.task.id, p.task.description))
+            .collect();
+        if !then.is_empty() {
+            println!("\nThen: {}", then.join(", "));
+        }
+    })
+}
+
+fn cmd_plan(args: &[String], use_rules: bool, out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo plan --capacity <6h>";
+
+    let capacity = match args {
+        [flag, value] if flag == "--capacity" => dates::parse_duration(value)?.num_minutes(),
+        _ => return Err(USAGE.to_string()),
+    };
+    let capacity = u32::try_from(capacity)
+        .ok()
+        .filter(|&c| c <= plan::MAX_CAPACITY)
+        .ok_or("Capacity too long; todo plan plans at most 31 days at a time")?;
+    let (tasks, _) = load_visible_tasks(use_rules)?;
+    let now = now();
+    let plan = plan::plan(&tasks, capacity, now);
+
+    let minutes = |m: u32| tracking::format_duration(chrono::Duration::minutes(m as i64));
+    let line = |task: &Task| {
+        let due = task
+            .due
+            .map(|d| format!("  ({})", d.relative(now)))
+            .unwrap_or_default();
+        format!(
+            "  {:>3}  {:>7}  {}{due}",
+            task.id,
+            task.estimate.map(minutes).unwrap_or_default(),
+            task.description
+        )
+    };
+
+    out.data(&plan, || {
+        println!(
+            "Plan for {}: {} planned, {} free",
+            minutes(plan.capacity),
+            minutes(plan.planned_minutes),
+            minutes(plan.capacity - plan.planned_minutes)
+        );
+        if plan.planned.is_empty() {
+            println!("  Nothing fits; try a larger --capacity");
+        }
+        for task in &plan.planned {
+            println!("{}", line(task));
+        }
+        if !plan.left_out.is_empty() {
+            println!("\nDidn't fit:");
+            for task in &plan.left_out {
+                println!("{}", line(task));
+            }
+        }
+        if !plan.unestimated.is_empty() {
+            println!("\nNo estimate (set one with todo edit <id> --estimate 1h):");
+            for task in &plan.unestimated {
+                println!("{}", line(task));
+            }
+        }
+    })
+}
+
+fn cmd_move(args: &[String], out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo move <id> backlog|in-progress|done";
+
+    let id = parse_id(args, USAGE)?;
+    let [_, status] = args else {
+        return Err(USAGE.to_string());
+    };
+    let status = Status::from_str(status)?;
+
+    let mut tasks = storage::open()?.load()?;
+    let task = tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or(format!("Task {id} not found"))?;
+
+    if task.status == status {
+        return out.task(
+            task,
+            &format!("Task {id} is already {status}: {}", task.description),
+        );
+    }
+
+    let index = tasks.iter().position(|t| t.id == id).expect("found above");
+    let added = if status == Status::Done {
+        task::complete(&mut tasks, index, now())
+    } else {
+        tasks[index].status = status;
+        None
+    };
+    let task = &tasks[index];
+    out.task(task, &format!("Moved to {status}: {}", task.description))?;
+    if let Some(added) = added {
+        print_repeat(&tasks[added], out);
+    }
+    storage::open()?.save(&tasks)
+}
+
+/// Say which task completing a recurring one added.
+fn print_repeat(next: &Task, out: &Output) {
+    if !out.is_json() {
+        let due = next.due.map(|d| format!(", due {d}")).unwrap_or_default();
+        println!("Repeats: added task {}{due}", next.id);
     }
+}
+
+fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+/// Parse the task ID that is the first argument of most commands.
+fn parse_id(args: &[String], usage: &str) -> Result<u32, String> {
+    let arg = args.first().ok_or(usage)?;
+    arg.parse().map_err(|_| format!("Invalid ID: '{arg}'"))
+}
 
-    let id: u32 = args[0]
-        .parse()
-        .map_err(|_| format!("Invalid ID: '{}'", args[0]))?;
+fn cmd_done(args: &[String], out: &Output) -> Result<(), String> {
+    let id = parse_id(args, "Usage: todo done <id>")?;
 
-    let mut tasks = storage::load_tasks()?;
+    let mut tasks = storage::open()?.load()?;
 
     let task = tasks
         .iter_mut()
         .find(|t| t.id == id)
         .ok_or(format!("Task {id} not found"))?;
 
-    if task.completed {
-        println!("Task {} is already completed: {}", task.id, task.description);
-        return Ok(());
+    if task.is_done() {
+        return out.task(
+            task,
+            &format!(
+                "Task {} is already completed: {}",
+                task.id, task.description
+            ),
+        );
     }
 
-    task.completed = true;
-    println!("Completed: {}", task.description);
-    storage::save_tasks(&tasks)?;
+    let index = tasks.iter().position(|t| t.id == id).expect("found above");
+    let added = task::complete(&mut tasks, index, now());
+    let task = &tasks[index];
+    out.task(task, &format!("Completed: {}", task.description))?;
+    if let Some(added) = added {
+        print_repeat(&tasks[added], out);
+    }
+    storage::open()?.save(&tasks)?;
 
     Ok(())
 }
 
-fn cmd_remove(args: &[String]) -> Result<(), String> {
-    if args.is_empty() {
-        return Err("Usage: todo remove <id>".to_string());
+/// Undo `todo done`: back to the backlog, with `completed_at` cleared. The
+/// history log records it as reopened.
+fn cmd_reopen(args: &[String], out: &Output) -> Result<(), String> {
+    let id = parse_id(args, "Usage: todo reopen <id>")?;
+
+    let mut tasks = storage::open()?.load()?;
+    let task = tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or(format!("Task {id} not found"))?;
+
+    if !task.is_done() {
+        return out.task(
+            task,
+            &format!("Task {id} isn't completed: {}", task.description),
+        );
     }
 
-    let id: u32 = args[0]
-        .parse()
-        .map_err(|_| format!("Invalid ID: '{}'", args[0]))?;
+    task.reopen();
+    out.task(task, &format!("Reopened: {}", task.description))?;
+    storage::open()?.save(&tasks)
+}
+
+fn cmd_snooze(args: &[String], out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo snooze <id> <30m|4h|3d|2w>";
 
-    let mut tasks = storage::load_tasks()?;
+    let id = parse_id(args, USAGE)?;
+    let [_, duration] = args else {
+        return Err(USAGE.to_string());
+    };
+    let until = now()
+        .checked_add_signed(dates::parse_duration(duration)?)
+        .ok_or(format!("Can't snooze for '{duration}'; that's too long"))?;
+
+    let mut tasks = storage::open()?.load()?;
+    let task = tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or(format!("Task {id} not found"))?;
+    if task.is_done() {
+        return Err(format!(
+            "Task {id} is already completed: {}",
+            task.description
+        ));
+    }
+
+    task.snoozed_until = Some(until);
+    out.task(
+        task,
+        &format!(
+            "Snoozed until {}: {}",
+            until.format("%a %Y-%m-%d %H:%M"),
+            task.description
+        ),
+    )?;
+    storage::open()?.save(&tasks)
+}
+
+fn cmd_remove(args: &[String], out: &Output) -> Result<(), String> {
+    let id = parse_id(args, "Usage: todo remove <id>")?;
+
+    let mut tasks = storage::open()?.load()?;
 
     let pos = tasks
         .iter()
@@ -137,12 +856,600 @@ fn cmd_remove(args: &[String]) -> Result<(), String> {
         .ok_or(format!("Task {id} not found"))?;
 
     let removed = tasks.remove(pos);
-    println!("Removed: {}", removed.description);
-    storage::save_tasks(&tasks)?;
+    out.task(&removed, &format!("Removed: {}", removed.description))?;
+    storage::open()?.save(&tasks)?;
 
     Ok(())
 }
 
+fn cmd_show(args: &[String], use_rules: bool, out: &Output) -> Result<(), String> {
+    let id = parse_id(args, "Usage: todo show <id>")?;
+    let mut tasks = storage::open()?.load()?;
+
+    let task = tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or(format!("Task {id} not found"))?;
+    // Hiding only applies to listings; asking for a task by ID shows it
+    if use_rules {
+        rules::escalate(&rules::load()?, task, now());
+    }
+    let task = &*task;
+
+    out.data(task, || {
+        let spent = task.time_spent(NaiveDateTime::MIN, now());
+
+        println!("Task {}: {}", task.id, task.description);
+        println!("  Status:   {}", task.status);
+        println!("  Priority: {}", task.priority);
+        if let Some(due) = &task.due {
+            println!("  Due:      {due}");
+        }
+        if !task.tags.is_empty() {
+            println!("  Tags:     {}", task.tags.join(", "));
+        }
+        if let Some(assignee) = &task.assignee {
+            println!("  Assignee: {assignee}");
+        }
+        if let Some(until) = &task.snoozed_until {
+            println!("  Snoozed:  until {}", until.format("%a %Y-%m-%d %H:%M"));
+        }
+        if let Some(minutes) = task.estimate {
+            let estimate = tracking::format_duration(chrono::Duration::minutes(minutes as i64));
+            println!("  Estimate: {estimate}");
+        }
+        if !task.depends_on.is_empty() {
+            let ids: Vec<String> = task.depends_on.iter().map(|id| id.to_string()).collect();
+            println!("  After:    {}", ids.join(", "));
+        }
+        if task.rollovers > 0 {
+            let plural = if task.rollovers == 1 { "" } else { "s" };
+            println!(
+                "  Rolled:   over to a new day {} time{plural}",
+                task.rollovers
+            );
+        }
+        if !task.intervals.is_empty() {
+            let running = if task.is_running() { " (running)" } else { "" };
+            println!("  Tracked:  {}{running}", tracking::format_duration(spent));
+        }
+        for (i, attachment) in task.attachments.iter().enumerate() {
+            let label = if i == 0 { "Attached:" } else { "" };
+            println!(
+                "  {label:<9} {} (added {})",
+                attachment.target,
+                attachment.added.format("%Y-%m-%d %H:%M")
+            );
+        }
+    })
+}
+
+fn cmd_attach(args: &[String], out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo attach <id> <file|url>";
+
+    let id = parse_id(args, USAGE)?;
+    let target = match &args[1..] {
+        [target] => attachment::resolve(target)?,
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let mut tasks = storage::open()?.load()?;
+    let task = tasks
+        .iter_mut()
+        .find(|t| t.id == id)
+        .ok_or(format!("Task {id} not found"))?;
+
+    if !task.attach(target.clone(), now()) {
+        return out.task(task, &format!("Already attached to task {id}: {target}"));
+    }
+    out.task(task, &format!("Attached to task {id}: {target}"))?;
+    storage::open()?.save(&tasks)
+}
+
+fn cmd_start(args: &[String], out: &Output) -> Result<(), String> {
+    let id = parse_id(args, "Usage: todo start <id>")?;
+    let mut tasks = storage::open()?.load()?;
+    let now = now();
+
+    if !tasks.iter().any(|t| t.id == id) {
+        return Err(format!("Task {id} not found"));
+    }
+
+    // Only one timer runs at a time, so starting a task stops the others
+    for task in tasks.iter_mut().filter(|t| t.id != id) {
+        if task.stop_timer(now) && !out.is_json() {
+            println!("Stopped: {}", task.description);
+        }
+    }
+
+    let task = tasks.iter_mut().find(|t| t.id == id).unwrap();
+    if task.is_running() {
+        return out.task(task, &format!("Already tracking: {}", task.description));
+    }
+
+    task.start_timer(now);
+    out.task(task, &format!("Started: {}", task.description))?;
+    storage::open()?.save(&tasks)
+}
+
+fn cmd_stop(out: &Output) -> Result<(), String> {
+    let mut tasks = storage::open()?.load()?;
+    let now = now();
+
+    let task = tasks
+        .iter_mut()
+        .find(|t| t.is_running())
+        .ok_or("No task is being tracked")?;
+
+    task.stop_timer(now);
+    let spent = task.time_spent(NaiveDateTime::MIN, now);
+    out.task(
+        task,
+        &format!(
+            "Stopped: {} ({} total)",
+            task.description,
+            tracking::format_duration(spent)
+        ),
+    )?;
+    storage::open()?.save(&tasks)
+}
+
+fn cmd_log(args: &[String], out: &Output) -> Result<(), String> {
+    let id = match args.first() {
+        Some(_) => Some(parse_id(args, "Usage: todo log [<id>]")?),
+        None => None,
+    };
+
+    let mut events = history::load()?;
+    if let Some(id) = id {
+        events.retain(|e| e.task == id);
+    }
+
+    out.data(&events, || {
+        if events.is_empty() {
+            match id {
+                Some(id) => println!("No history for task {id}"),
+                None => println!("No history yet"),
+            }
+            return;
+        }
+
+        for event in &events {
+            println!("{event}");
+        }
+    })
+}
+
+fn cmd_rules(use_rules: bool, out: &Output) -> Result<(), String> {
+    let active = if use_rules {
+        rules::load()?
+    } else {
+        Vec::new()
+    };
+
+    out.data(&active, || {
+        if !use_rules {
+            println!("Rules are off for this run (--no-rules)");
+        } else if active.is_empty() {
+            println!("No rules. Add them to {}, e.g.:", rules::RULES_FILE);
+            println!("{}", rules::EXAMPLE);
+        } else {
+            println!("Rules from {}:", rules::RULES_FILE);
+            for (i, rule) in active.iter().enumerate() {
+                println!("  {}. {rule}", i + 1);
+            }
+        }
+    })
+}
+
+fn cmd_template(args: &[String], out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo template save <name> (<id>... | --tag <tag>) | apply <name> [--tag <tag>]... [--set <placeholder>=<value>]... | list | remove <name>";
+
+    let mut templates = template::load()?;
+    match args {
+        [command] if command == "list" => out.data(&templates, || {
+            if templates.is_empty() {
+                println!("No templates. Save one with todo template save <name> <id>...");
+            }
+            for (name, tasks) in &templates {
+                let placeholders = template::placeholders(tasks);
+                let uses = if placeholders.is_empty() {
+                    String::new()
+                } else {
+                    format!(", uses {{{}}}", placeholders.join("}, {"))
+                };
+                println!("  {name}: {} tasks{uses}", tasks.len());
+            }
+        }),
+        [command, name] if command == "remove" => {
+            let removed = templates
+                .remove(name)
+                .ok_or(format!("No template named '{name}'"))?;
+            template::save(&templates)?;
+            out.data(&removed, || println!("Removed template: {name}"))
+        }
+        [command, name, selection @ ..] if command == "save" => {
+            let tasks = storage::open()?.load()?;
+            let chosen: Vec<&Task> = match selection {
+                [] => return Err(USAGE.to_string()),
+                [flag, tag] if flag == "--tag" => {
+                    let tag = tag.trim_start_matches('#').to_lowercase();
+                    let tagged: Vec<&Task> =
+                        tasks.iter().filter(|t| t.tags.contains(&tag)).collect();
+                    if tagged.is_empty() {
+                        return Err(format!("No tasks tagged '{tag}'"));
+                    }
+                    tagged
+                }
+                ids => ids
+                    .iter()
+                    .map(|id| {
+                        let id: u32 = id.parse().map_err(|_| format!("Invalid task ID: {id}"))?;
+                        tasks
+                            .iter()
+                            .find(|t| t.id == id)
+                            .ok_or(format!("Task {id} not found"))
+                    })
+                    .collect::<Result<_, String>>()?,
+            };
+
+            let saved = template::capture(&chosen);
+            let replaced = templates.insert(name.clone(), saved.clone()).is_some();
+            template::save(&templates)?;
+            out.data(&saved, || {
+                let verb = if replaced { "Replaced" } else { "Saved" };
+                println!("{verb} template {name} with {} tasks", saved.len());
+            })
+        }
+        [command, name, options @ ..] if command == "apply" => {
+            let chosen = templates.get(name).ok_or(format!(
+                "No template named '{name}'; see todo template list"
+            ))?;
+            let mut tags = Vec::new();
+            let mut values = HashMap::new();
+            let mut iter = options.iter();
+            while let Some(flag) = iter.next() {
+                let value = iter.next().ok_or(USAGE)?;
+                match flag.as_str() {
+                    "--tag" => tags.push(value.trim_start_matches('#').to_lowercase()),
+                    "--set" => {
+                        let (placeholder, value) = template::parse_value(value)?;
+                        values.insert(placeholder, value);
+                    }
+                    _ => return Err(format!("Unknown option: {flag}\n{USAGE}")),
+                }
+            }
+
+            let mut tasks = storage::open()?.load()?;
+            let added = template::instantiate(chosen, storage::next_id(&tasks), &values, &tags)?;
+            out.data(&added, || {
+                println!("Added {} tasks from template {name}:", added.len());
+                for task in &added {
+                    println!("  {}. {}", task.id, task.description);
+                }
+            })?;
+            tasks.extend(added);
+            storage::open()?.save(&tasks)
+        }
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn cmd_serve(args: &[String]) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo serve [--port <port>]";
+
+    let port = match args {
+        [] => 8080,
+        [flag, port] if flag == "--port" => port
+            .parse()
+            .map_err(|_| format!("Invalid port: '{port}'"))?,
+        _ => return Err(USAGE.to_string()),
+    };
+    storage::require_file("todo serve")?;
+    server::serve(port)
+}
+
+#[derive(Serialize)]
+struct EncryptionResult {
+    encrypted: bool,
+}
+
+/// Switch to encrypted storage, converting existing plain files. Before
+/// another command the note goes to stderr so it doesn't mix with the
+/// command's own output.
+fn cmd_encrypt(alone: bool, out: &Output) -> Result<(), String> {
+    storage::require_file("--encrypt")?;
+    crypto::set_mode(true);
+    let already = storage::is_encrypted();
+    if !already {
+        crypto::choose_passphrase()?;
+        if storage::exists() {
+            storage::convert()?;
+        }
+        template::convert()?;
+    }
+
+    if alone {
+        out.data(&EncryptionResult { encrypted: true }, || {
+            if already {
+                println!("Todos are already encrypted");
+            } else {
+                println!("Todos are now stored encrypted");
+            }
+        })?;
+    } else if !already && storage::exists() {
+        eprintln!("Todos are now stored encrypted");
+    }
+    Ok(())
+}
+
+fn cmd_decrypt(out: &Output) -> Result<(), String> {
+    storage::require_file("todo decrypt")?;
+    if storage::is_encrypted() {
+        crypto::set_mode(false);
+        storage::convert()?;
+        template::convert()?;
+    }
+    out.data(&EncryptionResult { encrypted: false }, || {
+        println!("Todos are stored as plain text")
+    })
+}
+
+#[derive(Serialize)]
+struct Stats {
+    pending: usize,
+    completed: usize,
+    assignees: Vec<assignee::AssigneeCount>,
+}
+
+fn cmd_stats(out: &Output) -> Result<(), String> {
+    let tasks = storage::open()?.load()?;
+    let completed = tasks.iter().filter(|t| t.is_done()).count();
+    let stats = Stats {
+        pending: tasks.len() - completed,
+        completed,
+        assignees: assignee::counts(&tasks),
+    };
+
+    out.data(&stats, || {
+        println!(
+            "{} tasks: {} pending, {} completed",
+            tasks.len(),
+            stats.pending,
+            stats.completed
+        );
+        // Only worth a breakdown once someone has been assigned something
+        if stats.assignees.iter().all(|c| c.assignee.is_none()) {
+            return;
+        }
+        println!();
+        println!("  {:<16} {:>7} {:>9}", "Assignee", "Pending", "Completed");
+        for count in &stats.assignees {
+            let name = count.assignee.as_deref().unwrap_or("(unassigned)");
+            println!("  {name:<16} {:>7} {:>9}", count.pending, count.completed);
+        }
+    })
+}
+
+fn cmd_contexts(out: &Output) -> Result<(), String> {
+    let tasks = storage::open()?.load()?;
+    let counts = context::counts(&tasks, now().date());
+
+    out.data(&counts, || {
+        if counts.is_empty() {
+            println!("Nothing open");
+            return;
+        }
+        println!("  {:<16} {:>4} {:>7}", "Context", "Open", "Overdue");
+        for count in &counts {
+            let name = match &count.context {
+                Some(context) => format!("@{context}"),
+                None => "(none)".to_string(),
+            };
+            println!("  {name:<16} {:>4} {:>7}", count.open, count.overdue);
+        }
+    })
+}
+
+#[derive(Serialize)]
+struct TagTime {
+    tag: String,
+    minutes: i64,
+}
+
+fn cmd_report(args: &[String], out: &Output) -> Result<(), String> {
+    let week = match args.first().map(String::as_str) {
+        None => false,
+        Some("--week") => true,
+        Some(other) => {
+            return Err(format!(
+                "Unknown option: {other}\nUsage: todo report [--week]"
+            ))
+        }
+    };
+
+    let tasks = storage::open()?.load()?;
+    let now = now();
+    let from = if week {
+        tracking::start_of_week(now)
+    } else {
+        NaiveDateTime::MIN
+    };
+
+    let report = tracking::time_by_tag(&tasks, from, now);
+    let rows: Vec<TagTime> = report
+        .iter()
+        .map(|(tag, spent)| TagTime {
+            tag: tag.clone(),
+            minutes: spent.num_minutes(),
+        })
+        .collect();
+
+    out.data(&rows, || {
+        let title = if week { "This week" } else { "All time" };
+        println!("{title}:");
+
+        if report.is_empty() {
+            println!("  No time tracked.");
+            return;
+        }
+
+        for (tag, spent) in &report {
+            println!("  {:<16} {:>8}", tag, tracking::format_duration(*spent));
+        }
+    })
+}
+
+fn cmd_review(args: &[String], out: &Output) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        None | Some("--week") => {}
+        Some(other) => {
+            return Err(format!(
+                "Unknown option: {other}\nUsage: todo review [--week]"
+            ))
+        }
+    }
+
+    let tasks = storage::open()?.load()?;
+    let review = review::week(&tasks, now());
+    out.data(&review, || print!("{}", review::render_markdown(&review)))
+}
+
+fn cmd_sync(out: &Output) -> Result<(), String> {
+    storage::require_file("todo sync")?;
+    let local = storage::load_tasks()?;
+    // The synced copy is encrypted whenever todos.json is
+    let encrypt = crypto::should_encrypt(storage::is_encrypted());
+    let (merged, result) = sync::sync(&sync::sync_dir(), &local, encrypt)?;
+    storage::write_tasks(&merged)?;
+
+    out.data(&result, || {
+        let mut steps = Vec::new();
+        if result.pulled {
+            steps.push("pulled");
+        }
+        if result.committed {
+            steps.push("committed");
+        }
+        if result.pushed {
+            steps.push("pushed");
+        }
+        if steps.is_empty() {
+            steps.push("already up to date");
+        }
+        println!(
+            "Synced {} tasks with {} ({})",
+            result.tasks,
+            result.dir.display(),
+            steps.join(", ")
+        );
+    })
+}
+
+#[derive(Serialize)]
+struct ImportResult<'a> {
+    imported: &'a [Task],
+    errors: &'a [import::RowError],
+}
+
+fn cmd_import(args: &[String], out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo import [--format csv] <file> [--map field=Header,...]";
+
+    let mut path = None;
+    let mut map = import::ColumnMap::default();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => match iter.next().ok_or(USAGE)?.as_str() {
+                "csv" => {}
+                other => return Err(format!("Unsupported import format: '{other}'. Use csv")),
+            },
+            "--map" => map.apply(iter.next().ok_or(USAGE)?)?,
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option: {flag}\n{USAGE}"))
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let path = path.ok_or(USAGE)?;
+
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {path}: {e}"))?;
+    let mut tasks = storage::open()?.load()?;
+    let (imported, errors) = import::import_csv(file, &map, storage::next_id(&tasks), now())?;
+
+    let result = ImportResult {
+        imported: &imported,
+        errors: &errors,
+    };
+    out.data(&result, || {
+        for error in &errors {
+            println!("  Skipped line {}: {}", error.line, error.message);
+        }
+        let skipped = if errors.is_empty() {
+            String::new()
+        } else {
+            format!(", skipped {}", errors.len())
+        };
+        println!("Imported {} tasks from {path}{skipped}", imported.len());
+    })?;
+
+    tasks.extend(imported);
+    storage::open()?.save(&tasks)
+}
+
+#[derive(Serialize)]
+struct ExportResult<'a> {
+    path: &'a str,
+    exported: usize,
+}
+
+fn cmd_export(args: &[String], out: &Output) -> Result<(), String> {
+    const USAGE: &str = "Usage: todo export [--format ics] [<file>] [--url]";
+
+    let mut path = None;
+    let mut to_stdout = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => match iter.next().ok_or(USAGE)?.as_str() {
+                "ics" => {}
+                other => return Err(format!("Unsupported export format: '{other}'. Use ics")),
+            },
+            "--url" => to_stdout = true,
+            flag if flag.starts_with("--") => {
+                return Err(format!("Unknown option: {flag}\n{USAGE}"))
+            }
+            _ if path.is_none() => path = Some(arg.as_str()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    if to_stdout && path.is_some() {
+        return Err(format!(
+            "--url prints to stdout, so it can't take a file\n{USAGE}"
+        ));
+    }
+
+    let tasks = storage::open()?.load()?;
+    let calendar = ics::calendar(&tasks, chrono::Utc::now().naive_utc());
+
+    // Just the calendar, so it can be piped or served as-is
+    if to_stdout {
+        print!("{calendar}");
+        return Ok(());
+    }
+
+    let path = path.unwrap_or("todos.ics");
+    std::fs::write(path, calendar).map_err(|e| format!("Failed to write {path}: {e}"))?;
+
+    let exported = tasks.iter().filter(|t| t.due.is_some()).count();
+    out.data(&ExportResult { path, exported }, || {
+        println!("Exported {exported} tasks with due dates to {path}");
+    })
+}
+
 fn main() {
     if let Err(e) = run() {
         eprintln!("Error: {e}");
@@ -0,0 +1,248 @@
+//! `todo next`: the one task to do now, and why. Each task that's ready to
+//! start collects points for what makes it pressing, and the most points
+//! wins.
+
+use crate::task::{Priority, Status, Task};
+use crate::tracking::format_duration;
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
+
+/// Most points a task gets for the tasks waiting on it
+const MAX_UNBLOCK_POINTS: i32 = 15;
+
+/// One thing that counted for or against a task.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reason {
+    pub points: i32,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Pick<'a> {
+    pub task: &'a Task,
+    pub score: i32,
+    pub reasons: Vec<Reason>,
+}
+
+/// IDs in `task.depends_on` that aren't done yet. A dependency that was
+/// removed doesn't hold anything up.
+pub fn waiting_on(task: &Task, tasks: &[Task]) -> Vec<u32> {
+    task.depends_on
+        .iter()
+        .copied()
+        .filter(|id| tasks.iter().any(|t| t.id == *id && !t.is_done()))
+        .collect()
+}
+
+/// Check that `task_id` can depend on `depends_on`: every ID exists, and
+/// none of them already depends on `task_id`, directly or not.
+pub fn check_depends_on(tasks: &[Task], task_id: u32, depends_on: &[u32]) -> Result<(), String> {
+    for &id in depends_on {
+        if id == task_id {
+            return Err(format!("Task {id} can't depend on itself"));
+        }
+        if !tasks.iter().any(|t| t.id == id) {
+            return Err(format!("Task {id} not found"));
+        }
+    }
+
+    let mut stack: Vec<u32> = depends_on.to_vec();
+    let mut seen = Vec::new();
+    while let Some(id) = stack.pop() {
+        if id == task_id {
+            return Err(format!(
+                "That would make a loop: task {task_id} would end up waiting on itself"
+            ));
+        }
+        if seen.contains(&id) {
+            continue;
+        }
+        seen.push(id);
+        if let Some(task) = tasks.iter().find(|t| t.id == id) {
+            stack.extend(&task.depends_on);
+        }
+    }
+    Ok(())
+}
+
+fn plural(n: usize, word: &str) -> String {
+    if n == 1 {
+        format!("1 {word}")
+    } else {
+        format!("{n} {word}s")
+    }
+}
+
+/// Score `task` against the rest of `tasks` at `now`.
+pub fn score<'a>(task: &'a Task, tasks: &[Task], now: NaiveDateTime) -> Pick<'a> {
+    let mut reasons = Vec::new();
+    let mut add = |points: i32, text: String| reasons.push(Reason { points, text });
+
+    match task.priority {
+        Priority::High => add(30, "high priority".to_string()),
+        Priority::Medium => add(15, "medium priority".to_string()),
+        Priority::Low => {}
+    }
+
+    if let Some(due) = task.due {
+        let left = due.deadline() - now;
+        let points = if due.is_overdue(now) {
+            40
+        } else if left <= Duration::days(1) {
+            30
+        } else if left <= Duration::days(3) {
+            20
+        } else if left <= Duration::days(7) {
+            10
+        } else {
+            0
+        };
+        if points > 0 {
+            add(points, due.relative(now));
+        }
+        if let Some(minutes) = task.estimate {
+            let needed = Duration::minutes(minutes as i64);
+            if !due.is_overdue(now) && needed > left {
+                add(
+                    10,
+                    format!("needs {} and less is left", format_duration(needed)),
+                );
+            }
+        }
+    }
+
+    if task.status == Status::InProgress {
+        add(10, "already in progress".to_string());
+    }
+
+    if let Some(minutes) = task.estimate {
+        let estimate = format_duration(Duration::minutes(minutes as i64));
+        match minutes {
+            0..=30 => add(10, format!("quick win: about {estimate}")),
+            31..=120 => add(5, format!("fits in a session: about {estimate}")),
+            121..=480 => {}
+            _ => add(-5, format!("big: about {estimate}; maybe split it up")),
+        }
+    }
+
+    let unblocks = tasks
+        .iter()
+        .filter(|t| !t.is_done() && t.depends_on.contains(&task.id))
+        .count();
+    if unblocks > 0 {
+        add(
+            (5 * unblocks as i32).min(MAX_UNBLOCK_POINTS),
+            format!("unblocks {}", plural(unblocks, "task")),
+        );
+    }
+
+    Pick {
+        task,
+        score: reasons.iter().map(|r| r.points).sum(),
+        reasons,
+    }
+}
+
+/// Tasks that can be started now, best first. Ties go to the earlier
+/// deadline, then the lower ID.
+pub fn ranked(tasks: &[Task], now: NaiveDateTime) -> Vec<Pick<'_>> {
+    let mut picks: Vec<Pick> = tasks
+        .iter()
+        .filter(|t| !t.is_done() && !t.is_snoozed(now) && waiting_on(t, tasks).is_empty())
+        .map(|t| score(t, tasks, now))
+        .collect();
+    picks.sort_by_key(|p| {
+        (
+            -p.score,
+            p.task.due.map_or(NaiveDateTime::MAX, |d| d.deadline()),
+            p.task.id,
+        )
+    });
+    picks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::Due;
+    use crate::testing::{self, may, now};
+    use chrono::NaiveDate;
+
+    fn task(id: u32, priority: Priority) -> Task {
+        Task {
+            priority,
+            ..testing::task(id)
+        }
+    }
+
+    fn ids(picks: &[Pick]) -> Vec<u32> {
+        picks.iter().map(|p| p.task.id).collect()
+    }
+
+    #[test]
+    fn test_score_explains_itself() {
+        let mut report = task(1, Priority::High);
+        report.due = Some(Due::Date(may(2)));
+        report.estimate = Some(20);
+        let mut waiting = task(2, Priority::Low);
+        waiting.depends_on = vec![1];
+        let tasks = vec![report, waiting];
+
+        let pick = score(&tasks[0], &tasks, now());
+        let reasons: Vec<(i32, &str)> = pick
+            .reasons
+            .iter()
+            .map(|r| (r.points, r.text.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                (30, "high priority"),
+                (20, "due tomorrow"),
+                (10, "quick win: about 20m"),
+                (5, "unblocks 1 task"),
+            ]
+        );
+        assert_eq!(pick.score, 65);
+    }
+
+    #[test]
+    fn test_ranked_skips_blocked_done_and_snoozed() {
+        let mut blocked = task(1, Priority::High);
+        blocked.depends_on = vec![2];
+        let medium = task(2, Priority::Medium);
+        let mut done = task(3, Priority::High);
+        done.status = Status::Done;
+        let mut snoozed = task(4, Priority::High);
+        snoozed.snoozed_until = Some(now() + Duration::hours(2));
+        let mut started = task(5, Priority::Low);
+        started.status = Status::InProgress;
+        let mut overdue = task(6, Priority::Low);
+        overdue.due = Some(Due::Date(NaiveDate::from_ymd_opt(2024, 4, 29).unwrap()));
+        let mut huge = task(7, Priority::Low);
+        huge.estimate = Some(3 * 480);
+
+        let mut tasks = vec![blocked, medium, done, snoozed, started, overdue, huge];
+        // 6 is overdue (40); 2 is medium and unblocks 1 (20); 5 is started (10)
+        assert_eq!(ids(&ranked(&tasks, now())), [6, 2, 5, 7]);
+
+        tasks[1].status = Status::Done;
+        assert_eq!(ids(&ranked(&tasks, now())), [6, 1, 5, 7]);
+    }
+
+    #[test]
+    fn test_check_depends_on() {
+        let mut tasks = vec![task(1, Priority::Low), task(2, Priority::Low)];
+        tasks[1].depends_on = vec![1];
+        assert_eq!(check_depends_on(&tasks, 2, &[1]), Ok(()));
+        assert!(check_depends_on(&tasks, 1, &[2]).is_err());
+        assert!(check_depends_on(&tasks, 1, &[1]).is_err());
+        assert_eq!(
+            check_depends_on(&tasks, 1, &[9]),
+            Err("Task 9 not found".to_string())
+        );
+        assert_eq!(waiting_on(&tasks[1], &tasks), [1]);
+        tasks[0].status = Status::Done;
+        assert!(waiting_on(&tasks[1], &tasks).is_empty());
+    }
+}
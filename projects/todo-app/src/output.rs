@@ -0,0 +1,84 @@
+use crate::task::Task;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Every command prints through this so `--json` works everywhere: text mode
+/// shows human-friendly messages, JSON mode shows the data behind them.
+pub struct Output {
+    format: OutputFormat,
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Output { format }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Report a single affected task: `message` in text mode, the task itself
+    /// as JSON otherwise.
+    pub fn task(&self, task: &Task, message: &str) -> Result<(), String> {
+        match self.format {
+            OutputFormat::Text => {
+                println!("{message}");
+                Ok(())
+            }
+            OutputFormat::Json => print_json(task),
+        }
+    }
+
+    /// Show arbitrary data: `text` renders it for humans, JSON mode
+    /// serializes `value` instead.
+    pub fn data<T: Serialize>(&self, value: &T, text: impl FnOnce()) -> Result<(), String> {
+        match self.format {
+            OutputFormat::Text => {
+                text();
+                Ok(())
+            }
+            OutputFormat::Json => print_json(value),
+        }
+    }
+
+    pub fn task_list(&self, tasks: &[Task]) -> Result<(), String> {
+        if self.is_json() {
+            return print_json(&tasks);
+        }
+
+        if tasks.is_empty() {
+            println!("No tasks yet. Add one with: todo add \"your task\"");
+            return Ok(());
+        }
+
+        let pending = tasks.iter().filter(|t| !t.is_done()).count();
+        let completed = tasks.iter().filter(|t| t.is_done()).count();
+
+        println!(
+            "  {:<4} {:<8} {:<9} Description",
+            "ID", "Status", "Priority"
+        );
+        println!("  {}", "-".repeat(50));
+
+        for task in tasks {
+            println!("{task}");
+        }
+
+        println!();
+        println!("  {pending} pending, {completed} completed");
+
+        Ok(())
+    }
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize output: {e}"))?;
+    println!("{json}");
+    Ok(())
+}
@@ -0,0 +1,148 @@
+//! `todo plan --capacity 6h`: the tasks to take on today. Of the tasks
+//! `todo next` would consider, it picks the set with estimates that fits
+//! the time available and is worth the most by `next`'s scoring, a 0/1
+//! knapsack over minutes.
+
+use crate::next;
+use crate::task::Task;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+/// The most `todo plan` plans at once, 31 days; the knapsack table grows
+/// with every minute of capacity.
+pub const MAX_CAPACITY: u32 = 31 * 24 * 60;
+
+#[derive(Debug, Serialize)]
+pub struct Plan<'a> {
+    /// Minutes available
+    pub capacity: u32,
+    /// Minutes the planned tasks take
+    pub planned_minutes: u32,
+    /// Best first, as `todo next` ranks them
+    pub planned: Vec<&'a Task>,
+    /// Ready to start but there wasn't room for them
+    pub left_out: Vec<&'a Task>,
+    /// Ready to start but with no estimate to plan by
+    pub unestimated: Vec<&'a Task>,
+}
+
+/// Which of `items`, as (minutes, value), to take for the most value in
+/// `capacity` minutes. Ties go to items earlier in the list.
+fn knapsack(items: &[(u32, u32)], capacity: u32) -> Vec<bool> {
+    // More room than everything takes together changes nothing
+    let total: u64 = items.iter().map(|&(minutes, _)| u64::from(minutes)).sum();
+    let capacity = u64::from(capacity).min(total) as usize;
+    let mut best = vec![0u32; capacity + 1];
+    // took[i][w]: whether item i improved the best value for w minutes
+    let mut took = vec![vec![false; capacity + 1]; items.len()];
+    for (i, &(minutes, value)) in items.iter().enumerate() {
+        let minutes = minutes as usize;
+        for w in (minutes..=capacity).rev() {
+            let with = best[w - minutes] + value;
+            if with > best[w] {
+                best[w] = with;
+                took[i][w] = true;
+            }
+        }
+    }
+
+    let mut chosen = vec![false; items.len()];
+    let mut w = capacity;
+    for i in (0..items.len()).rev() {
+        if took[i][w] {
+            chosen[i] = true;
+            w -= items[i].0 as usize;
+        }
+    }
+    chosen
+}
+
+/// Plan `capacity` minutes of work at `now`. Each task is worth its `todo
+/// next` score, and at least 1, so a task with nothing going for it still
+/// fills spare time.
+pub fn plan(tasks: &[Task], capacity: u32, now: NaiveDateTime) -> Plan<'_> {
+    let ranked = next::ranked(tasks, now);
+    let (estimated, unestimated): (Vec<_>, Vec<_>) =
+        ranked.iter().partition(|p| p.task.estimate.is_some());
+
+    let items: Vec<(u32, u32)> = estimated
+        .iter()
+        .map(|p| (p.task.estimate.unwrap_or(0), p.score.max(0) as u32 + 1))
+        .collect();
+    let chosen = knapsack(&items, capacity);
+
+    let mut plan = Plan {
+        capacity,
+        planned_minutes: 0,
+        planned: Vec::new(),
+        left_out: Vec::new(),
+        unestimated: unestimated.iter().map(|p| p.task).collect(),
+    };
+    for (pick, chosen) in estimated.iter().zip(chosen) {
+        if chosen {
+            plan.planned_minutes += pick.task.estimate.unwrap_or(0);
+            plan.planned.push(pick.task);
+        } else {
+            plan.left_out.push(pick.task);
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::Due;
+    use crate::task::{Priority, Status};
+    use crate::testing::{self, may, now};
+
+    fn task(id: u32, priority: Priority, estimate: Option<u32>) -> Task {
+        Task {
+            priority,
+            estimate,
+            ..testing::task(id)
+        }
+    }
+
+    fn ids(tasks: &[&Task]) -> Vec<u32> {
+        tasks.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn test_knapsack_beats_greedy() {
+        // Taking the most valuable first (5h) leaves room for nothing else
+        let items = [(300, 50), (180, 30), (180, 30)];
+        assert_eq!(knapsack(&items, 360), [false, true, true]);
+        assert_eq!(knapsack(&items, 300), [true, false, false]);
+        assert_eq!(knapsack(&items, 0), [false, false, false]);
+        // Equal value: the earlier one
+        assert_eq!(knapsack(&[(60, 5), (60, 5)], 90), [true, false]);
+        // Far more time than needed takes everything without a huge table
+        assert_eq!(knapsack(&items, u32::MAX), [true, true, true]);
+    }
+
+    #[test]
+    fn test_plan_fits_capacity_by_priority_and_due_date() {
+        let mut urgent = task(1, Priority::Low, Some(240));
+        urgent.due = Some(Due::Date(may(1)));
+        let important = task(2, Priority::High, Some(120));
+        let filler = task(3, Priority::Low, Some(180));
+        let unknown = task(4, Priority::High, None);
+        let mut done = task(5, Priority::High, Some(10));
+        done.status = Status::Done;
+        let mut blocked = task(6, Priority::High, Some(10));
+        blocked.depends_on = vec![3];
+        let tasks = vec![urgent, important, filler, unknown, done, blocked];
+
+        let plan = plan(&tasks, 6 * 60, now());
+        // High priority (35 with its 2h bonus) outranks due today (30)
+        assert_eq!(ids(&plan.planned), [2, 1]);
+        assert_eq!(plan.planned_minutes, 360);
+        assert_eq!(ids(&plan.left_out), [3]);
+        assert_eq!(ids(&plan.unestimated), [4]);
+
+        let plan = super::plan(&tasks, 60, now());
+        assert!(plan.planned.is_empty());
+        assert_eq!(ids(&plan.left_out), [2, 1, 3]);
+    }
+}
@@ -0,0 +1,151 @@
+//! Quick-add: `todo add "pay rent !high #finance @home due:friday rec:monthly"`
+//! sets the priority, tags, context, due date and recurrence from words in
+//! the description, so there are no flags to type.
+//!
+//! - `!low`, `!medium`, `!high`: priority
+//! - `#tag`: a tag, any number of them
+//! - `@context`: where it can be done
+//! - `due:<when>`: anything `--due` takes, with `_` for spaces: `due:friday_5pm`
+//! - `rec:<daily|weekly|monthly|yearly>`: repeat it when it's done
+//!
+//! Priorities, tags and contexts start with a letter, so "fix #123" keeps its issue
+//! number. Everything else is the description.
+
+use crate::dates::{self, Due, Recurrence};
+use crate::task::Priority;
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct QuickAdd {
+    pub description: String,
+    pub priority: Option<Priority>,
+    pub tags: Vec<String>,
+    pub context: Option<String>,
+    pub due: Option<Due>,
+    pub recur: Option<Recurrence>,
+}
+
+/// The name after a `#` or `@`, if `word` is one.
+fn name(word: &str, sigil: char) -> Option<String> {
+    let name = word.strip_prefix(sigil)?;
+    name.starts_with(|c: char| c.is_alphabetic())
+        .then(|| name.to_lowercase())
+}
+
+/// Pull the quick-add words out of `input`, with due dates relative to
+/// `now`. A word that looks like one but doesn't parse, like `!urgent` or
+/// `due:someday`, is an error rather than part of the description.
+pub fn parse(input: &str, now: NaiveDateTime) -> Result<QuickAdd, String> {
+    let mut parsed = QuickAdd::default();
+    let mut words = Vec::new();
+
+    for word in input.split_whitespace() {
+        if let Some(priority) = word
+            .strip_prefix('!')
+            .filter(|p| p.starts_with(|c: char| c.is_alphabetic()))
+        {
+            parsed.priority = Some(Priority::from_str(priority)?);
+        } else if let Some(tag) = name(word, '#') {
+            if !parsed.tags.contains(&tag) {
+                parsed.tags.push(tag);
+            }
+        } else if let Some(context) = name(word, '@') {
+            parsed.context = Some(context);
+        } else if let Some(when) = word.strip_prefix("due:") {
+            parsed.due = Some(dates::parse_due(&when.replace('_', " "), now)?);
+        } else if let Some(recur) = word.strip_prefix("rec:") {
+            parsed.recur = Some(Recurrence::from_str(recur)?);
+        } else {
+            words.push(word);
+        }
+    }
+
+    if words.is_empty() {
+        return Err(format!("'{input}' has no description left"));
+    }
+    parsed.description = words.join(" ");
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{may, now};
+
+    /// `quick` written back as quick-add text, with the due date as a plain
+    /// date so it reads the same whenever it's parsed.
+    fn format(quick: &QuickAdd) -> String {
+        let mut words = vec![quick.description.clone()];
+        if let Some(priority) = &quick.priority {
+            words.push(format!("!{priority}"));
+        }
+        words.extend(quick.tags.iter().map(|tag| format!("#{tag}")));
+        if let Some(context) = &quick.context {
+            words.push(format!("@{context}"));
+        }
+        match quick.due {
+            Some(Due::Date(date)) => words.push(format!("due:{}", date.format("%Y-%m-%d"))),
+            Some(Due::DateTime(at)) => words.push(format!("due:{}", at.format("%Y-%m-%d_%H:%M"))),
+            None => {}
+        }
+        if let Some(recur) = &quick.recur {
+            words.push(format!("rec:{recur}"));
+        }
+        words.join(" ")
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = parse(
+            "pay rent !high #finance @home due:friday rec:monthly",
+            now(),
+        )
+        .unwrap();
+        assert_eq!(
+            parsed,
+            QuickAdd {
+                description: "pay rent".to_string(),
+                priority: Some(Priority::High),
+                tags: vec!["finance".to_string()],
+                context: Some("home".to_string()),
+                due: Some(Due::Date(may(3))),
+                recur: Some(Recurrence::Monthly),
+            }
+        );
+
+        // Words go anywhere, and only real tags count
+        let parsed = parse("#Work fix #123 and email me@example.com", now()).unwrap();
+        assert_eq!(parsed.description, "fix #123 and email me@example.com");
+        assert_eq!(parsed.tags, ["work"]);
+
+        let parsed = parse("call mom due:tomorrow_5pm !!", now()).unwrap();
+        assert_eq!(parsed.description, "call mom !!");
+        assert_eq!(parsed.due.unwrap().to_string(), "Thu 2024-05-02 17:00");
+    }
+
+    #[test]
+    fn test_invalid_words() {
+        assert!(parse("ship it !urgent", now()).is_err());
+        assert!(parse("ship it due:someday", now()).is_err());
+        assert!(parse("ship it rec:hourly", now()).is_err());
+        assert!(parse("#just #tags", now()).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for input in [
+            "pay rent !high #finance @home due:2024-05-03 rec:monthly",
+            "call mom !low due:2024-05-02_17:00",
+            "water the plants #garden #home rec:weekly",
+            "read a book",
+        ] {
+            let parsed = parse(input, now()).unwrap();
+            assert_eq!(format(&parsed), input);
+            assert_eq!(parse(&format(&parsed), now()).unwrap(), parsed);
+        }
+
+        // Relative dates come back as the date they meant
+        let parsed = parse("pay rent due:friday", now()).unwrap();
+        assert_eq!(format(&parsed), "pay rent due:2024-05-03");
+    }
+}
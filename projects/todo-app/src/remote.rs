@@ -0,0 +1,111 @@
+use crate::github::GitHubIssues;
+use crate::storage::Storage;
+use crate::task::Task;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A task store behind a network API, such as GitHub issues. Its methods
+/// return boxed futures rather than being `async fn`, so backends can be
+/// chosen at runtime as `Box<dyn RemoteBackend>`.
+pub trait RemoteBackend: Send + Sync {
+    fn load(&self) -> BoxFuture<'_, Result<Vec<Task>, String>>;
+    /// Make the remote copy match `tasks`
+    fn save<'a>(&'a self, tasks: &'a [Task]) -> BoxFuture<'a, Result<(), String>>;
+}
+
+/// A remote backend run on its own runtime, so the CLI commands can use it
+/// like todos.json.
+pub struct Remote {
+    backend: Box<dyn RemoteBackend>,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl Remote {
+    pub fn new(backend: Box<dyn RemoteBackend>) -> Result<Self, String> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to start the async runtime: {e}"))?;
+        Ok(Remote { backend, runtime })
+    }
+}
+
+impl Storage for Remote {
+    fn load(&self) -> Result<Vec<Task>, String> {
+        self.runtime.block_on(self.backend.load())
+    }
+
+    fn save(&mut self, tasks: &[Task]) -> Result<(), String> {
+        self.runtime.block_on(self.backend.save(tasks))
+    }
+}
+
+/// `$TODO_BACKEND`, unless it's unset or `file` for todos.json.
+pub fn selected() -> Option<String> {
+    std::env::var("TODO_BACKEND")
+        .ok()
+        .filter(|name| !name.is_empty() && name != "file")
+}
+
+/// The backend `$TODO_BACKEND` picks, if any. `github` keeps tasks as issues
+/// in `$TODO_GITHUB_REPO`, using the token in `$GITHUB_TOKEN`.
+pub fn configured() -> Result<Option<Remote>, String> {
+    let Some(name) = selected() else {
+        return Ok(None);
+    };
+    let backend: Box<dyn RemoteBackend> = match name.as_str() {
+        "github" => {
+            let repo = std::env::var("TODO_GITHUB_REPO")
+                .ok()
+                .filter(|r| r.contains('/'))
+                .ok_or("TODO_BACKEND=github needs TODO_GITHUB_REPO set to owner/name")?;
+            let token = std::env::var("GITHUB_TOKEN")
+                .ok()
+                .filter(|t| !t.is_empty())
+                .ok_or("TODO_BACKEND=github needs a token in GITHUB_TOKEN")?;
+            Box::new(GitHubIssues::new(repo, token))
+        }
+        other => {
+            return Err(format!(
+                "Unknown TODO_BACKEND '{other}'. Use file or github"
+            ))
+        }
+    };
+    Remote::new(backend).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Priority;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryBackend(Mutex<Vec<Task>>);
+
+    impl RemoteBackend for MemoryBackend {
+        fn load(&self) -> BoxFuture<'_, Result<Vec<Task>, String>> {
+            Box::pin(async { Ok(self.0.lock().unwrap().clone()) })
+        }
+
+        fn save<'a>(&'a self, tasks: &'a [Task]) -> BoxFuture<'a, Result<(), String>> {
+            Box::pin(async move {
+                *self.0.lock().unwrap() = tasks.to_vec();
+                Ok(())
+            })
+        }
+    }
+
+    #[test]
+    fn test_remote_is_a_storage() {
+        let mut store: Box<dyn Storage> =
+            Box::new(Remote::new(Box::new(MemoryBackend::default())).unwrap());
+        assert!(store.load().unwrap().is_empty());
+
+        let tasks = vec![Task::new(1, "Review PRs".to_string(), Priority::High)];
+        store.save(&tasks).unwrap();
+        assert_eq!(store.load().unwrap(), tasks);
+    }
+}
@@ -0,0 +1,275 @@
+//! `todo review --week`: a Markdown summary of the week so far, to paste
+//! into a standup or status doc.
+
+use crate::task::Task;
+use crate::tracking::start_of_week;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// What happened to the tasks between `from` and `to`.
+#[derive(Debug, Serialize)]
+pub struct Review<'a> {
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    /// Marked done in the period
+    pub completed: Vec<&'a Task>,
+    /// Added in the period, done or not
+    pub added: Vec<&'a Task>,
+    /// Still open and past their deadline at `to`
+    pub overdue: Vec<&'a Task>,
+    /// Open at the start of the period and still open. Tasks from before
+    /// `created_at` was recorded count as open since before it.
+    pub carried_over: Vec<&'a Task>,
+}
+
+fn within(at: Option<NaiveDateTime>, from: NaiveDateTime, to: NaiveDateTime) -> bool {
+    at.is_some_and(|at| from <= at && at <= to)
+}
+
+/// The week containing `now`, from Monday up to `now`.
+pub fn week(tasks: &[Task], now: NaiveDateTime) -> Review<'_> {
+    let from = start_of_week(now);
+    let open: Vec<&Task> = tasks.iter().filter(|t| !t.is_done()).collect();
+    Review {
+        from,
+        to: now,
+        completed: tasks
+            .iter()
+            .filter(|t| t.is_done() && within(t.completed_at, from, now))
+            .collect(),
+        added: tasks
+            .iter()
+            .filter(|t| within(t.created_at, from, now))
+            .collect(),
+        overdue: open
+            .iter()
+            .copied()
+            .filter(|t| t.due.is_some_and(|d| d.is_overdue(now)))
+            .collect(),
+        carried_over: open
+            .iter()
+            .copied()
+            .filter(|t| t.created_at.is_none_or(|c| c < from))
+            .collect(),
+    }
+}
+
+/// `tasks` by tag, alphabetically, with untagged ones last. A task with
+/// several tags is listed under each.
+fn by_tag<'a>(tasks: &[&'a Task]) -> Vec<(String, Vec<&'a Task>)> {
+    let mut tagged: BTreeMap<String, Vec<&Task>> = BTreeMap::new();
+    let mut untagged = Vec::new();
+    for task in tasks {
+        if task.tags.is_empty() {
+            untagged.push(*task);
+        }
+        for tag in &task.tags {
+            tagged.entry(tag.clone()).or_default().push(*task);
+        }
+    }
+
+    let mut groups: Vec<(String, Vec<&Task>)> = tagged.into_iter().collect();
+    if !untagged.is_empty() {
+        groups.push(("Untagged".to_string(), untagged));
+    }
+    groups
+}
+
+fn section(out: &mut Vec<String>, title: &str, tasks: &[&Task], detail: impl Fn(&Task) -> String) {
+    out.push(String::new());
+    out.push(format!("## {title} ({})", tasks.len()));
+    if tasks.is_empty() {
+        out.push(String::new());
+        out.push("_None._".to_string());
+        return;
+    }
+    for (tag, tasks) in by_tag(tasks) {
+        out.push(String::new());
+        out.push(format!("### {tag}"));
+        out.push(String::new());
+        for task in tasks {
+            let check = if task.is_done() { "x" } else { " " };
+            out.push(format!(
+                "- [{check}] {} (#{}{})",
+                task.description,
+                task.id,
+                detail(task)
+            ));
+        }
+    }
+}
+
+/// The review as Markdown: a heading, a one-line summary, then a section
+/// for each list grouped by tag.
+pub fn render_markdown(review: &Review) -> String {
+    let mut out = vec![
+        format!(
+            "# Weekly review: {} to {}",
+            review.from.format("%a %Y-%m-%d"),
+            review.to.format("%a %Y-%m-%d")
+        ),
+        String::new(),
+        format!(
+            "{} completed, {} added, {} overdue, {} carried over.",
+            review.completed.len(),
+            review.added.len(),
+            review.overdue.len(),
+            review.carried_over.len()
+        ),
+    ];
+
+    section(&mut out, "Completed", &review.completed, |t| {
+        t.completed_at
+            .map(|at| format!(", done {}", at.format("%a")))
+            .unwrap_or_default()
+    });
+    section(&mut out, "Added", &review.added, |_| String::new());
+    section(&mut out, "Overdue", &review.overdue, |t| {
+        t.due
+            .map(|d| format!(", {}", d.relative(review.to)))
+            .unwrap_or_default()
+    });
+    section(&mut out, "Carried over", &review.carried_over, |t| {
+        t.created_at
+            .map(|at| format!(", open since {}", at.format("%Y-%m-%d")))
+            .unwrap_or_default()
+    });
+
+    out.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::Due;
+    use crate::task::Status;
+    use crate::testing::{self, now};
+    use chrono::{Duration, NaiveDate};
+
+    /// Created `created_days_ago` before the shared clock, a Wednesday; the
+    /// week began Monday 2024-04-29
+    fn task(id: u32, tags: &[&str], created_days_ago: i64) -> Task {
+        Task {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            created_at: Some(now() - Duration::days(created_days_ago)),
+            ..testing::task(id)
+        }
+    }
+
+    fn ids(tasks: &[&Task]) -> Vec<u32> {
+        tasks.iter().map(|t| t.id).collect()
+    }
+
+    fn tasks() -> Vec<Task> {
+        // Old, finished on Tuesday
+        let mut shipped = task(1, &["work"], 10);
+        shipped.status = Status::Done;
+        shipped.completed_at = Some(now() - Duration::days(1));
+        // New this week and already done
+        let mut quick = task(2, &["home", "work"], 1);
+        quick.status = Status::Done;
+        quick.completed_at = Some(now() - Duration::hours(2));
+        // Old and overdue
+        let mut taxes = task(3, &[], 20);
+        taxes.due = Some(Due::Date(NaiveDate::from_ymd_opt(2024, 4, 30).unwrap()));
+        // From before `created_at` was recorded
+        let mut legacy = task(4, &["work"], 0);
+        legacy.created_at = None;
+        // Finished last week
+        let mut old_done = task(5, &["work"], 30);
+        old_done.status = Status::Done;
+        old_done.completed_at = Some(now() - Duration::days(7));
+        // Added this week, still open
+        let new = task(6, &[], 0);
+        vec![shipped, quick, taxes, legacy, old_done, new]
+    }
+
+    #[test]
+    fn test_week_sorts_tasks_into_lists() {
+        let tasks = tasks();
+        let review = week(&tasks, now());
+        assert_eq!(
+            review.from.date(),
+            NaiveDate::from_ymd_opt(2024, 4, 29).unwrap()
+        );
+        assert_eq!(ids(&review.completed), [1, 2]);
+        assert_eq!(ids(&review.added), [2, 6]);
+        assert_eq!(ids(&review.overdue), [3]);
+        assert_eq!(ids(&review.carried_over), [3, 4]);
+    }
+
+    #[test]
+    fn test_by_tag_lists_untagged_last() {
+        let tasks = tasks();
+        let refs: Vec<&Task> = tasks.iter().collect();
+        let groups: Vec<(String, Vec<u32>)> = by_tag(&refs)
+            .into_iter()
+            .map(|(tag, tasks)| (tag, ids(&tasks)))
+            .collect();
+        assert_eq!(
+            groups,
+            [
+                ("home".to_string(), vec![2]),
+                ("work".to_string(), vec![1, 2, 4, 5]),
+                ("Untagged".to_string(), vec![3, 6]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let tasks = tasks();
+        let markdown = render_markdown(&week(&tasks, now()));
+        let expected = "\
+# Weekly review: Mon 2024-04-29 to Wed 2024-05-01
+
+2 completed, 2 added, 1 overdue, 2 carried over.
+
+## Completed (2)
+
+### home
+
+- [x] Task 2 (#2, done Wed)
+
+### work
+
+- [x] Task 1 (#1, done Tue)
+- [x] Task 2 (#2, done Wed)
+
+## Added (2)
+
+### home
+
+- [x] Task 2 (#2)
+
+### work
+
+- [x] Task 2 (#2)
+
+### Untagged
+
+- [ ] Task 6 (#6)
+
+## Overdue (1)
+
+### Untagged
+
+- [ ] Task 3 (#3, overdue by 1 day)
+
+## Carried over (2)
+
+### work
+
+- [ ] Task 4 (#4)
+
+### Untagged
+
+- [ ] Task 3 (#3, open since 2024-04-11)
+";
+        assert_eq!(markdown, expected);
+
+        let empty = render_markdown(&week(&[], now()));
+        assert!(empty.contains("## Overdue (0)\n\n_None._\n"), "{empty}");
+    }
+}
@@ -0,0 +1,181 @@
+use crate::task::{Priority, Task};
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+pub const RULES_FILE: &str = "todo-rules.json";
+
+/// Shown by `todo rules` when there's nothing configured yet.
+pub const EXAMPLE: &str = r#"[
+  { "when": { "overdue_days": 3 }, "then": { "priority": "high" } },
+  { "when": { "tag": "someday" }, "then": "hide" }
+]"#;
+
+/// If a task matches `when`, `then` applies. Rules only change how tasks
+/// are shown; what's saved in todos.json is left alone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub when: Condition,
+    pub then: Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// Pending and past its deadline by more than this many days
+    OverdueDays(u32),
+    Tag(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Priority(Priority),
+    /// Leave the task out of `todo list` and `todo agenda`
+    Hide,
+}
+
+impl Condition {
+    fn matches(&self, task: &Task, now: NaiveDateTime) -> bool {
+        match self {
+            Condition::OverdueDays(days) => {
+                !task.is_done()
+                    && task
+                        .due
+                        .is_some_and(|due| now - due.deadline() > Duration::days(*days as i64))
+            }
+            Condition::Tag(tag) => task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.when {
+            Condition::OverdueDays(1) => write!(f, "overdue by more than 1 day")?,
+            Condition::OverdueDays(days) => write!(f, "overdue by more than {days} days")?,
+            Condition::Tag(tag) => write!(f, "tagged #{tag}")?,
+        }
+        match &self.then {
+            Action::Priority(priority) => write!(f, " → priority {priority}"),
+            Action::Hide => write!(f, " → hidden from list and agenda"),
+        }
+    }
+}
+
+/// The rules in `RULES_FILE`; none if it doesn't exist.
+pub fn load() -> Result<Vec<Rule>, String> {
+    if !Path::new(RULES_FILE).exists() {
+        return Ok(Vec::new());
+    }
+
+    let content =
+        fs::read_to_string(RULES_FILE).map_err(|e| format!("Failed to read {RULES_FILE}: {e}"))?;
+    parse(&content).map_err(|e| format!("Failed to parse {RULES_FILE}: {e}"))
+}
+
+fn parse(content: &str) -> Result<Vec<Rule>, String> {
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(content).map_err(|e| e.to_string())
+}
+
+/// Apply every matching priority rule to `task`, in order, so later rules
+/// win.
+pub fn escalate(rules: &[Rule], task: &mut Task, now: NaiveDateTime) {
+    for rule in rules {
+        if let Action::Priority(priority) = &rule.then {
+            if rule.when.matches(task, now) {
+                task.priority = priority.clone();
+            }
+        }
+    }
+}
+
+pub fn is_hidden(rules: &[Rule], task: &Task, now: NaiveDateTime) -> bool {
+    rules
+        .iter()
+        .any(|r| r.then == Action::Hide && r.when.matches(task, now))
+}
+
+/// Escalate every task and drop the hidden ones. Returns how many were
+/// hidden.
+pub fn apply(rules: &[Rule], tasks: &mut Vec<Task>, now: NaiveDateTime) -> usize {
+    let before = tasks.len();
+    tasks.retain(|t| !is_hidden(rules, t, now));
+    for task in tasks.iter_mut() {
+        escalate(rules, task, now);
+    }
+    before - tasks.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dates::Due;
+    use crate::task::Status;
+    use crate::testing::at;
+    use chrono::NaiveDate;
+
+    /// Later than the shared clock, so tasks can be days overdue in May
+    fn now() -> NaiveDateTime {
+        at(10, 12, 0)
+    }
+
+    fn due(task: &mut Task, month: u32, day: u32) {
+        task.due = Some(Due::Date(
+            NaiveDate::from_ymd_opt(2024, month, day).unwrap(),
+        ));
+    }
+
+    #[test]
+    fn test_example_parses() {
+        let rules = parse(EXAMPLE).unwrap();
+        assert_eq!(
+            rules[0],
+            Rule {
+                when: Condition::OverdueDays(3),
+                then: Action::Priority(Priority::High),
+            }
+        );
+        assert_eq!(rules[1].then, Action::Hide);
+        assert_eq!(
+            rules[0].to_string(),
+            "overdue by more than 3 days → priority high"
+        );
+        assert!(parse(r#"[{ "when": { "color": "red" }, "then": "hide" }]"#).is_err());
+    }
+
+    #[test]
+    fn test_overdue_escalation() {
+        let rules = parse(EXAMPLE).unwrap();
+        let mut long_overdue = Task::new(1, "Old".to_string(), Priority::Low);
+        due(&mut long_overdue, 5, 5);
+        let mut recent = Task::new(2, "Recent".to_string(), Priority::Low);
+        due(&mut recent, 5, 8);
+        let mut done = long_overdue.clone();
+        done.status = Status::Done;
+
+        for task in [&mut long_overdue, &mut recent, &mut done] {
+            escalate(&rules, task, now());
+        }
+        assert_eq!(long_overdue.priority, Priority::High);
+        assert_eq!(recent.priority, Priority::Low);
+        assert_eq!(done.priority, Priority::Low);
+    }
+
+    #[test]
+    fn test_apply_hides_tagged_tasks() {
+        let rules = parse(EXAMPLE).unwrap();
+        let mut someday = Task::new(1, "Learn the banjo".to_string(), Priority::Low);
+        someday.tags = vec!["someday".to_string()];
+        let mut tasks = vec![someday, Task::new(2, "Now".to_string(), Priority::Low)];
+
+        assert_eq!(apply(&rules, &mut tasks, now()), 1);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, 2);
+    }
+}
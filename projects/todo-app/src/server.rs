@@ -0,0 +1,373 @@
+use crate::storage::{self, Storage};
+use crate::task::{self, Priority, Status, Task};
+use crate::{assignee, dates};
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{Local, NaiveDateTime};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+
+/// One store for every request. Handlers hold the lock for a whole
+/// load-change-save, so concurrent requests can't lose each other's edits.
+type Shared = Arc<Mutex<Box<dyn Storage>>>;
+
+/// An error response: the status and `{"error": message}`.
+#[derive(Debug)]
+pub struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+fn bad_request(message: String) -> ApiError {
+    ApiError(StatusCode::BAD_REQUEST, message)
+}
+
+fn internal(message: String) -> ApiError {
+    ApiError(StatusCode::INTERNAL_SERVER_ERROR, message)
+}
+
+fn not_found(id: u32) -> ApiError {
+    ApiError(StatusCode::NOT_FOUND, format!("Task {id} not found"))
+}
+
+/// Malformed bodies get the same JSON error shape as everything else.
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError(rejection.status(), rejection.body_text())
+    }
+}
+
+/// Body of `POST /tasks`. Only the description is required.
+#[derive(Debug, Deserialize)]
+pub struct NewTask {
+    description: String,
+    priority: Option<Priority>,
+    /// Anything `todo add --due` accepts, e.g. "tomorrow 5pm"
+    due: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    assignee: Option<String>,
+}
+
+/// Body of `PATCH /tasks/{id}`. Fields left out stay as they are.
+#[derive(Debug, Deserialize)]
+pub struct TaskUpdate {
+    description: Option<String>,
+    priority: Option<Priority>,
+    status: Option<Status>,
+    /// From before `status`: true is done, false is backlog
+    completed: Option<bool>,
+    due: Option<String>,
+    tags: Option<Vec<String>>,
+    assignee: Option<String>,
+}
+
+fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    tags.iter()
+        .map(|t| t.trim_start_matches('#').to_lowercase())
+        .collect()
+}
+
+/// Run `change` on the stored tasks under the lock and save the result.
+fn with_tasks<T>(
+    shared: &Shared,
+    change: impl FnOnce(&mut Vec<Task>) -> Result<T, ApiError>,
+) -> Result<T, ApiError> {
+    let mut store = shared
+        .lock()
+        .map_err(|_| internal("Task store lock poisoned".to_string()))?;
+    let mut tasks = store.load().map_err(internal)?;
+    let result = change(&mut tasks)?;
+    store.save(&tasks).map_err(internal)?;
+    Ok(result)
+}
+
+async fn list_tasks(State(shared): State<Shared>) -> Result<Json<Vec<Task>>, ApiError> {
+    let store = shared
+        .lock()
+        .map_err(|_| internal("Task store lock poisoned".to_string()))?;
+    Ok(Json(store.load().map_err(internal)?))
+}
+
+async fn get_task(
+    State(shared): State<Shared>,
+    Path(id): Path<u32>,
+) -> Result<Json<Task>, ApiError> {
+    let store = shared
+        .lock()
+        .map_err(|_| internal("Task store lock poisoned".to_string()))?;
+    let tasks = store.load().map_err(internal)?;
+    let task = tasks
+        .into_iter()
+        .find(|t| t.id == id)
+        .ok_or(not_found(id))?;
+    Ok(Json(task))
+}
+
+async fn create_task(
+    State(shared): State<Shared>,
+    body: Result<Json<NewTask>, JsonRejection>,
+) -> Result<(StatusCode, Json<Task>), ApiError> {
+    let Json(new) = body?;
+    if new.description.trim().is_empty() {
+        return Err(bad_request("The description can't be empty".to_string()));
+    }
+    let due = new
+        .due
+        .map(|d| dates::parse_due(&d, now()))
+        .transpose()
+        .map_err(bad_request)?;
+    let assignee = new
+        .assignee
+        .map(|a| assignee::parse(&a))
+        .transpose()
+        .map_err(bad_request)?;
+
+    let task = with_tasks(&shared, |tasks| {
+        let mut task = Task::new(
+            storage::next_id(tasks),
+            new.description,
+            new.priority.unwrap_or(Priority::Medium),
+        );
+        task.due = due;
+        task.tags = normalize_tags(new.tags);
+        task.assignee = assignee;
+        tasks.push(task.clone());
+        Ok(task)
+    })?;
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+async fn update_task(
+    State(shared): State<Shared>,
+    Path(id): Path<u32>,
+    body: Result<Json<TaskUpdate>, JsonRejection>,
+) -> Result<Json<Task>, ApiError> {
+    let Json(update) = body?;
+    let due = update
+        .due
+        .map(|d| dates::parse_due(&d, now()))
+        .transpose()
+        .map_err(bad_request)?;
+    let assignee = update
+        .assignee
+        .map(|a| assignee::parse(&a))
+        .transpose()
+        .map_err(bad_request)?;
+
+    let task = with_tasks(&shared, |tasks| {
+        let index = tasks.iter().position(|t| t.id == id).ok_or(not_found(id))?;
+        let status = update.status.or(update.completed.map(|completed| {
+            if completed {
+                Status::Done
+            } else {
+                Status::Backlog
+            }
+        }));
+        // Through `task::complete`, so a recurring task repeats as it does
+        // with `todo done`
+        if status == Some(Status::Done) && !tasks[index].is_done() {
+            task::complete(tasks, index, now());
+        } else if let Some(status) = status {
+            tasks[index].status = status;
+        }
+
+        let task = &mut tasks[index];
+        if let Some(description) = update.description {
+            task.description = description;
+        }
+        if let Some(priority) = update.priority {
+            task.priority = priority;
+        }
+        if due.is_some() {
+            task.due = due;
+        }
+        if let Some(tags) = update.tags {
+            task.tags = normalize_tags(tags);
+        }
+        if assignee.is_some() {
+            task.assignee = assignee;
+        }
+        Ok(task.clone())
+    })?;
+    Ok(Json(task))
+}
+
+async fn delete_task(
+    State(shared): State<Shared>,
+    Path(id): Path<u32>,
+) -> Result<StatusCode, ApiError> {
+    with_tasks(&shared, |tasks| {
+        let position = tasks.iter().position(|t| t.id == id).ok_or(not_found(id))?;
+        tasks.remove(position);
+        Ok(())
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn router(store: Box<dyn Storage>) -> Router {
+    Router::new()
+        .route("/tasks", get(list_tasks).post(create_task))
+        .route(
+            "/tasks/{id}",
+            get(get_task).patch(update_task).delete(delete_task),
+        )
+        .with_state(Arc::new(Mutex::new(store)))
+}
+
+/// Serve the task store on `port` until the process is stopped.
+pub fn serve(port: u16) -> Result<(), String> {
+    let runtime =
+        tokio::runtime::Runtime::new().map_err(|e| format!("Failed to start server: {e}"))?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| format!("Failed to listen on port {port}: {e}"))?;
+        println!("Serving tasks on http://127.0.0.1:{port}/tasks (Ctrl-C to stop)");
+        axum::serve(listener, router(Box::new(storage::FileStorage)))
+            .await
+            .map_err(|e| format!("Server error: {e}"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    #[derive(Default)]
+    struct MemoryStorage(Vec<Task>);
+
+    impl Storage for MemoryStorage {
+        fn load(&self) -> Result<Vec<Task>, String> {
+            Ok(self.0.clone())
+        }
+
+        fn save(&mut self, tasks: &[Task]) -> Result<(), String> {
+            self.0 = tasks.to_vec();
+            Ok(())
+        }
+    }
+
+    /// Send one request and return the status and parsed JSON body (null
+    /// for an empty body).
+    async fn send(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body.map_or(Body::empty(), |b| Body::from(b.to_string())))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let json = if bytes.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap()
+        };
+        (status, json)
+    }
+
+    #[tokio::test]
+    async fn test_create_list_update_delete() {
+        let app = router(Box::new(MemoryStorage::default()));
+
+        let (status, created) = send(
+            &app,
+            "POST",
+            "/tasks",
+            Some(serde_json::json!({
+                "description": "Write the API",
+                "priority": "high",
+                "tags": ["#Work"],
+                "assignee": "@Alice"
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["id"], 1);
+        assert_eq!(created["priority"], "high");
+        assert_eq!(created["tags"], serde_json::json!(["work"]));
+        assert_eq!(created["assignee"], "alice");
+
+        let (status, listed) = send(&app, "GET", "/tasks", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+
+        let (status, updated) = send(
+            &app,
+            "PATCH",
+            "/tasks/1",
+            Some(serde_json::json!({ "completed": true })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["status"], "done");
+        assert_eq!(updated["description"], "Write the API");
+
+        let (_, updated) = send(
+            &app,
+            "PATCH",
+            "/tasks/1",
+            Some(serde_json::json!({ "status": "in-progress" })),
+        )
+        .await;
+        assert_eq!(updated["status"], "in-progress");
+
+        let (status, _) = send(&app, "DELETE", "/tasks/1", None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (_, listed) = send(&app, "GET", "/tasks", None).await;
+        assert_eq!(listed, serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let app = router(Box::new(MemoryStorage::default()));
+
+        let (status, body) = send(&app, "GET", "/tasks/7", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "Task 7 not found");
+
+        let (status, _) = send(
+            &app,
+            "POST",
+            "/tasks",
+            Some(serde_json::json!({ "description": "x", "due": "someday maybe" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = send(
+            &app,
+            "POST",
+            "/tasks",
+            Some(serde_json::json!({ "description": "x", "priority": "urgent" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body["error"].as_str().unwrap().contains("urgent"));
+
+        let (status, _) = send(&app, "DELETE", "/tasks/1", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -1,31 +1,320 @@
-use crate::task::Task;
-use std::fs;
+use crate::crypto;
+use crate::history;
+use crate::remote;
+use crate::sync;
+use crate::task::{Status, Task};
+use chrono::{Local, NaiveDateTime};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File, TryLockError};
+use std::hash::{Hash, Hasher};
 use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
 const STORAGE_FILE: &str = "todos.json";
 
+/// Held while todos.json is read back and rewritten, so two `todo` commands
+/// saving at once take turns. Left in place afterwards.
+const LOCK_FILE: &str = "todos.json.lock";
+
+/// How long to wait for another `todo` to finish saving
+const LOCK_ATTEMPTS: u32 = 50;
+const LOCK_RETRY: Duration = Duration::from_millis(100);
+
+/// The revision of todos.json this command last loaded or saved, and its
+/// tasks, to tell at save time whether another command wrote in between.
+static LOADED: Mutex<Option<(u64, Vec<Task>)>> = Mutex::new(None);
+
+/// Where tasks are loaded from and saved to: todos.json, or a remote backend
+/// picked with `$TODO_BACKEND`. `todo serve` takes one so its handlers can be
+/// tested against tasks in memory.
+pub trait Storage: Send {
+    fn load(&self) -> Result<Vec<Task>, String>;
+    fn save(&mut self, tasks: &[Task]) -> Result<(), String>;
+}
+
+/// The store the CLI commands use.
+pub fn open() -> Result<Box<dyn Storage>, String> {
+    Ok(match remote::configured()? {
+        Some(remote) => Box::new(remote),
+        None => Box::new(FileStorage),
+    })
+}
+
+/// For commands that only make sense with todos.json, like encryption and
+/// `todo sync`.
+pub fn require_file(command: &str) -> Result<(), String> {
+    match remote::selected() {
+        None => Ok(()),
+        Some(backend) => Err(format!(
+            "{command} works on todos.json only; unset TODO_BACKEND={backend} to use it"
+        )),
+    }
+}
+
+/// todos.json in the current directory, with history and encryption.
+pub struct FileStorage;
+
+impl Storage for FileStorage {
+    fn load(&self) -> Result<Vec<Task>, String> {
+        load_tasks()
+    }
+
+    fn save(&mut self, tasks: &[Task]) -> Result<(), String> {
+        save_tasks(tasks)
+    }
+}
+
 pub fn load_tasks() -> Result<Vec<Task>, String> {
+    let (tasks, revision) = read_tasks()?;
+    remember(revision, &tasks);
+    Ok(tasks)
+}
+
+/// The tasks in todos.json and its revision, a hash of the file; a missing
+/// file is revision 0.
+fn read_tasks() -> Result<(Vec<Task>, u64), String> {
     let path = Path::new(STORAGE_FILE);
 
     if !path.exists() {
-        return Ok(Vec::new());
+        return Ok((Vec::new(), 0));
     }
 
-    let content =
-        fs::read_to_string(path).map_err(|e| format!("Failed to read {STORAGE_FILE}: {e}"))?;
+    let raw = fs::read(path).map_err(|e| format!("Failed to read {STORAGE_FILE}: {e}"))?;
+    let revision = revision(&raw);
+    let content = if crypto::is_encrypted(&raw) {
+        crypto::decrypt_file(&raw, STORAGE_FILE)?
+    } else {
+        raw
+    };
+    let content = String::from_utf8(content)
+        .map_err(|_| format!("Failed to read {STORAGE_FILE}: not UTF-8"))?;
 
+    let mut tasks =
+        parse_tasks(&content).map_err(|e| format!("Failed to parse {STORAGE_FILE}: {e}"))?;
+    unsnooze_expired(&mut tasks, Local::now().naive_local());
+    Ok((tasks, revision))
+}
+
+fn revision(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish().max(1)
+}
+
+fn remember(revision: u64, tasks: &[Task]) {
+    if let Ok(mut loaded) = LOADED.lock() {
+        *loaded = Some((revision, tasks.to_vec()));
+    }
+}
+
+/// Take the lock on todos.json, waiting a few seconds for another `todo`
+/// that holds it. Released when the file is dropped.
+fn lock() -> Result<File, String> {
+    let file = File::create(LOCK_FILE).map_err(|e| format!("Failed to create {LOCK_FILE}: {e}"))?;
+    for _ in 0..LOCK_ATTEMPTS {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(TryLockError::WouldBlock) => thread::sleep(LOCK_RETRY),
+            Err(TryLockError::Error(e)) => {
+                return Err(format!("Failed to lock {STORAGE_FILE}: {e}"))
+            }
+        }
+    }
+    Err(format!(
+        "{STORAGE_FILE} is still being saved by another todo command; try again"
+    ))
+}
+
+/// Clear snoozes that ran out by `now`, so the tasks show up again.
+pub fn unsnooze_expired(tasks: &mut [Task], now: NaiveDateTime) {
+    for task in tasks {
+        if task.snoozed_until.is_some() && !task.is_snoozed(now) {
+            task.snoozed_until = None;
+        }
+    }
+}
+
+/// Whether todos.json is stored encrypted. A missing file isn't.
+pub fn is_encrypted() -> bool {
+    fs::read(STORAGE_FILE).is_ok_and(|content| crypto::is_encrypted(&content))
+}
+
+pub fn exists() -> bool {
+    Path::new(STORAGE_FILE).exists()
+}
+
+/// Parse the contents of a todos.json file; blank content means no tasks.
+pub fn parse_tasks(content: &str) -> Result<Vec<Task>, String> {
     if content.trim().is_empty() {
         return Ok(Vec::new());
     }
 
-    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {STORAGE_FILE}: {e}"))
+    let mut value: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    migrate_completed(&mut value);
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// Files from before `status` have `"completed": true/false` instead; turn
+/// that into done or backlog.
+fn migrate_completed(tasks: &mut serde_json::Value) {
+    let Some(tasks) = tasks.as_array_mut() else {
+        return;
+    };
+    for task in tasks.iter_mut().filter_map(|t| t.as_object_mut()) {
+        let Some(completed) = task.remove("completed") else {
+            continue;
+        };
+        if !task.contains_key("status") {
+            let status = if completed.as_bool() == Some(true) {
+                Status::Done
+            } else {
+                Status::Backlog
+            };
+            task.insert("status".to_string(), status.to_string().into());
+        }
+    }
+}
+
+pub fn to_json(tasks: &[Task]) -> Result<String, String> {
+    serde_json::to_string_pretty(tasks).map_err(|e| format!("Failed to serialize tasks: {e}"))
 }
 
+/// Save tasks, stamping the ones that changed since the last save. If
+/// another command saved since these were loaded, its changes are merged in
+/// rather than overwritten.
 pub fn save_tasks(tasks: &[Task]) -> Result<(), String> {
-    let json = serde_json::to_string_pretty(tasks)
-        .map_err(|e| format!("Failed to serialize tasks: {e}"))?;
+    let _lock = lock()?;
+    // An unreadable file is overwritten, as before, but not merged with
+    let (previous, current) = match read_tasks() {
+        Ok((tasks, revision)) => (tasks, Some(revision)),
+        Err(_) => (Vec::new(), None),
+    };
+    let base = LOADED.lock().ok().and_then(|loaded| loaded.clone());
+
+    let tasks = match (base, current) {
+        (Some((loaded, base)), Some(current)) if loaded != current => {
+            let (merged, moved) = reconcile(&base, &previous, tasks);
+            eprintln!("{STORAGE_FILE} changed while this command ran; kept both sets of changes.");
+            for (from, to) in moved {
+                eprintln!(
+                    "Task {from} is now task {to}: another command added a task {from} first."
+                );
+            }
+            merged
+        }
+        _ => tasks.to_vec(),
+    };
+    let stamped = stamp_changes(&previous, &tasks, Local::now().naive_local());
+    commit(&previous, &stamped)
+}
+
+/// Merge `ours`, edited from `base`, with `theirs`, what another command
+/// saved from the same `base` in the meantime. Their tasks keep their IDs;
+/// returns which of ours had to move to a new one.
+fn reconcile(base: &[Task], theirs: &[Task], ours: &[Task]) -> (Vec<Task>, Vec<(u32, u32)>) {
+    let merged = sync::merge(base, theirs, ours);
+    let moved = ours
+        .iter()
+        .filter(|task| !base.iter().any(|b| b.id == task.id))
+        .filter_map(|task| {
+            let same = |t: &&Task| t.description == task.description;
+            let at_id = merged.iter().find(|t| t.id == task.id);
+            if at_id.is_some_and(|t| same(&t)) {
+                return None;
+            }
+            let moved = merged
+                .iter()
+                .filter(|t| !theirs.iter().any(|th| th.id == t.id))
+                .find(same)?;
+            Some((task.id, moved.id))
+        })
+        .collect();
+    (merged, moved)
+}
+
+/// Save tasks exactly as given, without touching `modified`, and append what
+/// changed to the history log.
+pub fn write_tasks(tasks: &[Task]) -> Result<(), String> {
+    let _lock = lock()?;
+    let (previous, _) = read_tasks().unwrap_or_default();
+    commit(&previous, tasks)
+}
 
-    fs::write(STORAGE_FILE, json).map_err(|e| format!("Failed to write {STORAGE_FILE}: {e}"))
+/// Write `tasks` over `previous` and log the changes. The lock must be held.
+fn commit(previous: &[Task], tasks: &[Task]) -> Result<(), String> {
+    let revision = write_file(tasks)?;
+    remember(revision, tasks);
+    history::append(&history::changes(
+        previous,
+        tasks,
+        Local::now().naive_local(),
+    ))
+}
+
+/// Write todos.json encrypted or not, keeping whatever form it's in unless
+/// `--encrypt` or `todo decrypt` asked for a change. It's written to a
+/// temporary file first and renamed over, so a reader never sees half of it.
+/// Returns the new revision.
+fn write_file(tasks: &[Task]) -> Result<u64, String> {
+    let json = to_json(tasks)?;
+    let content = if crypto::should_encrypt(is_encrypted()) {
+        crypto::encrypt_file(json.as_bytes())?
+    } else {
+        json.into_bytes()
+    };
+    let temp = format!("{STORAGE_FILE}.{}.tmp", std::process::id());
+    fs::write(&temp, &content)
+        .and_then(|()| fs::rename(&temp, STORAGE_FILE))
+        .map_err(|e| {
+            let _ = fs::remove_file(&temp);
+            format!("Failed to write {STORAGE_FILE}: {e}")
+        })?;
+    Ok(revision(&content))
+}
+
+/// Rewrite todos.json and the history log in the form the current mode asks
+/// for, without recording any changes.
+pub fn convert() -> Result<(), String> {
+    let _lock = lock()?;
+    let (tasks, _) = read_tasks()?;
+    let events = history::load()?;
+    let revision = write_file(&tasks)?;
+    remember(revision, &tasks);
+    history::rewrite(&events)
+}
+
+/// Set `modified` to `now` on every task that is new or differs from its
+/// previous version, `created_at` on new tasks, and `completed_at` on tasks
+/// that just became done. Reopening a task clears `completed_at`.
+pub fn stamp_changes(previous: &[Task], tasks: &[Task], now: NaiveDateTime) -> Vec<Task> {
+    tasks
+        .iter()
+        .map(|task| {
+            let mut task = task.clone();
+            let unchanged = previous.iter().any(|old| {
+                let mut old = old.clone();
+                old.modified = task.modified;
+                old == task
+            });
+            if !unchanged {
+                task.modified = Some(now);
+            }
+
+            let old = previous.iter().find(|old| old.id == task.id);
+            if old.is_none() && task.created_at.is_none() {
+                task.created_at = Some(now);
+            }
+            let was_done = old.is_some_and(|old| old.is_done());
+            if !task.is_done() {
+                task.completed_at = None;
+            } else if !was_done && task.completed_at.is_none() {
+                task.completed_at = Some(now);
+            }
+            task
+        })
+        .collect()
 }
 
 pub fn next_id(tasks: &[Task]) -> u32 {
@@ -52,4 +341,90 @@ mod tests {
         ];
         assert_eq!(next_id(&tasks), 6);
     }
+
+    #[test]
+    fn test_stamp_changes_only_touches_changed_tasks() {
+        let now = NaiveDateTime::MIN;
+        let same = Task::new(1, "Same".to_string(), Priority::Low);
+        let mut edited = Task::new(2, "Edited".to_string(), Priority::Low);
+        let previous = vec![same.clone(), edited.clone()];
+        edited.status = Status::Done;
+        let added = Task::new(3, "Added".to_string(), Priority::Low);
+
+        let stamped = stamp_changes(&previous, &[same, edited, added], now);
+        let modified: Vec<_> = stamped.iter().map(|t| t.modified).collect();
+        assert_eq!(modified, vec![None, Some(now), Some(now)]);
+        let created: Vec<_> = stamped.iter().map(|t| t.created_at).collect();
+        assert_eq!(created, vec![None, None, Some(now)]);
+        let completed: Vec<_> = stamped.iter().map(|t| t.completed_at).collect();
+        assert_eq!(completed, vec![None, Some(now), None]);
+
+        // Saving again keeps the stamps; reopening clears `completed_at`
+        let later = now + chrono::Duration::days(1);
+        let again = stamp_changes(&stamped, &stamped, later);
+        assert_eq!(again, stamped);
+        let mut reopened = stamped[1].clone();
+        reopened.status = Status::Backlog;
+        let reopened = stamp_changes(&stamped, &[reopened], later);
+        assert_eq!(reopened[0].completed_at, None);
+    }
+
+    #[test]
+    fn test_reconcile_keeps_both_commands_changes() {
+        let base = vec![Task::new(1, "Shared".to_string(), Priority::Low)];
+
+        // Both commands added task 2, and the other one also finished task 1
+        let mut theirs = base.clone();
+        theirs[0].status = Status::Done;
+        theirs.push(Task::new(2, "Theirs".to_string(), Priority::Low));
+        let mut ours = base.clone();
+        ours[0].priority = Priority::High;
+        ours.push(Task::new(2, "Ours".to_string(), Priority::Low));
+
+        let (merged, moved) = reconcile(&base, &theirs, &ours);
+        let summary: Vec<_> = merged
+            .iter()
+            .map(|t| (t.id, t.description.as_str()))
+            .collect();
+        assert_eq!(summary, [(1, "Shared"), (2, "Theirs"), (3, "Ours")]);
+        assert_eq!(merged[0].status, Status::Done);
+        assert_eq!(merged[0].priority, Priority::High);
+        assert_eq!(moved, [(2, 3)]);
+
+        // Nothing to move when the new IDs don't collide
+        let (_, moved) = reconcile(&base, &base, &ours);
+        assert!(moved.is_empty());
+    }
+
+    #[test]
+    fn test_parse_tasks_migrates_completed() {
+        let old = r#"[
+            {"id":1,"description":"Old done","completed":true,"priority":"low"},
+            {"id":2,"description":"Old open","completed":false,"priority":"low"},
+            {"id":3,"description":"New","status":"in-progress","priority":"low"}
+        ]"#;
+        let statuses: Vec<Status> = parse_tasks(old).unwrap().iter().map(|t| t.status).collect();
+        assert_eq!(
+            statuses,
+            [Status::Done, Status::Backlog, Status::InProgress]
+        );
+
+        let saved = to_json(&parse_tasks(old).unwrap()).unwrap();
+        assert!(!saved.contains("completed"));
+    }
+
+    #[test]
+    fn test_unsnooze_expired() {
+        let now = NaiveDateTime::MIN + chrono::Duration::days(10);
+        let mut tasks = vec![
+            Task::new(1, "Expired".to_string(), Priority::Low),
+            Task::new(2, "Still snoozed".to_string(), Priority::Low),
+        ];
+        tasks[0].snoozed_until = Some(now - chrono::Duration::hours(1));
+        tasks[1].snoozed_until = Some(now + chrono::Duration::hours(1));
+
+        unsnooze_expired(&mut tasks, now);
+        assert_eq!(tasks[0].snoozed_until, None);
+        assert!(tasks[1].is_snoozed(now));
+    }
 }
@@ -0,0 +1,500 @@
+use crate::attachment::Attachment;
+use crate::crypto;
+use crate::storage;
+use crate::task::Task;
+use crate::tracking::WorkInterval;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SYNC_FILE: &str = "todos.json";
+
+/// What `sync` did, for reporting back to the user.
+#[derive(Debug, serde::Serialize)]
+pub struct SyncResult {
+    pub dir: PathBuf,
+    pub tasks: usize,
+    pub pulled: bool,
+    pub committed: bool,
+    pub pushed: bool,
+}
+
+/// The git checkout used for syncing: `$TODO_SYNC_DIR`, or `~/.todo-sync`.
+pub fn sync_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("TODO_SYNC_DIR") {
+        return PathBuf::from(dir);
+    }
+    let home = std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    home.join(".todo-sync")
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    git_bytes(dir, args).map(|stdout| String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// Like `git`, for output that may not be text, such as an encrypted file.
+fn git_bytes(dir: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run git: {e}"))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+/// The synced task list as of `rev` and whether it was encrypted there, or
+/// `None` if it doesn't exist there.
+fn tasks_at(dir: &Path, rev: &str) -> Result<Option<(Vec<Task>, bool)>, String> {
+    let Ok(raw) = git_bytes(dir, &["show", &format!("{rev}:{SYNC_FILE}")]) else {
+        return Ok(None);
+    };
+    let what = format!("{SYNC_FILE} at {rev}");
+    let encrypted = crypto::is_encrypted(&raw);
+    let content = if encrypted {
+        crypto::decrypt_file(&raw, &what)?
+    } else {
+        raw
+    };
+    let content =
+        String::from_utf8(content).map_err(|_| format!("Failed to parse {what}: not UTF-8"))?;
+    storage::parse_tasks(&content)
+        .map(|tasks| Some((tasks, encrypted)))
+        .map_err(|e| format!("Failed to parse {what}: {e}"))
+}
+
+/// A fresh clone of an empty repo has no upstream branch. Once another
+/// machine has pushed one, start tracking it.
+fn track_remote_branch(dir: &Path, remote: &str) -> Result<(), String> {
+    if git(dir, &["rev-parse", "--abbrev-ref", "@{u}"]).is_ok() {
+        return Ok(());
+    }
+
+    let branch = git(dir, &["symbolic-ref", "--short", "HEAD"])?;
+    let remote_branch = format!("{remote}/{}", branch.trim());
+    if git(dir, &["rev-parse", "--verify", "--quiet", &remote_branch]).is_ok() {
+        git(dir, &["branch", "--set-upstream-to", &remote_branch])?;
+    }
+    Ok(())
+}
+
+/// Merge `local` with the copy in the git checkout at `dir` and its upstream,
+/// commit the result, and push it. With `encrypt` the committed file is
+/// encrypted like todos.json, so the remote never sees the tasks. Returns the
+/// merged tasks, which the caller should write back locally.
+pub fn sync(dir: &Path, local: &[Task], encrypt: bool) -> Result<(Vec<Task>, SyncResult), String> {
+    if git(dir, &["rev-parse", "--git-dir"]).is_err() {
+        return Err(format!(
+            "{} is not a git repository. Clone your sync repo there first:\n  git clone <url> {}",
+            dir.display(),
+            dir.display()
+        ));
+    }
+
+    // What we last synced is the common ancestor of both sides' edits
+    let head = tasks_at(dir, "HEAD")?;
+    let base = head
+        .as_ref()
+        .map(|(tasks, _)| tasks.clone())
+        .unwrap_or_default();
+
+    let remote_name = git(dir, &["remote"])?.lines().next().map(str::to_string);
+    if let Some(name) = &remote_name {
+        git(dir, &["fetch", "--quiet", name])?;
+        track_remote_branch(dir, name)?;
+    }
+    let has_upstream = git(dir, &["rev-parse", "--abbrev-ref", "@{u}"]).is_ok();
+
+    let remote = if has_upstream {
+        let remote = tasks_at(dir, "@{u}")?;
+        // Build the new commit directly on top of upstream; anything only
+        // in our old commits is already part of `base`.
+        git(dir, &["reset", "--soft", "@{u}"])?;
+        remote
+    } else {
+        None
+    };
+
+    let theirs = remote.as_ref().map(|(tasks, _)| tasks.as_slice());
+    let merged = merge(&base, local, theirs.unwrap_or(&base));
+
+    // The commit the new one goes on top of
+    let parent = if has_upstream { &remote } else { &head };
+    let unchanged = parent
+        .as_ref()
+        .is_some_and(|(tasks, encrypted)| *tasks == merged && *encrypted == encrypt);
+    if unchanged {
+        // Encrypting again would change every byte without changing a task
+        git(dir, &["checkout", "HEAD", "--", SYNC_FILE])?;
+    } else {
+        let json = storage::to_json(&merged)?;
+        let content = if encrypt {
+            crypto::encrypt_file(json.as_bytes())?
+        } else {
+            json.into_bytes()
+        };
+        fs::write(dir.join(SYNC_FILE), content)
+            .map_err(|e| format!("Failed to write {}: {e}", dir.join(SYNC_FILE).display()))?;
+        git(dir, &["add", SYNC_FILE])?;
+    }
+
+    let committed = git(dir, &["diff", "--cached", "--quiet"]).is_err();
+    if committed {
+        git(dir, &["commit", "--quiet", "-m", "Sync todos"])?;
+    }
+
+    let ahead = !has_upstream || git(dir, &["status", "-sb"])?.contains("ahead");
+    let pushed = match &remote_name {
+        Some(name) if ahead => {
+            git(dir, &["push", "--quiet", "-u", name, "HEAD"])?;
+            true
+        }
+        _ => false,
+    };
+
+    let result = SyncResult {
+        dir: dir.to_path_buf(),
+        tasks: merged.len(),
+        pulled: remote.is_some(),
+        committed,
+        pushed,
+    };
+    Ok((merged, result))
+}
+
+/// Three-way merge of task lists against their last synced version `base`.
+///
+/// Tasks from both sides are kept. A field changed on only one side takes
+/// that change; a field changed on both takes the more recently modified
+/// task's value. Tracked time is combined. A deletion wins unless the other
+/// side edited the task, and two different tasks created under the same ID
+/// are both kept by giving the remote one a new ID.
+pub fn merge(base: &[Task], local: &[Task], remote: &[Task]) -> Vec<Task> {
+    let (base, local) = follow_renumbering(base, local, remote);
+    let (base, local) = (base.as_slice(), local.as_slice());
+    let find = |tasks: &[Task], id: u32| tasks.iter().find(|t| t.id == id).cloned();
+    let mut merged = Vec::new();
+    let mut renumber = Vec::new();
+
+    for l in local {
+        let b = find(base, l.id);
+        match (find(remote, l.id), &b) {
+            // Deleted remotely and untouched here
+            (None, Some(b)) if b == l => {}
+            (None, _) => merged.push(l.clone()),
+            // Created on both sides independently
+            (Some(r), None) if r.description != l.description => {
+                merged.push(l.clone());
+                renumber.push(r);
+            }
+            (Some(r), b) => merged.push(merge_task(b.as_ref(), l, &r)),
+        }
+    }
+
+    for r in remote.iter().filter(|r| find(local, r.id).is_none()) {
+        match find(base, r.id) {
+            // Deleted here and untouched remotely
+            Some(b) if b == *r => {}
+            _ => merged.push(r.clone()),
+        }
+    }
+
+    for mut task in renumber {
+        task.id = storage::next_id(&merged);
+        merged.push(task);
+    }
+
+    merged.sort_by_key(|t| t.id);
+    merged
+}
+
+/// If the other machine gave one of our synced tasks a new ID to resolve a
+/// collision, move it to that ID here too so edits line up with the right
+/// task. A task counts as moved when its old ID now holds something else and
+/// a previously unused ID holds its description.
+fn follow_renumbering(base: &[Task], local: &[Task], remote: &[Task]) -> (Vec<Task>, Vec<Task>) {
+    let moves: Vec<(u32, u32)> = base
+        .iter()
+        .filter_map(|b| {
+            let current = remote.iter().find(|r| r.id == b.id)?;
+            if current.description == b.description {
+                return None;
+            }
+            remote
+                .iter()
+                .find(|r| {
+                    r.description == b.description && base.iter().all(|other| other.id != r.id)
+                })
+                .map(|r| (b.id, r.id))
+        })
+        .collect();
+
+    let relabel = |tasks: &[Task]| -> Vec<Task> {
+        tasks
+            .iter()
+            .map(|t| {
+                let mut t = t.clone();
+                let moved = |id: u32| {
+                    moves
+                        .iter()
+                        .find(|(from, _)| *from == id)
+                        .map_or(id, |(_, to)| *to)
+                };
+                t.id = moved(t.id);
+                t.depends_on = t.depends_on.iter().map(|&id| moved(id)).collect();
+                t
+            })
+            .collect()
+    };
+
+    (relabel(base), relabel(local))
+}
+
+fn merge_task(base: Option<&Task>, local: &Task, remote: &Task) -> Task {
+    let newer = if remote.modified > local.modified {
+        remote
+    } else {
+        local
+    };
+
+    macro_rules! pick {
+        ($field:ident) => {
+            match base {
+                Some(b) if local.$field == b.$field => remote.$field.clone(),
+                Some(b) if remote.$field == b.$field => local.$field.clone(),
+                _ => newer.$field.clone(),
+            }
+        };
+    }
+
+    Task {
+        id: local.id,
+        description: pick!(description),
+        status: pick!(status),
+        priority: pick!(priority),
+        due: pick!(due),
+        tags: pick!(tags),
+        context: pick!(context),
+        recur: pick!(recur),
+        assignee: pick!(assignee),
+        snoozed_until: pick!(snoozed_until),
+        estimate: pick!(estimate),
+        depends_on: pick!(depends_on),
+        // Each side only ever counts up
+        rollovers: local.rollovers.max(remote.rollovers),
+        intervals: merge_intervals(&local.intervals, &remote.intervals),
+        attachments: merge_attachments(&local.attachments, &remote.attachments),
+        modified: local.modified.max(remote.modified),
+        created_at: pick!(created_at),
+        completed_at: pick!(completed_at),
+        spawned_next: pick!(spawned_next),
+    }
+}
+
+/// Union of work intervals by start time. If one side has stopped an
+/// interval the other still has running, the stopped version wins.
+fn merge_intervals(local: &[WorkInterval], remote: &[WorkInterval]) -> Vec<WorkInterval> {
+    let mut merged: Vec<WorkInterval> = local.to_vec();
+
+    for r in remote {
+        match merged.iter_mut().find(|m| m.start == r.start) {
+            Some(m) if m.end.is_none() => m.end = r.end,
+            Some(_) => {}
+            None => merged.push(r.clone()),
+        }
+    }
+
+    merged.sort_by_key(|i| i.start);
+    merged
+}
+
+/// Union of attachments by target, oldest first.
+fn merge_attachments(local: &[Attachment], remote: &[Attachment]) -> Vec<Attachment> {
+    let mut merged: Vec<Attachment> = local.to_vec();
+    for r in remote {
+        if !merged.iter().any(|m| m.target == r.target) {
+            merged.push(r.clone());
+        }
+    }
+    merged.sort_by_key(|a| a.added);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::{Priority, Status};
+    use crate::testing::at;
+
+    fn task(id: u32, description: &str) -> Task {
+        Task::new(id, description.to_string(), Priority::Medium)
+    }
+
+    /// Noon on May `d`
+    fn day(d: u32) -> Option<chrono::NaiveDateTime> {
+        Some(at(d, 12, 0))
+    }
+
+    #[test]
+    fn test_encrypted_sync_commits_no_plaintext() {
+        let dir = std::env::temp_dir().join(format!("todo-sync-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let key = dir.join("key");
+        fs::write(&key, "correct horse\n").unwrap();
+        crypto::set_key_file(key);
+        for args in [
+            &["init", "--quiet"][..],
+            &["config", "user.email", "todo@example.com"],
+            &["config", "user.name", "todo"],
+        ] {
+            git(&dir, args).unwrap();
+        }
+
+        let local = vec![task(1, "Renew passport")];
+        let (_, result) = sync(&dir, &local, true).unwrap();
+        assert!(result.committed);
+        let committed = git_bytes(&dir, &["show", &format!("HEAD:{SYNC_FILE}")]).unwrap();
+        assert!(crypto::is_encrypted(&committed));
+        assert!(!committed.windows(8).any(|w| w == b"passport"));
+        assert_eq!(tasks_at(&dir, "HEAD").unwrap(), Some((local.clone(), true)));
+
+        // Nothing changed, so nothing is re-encrypted and committed again
+        let (_, result) = sync(&dir, &local, true).unwrap();
+        assert!(!result.committed);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_union_of_new_tasks() {
+        let base = vec![task(1, "Shared")];
+        let local = vec![task(1, "Shared"), task(2, "Laptop")];
+        let remote = vec![task(1, "Shared"), task(3, "Desktop")];
+
+        let ids: Vec<u32> = merge(&base, &local, &remote).iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_same_id_created_on_both_sides_is_renumbered() {
+        let merged = merge(&[], &[task(1, "Laptop")], &[task(1, "Desktop")]);
+        let names: Vec<(u32, &str)> = merged
+            .iter()
+            .map(|t| (t.id, t.description.as_str()))
+            .collect();
+        assert_eq!(names, vec![(1, "Laptop"), (2, "Desktop")]);
+    }
+
+    #[test]
+    fn test_follows_remote_renumbering() {
+        // We synced "Laptop" as 1; the other machine already had a 1 and
+        // moved ours to 2. Completing 1 here must complete "Laptop".
+        let base = vec![task(1, "Laptop")];
+        let mut local = base.clone();
+        local[0].status = Status::Done;
+        let remote = vec![task(1, "Desktop"), task(2, "Laptop")];
+
+        let merged = merge(&base, &local, &remote);
+        let state: Vec<(u32, &str, bool)> = merged
+            .iter()
+            .map(|t| (t.id, t.description.as_str(), t.is_done()))
+            .collect();
+        assert_eq!(state, vec![(1, "Desktop", false), (2, "Laptop", true)]);
+    }
+
+    #[test]
+    fn test_edits_to_different_fields_both_survive() {
+        let base = vec![task(1, "Write docs")];
+        let mut local = base.clone();
+        local[0].status = Status::Done;
+        local[0].modified = day(2);
+        let mut remote = base.clone();
+        remote[0].priority = Priority::High;
+        remote[0].assignee = Some("alice".to_string());
+        remote[0].modified = day(1);
+
+        let merged = merge(&base, &local, &remote);
+        assert!(merged[0].is_done());
+        assert_eq!(merged[0].priority, Priority::High);
+        assert_eq!(merged[0].assignee.as_deref(), Some("alice"));
+        assert_eq!(merged[0].modified, day(2));
+    }
+
+    #[test]
+    fn test_conflicting_field_newest_wins() {
+        let base = vec![task(1, "Draft")];
+        let mut local = base.clone();
+        local[0].description = "Local title".to_string();
+        local[0].modified = day(1);
+        let mut remote = base.clone();
+        remote[0].description = "Remote title".to_string();
+        remote[0].modified = day(2);
+
+        assert_eq!(merge(&base, &local, &remote)[0].description, "Remote title");
+    }
+
+    #[test]
+    fn test_deletions() {
+        let base = vec![task(1, "Old"), task(2, "Also old")];
+        let mut edited = task(2, "Also old");
+        edited.status = Status::Done;
+
+        // 1 deleted locally, untouched remotely: gone.
+        // 2 deleted locally, but completed remotely: kept.
+        let merged = merge(&base, &[], &[task(1, "Old"), edited]);
+        assert_eq!(merged.len(), 1);
+        assert!(merged[0].is_done());
+    }
+
+    #[test]
+    fn test_intervals_are_combined() {
+        let start = |d| WorkInterval {
+            start: day(d).unwrap(),
+            end: None,
+        };
+        let mut local = task(1, "Work");
+        local.intervals = vec![start(1)];
+        let mut remote = task(1, "Work");
+        remote.intervals = vec![
+            WorkInterval {
+                end: day(2),
+                ..start(1)
+            },
+            start(3),
+        ];
+
+        let merged = merge(&[], &[local], &[remote]);
+        assert_eq!(merged[0].intervals.len(), 2);
+        assert_eq!(merged[0].intervals[0].end, day(2));
+    }
+
+    #[test]
+    fn test_attachments_are_combined() {
+        let mut local = task(1, "Spec");
+        local.attach("https://example.com/a".to_string(), day(2).unwrap());
+        let mut remote = task(1, "Spec");
+        remote.attach("https://example.com/b".to_string(), day(1).unwrap());
+        remote.attach("https://example.com/a".to_string(), day(3).unwrap());
+
+        let merged = merge(&[], &[local], &[remote]);
+        let targets: Vec<&str> = merged[0]
+            .attachments
+            .iter()
+            .map(|a| a.target.as_str())
+            .collect();
+        assert_eq!(
+            targets,
+            vec!["https://example.com/b", "https://example.com/a"]
+        );
+    }
+}
@@ -1,3 +1,7 @@
+use crate::attachment::Attachment;
+use crate::dates::{Due, Recurrence};
+use crate::tracking::WorkInterval;
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -25,19 +29,105 @@ impl Priority {
             "low" => Ok(Priority::Low),
             "medium" | "med" => Ok(Priority::Medium),
             "high" => Ok(Priority::High),
+            _ => Err(format!("Invalid priority: '{s}'. Use low, medium, or high")),
+        }
+    }
+}
+
+/// Where a task stands; the columns of `todo board`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    #[default]
+    Backlog,
+    InProgress,
+    Done,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Backlog => write!(f, "backlog"),
+            Status::InProgress => write!(f, "in-progress"),
+            Status::Done => write!(f, "done"),
+        }
+    }
+}
+
+impl Status {
+    pub const ALL: [Status; 3] = [Status::Backlog, Status::InProgress, Status::Done];
+
+    pub fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "backlog" | "todo" => Ok(Status::Backlog),
+            "in-progress" | "in_progress" | "doing" | "wip" => Ok(Status::InProgress),
+            "done" => Ok(Status::Done),
             _ => Err(format!(
-                "Invalid priority: '{s}'. Use low, medium, or high"
+                "Invalid status: '{s}'. Use backlog, in-progress, or done"
             )),
         }
     }
+
+    /// Column heading on the board
+    pub fn title(&self) -> &'static str {
+        match self {
+            Status::Backlog => "Backlog",
+            Status::InProgress => "In Progress",
+            Status::Done => "Done",
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Task {
     pub id: u32,
     pub description: String,
-    pub completed: bool,
+    /// Replaced `completed: bool`; `storage::parse_tasks` converts old files
+    #[serde(default)]
+    pub status: Status,
     pub priority: Priority,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<Due>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub intervals: Vec<WorkInterval>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    /// Where it can be done, like `home` or `phone`; `@home` in quick-add
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// `todo done` adds the next occurrence, due one period later
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recur: Option<Recurrence>,
+    /// Who's doing it, when a team shares the list through `todo sync`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    /// Hidden from `todo list` until then; see `todo snooze`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<NaiveDateTime>,
+    /// Rough effort in minutes, for `todo next`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<u32>,
+    /// IDs of tasks that have to be done before this one can start
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<u32>,
+    /// How many times `todo today` has moved it on to a new day unfinished
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub rollovers: u32,
+    /// When the task last changed; `todo sync` uses it to pick the newer edit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<NaiveDateTime>,
+    /// When it was added. Tasks from before this was recorded have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<NaiveDateTime>,
+    /// When it was last marked done; cleared if it's reopened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<NaiveDateTime>,
+    /// The id of the next occurrence completing it added, so completing it
+    /// again after a reopen doesn't add another
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spawned_next: Option<u32>,
 }
 
 impl Task {
@@ -45,33 +135,141 @@ impl Task {
         Task {
             id,
             description,
-            completed: false,
+            status: Status::Backlog,
             priority,
+            due: None,
+            tags: Vec::new(),
+            intervals: Vec::new(),
+            attachments: Vec::new(),
+            context: None,
+            recur: None,
+            assignee: None,
+            snoozed_until: None,
+            estimate: None,
+            depends_on: Vec::new(),
+            rollovers: 0,
+            modified: None,
+            created_at: None,
+            completed_at: None,
+            spawned_next: None,
         }
     }
+
+    pub fn is_done(&self) -> bool {
+        self.status == Status::Done
+    }
+
+    /// Undo completing it: back to the backlog, with `completed_at` cleared.
+    /// Any occurrence completing it added stays.
+    pub fn reopen(&mut self) {
+        self.status = Status::Backlog;
+        self.completed_at = None;
+    }
+
+    pub fn is_snoozed(&self, now: NaiveDateTime) -> bool {
+        self.snoozed_until.is_some_and(|until| until > now)
+    }
+
+    /// The next occurrence of a recurring task, due one period after this
+    /// one was, or after `today` if it had no due date. Its id is left for
+    /// the caller to assign.
+    pub fn next_occurrence(&self, today: NaiveDate) -> Option<Task> {
+        let recur = self.recur?;
+        let mut next = Task::new(self.id, self.description.clone(), self.priority.clone());
+        next.due = Some(recur.after(self.due.unwrap_or(Due::Date(today)))?);
+        next.recur = Some(recur);
+        next.tags = self.tags.clone();
+        next.context = self.context.clone();
+        next.assignee = self.assignee.clone();
+        next.estimate = self.estimate;
+        Some(next)
+    }
+}
+
+/// Mark `tasks[index]` done, however it's being done: `todo done`, `todo
+/// move`, or the server. A recurring task adds its next occurrence unless
+/// the one it added before is still there. Returns the index of the task
+/// added, if any.
+pub fn complete(tasks: &mut Vec<Task>, index: usize, now: NaiveDateTime) -> Option<usize> {
+    let next_id = crate::storage::next_id(tasks);
+    let spawned = tasks[index]
+        .spawned_next
+        .is_some_and(|id| tasks.iter().any(|t| t.id == id));
+    let task = &mut tasks[index];
+    task.status = Status::Done;
+    // Saving stamps it too, but then `--json` wouldn't show it
+    task.completed_at = Some(now);
+    if spawned {
+        return None;
+    }
+
+    let mut next = task.next_occurrence(now.date())?;
+    next.id = next_id;
+    task.spawned_next = Some(next_id);
+    tasks.push(next);
+    Some(tasks.len() - 1)
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
 }
 
 impl fmt::Display for Task {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let status = if self.completed { "x" } else { " " };
+        let status = match self.status {
+            Status::Backlog => " ",
+            Status::InProgress => "~",
+            Status::Done => "x",
+        };
         write!(
             f,
             "  {:<4} [{}]      {:<8}  {}",
             self.id, status, self.priority, self.description
-        )
+        )?;
+
+        for tag in &self.tags {
+            write!(f, " #{tag}")?;
+        }
+
+        if let Some(assignee) = &self.assignee {
+            write!(f, " @{assignee}")?;
+        }
+
+        if let Some(context) = &self.context {
+            write!(f, " (at {context})")?;
+        }
+
+        if let Some(due) = &self.due {
+            write!(f, " (due {due})")?;
+        }
+
+        if let Some(recur) = &self.recur {
+            write!(f, " (repeats {recur})")?;
+        }
+
+        if let Some(until) = &self.snoozed_until {
+            write!(f, " (snoozed until {})", until.format("%a %Y-%m-%d %H:%M"))?;
+        }
+
+        if self.is_running() {
+            write!(f, " [running]")?;
+        }
+
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{may, now};
 
     #[test]
     fn test_new_task() {
         let task = Task::new(1, "Test task".to_string(), Priority::Medium);
         assert_eq!(task.id, 1);
         assert_eq!(task.description, "Test task");
-        assert!(!task.completed);
+        assert_eq!(task.status, Status::Backlog);
         assert_eq!(task.priority, Priority::Medium);
     }
 
@@ -83,6 +281,15 @@ mod tests {
         assert!(Priority::from_str("invalid").is_err());
     }
 
+    #[test]
+    fn test_status_from_str() {
+        assert_eq!(Status::from_str("in-progress"), Ok(Status::InProgress));
+        assert_eq!(Status::from_str("WIP"), Ok(Status::InProgress));
+        assert_eq!(Status::from_str("todo"), Ok(Status::Backlog));
+        assert!(Status::from_str("blocked").is_err());
+        assert_eq!(Status::InProgress.to_string(), "in-progress");
+    }
+
     #[test]
     fn test_priority_display() {
         assert_eq!(format!("{}", Priority::Low), "low");
@@ -99,4 +306,81 @@ mod tests {
         assert_eq!(parsed.id, 1);
         assert_eq!(parsed.priority, Priority::High);
     }
+
+    #[test]
+    fn test_next_occurrence() {
+        let mut task = Task::new(1, "Pay rent".to_string(), Priority::High);
+        assert!(task.next_occurrence(may(1)).is_none());
+
+        task.recur = Some(Recurrence::Monthly);
+        task.context = Some("home".to_string());
+        task.status = Status::Done;
+        let next = task.next_occurrence(may(1)).unwrap();
+        assert_eq!(
+            next.due,
+            Some(Due::Date(NaiveDate::from_ymd_opt(2024, 6, 1).unwrap()))
+        );
+        assert_eq!(next.status, Status::Backlog);
+        assert_eq!(next.context.as_deref(), Some("home"));
+
+        task.due = Some(Due::Date(may(3)));
+        let next = task.next_occurrence(may(1)).unwrap();
+        assert_eq!(
+            next.due,
+            Some(Due::Date(NaiveDate::from_ymd_opt(2024, 6, 3).unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_complete_adds_the_next_occurrence_once() {
+        let now = now();
+        let mut rent = Task::new(3, "Pay rent".to_string(), Priority::High);
+        rent.recur = Some(Recurrence::Monthly);
+        let mut tasks = vec![Task::new(7, "Other".to_string(), Priority::Low), rent];
+
+        let added = complete(&mut tasks, 1, now).unwrap();
+        assert_eq!(tasks[added].id, 8);
+        assert_eq!(tasks[1].spawned_next, Some(8));
+        assert_eq!(tasks[1].completed_at, Some(now));
+
+        // Done again: the occurrence is already there
+        tasks[1].status = Status::InProgress;
+        assert_eq!(complete(&mut tasks, 1, now), None);
+        assert_eq!(tasks.len(), 3);
+
+        // Unless it was removed in the meantime
+        tasks.pop();
+        assert!(complete(&mut tasks, 1, now).is_some());
+        assert!(complete(&mut tasks, 0, now).is_none());
+    }
+
+    #[test]
+    fn test_reopen_and_complete_again() {
+        let now = now();
+        let mut rent = Task::new(1, "Pay rent".to_string(), Priority::High);
+        rent.recur = Some(Recurrence::Monthly);
+        rent.due = Some(Due::Date(may(1)));
+        let mut tasks = vec![rent];
+        complete(&mut tasks, 0, now).unwrap();
+
+        tasks[0].reopen();
+        assert_eq!(tasks[0].status, Status::Backlog);
+        assert_eq!(tasks[0].completed_at, None);
+
+        assert_eq!(complete(&mut tasks, 0, now), None);
+        let june: Vec<u32> = tasks
+            .iter()
+            .filter(|t| t.due == Some(Due::Date(NaiveDate::from_ymd_opt(2024, 6, 1).unwrap())))
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(june, [2]);
+    }
+
+    #[test]
+    fn test_task_without_due_still_loads() {
+        let json = r#"{"id":1,"description":"Old","completed":false,"priority":"low"}"#;
+        let parsed: Task = serde_json::from_str(json).unwrap();
+        assert_eq!(parsed.due, None);
+        assert!(!serde_json::to_string(&parsed).unwrap().contains("due"));
+    }
 }
@@ -0,0 +1,255 @@
+use crate::task::{Priority, Task};
+use crate::{crypto, storage};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Saved with `todo template save`, by name. Kept apart from todos.json so
+/// templates aren't synced or counted as tasks, but encrypted whenever
+/// todos.json is, since they hold task descriptions and tags.
+pub const TEMPLATES_FILE: &str = "todo-templates.json";
+
+/// One task of a template: what `todo template apply` copies into a new
+/// task. Text can hold placeholders like `{version}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateTask {
+    pub description: String,
+    pub priority: Priority,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<u32>,
+    /// Positions in the template of the tasks this one waits for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<usize>,
+}
+
+pub type Templates = BTreeMap<String, Vec<TemplateTask>>;
+
+/// The templates in `TEMPLATES_FILE`; none if it doesn't exist.
+pub fn load() -> Result<Templates, String> {
+    if !Path::new(TEMPLATES_FILE).exists() {
+        return Ok(Templates::new());
+    }
+    let raw =
+        fs::read(TEMPLATES_FILE).map_err(|e| format!("Failed to read {TEMPLATES_FILE}: {e}"))?;
+    let content = if crypto::is_encrypted(&raw) {
+        crypto::decrypt_file(&raw, TEMPLATES_FILE)?
+    } else {
+        raw
+    };
+    let content = String::from_utf8(content)
+        .map_err(|_| format!("Failed to read {TEMPLATES_FILE}: not UTF-8"))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {TEMPLATES_FILE}: {e}"))
+}
+
+pub fn save(templates: &Templates) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(templates)
+        .map_err(|e| format!("Failed to serialize templates: {e}"))?
+        + "\n";
+    let content = if crypto::should_encrypt(storage::is_encrypted()) {
+        crypto::encrypt_file(json.as_bytes())?
+    } else {
+        json.into_bytes()
+    };
+    fs::write(TEMPLATES_FILE, content).map_err(|e| format!("Failed to write {TEMPLATES_FILE}: {e}"))
+}
+
+/// Rewrite the templates in the form `--encrypt` or `todo decrypt` just
+/// switched todos.json to.
+pub fn convert() -> Result<(), String> {
+    if !Path::new(TEMPLATES_FILE).exists() {
+        return Ok(());
+    }
+    save(&load()?)
+}
+
+/// `tasks` as a template, in the order given. Dependencies on tasks outside
+/// the set are dropped; the rest point at positions in the template.
+pub fn capture(tasks: &[&Task]) -> Vec<TemplateTask> {
+    tasks
+        .iter()
+        .map(|task| TemplateTask {
+            description: task.description.clone(),
+            priority: task.priority.clone(),
+            tags: task.tags.clone(),
+            context: task.context.clone(),
+            estimate: task.estimate,
+            after: task
+                .depends_on
+                .iter()
+                .filter_map(|id| tasks.iter().position(|t| t.id == *id))
+                .collect(),
+        })
+        .collect()
+}
+
+/// The names of the `{placeholders}` in `text`, in order.
+fn placeholder_names(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('}') else { break };
+        let name = &rest[..end];
+        if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            names.push(name);
+        }
+        rest = &rest[end + 1..];
+    }
+    names
+}
+
+/// Every placeholder the template uses, alphabetically.
+pub fn placeholders(template: &[TemplateTask]) -> Vec<String> {
+    let names: BTreeSet<&str> = template
+        .iter()
+        .flat_map(|t| {
+            std::iter::once(t.description.as_str())
+                .chain(t.tags.iter().map(String::as_str))
+                .chain(t.context.as_deref())
+        })
+        .flat_map(placeholder_names)
+        .collect();
+    names.into_iter().map(String::from).collect()
+}
+
+/// `text` with its placeholders filled in, in one pass, so a value that
+/// looks like a placeholder itself is left as it is.
+fn substitute(text: &str, values: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let found = after
+            .find('}')
+            .and_then(|end| Some((values.get(&after[..end])?, end)));
+        match found {
+            Some((value, end)) => {
+                result.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push('{');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// New tasks from `template`, numbered from `first_id`, with its
+/// placeholders filled in from `values` and `tags` added to each.
+pub fn instantiate(
+    template: &[TemplateTask],
+    first_id: u32,
+    values: &HashMap<String, String>,
+    tags: &[String],
+) -> Result<Vec<Task>, String> {
+    let missing: Vec<String> = placeholders(template)
+        .into_iter()
+        .filter(|name| !values.contains_key(name))
+        .collect();
+    if let Some(name) = missing.first() {
+        let flags: Vec<String> = missing.iter().map(|n| format!("--set {n}=...")).collect();
+        return Err(format!(
+            "The template needs a value for {{{name}}}; add {}",
+            flags.join(" ")
+        ));
+    }
+
+    let id_of = |position: usize| first_id + position as u32;
+    Ok(template
+        .iter()
+        .enumerate()
+        .map(|(position, t)| {
+            let mut task = Task::new(
+                id_of(position),
+                substitute(&t.description, values),
+                t.priority.clone(),
+            );
+            task.tags = t.tags.iter().map(|tag| substitute(tag, values)).collect();
+            for tag in tags {
+                if !task.tags.contains(tag) {
+                    task.tags.push(tag.clone());
+                }
+            }
+            task.context = t.context.as_ref().map(|c| substitute(c, values));
+            task.estimate = t.estimate;
+            task.depends_on = t.after.iter().map(|&p| id_of(p)).collect();
+            task
+        })
+        .collect())
+}
+
+/// Parse a `--set name=value` for `todo template apply`.
+pub fn parse_value(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!(
+            "Invalid --set '{arg}'; use name=value, e.g. version=2.0"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checklist() -> Vec<Task> {
+        let mut bump = Task::new(4, "Bump version to {version}".to_string(), Priority::High);
+        bump.tags = vec!["release".to_string()];
+        let mut notes = Task::new(7, "Write notes for {version}".to_string(), Priority::Medium);
+        notes.depends_on = vec![4, 2];
+        let mut tag = Task::new(9, "Tag v{version}".to_string(), Priority::Medium);
+        tag.depends_on = vec![7];
+        tag.estimate = Some(15);
+        vec![bump, notes, tag]
+    }
+
+    #[test]
+    fn test_capture_keeps_dependencies_inside_the_set() {
+        let tasks = checklist();
+        let template = capture(&tasks.iter().collect::<Vec<_>>());
+        let after: Vec<&[usize]> = template.iter().map(|t| t.after.as_slice()).collect();
+        assert_eq!(after, [&[][..], &[0], &[1]]);
+        assert_eq!(placeholders(&template), ["version"]);
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let tasks = checklist();
+        let template = capture(&tasks.iter().collect::<Vec<_>>());
+        let values = HashMap::from([("version".to_string(), "2.0".to_string())]);
+        let added = instantiate(&template, 20, &values, &["v2.0".to_string()]).unwrap();
+
+        let ids: Vec<u32> = added.iter().map(|t| t.id).collect();
+        assert_eq!(ids, [20, 21, 22]);
+        assert_eq!(added[0].description, "Bump version to 2.0");
+        assert_eq!(added[0].tags, ["release", "v2.0"]);
+        assert_eq!(added[2].description, "Tag v2.0");
+        assert_eq!(added[2].depends_on, [21]);
+        assert_eq!(added[2].estimate, Some(15));
+
+        let err = instantiate(&template, 20, &HashMap::new(), &[]).unwrap_err();
+        assert!(err.contains("{version}; add --set version=..."), "{err}");
+        assert!(parse_value("version").is_err());
+    }
+
+    #[test]
+    fn test_substitute_in_one_pass() {
+        let values = HashMap::from([
+            ("a".to_string(), "{b}".to_string()),
+            ("b".to_string(), "x".to_string()),
+        ]);
+        assert_eq!(substitute("{a} {b}", &values), "{b} x");
+        assert_eq!(substitute("{{a}} {c} {", &values), "{{b}} {c} {");
+    }
+}
@@ -0,0 +1,26 @@
+//! Fixtures shared by the tests: a fixed clock in May 2024 and a plain task
+//! to build on.
+
+use crate::task::{Priority, Task};
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// A day in May 2024.
+pub fn may(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+}
+
+/// `may(day)` at `hour:minute`.
+pub fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    may(day).and_hms_opt(hour, minute, 0).unwrap()
+}
+
+/// What the tests take as now: Wednesday 2024-05-01 09:00.
+pub fn now() -> NaiveDateTime {
+    at(1, 9, 0)
+}
+
+/// A medium-priority task described as "Task {id}", for tests to set the
+/// fields they look at with `Task { ..task(id) }`.
+pub fn task(id: u32) -> Task {
+    Task::new(id, format!("Task {id}"), Priority::Medium)
+}
@@ -0,0 +1,106 @@
+use crate::dates::Due;
+use crate::task::Task;
+use chrono::NaiveDate;
+
+/// `todo today --roll`: move every unfinished task due before `today` to
+/// today, keeping the time of day of a timed one. Returns how many moved. A
+/// task only moves once it's behind, so running this again the same day
+/// changes nothing.
+pub fn roll_over(tasks: &mut [Task], today: NaiveDate) -> usize {
+    let mut rolled = 0;
+    for task in tasks.iter_mut().filter(|t| !t.is_done()) {
+        let Some(due) = task.due else {
+            continue;
+        };
+        if due.date() >= today {
+            continue;
+        }
+        task.due = Some(match due {
+            Due::Date(_) => Due::Date(today),
+            Due::DateTime(dt) => Due::DateTime(today.and_time(dt.time())),
+        });
+        task.rollovers += 1;
+        rolled += 1;
+    }
+    rolled
+}
+
+/// How many unfinished tasks are due before `today`: what `roll_over` would
+/// move.
+pub fn overdue(tasks: &[Task], today: NaiveDate) -> usize {
+    tasks
+        .iter()
+        .filter(|t| !t.is_done() && t.due.is_some_and(|d| d.date() < today))
+        .count()
+}
+
+/// Tasks due today: unfinished first, then timed ones by time, then the
+/// rest by ID.
+pub fn due_today(tasks: &[Task], today: NaiveDate) -> Vec<Task> {
+    let mut due: Vec<Task> = tasks
+        .iter()
+        .filter(|t| t.due.is_some_and(|d| d.date() == today))
+        .cloned()
+        .collect();
+    due.sort_by_key(|t| (t.is_done(), t.due.map(|d| d.deadline()), t.id));
+    due
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Status;
+    use crate::testing::{self, may};
+
+    fn task(id: u32, due: Option<Due>) -> Task {
+        Task {
+            due,
+            ..testing::task(id)
+        }
+    }
+
+    #[test]
+    fn test_roll_over_moves_unfinished_past_tasks() {
+        let at_nine = may(3).and_hms_opt(9, 0, 0).unwrap();
+        let mut tasks = vec![
+            task(1, Some(Due::Date(may(3)))),
+            task(2, Some(Due::DateTime(at_nine))),
+            task(3, Some(Due::Date(may(5)))),
+            task(4, None),
+            task(5, Some(Due::Date(may(4)))),
+        ];
+        tasks[4].status = Status::Done;
+
+        assert_eq!(overdue(&tasks, may(5)), 2);
+        assert_eq!(roll_over(&mut tasks, may(5)), 2);
+        assert_eq!(overdue(&tasks, may(5)), 0);
+        assert_eq!(tasks[0].due, Some(Due::Date(may(5))));
+        assert_eq!(tasks[0].rollovers, 1);
+        assert_eq!(
+            tasks[1].due,
+            Some(Due::DateTime(may(5).and_hms_opt(9, 0, 0).unwrap()))
+        );
+        assert_eq!(tasks[2].rollovers, 0);
+        assert_eq!(tasks[4].due, Some(Due::Date(may(4))));
+
+        // Once a day: the second run finds nothing behind
+        assert_eq!(roll_over(&mut tasks, may(5)), 0);
+        assert_eq!(roll_over(&mut tasks, may(6)), 3);
+        assert_eq!(tasks[0].rollovers, 2);
+    }
+
+    #[test]
+    fn test_due_today_order() {
+        let at = |hour| Some(Due::DateTime(may(5).and_hms_opt(hour, 0, 0).unwrap()));
+        let mut tasks = vec![
+            task(1, Some(Due::Date(may(5)))),
+            task(2, at(15)),
+            task(3, at(9)),
+            task(4, Some(Due::Date(may(6)))),
+        ];
+        tasks[2].status = Status::Done;
+
+        let ids: Vec<u32> = due_today(&tasks, may(5)).iter().map(|t| t.id).collect();
+        assert_eq!(ids, [2, 1, 3]);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::task::Task;
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One stretch of work on a task. `end` is `None` while the timer runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkInterval {
+    pub start: NaiveDateTime,
+    pub end: Option<NaiveDateTime>,
+}
+
+impl WorkInterval {
+    /// Time spent inside `[from, now]`; a running interval counts up to `now`.
+    fn overlap(&self, from: NaiveDateTime, now: NaiveDateTime) -> Duration {
+        let start = self.start.max(from);
+        let end = self.end.unwrap_or(now).min(now);
+        (end - start).max(Duration::zero())
+    }
+}
+
+impl Task {
+    pub fn is_running(&self) -> bool {
+        self.intervals.iter().any(|i| i.end.is_none())
+    }
+
+    pub fn start_timer(&mut self, now: NaiveDateTime) {
+        if !self.is_running() {
+            self.intervals.push(WorkInterval {
+                start: now,
+                end: None,
+            });
+        }
+    }
+
+    /// Close any running interval. Returns false if nothing was running.
+    pub fn stop_timer(&mut self, now: NaiveDateTime) -> bool {
+        let mut stopped = false;
+        for interval in self.intervals.iter_mut().filter(|i| i.end.is_none()) {
+            interval.end = Some(now);
+            stopped = true;
+        }
+        stopped
+    }
+
+    /// Total tracked time since `from` (pass `NaiveDateTime::MIN` for all time).
+    pub fn time_spent(&self, from: NaiveDateTime, now: NaiveDateTime) -> Duration {
+        self.intervals
+            .iter()
+            .map(|i| i.overlap(from, now))
+            .fold(Duration::zero(), |a, b| a + b)
+    }
+}
+
+/// Midnight on the Monday of the week containing `now`.
+pub fn start_of_week(now: NaiveDateTime) -> NaiveDateTime {
+    let days_since_monday = now.weekday().num_days_from_monday() as i64;
+    (now.date() - Duration::days(days_since_monday)).and_time(NaiveTime::MIN)
+}
+
+/// Time spent since `from`, grouped by tag. A task with several tags counts
+/// toward each of them; untagged work is grouped under "(untagged)".
+pub fn time_by_tag(
+    tasks: &[Task],
+    from: NaiveDateTime,
+    now: NaiveDateTime,
+) -> Vec<(String, Duration)> {
+    let mut totals: BTreeMap<String, Duration> = BTreeMap::new();
+
+    for task in tasks {
+        let spent = task.time_spent(from, now);
+        if spent.is_zero() {
+            continue;
+        }
+
+        if task.tags.is_empty() {
+            *totals
+                .entry("(untagged)".to_string())
+                .or_insert(Duration::zero()) += spent;
+        }
+        for tag in &task.tags {
+            *totals.entry(tag.clone()).or_insert(Duration::zero()) += spent;
+        }
+    }
+
+    let mut sorted: Vec<(String, Duration)> = totals.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    sorted
+}
+
+/// Format as "2h 05m", or "12m" under an hour.
+pub fn format_duration(d: Duration) -> String {
+    let minutes = d.num_minutes();
+    if minutes < 60 {
+        format!("{minutes}m")
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::Priority;
+    use crate::testing;
+    use chrono::NaiveDate;
+
+    /// Wednesday 2024-05-01 at `h`:`m`
+    fn at(h: u32, m: u32) -> NaiveDateTime {
+        testing::at(1, h, m)
+    }
+
+    fn task_with_tags(tags: &[&str]) -> Task {
+        let mut task = Task::new(1, "Work".to_string(), Priority::Medium);
+        task.tags = tags.iter().map(|t| t.to_string()).collect();
+        task
+    }
+
+    #[test]
+    fn test_start_stop_accumulates() {
+        let mut task = task_with_tags(&[]);
+        task.start_timer(at(9, 0));
+        assert!(task.is_running());
+        assert!(task.stop_timer(at(10, 30)));
+        task.start_timer(at(11, 0));
+        task.stop_timer(at(11, 15));
+
+        assert!(!task.is_running());
+        assert_eq!(
+            task.time_spent(NaiveDateTime::MIN, at(12, 0)),
+            Duration::minutes(105)
+        );
+    }
+
+    #[test]
+    fn test_running_interval_counts_until_now() {
+        let mut task = task_with_tags(&[]);
+        task.start_timer(at(9, 0));
+        task.start_timer(at(9, 30)); // already running: no-op
+        assert_eq!(task.intervals.len(), 1);
+        assert_eq!(
+            task.time_spent(NaiveDateTime::MIN, at(9, 45)),
+            Duration::minutes(45)
+        );
+    }
+
+    #[test]
+    fn test_stop_without_running() {
+        let mut task = task_with_tags(&[]);
+        assert!(!task.stop_timer(at(9, 0)));
+    }
+
+    #[test]
+    fn test_time_by_tag_clips_to_range() {
+        let mut a = task_with_tags(&["work", "rust"]);
+        a.start_timer(at(8, 0));
+        a.stop_timer(at(10, 0));
+        let mut b = task_with_tags(&[]);
+        b.start_timer(at(9, 0));
+        b.stop_timer(at(9, 30));
+
+        let report = time_by_tag(&[a, b], at(9, 0), at(12, 0));
+        assert_eq!(
+            report,
+            vec![
+                ("rust".to_string(), Duration::hours(1)),
+                ("work".to_string(), Duration::hours(1)),
+                ("(untagged)".to_string(), Duration::minutes(30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_start_of_week() {
+        let monday = NaiveDate::from_ymd_opt(2024, 4, 29)
+            .unwrap()
+            .and_time(NaiveTime::MIN);
+        assert_eq!(start_of_week(at(15, 0)), monday);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(12)), "12m");
+        assert_eq!(format_duration(Duration::minutes(125)), "2h 05m");
+    }
+}
@@ -0,0 +1,225 @@
+//! The top words as a bar chart: each word's count drawn as a bar as long
+//! as its share of the most frequent word, with its percentage of all words.
+//! Bars are colored on a terminal unless `NO_COLOR` is set, and fit the
+//! terminal's width.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, IsTerminal};
+use std::process::{Command, Stdio};
+
+/// Width assumed when the terminal's can't be found
+const DEFAULT_WIDTH: usize = 80;
+
+/// Words longer than this many columns are cut short with an ellipsis
+pub const MAX_LABEL: usize = 24;
+
+/// Bars never get shorter or longer than this, whatever the width
+const MIN_BAR: usize = 10;
+const MAX_BAR: usize = 50;
+
+/// Eighths of a block, for the ragged end of a bar
+const PARTIAL: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+const CYAN: &str = "\x1b[36m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// How the chart is drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub color: bool,
+    /// Columns available for each line
+    pub width: usize,
+}
+
+impl Style {
+    /// Color only when writing to a terminal and `NO_COLOR` isn't set; the
+    /// width from `$COLUMNS` or the terminal itself.
+    pub fn detect() -> Style {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        Style {
+            color: io::stdout().is_terminal() && !no_color,
+            width: terminal_width().unwrap_or(DEFAULT_WIDTH),
+        }
+    }
+}
+
+fn terminal_width() -> Option<usize> {
+    if let Some(columns) = env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(columns);
+    }
+    // `stty size` prints "rows columns" for the terminal on its stdin
+    let tty = File::open("/dev/tty").ok()?;
+    let output = Command::new("stty")
+        .arg("size")
+        .stdin(tty)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let size = String::from_utf8(output.stdout).ok()?;
+    size.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Columns `c` takes on a terminal: two for wide characters like CJK and
+/// most emoji, one for everything else.
+fn char_width(c: char) -> usize {
+    let wide = matches!(c as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD);
+    if wide {
+        2
+    } else {
+        1
+    }
+}
+
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// `text` cut to at most `max` columns, ending in "…" if anything was cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    if display_width(text) <= max {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        if width + char_width(c) > max.saturating_sub(1) {
+            break;
+        }
+        width += char_width(c);
+        out.push(c);
+    }
+    out.push('…');
+    out
+}
+
+/// `text` padded with spaces to `width` columns.
+fn pad(text: &str, width: usize) -> String {
+    format!(
+        "{text}{}",
+        " ".repeat(width.saturating_sub(display_width(text)))
+    )
+}
+
+/// A bar `fraction` of `width` long, to the nearest eighth of a column.
+fn bar(fraction: f64, width: usize) -> String {
+    let eighths = (fraction.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+    format!("{}{}", "█".repeat(eighths / 8), PARTIAL[eighths % 8])
+}
+
+/// One line per `(label, count)`, ranked from 1, with each bar scaled to
+/// the largest count and a percentage of `total`.
+pub fn render(rows: &[(String, usize)], total: usize, style: Style) -> Vec<String> {
+    let Some(most) = rows.iter().map(|(_, n)| *n).max().filter(|&n| n > 0) else {
+        return Vec::new();
+    };
+    let labels: Vec<String> = rows
+        .iter()
+        .map(|(label, _)| truncate(label, MAX_LABEL))
+        .collect();
+    let label_width = labels.iter().map(|l| display_width(l)).max().unwrap_or(0);
+    let count_width = most.to_string().len();
+    let rank_width = rows.len().to_string().len().max(2);
+    // "  NN. label  bar  count  (ppp.p%)"
+    let fixed = 2 + rank_width + 2 + label_width + 2 + 2 + count_width + 2 + 8;
+    let bar_width = style.width.saturating_sub(fixed).clamp(MIN_BAR, MAX_BAR);
+
+    let paint = |code: &str, text: String| {
+        if style.color {
+            format!("{code}{text}{RESET}")
+        } else {
+            text
+        }
+    };
+    rows.iter()
+        .zip(&labels)
+        .enumerate()
+        .map(|(rank, ((_, count), label))| {
+            let share = *count as f64 / most as f64;
+            let percent = *count as f64 * 100.0 / total.max(1) as f64;
+            format!(
+                "  {:>rank_width$}. {}  {}  {count:>count_width$}  {}",
+                rank + 1,
+                pad(label, label_width),
+                paint(CYAN, pad(&bar(share, bar_width), bar_width)),
+                paint(DIM, format!("({percent:.1}%)")),
+            )
+            .trim_end()
+            .to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: Style = Style {
+        color: false,
+        width: 60,
+    };
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("internationalization", 10), "internati…");
+        // Wide characters take two columns each
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(truncate("日本語テキスト", 7), "日本語…");
+    }
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(1.0, 4), "████");
+        assert_eq!(bar(0.5, 3), "█▌");
+        assert_eq!(bar(0.0, 4), "");
+    }
+
+    #[test]
+    fn test_render() {
+        let rows = vec![("the".to_string(), 4), ("cat".to_string(), 2)];
+        let lines = render(&rows, 8, PLAIN);
+        // 60 columns less the rank, label, count and percentage leaves 36
+        assert_eq!(
+            lines,
+            [
+                format!("   1. the  {}  4  (50.0%)", "█".repeat(36)),
+                format!("   2. cat  {}  2  (25.0%)", pad(&"█".repeat(18), 36)),
+            ]
+        );
+        let colored = render(
+            &rows,
+            8,
+            Style {
+                color: true,
+                ..PLAIN
+            },
+        );
+        assert!(colored[0].contains("\x1b[36m█"));
+        assert!(render(&[], 0, PLAIN).is_empty());
+    }
+
+    #[test]
+    fn test_render_narrow_terminal() {
+        let rows = vec![("a".repeat(40), 1)];
+        let lines = render(&rows, 1, Style { width: 20, ..PLAIN });
+        // The word is cut short and the bar keeps its minimum length
+        assert!(lines[0].contains(&format!("{}…", "a".repeat(MAX_LABEL - 1))));
+        assert!(lines[0].contains(&"█".repeat(MIN_BAR)));
+    }
+}
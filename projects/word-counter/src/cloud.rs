@@ -0,0 +1,176 @@
+//! `--cloud`: the most frequent words as an SVG word cloud. Font size grows
+//! with the square root of the count, and words are placed greedily along a
+//! spiral out from the centre, biggest first. Nothing is random, so the
+//! same counts always give the same picture.
+
+use std::fmt::Write;
+
+/// How many words go in the cloud
+pub const MAX_WORDS: usize = 60;
+
+const MIN_FONT: f64 = 12.0;
+const MAX_FONT: f64 = 72.0;
+
+/// Average glyph width as a fraction of the font size, for a sans-serif
+/// font. SVG has no way to measure text, so boxes are estimated.
+const CHAR_WIDTH: f64 = 0.6;
+
+/// Space kept around each word and the edge of the picture
+const PADDING: f64 = 4.0;
+
+const COLORS: [&str; 6] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#9467bd", "#ff7f0e", "#17becf",
+];
+
+/// A word and where it landed. `x` and `y` are its centre.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placed {
+    pub word: String,
+    pub count: u64,
+    pub font_size: f64,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Placed {
+    fn overlaps(&self, other: &Placed) -> bool {
+        (self.x - other.x).abs() * 2.0 < self.width + other.width + PADDING
+            && (self.y - other.y).abs() * 2.0 < self.height + other.height + PADDING
+    }
+}
+
+/// Lay out `words`, which should be sorted most frequent first.
+pub fn layout(words: &[(&str, u64)]) -> Vec<Placed> {
+    let Some(&(_, most)) = words.first() else {
+        return Vec::new();
+    };
+    let least = words.last().map_or(most, |&(_, n)| n);
+    let (low, high) = ((least as f64).sqrt(), (most as f64).sqrt());
+
+    let mut placed: Vec<Placed> = Vec::new();
+    for &(word, count) in words {
+        let scale = if high > low {
+            ((count as f64).sqrt() - low) / (high - low)
+        } else {
+            1.0
+        };
+        let font_size = (MIN_FONT + scale * (MAX_FONT - MIN_FONT)).round();
+        let mut candidate = Placed {
+            word: word.to_string(),
+            count,
+            font_size,
+            x: 0.0,
+            y: 0.0,
+            width: word.chars().count() as f64 * font_size * CHAR_WIDTH,
+            height: font_size,
+        };
+
+        // Archimedean spiral, squashed vertically to suit wide words
+        let mut angle: f64 = 0.0;
+        while placed.iter().any(|p| p.overlaps(&candidate)) {
+            angle += 0.1;
+            let radius = 2.0 * angle;
+            candidate.x = (radius * angle.cos()).round();
+            candidate.y = (radius * angle.sin() * 0.6).round();
+        }
+        placed.push(candidate);
+    }
+    placed
+}
+
+/// The cloud as a standalone SVG document sized to fit the words.
+pub fn render(placed: &[Placed]) -> String {
+    let left = placed
+        .iter()
+        .map(|p| p.x - p.width / 2.0)
+        .fold(0.0, f64::min);
+    let right = placed
+        .iter()
+        .map(|p| p.x + p.width / 2.0)
+        .fold(0.0, f64::max);
+    let top = placed
+        .iter()
+        .map(|p| p.y - p.height / 2.0)
+        .fold(0.0, f64::min);
+    let bottom = placed
+        .iter()
+        .map(|p| p.y + p.height / 2.0)
+        .fold(0.0, f64::max);
+    let (width, height) = (right - left + 2.0 * PADDING, bottom - top + 2.0 * PADDING);
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{height:.0}\" \
+         viewBox=\"{:.0} {:.0} {width:.0} {height:.0}\" font-family=\"Helvetica, Arial, sans-serif\">\n",
+        left - PADDING,
+        top - PADDING
+    );
+    for (i, p) in placed.iter().enumerate() {
+        // dominant-baseline centres the text on y, like the layout assumes
+        let _ = writeln!(
+            svg,
+            "  <text x=\"{:.0}\" y=\"{:.0}\" font-size=\"{:.0}\" fill=\"{}\" text-anchor=\"middle\" \
+             dominant-baseline=\"central\"><title>{} ({})</title>{}</text>",
+            p.x,
+            p.y,
+            p.font_size,
+            COLORS[i % COLORS.len()],
+            escape(&p.word),
+            p.count,
+            escape(&p.word)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_font_sizes_follow_sqrt_of_count() {
+        let placed = layout(&[("the", 100), ("cat", 25), ("hat", 1)]);
+        assert_eq!(placed[0].font_size, MAX_FONT);
+        assert_eq!(placed[2].font_size, MIN_FONT);
+        // sqrt(25) is 4/9 of the way from sqrt(1) to sqrt(100)
+        assert_eq!(placed[1].font_size, (12.0f64 + 60.0 * 4.0 / 9.0).round());
+        assert!(layout(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_layout_has_no_overlaps_and_is_deterministic() {
+        let words: Vec<(String, u64)> = (0..40)
+            .map(|i| (format!("word{i}"), 100 - 2 * i as u64))
+            .collect();
+        let words: Vec<(&str, u64)> = words.iter().map(|(w, n)| (w.as_str(), *n)).collect();
+        let placed = layout(&words);
+
+        assert_eq!(placed.len(), 40);
+        assert_eq!((placed[0].x, placed[0].y), (0.0, 0.0));
+        for (i, a) in placed.iter().enumerate() {
+            for b in &placed[i + 1..] {
+                assert!(!a.overlaps(b), "{} overlaps {}", a.word, b.word);
+            }
+        }
+        assert_eq!(placed, layout(&words));
+    }
+
+    #[test]
+    fn test_render_escapes_words() {
+        let svg = render(&layout(&[("rock&roll", 3), ("<b>", 1)]));
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains(">rock&amp;roll</text>"));
+        assert!(svg.contains("<title>&lt;b&gt; (1)</title>"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+}
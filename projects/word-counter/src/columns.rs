@@ -0,0 +1,219 @@
+//! `--column` and `--group-by`: count the words in one column of a CSV or
+//! TSV file, reading a record at a time so exported datasets of any size
+//! work.
+
+use crate::{for_each_word, TokenizerOptions, WordCounts};
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+/// Tab for `.tsv` and `.tab` files, comma otherwise.
+pub fn delimiter(filename: &str) -> u8 {
+    let lower = filename.to_lowercase();
+    if lower.ends_with(".tsv") || lower.ends_with(".tab") {
+        b'\t'
+    } else {
+        b','
+    }
+}
+
+fn reader<R: Read>(input: R, delimiter: u8) -> csv::Reader<R> {
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(input)
+}
+
+/// Where the column called `name` is. An exact match wins, then one that
+/// differs only in case or surrounding spaces.
+fn column_index(headers: &csv::StringRecord, name: &str) -> Result<usize, String> {
+    headers
+        .iter()
+        .position(|h| h == name)
+        .or_else(|| {
+            headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+        })
+        .ok_or_else(|| {
+            let names: Vec<&str> = headers.iter().collect();
+            format!("No column '{name}'; the columns are: {}", names.join(", "))
+        })
+}
+
+fn headers<R: Read>(records: &mut csv::Reader<R>) -> Result<csv::StringRecord, String> {
+    records
+        .headers()
+        .cloned()
+        .map_err(|e| format!("Couldn't read the header row: {e}"))
+}
+
+/// The text of one column, a line per record, as a reader, so everything
+/// that counts a plain file can count a column.
+pub struct ColumnReader<R: Read> {
+    records: csv::Reader<R>,
+    column: usize,
+    record: csv::StringRecord,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> ColumnReader<R> {
+    pub fn new(input: R, delimiter: u8, column: &str) -> Result<Self, String> {
+        let mut records = reader(input, delimiter);
+        let column = column_index(&headers(&mut records)?, column)?;
+        Ok(ColumnReader {
+            records,
+            column,
+            record: csv::StringRecord::new(),
+            buffer: Vec::new(),
+            position: 0,
+        })
+    }
+}
+
+impl<R: Read> Read for ColumnReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.buffer.len() {
+            let more = self
+                .records
+                .read_record(&mut self.record)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if !more {
+                return Ok(0);
+            }
+            self.buffer.clear();
+            self.position = 0;
+            // A short row just has nothing in the column
+            self.buffer
+                .extend_from_slice(self.record.get(self.column).unwrap_or("").as_bytes());
+            self.buffer.push(b'\n');
+        }
+
+        let n = buf.len().min(self.buffer.len() - self.position);
+        buf[..n].copy_from_slice(&self.buffer[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+/// The words of the rows that share one value in the `--group-by` column.
+#[derive(Debug)]
+pub struct Group {
+    pub value: String,
+    pub rows: usize,
+    pub counts: WordCounts,
+}
+
+/// Count the words in `column` separately for each value of `group_by`.
+/// Groups come in numeric order when every value is a number, like
+/// ratings, and alphabetically otherwise.
+pub fn grouped<R: Read>(
+    input: R,
+    delimiter: u8,
+    column: &str,
+    group_by: &str,
+    options: &TokenizerOptions,
+) -> Result<Vec<Group>, String> {
+    let mut records = reader(input, delimiter);
+    let headers = headers(&mut records)?;
+    let column = column_index(&headers, column)?;
+    let group_by = column_index(&headers, group_by)?;
+
+    let mut groups: Vec<Group> = Vec::new();
+    // Each value's place in `groups`
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut record = csv::StringRecord::new();
+    while records
+        .read_record(&mut record)
+        .map_err(|e| e.to_string())?
+    {
+        let value = record.get(group_by).unwrap_or("").trim();
+        let index = match positions.get(value) {
+            Some(&index) => index,
+            None => {
+                groups.push(Group {
+                    value: value.to_string(),
+                    rows: 0,
+                    counts: WordCounts::new(),
+                });
+                positions.insert(value.to_string(), groups.len() - 1);
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        group.rows += 1;
+        let text = record.get(column).unwrap_or("");
+        for_each_word(text, options, &mut |word| group.counts.add(word));
+    }
+
+    let numbers: Option<Vec<f64>> = groups.iter().map(|g| g.value.parse().ok()).collect();
+    match numbers {
+        Some(numbers) => {
+            let mut paired: Vec<(f64, Group)> = numbers.into_iter().zip(groups).collect();
+            paired.sort_by(|a, b| a.0.total_cmp(&b.0));
+            Ok(paired.into_iter().map(|(_, g)| g).collect())
+        }
+        None => {
+            groups.sort_by(|a, b| a.value.cmp(&b.value));
+            Ok(groups)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REVIEWS: &str = "id,rating,comment_text\n\
+                           1,5,\"Great app, love it\"\n\
+                           2,10,Crashes on start\n\
+                           3,5,Love the dark mode\n\
+                           4,1\n";
+
+    #[test]
+    fn test_column_reader_streams_one_column() {
+        let mut reader = ColumnReader::new(REVIEWS.as_bytes(), b',', "Comment_Text").unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(
+            text,
+            "Great app, love it\nCrashes on start\nLove the dark mode\n\n"
+        );
+
+        let tsv = "name\tnotes\nx\tsome notes here\n";
+        let mut reader = ColumnReader::new(tsv.as_bytes(), delimiter("a.TSV"), "notes").unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "some notes here\n");
+    }
+
+    #[test]
+    fn test_missing_column_lists_the_columns() {
+        let err = ColumnReader::new(REVIEWS.as_bytes(), b',', "review")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err,
+            "No column 'review'; the columns are: id, rating, comment_text"
+        );
+    }
+
+    #[test]
+    fn test_grouped_counts() {
+        let groups = grouped(
+            REVIEWS.as_bytes(),
+            b',',
+            "comment_text",
+            "rating",
+            &TokenizerOptions::default(),
+        )
+        .unwrap();
+        let summary: Vec<(&str, usize, usize)> = groups
+            .iter()
+            .map(|g| (g.value.as_str(), g.rows, g.counts.total()))
+            .collect();
+        // 10 after 5: numeric, not alphabetical
+        assert_eq!(summary, [("1", 1, 0), ("5", 2, 8), ("10", 1, 3)]);
+        assert_eq!(groups[1].counts.get("love"), Some(&2));
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::{HashMap, VecDeque};
+
+pub const DEFAULT_WINDOW: usize = 5;
+
+/// Counts how often each word appears within `window` tokens of a target word.
+///
+/// This is one row of a full co-occurrence matrix. Keeping only the row we
+/// were asked about means memory grows with the vocabulary near the target,
+/// not with the square of the whole vocabulary.
+pub struct Cooccurrence {
+    target: String,
+    window: usize,
+    recent: VecDeque<String>,
+    counts: HashMap<String, usize>,
+}
+
+impl Cooccurrence {
+    pub fn new(target: String, window: usize) -> Self {
+        Cooccurrence {
+            target,
+            window,
+            recent: VecDeque::with_capacity(window + 1),
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Feed the next token of the stream.
+    pub fn push(&mut self, word: &str) {
+        let is_target = word == self.target;
+
+        for prev in &self.recent {
+            if *prev == self.target && !is_target {
+                *self.counts.entry(word.to_string()).or_insert(0) += 1;
+            } else if is_target && *prev != self.target {
+                *self.counts.entry(prev.clone()).or_insert(0) += 1;
+            }
+        }
+
+        self.recent.push_back(word.to_string());
+        if self.recent.len() > self.window {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Neighbors sorted by count (descending), then alphabetically.
+    pub fn top(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut sorted: Vec<(&str, usize)> =
+            self.counts.iter().map(|(w, c)| (w.as_str(), *c)).collect();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        sorted.truncate(n);
+        sorted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near(text: &str, target: &str, window: usize) -> Cooccurrence {
+        let mut c = Cooccurrence::new(target.to_string(), window);
+        for word in text.split_whitespace() {
+            c.push(word);
+        }
+        c
+    }
+
+    #[test]
+    fn test_counts_both_sides_of_target() {
+        let c = near("a b x c d", "x", 1);
+        assert_eq!(c.top(10), vec![("b", 1), ("c", 1)]);
+    }
+
+    #[test]
+    fn test_window_limits_distance() {
+        let c = near("far a b x", "x", 2);
+        assert_eq!(c.top(10), vec![("a", 1), ("b", 1)]);
+    }
+
+    #[test]
+    fn test_repeated_target_accumulates() {
+        let c = near("x rust x rust", "x", 1);
+        assert_eq!(c.top(10), vec![("rust", 3)]);
+    }
+
+    #[test]
+    fn test_target_not_counted_as_own_neighbor() {
+        let c = near("x x x", "x", 2);
+        assert!(c.top(10).is_empty());
+    }
+}
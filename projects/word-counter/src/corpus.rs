@@ -0,0 +1,298 @@
+use crate::cooccurrence::DEFAULT_WINDOW;
+use crate::progress::Progress;
+use crate::{stream_words, TokenizerOptions};
+use std::collections::HashMap;
+use std::io::{self, Read};
+
+/// An in-memory index over many documents, built once so repeated queries
+/// don't re-read the files.
+///
+/// Each distinct word gets a numeric ID, each document is kept as its
+/// sequence of IDs, and each word has a posting list of the positions where
+/// it occurs in each document. Counting a word or finding the files that
+/// contain it only touches that word's postings; `near` only visits the
+/// positions of the target word.
+#[derive(Debug, Default)]
+pub struct Corpus {
+    words: Vec<String>,
+    ids: HashMap<String, u32>,
+    documents: Vec<Document>,
+    /// Indexed by word ID
+    postings: Vec<Vec<Posting>>,
+    total: usize,
+}
+
+#[derive(Debug)]
+struct Document {
+    name: String,
+    tokens: Vec<u32>,
+}
+
+#[derive(Debug)]
+struct Posting {
+    document: usize,
+    positions: Vec<u32>,
+}
+
+/// One line of input to `word-counter repl`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    Count(String),
+    Top(usize),
+    Near { word: String, window: usize },
+    FilesContaining(String),
+    Help,
+}
+
+pub const QUERY_USAGE: &str = "Queries: count <word>, top [n], near <word> [window], \
+                           files containing <word>, help, quit";
+
+impl std::str::FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let word = |w: &str| {
+            TokenizerOptions::default()
+                .normalize(w)
+                .ok_or(format!("Not a word: '{w}'"))
+        };
+        let number = |n: &str| {
+            n.parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .ok_or(format!("Not a positive number: '{n}'"))
+        };
+
+        match words.as_slice() {
+            ["count", w] => Ok(Query::Count(word(w)?)),
+            ["top"] => Ok(Query::Top(10)),
+            ["top", n] => Ok(Query::Top(number(n)?)),
+            ["near", w] => Ok(Query::Near {
+                word: word(w)?,
+                window: DEFAULT_WINDOW,
+            }),
+            ["near", w, n] => Ok(Query::Near {
+                word: word(w)?,
+                window: number(n)?,
+            }),
+            ["files", "containing", w] => Ok(Query::FilesContaining(word(w)?)),
+            ["help"] => Ok(Query::Help),
+            _ => Err(QUERY_USAGE.to_string()),
+        }
+    }
+}
+
+impl Corpus {
+    pub fn new() -> Self {
+        Corpus::default()
+    }
+
+    /// Tokenize `reader` and index it under `name`.
+    pub fn add_document<R: Read>(
+        &mut self,
+        name: &str,
+        reader: R,
+        options: &TokenizerOptions,
+        progress: &mut Progress,
+    ) -> io::Result<()> {
+        // Only intern once the whole document has read cleanly, so a file
+        // that fails halfway leaves nothing behind
+        let mut words = Vec::new();
+        stream_words(reader, options, progress, |word| words.push(word))?;
+        let tokens = words.into_iter().map(|w| self.intern(w)).collect();
+        self.index(name, tokens);
+        Ok(())
+    }
+
+    fn intern(&mut self, word: String) -> u32 {
+        if let Some(&id) = self.ids.get(&word) {
+            return id;
+        }
+        let id = self.words.len() as u32;
+        self.ids.insert(word.clone(), id);
+        self.words.push(word);
+        self.postings.push(Vec::new());
+        id
+    }
+
+    fn index(&mut self, name: &str, tokens: Vec<u32>) {
+        let document = self.documents.len();
+        for (position, &id) in tokens.iter().enumerate() {
+            let postings = &mut self.postings[id as usize];
+            match postings.last_mut() {
+                Some(p) if p.document == document => p.positions.push(position as u32),
+                _ => postings.push(Posting {
+                    document,
+                    positions: vec![position as u32],
+                }),
+            }
+        }
+        self.total += tokens.len();
+        self.documents.push(Document {
+            name: name.to_string(),
+            tokens,
+        });
+    }
+
+    pub fn document_count(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Number of distinct words
+    pub fn unique(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Number of words indexed, including repeats
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    fn postings(&self, word: &str) -> &[Posting] {
+        self.ids
+            .get(word)
+            .map(|&id| self.postings[id as usize].as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn count(&self, word: &str) -> usize {
+        self.postings(word).iter().map(|p| p.positions.len()).sum()
+    }
+
+    /// The `n` most frequent words, ties broken alphabetically.
+    pub fn top(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut sorted: Vec<(&str, usize)> = self
+            .words
+            .iter()
+            .zip(&self.postings)
+            .map(|(w, p)| (w.as_str(), p.iter().map(|p| p.positions.len()).sum()))
+            .collect();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        sorted.truncate(n);
+        sorted
+    }
+
+    /// Words within `window` tokens of `word`, most frequent first. Counts
+    /// the same way as `Cooccurrence`, but never across document boundaries.
+    pub fn near(&self, word: &str, window: usize, n: usize) -> Vec<(&str, usize)> {
+        let Some(&target) = self.ids.get(word) else {
+            return Vec::new();
+        };
+
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for posting in self.postings(word) {
+            let tokens = &self.documents[posting.document].tokens;
+            for &position in &posting.positions {
+                let position = position as usize;
+                let from = position.saturating_sub(window);
+                let to = (position + window + 1).min(tokens.len());
+                for &id in &tokens[from..to] {
+                    if id != target {
+                        *counts.entry(id).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+
+        let mut sorted: Vec<(&str, usize)> = counts
+            .into_iter()
+            .map(|(id, c)| (self.words[id as usize].as_str(), c))
+            .collect();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        sorted.truncate(n);
+        sorted
+    }
+
+    /// Documents that contain `word`, with how often, most first.
+    pub fn files_containing(&self, word: &str) -> Vec<(&str, usize)> {
+        let mut files: Vec<(&str, usize)> = self
+            .postings(word)
+            .iter()
+            .map(|p| (self.documents[p.document].name.as_str(), p.positions.len()))
+            .collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cooccurrence::Cooccurrence;
+
+    fn corpus(documents: &[(&str, &str)]) -> Corpus {
+        let mut corpus = Corpus::new();
+        let options = TokenizerOptions::default();
+        for (name, text) in documents {
+            let mut progress = Progress::new(text.len() as u64);
+            corpus
+                .add_document(name, text.as_bytes(), &options, &mut progress)
+                .unwrap();
+        }
+        corpus
+    }
+
+    #[test]
+    fn test_counts_across_documents() {
+        let c = corpus(&[
+            ("a.txt", "Rust is fast. rust!"),
+            ("b.txt", "rust and tokio"),
+        ]);
+        assert_eq!(c.document_count(), 2);
+        assert_eq!(c.total(), 7);
+        assert_eq!(c.count("rust"), 3);
+        assert_eq!(c.count("python"), 0);
+        assert_eq!(c.top(2), vec![("rust", 3), ("and", 1)]);
+    }
+
+    #[test]
+    fn test_files_containing() {
+        let c = corpus(&[
+            ("a.txt", "tokio"),
+            ("b.txt", "nothing here"),
+            ("c.txt", "tokio tokio"),
+        ]);
+        assert_eq!(
+            c.files_containing("tokio"),
+            vec![("c.txt", 2), ("a.txt", 1)]
+        );
+        assert!(c.files_containing("missing").is_empty());
+    }
+
+    #[test]
+    fn test_near_matches_cooccurrence() {
+        let text = "x a b x c x d e f x";
+        let mut expected = Cooccurrence::new("x".to_string(), 2);
+        text.split_whitespace().for_each(|w| expected.push(w));
+
+        let c = corpus(&[("a.txt", text)]);
+        assert_eq!(c.near("x", 2, 10), expected.top(10));
+    }
+
+    #[test]
+    fn test_near_stops_at_document_boundaries() {
+        let c = corpus(&[("a.txt", "before"), ("b.txt", "error after")]);
+        assert_eq!(c.near("error", 5, 10), vec![("after", 1)]);
+    }
+
+    #[test]
+    fn test_parse_queries() {
+        assert_eq!("count Rust".parse(), Ok(Query::Count("rust".to_string())));
+        assert_eq!("top".parse(), Ok(Query::Top(10)));
+        assert_eq!("top 20".parse(), Ok(Query::Top(20)));
+        assert_eq!(
+            "near error 3".parse(),
+            Ok(Query::Near {
+                word: "error".to_string(),
+                window: 3
+            })
+        );
+        assert_eq!(
+            "files containing tokio".parse(),
+            Ok(Query::FilesContaining("tokio".to_string()))
+        );
+        assert!("top zero".parse::<Query>().is_err());
+        assert!("delete everything".parse::<Query>().is_err());
+    }
+}
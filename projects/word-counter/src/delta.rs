@@ -0,0 +1,94 @@
+//! `word-counter delta old.md new.md`: how an edit pass changed a text's
+//! words, by comparing the counts of two versions.
+
+use crate::WordCounts;
+
+/// The difference between the word counts of two versions of a text.
+#[derive(Debug, PartialEq)]
+pub struct Delta {
+    pub old_total: usize,
+    pub new_total: usize,
+    /// Occurrences the new version has beyond the old, summed over words
+    pub added: usize,
+    /// Occurrences the old version has beyond the new, summed over words
+    pub removed: usize,
+    /// Words only the new version uses, most frequent first
+    pub introduced: Vec<(String, usize)>,
+    /// Words only the old version uses, most frequent first
+    pub dropped: Vec<(String, usize)>,
+}
+
+impl Delta {
+    /// Words gained overall; negative when the text got shorter.
+    pub fn net(&self) -> i64 {
+        self.new_total as i64 - self.old_total as i64
+    }
+}
+
+/// The words in `from` that `to` doesn't have, ties broken alphabetically.
+fn missing(from: &WordCounts, to: &WordCounts) -> Vec<(String, usize)> {
+    let mut words: Vec<(String, usize)> = from
+        .iter()
+        .filter(|(word, _)| to.get(word).is_none())
+        .map(|(word, n)| (word.to_string(), n))
+        .collect();
+    words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    words
+}
+
+pub fn delta(old: &WordCounts, new: &WordCounts) -> Delta {
+    let count = |counts: &WordCounts, word: &str| counts.get(word).copied().unwrap_or(0);
+    let added = new
+        .iter()
+        .map(|(word, n)| n.saturating_sub(count(old, word)))
+        .sum();
+    let removed = old
+        .iter()
+        .map(|(word, n)| n.saturating_sub(count(new, word)))
+        .sum();
+    Delta {
+        old_total: old.total(),
+        new_total: new.total(),
+        added,
+        removed,
+        introduced: missing(new, old),
+        dropped: missing(old, new),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_words;
+
+    #[test]
+    fn test_delta_counts_changes() {
+        let old = count_words("the cat sat on the mat very very quietly");
+        let new = count_words("the cat sat on the rug quite quietly the end");
+        let delta = delta(&old, &new);
+        assert_eq!((delta.old_total, delta.new_total), (9, 10));
+        // the +1, rug, quite, end; mat, very ×2
+        assert_eq!((delta.added, delta.removed), (4, 3));
+        assert_eq!(delta.net(), 1);
+        assert_eq!(
+            delta.introduced,
+            [
+                ("end".to_string(), 1),
+                ("quite".to_string(), 1),
+                ("rug".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            delta.dropped,
+            [("very".to_string(), 2), ("mat".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_delta_of_identical_texts_is_empty() {
+        let text = count_words("nothing changed here");
+        let delta = delta(&text, &text);
+        assert_eq!((delta.added, delta.removed, delta.net()), (0, 0, 0));
+        assert!(delta.introduced.is_empty() && delta.dropped.is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+//! `--dialogue`: word counts per speaker in a chat log, interview or script,
+//! where each turn starts with the speaker's name.
+//!
+//! ```text
+//! Alice: Have you tried Rust?
+//! Bob: Not yet.
+//!   Is it hard?        <- no prefix: still Bob's turn
+//! ```
+
+use crate::{for_each_word, TokenizerOptions, WordCounts};
+use regex::Regex;
+
+/// Longest name the default `Name:` detection accepts
+const MAX_NAME: usize = 32;
+
+/// Most words in a name the default detection accepts
+const MAX_NAME_WORDS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Speaker {
+    pub name: String,
+    /// Lines that started with this speaker's prefix
+    pub turns: usize,
+    pub counts: WordCounts,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Dialogue {
+    /// In order of first appearance
+    pub speakers: Vec<Speaker>,
+    /// Words before the first speaker prefix, such as a title
+    pub unattributed: usize,
+}
+
+impl Dialogue {
+    /// Words spoken by anyone
+    pub fn total(&self) -> usize {
+        self.speakers.iter().map(|s| s.counts.total()).sum()
+    }
+
+    /// Fraction of all spoken words that are `speaker`'s
+    pub fn share(&self, speaker: &Speaker) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => speaker.counts.total() as f64 / total as f64,
+        }
+    }
+
+    /// Speakers with the most words first
+    pub fn by_words(&self) -> Vec<&Speaker> {
+        let mut speakers: Vec<&Speaker> = self.speakers.iter().collect();
+        speakers.sort_by(|a, b| {
+            b.counts
+                .total()
+                .cmp(&a.counts.total())
+                .then(a.name.cmp(&b.name))
+        });
+        speakers
+    }
+}
+
+/// The default speaker prefix: a short name of up to three words followed by
+/// a colon and a space, like `Alice:` or `Dr. Jones:`. Names start with a
+/// letter, so times like `10:30` and URLs aren't taken for speakers.
+/// Returns the name and the rest of the line.
+pub fn name_prefix(line: &str) -> Option<(&str, &str)> {
+    let (name, rest) = line.split_once(':')?;
+    let name = name.trim();
+    let valid = name.chars().next()?.is_alphabetic()
+        && name.chars().count() <= MAX_NAME
+        && name.split_whitespace().count() <= MAX_NAME_WORDS
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '.' | '-' | '\'' | '_'));
+    let spaced = rest.is_empty() || rest.starts_with(char::is_whitespace);
+    (valid && spaced).then_some((name, rest))
+}
+
+/// Compile a `--speaker-regex`. It only matches at the start of a line.
+pub fn speaker_pattern(pattern: &str) -> Result<Regex, String> {
+    // Checked alone first, so an error shows the pattern as it was given
+    Regex::new(pattern).map_err(|e| e.to_string())?;
+    Regex::new(&format!("^(?:{pattern})")).map_err(|e| e.to_string())
+}
+
+/// The speaker prefix `pattern` finds: its first group if it has one,
+/// otherwise the whole match.
+fn pattern_prefix<'a>(pattern: &Regex, line: &'a str) -> Option<(&'a str, &'a str)> {
+    let captures = pattern.captures(line)?;
+    let found = captures.get(0)?;
+    let name = captures.get(1).unwrap_or(found).as_str().trim();
+    (!name.is_empty()).then_some((name, &line[found.end()..]))
+}
+
+/// Split `text` into turns and count each speaker's words. Lines without a
+/// prefix carry on the current turn. Speakers are matched by exact name.
+pub fn dialogue(text: &str, pattern: Option<&Regex>, options: &TokenizerOptions) -> Dialogue {
+    let mut result = Dialogue::default();
+    let mut current: Option<usize> = None;
+
+    for line in text.lines() {
+        let prefix = match pattern {
+            Some(pattern) => pattern_prefix(pattern, line),
+            None => name_prefix(line.trim_start()),
+        };
+        let speech = match prefix {
+            Some((name, rest)) => {
+                let index = match result.speakers.iter().position(|s| s.name == name) {
+                    Some(index) => index,
+                    None => {
+                        result.speakers.push(Speaker {
+                            name: name.to_string(),
+                            turns: 0,
+                            counts: WordCounts::new(),
+                        });
+                        result.speakers.len() - 1
+                    }
+                };
+                result.speakers[index].turns += 1;
+                current = Some(index);
+                rest
+            }
+            None => line,
+        };
+
+        match current {
+            Some(index) => {
+                let counts = &mut result.speakers[index].counts;
+                for_each_word(speech, options, &mut |word| counts.add(word));
+            }
+            None => for_each_word(speech, options, &mut |_| result.unattributed += 1),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(dialogue: &Dialogue) -> Vec<(&str, usize, usize)> {
+        dialogue
+            .speakers
+            .iter()
+            .map(|s| (s.name.as_str(), s.turns, s.counts.total()))
+            .collect()
+    }
+
+    #[test]
+    fn test_name_prefix() {
+        assert_eq!(name_prefix("Alice: hi"), Some(("Alice", " hi")));
+        assert_eq!(name_prefix("Dr. Jones: yes"), Some(("Dr. Jones", " yes")));
+        assert_eq!(name_prefix("BOB:"), Some(("BOB", "")));
+        assert_eq!(name_prefix("10:30 start"), None);
+        assert_eq!(name_prefix("see https://example.com"), None);
+        assert_eq!(name_prefix("The reason is simple: we ran out"), None);
+        assert_eq!(name_prefix("no colon here"), None);
+    }
+
+    #[test]
+    fn test_dialogue_turns_and_continuations() {
+        let text = "Interview transcript\n\
+                    Alice: Have you tried Rust?\n\
+                    Bob: Not yet.\n  Is it hard to learn?\n\
+                    Alice: The borrow checker takes a while.\n";
+        let result = dialogue(text, None, &TokenizerOptions::default());
+
+        assert_eq!(words(&result), [("Alice", 2, 10), ("Bob", 1, 7)]);
+        assert_eq!(result.unattributed, 2);
+        assert_eq!(result.total(), 17);
+        assert_eq!(result.speakers[1].counts.get("hard"), Some(&1));
+        assert!((result.share(&result.speakers[0]) - 10.0 / 17.0).abs() < 1e-9);
+        assert_eq!(result.by_words()[0].name, "Alice");
+    }
+
+    #[test]
+    fn test_speaker_pattern() {
+        let prefix = |pattern: &str, line| pattern_prefix(&speaker_pattern(pattern).unwrap(), line);
+        assert_eq!(
+            prefix(r"<([^>]+)> ?", "<Bob Smith> hello"),
+            Some(("Bob Smith", "hello"))
+        );
+        // Without a group the whole match is the name
+        assert_eq!(
+            prefix(r"[A-Z]+:", "JULIET: O Romeo"),
+            Some(("JULIET:", " O Romeo"))
+        );
+        // Only at the start of a line, even with alternatives
+        assert_eq!(prefix(r"\[(\w+)\]|(\w+):", "no prefix [alice]"), None);
+        assert!(speaker_pattern("[abc").is_err());
+    }
+
+    #[test]
+    fn test_dialogue_with_pattern() {
+        let pattern = speaker_pattern(r"\[\d\d:\d\d\] <([^>]+)>").unwrap();
+        let text = "[09:00] <ferris> good morning all\n\
+                    [09:01] <crab> morning\n\
+                    [09:02] <ferris> standup in five\n";
+        let result = dialogue(text, Some(&pattern), &TokenizerOptions::default());
+        assert_eq!(words(&result), [("ferris", 2, 6), ("crab", 1, 1)]);
+        // Without the pattern, nothing looks like `Name:`
+        let plain = dialogue(text, None, &TokenizerOptions::default());
+        assert!(plain.speakers.is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+//! `--rare`: words used only once and words missing from a dictionary,
+//! which in a long document are often typos.
+
+use crate::{TokenizerOptions, WordCounts};
+use std::collections::HashSet;
+use std::io::{self, BufRead};
+
+/// Known words, normalized the way counted words are.
+#[derive(Debug, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+impl Dictionary {
+    /// Read a word list with one word per line, like /usr/share/dict/words.
+    /// Hunspell `.dic` files work too: the word count on the first line and
+    /// the `/FLAGS` after each word are skipped. Lines starting with `#` are
+    /// comments.
+    pub fn load(reader: impl BufRead, options: &TokenizerOptions) -> io::Result<Dictionary> {
+        let mut words = HashSet::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let entry = line.split('/').next().unwrap_or_default().trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            if i == 0 && entry.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            if let Some(word) = options.normalize(entry) {
+                words.insert(word);
+            }
+        }
+        Ok(Dictionary { words })
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+/// What `--rare` reports.
+#[derive(Debug, PartialEq)]
+pub struct RareWords<'a> {
+    /// Words used exactly once, alphabetically
+    pub hapax: Vec<&'a str>,
+    /// Words not in the dictionary, least used first; empty without one
+    pub unknown: Vec<(&'a str, usize)>,
+}
+
+/// Numbers aren't words a dictionary would list.
+fn is_number(word: &str) -> bool {
+    word.chars().all(|c| c.is_ascii_digit())
+}
+
+pub fn rare_words<'a>(counts: &'a WordCounts, dictionary: Option<&Dictionary>) -> RareWords<'a> {
+    let mut hapax: Vec<&str> = counts
+        .iter()
+        .filter(|(_, count)| *count == 1)
+        .map(|(word, _)| word)
+        .collect();
+    hapax.sort();
+
+    let mut unknown: Vec<(&str, usize)> = match dictionary {
+        Some(dictionary) => counts
+            .iter()
+            .filter(|(word, _)| !is_number(word) && !dictionary.contains(word))
+            .collect(),
+        None => Vec::new(),
+    };
+    unknown.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(b.0)));
+
+    RareWords { hapax, unknown }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_words;
+
+    fn dictionary(text: &str) -> Dictionary {
+        Dictionary::load(text.as_bytes(), &TokenizerOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_load_plain_and_hunspell() {
+        let plain = dictionary("The\ncat\n\n# comment\nsat\n");
+        assert_eq!(plain.len(), 3);
+        assert!(plain.contains("the"));
+        assert!(!plain.contains("comment"));
+
+        let hunspell = dictionary("3\nwalk/DSG\ncat/S\nmat\n");
+        assert_eq!(hunspell.len(), 3);
+        assert!(hunspell.contains("walk"));
+        assert!(!hunspell.contains("3"));
+    }
+
+    #[test]
+    fn test_hapax() {
+        let counts = count_words("the cat sat on the mat");
+        let rare = rare_words(&counts, None);
+        assert_eq!(rare.hapax, ["cat", "mat", "on", "sat"]);
+        assert!(rare.unknown.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_words() {
+        let counts = count_words("Teh cat sat on teh mat in 2024, the cat");
+        let dict = dictionary("the\ncat\nsat\non\nmat\nin\n");
+        let rare = rare_words(&counts, Some(&dict));
+        assert_eq!(rare.unknown, [("teh", 2)]);
+
+        let rare = rare_words(&counts, Some(&dictionary("cat\n")));
+        assert_eq!(rare.unknown[0], ("in", 1));
+        assert_eq!(rare.unknown.last(), Some(&("teh", 2)));
+    }
+}
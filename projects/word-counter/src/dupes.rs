@@ -0,0 +1,198 @@
+//! Repeated lines and near-duplicate paragraphs, for spotting copy-paste
+//! leftovers in long documents.
+
+use crate::TokenizerOptions;
+use std::collections::{HashMap, HashSet};
+
+/// Paragraphs at least this similar are reported
+pub const SIMILARITY_THRESHOLD: f64 = 0.9;
+
+/// Words per shingle when comparing paragraphs
+const SHINGLE_SIZE: usize = 3;
+
+/// A line that appears more than once, with every 1-based line number.
+#[derive(Debug, PartialEq)]
+pub struct RepeatedLine {
+    pub text: String,
+    pub line_numbers: Vec<usize>,
+}
+
+/// Two paragraphs, identified by their first line, and how similar they are.
+#[derive(Debug, PartialEq)]
+pub struct SimilarParagraphs {
+    pub first: Paragraph,
+    pub second: Paragraph,
+    /// Jaccard similarity of their word shingles, 0.0 to 1.0
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paragraph {
+    pub line: usize,
+    pub text: String,
+}
+
+/// Lines that occur more than once, ignoring blank lines and differences in
+/// surrounding or repeated whitespace. Ordered by first occurrence.
+pub fn repeated_lines(text: &str) -> Vec<RepeatedLine> {
+    let mut seen: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut order = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let normalized = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if normalized.is_empty() {
+            continue;
+        }
+        let numbers = seen.entry(normalized.clone()).or_default();
+        if numbers.is_empty() {
+            order.push(normalized);
+        }
+        numbers.push(i + 1);
+    }
+
+    order
+        .into_iter()
+        .filter_map(|text| {
+            let line_numbers = seen.remove(&text)?;
+            (line_numbers.len() > 1).then_some(RepeatedLine { text, line_numbers })
+        })
+        .collect()
+}
+
+/// Split on blank lines, remembering where each paragraph starts.
+pub fn paragraphs(text: &str) -> Vec<Paragraph> {
+    let mut paragraphs = Vec::new();
+    let mut current: Option<Paragraph> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            paragraphs.extend(current.take());
+            continue;
+        }
+        match current.as_mut() {
+            Some(p) => {
+                p.text.push('\n');
+                p.text.push_str(line);
+            }
+            None => {
+                current = Some(Paragraph {
+                    line: i + 1,
+                    text: line.to_string(),
+                })
+            }
+        }
+    }
+
+    paragraphs.extend(current);
+    paragraphs
+}
+
+/// Overlapping runs of `SHINGLE_SIZE` normalized words. Paragraphs shorter
+/// than that become a single shingle.
+fn shingles(text: &str) -> HashSet<Vec<String>> {
+    let options = TokenizerOptions::default();
+    let words: Vec<String> = text
+        .split_whitespace()
+        .filter_map(|w| options.normalize(w))
+        .collect();
+
+    if words.len() < SHINGLE_SIZE {
+        return HashSet::from([words]);
+    }
+    words.windows(SHINGLE_SIZE).map(|w| w.to_vec()).collect()
+}
+
+fn jaccard<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// Every pair of paragraphs at least `threshold` similar, most similar first.
+/// Compares all pairs, which is fine for documents of a few thousand
+/// paragraphs.
+pub fn similar_paragraphs(text: &str, threshold: f64) -> Vec<SimilarParagraphs> {
+    let paragraphs = paragraphs(text);
+    let shingled: Vec<HashSet<Vec<String>>> =
+        paragraphs.iter().map(|p| shingles(&p.text)).collect();
+
+    let mut pairs = Vec::new();
+    for i in 0..paragraphs.len() {
+        for j in i + 1..paragraphs.len() {
+            let similarity = jaccard(&shingled[i], &shingled[j]);
+            if similarity >= threshold {
+                pairs.push(SimilarParagraphs {
+                    first: paragraphs[i].clone(),
+                    second: paragraphs[j].clone(),
+                    similarity,
+                });
+            }
+        }
+    }
+
+    pairs.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then(a.first.line.cmp(&b.first.line))
+    });
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_lines() {
+        let text = "alpha\nbeta\n\n  alpha  \ngamma\n\nbeta\nalpha\n";
+        assert_eq!(
+            repeated_lines(text),
+            vec![
+                RepeatedLine {
+                    text: "alpha".to_string(),
+                    line_numbers: vec![1, 4, 8],
+                },
+                RepeatedLine {
+                    text: "beta".to_string(),
+                    line_numbers: vec![2, 7],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paragraph_line_numbers() {
+        let lines: Vec<usize> = paragraphs("\none\ntwo\n\n\nthree\n")
+            .iter()
+            .map(|p| p.line)
+            .collect();
+        assert_eq!(lines, vec![2, 6]);
+    }
+
+    #[test]
+    fn test_near_duplicate_paragraphs() {
+        let original = "The quick brown fox jumps over the lazy dog while the farmer \
+                        watches from the porch and the cat sleeps in the warm afternoon sun \
+                        beside an old red barn near the quiet river bend";
+        let edited = original.replace("sun", "sun,").to_uppercase();
+        let different = "Completely unrelated text about compilers and borrow checking.";
+        let text = format!("{original}\n\n{different}\n\n{edited}\n");
+
+        let pairs = similar_paragraphs(&text, SIMILARITY_THRESHOLD);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].first.line, pairs[0].second.line), (1, 5));
+        assert_eq!(pairs[0].similarity, 1.0);
+    }
+
+    #[test]
+    fn test_small_edit_drops_below_threshold() {
+        let a = "one two three four five six";
+        let b = "one two three four five seven";
+        // 4 shingles each, 3 shared: 3/5
+        let text = format!("{a}\n\n{b}");
+        assert!(similar_paragraphs(&text, SIMILARITY_THRESHOLD).is_empty());
+        assert_eq!(similar_paragraphs(&text, 0.5)[0].similarity, 0.6);
+    }
+}
@@ -0,0 +1,188 @@
+//! Reading files that aren't UTF-8. The encoding comes from a byte order
+//! mark if there is one, otherwise from the first few kilobytes, or from
+//! `--encoding`; the text is converted to UTF-8 as it streams.
+
+use encoding_rs::{CoderResult, Decoder, Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use std::io::{self, Read};
+
+/// How much of the input is looked at to guess its encoding
+const SNIFF_SIZE: usize = 8 * 1024;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The encoding `--encoding` names, by the labels browsers accept:
+/// `utf-8`, `utf-16le`, `latin1`, `windows-1252`, `shift_jis`, ...
+pub fn parse(label: &str) -> Result<&'static Encoding, String> {
+    Encoding::for_label(label.trim().as_bytes()).ok_or(format!(
+        "Unknown encoding '{label}'; try utf-8, utf-16le, utf-16be or latin1"
+    ))
+}
+
+/// Guess the encoding of text starting with `head`, with no byte order
+/// mark. UTF-16 shows up as a zero byte beside most ASCII characters;
+/// anything else that isn't valid UTF-8 is taken as Windows-1252, which
+/// covers Latin-1.
+pub fn detect(head: &[u8]) -> &'static Encoding {
+    let pairs = head.len() / 2;
+    if pairs > 0 {
+        let zeros_at = |offset: usize| {
+            head.iter()
+                .skip(offset)
+                .step_by(2)
+                .filter(|b| **b == 0)
+                .count()
+        };
+        if zeros_at(1) * 2 > pairs {
+            return UTF_16LE;
+        }
+        if zeros_at(0) * 2 > pairs {
+            return UTF_16BE;
+        }
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => UTF_8,
+        // Cut off mid-character at the end of the sample
+        Err(e) if e.error_len().is_none() => UTF_8,
+        Err(_) => WINDOWS_1252,
+    }
+}
+
+/// Streams any input as UTF-8. Bytes that aren't valid in the encoding
+/// become U+FFFD rather than an error.
+pub struct DecodeReader<R: Read> {
+    input: R,
+    encoding: &'static Encoding,
+    decoder: Decoder,
+    /// Read but not yet decoded
+    pending: Vec<u8>,
+    position: usize,
+    decoded: Vec<u8>,
+    decoded_position: usize,
+    eof: bool,
+    done: bool,
+}
+
+impl<R: Read> DecodeReader<R> {
+    /// Decode `input` as `encoding`, or as whatever its start suggests when
+    /// that's `None`.
+    pub fn new(mut input: R, encoding: Option<&'static Encoding>) -> io::Result<Self> {
+        let mut head = Vec::with_capacity(SNIFF_SIZE);
+        (&mut input)
+            .take(SNIFF_SIZE as u64)
+            .read_to_end(&mut head)?;
+
+        let (encoding, decoder) = match encoding {
+            Some(encoding) => (encoding, encoding.new_decoder_with_bom_removal()),
+            None => {
+                let encoding = match Encoding::for_bom(&head) {
+                    Some((encoding, bom)) => {
+                        head.drain(..bom);
+                        encoding
+                    }
+                    None => detect(&head),
+                };
+                (encoding, encoding.new_decoder_without_bom_handling())
+            }
+        };
+
+        Ok(DecodeReader {
+            input,
+            encoding,
+            decoder,
+            eof: head.len() < SNIFF_SIZE,
+            pending: head,
+            position: 0,
+            decoded: Vec::new(),
+            decoded_position: 0,
+            done: false,
+        })
+    }
+
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+}
+
+impl<R: Read> Read for DecodeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.decoded_position == self.decoded.len() {
+            if self.done {
+                return Ok(0);
+            }
+            if self.position == self.pending.len() && !self.eof {
+                self.pending.resize(CHUNK_SIZE, 0);
+                let n = self.input.read(&mut self.pending)?;
+                self.pending.truncate(n);
+                self.position = 0;
+                self.eof = n == 0;
+            }
+
+            let input = &self.pending[self.position..];
+            let room = self
+                .decoder
+                .max_utf8_buffer_length(input.len())
+                .unwrap_or(CHUNK_SIZE * 4);
+            self.decoded.resize(room, 0);
+            let (result, read, written, _) =
+                self.decoder
+                    .decode_to_utf8(input, &mut self.decoded, self.eof);
+            self.position += read;
+            self.decoded.truncate(written);
+            self.decoded_position = 0;
+            self.done = self.eof && result == CoderResult::InputEmpty;
+        }
+
+        let n = buf.len().min(self.decoded.len() - self.decoded_position);
+        buf[..n].copy_from_slice(&self.decoded[self.decoded_position..self.decoded_position + n]);
+        self.decoded_position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Café crème, naïve façade. Déjà vu!\n";
+
+    fn decode(bytes: &[u8], encoding: Option<&'static Encoding>) -> (String, &'static str) {
+        let mut reader = DecodeReader::new(bytes, encoding).unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        (text, reader.encoding().name())
+    }
+
+    #[test]
+    fn test_detects_fixture_encodings() {
+        let fixtures: [(&[u8], &str); 5] = [
+            (include_bytes!("../fixtures/utf8.txt"), "UTF-8"),
+            (include_bytes!("../fixtures/utf8-bom.txt"), "UTF-8"),
+            (include_bytes!("../fixtures/latin1.txt"), "windows-1252"),
+            (include_bytes!("../fixtures/utf16le-bom.txt"), "UTF-16LE"),
+            (include_bytes!("../fixtures/utf16be.txt"), "UTF-16BE"),
+        ];
+        for (bytes, name) in fixtures {
+            assert_eq!(decode(bytes, None), (TEXT.to_string(), name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_forced_encoding() {
+        let latin1 = include_bytes!("../fixtures/latin1.txt");
+        assert_eq!(decode(latin1, Some(parse("latin1").unwrap())).0, TEXT);
+        // Read as UTF-8, the accented bytes are invalid and get replaced
+        let (text, _) = decode(latin1, Some(UTF_8));
+        assert!(text.starts_with("Caf\u{FFFD} cr\u{FFFD}me"), "{text}");
+        assert!(parse("klingon").is_err());
+    }
+
+    #[test]
+    fn test_streams_past_the_sniffed_start() {
+        // Long enough to need several reads, with a character split
+        // across the chunk boundaries
+        let text = TEXT.repeat(5_000);
+        let utf16: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(decode(&utf16, None), (text, "UTF-16LE"));
+        assert_eq!(detect(b""), UTF_8);
+        assert_eq!(detect(&"déjà".as_bytes()[..2]), UTF_8);
+    }
+}
@@ -0,0 +1,59 @@
+//! Downloading a web page to count its words. Only built with the `url`
+//! feature, which pulls in reqwest.
+
+use crate::html;
+use reqwest::blocking::Client;
+use reqwest::header::CONTENT_TYPE;
+use std::io::Read;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Pages bigger than this are refused rather than counted
+pub const MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Fetch `url` and return its text, with markup stripped if it's HTML.
+pub fn fetch_text(url: &str) -> Result<String, String> {
+    let client = Client::builder()
+        .timeout(TIMEOUT)
+        .user_agent("word-counter")
+        .build()
+        .map_err(|e| format!("Failed to set up HTTP client: {e}"))?;
+
+    let response = client
+        .get(url)
+        .send()
+        .map_err(|e| format!("Failed to fetch {url}: {e}"))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch {url}: {}", response.status()));
+    }
+
+    let too_big = || format!("{url} is larger than {} MB", MAX_BYTES / 1024 / 1024);
+    if response.content_length().is_some_and(|len| len > MAX_BYTES) {
+        return Err(too_big());
+    }
+
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.contains("html"));
+
+    // Content-Length can be missing or wrong, so cap the read itself too
+    let mut body = Vec::new();
+    response
+        .take(MAX_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| format!("Failed to read {url}: {e}"))?;
+    if body.len() as u64 > MAX_BYTES {
+        return Err(too_big());
+    }
+
+    let text = String::from_utf8_lossy(&body);
+    Ok(if is_html {
+        html::strip_html(&text)
+    } else {
+        text.into_owned()
+    })
+}
@@ -0,0 +1,151 @@
+//! Turning an HTML page into the plain text a reader would see.
+
+/// Tags that don't break a word: `<b>bold</b>er` is one word.
+const INLINE_TAGS: &[&str] = &[
+    "a", "abbr", "b", "code", "em", "i", "mark", "small", "span", "strong", "sub", "sup", "u",
+];
+
+/// Tags whose contents aren't page text.
+const SKIPPED_TAGS: &[&str] = &["script", "style", "noscript", "template"];
+
+/// Drop tags, comments, scripts, and styles, and decode entities. Block
+/// tags become spaces so `<p>one</p><p>two</p>` stays two words.
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            // A stray '<' with no tag after it is just text
+            text.push('<');
+            rest = &rest[1..];
+            continue;
+        };
+        let name = tag_name(&rest[1..end]);
+        rest = &rest[end + 1..];
+
+        if SKIPPED_TAGS.contains(&name.as_str()) {
+            let close = format!("</{name}");
+            rest = find_ignore_case(rest, &close)
+                .and_then(|i| rest[i..].find('>').map(|j| &rest[i + j + 1..]))
+                .unwrap_or("");
+        }
+        if !INLINE_TAGS.contains(&name.as_str()) {
+            text.push(' ');
+        }
+    }
+
+    text.push_str(&decode_entities(rest));
+    text
+}
+
+/// "a href=..." → "a", "/P" → "p"
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .to_ascii_lowercase()
+        .find(&needle.to_ascii_lowercase())
+}
+
+/// Named entities common in page text, plus numeric ones like `&#8217;`.
+/// Anything unrecognized is left as written.
+fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse()))
+                    .and_then(|n| n.ok())
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(html: &str) -> Vec<String> {
+        strip_html(html)
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_block_tags_separate_words() {
+        assert_eq!(words("<p>one</p><p>two</p>"), vec!["one", "two"]);
+        assert_eq!(
+            words("<b>bold</b>er <a href='/x'>link</a>"),
+            vec!["bolder", "link"]
+        );
+    }
+
+    #[test]
+    fn test_scripts_styles_and_comments_are_dropped() {
+        let html = "<html><head><style>p { color: red }</style>\
+                    <SCRIPT>var x = '<p>';</SCRIPT></head>\
+                    <body><!-- hidden <b>note</b> -->Visible</body></html>";
+        assert_eq!(words(html), vec!["Visible"]);
+    }
+
+    #[test]
+    fn test_entities() {
+        assert_eq!(
+            strip_html("Tom &amp; Jerry&#8217;s &lt;show&gt; &bogus; &#x41;"),
+            "Tom & Jerry’s <show> &bogus; A"
+        );
+    }
+
+    #[test]
+    fn test_stray_angle_bracket() {
+        assert_eq!(strip_html("1 < 2"), "1 < 2");
+    }
+}
@@ -0,0 +1,156 @@
+//! `--index .wcindex`: remember each file's word counts under a hash of its
+//! content, so counting a directory again only reads the files that changed
+//! and takes the rest from the index.
+//!
+//! The index is JSON. Counts depend on how the text was split into words,
+//! so an index made with other settings (`--profile`, `--encoding`) is
+//! started over rather than reused.
+
+use crate::WordCounts;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+const VERSION: u64 = 1;
+
+/// A stable 64-bit FNV-1a hash, so an index reads the same across builds.
+pub fn content_hash(content: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in content {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+#[derive(Debug, Default)]
+pub struct Index {
+    /// How the counts were made, e.g. "profile=prose encoding=auto"
+    settings: String,
+    files: HashMap<String, WordCounts>,
+    /// Hashes looked up or added this run; only these are saved again
+    used: HashSet<String>,
+    /// Files taken from the index and files counted this run
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl Index {
+    pub fn new(settings: &str) -> Self {
+        Index {
+            settings: settings.to_string(),
+            ..Index::default()
+        }
+    }
+
+    /// Read the index at `path`. A missing file, or one made with other
+    /// settings, gives an empty index.
+    pub fn load(path: &Path, settings: &str) -> Result<Self, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Index::new(settings)),
+            Err(e) => return Err(format!("Error reading '{}': {e}", path.display())),
+        };
+        Self::parse(&text, settings)
+            .ok_or_else(|| format!("'{}' isn't a word-counter index", path.display()))
+    }
+
+    fn parse(text: &str, settings: &str) -> Option<Self> {
+        let value: Value = serde_json::from_str(text).ok()?;
+        if value["version"].as_u64()? != VERSION {
+            return None;
+        }
+        let mut index = Index::new(settings);
+        if value["settings"].as_str()? != settings {
+            return Some(index);
+        }
+        for (hash, words) in value["files"].as_object()? {
+            let mut counts = WordCounts::new();
+            for (word, n) in words.as_object()? {
+                counts.add_count(word.clone(), n.as_u64()? as usize);
+            }
+            index.files.insert(hash.clone(), counts);
+        }
+        Some(index)
+    }
+
+    /// The counts for a file with this content hash, if it was counted
+    /// before.
+    pub fn get(&mut self, hash: &str) -> Option<&WordCounts> {
+        let counts = self.files.get(hash)?;
+        self.used.insert(hash.to_string());
+        self.hits += 1;
+        Some(counts)
+    }
+
+    pub fn insert(&mut self, hash: String, counts: WordCounts) {
+        self.used.insert(hash.clone());
+        self.files.insert(hash, counts);
+        self.misses += 1;
+    }
+
+    /// The index as JSON, without the files that weren't seen this run.
+    pub fn to_json(&self) -> String {
+        let mut hashes: Vec<&String> = self.used.iter().collect();
+        hashes.sort();
+        let files: Map<String, Value> = hashes
+            .into_iter()
+            .map(|hash| {
+                let mut words: Vec<(&str, usize)> = self.files[hash].iter().collect();
+                words.sort();
+                let words: Map<String, Value> = words
+                    .into_iter()
+                    .map(|(word, n)| (word.to_string(), json!(n)))
+                    .collect();
+                (hash.clone(), Value::Object(words))
+            })
+            .collect();
+        json!({
+            "version": VERSION,
+            "settings": self.settings,
+            "files": files,
+        })
+        .to_string()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_json())
+            .map_err(|e| format!("Error writing '{}': {e}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_words;
+
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(b""), "cbf29ce484222325");
+        assert_eq!(content_hash(b"a"), "af63dc4c8601ec8c");
+        assert_ne!(content_hash(b"the cat"), content_hash(b"the hat"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut index = Index::new("profile=prose");
+        let counts = count_words("the cat and the hat");
+        index.insert(content_hash(b"one"), counts.clone());
+        index.insert(content_hash(b"two"), count_words("gone"));
+
+        let mut reloaded = Index::parse(&index.to_json(), "profile=prose").unwrap();
+        assert_eq!(reloaded.get(&content_hash(b"one")), Some(&counts));
+        assert_eq!(reloaded.get(&content_hash(b"three")), None);
+        assert_eq!(reloaded.hits, 1);
+
+        // Only what this run saw is kept
+        let pruned = Index::parse(&reloaded.to_json(), "profile=prose").unwrap();
+        assert_eq!(pruned.files.len(), 1);
+
+        // Other settings start over
+        let other = Index::parse(&index.to_json(), "profile=code").unwrap();
+        assert!(other.files.is_empty());
+        assert!(Index::parse("[1, 2]", "profile=prose").is_none());
+    }
+}
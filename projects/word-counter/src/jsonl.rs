@@ -0,0 +1,152 @@
+//! `--jsonl --field message`: count the words in one field of a JSON Lines
+//! file, like a structured log, a line at a time. Lines that aren't JSON
+//! objects are skipped and counted rather than stopping the count.
+
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Read};
+
+/// What reading the lines turned up, besides the text of the field.
+#[derive(Debug, Default, PartialEq)]
+pub struct FieldStats {
+    /// Non-blank lines read
+    pub lines: u64,
+    /// Lines that weren't a JSON object
+    pub malformed: u64,
+    /// Objects without the field, or with something other than text, a
+    /// number or true/false in it
+    pub missing: u64,
+}
+
+/// The field at `path` in `value`. A dotted path like `error.message` looks
+/// inside nested objects, unless there's a field with the dots in its name.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let object = value.as_object()?;
+    if let Some(field) = object.get(path) {
+        return Some(field);
+    }
+    let (first, rest) = path.split_once('.')?;
+    lookup(object.get(first)?, rest)
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+impl FieldStats {
+    /// The text of `field` on each line of `input`, a line per record, as
+    /// a reader, so everything that counts a plain file can count a field.
+    pub fn reader<'a, R: Read>(&'a mut self, input: R, field: &str) -> FieldReader<'a, R> {
+        FieldReader {
+            input: BufReader::new(input),
+            field: field.to_string(),
+            stats: self,
+            line: Vec::new(),
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Add the field's text from one line to `out`.
+    fn push(&mut self, line: &[u8], field: &str, out: &mut Vec<u8>) {
+        if line.trim_ascii().is_empty() {
+            return;
+        }
+        self.lines += 1;
+        let value: Value = match serde_json::from_slice(line) {
+            Ok(value @ Value::Object(_)) => value,
+            _ => {
+                self.malformed += 1;
+                return;
+            }
+        };
+        match lookup(&value, field).and_then(text) {
+            Some(text) => {
+                out.extend_from_slice(text.as_bytes());
+                out.push(b'\n');
+            }
+            None => self.missing += 1,
+        }
+    }
+}
+
+pub struct FieldReader<'a, R: Read> {
+    input: BufReader<R>,
+    field: String,
+    stats: &'a mut FieldStats,
+    line: Vec<u8>,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> Read for FieldReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.buffer.len() {
+            self.line.clear();
+            if self.input.read_until(b'\n', &mut self.line)? == 0 {
+                return Ok(0);
+            }
+            self.buffer.clear();
+            self.position = 0;
+            self.stats.push(&self.line, &self.field, &mut self.buffer);
+        }
+
+        let n = buf.len().min(self.buffer.len() - self.position);
+        buf[..n].copy_from_slice(&self.buffer[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(input: &str, field: &str) -> (String, FieldStats) {
+        let mut stats = FieldStats::default();
+        let mut text = String::new();
+        stats
+            .reader(input.as_bytes(), field)
+            .read_to_string(&mut text)
+            .unwrap();
+        (text, stats)
+    }
+
+    #[test]
+    fn test_extracts_the_field() {
+        let log = r#"{"level":"info","message":"Server started"}
+{"level":"error","message":"Disk full","code":28}
+
+not json at all
+{"level":"debug"}
+["an", "array"]
+{"level":"warn","message":"Disk almost full"}"#;
+        let (text, stats) = extract(log, "message");
+        assert_eq!(text, "Server started\nDisk full\nDisk almost full\n");
+        assert_eq!(
+            stats,
+            FieldStats {
+                lines: 6,
+                malformed: 2,
+                missing: 1,
+            }
+        );
+
+        let (text, _) = extract(log, "code");
+        assert_eq!(text, "28\n");
+    }
+
+    #[test]
+    fn test_nested_fields() {
+        let log = r#"{"error":{"message":"timed out"}}
+{"error.message":"literal key"}
+{"error":"flat"}"#;
+        let (text, stats) = extract(log, "error.message");
+        assert_eq!(text, "timed out\nliteral key\n");
+        assert_eq!(stats.missing, 1);
+    }
+}
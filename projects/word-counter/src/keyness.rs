@@ -0,0 +1,201 @@
+//! `--reference`: keyness, the words used much more or much less often than
+//! in a reference corpus, ranked by Dunning's log-likelihood.
+
+use crate::{TokenizerOptions, WordCounts};
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+/// Log-likelihood above which a difference is significant at p < 0.05
+/// (the chi-squared critical value for one degree of freedom)
+pub const SIGNIFICANT: f64 = 3.84;
+
+/// Word frequencies from a reference corpus.
+#[derive(Debug, Default)]
+pub struct ReferenceList {
+    counts: HashMap<String, u64>,
+    total: u64,
+}
+
+impl ReferenceList {
+    /// Read a frequency list with a word and its count on each line, in
+    /// either order and separated by whitespace or a tab, e.g. `the 69971`
+    /// or `69971 the`. Lines starting with `#` are comments. Words are
+    /// normalized like counted words, and ones that normalize to the same
+    /// word are added together.
+    pub fn load(reader: impl BufRead, options: &TokenizerOptions) -> io::Result<ReferenceList> {
+        let mut list = ReferenceList::default();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (word, count) = match fields.as_slice() {
+                [a, b] => match (a.parse::<u64>(), b.parse::<u64>()) {
+                    (Ok(_), Ok(n)) | (Err(_), Ok(n)) => (*a, n),
+                    (Ok(n), Err(_)) => (*b, n),
+                    (Err(_), Err(_)) => return Err(bad_line(i, line)),
+                },
+                _ => return Err(bad_line(i, line)),
+            };
+            if let Some(word) = options.normalize(word) {
+                *list.counts.entry(word).or_insert(0) += count;
+                list.total += count;
+            }
+        }
+        Ok(list)
+    }
+
+    pub fn get(&self, word: &str) -> u64 {
+        self.counts.get(word).copied().unwrap_or(0)
+    }
+
+    /// Sum of all the counts
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+}
+
+fn bad_line(index: usize, line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "line {}: expected a word and a count, got '{line}'",
+            index + 1
+        ),
+    )
+}
+
+/// Dunning's log-likelihood (G²) that a word seen `a` times in `c` words
+/// of input and `b` times in `d` words of reference has the same rate in
+/// both. 0 when the rates match; larger means a bigger difference.
+pub fn log_likelihood(a: u64, b: u64, c: u64, d: u64) -> f64 {
+    let (a, b, c, d) = (a as f64, b as f64, c as f64, d as f64);
+    if c == 0.0 || d == 0.0 {
+        return 0.0;
+    }
+    let expected_a = c * (a + b) / (c + d);
+    let expected_b = d * (a + b) / (c + d);
+    // x ln(x / e), taking 0 ln 0 as 0
+    let term = |x: f64, e: f64| if x > 0.0 { x * (x / e).ln() } else { 0.0 };
+    2.0 * (term(a, expected_a) + term(b, expected_b))
+}
+
+/// A word whose rate differs significantly from the reference.
+#[derive(Debug, PartialEq)]
+pub struct Keyword<'a> {
+    pub word: &'a str,
+    /// Times in the input
+    pub count: u64,
+    /// Times in the reference list
+    pub reference: u64,
+    pub log_likelihood: f64,
+}
+
+/// What `--reference` reports, each list strongest first.
+#[derive(Debug, PartialEq)]
+pub struct Keyness<'a> {
+    /// Used more often than in the reference
+    pub over: Vec<Keyword<'a>>,
+    /// Used less often than in the reference, including not at all
+    pub under: Vec<Keyword<'a>>,
+}
+
+/// Every word in either the input or the reference whose log-likelihood
+/// reaches `SIGNIFICANT`, split by which way it leans.
+pub fn keyness<'a>(counts: &'a WordCounts, reference: &'a ReferenceList) -> Keyness<'a> {
+    let total = counts.total() as u64;
+    let mut over = Vec::new();
+    let mut under = Vec::new();
+
+    let in_input = counts.iter().map(|(word, n)| (word, n as u64));
+    let only_in_reference = reference
+        .counts
+        .keys()
+        .filter(|word| counts.get(word).is_none())
+        .map(|word| (word.as_str(), 0));
+
+    for (word, count) in in_input.chain(only_in_reference) {
+        let in_reference = reference.get(word);
+        let ll = log_likelihood(count, in_reference, total, reference.total);
+        if ll < SIGNIFICANT {
+            continue;
+        }
+        let keyword = Keyword {
+            word,
+            count,
+            reference: in_reference,
+            log_likelihood: ll,
+        };
+        // Compare rates without dividing: a / c against b / d
+        if count as f64 * reference.total as f64 > in_reference as f64 * total as f64 {
+            over.push(keyword);
+        } else {
+            under.push(keyword);
+        }
+    }
+
+    let strongest_first = |a: &Keyword, b: &Keyword| {
+        b.log_likelihood
+            .total_cmp(&a.log_likelihood)
+            .then(a.word.cmp(b.word))
+    };
+    over.sort_by(strongest_first);
+    under.sort_by(strongest_first);
+    Keyness { over, under }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_words;
+
+    fn reference(text: &str) -> ReferenceList {
+        ReferenceList::load(text.as_bytes(), &TokenizerOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_load_either_order() {
+        let list = reference("# Brown corpus\nThe 70\n36 of\nthe\t5\n\nrust 1\n");
+        assert_eq!(list.get("the"), 75);
+        assert_eq!(list.get("of"), 36);
+        assert_eq!(list.total(), 112);
+
+        let bad = ReferenceList::load("the cat sat\n".as_bytes(), &TokenizerOptions::default());
+        assert!(bad.unwrap_err().to_string().contains("line 1"));
+        assert!(ReferenceList::load("the cat\n".as_bytes(), &TokenizerOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_log_likelihood() {
+        // Same rate in both
+        assert_eq!(log_likelihood(10, 100, 1_000, 10_000), 0.0);
+        // Worked example: 10 in 1,000 words against 10 in 10,000
+        let ll = log_likelihood(10, 10, 1_000, 10_000);
+        assert!((ll - 22.14).abs() < 0.01, "{ll}");
+        assert_eq!(log_likelihood(5, 0, 0, 100), 0.0);
+    }
+
+    #[test]
+    fn test_keyness_over_and_under() {
+        let text = "rust ".repeat(20) + &"the cat ".repeat(40);
+        let counts = count_words(&text);
+        let list = reference("the 500\ncat 400\nof 300\nrust 1\n");
+        let keyness = keyness(&counts, &list);
+
+        let over: Vec<&str> = keyness.over.iter().map(|k| k.word).collect();
+        assert_eq!(over, ["rust"]);
+        assert_eq!(keyness.over[0].count, 20);
+        assert_eq!(keyness.over[0].reference, 1);
+
+        let under: Vec<&str> = keyness.under.iter().map(|k| k.word).collect();
+        assert_eq!(under, ["of"]);
+        assert_eq!(keyness.under[0].count, 0);
+    }
+}
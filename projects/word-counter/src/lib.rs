@@ -0,0 +1,374 @@
+//! Word frequency counting, usable from any project in this repo.
+//!
+//! ```
+//! let counts = word_counter::count_words("the cat and the hat");
+//! assert_eq!(counts.get("the"), Some(&2));
+//! assert_eq!(counts.total(), 5);
+//! ```
+
+pub mod bars;
+pub mod cloud;
+pub mod columns;
+pub mod cooccurrence;
+pub mod corpus;
+pub mod delta;
+pub mod dialogue;
+pub mod dictionary;
+pub mod dupes;
+pub mod encoding;
+#[cfg(feature = "url")]
+pub mod fetch;
+pub mod html;
+pub mod index;
+pub mod jsonl;
+pub mod keyness;
+pub mod lines;
+pub mod markdown;
+pub mod phrase;
+pub mod progress;
+pub mod sample;
+pub mod sketch;
+pub mod spill;
+pub mod stem;
+pub mod tokenize;
+
+use progress::Progress;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use tokenize::Profile;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How raw whitespace-separated tokens are turned into counted words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenizerOptions {
+    /// Fold case so "Hello" and "hello" count as the same word
+    pub lowercase: bool,
+    /// Keep apostrophes inside words ("don't") instead of stripping them
+    pub keep_apostrophes: bool,
+    /// Ignore words shorter than this many characters
+    pub min_length: usize,
+    /// How text is split into words before the options above apply
+    pub profile: Profile,
+}
+
+impl Default for TokenizerOptions {
+    fn default() -> Self {
+        TokenizerOptions {
+            lowercase: true,
+            keep_apostrophes: true,
+            min_length: 1,
+            profile: Profile::Prose,
+        }
+    }
+}
+
+impl TokenizerOptions {
+    /// Clean up a raw token, stripping everything except letters, digits,
+    /// and (optionally) apostrophes. Returns `None` if nothing is left.
+    pub fn normalize(&self, word: &str) -> Option<String> {
+        let word = if self.lowercase {
+            word.to_lowercase()
+        } else {
+            word.to_string()
+        };
+
+        let cleaned: String = word
+            .chars()
+            .filter(|c| c.is_alphanumeric() || (self.keep_apostrophes && *c == '\''))
+            .collect();
+
+        if cleaned.is_empty() || cleaned.chars().count() < self.min_length {
+            None
+        } else {
+            Some(cleaned)
+        }
+    }
+}
+
+/// How to order words when listing results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    /// Most frequent first
+    Count,
+    /// Alphabetical
+    Alpha,
+    /// Longest first
+    Length,
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "count" => Ok(SortOrder::Count),
+            "alpha" => Ok(SortOrder::Alpha),
+            "length" => Ok(SortOrder::Length),
+            _ => Err(format!("Invalid sort: '{s}'. Use count, alpha, or length")),
+        }
+    }
+}
+
+impl std::fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SortOrder::Count => write!(f, "count"),
+            SortOrder::Alpha => write!(f, "alpha"),
+            SortOrder::Length => write!(f, "length"),
+        }
+    }
+}
+
+impl SortOrder {
+    /// Compare two (word, count) entries. Ties always fall back to
+    /// alphabetical order so output is stable.
+    pub fn compare(&self, a: &(&str, usize), b: &(&str, usize)) -> std::cmp::Ordering {
+        let primary = match self {
+            SortOrder::Count => b.1.cmp(&a.1),
+            SortOrder::Alpha => std::cmp::Ordering::Equal,
+            SortOrder::Length => b.0.chars().count().cmp(&a.0.chars().count()),
+        };
+        primary.then(a.0.cmp(b.0))
+    }
+}
+
+/// Word frequencies plus the running total.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WordCounts {
+    counts: HashMap<String, usize>,
+    total: usize,
+}
+
+impl WordCounts {
+    pub fn new() -> Self {
+        WordCounts::default()
+    }
+
+    pub fn add(&mut self, word: String) {
+        self.add_count(word, 1);
+    }
+
+    /// Count `word` `n` more times.
+    pub fn add_count(&mut self, word: String, n: usize) {
+        *self.counts.entry(word).or_insert(0) += n;
+        self.total += n;
+    }
+
+    /// Add every count in `other` to these.
+    pub fn merge(&mut self, other: &WordCounts) {
+        for (word, n) in other.iter() {
+            self.add_count(word.to_string(), n);
+        }
+    }
+
+    pub fn get(&self, word: &str) -> Option<&usize> {
+        self.counts.get(word)
+    }
+
+    /// Number of distinct words
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Number of words counted, including repeats
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.counts.iter().map(|(w, c)| (w.as_str(), *c))
+    }
+
+    /// The `n` most frequent words, ties broken alphabetically.
+    pub fn top(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut sorted = self.sorted(SortOrder::Count, false);
+        sorted.truncate(n);
+        sorted
+    }
+
+    /// Every word in the given order, optionally reversed.
+    pub fn sorted(&self, order: SortOrder, reverse: bool) -> Vec<(&str, usize)> {
+        let mut sorted: Vec<(&str, usize)> = self.iter().collect();
+        sorted.sort_by(|a, b| {
+            let ordering = order.compare(a, b);
+            if reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        sorted
+    }
+}
+
+/// Count words in `text` with the default tokenizer options.
+pub fn count_words(text: &str) -> WordCounts {
+    count_words_with(text, &TokenizerOptions::default())
+}
+
+pub fn count_words_with(text: &str, options: &TokenizerOptions) -> WordCounts {
+    let mut counts = WordCounts::new();
+    for_each_word(text, options, &mut |word| counts.add(word));
+    counts
+}
+
+pub fn for_each_word(text: &str, options: &TokenizerOptions, on_word: &mut impl FnMut(String)) {
+    options.profile.tokenizer().words(text, options, on_word);
+}
+
+/// Stream normalized words from a reader in fixed-size chunks so large files
+/// never need to be held in memory at once. A word split across two chunks is
+/// carried over until the next whitespace byte arrives.
+pub fn stream_words<R: Read>(
+    mut reader: R,
+    options: &TokenizerOptions,
+    progress: &mut Progress,
+    mut on_word: impl FnMut(String),
+) -> io::Result<()> {
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut pending: Vec<u8> = Vec::new();
+
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        progress.advance(n);
+        pending.extend_from_slice(&buf[..n]);
+
+        // Splitting on ASCII whitespace always lands on a UTF-8 char boundary
+        if let Some(split) = pending.iter().rposition(|b| b.is_ascii_whitespace()) {
+            let rest = pending.split_off(split + 1);
+            for_each_word(to_str(&pending)?, options, &mut on_word);
+            pending = rest;
+        }
+    }
+
+    for_each_word(to_str(&pending)?, options, &mut on_word);
+    progress.finish();
+
+    Ok(())
+}
+
+fn to_str(bytes: &[u8]) -> io::Result<&str> {
+    std::str::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_simple() {
+        let counts = count_words("hello world hello");
+        assert_eq!(counts.get("hello"), Some(&2));
+        assert_eq!(counts.get("world"), Some(&1));
+    }
+
+    #[test]
+    fn test_count_case_insensitive() {
+        let counts = count_words("Hello HELLO hello");
+        assert_eq!(counts.get("hello"), Some(&3));
+        assert_eq!(counts.len(), 1);
+    }
+
+    #[test]
+    fn test_count_punctuation() {
+        let counts = count_words("hello, world! hello.");
+        assert_eq!(counts.get("hello"), Some(&2));
+        assert_eq!(counts.get("world"), Some(&1));
+    }
+
+    #[test]
+    fn test_count_empty() {
+        let counts = count_words("");
+        assert!(counts.is_empty());
+    }
+
+    #[test]
+    fn test_count_whitespace_only() {
+        let counts = count_words("   \n\t  ");
+        assert!(counts.is_empty());
+    }
+
+    #[test]
+    fn test_preserves_apostrophes() {
+        let counts = count_words("don't can't won't");
+        assert_eq!(counts.get("don't"), Some(&1));
+        assert_eq!(counts.get("can't"), Some(&1));
+    }
+
+    #[test]
+    fn test_tokenizer_options() {
+        let options = TokenizerOptions {
+            lowercase: false,
+            keep_apostrophes: false,
+            min_length: 3,
+            ..TokenizerOptions::default()
+        };
+        let counts = count_words_with("Don't do it, Rust rust", &options);
+        assert_eq!(counts.get("Dont"), Some(&1));
+        assert_eq!(counts.get("Rust"), Some(&1));
+        assert_eq!(counts.get("rust"), Some(&1));
+        assert_eq!(counts.get("do"), None);
+    }
+
+    #[test]
+    fn test_totals_and_top() {
+        let counts = count_words("b a b c b a");
+        assert_eq!(counts.total(), 6);
+        assert_eq!(counts.top(2), vec![("b", 3), ("a", 2)]);
+    }
+
+    #[test]
+    fn test_sort_orders() {
+        let counts = count_words("bb a ccc a bb a");
+        let words = |order, reverse| -> Vec<String> {
+            counts
+                .sorted(order, reverse)
+                .iter()
+                .map(|(w, _)| w.to_string())
+                .collect()
+        };
+
+        assert_eq!(words(SortOrder::Count, false), ["a", "bb", "ccc"]);
+        assert_eq!(words(SortOrder::Count, true), ["ccc", "bb", "a"]);
+        assert_eq!(words(SortOrder::Alpha, false), ["a", "bb", "ccc"]);
+        assert_eq!(words(SortOrder::Length, false), ["ccc", "bb", "a"]);
+        assert_eq!(words(SortOrder::Length, true), ["a", "bb", "ccc"]);
+    }
+
+    #[test]
+    fn test_sort_order_from_str() {
+        assert_eq!("Alpha".parse(), Ok(SortOrder::Alpha));
+        assert!("random".parse::<SortOrder>().is_err());
+    }
+
+    #[test]
+    fn test_streaming_matches_in_memory() {
+        // Longer than one chunk so words straddle chunk boundaries
+        let text = "alpha beta gamma délta\n".repeat(CHUNK_SIZE / 10);
+        let options = TokenizerOptions::default();
+        let mut progress = Progress::new(text.len() as u64);
+        let mut streamed = WordCounts::new();
+        stream_words(text.as_bytes(), &options, &mut progress, |w| {
+            streamed.add(w)
+        })
+        .unwrap();
+        assert_eq!(streamed, count_words(&text));
+        assert_eq!(progress.bytes_processed(), text.len() as u64);
+    }
+
+    #[test]
+    fn test_streaming_rejects_invalid_utf8() {
+        let bytes: &[u8] = &[b'o', b'k', b' ', 0xff, 0xfe];
+        let options = TokenizerOptions::default();
+        let mut progress = Progress::new(bytes.len() as u64);
+        assert!(stream_words(bytes, &options, &mut progress, |_| {}).is_err());
+    }
+}
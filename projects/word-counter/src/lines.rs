@@ -0,0 +1,252 @@
+//! `--line-stats`: line lengths, blank lines and the longest lines, gathered
+//! as the input streams past the word counter.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+/// How many of the longest lines are reported
+pub const LONGEST: usize = 5;
+
+/// Characters of each long line kept for display
+const PREVIEW_CHARS: usize = 60;
+
+/// One of the longest lines seen so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LongLine {
+    /// 1-based
+    pub number: u64,
+    /// In characters, not counting the line ending
+    pub length: usize,
+    /// The start of the line, with "…" if it goes on
+    pub preview: String,
+}
+
+/// Statistics over every line fed through `push`. Lengths count characters,
+/// and whitespace-only lines count as blank rather than towards the lengths.
+#[derive(Debug, Default)]
+pub struct LineStats {
+    lines: u64,
+    blank: u64,
+    /// Length of a non-blank line -> how many have it. Bounded by the
+    /// number of distinct lengths, so the median needs no list of lines.
+    lengths: BTreeMap<usize, u64>,
+    longest: Vec<LongLine>,
+    // The line being read
+    in_line: bool,
+    length: usize,
+    has_text: bool,
+    preview: Vec<u8>,
+    last_was_cr: bool,
+}
+
+impl LineStats {
+    /// Feed the next bytes of the input, which may end mid-line or even
+    /// mid-character.
+    pub fn push(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            if b == b'\n' {
+                self.end_line();
+                continue;
+            }
+            self.in_line = true;
+            self.last_was_cr = b == b'\r';
+            // Count characters by their first byte
+            if b & 0xC0 != 0x80 {
+                self.length += 1;
+            }
+            if !b.is_ascii_whitespace() {
+                self.has_text = true;
+            }
+            if self.length <= PREVIEW_CHARS {
+                self.preview.push(b);
+            }
+        }
+    }
+
+    /// Count a last line that has no newline after it.
+    pub fn finish(&mut self) {
+        if self.in_line {
+            self.end_line();
+        }
+    }
+
+    fn end_line(&mut self) {
+        self.lines += 1;
+        // A Windows line ending isn't part of the line
+        if self.last_was_cr {
+            self.length -= 1;
+            if self.preview.last() == Some(&b'\r') {
+                self.preview.pop();
+            }
+        }
+
+        if self.has_text {
+            *self.lengths.entry(self.length).or_insert(0) += 1;
+            self.record_if_long();
+        } else {
+            self.blank += 1;
+        }
+
+        self.in_line = false;
+        self.length = 0;
+        self.has_text = false;
+        self.preview.clear();
+        self.last_was_cr = false;
+    }
+
+    fn record_if_long(&mut self) {
+        let beats_shortest = self
+            .longest
+            .last()
+            .is_none_or(|shortest| self.length > shortest.length);
+        if self.longest.len() == LONGEST && !beats_shortest {
+            return;
+        }
+
+        let mut preview = String::from_utf8_lossy(&self.preview)
+            .trim_end()
+            .to_string();
+        if self.length > PREVIEW_CHARS {
+            preview.push('…');
+        }
+        // After any line of the same length, so ties go to the earlier line
+        let at = self.longest.partition_point(|l| l.length >= self.length);
+        self.longest.insert(
+            at,
+            LongLine {
+                number: self.lines,
+                length: self.length,
+                preview,
+            },
+        );
+        self.longest.truncate(LONGEST);
+    }
+
+    pub fn lines(&self) -> u64 {
+        self.lines
+    }
+
+    pub fn blank(&self) -> u64 {
+        self.blank
+    }
+
+    /// Mean length of the non-blank lines
+    pub fn average(&self) -> f64 {
+        let count = self.lines - self.blank;
+        if count == 0 {
+            return 0.0;
+        }
+        let total: u64 = self.lengths.iter().map(|(len, n)| *len as u64 * n).sum();
+        total as f64 / count as f64
+    }
+
+    /// Median length of the non-blank lines
+    pub fn median(&self) -> f64 {
+        let count = self.lines - self.blank;
+        if count == 0 {
+            return 0.0;
+        }
+        // The lengths at 0-based positions (count - 1) / 2 and count / 2
+        let nth = |n: u64| {
+            let mut seen = 0;
+            for (len, k) in &self.lengths {
+                seen += k;
+                if seen > n {
+                    return *len;
+                }
+            }
+            0
+        };
+        (nth((count - 1) / 2) + nth(count / 2)) as f64 / 2.0
+    }
+
+    pub fn max(&self) -> usize {
+        self.longest.first().map_or(0, |l| l.length)
+    }
+
+    /// Longest first; ties in line order
+    pub fn longest(&self) -> &[LongLine] {
+        &self.longest
+    }
+
+    /// Wrap `inner` so everything read through it is also fed to these
+    /// stats. Call `finish` once it's been read to the end.
+    pub fn reader<R: Read>(&mut self, inner: R) -> LineStatsReader<'_, R> {
+        LineStatsReader { inner, stats: self }
+    }
+}
+
+/// A reader that passes `inner` through unchanged while measuring its lines.
+pub struct LineStatsReader<'a, R> {
+    inner: R,
+    stats: &'a mut LineStats,
+}
+
+impl<R: Read> Read for LineStatsReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.stats.push(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(text: &str) -> LineStats {
+        let mut stats = LineStats::default();
+        stats.push(text.as_bytes());
+        stats.finish();
+        stats
+    }
+
+    #[test]
+    fn test_lengths_and_blanks() {
+        let s = stats("one\n\nthree\n   \nfive five\r\nsix");
+        assert_eq!(s.lines(), 6);
+        assert_eq!(s.blank(), 2);
+        // 3, 5, 9 and 3 characters
+        assert_eq!(s.average(), 5.0);
+        assert_eq!(s.median(), 4.0);
+        assert_eq!(s.max(), 9);
+        assert_eq!(stats("a\nbbb\ncc\n").median(), 2.0);
+        assert_eq!(stats("").lines(), 0);
+        assert_eq!(stats("").average(), 0.0);
+    }
+
+    #[test]
+    fn test_longest_lines() {
+        let text = "aa\nbbbbb\nc\ndddd\neeeee\nff\ngggggg\n";
+        let longest: Vec<(u64, usize)> = stats(text)
+            .longest()
+            .iter()
+            .map(|l| (l.number, l.length))
+            .collect();
+        assert_eq!(longest, [(7, 6), (2, 5), (5, 5), (4, 4), (1, 2)]);
+
+        let long = "x".repeat(PREVIEW_CHARS + 10);
+        let s = stats(&format!("short\n{long}\n"));
+        assert_eq!(s.longest()[0].length, PREVIEW_CHARS + 10);
+        assert_eq!(s.longest()[0].preview.chars().count(), PREVIEW_CHARS + 1);
+        assert!(s.longest()[0].preview.ends_with('…'));
+    }
+
+    #[test]
+    fn test_reader_counts_characters_across_chunks() {
+        let text = "héllo wörld\nsecond line\n";
+        let mut stats = LineStats::default();
+        let mut out = Vec::new();
+        let mut reader = stats.reader(text.as_bytes());
+        // One byte at a time, so characters split between reads
+        let mut byte = [0u8];
+        while reader.read(&mut byte).unwrap() > 0 {
+            out.push(byte[0]);
+        }
+        stats.finish();
+        assert_eq!(out, text.as_bytes());
+        assert_eq!(stats.lines(), 2);
+        assert_eq!(stats.max(), 11);
+        assert_eq!(stats.longest()[0].preview, "héllo wörld");
+    }
+}
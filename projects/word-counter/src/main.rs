@@ -1,61 +1,1447 @@
-use std::collections::HashMap;
+use encoding_rs::Encoding;
+use regex::Regex;
 use std::env;
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use word_counter::bars::{self, Style};
+use word_counter::cloud;
+use word_counter::columns::{self, ColumnReader, Group};
+use word_counter::cooccurrence::{Cooccurrence, DEFAULT_WINDOW};
+use word_counter::corpus::{self, Corpus, Query};
+use word_counter::delta::{self, Delta};
+use word_counter::dialogue::{self, Dialogue};
+use word_counter::dictionary::{self, Dictionary, RareWords};
+use word_counter::dupes::{self, RepeatedLine, SimilarParagraphs};
+use word_counter::encoding::{self, DecodeReader};
+use word_counter::index::{self, Index};
+use word_counter::jsonl::FieldStats;
+use word_counter::keyness::{self, Keyness, ReferenceList};
+use word_counter::lines::LineStats;
+use word_counter::markdown::{self, Section};
+use word_counter::phrase::PhraseCounter;
+use word_counter::progress::{self, Progress};
+use word_counter::sample::{self, LineSampler, SampleStats};
+use word_counter::sketch::{self, ApproxCounts};
+use word_counter::spill::{Merged, SpillCounts};
+use word_counter::stem::{self, StemForms};
+use word_counter::tokenize::Profile;
+use word_counter::{SortOrder, TokenizerOptions, WordCounts};
 
-fn count_words(text: &str) -> HashMap<String, usize> {
-    let mut counts: HashMap<String, usize> = HashMap::new();
+const USAGE: &str = "Usage: word-counter <file|url> [--near <word>] [--window <n>] \
+                     [--sort count|alpha|length] [--reverse] [--stem] [--dupes] [--by-section] [--approx]\n       \
+                     [--profile prose|code|log] [--sample <rate>] [--rare [--dict <file>]] [--line-stats]\n       \
+                     [--reference <freq list>] [--cloud <file.svg>] [--dialogue [--speaker-regex <pattern>]]\n       \
+                     [--column <name> [--group-by <name>]] [--jsonl --field <name>] [--encoding <name>]\n       \
+                     [--max-unique <n>]\n       \
+                     word-counter <file|url>... --phrase <phrase>\n       \
+                     word-counter <file|dir>... --index <file> [--sort ...] [--reverse] [--profile ...]\n       \
+                     word-counter repl <file|dir>...\n       \
+                     word-counter delta <old file> <new file>";
 
-    for word in text.split_whitespace() {
-        let cleaned: String = word
-            .to_lowercase()
-            .chars()
-            .filter(|c| c.is_alphanumeric() || *c == '\'')
-            .collect();
+struct Options {
+    /// Only `--phrase` takes more than one
+    files: Vec<String>,
+    near: Option<String>,
+    window: usize,
+    sort: SortOrder,
+    reverse: bool,
+    /// Group inflections ("runs", "running") under their stem
+    stem: bool,
+    /// Report repeated lines and near-duplicate paragraphs instead of counting
+    dupes: bool,
+    /// Count this exact phrase in each file instead of counting words
+    phrase: Option<String>,
+    /// Count each heading section of a Markdown file separately
+    by_section: bool,
+    /// Estimate counts in fixed memory instead of counting exactly
+    approx: bool,
+    /// How text is split into words
+    profile: Profile,
+    /// Count only this fraction of the lines and scale the counts up
+    sample: Option<f64>,
+    /// Also list words used once and words missing from `dict`
+    rare: bool,
+    /// Word list for `rare`
+    dict: Option<String>,
+    /// Also report line lengths and the longest lines
+    line_stats: bool,
+    /// Word frequency list to compare the counts against
+    reference: Option<String>,
+    /// Also draw the top words as an SVG word cloud in this file
+    cloud: Option<String>,
+    /// Count each speaker's words in a transcript instead of counting words
+    dialogue: bool,
+    /// How `dialogue` finds speakers, instead of `Name:` prefixes
+    speaker_regex: Option<Regex>,
+    /// Count only this column of a CSV or TSV file
+    column: Option<String>,
+    /// Count `column` separately for each value of this column
+    group_by: Option<String>,
+    /// Count only this field of each line of a JSON Lines file
+    field: Option<String>,
+    /// Read files in this encoding instead of guessing it
+    encoding: Option<&'static Encoding>,
+    /// Count every file under `files`, reusing counts saved in this index
+    /// for files that haven't changed
+    index: Option<String>,
+    /// Spill the counts to disk whenever they hold this many distinct words
+    max_unique: Option<usize>,
+}
+
+impl Options {
+    fn tokenizer(&self) -> TokenizerOptions {
+        TokenizerOptions {
+            profile: self.profile,
+            ..TokenizerOptions::default()
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut files = Vec::new();
+    let mut near = None;
+    let mut window = DEFAULT_WINDOW;
+    let mut sort = SortOrder::Count;
+    let mut reverse = false;
+    let mut stem = false;
+    let mut dupes = false;
+    let mut phrase = None;
+    let mut by_section = false;
+    let mut approx = false;
+    let mut profile = Profile::Prose;
+    let mut sample = None;
+    let mut rare = false;
+    let mut dict = None;
+    let mut line_stats = false;
+    let mut reference = None;
+    let mut cloud = None;
+    let mut dialogue = false;
+    let mut speaker_regex = None;
+    let mut column = None;
+    let mut group_by = None;
+    let mut jsonl = false;
+    let mut field = None;
+    let mut encoding = None;
+    let mut index = None;
+    let mut max_unique = None;
 
-        if !cleaned.is_empty() {
-            *counts.entry(cleaned).or_insert(0) += 1;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--near" => near = Some(iter.next().ok_or(USAGE)?),
+            "--window" => {
+                let n = iter.next().ok_or(USAGE)?;
+                window = n
+                    .parse()
+                    .ok()
+                    .filter(|&w| w > 0)
+                    .ok_or(format!("Invalid window: '{n}'"))?;
+            }
+            "--sort" => sort = iter.next().ok_or(USAGE)?.parse()?,
+            "--reverse" => reverse = true,
+            "--stem" => stem = true,
+            "--dupes" => dupes = true,
+            "--by-section" => by_section = true,
+            "--approx" => approx = true,
+            "--profile" => profile = iter.next().ok_or(USAGE)?.parse()?,
+            "--sample" => sample = Some(sample::parse_rate(iter.next().ok_or(USAGE)?)?),
+            "--phrase" => phrase = Some(iter.next().ok_or(USAGE)?.clone()),
+            "--rare" => rare = true,
+            "--dict" => dict = Some(iter.next().ok_or(USAGE)?.clone()),
+            "--line-stats" => line_stats = true,
+            "--reference" => reference = Some(iter.next().ok_or(USAGE)?.clone()),
+            "--cloud" => cloud = Some(iter.next().ok_or(USAGE)?.clone()),
+            "--dialogue" => dialogue = true,
+            "--speaker-regex" => {
+                let pattern = iter.next().ok_or(USAGE)?;
+                speaker_regex = Some(
+                    dialogue::speaker_pattern(pattern)
+                        .map_err(|e| format!("Invalid --speaker-regex '{pattern}': {e}"))?,
+                );
+                dialogue = true;
+            }
+            "--column" => column = Some(iter.next().ok_or(USAGE)?.clone()),
+            "--group-by" => group_by = Some(iter.next().ok_or(USAGE)?.clone()),
+            "--jsonl" => jsonl = true,
+            "--field" => field = Some(iter.next().ok_or(USAGE)?.clone()),
+            "--encoding" => encoding = Some(encoding::parse(iter.next().ok_or(USAGE)?)?),
+            "--index" => index = Some(iter.next().ok_or(USAGE)?.clone()),
+            "--max-unique" => {
+                let n = iter.next().ok_or(USAGE)?;
+                max_unique = Some(
+                    n.parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or(format!("Invalid --max-unique: '{n}'"))?,
+                );
+            }
+            _ if arg.starts_with("--") => return Err(USAGE.to_string()),
+            _ => files.push(arg.clone()),
         }
     }
 
-    counts
+    if files.is_empty() || (files.len() > 1 && phrase.is_none() && index.is_none()) {
+        return Err(USAGE.to_string());
+    }
+    if approx && (sort != SortOrder::Count || reverse) {
+        return Err(
+            "--approx only finds the most frequent words; drop --sort/--reverse".to_string(),
+        );
+    }
+
+    if sample.is_some() && (dupes || by_section || approx || phrase.is_some() || near.is_some()) {
+        return Err(
+            "--sample only estimates plain word counts; drop --dupes, --by-section, --approx, --phrase and --near"
+                .to_string(),
+        );
+    }
+
+    if dict.is_some() && !rare {
+        return Err("--dict is the word list for --rare; add --rare".to_string());
+    }
+    if rare && (stem || approx || sample.is_some() || dupes || by_section || phrase.is_some()) {
+        return Err(
+            "--rare needs exact counts of the words as written; drop --stem, --approx, --sample, --dupes, --by-section and --phrase"
+                .to_string(),
+        );
+    }
+
+    if line_stats && (dupes || by_section || phrase.is_some()) {
+        return Err(
+            "--line-stats goes with a word count; drop --dupes, --by-section and --phrase"
+                .to_string(),
+        );
+    }
+
+    if reference.is_some()
+        && (stem || approx || sample.is_some() || dupes || by_section || phrase.is_some())
+    {
+        return Err(
+            "--reference compares exact counts of the words as written; drop --stem, --approx, --sample, --dupes, --by-section and --phrase"
+                .to_string(),
+        );
+    }
+
+    if cloud.is_some() && (dupes || by_section || phrase.is_some()) {
+        return Err(
+            "--cloud draws the word counts; drop --dupes, --by-section and --phrase".to_string(),
+        );
+    }
+
+    if dialogue
+        && (stem
+            || dupes
+            || by_section
+            || approx
+            || sample.is_some()
+            || phrase.is_some()
+            || near.is_some()
+            || rare
+            || line_stats
+            || reference.is_some()
+            || cloud.is_some())
+    {
+        return Err(
+            "--dialogue counts words per speaker on its own; drop --stem, --dupes, --by-section, --approx, --sample, --phrase, --near, --rare, --line-stats, --reference and --cloud"
+                .to_string(),
+        );
+    }
+
+    if column.is_some() && (by_section || phrase.is_some()) {
+        return Err(
+            "--column reads one CSV or TSV file; drop --by-section and --phrase".to_string(),
+        );
+    }
+    if group_by.is_some() && column.is_none() {
+        return Err("--group-by splits the counts of a --column; add --column".to_string());
+    }
+    if group_by.is_some()
+        && (stem
+            || dupes
+            || approx
+            || sample.is_some()
+            || near.is_some()
+            || rare
+            || line_stats
+            || reference.is_some()
+            || cloud.is_some()
+            || dialogue)
+    {
+        return Err(
+            "--group-by counts words per group on its own; drop --stem, --dupes, --approx, --sample, --near, --rare, --line-stats, --reference, --cloud and --dialogue"
+                .to_string(),
+        );
+    }
+
+    if jsonl != field.is_some() {
+        return Err("--jsonl counts one --field of each line; give both".to_string());
+    }
+    if jsonl && (column.is_some() || by_section || phrase.is_some() || dialogue) {
+        return Err(
+            "--jsonl reads one JSON Lines file; drop --column, --by-section, --phrase and --dialogue"
+                .to_string(),
+        );
+    }
+
+    if index.is_some()
+        && (near.is_some()
+            || stem
+            || dupes
+            || phrase.is_some()
+            || by_section
+            || approx
+            || sample.is_some()
+            || rare
+            || line_stats
+            || reference.is_some()
+            || cloud.is_some()
+            || dialogue
+            || column.is_some()
+            || jsonl)
+    {
+        return Err(
+            "--index saves plain word counts; it goes only with --sort, --reverse, --profile and --encoding"
+                .to_string(),
+        );
+    }
+
+    if max_unique.is_some()
+        && (near.is_some()
+            || stem
+            || dupes
+            || phrase.is_some()
+            || by_section
+            || approx
+            || sample.is_some()
+            || rare
+            || reference.is_some()
+            || cloud.is_some()
+            || dialogue
+            || group_by.is_some()
+            || index.is_some())
+    {
+        return Err(
+            "--max-unique keeps only the top words in memory; drop --near, --stem, --dupes, --phrase, --by-section, --approx, --sample, --rare, --reference, --cloud, --dialogue, --group-by and --index"
+                .to_string(),
+        );
+    }
+
+    // Normalize the --near word the way the profile will count it
+    let tokenizer = TokenizerOptions {
+        profile,
+        ..TokenizerOptions::default()
+    };
+    let near = match near {
+        Some(word) => {
+            let mut words = Vec::new();
+            word_counter::for_each_word(word, &tokenizer, &mut |w| words.push(w));
+            match words.as_slice() {
+                [normalized] => Some(normalized.clone()),
+                _ => return Err(format!("Not a word: '{word}'")),
+            }
+        }
+        None => None,
+    };
+
+    Ok(Options {
+        files,
+        near,
+        window,
+        sort,
+        reverse,
+        stem,
+        dupes,
+        phrase,
+        by_section,
+        approx,
+        profile,
+        sample,
+        rare,
+        dict,
+        line_stats,
+        reference,
+        cloud,
+        dialogue,
+        speaker_regex,
+        column,
+        group_by,
+        field,
+        encoding,
+        index,
+        max_unique,
+    })
 }
 
-fn display_results(counts: &HashMap<String, usize>, top_n: usize) {
-    let mut sorted: Vec<(&String, &usize)> = counts.iter().collect();
-    sorted.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+fn display_results(
+    counts: &WordCounts,
+    top_n: usize,
+    options: &Options,
+    forms: Option<&StemForms>,
+    sampled: Option<&SampleStats>,
+    bytes: u64,
+    elapsed: Duration,
+) {
+    if let Some(stats) = sampled {
+        println!(
+            "\nSampled {} of {} lines ({:.1}%)",
+            stats.lines_kept,
+            stats.lines_read,
+            stats.rate * 100.0
+        );
+    }
 
-    let total: usize = counts.values().sum();
+    let mut top = counts.sorted(options.sort, options.reverse);
+    top.truncate(top_n);
+    let total = counts.total();
     let unique = counts.len();
 
-    println!("\nTop {} words:", top_n.min(sorted.len()));
-    for (rank, (word, count)) in sorted.iter().take(top_n).enumerate() {
+    display_heading(top.len(), options);
+    let rows: Vec<(String, usize)> = top
+        .iter()
+        .map(|(word, count)| {
+            let label = match forms.and_then(|f| f.most_common(word)) {
+                Some(form) if form != *word => format!("{word} ({form})"),
+                _ => word.to_string(),
+            };
+            (label, *count)
+        })
+        .collect();
+    match sampled {
+        Some(stats) => {
+            for (rank, (label, count)) in rows.iter().enumerate() {
+                println!(
+                    "  {:>2}. {:<15} — ~{:.0} (±{:.0}%)",
+                    rank + 1,
+                    bars::truncate(label, bars::MAX_LABEL),
+                    stats.estimate(*count),
+                    stats.margin(*count) * 100.0
+                );
+            }
+        }
+        None => {
+            for line in bars::render(&rows, total, Style::detect()) {
+                println!("{line}");
+            }
+        }
+    }
+
+    match sampled {
+        Some(stats) => {
+            println!(
+                "\nTotal: ~{:.0} words (±{:.0}%), {unique} unique in the sample",
+                stats.estimate(total),
+                stats.margin(total) * 100.0
+            );
+            println!(
+                "Estimates are scaled from the sample with 95% margins; rare words may be missing."
+            );
+        }
+        None => println!("\nTotal: {total} words, {unique} unique"),
+    }
+
+    if let Some(rate) = progress::throughput(bytes, total, elapsed) {
+        println!("Throughput: {rate}");
+    }
+}
+
+fn display_heading(shown: usize, options: &Options) {
+    if options.sort == SortOrder::Count && !options.reverse {
+        println!("\nTop {shown} words:");
+    } else {
+        let reversed = if options.reverse { ", reversed" } else { "" };
+        println!("\n{shown} words (sorted by {}{reversed}):", options.sort);
+    }
+}
+
+/// The top words with `--max-unique`, from counts merged back from disk.
+fn display_merged(merged: &Merged, options: &Options, bytes: u64, elapsed: Duration) {
+    display_heading(merged.top.len(), options);
+    for line in bars::render(&merged.top, merged.total, Style::detect()) {
+        println!("{line}");
+    }
+    println!("\nTotal: {} words, {} unique", merged.total, merged.unique);
+    match merged.spills {
+        0 => {}
+        1 => println!("Counts were spilled to disk once and merged"),
+        n => println!("Counts were spilled to disk {n} times and merged"),
+    }
+    if let Some(rate) = progress::throughput(bytes, merged.total, elapsed) {
+        println!("Throughput: {rate}");
+    }
+}
+
+fn display_approx(counts: &ApproxCounts, top_n: usize, bytes: u64, elapsed: Duration) {
+    let top = counts.top(top_n);
+
+    println!("\nTop {} words (approximate):", top.len());
+    for (rank, (word, count)) in top.iter().enumerate() {
+        println!("  {:>2}. {:<15} — ~{}", rank + 1, word, count);
+    }
+
+    println!("\nTotal: {} words", counts.total());
+    println!(
+        "Counts are estimates from a {} KiB sketch: never too low, and with {:.1}% \
+         confidence at most {} too high.",
+        counts.memory() / 1024,
+        (1.0 - sketch::FAILURE_PROBABILITY) * 100.0,
+        counts.error_bound()
+    );
+
+    if let Some(rate) = progress::throughput(bytes, counts.total() as usize, elapsed) {
+        println!("Throughput: {rate}");
+    }
+}
+
+fn display_near(near: &Cooccurrence, top_n: usize) {
+    let top = near.top(top_n);
+
+    if top.is_empty() {
+        println!(
+            "\nNo words found within {} of '{}'",
+            near.window(),
+            near.target()
+        );
+        return;
+    }
+
+    println!(
+        "\nWords near '{}' (window {}):",
+        near.target(),
+        near.window()
+    );
+    for (rank, (word, count)) in top.iter().enumerate() {
         println!("  {:>2}. {:<15} — {}", rank + 1, word, count);
     }
+}
 
-    println!("\nTotal: {total} words, {unique} unique");
+/// Words separated by commas, wrapped to lines of about `width` characters.
+fn wrap_words(words: &[String], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in words {
+        if !line.is_empty() && line.chars().count() + word.chars().count() + 2 > width {
+            lines.push(format!("{line},"));
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push_str(", ");
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
 }
 
-fn run() -> Result<(), String> {
-    let args: Vec<String> = env::args().collect();
+/// `dictionary` is the path of the word list, if one was given.
+fn display_rare(rare: &RareWords, dictionary: Option<&str>) {
+    const WIDTH: usize = 72;
+
+    if rare.hapax.is_empty() {
+        println!("\nNo words are used only once.");
+    } else {
+        println!("\nUsed once ({}):", rare.hapax.len());
+        let words: Vec<String> = rare.hapax.iter().map(|w| w.to_string()).collect();
+        for line in wrap_words(&words, WIDTH) {
+            println!("  {line}");
+        }
+    }
+
+    let Some(path) = dictionary else {
+        return;
+    };
+    if rare.unknown.is_empty() {
+        println!("\nEvery word is in {path}.");
+        return;
+    }
+    println!(
+        "\nNot in {path} ({}), least used first:",
+        rare.unknown.len()
+    );
+    let words: Vec<String> = rare
+        .unknown
+        .iter()
+        .map(|(word, count)| match count {
+            1 => word.to_string(),
+            _ => format!("{word} ({count})"),
+        })
+        .collect();
+    for line in wrap_words(&words, WIDTH) {
+        println!("  {line}");
+    }
+}
+
+fn load_dictionary(path: &str, tokenizer: &TokenizerOptions) -> Result<Dictionary, String> {
+    let file = File::open(path).map_err(|e| format!("Error reading '{path}': {e}"))?;
+    let dictionary = Dictionary::load(BufReader::new(file), tokenizer)
+        .map_err(|e| format!("Error reading '{path}': {e}"))?;
+    if dictionary.is_empty() {
+        return Err(format!("No words found in the dictionary '{path}'"));
+    }
+    Ok(dictionary)
+}
+
+fn write_cloud(path: &str, words: &[(&str, u64)]) -> Result<(), String> {
+    let svg = cloud::render(&cloud::layout(words));
+    fs::write(path, svg).map_err(|e| format!("Error writing '{path}': {e}"))?;
+    println!(
+        "\nWord cloud of the top {} words written to {path}",
+        words.len()
+    );
+    Ok(())
+}
+
+fn load_reference(path: &str, tokenizer: &TokenizerOptions) -> Result<ReferenceList, String> {
+    let file = File::open(path).map_err(|e| format!("Error reading '{path}': {e}"))?;
+    let reference = ReferenceList::load(BufReader::new(file), tokenizer)
+        .map_err(|e| format!("Error reading '{path}': {e}"))?;
+    if reference.is_empty() {
+        return Err(format!(
+            "No word counts found in the reference list '{path}'"
+        ));
+    }
+    Ok(reference)
+}
+
+/// `path` is the reference list's, for the heading.
+fn display_keyness(keyness: &Keyness, reference: &ReferenceList, path: &str, top_n: usize) {
+    println!(
+        "\nCompared with {path} ({} words), by log-likelihood:",
+        reference.total()
+    );
+    let lists = [
+        ("More common here", &keyness.over),
+        ("Less common here", &keyness.under),
+    ];
+    for (title, keywords) in lists {
+        if keywords.is_empty() {
+            println!("  {title}: nothing significant");
+            continue;
+        }
+        println!("  {title}:");
+        for (rank, k) in keywords.iter().take(top_n).enumerate() {
+            println!(
+                "    {:>2}. {:<15} — {:>6} vs {:<8} LL {:.1}",
+                rank + 1,
+                k.word,
+                k.count,
+                k.reference,
+                k.log_likelihood
+            );
+        }
+    }
+    println!(
+        "Only differences with LL ≥ {} (p < 0.05) are listed.",
+        keyness::SIGNIFICANT
+    );
+}
+
+fn display_line_stats(stats: &LineStats) {
+    println!("\nLines: {} ({} blank)", stats.lines(), stats.blank());
+    if stats.longest().is_empty() {
+        return;
+    }
+    println!(
+        "  Length: average {:.1}, median {}, longest {} characters",
+        stats.average(),
+        stats.median(),
+        stats.max()
+    );
+
+    println!("\nLongest lines:");
+    for line in stats.longest() {
+        println!(
+            "  line {:<6} {:>5} chars — {}",
+            line.number, line.length, line.preview
+        );
+    }
+}
+
+/// The first line of a paragraph, shortened for display.
+fn preview(paragraph: &str) -> String {
+    const MAX_CHARS: usize = 60;
+    let first = paragraph.lines().next().unwrap_or("").trim();
+    if first.chars().count() > MAX_CHARS {
+        format!("{}…", first.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        first.to_string()
+    }
+}
+
+fn display_dupes(lines: &[RepeatedLine], paragraphs: &[SimilarParagraphs]) {
+    if lines.is_empty() && paragraphs.is_empty() {
+        println!("\nNo duplicates found.");
+        return;
+    }
+
+    if !lines.is_empty() {
+        println!("\nRepeated lines:");
+        for line in lines {
+            let numbers: Vec<String> = line.line_numbers.iter().map(|n| n.to_string()).collect();
+            println!(
+                "  lines {:<15} — {}",
+                numbers.join(", "),
+                preview(&line.text)
+            );
+        }
+    }
+
+    if !paragraphs.is_empty() {
+        println!(
+            "\nSimilar paragraphs (≥{:.0}% alike):",
+            dupes::SIMILARITY_THRESHOLD * 100.0
+        );
+        for pair in paragraphs {
+            println!(
+                "  lines {} and {} — {:.0}% similar",
+                pair.first.line,
+                pair.second.line,
+                pair.similarity * 100.0
+            );
+            println!("    {}", preview(&pair.first.text));
+        }
+    }
+}
+
+fn is_url(arg: &str) -> bool {
+    arg.starts_with("https://") || arg.starts_with("http://")
+}
+
+#[cfg(feature = "url")]
+fn fetch_page(url: &str) -> Result<String, String> {
+    word_counter::fetch::fetch_text(url)
+}
+
+#[cfg(not(feature = "url"))]
+fn fetch_page(url: &str) -> Result<String, String> {
+    Err(format!(
+        "Can't fetch '{url}': word-counter was built without the url feature"
+    ))
+}
+
+/// Open a file, or download a web page, returning a reader of its text as
+/// UTF-8 and its size in bytes. A file is read as `encoding`, or as the
+/// encoding it looks to be in.
+fn open_input(
+    name: &str,
+    encoding: Option<&'static Encoding>,
+) -> Result<(Box<dyn Read>, u64), String> {
+    if is_url(name) {
+        let text = fetch_page(name)?;
+        let size = text.len() as u64;
+        return Ok((Box::new(Cursor::new(text.into_bytes())), size));
+    }
+
+    let file = File::open(name).map_err(|e| format!("Error reading '{name}': {e}"))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Error reading '{name}': {e}"))?
+        .len();
+    let decoded =
+        DecodeReader::new(file, encoding).map_err(|e| format!("Error reading '{name}': {e}"))?;
+    if decoded.encoding() == encoding_rs::UTF_8 {
+        return Ok((Box::new(decoded), size));
+    }
+    // The progress bar counts decoded bytes, which won't add up to the size
+    let how = if encoding.is_some() {
+        ""
+    } else {
+        " (detected)"
+    };
+    println!("Encoding: {}{how}", decoded.encoding().name());
+    Ok((Box::new(decoded), 0))
+}
+
+fn run_dupes(filename: &str, mut input: impl Read) -> Result<(), String> {
+    let mut text = String::new();
+    input
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Error reading '{filename}': {e}"))?;
+    display_dupes(
+        &dupes::repeated_lines(&text),
+        &dupes::similar_paragraphs(&text, dupes::SIMILARITY_THRESHOLD),
+    );
+    Ok(())
+}
+
+fn run_by_section(
+    filename: &str,
+    mut input: impl Read,
+    tokenizer: &TokenizerOptions,
+) -> Result<(), String> {
+    if !markdown::is_markdown(filename) {
+        return Err(format!(
+            "--by-section needs a Markdown file (.md or .markdown), not '{filename}'"
+        ));
+    }
+    let mut text = String::new();
+    input
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Error reading '{filename}': {e}"))?;
+    display_sections(&markdown::sections(&text, tokenizer));
+    Ok(())
+}
+
+fn run_dialogue(
+    filename: &str,
+    mut input: impl Read,
+    pattern: Option<&Regex>,
+    tokenizer: &TokenizerOptions,
+) -> Result<(), String> {
+    let mut text = String::new();
+    input
+        .read_to_string(&mut text)
+        .map_err(|e| format!("Error reading '{filename}': {e}"))?;
+    display_dialogue(
+        &dialogue::dialogue(&text, pattern, tokenizer),
+        pattern.is_some(),
+    );
+    Ok(())
+}
+
+/// One line per speaker, most words first, with their share of the words,
+/// their turns and their top words.
+fn display_dialogue(dialogue: &Dialogue, custom_pattern: bool) {
+    if dialogue.speakers.is_empty() {
+        if custom_pattern {
+            println!("\nNo lines start with the speaker pattern.");
+        } else {
+            println!("\nNo speakers found; lines should start like 'Name: ...', or describe them with --speaker-regex.");
+        }
+        return;
+    }
 
-    if args.len() != 2 {
-        return Err("Usage: word-counter <file>".to_string());
+    let speakers = dialogue.by_words();
+    let width = speakers
+        .iter()
+        .map(|s| s.name.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    println!("\nWords by speaker:");
+    for speaker in speakers {
+        let turns = if speaker.turns == 1 { "turn" } else { "turns" };
+        let top: Vec<&str> = speaker.counts.top(3).iter().map(|(w, _)| *w).collect();
+        println!(
+            "  {:<width$}  {:>6}  {:>5.1}%  {:>4} {turns:<5}  {}",
+            speaker.name,
+            speaker.counts.total(),
+            dialogue.share(speaker) * 100.0,
+            speaker.turns,
+            top.join(", ")
+        );
+    }
+
+    println!(
+        "\nTotal: {} words from {} speakers",
+        dialogue.total(),
+        dialogue.speakers.len()
+    );
+    if dialogue.unattributed > 0 {
+        println!(
+            "  ({} words before the first speaker weren't counted)",
+            dialogue.unattributed
+        );
     }
+}
+
+fn run_grouped(
+    filename: &str,
+    input: impl Read,
+    column: &str,
+    group_by: &str,
+    tokenizer: &TokenizerOptions,
+) -> Result<(), String> {
+    let groups = columns::grouped(
+        input,
+        columns::delimiter(filename),
+        column,
+        group_by,
+        tokenizer,
+    )
+    .map_err(|e| format!("Error reading '{filename}': {e}"))?;
+    display_groups(&groups, group_by);
+    Ok(())
+}
 
-    let filename = &args[1];
-    println!("Reading: {filename}");
+/// One line per value of the `--group-by` column with its words, rows and
+/// top words.
+fn display_groups(groups: &[Group], group_by: &str) {
+    if groups.is_empty() {
+        println!("\nNo rows found in the file.");
+        return;
+    }
 
-    let text =
-        fs::read_to_string(filename).map_err(|e| format!("Error reading '{filename}': {e}"))?;
+    let label = |g: &Group| {
+        if g.value.is_empty() {
+            "(empty)".to_string()
+        } else {
+            g.value.clone()
+        }
+    };
+    let width = groups
+        .iter()
+        .map(|g| label(g).chars().count())
+        .max()
+        .unwrap_or(0);
 
-    let counts = count_words(&text);
+    println!("\nWords by {group_by}:");
+    for group in groups {
+        let rows = if group.rows == 1 { "row" } else { "rows" };
+        let top: Vec<&str> = group.counts.top(3).iter().map(|(w, _)| *w).collect();
+        println!(
+            "  {:<width$}  {:>6}  {:>5} {rows:<4}  {}",
+            label(group),
+            group.counts.total(),
+            group.rows,
+            top.join(", ")
+        );
+    }
+
+    let words: usize = groups.iter().map(|g| g.counts.total()).sum();
+    let rows: usize = groups.iter().map(|g| g.rows).sum();
+    println!(
+        "\nTotal: {words} words in {rows} rows, {} groups",
+        groups.len()
+    );
+}
+
+/// One line per section, indented by heading level, with its own word count,
+/// the count including its subsections when it has any, and its top words.
+fn display_sections(sections: &[Section]) {
+    if sections.is_empty() {
+        println!("\nNo words found in the file.");
+        return;
+    }
+
+    let titles: Vec<String> = sections
+        .iter()
+        .map(|s| format!("{}{}", "  ".repeat(s.level.saturating_sub(1)), s.title))
+        .collect();
+    let width = titles.iter().map(|t| t.chars().count()).max().unwrap_or(0);
+
+    println!("\nWords by section:");
+    for (i, (section, title)) in sections.iter().zip(&titles).enumerate() {
+        let own = section.counts.total();
+        let nested = markdown::nested_total(sections, i);
+        let total = if nested > own {
+            format!("{own:>6} ({nested} with subsections)")
+        } else {
+            format!("{own:>6}")
+        };
+        let top: Vec<&str> = section.counts.top(3).iter().map(|(w, _)| *w).collect();
+        println!("  {title:<width$}  {total}  {}", top.join(", "));
+    }
+
+    let total: usize = sections.iter().map(|s| s.counts.total()).sum();
+    println!("\nTotal: {total} words in {} sections", sections.len());
+}
 
+/// Count `phrase` in each input, streaming so large files stay cheap.
+fn run_phrase(
+    phrase: &str,
+    files: &[String],
+    tokenizer: &TokenizerOptions,
+    encoding: Option<&'static Encoding>,
+) -> Result<(), String> {
+    let mut counter = PhraseCounter::new(phrase, tokenizer)?;
+    let mut counts = Vec::new();
+
+    for name in files {
+        let (input, size) = open_input(name, encoding)?;
+        let mut progress = Progress::new(size);
+        counter.reset();
+        word_counter::stream_words(input, tokenizer, &mut progress, |word| counter.push(&word))
+            .map_err(|e| format!("Error reading '{name}': {e}"))?;
+        counts.push((name.as_str(), counter.count()));
+    }
+
+    display_phrase(&counter.phrase(), &counts);
+    Ok(())
+}
+
+fn display_phrase(phrase: &str, counts: &[(&str, usize)]) {
+    let total: usize = counts.iter().map(|(_, n)| n).sum();
+    let width = counts
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain(std::iter::once("Total".len()))
+        .max()
+        .unwrap_or(0);
+
+    println!("\nOccurrences of \"{phrase}\":");
+    for (name, count) in counts {
+        println!("  {name:<width$}  {count:>6}");
+    }
+    if counts.len() > 1 {
+        println!("  {:<width$}  {total:>6}", "Total");
+    }
+}
+
+/// Every file under `path`, recursing into directories, in name order.
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)
+        .and_then(|dir| dir.map(|e| e.map(|e| e.path())).collect())
+        .map_err(|e| format!("Error reading '{}': {e}", path.display()))?;
+    entries.sort();
+    for entry in entries {
+        collect_files(&entry, files)?;
+    }
+    Ok(())
+}
+
+/// Index every file under `paths` once. Files that aren't UTF-8 text are
+/// reported and skipped.
+fn load_corpus(paths: &[String]) -> Result<Corpus, String> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect_files(Path::new(path), &mut files)?;
+    }
+
+    let tokenizer = TokenizerOptions::default();
+    let mut corpus = Corpus::new();
+    for file in &files {
+        let name = file.display().to_string();
+        let (input, size) = open_input(&name, None)?;
+        let mut progress = Progress::new(size);
+        if let Err(e) = corpus.add_document(&name, input, &tokenizer, &mut progress) {
+            eprintln!("Skipped '{name}': {e}");
+        }
+    }
+    Ok(corpus)
+}
+
+fn display_ranked(title: &str, rows: &[(&str, usize)], empty: &str) {
+    if rows.is_empty() {
+        println!("{empty}");
+        return;
+    }
+
+    println!("{title}");
+    for (rank, (name, count)) in rows.iter().enumerate() {
+        println!("  {:>2}. {:<15} — {}", rank + 1, name, count);
+    }
+}
+
+fn answer(corpus: &Corpus, query: &Query) {
+    match query {
+        Query::Count(word) => println!("{word}: {}", corpus.count(word)),
+        Query::Top(n) => display_ranked(
+            &format!("Top {} words:", n.min(&corpus.unique())),
+            &corpus.top(*n),
+            "No words loaded",
+        ),
+        Query::Near { word, window } => display_ranked(
+            &format!("Words near '{word}' (window {window}):"),
+            &corpus.near(word, *window, 10),
+            &format!("No words found within {window} of '{word}'"),
+        ),
+        Query::FilesContaining(word) => display_ranked(
+            &format!("Files containing '{word}':"),
+            &corpus.files_containing(word),
+            &format!("No files contain '{word}'"),
+        ),
+        Query::Help => println!("{}", corpus::QUERY_USAGE),
+    }
+}
+
+/// Load the corpus once, then answer queries from stdin until EOF or quit.
+fn run_repl(paths: &[String]) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let started = Instant::now();
+    let corpus = load_corpus(paths)?;
+    println!(
+        "Loaded {} files, {} words ({} unique) in {:.1?}",
+        corpus.document_count(),
+        corpus.total(),
+        corpus.unique(),
+        started.elapsed()
+    );
+    println!("{}", corpus::QUERY_USAGE);
+
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => return Err(format!("Error reading input: {e}")),
+        }
+
+        let line = line.trim();
+        match line {
+            "" => continue,
+            "quit" | "exit" => break,
+            _ => match line.parse::<Query>() {
+                Ok(query) => answer(&corpus, &query),
+                Err(e) => println!("{e}"),
+            },
+        }
+    }
+
+    Ok(())
+}
+
+fn count_file(name: &str, tokenizer: &TokenizerOptions) -> Result<WordCounts, String> {
+    let (input, size) = open_input(name, None)?;
+    let mut counts = WordCounts::new();
+    word_counter::stream_words(input, tokenizer, &mut Progress::new(size), |word| {
+        counts.add(word)
+    })
+    .map_err(|e| format!("Error reading '{name}': {e}"))?;
+    Ok(counts)
+}
+
+/// Count every file under `options.files` as one text, taking the counts of
+/// files whose content is in the index from there and saving the rest.
+fn run_indexed(options: &Options, path: &str) -> Result<(), String> {
+    let tokenizer = options.tokenizer();
+    let encoding = options.encoding.map_or("auto", |e| e.name());
+    let settings = format!("profile={} encoding={encoding}", options.profile);
+    let path = Path::new(path);
+    let mut index = Index::load(path, &settings)?;
+
+    let mut files = Vec::new();
+    for name in &options.files {
+        collect_files(Path::new(name), &mut files)?;
+    }
+    // Not the index itself, when it's kept in the directory it covers
+    let own = fs::canonicalize(path).ok();
+    files.retain(|file| own.is_none() || fs::canonicalize(file).ok() != own);
+
+    let started = Instant::now();
+    let mut bytes = 0;
+    let mut counts = WordCounts::new();
+    for file in &files {
+        let name = file.display().to_string();
+        let content = fs::read(file).map_err(|e| format!("Error reading '{name}': {e}"))?;
+        bytes += content.len() as u64;
+        let hash = index::content_hash(&content);
+        if let Some(saved) = index.get(&hash) {
+            counts.merge(saved);
+            continue;
+        }
+
+        let mut file_counts = WordCounts::new();
+        let input = DecodeReader::new(Cursor::new(content), options.encoding)
+            .map_err(|e| format!("Error reading '{name}': {e}"))?;
+        word_counter::stream_words(input, &tokenizer, &mut Progress::new(0), |word| {
+            file_counts.add(word)
+        })
+        .map_err(|e| format!("Error reading '{name}': {e}"))?;
+        counts.merge(&file_counts);
+        index.insert(hash, file_counts);
+    }
+    index.save(path)?;
+
+    println!(
+        "Files: {} ({} from the index, {} counted)",
+        files.len(),
+        index.hits,
+        index.misses
+    );
     if counts.is_empty() {
+        println!("No words found in the files.");
+        return Ok(());
+    }
+    display_results(&counts, 10, options, None, None, bytes, started.elapsed());
+    Ok(())
+}
+
+/// Compare the words of two versions of a text.
+fn run_delta(paths: &[String]) -> Result<(), String> {
+    let [old, new] = paths else {
+        return Err(USAGE.to_string());
+    };
+    let tokenizer = TokenizerOptions::default();
+    let delta = delta::delta(&count_file(old, &tokenizer)?, &count_file(new, &tokenizer)?);
+    display_delta(&delta, old, new, 10);
+    Ok(())
+}
+
+fn display_delta(delta: &Delta, old: &str, new: &str, top_n: usize) {
+    println!(
+        "{old}: {} words
+{new}: {} words",
+        delta.old_total, delta.new_total
+    );
+    println!(
+        "
++{} added, -{} removed (net {:+})",
+        delta.added,
+        delta.removed,
+        delta.net()
+    );
+
+    let lists = [
+        ("New words", &delta.introduced),
+        ("Removed words", &delta.dropped),
+    ];
+    for (title, words) in lists {
+        if words.is_empty() {
+            println!(
+                "
+{title}: none"
+            );
+            continue;
+        }
+        println!(
+            "
+{title} ({}):",
+            words.len()
+        );
+        for (rank, (word, count)) in words.iter().take(top_n).enumerate() {
+            println!("  {:>2}. {:<15} — {}", rank + 1, word, count);
+        }
+    }
+}
+
+/// How many lines of a `--jsonl` file went uncounted, and why.
+fn display_field_stats(field: &str, stats: &FieldStats) {
+    let mut notes = Vec::new();
+    if stats.malformed > 0 {
+        notes.push(format!("{} skipped as malformed", stats.malformed));
+    }
+    if stats.missing > 0 {
+        notes.push(format!("{} without a '{field}' field", stats.missing));
+    }
+    if !notes.is_empty() {
+        println!("Lines: {} ({})", stats.lines, notes.join(", "));
+    }
+}
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("repl") => return run_repl(&args[1..]),
+        Some("delta") => return run_delta(&args[1..]),
+        _ => {}
+    }
+    let options = parse_args(&args)?;
+
+    if let Some(phrase) = &options.phrase {
+        return run_phrase(
+            phrase,
+            &options.files,
+            &options.tokenizer(),
+            options.encoding,
+        );
+    }
+
+    if let Some(path) = &options.index {
+        println!("Reading: {}", options.files.join(", "));
+        return run_indexed(&options, path);
+    }
+
+    let filename = &options.files[0];
+    let verb = if is_url(filename) {
+        "Fetching"
+    } else {
+        "Reading"
+    };
+    println!("{verb}: {filename}");
+
+    let (input, size) = open_input(filename, options.encoding)?;
+
+    if let (Some(column), Some(group_by)) = (&options.column, &options.group_by) {
+        return run_grouped(filename, input, column, group_by, &options.tokenizer());
+    }
+    // Everything below reads just the column's text, a line per row. The
+    // progress bar would count those bytes against the file's size, so it's
+    // left off.
+    let (input, size): (Box<dyn Read>, u64) = match &options.column {
+        Some(column) => (
+            Box::new(
+                ColumnReader::new(input, columns::delimiter(filename), column)
+                    .map_err(|e| format!("Error reading '{filename}': {e}"))?,
+            ),
+            0,
+        ),
+        None => (input, size),
+    };
+    // Likewise just the field's text, a line per record
+    let mut field_stats = options.field.is_some().then(FieldStats::default);
+    let (input, size): (Box<dyn Read + '_>, u64) = match (&options.field, field_stats.as_mut()) {
+        (Some(field), Some(stats)) => (Box::new(stats.reader(input, field)), 0),
+        _ => (input, size),
+    };
+
+    if options.dupes {
+        run_dupes(filename, input)?;
+        if let (Some(field), Some(stats)) = (&options.field, &field_stats) {
+            display_field_stats(field, stats);
+        }
+        return Ok(());
+    }
+    if options.by_section {
+        return run_by_section(filename, input, &options.tokenizer());
+    }
+    if options.dialogue {
+        return run_dialogue(
+            filename,
+            input,
+            options.speaker_regex.as_ref(),
+            &options.tokenizer(),
+        );
+    }
+
+    let mut progress = Progress::new(size);
+    let tokenizer = options.tokenizer();
+    // Loaded up front so a bad path fails before a long count
+    let dictionary = match &options.dict {
+        Some(path) => Some((path.as_str(), load_dictionary(path, &tokenizer)?)),
+        None => None,
+    };
+    let reference = match &options.reference {
+        Some(path) => Some((path.as_str(), load_reference(path, &tokenizer)?)),
+        None => None,
+    };
+    // Measures every line, even when --sample counts only some
+    let mut line_stats = options.line_stats.then(LineStats::default);
+    let input: Box<dyn Read + '_> = match line_stats.as_mut() {
+        Some(stats) => Box::new(stats.reader(input)),
+        None => input,
+    };
+    let mut counts = WordCounts::new();
+    let mut approx = options.approx.then(ApproxCounts::default);
+    let mut spill = options.max_unique.map(SpillCounts::new);
+    let mut forms = options.stem.then(StemForms::new);
+    let mut near = options.near.clone().map(|target| {
+        let target = if options.stem {
+            stem::stem(&target)
+        } else {
+            target
+        };
+        Cooccurrence::new(target, options.window)
+    });
+
+    let mut on_word = |word: String| {
+        let word = match forms.as_mut() {
+            Some(forms) => {
+                let stemmed = stem::stem(&word);
+                forms.add(&stemmed, &word);
+                stemmed
+            }
+            None => word,
+        };
+        if let Some(near) = near.as_mut() {
+            near.push(&word);
+        }
+        match (approx.as_mut(), spill.as_mut()) {
+            (Some(approx), _) => approx.add(word),
+            (None, Some(spill)) => spill.add(word),
+            (None, None) => counts.add(word),
+        }
+    };
+
+    // A sampler reads the whole input itself, so it drives the progress bar
+    let (progress, sampled) = match options.sample {
+        Some(rate) => {
+            let mut sampler = LineSampler::new(input, rate, progress);
+            word_counter::stream_words(
+                &mut sampler,
+                &tokenizer,
+                &mut Progress::new(0),
+                &mut on_word,
+            )
+            .map_err(|e| format!("Error reading '{filename}': {e}"))?;
+            let stats = sampler.stats();
+            (sampler.finish(), Some(stats))
+        }
+        None => {
+            word_counter::stream_words(input, &tokenizer, &mut progress, &mut on_word)
+                .map_err(|e| format!("Error reading '{filename}': {e}"))?;
+            (progress, None)
+        }
+    };
+
+    if let Some(stats) = line_stats.as_mut() {
+        stats.finish();
+    }
+    if let (Some(field), Some(stats)) = (&options.field, &field_stats) {
+        display_field_stats(field, stats);
+    }
+
+    if let Some(spill) = spill {
+        let merged = spill
+            .finish(options.sort, options.reverse, 10)
+            .map_err(|e| format!("Error spilling counts to disk: {e}"))?;
+        if merged.unique == 0 {
+            println!("No words found in the file.");
+            return Ok(());
+        }
+        display_merged(
+            &merged,
+            &options,
+            progress.bytes_processed(),
+            progress.elapsed(),
+        );
+        if let Some(stats) = &line_stats {
+            display_line_stats(stats);
+        }
+        return Ok(());
+    }
+
+    if counts.is_empty() && approx.as_ref().is_none_or(|a| a.is_empty()) {
         println!("No words found in the file.");
         return Ok(());
     }
 
-    display_results(&counts, 10);
+    match &approx {
+        Some(approx) => display_approx(approx, 10, progress.bytes_processed(), progress.elapsed()),
+        None => display_results(
+            &counts,
+            10,
+            &options,
+            forms.as_ref(),
+            sampled.as_ref(),
+            progress.bytes_processed(),
+            progress.elapsed(),
+        ),
+    }
+
+    if let Some(near) = &near {
+        display_near(near, 10);
+    }
+
+    if options.rare {
+        let rare = dictionary::rare_words(&counts, dictionary.as_ref().map(|(_, d)| d));
+        display_rare(&rare, dictionary.as_ref().map(|(path, _)| *path));
+    }
+
+    if let Some((path, reference)) = &reference {
+        display_keyness(&keyness::keyness(&counts, reference), reference, path, 10);
+    }
+
+    if let Some(stats) = &line_stats {
+        display_line_stats(stats);
+    }
+
+    if let Some(path) = &options.cloud {
+        let top: Vec<(&str, u64)> = match &approx {
+            Some(approx) => approx.top(cloud::MAX_WORDS),
+            None => counts
+                .top(cloud::MAX_WORDS)
+                .into_iter()
+                .map(|(word, n)| (word, n as u64))
+                .collect(),
+        };
+        write_cloud(path, &top)?;
+    }
 
     Ok(())
 }
@@ -71,43 +1457,208 @@ fn main() {
 mod tests {
     use super::*;
 
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args_near() {
+        let opts = parse_args(&args(&["a.txt", "--near", "Rust,", "--window", "3"])).unwrap();
+        assert_eq!(opts.files, vec!["a.txt"]);
+        assert_eq!(opts.near.as_deref(), Some("rust"));
+        assert_eq!(opts.window, 3);
+    }
+
     #[test]
-    fn test_count_simple() {
-        let counts = count_words("hello world hello");
-        assert_eq!(counts.get("hello"), Some(&2));
-        assert_eq!(counts.get("world"), Some(&1));
+    fn test_parse_args_sort() {
+        let opts = parse_args(&args(&["a.txt", "--sort", "length", "--reverse"])).unwrap();
+        assert_eq!(opts.sort, SortOrder::Length);
+        assert!(opts.reverse);
+        assert!(!opts.stem);
+        assert!(parse_args(&args(&["a.txt", "--stem"])).unwrap().stem);
+        assert!(parse_args(&args(&["a.txt", "--dupes"])).unwrap().dupes);
+        assert!(
+            parse_args(&args(&["a.md", "--by-section"]))
+                .unwrap()
+                .by_section
+        );
+        assert!(parse_args(&args(&["a.txt", "--approx"])).unwrap().approx);
+        assert!(parse_args(&args(&["a.txt", "--approx", "--sort", "alpha"])).is_err());
+        assert!(parse_args(&args(&["a.txt", "--sort", "size"])).is_err());
     }
 
     #[test]
-    fn test_count_case_insensitive() {
-        let counts = count_words("Hello HELLO hello");
-        assert_eq!(counts.get("hello"), Some(&3));
-        assert_eq!(counts.len(), 1);
+    fn test_parse_args_profile() {
+        let opts = parse_args(&args(&["a.rs", "--profile", "code", "--near", "std::io"])).unwrap();
+        assert_eq!(opts.profile, Profile::Code);
+        assert_eq!(opts.near.as_deref(), Some("std::io"));
+        assert_eq!(
+            parse_args(&args(&["a.txt"])).unwrap().profile,
+            Profile::Prose
+        );
+        assert!(parse_args(&args(&["a.txt", "--profile", "json"])).is_err());
+        assert!(parse_args(&args(&["a.txt", "--near", "two words"])).is_err());
     }
 
     #[test]
-    fn test_count_punctuation() {
-        let counts = count_words("hello, world! hello.");
-        assert_eq!(counts.get("hello"), Some(&2));
-        assert_eq!(counts.get("world"), Some(&1));
+    fn test_parse_args_sample() {
+        let opts = parse_args(&args(&["big.txt", "--sample", "10%"])).unwrap();
+        assert_eq!(opts.sample, Some(0.1));
+        assert_eq!(parse_args(&args(&["big.txt"])).unwrap().sample, None);
+        assert!(parse_args(&args(&["big.txt", "--sample", "2"])).is_err());
+        assert!(parse_args(&args(&["big.txt", "--sample", "0.1", "--approx"])).is_err());
     }
 
     #[test]
-    fn test_count_empty() {
-        let counts = count_words("");
-        assert!(counts.is_empty());
+    fn test_parse_args_rare() {
+        let opts = parse_args(&args(&["a.txt", "--rare", "--dict", "words.txt"])).unwrap();
+        assert!(opts.rare);
+        assert_eq!(opts.dict.as_deref(), Some("words.txt"));
+        assert!(parse_args(&args(&["a.txt", "--rare"]))
+            .unwrap()
+            .dict
+            .is_none());
+        assert!(parse_args(&args(&["a.txt", "--dict", "words.txt"])).is_err());
+        assert!(parse_args(&args(&["a.txt", "--rare", "--stem"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_line_stats() {
+        let opts = parse_args(&args(&["a.log", "--line-stats", "--sample", "10%"])).unwrap();
+        assert!(opts.line_stats);
+        assert!(!parse_args(&args(&["a.log"])).unwrap().line_stats);
+        assert!(parse_args(&args(&["a.log", "--line-stats", "--dupes"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_reference() {
+        let opts = parse_args(&args(&["a.txt", "--reference", "brown.freq"])).unwrap();
+        assert_eq!(opts.reference.as_deref(), Some("brown.freq"));
+        assert!(parse_args(&args(&["a.txt"])).unwrap().reference.is_none());
+        assert!(parse_args(&args(&["a.txt", "--reference"])).is_err());
+        assert!(parse_args(&args(&["a.txt", "--reference", "b.freq", "--approx"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_cloud() {
+        let opts = parse_args(&args(&["a.txt", "--cloud", "out.svg", "--approx"])).unwrap();
+        assert_eq!(opts.cloud.as_deref(), Some("out.svg"));
+        assert!(parse_args(&args(&["a.txt", "--cloud"])).is_err());
+        assert!(parse_args(&args(&["a.md", "--cloud", "out.svg", "--by-section"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_dialogue() {
+        let opts = parse_args(&args(&["chat.txt", "--dialogue"])).unwrap();
+        assert!(opts.dialogue);
+        assert!(opts.speaker_regex.is_none());
+        // A pattern implies --dialogue
+        let opts = parse_args(&args(&["chat.log", "--speaker-regex", r"<(\w+)>"])).unwrap();
+        assert!(opts.dialogue);
+        assert!(opts.speaker_regex.is_some());
+        assert!(parse_args(&args(&["chat.log", "--speaker-regex", "(a|b"])).is_err());
+        assert!(parse_args(&args(&["chat.txt", "--dialogue", "--stem"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_encoding() {
+        let opts = parse_args(&args(&["old.txt", "--encoding", "latin1"])).unwrap();
+        assert_eq!(opts.encoding, Some(encoding_rs::WINDOWS_1252));
+        assert!(parse_args(&args(&["old.txt"])).unwrap().encoding.is_none());
+        assert!(parse_args(&args(&["old.txt", "--encoding", "ebcdic-ish"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_jsonl() {
+        let opts = parse_args(&args(&["app.log", "--jsonl", "--field", "message"])).unwrap();
+        assert_eq!(opts.field.as_deref(), Some("message"));
+        assert!(parse_args(&args(&["app.log", "--jsonl", "--field", "msg", "--dupes"])).is_ok());
+        assert!(parse_args(&args(&["app.log", "--jsonl"])).is_err());
+        assert!(parse_args(&args(&["app.log", "--field", "message"])).is_err());
+        assert!(parse_args(&args(&[
+            "app.log", "--jsonl", "--field", "message", "--column", "text"
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_args_index() {
+        let opts = parse_args(&args(&["docs", "notes.txt", "--index", ".wcindex"])).unwrap();
+        assert_eq!(opts.index.as_deref(), Some(".wcindex"));
+        assert_eq!(opts.files, ["docs", "notes.txt"]);
+        assert!(parse_args(&args(&["docs", "--index", "i", "--sort", "alpha"])).is_ok());
+        assert!(parse_args(&args(&["docs", "--index", "i", "--stem"])).is_err());
+        assert!(parse_args(&args(&["docs", "--index"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_args_max_unique() {
+        let opts = parse_args(&args(&[
+            "big.txt",
+            "--max-unique",
+            "1000",
+            "--sort",
+            "alpha",
+        ]))
+        .unwrap();
+        assert_eq!(opts.max_unique, Some(1000));
+        assert!(parse_args(&args(&["big.txt", "--max-unique", "0"])).is_err());
+        assert!(parse_args(&args(&["big.txt", "--max-unique", "10", "--stem"])).is_err());
+        assert!(parse_args(&args(&["big.txt", "--max-unique", "10", "--line-stats"])).is_ok());
+    }
+
+    #[test]
+    fn test_parse_args_column() {
+        let opts = parse_args(&args(&["reviews.csv", "--column", "comment_text"])).unwrap();
+        assert_eq!(opts.column.as_deref(), Some("comment_text"));
+        assert!(opts.group_by.is_none());
+        // A plain column count still takes the usual extras
+        assert!(parse_args(&args(&["r.csv", "--column", "text", "--stem", "--dupes"])).is_ok());
+        let opts = parse_args(&args(&[
+            "reviews.csv",
+            "--column",
+            "comment_text",
+            "--group-by",
+            "rating",
+        ]))
+        .unwrap();
+        assert_eq!(opts.group_by.as_deref(), Some("rating"));
+        assert!(parse_args(&args(&["r.csv", "--group-by", "rating"])).is_err());
+        assert!(parse_args(&args(&[
+            "r.csv",
+            "--column",
+            "a",
+            "--group-by",
+            "b",
+            "--stem"
+        ]))
+        .is_err());
+        assert!(parse_args(&args(&["r.md", "--column", "a", "--by-section"])).is_err());
+    }
+
+    #[test]
+    fn test_wrap_words() {
+        let words: Vec<String> = ["alpha", "beta", "gamma", "delta"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(wrap_words(&words, 12), ["alpha, beta,", "gamma, delta"]);
+        assert_eq!(wrap_words(&words, 80), ["alpha, beta, gamma, delta"]);
     }
 
     #[test]
-    fn test_count_whitespace_only() {
-        let counts = count_words("   \n\t  ");
-        assert!(counts.is_empty());
+    fn test_parse_args_phrase_takes_several_files() {
+        let opts = parse_args(&args(&["a.log", "b.log", "--phrase", "error while"])).unwrap();
+        assert_eq!(opts.files, vec!["a.log", "b.log"]);
+        assert_eq!(opts.phrase.as_deref(), Some("error while"));
+        assert!(parse_args(&args(&["a.log", "--phrase"])).is_err());
     }
 
     #[test]
-    fn test_preserves_apostrophes() {
-        let counts = count_words("don't can't won't");
-        assert_eq!(counts.get("don't"), Some(&1));
-        assert_eq!(counts.get("can't"), Some(&1));
+    fn test_parse_args_errors() {
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["a.txt", "b.txt"])).is_err());
+        assert!(parse_args(&args(&["a.txt", "--window", "0"])).is_err());
+        assert!(parse_args(&args(&["a.txt", "--near"])).is_err());
     }
 }
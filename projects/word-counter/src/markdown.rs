@@ -0,0 +1,191 @@
+//! Splitting a Markdown document into heading sections and counting the
+//! words in each, so authors can track chapter lengths.
+
+use crate::{for_each_word, TokenizerOptions, WordCounts};
+use std::path::Path;
+
+/// One heading and the words under it, up to the next heading of any level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// 1–6 for `#` to `######`; 0 for text before the first heading
+    pub level: usize,
+    pub title: String,
+    /// 1-based line of the heading
+    pub line: usize,
+    /// Words in the body, not counting the heading itself
+    pub counts: WordCounts,
+}
+
+pub fn is_markdown(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| matches!(e.to_lowercase().as_str(), "md" | "markdown"))
+}
+
+/// `## Title ##` → (2, "Title"). Needs the space after the hashes, so
+/// `#hashtag` stays text.
+fn atx_heading(line: &str) -> Option<(usize, String)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let title = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, title.to_string()))
+}
+
+/// A `===` or `---` line under a paragraph line makes it a level 1 or 2
+/// heading.
+fn setext_level(line: &str) -> Option<usize> {
+    let trimmed = line.trim();
+    if !trimmed.is_empty() && trimmed.chars().all(|c| c == '=') {
+        Some(1)
+    } else if trimmed.len() >= 2 && trimmed.chars().all(|c| c == '-') {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+fn is_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// Sections in document order. Text before the first heading gets a level
+/// 0 section only if it has words. Headings inside fenced code blocks are
+/// code, not structure.
+pub fn sections(text: &str, options: &TokenizerOptions) -> Vec<Section> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut sections = vec![Section {
+        level: 0,
+        title: "(before the first heading)".to_string(),
+        line: 1,
+        counts: WordCounts::new(),
+    }];
+    let mut in_code = false;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let heading = if is_fence(line) {
+            in_code = !in_code;
+            None
+        } else if in_code {
+            None
+        } else if let Some(heading) = atx_heading(line) {
+            Some((heading, 1))
+        } else {
+            let underline = lines.get(i + 1).and_then(|next| setext_level(next));
+            underline
+                .filter(|_| !line.trim().is_empty())
+                .map(|level| ((level, line.trim().to_string()), 2))
+        };
+
+        match heading {
+            Some(((level, title), consumed)) => {
+                sections.push(Section {
+                    level,
+                    title,
+                    line: i + 1,
+                    counts: WordCounts::new(),
+                });
+                i += consumed;
+            }
+            None => {
+                let counts = &mut sections.last_mut().expect("never empty").counts;
+                for_each_word(line, options, &mut |word| counts.add(word));
+                i += 1;
+            }
+        }
+    }
+
+    if sections[0].counts.is_empty() {
+        sections.remove(0);
+    }
+    sections
+}
+
+/// Words in `sections[index]` plus every subsection under it. Text before
+/// the first heading has no subsections.
+pub fn nested_total(sections: &[Section], index: usize) -> usize {
+    let level = sections[index].level;
+    if level == 0 {
+        return sections[index].counts.total();
+    }
+    let end = sections[index + 1..]
+        .iter()
+        .position(|s| s.level <= level)
+        .map_or(sections.len(), |offset| index + 1 + offset);
+    sections[index..end].iter().map(|s| s.counts.total()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(text: &str) -> Vec<(usize, String, usize)> {
+        sections(text, &TokenizerOptions::default())
+            .into_iter()
+            .map(|s| (s.level, s.title, s.counts.total()))
+            .collect()
+    }
+
+    #[test]
+    fn test_atx_headings() {
+        assert_eq!(atx_heading("## Setup ##"), Some((2, "Setup".to_string())));
+        assert_eq!(atx_heading("# Title"), Some((1, "Title".to_string())));
+        assert_eq!(atx_heading("#hashtag"), None);
+        assert_eq!(atx_heading("####### seven"), None);
+        assert_eq!(atx_heading("    # indented code"), None);
+    }
+
+    #[test]
+    fn test_words_go_to_the_nearest_heading() {
+        let text = "Preface words here\n\n# One\nalpha beta\n\n## One A\ngamma\n\n# Two\ndelta";
+        assert_eq!(
+            outline(text),
+            vec![
+                (0, "(before the first heading)".to_string(), 3),
+                (1, "One".to_string(), 2),
+                (2, "One A".to_string(), 1),
+                (1, "Two".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_setext_headings_and_code_fences() {
+        let text = "Title\n=====\nintro\n\n```\n# not a heading\n```\n\nPart\n----\nbody text";
+        assert_eq!(
+            outline(text),
+            vec![(1, "Title".to_string(), 4), (2, "Part".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn test_nested_total() {
+        let s = sections(
+            "# A\none\n## A1\ntwo three\n### A1a\nfour\n# B\nfive",
+            &TokenizerOptions::default(),
+        );
+        assert_eq!(nested_total(&s, 0), 4);
+        assert_eq!(nested_total(&s, 1), 3);
+        assert_eq!(nested_total(&s, 3), 1);
+
+        let with_preface = sections("before\n# A\none", &TokenizerOptions::default());
+        assert_eq!(nested_total(&with_preface, 0), 1);
+    }
+
+    #[test]
+    fn test_is_markdown() {
+        assert!(is_markdown("book/ch1.md"));
+        assert!(is_markdown("README.Markdown"));
+        assert!(!is_markdown("notes.txt"));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::TokenizerOptions;
+use std::collections::VecDeque;
+
+/// Counts occurrences of a multi-word phrase in a stream of normalized
+/// words, so "Error, while parsing" matches `error while parsing`. Matches
+/// may overlap: "ha ha" occurs twice in "ha ha ha".
+pub struct PhraseCounter {
+    phrase: Vec<String>,
+    recent: VecDeque<String>,
+    count: usize,
+}
+
+impl PhraseCounter {
+    /// Normalize `phrase` the same way counted words are. Fails if no words
+    /// are left.
+    pub fn new(phrase: &str, options: &TokenizerOptions) -> Result<Self, String> {
+        let mut words = Vec::new();
+        crate::for_each_word(phrase, options, &mut |w| words.push(w));
+
+        if words.is_empty() {
+            return Err(format!("No words in phrase: '{phrase}'"));
+        }
+
+        Ok(PhraseCounter {
+            recent: VecDeque::with_capacity(words.len()),
+            phrase: words,
+            count: 0,
+        })
+    }
+
+    /// The phrase as matched, e.g. "error while parsing"
+    pub fn phrase(&self) -> String {
+        self.phrase.join(" ")
+    }
+
+    /// Feed the next word of the stream.
+    pub fn push(&mut self, word: &str) {
+        if self.recent.len() == self.phrase.len() {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(word.to_string());
+
+        if self.recent.iter().eq(self.phrase.iter()) {
+            self.count += 1;
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Start over for another file, keeping the phrase.
+    pub fn reset(&mut self) {
+        self.recent.clear();
+        self.count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(phrase: &str, text: &str) -> usize {
+        let options = TokenizerOptions::default();
+        let mut counter = PhraseCounter::new(phrase, &options).unwrap();
+        crate::for_each_word(text, &options, &mut |w| counter.push(&w));
+        counter.count()
+    }
+
+    #[test]
+    fn test_case_and_punctuation_tolerant() {
+        let text = "Error while parsing. ERROR, while parsing! error while (parsing)";
+        assert_eq!(count("error while parsing", text), 3);
+    }
+
+    #[test]
+    fn test_must_be_consecutive() {
+        assert_eq!(count("error while parsing", "error while not parsing"), 0);
+        assert_eq!(count("ha ha", "ha ha ha"), 2);
+    }
+
+    #[test]
+    fn test_empty_phrase_rejected() {
+        assert!(PhraseCounter::new("  ...  ", &TokenizerOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_reset_between_files() {
+        let options = TokenizerOptions::default();
+        let mut counter = PhraseCounter::new("a b", &options).unwrap();
+        counter.push("a");
+        counter.reset();
+        counter.push("b");
+        assert_eq!(counter.count(), 0);
+    }
+}
@@ -0,0 +1,134 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// Inputs at least this large (in bytes) get a progress bar on stderr.
+pub const PROGRESS_THRESHOLD: u64 = 10 * 1024 * 1024;
+
+const BAR_WIDTH: usize = 40;
+
+pub struct Progress {
+    total_bytes: u64,
+    processed: u64,
+    enabled: bool,
+    last_percent: Option<u64>,
+    started: Instant,
+}
+
+impl Progress {
+    pub fn new(total_bytes: u64) -> Self {
+        Progress {
+            total_bytes,
+            processed: 0,
+            enabled: total_bytes >= PROGRESS_THRESHOLD,
+            last_percent: None,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn advance(&mut self, bytes: usize) {
+        self.processed += bytes as u64;
+
+        if !self.enabled {
+            return;
+        }
+
+        // Only redraw when the visible percentage changes
+        let percent = percent(self.processed, self.total_bytes);
+        if self.last_percent != Some(percent) {
+            self.last_percent = Some(percent);
+            eprint!("\r{}", render_bar(self.processed, self.total_bytes));
+            io::stderr().flush().ok();
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if self.enabled {
+            eprintln!("\r{}", render_bar(self.total_bytes, self.total_bytes));
+        }
+    }
+
+    pub fn bytes_processed(&self) -> u64 {
+        self.processed
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+fn percent(processed: u64, total: u64) -> u64 {
+    if total == 0 {
+        return 100;
+    }
+    (processed.min(total) * 100) / total
+}
+
+pub fn render_bar(processed: u64, total: u64) -> String {
+    let percent = percent(processed, total);
+    let filled = (percent as usize * BAR_WIDTH) / 100;
+
+    format!(
+        "[{}{}] {:>3}% ({:.1}/{:.1} MB)",
+        "#".repeat(filled),
+        " ".repeat(BAR_WIDTH - filled),
+        percent,
+        megabytes(processed.min(total)),
+        megabytes(total)
+    )
+}
+
+fn megabytes(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/// Format throughput as "12.3 MB/s, 45678 words/s", or `None` if the run was
+/// too fast to measure.
+pub fn throughput(bytes: u64, words: usize, elapsed: Duration) -> Option<String> {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        return None;
+    }
+
+    Some(format!(
+        "{:.1} MB/s, {:.0} words/s",
+        megabytes(bytes) / secs,
+        words as f64 / secs
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_bar_empty() {
+        let bar = render_bar(0, 100);
+        assert!(bar.starts_with(&format!("[{}]", " ".repeat(BAR_WIDTH))));
+        assert!(bar.contains("  0%"));
+    }
+
+    #[test]
+    fn test_render_bar_half() {
+        let bar = render_bar(50, 100);
+        assert!(bar.contains(&"#".repeat(BAR_WIDTH / 2)));
+        assert!(bar.contains(" 50%"));
+    }
+
+    #[test]
+    fn test_render_bar_clamps_overflow() {
+        assert!(render_bar(200, 100).contains("100%"));
+    }
+
+    #[test]
+    fn test_small_input_disables_bar() {
+        assert!(!Progress::new(1024).enabled);
+        assert!(Progress::new(PROGRESS_THRESHOLD).enabled);
+    }
+
+    #[test]
+    fn test_throughput() {
+        let text = throughput(2 * 1024 * 1024, 1000, Duration::from_secs(2)).unwrap();
+        assert_eq!(text, "1.0 MB/s, 500 words/s");
+        assert!(throughput(10, 10, Duration::ZERO).is_none());
+    }
+}
@@ -0,0 +1,214 @@
+//! `--sample`: count a random fraction of the lines and scale the counts up,
+//! for a quick look at inputs too big to count in full.
+
+use crate::progress::Progress;
+use std::io::{self, BufRead, BufReader, Read};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// z-score for the 95% confidence margins
+const Z_95: f64 = 1.96;
+
+/// xorshift64*: fast, and plenty random for picking lines.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Zero is the one state xorshift never leaves
+        Rng(seed.max(1))
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let n = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (n >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// How many lines were read and how many made it into the sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleStats {
+    pub rate: f64,
+    pub lines_read: u64,
+    pub lines_kept: u64,
+}
+
+impl SampleStats {
+    /// Scale a count from the sample up to the whole input. Uses the share
+    /// of lines actually kept rather than the requested rate.
+    pub fn estimate(&self, count: usize) -> f64 {
+        if self.lines_kept == 0 {
+            return 0.0;
+        }
+        count as f64 * self.lines_read as f64 / self.lines_kept as f64
+    }
+
+    /// The 95% margin of error of `estimate(count)`, as a fraction of it.
+    /// Treats occurrences as independent, so words that cluster on a few
+    /// lines are less certain than this says.
+    pub fn margin(&self, count: usize) -> f64 {
+        if count == 0 {
+            return 1.0;
+        }
+        Z_95 * ((1.0 - self.rate) / count as f64).sqrt()
+    }
+}
+
+/// Parse a `--sample` rate: a fraction like 0.1 or a percentage like 10%.
+pub fn parse_rate(s: &str) -> Result<f64, String> {
+    let rate = match s.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().map(|p| p / 100.0),
+        None => s.parse::<f64>(),
+    };
+    match rate {
+        Ok(rate) if rate > 0.0 && rate <= 1.0 => Ok(rate),
+        _ => Err(format!(
+            "Invalid sample rate: '{s}'. Use a fraction like 0.1 or a percentage like 10%"
+        )),
+    }
+}
+
+/// A reader that passes on each line of `inner` with probability `rate`
+/// and drops the rest. Every byte still has to be read to find the lines,
+/// but skipped lines are never tokenized or counted. `progress` tracks the
+/// bytes read from `inner`, not the sampled bytes passed on.
+pub struct LineSampler<R> {
+    inner: BufReader<R>,
+    rate: f64,
+    rng: Rng,
+    line: Vec<u8>,
+    offset: usize,
+    progress: Progress,
+    lines_read: u64,
+    lines_kept: u64,
+}
+
+impl<R: Read> LineSampler<R> {
+    pub fn new(inner: R, rate: f64, progress: Progress) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1);
+        LineSampler::with_seed(inner, rate, progress, seed)
+    }
+
+    /// The same lines every time, for tests.
+    pub fn with_seed(inner: R, rate: f64, progress: Progress, seed: u64) -> Self {
+        LineSampler {
+            inner: BufReader::new(inner),
+            rate,
+            rng: Rng::new(seed),
+            line: Vec::new(),
+            offset: 0,
+            progress,
+            lines_read: 0,
+            lines_kept: 0,
+        }
+    }
+
+    pub fn stats(&self) -> SampleStats {
+        SampleStats {
+            rate: self.rate,
+            lines_read: self.lines_read,
+            lines_kept: self.lines_kept,
+        }
+    }
+
+    /// Finish the progress bar and hand it back for the throughput report.
+    pub fn finish(mut self) -> Progress {
+        self.progress.finish();
+        self.progress
+    }
+}
+
+impl<R: Read> Read for LineSampler<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.offset == self.line.len() {
+            self.line.clear();
+            self.offset = 0;
+            let n = self.inner.read_until(b'\n', &mut self.line)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            self.progress.advance(n);
+            self.lines_read += 1;
+            if self.rng.next_f64() < self.rate {
+                self.lines_kept += 1;
+            } else {
+                self.line.clear();
+            }
+        }
+
+        let n = buf.len().min(self.line.len() - self.offset);
+        buf[..n].copy_from_slice(&self.line[self.offset..self.offset + n]);
+        self.offset += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(n: usize) -> String {
+        (0..n).map(|i| format!("line {i} the end\n")).collect()
+    }
+
+    fn sample(text: &str, rate: f64) -> (String, SampleStats) {
+        let mut sampler = LineSampler::with_seed(text.as_bytes(), rate, Progress::new(0), 42);
+        let mut out = String::new();
+        sampler.read_to_string(&mut out).unwrap();
+        (out, sampler.stats())
+    }
+
+    #[test]
+    fn test_full_rate_keeps_everything() {
+        let text = lines(100);
+        let (out, stats) = sample(&text, 1.0);
+        assert_eq!(out, text);
+        assert_eq!(stats.lines_read, 100);
+        assert_eq!(stats.lines_kept, 100);
+        assert_eq!(stats.estimate(7), 7.0);
+        assert_eq!(stats.margin(7), 0.0);
+    }
+
+    #[test]
+    fn test_keeps_whole_lines_at_about_the_rate() {
+        let text = lines(10_000);
+        let (out, stats) = sample(&text, 0.1);
+        assert_eq!(stats.lines_read, 10_000);
+        // Expect 1000 ± 30; allow over five standard deviations
+        assert!((850..=1150).contains(&stats.lines_kept), "{stats:?}");
+        assert_eq!(out.lines().count() as u64, stats.lines_kept);
+        assert!(out
+            .lines()
+            .all(|l| l.starts_with("line ") && l.ends_with(" the end")));
+
+        // "the" is on every line, so scaling the sample recovers the total
+        let the = out.matches("the").count();
+        assert_eq!(stats.estimate(the), 10_000.0);
+    }
+
+    #[test]
+    fn test_margin_shrinks_with_more_occurrences() {
+        let stats = SampleStats {
+            rate: 0.1,
+            lines_read: 1000,
+            lines_kept: 100,
+        };
+        assert!(stats.margin(10_000) < 0.02);
+        assert!(stats.margin(10) > 0.5);
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("0.1"), Ok(0.1));
+        assert_eq!(parse_rate("25%"), Ok(0.25));
+        assert_eq!(parse_rate("1"), Ok(1.0));
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("1.5").is_err());
+        assert!(parse_rate("lots").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Approximate word counts in bounded memory, for inputs with too many
+//! distinct words to hold in a `HashMap`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// Each estimate overshoots by at most this fraction of all words counted...
+pub const EPSILON: f64 = 0.0001;
+/// ...except with this probability.
+pub const FAILURE_PROBABILITY: f64 = 0.001;
+
+/// How many candidate words to track for the top list. Far more than are
+/// shown, so a word that starts slowly still gets a place.
+pub const CANDIDATES: usize = 1000;
+
+/// A Count-Min Sketch: `depth` rows of `width` counters, each row indexed by
+/// a different hash. A word's estimate is the smallest of its counters;
+/// collisions only ever add, so estimates are never too low.
+#[derive(Debug, Clone)]
+pub struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    counters: Vec<u64>,
+}
+
+impl CountMinSketch {
+    pub fn new(width: usize, depth: usize) -> Self {
+        CountMinSketch {
+            width,
+            depth,
+            counters: vec![0; width * depth],
+        }
+    }
+
+    /// Sized so estimates are within `epsilon` × total of the true count with
+    /// probability `1 - failure`.
+    pub fn with_error(epsilon: f64, failure: f64) -> Self {
+        let width = (std::f64::consts::E / epsilon).ceil() as usize;
+        let depth = (1.0 / failure).ln().ceil() as usize;
+        CountMinSketch::new(width, depth)
+    }
+
+    /// One counter per row, from two hashes combined (Kirsch–Mitzenmacher)
+    /// rather than `depth` separate ones.
+    fn indexes(&self, item: &str) -> impl Iterator<Item = usize> + '_ {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        let h1 = hasher.finish();
+        0xa5a5_a5a5_u64.hash(&mut hasher);
+        let h2 = hasher.finish() | 1;
+
+        (0..self.depth).map(move |row| {
+            let h = h1.wrapping_add((row as u64).wrapping_mul(h2));
+            row * self.width + (h % self.width as u64) as usize
+        })
+    }
+
+    /// Count one occurrence and return the new estimate.
+    pub fn add(&mut self, item: &str) -> u64 {
+        let indexes: Vec<usize> = self.indexes(item).collect();
+        indexes
+            .into_iter()
+            .map(|i| {
+                self.counters[i] += 1;
+                self.counters[i]
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    pub fn estimate(&self, item: &str) -> u64 {
+        self.indexes(item)
+            .map(|i| self.counters[i])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Bytes used by the counters
+    pub fn memory(&self) -> usize {
+        self.counters.len() * std::mem::size_of::<u64>()
+    }
+}
+
+/// Word counts from a sketch plus the `capacity` words with the highest
+/// estimates so far. `ranked` is ordered by estimate, so its first entry is
+/// the one to evict when a new word overtakes it.
+#[derive(Debug, Clone)]
+pub struct ApproxCounts {
+    sketch: CountMinSketch,
+    capacity: usize,
+    candidates: HashMap<String, u64>,
+    ranked: BTreeSet<(u64, String)>,
+    total: u64,
+}
+
+impl Default for ApproxCounts {
+    fn default() -> Self {
+        ApproxCounts::new(
+            CountMinSketch::with_error(EPSILON, FAILURE_PROBABILITY),
+            CANDIDATES,
+        )
+    }
+}
+
+impl ApproxCounts {
+    pub fn new(sketch: CountMinSketch, capacity: usize) -> Self {
+        ApproxCounts {
+            sketch,
+            capacity,
+            candidates: HashMap::new(),
+            ranked: BTreeSet::new(),
+            total: 0,
+        }
+    }
+
+    pub fn add(&mut self, word: String) {
+        self.total += 1;
+        let estimate = self.sketch.add(&word);
+
+        if let Some(old) = self.candidates.get_mut(&word) {
+            self.ranked.remove(&(*old, word.clone()));
+            *old = estimate;
+            self.ranked.insert((estimate, word));
+            return;
+        }
+
+        if self.candidates.len() >= self.capacity {
+            match self.ranked.first() {
+                Some((lowest, _)) if *lowest < estimate => {
+                    let (_, evicted) = self.ranked.pop_first().expect("just checked");
+                    self.candidates.remove(&evicted);
+                }
+                _ => return,
+            }
+        }
+        self.candidates.insert(word.clone(), estimate);
+        self.ranked.insert((estimate, word));
+    }
+
+    /// Number of words counted, including repeats
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// How far any estimate may be over its true count (with probability
+    /// `1 - FAILURE_PROBABILITY` for the default sketch).
+    pub fn error_bound(&self) -> u64 {
+        (std::f64::consts::E / self.sketch.width as f64 * self.total as f64).ceil() as u64
+    }
+
+    pub fn memory(&self) -> usize {
+        self.sketch.memory()
+    }
+
+    /// The `n` words with the highest estimates, ties broken alphabetically.
+    pub fn top(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut sorted: Vec<(&str, u64)> = self
+            .candidates
+            .iter()
+            .map(|(w, c)| (w.as_str(), *c))
+            .collect();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        sorted.truncate(n);
+        sorted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_words;
+
+    /// A skewed distribution like real text: word `i` appears `200 / i`
+    /// times, plus a long tail of words seen once.
+    fn zipf_text() -> String {
+        let mut words = Vec::new();
+        for i in 1..=50 {
+            for _ in 0..200 / i {
+                words.push(format!("w{i}"));
+            }
+        }
+        for i in 0..2000 {
+            words.push(format!("rare{i}"));
+        }
+        words.join(" ")
+    }
+
+    #[test]
+    fn test_estimates_never_undercount() {
+        let text = zipf_text();
+        let exact = count_words(&text);
+        // Deliberately small, so collisions happen
+        let mut sketch = CountMinSketch::new(64, 4);
+        for word in text.split_whitespace() {
+            sketch.add(word);
+        }
+        for (word, count) in exact.iter() {
+            assert!(sketch.estimate(word) >= count as u64, "{word}");
+        }
+    }
+
+    #[test]
+    fn test_top_matches_exact_counts() {
+        let text = zipf_text();
+        let exact = count_words(&text);
+        let mut approx = ApproxCounts::new(CountMinSketch::with_error(0.001, 0.01), 50);
+        for word in text.split_whitespace() {
+            approx.add(word.to_string());
+        }
+
+        assert_eq!(approx.total(), exact.total() as u64);
+        let expected: Vec<(&str, u64)> = exact
+            .top(10)
+            .into_iter()
+            .map(|(w, c)| (w, c as u64))
+            .collect();
+        let top = approx.top(10);
+        let words =
+            |list: &[(&str, u64)]| list.iter().map(|(w, _)| w.to_string()).collect::<Vec<_>>();
+        assert_eq!(words(&top), words(&expected));
+        for ((_, estimate), (_, count)) in top.iter().zip(&expected) {
+            assert!(estimate >= count && estimate - count <= approx.error_bound());
+        }
+    }
+
+    #[test]
+    fn test_candidates_stay_bounded() {
+        let mut approx = ApproxCounts::new(CountMinSketch::new(256, 4), 5);
+        for i in 0..1000 {
+            approx.add(format!("word{i}"));
+        }
+        assert_eq!(approx.candidates.len(), 5);
+        assert_eq!(approx.ranked.len(), 5);
+    }
+}
@@ -0,0 +1,277 @@
+//! `--max-unique 1000000`: count texts with more distinct words than fit in
+//! memory. Once the counts hold that many words they're written to a
+//! temporary file, sorted by word, and counting starts afresh; at the end
+//! the files are merged like an external merge sort, adding up each word's
+//! counts as it comes round in every file. Past `MERGE_WIDTH` files they're
+//! merged in batches first, so only that many are ever open at once.
+
+use crate::{SortOrder, WordCounts};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{self, AtomicUsize};
+
+/// Tells apart the temporary directories of counts in one process
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// The most files merged at once, well under the usual open file limit
+const MERGE_WIDTH: usize = 64;
+
+type Entries = Box<dyn Iterator<Item = io::Result<(String, usize)>>>;
+
+/// Word counts that spill to disk past `max_unique` distinct words. The
+/// temporary files are removed when it's dropped.
+pub struct SpillCounts {
+    counts: WordCounts,
+    max_unique: usize,
+    dir: PathBuf,
+    runs: Vec<PathBuf>,
+    /// Files written so far, spills and merged batches, for naming the next
+    written: usize,
+    spills: usize,
+    /// The first error writing a file, kept for `finish` to report
+    error: Option<io::Error>,
+}
+
+/// The merged counts: only the words to show, and totals over all of them.
+#[derive(Debug, PartialEq)]
+pub struct Merged {
+    pub top: Vec<(String, usize)>,
+    pub total: usize,
+    pub unique: usize,
+    /// How many times the counts were written to disk
+    pub spills: usize,
+}
+
+impl SpillCounts {
+    pub fn new(max_unique: usize) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "word-counter-{}-{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, atomic::Ordering::Relaxed)
+        ));
+        SpillCounts {
+            counts: WordCounts::new(),
+            max_unique: max_unique.max(1),
+            dir,
+            runs: Vec::new(),
+            written: 0,
+            spills: 0,
+            error: None,
+        }
+    }
+
+    pub fn add(&mut self, word: String) {
+        self.counts.add(word);
+        if self.counts.len() >= self.max_unique && self.error.is_none() {
+            if let Err(e) = self.spill() {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Write the counts so far to a new file, sorted by word, a `word\tcount`
+    /// line each. Words never contain whitespace.
+    fn spill(&mut self) -> io::Result<()> {
+        let mut file = self.new_run()?;
+        for (word, count) in sorted(std::mem::take(&mut self.counts)) {
+            writeln!(file, "{word}\t{count}")?;
+        }
+        file.flush()?;
+        self.spills += 1;
+        Ok(())
+    }
+
+    /// Create the next file and add it to `runs`.
+    fn new_run(&mut self) -> io::Result<BufWriter<File>> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("run-{}.tsv", self.written));
+        let file = File::create(&path)?;
+        self.written += 1;
+        self.runs.push(path);
+        Ok(BufWriter::new(file))
+    }
+
+    /// Merge the oldest `MERGE_WIDTH` files into one until few enough are
+    /// left to merge in a single pass alongside the counts in memory.
+    fn merge_batches(&mut self) -> io::Result<()> {
+        while self.runs.len() >= MERGE_WIDTH {
+            let batch: Vec<PathBuf> = self.runs.drain(..MERGE_WIDTH).collect();
+            let sources = batch.iter().map(open_run).collect::<io::Result<Vec<_>>>()?;
+            let mut file = self.new_run()?;
+            merge(sources, |word, count| writeln!(file, "{word}\t{count}"))?;
+            file.flush()?;
+            for path in batch {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge everything counted and keep the first `n` words in `order`.
+    pub fn finish(mut self, order: SortOrder, reverse: bool, n: usize) -> io::Result<Merged> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.merge_batches()?;
+        let in_memory = sorted(std::mem::take(&mut self.counts));
+        let mut sources: Vec<Entries> = vec![Box::new(in_memory.into_iter().map(Ok))];
+        for path in &self.runs {
+            sources.push(open_run(path)?);
+        }
+
+        let compare = |a: &(String, usize), b: &(String, usize)| {
+            let ordering = order.compare(&(a.0.as_str(), a.1), &(b.0.as_str(), b.1));
+            if reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+        let mut merged = Merged {
+            top: Vec::new(),
+            total: 0,
+            unique: 0,
+            spills: self.spills,
+        };
+        merge(sources, |word, count| {
+            merged.total += count;
+            merged.unique += 1;
+            let entry = (word, count);
+            let place = merged
+                .top
+                .partition_point(|t| compare(t, &entry) != Ordering::Greater);
+            if place < n {
+                merged.top.insert(place, entry);
+                merged.top.truncate(n);
+            }
+            Ok(())
+        })?;
+        Ok(merged)
+    }
+}
+
+impl Drop for SpillCounts {
+    fn drop(&mut self) {
+        if self.written > 0 {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+}
+
+fn sorted(counts: WordCounts) -> Vec<(String, usize)> {
+    let mut entries: Vec<(String, usize)> = counts.counts.into_iter().collect();
+    entries.sort_unstable();
+    entries
+}
+
+fn open_run(path: &PathBuf) -> io::Result<Entries> {
+    let lines = BufReader::new(File::open(path)?).lines();
+    Ok(Box::new(lines.map(|line| parse_line(&line?))))
+}
+
+/// Merge `sources`, each sorted by word, calling `emit` once per word with
+/// its counts added up, in word order.
+fn merge(
+    mut sources: Vec<Entries>,
+    mut emit: impl FnMut(String, usize) -> io::Result<()>,
+) -> io::Result<()> {
+    // The smallest word from each source, to take them in order
+    let mut heap = BinaryHeap::new();
+    for (i, source) in sources.iter_mut().enumerate() {
+        if let Some(entry) = source.next() {
+            let (word, count) = entry?;
+            heap.push(Reverse((word, i, count)));
+        }
+    }
+
+    let mut current: Option<(String, usize)> = None;
+    while let Some(Reverse((word, i, count))) = heap.pop() {
+        if let Some(entry) = sources[i].next() {
+            let (next, next_count) = entry?;
+            heap.push(Reverse((next, i, next_count)));
+        }
+        match current.as_mut() {
+            Some((same, sum)) if *same == word => *sum += count,
+            _ => {
+                if let Some((done, sum)) = current.replace((word, count)) {
+                    emit(done, sum)?;
+                }
+            }
+        }
+    }
+    if let Some((done, sum)) = current {
+        emit(done, sum)?;
+    }
+    Ok(())
+}
+
+fn parse_line(line: &str) -> io::Result<(String, usize)> {
+    line.split_once('\t')
+        .and_then(|(word, count)| Some((word.to_string(), count.parse().ok()?)))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("bad line '{line}'")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count_words;
+
+    const TEXT: &str = "the cat and the dog and the bird saw a cat";
+
+    fn spilled(max_unique: usize, order: SortOrder, reverse: bool) -> (Merged, PathBuf) {
+        let mut spill = SpillCounts::new(max_unique);
+        for word in TEXT.split_whitespace() {
+            spill.add(word.to_string());
+        }
+        let dir = spill.dir.clone();
+        (spill.finish(order, reverse, 3).unwrap(), dir)
+    }
+
+    #[test]
+    fn test_merge_matches_counting_in_memory() {
+        let (merged, dir) = spilled(2, SortOrder::Count, false);
+        let exact = count_words(TEXT);
+        assert!(merged.spills > 1);
+        assert_eq!(merged.total, exact.total());
+        assert_eq!(merged.unique, exact.len());
+        let top: Vec<(&str, usize)> = merged.top.iter().map(|(w, n)| (w.as_str(), *n)).collect();
+        assert_eq!(top, exact.top(3));
+        // The temporary files are gone once the counts are merged
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_merge_in_other_orders() {
+        let (merged, _) = spilled(3, SortOrder::Alpha, false);
+        assert_eq!(merged.top[0], ("a".to_string(), 1));
+        let (merged, _) = spilled(3, SortOrder::Count, true);
+        let words: Vec<&str> = merged.top.iter().map(|(w, _)| w.as_str()).collect();
+        assert_eq!(words, ["saw", "dog", "bird"]);
+        // Below the limit nothing touches the disk
+        assert_eq!(spilled(100, SortOrder::Count, false).0.spills, 0);
+    }
+
+    #[test]
+    fn test_merge_in_batches_past_the_merge_width() {
+        let words: Vec<String> = (0..3 * MERGE_WIDTH).map(|i| format!("w{i}")).collect();
+        let mut spill = SpillCounts::new(1);
+        for word in words.iter().chain(&words[..10]) {
+            spill.add(word.clone());
+        }
+        // Batches are folded first, so the last merge stays within the width
+        spill.merge_batches().unwrap();
+        assert!(spill.runs.len() < MERGE_WIDTH);
+        let dir = spill.dir.clone();
+
+        let merged = spill.finish(SortOrder::Count, false, 3).unwrap();
+        assert_eq!(merged.spills, 3 * MERGE_WIDTH + 10);
+        assert_eq!(merged.total, 3 * MERGE_WIDTH + 10);
+        assert_eq!(merged.unique, 3 * MERGE_WIDTH);
+        let top: Vec<(&str, usize)> = merged.top.iter().map(|(w, n)| (w.as_str(), *n)).collect();
+        assert_eq!(top, [("w0", 2), ("w1", 2), ("w2", 2)]);
+        assert!(!dir.exists());
+    }
+}
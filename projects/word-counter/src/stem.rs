@@ -0,0 +1,361 @@
+//! Porter stemming, so inflections like "running" and "runs" count as one word.
+
+use std::collections::HashMap;
+
+/// Irregular forms Porter's suffix rules can't relate to their base word.
+const IRREGULAR: &[(&str, &str)] = &[
+    ("ran", "run"),
+    ("went", "go"),
+    ("gone", "go"),
+    ("was", "be"),
+    ("were", "be"),
+    ("been", "be"),
+    ("had", "have"),
+    ("has", "have"),
+    ("did", "do"),
+    ("done", "do"),
+    ("said", "say"),
+    ("made", "make"),
+    ("took", "take"),
+    ("taken", "take"),
+    ("saw", "see"),
+    ("seen", "see"),
+    ("came", "come"),
+    ("got", "get"),
+    ("gave", "give"),
+    ("given", "give"),
+    ("knew", "know"),
+    ("known", "know"),
+    ("thought", "think"),
+    ("found", "find"),
+    ("told", "tell"),
+    ("wrote", "write"),
+    ("written", "write"),
+    ("men", "man"),
+    ("women", "woman"),
+    ("children", "child"),
+];
+
+/// Reduce a lowercase word to its stem: "running" → "run", "ponies" → "poni".
+/// Stems aren't always real words; words that aren't plain ASCII letters
+/// (after dropping a possessive "'s") are returned unchanged.
+pub fn stem(word: &str) -> String {
+    let word = word.strip_suffix("'s").unwrap_or(word);
+
+    let word = IRREGULAR
+        .iter()
+        .find(|(form, _)| *form == word)
+        .map_or(word, |(_, base)| base);
+
+    if word.len() <= 2 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+        return word.to_string();
+    }
+
+    let mut stemmer = Stemmer {
+        b: word.as_bytes().to_vec(),
+        j: 0,
+    };
+    stemmer.step1ab();
+    if stemmer.b.len() > 1 {
+        stemmer.step1c();
+        stemmer.step2();
+        stemmer.step3();
+        stemmer.step4();
+        stemmer.step5();
+    }
+
+    String::from_utf8(stemmer.b).unwrap_or_else(|_| word.to_string())
+}
+
+/// Martin Porter's 1980 algorithm, following his reference implementation.
+/// `j` is the length of the stem left once the suffix matched by the last
+/// successful `ends` call is removed.
+struct Stemmer {
+    b: Vec<u8>,
+    j: usize,
+}
+
+impl Stemmer {
+    fn cons(&self, i: usize) -> bool {
+        match self.b[i] {
+            b'a' | b'e' | b'i' | b'o' | b'u' => false,
+            b'y' => i == 0 || !self.cons(i - 1),
+            _ => true,
+        }
+    }
+
+    /// The number of vowel-consonant sequences in the first `len` letters.
+    fn m(&self, len: usize) -> usize {
+        let mut n = 0;
+        let mut i = 0;
+
+        while i < len && self.cons(i) {
+            i += 1;
+        }
+        loop {
+            while i < len && !self.cons(i) {
+                i += 1;
+            }
+            if i >= len {
+                return n;
+            }
+            while i < len && self.cons(i) {
+                i += 1;
+            }
+            n += 1;
+        }
+    }
+
+    fn vowel_in_stem(&self) -> bool {
+        (0..self.j).any(|i| !self.cons(i))
+    }
+
+    /// Letters `i - 1` and `i` are the same consonant.
+    fn double_cons(&self, i: usize) -> bool {
+        i >= 1 && self.b[i] == self.b[i - 1] && self.cons(i)
+    }
+
+    /// Letters `i - 2..=i` are consonant-vowel-consonant and the last is not
+    /// w, x, or y, as in "hop" or "fil".
+    fn cvc(&self, i: usize) -> bool {
+        i >= 2
+            && self.cons(i)
+            && !self.cons(i - 1)
+            && self.cons(i - 2)
+            && !matches!(self.b[i], b'w' | b'x' | b'y')
+    }
+
+    fn ends(&mut self, suffix: &str) -> bool {
+        if self.b.ends_with(suffix.as_bytes()) {
+            self.j = self.b.len() - suffix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn set_to(&mut self, replacement: &str) {
+        self.b.truncate(self.j);
+        self.b.extend_from_slice(replacement.as_bytes());
+    }
+
+    fn replace_if_measured(&mut self, replacement: &str) {
+        if self.m(self.j) > 0 {
+            self.set_to(replacement);
+        }
+    }
+
+    fn last(&self) -> usize {
+        self.b.len() - 1
+    }
+
+    /// Plurals and -ed or -ing: caresses → caress, ponies → poni,
+    /// agreed → agree, hopping → hop, filing → file.
+    fn step1ab(&mut self) {
+        if self.b[self.last()] == b's' {
+            if self.ends("sses") {
+                self.b.truncate(self.b.len() - 2);
+            } else if self.ends("ies") {
+                self.set_to("i");
+            } else if self.b[self.last() - 1] != b's' {
+                self.b.pop();
+            }
+        }
+
+        if self.ends("eed") {
+            if self.m(self.j) > 0 {
+                self.b.pop();
+            }
+        } else if (self.ends("ed") || self.ends("ing")) && self.vowel_in_stem() {
+            self.b.truncate(self.j);
+            if self.ends("at") {
+                self.set_to("ate");
+            } else if self.ends("bl") {
+                self.set_to("ble");
+            } else if self.ends("iz") {
+                self.set_to("ize");
+            } else if self.double_cons(self.last()) {
+                if !matches!(self.b[self.last()], b'l' | b's' | b'z') {
+                    self.b.pop();
+                }
+            } else if self.m(self.b.len()) == 1 && self.cvc(self.last()) {
+                self.b.push(b'e');
+            }
+        }
+    }
+
+    /// Terminal y to i when there is another vowel: happy → happi.
+    fn step1c(&mut self) {
+        if self.ends("y") && self.vowel_in_stem() {
+            let last = self.last();
+            self.b[last] = b'i';
+        }
+    }
+
+    /// Double suffixes to single ones: relational → relate.
+    fn step2(&mut self) {
+        const RULES: &[(&str, &str)] = &[
+            ("ational", "ate"),
+            ("tional", "tion"),
+            ("enci", "ence"),
+            ("anci", "ance"),
+            ("izer", "ize"),
+            ("bli", "ble"),
+            ("alli", "al"),
+            ("entli", "ent"),
+            ("eli", "e"),
+            ("ousli", "ous"),
+            ("ization", "ize"),
+            ("ation", "ate"),
+            ("ator", "ate"),
+            ("alism", "al"),
+            ("iveness", "ive"),
+            ("fulness", "ful"),
+            ("ousness", "ous"),
+            ("aliti", "al"),
+            ("iviti", "ive"),
+            ("biliti", "ble"),
+            ("logi", "log"),
+        ];
+        self.apply_first(RULES);
+    }
+
+    /// -ic-, -full, -ness etc.: hopeful → hope, goodness → good.
+    fn step3(&mut self) {
+        const RULES: &[(&str, &str)] = &[
+            ("icate", "ic"),
+            ("ative", ""),
+            ("alize", "al"),
+            ("iciti", "ic"),
+            ("ical", "ic"),
+            ("ful", ""),
+            ("ness", ""),
+        ];
+        self.apply_first(RULES);
+    }
+
+    fn apply_first(&mut self, rules: &[(&str, &str)]) {
+        if let Some((_, replacement)) = rules.iter().find(|(suffix, _)| self.ends(suffix)) {
+            self.replace_if_measured(replacement);
+        }
+    }
+
+    /// Drop -ant, -ence etc. from longer stems: adjustment → adjust.
+    fn step4(&mut self) {
+        const SUFFIXES: &[&str] = &[
+            "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ion",
+            "ou", "ism", "ate", "iti", "ous", "ive", "ize",
+        ];
+
+        let Some(suffix) = SUFFIXES.iter().find(|s| self.ends(s)) else {
+            return;
+        };
+        // -ion only goes after s or t: adoption → adopt, but not onion
+        if *suffix == "ion" && (self.j == 0 || !matches!(self.b[self.j - 1], b's' | b't')) {
+            return;
+        }
+        if self.m(self.j) > 1 {
+            self.b.truncate(self.j);
+        }
+    }
+
+    /// Tidy up a final -e and -ll: probate → probat, controll → control.
+    fn step5(&mut self) {
+        let last = self.last();
+
+        if self.b[last] == b'e' {
+            let m = self.m(self.b.len());
+            if m > 1 || (m == 1 && !self.cvc(last - 1)) {
+                self.b.pop();
+            }
+        } else if self.b[last] == b'l' && self.double_cons(last) && self.m(self.b.len()) > 1 {
+            self.b.pop();
+        }
+    }
+}
+
+/// For each stem, how often each original form was seen.
+#[derive(Debug, Default)]
+pub struct StemForms {
+    forms: HashMap<String, HashMap<String, usize>>,
+}
+
+impl StemForms {
+    pub fn new() -> Self {
+        StemForms::default()
+    }
+
+    pub fn add(&mut self, stem: &str, word: &str) {
+        *self
+            .forms
+            .entry(stem.to_string())
+            .or_default()
+            .entry(word.to_string())
+            .or_insert(0) += 1;
+    }
+
+    /// The form of `stem` seen most often, ties broken alphabetically.
+    pub fn most_common(&self, stem: &str) -> Option<&str> {
+        self.forms
+            .get(stem)?
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .map(|(word, _)| word.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_porter_examples() {
+        let cases = [
+            ("caresses", "caress"),
+            ("ponies", "poni"),
+            ("cats", "cat"),
+            ("agreed", "agre"),
+            ("plastered", "plaster"),
+            ("motoring", "motor"),
+            ("sing", "sing"),
+            ("hopping", "hop"),
+            ("falling", "fall"),
+            ("filing", "file"),
+            ("happy", "happi"),
+            ("relational", "relat"),
+            ("generalizations", "gener"),
+            ("adoption", "adopt"),
+            ("adjustment", "adjust"),
+            ("controlling", "control"),
+        ];
+        for (word, expected) in cases {
+            assert_eq!(stem(word), expected, "stem of {word}");
+        }
+    }
+
+    #[test]
+    fn test_inflections_share_a_stem() {
+        assert_eq!(stem("running"), "run");
+        assert_eq!(stem("runs"), "run");
+        assert_eq!(stem("ran"), "run");
+        assert_eq!(stem("gave"), stem("giving"));
+    }
+
+    #[test]
+    fn test_leaves_other_words_alone() {
+        assert_eq!(stem("is"), "is");
+        assert_eq!(stem("don't"), "don't");
+        assert_eq!(stem("café"), "café");
+        assert_eq!(stem("rust's"), "rust");
+    }
+
+    #[test]
+    fn test_most_common_form() {
+        let mut forms = StemForms::new();
+        forms.add("run", "running");
+        forms.add("run", "ran");
+        forms.add("run", "running");
+        assert_eq!(forms.most_common("run"), Some("running"));
+        assert_eq!(forms.most_common("walk"), None);
+    }
+}
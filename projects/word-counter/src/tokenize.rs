@@ -0,0 +1,220 @@
+//! Tokenizer profiles: prose splits on whitespace and strips punctuation,
+//! code keeps identifiers and `::` paths whole, logs skip timestamps and ids.
+
+use crate::TokenizerOptions;
+
+/// Turns text into counted words. A word must never span whitespace:
+/// streaming hands over text in chunks split at whitespace.
+pub trait Tokenizer {
+    /// Call `on_word` with each word in `text`, in order.
+    fn words(&self, text: &str, options: &TokenizerOptions, on_word: &mut dyn FnMut(String));
+}
+
+/// Which `Tokenizer` to use, chosen with `--profile`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Profile {
+    #[default]
+    Prose,
+    Code,
+    Log,
+}
+
+impl std::str::FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "prose" => Ok(Profile::Prose),
+            "code" => Ok(Profile::Code),
+            "log" => Ok(Profile::Log),
+            _ => Err(format!("Invalid profile: '{s}'. Use prose, code, or log")),
+        }
+    }
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Profile::Prose => write!(f, "prose"),
+            Profile::Code => write!(f, "code"),
+            Profile::Log => write!(f, "log"),
+        }
+    }
+}
+
+impl Profile {
+    pub fn tokenizer(&self) -> &'static dyn Tokenizer {
+        match self {
+            Profile::Prose => &Prose,
+            Profile::Code => &Code,
+            Profile::Log => &Log,
+        }
+    }
+}
+
+/// Whitespace-separated words with punctuation stripped.
+pub struct Prose;
+
+impl Tokenizer for Prose {
+    fn words(&self, text: &str, options: &TokenizerOptions, on_word: &mut dyn FnMut(String)) {
+        text.split_whitespace()
+            .filter_map(|w| options.normalize(w))
+            .for_each(on_word);
+    }
+}
+
+/// Identifiers: letters, digits and `_`, with `::` joining the parts of a
+/// path. `foo.bar()` is two words; `std::io::Read` is one.
+pub struct Code;
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The identifiers and paths in `text`.
+fn identifiers(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut result = Vec::new();
+    let mut start = None;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        if is_identifier(c) {
+            start.get_or_insert(pos);
+            i += 1;
+            continue;
+        }
+
+        let joins_path = c == ':'
+            && start.is_some()
+            && chars.get(i + 1).is_some_and(|&(_, c)| c == ':')
+            && chars.get(i + 2).is_some_and(|&(_, c)| is_identifier(c));
+        if joins_path {
+            i += 2;
+            continue;
+        }
+
+        if let Some(s) = start.take() {
+            result.push(&text[s..pos]);
+        }
+        i += 1;
+    }
+    if let Some(s) = start {
+        result.push(&text[s..]);
+    }
+    result
+}
+
+impl Tokenizer for Code {
+    fn words(&self, text: &str, options: &TokenizerOptions, on_word: &mut dyn FnMut(String)) {
+        for identifier in identifiers(text) {
+            if identifier.chars().count() < options.min_length {
+                continue;
+            }
+            if options.lowercase {
+                on_word(identifier.to_lowercase());
+            } else {
+                on_word(identifier.to_string());
+            }
+        }
+    }
+}
+
+/// Prose words, minus the timestamps, hex ids and UUIDs that make every
+/// log line look different. `key=value` pairs count as two words.
+pub struct Log;
+
+/// Dates and times like `2024-05-01T12:00:00Z`, `12:00:00.123` or
+/// `2024/05/01`: digits with date or time separators.
+fn is_timestamp(token: &str) -> bool {
+    token.chars().any(|c| c.is_ascii_digit())
+        && token.contains(['-', ':', '/'])
+        && token
+            .chars()
+            .all(|c| c.is_ascii_digit() || "-:./,+TZ".contains(c))
+}
+
+/// `0x1f`, git hashes, UUIDs: hex digits (and dashes) with at least one
+/// digit, long enough not to be an ordinary word like "cafe" or "add".
+fn is_hex_id(token: &str) -> bool {
+    if let Some(hex) = token.strip_prefix("0x") {
+        return !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    let digits = token.chars().filter(|c| *c != '-').count();
+    digits >= 6
+        && token.chars().any(|c| c.is_ascii_digit())
+        && token.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+impl Tokenizer for Log {
+    fn words(&self, text: &str, options: &TokenizerOptions, on_word: &mut dyn FnMut(String)) {
+        text.split(|c: char| c.is_whitespace() || c == '=')
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+            .filter(|w| !is_timestamp(w) && !is_hex_id(&w.to_lowercase()))
+            .filter_map(|w| options.normalize(w))
+            .for_each(on_word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(profile: Profile, text: &str) -> Vec<String> {
+        let options = TokenizerOptions {
+            profile,
+            ..TokenizerOptions::default()
+        };
+        let mut result = Vec::new();
+        crate::for_each_word(text, &options, &mut |w| result.push(w));
+        result
+    }
+
+    #[test]
+    fn test_prose_is_the_default() {
+        assert_eq!(TokenizerOptions::default().profile, Profile::Prose);
+        assert_eq!(
+            words(Profile::Prose, "snake_case, isn't"),
+            ["snakecase", "isn't"]
+        );
+    }
+
+    #[test]
+    fn test_code_keeps_identifiers_and_paths() {
+        assert_eq!(
+            words(
+                Profile::Code,
+                "let x = std::io::stdin().read_line(&mut buf);"
+            ),
+            ["let", "x", "std::io::stdin", "read_line", "mut", "buf"]
+        );
+        // A lone colon or a trailing :: doesn't join
+        assert_eq!(
+            words(Profile::Code, "key:value Vec::"),
+            ["key", "value", "vec"]
+        );
+        assert_eq!(words(Profile::Code, "::crate::Foo"), ["crate::foo"]);
+    }
+
+    #[test]
+    fn test_log_drops_timestamps_and_ids() {
+        let line = "[2024-05-01T12:00:00Z] 12:00:01.250 ERROR request 3f2a9c1e \
+                    failed (id=550e8400-e29b-41d4-a716-446655440000, ptr 0x7ffd) cafe";
+        assert_eq!(
+            words(Profile::Log, line),
+            ["error", "request", "failed", "id", "ptr", "cafe"]
+        );
+        assert_eq!(
+            words(Profile::Log, "took 250 ms, commit a1b2c3d"),
+            ["took", "250", "ms", "commit"]
+        );
+    }
+
+    #[test]
+    fn test_profile_from_str() {
+        assert_eq!("Code".parse::<Profile>(), Ok(Profile::Code));
+        assert_eq!(Profile::Log.to_string(), "log");
+        assert!("markdown".parse::<Profile>().is_err());
+    }
+}